@@ -0,0 +1,269 @@
+//! Security advisories for the connected firmware, from a feed published by
+//! the librekeys organisation.
+//!
+//! Off by default; switched on in About → Security Advisories and saved in
+//! [`Settings::advisory_checks`](crate::settings::Settings::advisory_checks).
+//! The feed is a short JSON list of advisories, each naming a firmware
+//! family (as [`FirmwareType`] displays it, e.g. `pico-fido`) and the
+//! version ranges it affects. It is fetched at most once per
+//! [`CHECK_INTERVAL`], failed attempts included, and kept in the platform
+//! cache directory; between fetches, and when the feed cannot be reached,
+//! the cached copy is matched instead. Nothing about the key is sent: the
+//! whole feed is downloaded and matched locally.
+
+use crate::hal::common::version::FirmwareVersion;
+use crate::hal::types::FirmwareType;
+use crate::settings;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Where the advisories feed is published.
+const FEED_URL: &str =
+    "https://raw.githubusercontent.com/librekeys/security-advisories/main/advisories.json";
+
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Minimum time between two fetches of the feed.
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Versions an advisory applies to: from `introduced` (inclusive, or the
+/// first release when absent) up to `fixed` (exclusive, or every later
+/// release when absent).
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct AffectedRange {
+    pub introduced: Option<String>,
+    pub fixed: Option<String>,
+}
+
+impl AffectedRange {
+    /// Whether `version` falls in the range. A bound that is not a version
+    /// matches nothing, so a malformed entry never raises an alarm.
+    fn contains(&self, version: &FirmwareVersion) -> bool {
+        let bound = |bound: &Option<String>| match bound {
+            None => Some(None),
+            Some(text) => FirmwareVersion::parse(text).map(|v| Some(key(&v))),
+        };
+        let (Some(introduced), Some(fixed)) = (bound(&self.introduced), bound(&self.fixed)) else {
+            return false;
+        };
+        let version = key(version);
+        introduced.is_none_or(|low| version >= low) && fixed.is_none_or(|high| version < high)
+    }
+
+    /// The range as shown to the user, e.g. `7.0 to before 7.4`.
+    pub fn describe(&self) -> String {
+        match (&self.introduced, &self.fixed) {
+            (Some(low), Some(high)) => format!("{} to before {}", low, high),
+            (Some(low), None) => format!("{} and later", low),
+            (None, Some(high)) => format!("before {}", high),
+            (None, None) => "all versions".to_string(),
+        }
+    }
+}
+
+/// One published advisory.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Advisory {
+    /// Identifier, e.g. `LK-2025-001`.
+    pub id: String,
+    pub title: String,
+    /// What is wrong and what to do about it.
+    #[serde(default)]
+    pub summary: String,
+    /// Firmware family, e.g. `pico-fido`.
+    pub firmware: String,
+    #[serde(default)]
+    pub affected: Vec<AffectedRange>,
+    /// Page with the full advisory.
+    #[serde(default)]
+    pub url: Option<String>,
+}
+
+impl Advisory {
+    /// Whether the advisory applies to `firmware` at `version`.
+    pub fn affects(&self, firmware: &FirmwareType, version: &FirmwareVersion) -> bool {
+        self.firmware.eq_ignore_ascii_case(&firmware.to_string())
+            && self.affected.iter().any(|range| range.contains(version))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+struct Feed {
+    advisories: Vec<Advisory>,
+}
+
+/// Cached feed.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+struct CachedFeed {
+    /// When the feed was last asked for, successfully or not, in UNIX
+    /// seconds.
+    checked_at: u64,
+    /// When `advisories` were fetched, in UNIX seconds; 0 if never.
+    fetched_at: u64,
+    advisories: Vec<Advisory>,
+}
+
+fn key(version: &FirmwareVersion) -> (u16, u16, u16) {
+    (version.major, version.minor, version.patch)
+}
+
+/// Advisories among `advisories` that apply to `firmware` at `installed`.
+/// A version that cannot be parsed matches nothing.
+pub fn matching(
+    advisories: &[Advisory],
+    firmware: &FirmwareType,
+    installed: &str,
+) -> Vec<Advisory> {
+    let Some(installed) = FirmwareVersion::parse(installed) else {
+        return Vec::new();
+    };
+    advisories
+        .iter()
+        .filter(|a| a.affects(firmware, &installed))
+        .cloned()
+        .collect()
+}
+
+/// Whether the feed may be fetched again at `now` (UNIX seconds).
+fn due(cached: &CachedFeed, now: u64) -> bool {
+    now.saturating_sub(cached.checked_at) >= CHECK_INTERVAL.as_secs()
+}
+
+fn cache_path() -> Option<PathBuf> {
+    let dirs = settings::project_dirs()?;
+    Some(dirs.cache_dir().join("advisories.json"))
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn fetch() -> Result<Vec<Advisory>, String> {
+    let body = ureq::get(FEED_URL)
+        .timeout(FETCH_TIMEOUT)
+        .set(
+            "User-Agent",
+            concat!("PicoForge/", env!("CARGO_PKG_VERSION")),
+        )
+        .call()
+        .map_err(|e| e.to_string())?
+        .into_string()
+        .map_err(|e| e.to_string())?;
+    serde_json::from_str::<Feed>(&body)
+        .map(|feed| feed.advisories)
+        .map_err(|e| format!("Unexpected advisories feed: {}", e))
+}
+
+fn read_cache() -> CachedFeed {
+    cache_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+fn write_cache(cached: &CachedFeed) {
+    let Some(path) = cache_path() else {
+        return;
+    };
+    let result = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| {
+            let json = serde_json::to_string(cached).map_err(std::io::Error::other)?;
+            fs::write(&path, json)
+        });
+    if let Err(e) = result {
+        log::warn!("Could not cache the advisories feed at {:?}: {}", path, e);
+    }
+}
+
+/// Advisories that apply to `firmware` at `installed`. Blocking; fetches the
+/// feed when the cached copy is older than [`CHECK_INTERVAL`] and otherwise
+/// matches the cache. Call only when the user opted in.
+pub fn check(firmware: &FirmwareType, installed: &str) -> Vec<Advisory> {
+    let mut cached = read_cache();
+    let now = now();
+    if due(&cached, now) {
+        cached.checked_at = now;
+        match fetch() {
+            Ok(advisories) => {
+                log::info!("Fetched {} security advisories", advisories.len());
+                cached.fetched_at = now;
+                cached.advisories = advisories;
+            }
+            Err(e) => log::warn!("Could not fetch security advisories: {}", e),
+        }
+        write_cache(&cached);
+    }
+    matching(&cached.advisories, firmware, installed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn advisory(introduced: Option<&str>, fixed: Option<&str>) -> Advisory {
+        Advisory {
+            id: "LK-2025-001".into(),
+            title: "PIN retries not persisted".into(),
+            summary: "Update the firmware.".into(),
+            firmware: "pico-fido".into(),
+            affected: vec![AffectedRange {
+                introduced: introduced.map(str::to_string),
+                fixed: fixed.map(str::to_string),
+            }],
+            url: None,
+        }
+    }
+
+    #[test]
+    fn test_parses_feed() {
+        let json = r#"{"advisories":[{"id":"LK-2025-001","title":"T","firmware":"pico-fido","affected":[{"introduced":"6.0","fixed":"7.4"}]}]}"#;
+        let feed: Feed = serde_json::from_str(json).unwrap();
+        assert_eq!(feed.advisories[0].affected[0].fixed.as_deref(), Some("7.4"));
+        assert!(feed.advisories[0].url.is_none());
+    }
+
+    #[test]
+    fn test_range_bounds() {
+        let advisories = vec![advisory(Some("7.0"), Some("7.4"))];
+        let pico = FirmwareType::PicoFido;
+        assert!(matching(&advisories, &pico, "6.6").is_empty());
+        assert_eq!(matching(&advisories, &pico, "7.0").len(), 1);
+        assert_eq!(matching(&advisories, &pico, "7.2.1").len(), 1);
+        assert!(matching(&advisories, &pico, "7.4").is_empty());
+
+        let open_ended = vec![advisory(None, None)];
+        assert_eq!(matching(&open_ended, &pico, "1.0").len(), 1);
+    }
+
+    #[test]
+    fn test_other_firmware_and_bad_versions_do_not_match() {
+        let advisories = vec![advisory(None, Some("7.4"))];
+        assert!(matching(&advisories, &FirmwareType::RSKey, "7.0").is_empty());
+        assert!(matching(&advisories, &FirmwareType::PicoFido, "Unknown").is_empty());
+
+        let malformed = vec![advisory(Some("soon"), None)];
+        assert!(matching(&malformed, &FirmwareType::PicoFido, "7.0").is_empty());
+    }
+
+    #[test]
+    fn test_fetches_at_most_once_per_interval() {
+        let interval = CHECK_INTERVAL.as_secs();
+        let cached = CachedFeed {
+            checked_at: 1_000,
+            ..Default::default()
+        };
+        assert!(!due(&cached, 1_000 + interval - 1));
+        assert!(due(&cached, 1_000 + interval));
+        assert!(due(&CachedFeed::default(), interval));
+    }
+}
@@ -0,0 +1,382 @@
+//! Passphrase-encrypted export and import of application data.
+//!
+//! Bundles everything PicoForge keeps on disk that is worth carrying to another
+//! machine: the settings, the device inventory, the provisioning report key
+//! (so reports signed on the old machine still verify), the secure lock host
+//! keys (without which a secure-locked key stays locked), the notes vault and
+//! the audit log. The vault stays sealed: it still needs its security key.
+//! Logs and caches are left out; they are rebuilt on the new machine.
+//!
+//! The archive is a small JSON envelope around an AES-256-GCM ciphertext. The
+//! key is derived from the passphrase with PBKDF2-HMAC-SHA256; `ring` (already
+//! used for the CTAP PIN protocol) has no memory-hard KDF, so the iteration
+//! count follows current OWASP guidance instead. Only the KDF parameters are in
+//! the clear; serials, paths and log contents are all inside the ciphertext.
+//!
+//! On import the settings and key are replaced (the previous key is kept as
+//! `report.key.bak`), host keys are added (a different local one is kept as
+//! `<serial>.key.bak`), the vault replaces a local one (kept as
+//! `vault.json.bak`) and the inventory is merged into the local one. The
+//! imported audit log is written next to the live one as
+//! `audit-imported-<time>.log` rather than merged, so the current trail is
+//! never rewritten.
+
+use crate::inventory::{self, Inventory};
+use crate::logging::{self, LogStatus};
+use crate::provisioning;
+use crate::secure_lock;
+use crate::settings::{self, Settings};
+use crate::vault::{self, VaultFile};
+use ring::aead::{AES_256_GCM, Aad, LessSafeKey, NONCE_LEN, Nonce, UnboundKey};
+use ring::pbkdf2;
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::num::NonZeroU32;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Identifies PicoForge archives; also bound into the ciphertext as AAD.
+const ARCHIVE_FORMAT: &str = "picoforge-app-data";
+const ARCHIVE_VERSION: u32 = 1;
+
+const KDF_NAME: &str = "PBKDF2-HMAC-SHA256";
+const CIPHER_NAME: &str = "AES-256-GCM";
+
+/// PBKDF2 iterations for new archives.
+const KDF_ITERATIONS: u32 = 600_000;
+/// Most PBKDF2 iterations [`decrypt`] accepts, so a crafted archive cannot
+/// keep the app deriving a key for hours.
+const MAX_KDF_ITERATIONS: u32 = 10 * KDF_ITERATIONS;
+const SALT_LEN: usize = 16;
+
+/// Shortest passphrase accepted for export.
+pub const MIN_PASSPHRASE_LEN: usize = 8;
+
+/// Suggested file extension for archives.
+pub const ARCHIVE_EXTENSION: &str = "pfbackup";
+
+/// Plaintext contents of an archive.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AppData {
+    /// When the archive was created, in UNIX seconds.
+    pub created_at: u64,
+    /// PicoForge version that created the archive.
+    pub app_version: String,
+    /// Application settings.
+    pub settings: Option<Settings>,
+    /// Last configured USB identity per device.
+    pub inventory: Option<Inventory>,
+    /// Provisioning report key, hex encoded.
+    pub provisioning_key: Option<String>,
+    /// Secure lock host keys by serial, hex encoded. Missing in archives
+    /// written before host keys were carried.
+    #[serde(default)]
+    pub secure_lock_keys: BTreeMap<String, String>,
+    /// Sealed notes vault, as in `vault.json`.
+    #[serde(default)]
+    pub vault: Option<VaultFile>,
+    /// Contents of `audit.log`.
+    pub audit_log: Option<String>,
+}
+
+/// Encrypted archive as written to disk.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+struct Envelope {
+    format: String,
+    version: u32,
+    kdf: String,
+    iterations: u32,
+    salt: String,
+    cipher: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Directory holding `audit.log` for this session, if file logging is active.
+fn audit_log_dir() -> Option<PathBuf> {
+    match logging::log_status() {
+        Some(LogStatus::File(dir)) => Some(dir.clone()),
+        _ => None,
+    }
+}
+
+/// Gather the application data from disk.
+pub fn collect() -> Result<AppData, String> {
+    let (settings, settings_error) = settings::load();
+    if let Some(e) = settings_error {
+        return Err(e);
+    }
+
+    let provisioning_key = match provisioning::key_path() {
+        Some(path) if path.exists() => {
+            Some(hex::encode(fs::read(&path).map_err(|e| {
+                format!("Could not read {}: {}", path.display(), e)
+            })?))
+        }
+        _ => None,
+    };
+
+    let secure_lock_keys = secure_lock::all_keys()?
+        .into_iter()
+        .map(|(serial, key)| (serial, hex::encode(key)))
+        .collect();
+
+    let audit_log = match audit_log_dir().map(|dir| dir.join("audit.log")) {
+        Some(path) if path.exists() => Some(
+            fs::read_to_string(&path)
+                .map_err(|e| format!("Could not read {}: {}", path.display(), e))?,
+        ),
+        _ => None,
+    };
+
+    Ok(AppData {
+        created_at: now(),
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        settings: Some(settings),
+        inventory: Some(inventory::load()),
+        provisioning_key,
+        secure_lock_keys,
+        vault: vault::load()?,
+        audit_log,
+    })
+}
+
+fn derive_key(passphrase: &str, salt: &[u8], iterations: u32) -> Result<LessSafeKey, String> {
+    let iterations = NonZeroU32::new(iterations).ok_or("Invalid KDF iteration count")?;
+    let mut key = [0u8; 32];
+    pbkdf2::derive(
+        pbkdf2::PBKDF2_HMAC_SHA256,
+        iterations,
+        salt,
+        passphrase.as_bytes(),
+        &mut key,
+    );
+    let unbound = UnboundKey::new(&AES_256_GCM, &key).map_err(|_| "Invalid key".to_string())?;
+    Ok(LessSafeKey::new(unbound))
+}
+
+fn encrypt_with(data: &AppData, passphrase: &str, iterations: u32) -> Result<String, String> {
+    let rng = SystemRandom::new();
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    rng.fill(&mut salt)
+        .and_then(|_| rng.fill(&mut nonce))
+        .map_err(|_| "Could not generate random salt".to_string())?;
+
+    let key = derive_key(passphrase, &salt, iterations)?;
+    let mut buffer =
+        serde_json::to_vec(data).map_err(|e| format!("Could not serialize app data: {}", e))?;
+    key.seal_in_place_append_tag(
+        Nonce::assume_unique_for_key(nonce),
+        Aad::from(ARCHIVE_FORMAT.as_bytes()),
+        &mut buffer,
+    )
+    .map_err(|_| "Encryption failed".to_string())?;
+
+    let envelope = Envelope {
+        format: ARCHIVE_FORMAT.to_string(),
+        version: ARCHIVE_VERSION,
+        kdf: KDF_NAME.to_string(),
+        iterations,
+        salt: hex::encode(salt),
+        cipher: CIPHER_NAME.to_string(),
+        nonce: hex::encode(nonce),
+        ciphertext: hex::encode(buffer),
+    };
+    serde_json::to_string_pretty(&envelope).map_err(|e| format!("Could not write archive: {}", e))
+}
+
+/// Encrypt `data` into an archive. Slow on purpose (key derivation); call off
+/// the UI thread.
+pub fn encrypt(data: &AppData, passphrase: &str) -> Result<String, String> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+        return Err(format!(
+            "Passphrase must be at least {} characters",
+            MIN_PASSPHRASE_LEN
+        ));
+    }
+    encrypt_with(data, passphrase, KDF_ITERATIONS)
+}
+
+/// Decrypt an archive produced by [`encrypt`].
+pub fn decrypt(archive: &str, passphrase: &str) -> Result<AppData, String> {
+    let envelope: Envelope = serde_json::from_str(archive)
+        .map_err(|_| "Not a PicoForge app data archive".to_string())?;
+    if envelope.format != ARCHIVE_FORMAT {
+        return Err("Not a PicoForge app data archive".into());
+    }
+    if envelope.version != ARCHIVE_VERSION
+        || envelope.kdf != KDF_NAME
+        || envelope.cipher != CIPHER_NAME
+    {
+        return Err(format!(
+            "Unsupported archive version {} ({}, {})",
+            envelope.version, envelope.kdf, envelope.cipher
+        ));
+    }
+
+    if envelope.iterations > MAX_KDF_ITERATIONS {
+        return Err(format!(
+            "Archive asks for {} KDF iterations; at most {} are supported",
+            envelope.iterations, MAX_KDF_ITERATIONS
+        ));
+    }
+
+    let corrupt = |_: hex::FromHexError| "Archive is corrupted".to_string();
+    let salt = hex::decode(&envelope.salt).map_err(corrupt)?;
+    let nonce: [u8; NONCE_LEN] = hex::decode(&envelope.nonce)
+        .map_err(corrupt)?
+        .try_into()
+        .map_err(|_| "Archive is corrupted".to_string())?;
+    let mut buffer = hex::decode(&envelope.ciphertext).map_err(corrupt)?;
+
+    let key = derive_key(passphrase, &salt, envelope.iterations)?;
+    let plaintext = key
+        .open_in_place(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::from(ARCHIVE_FORMAT.as_bytes()),
+            &mut buffer,
+        )
+        .map_err(|_| "Wrong passphrase, or the archive is corrupted".to_string())?;
+
+    serde_json::from_slice(plaintext).map_err(|e| format!("Could not read archive contents: {}", e))
+}
+
+/// Write imported data to disk. Returns a line per restored item.
+pub fn restore(data: &AppData) -> Result<Vec<String>, String> {
+    let mut restored = Vec::new();
+
+    if let Some(settings) = &data.settings {
+        settings::save(settings)?;
+        restored.push("Settings".to_string());
+    }
+
+    if let Some(imported) = &data.inventory {
+        // Merge so devices configured on this machine are kept; the archive
+        // wins for serials present in both.
+        let mut merged = inventory::load();
+        merged.devices.extend(imported.devices.clone());
+        inventory::save(&merged)?;
+        restored.push(format!("Inventory ({} device(s))", imported.devices.len()));
+    }
+
+    if let Some(key_hex) = &data.provisioning_key {
+        let key = hex::decode(key_hex)
+            .ok()
+            .filter(|key| key.len() == provisioning::KEY_LEN)
+            .ok_or("Archive key is corrupted")?;
+        let path = provisioning::key_path().ok_or("Could not determine the data directory")?;
+        if path.exists() {
+            let backup = path.with_extension("key.bak");
+            fs::rename(&path, &backup)
+                .map_err(|e| format!("Could not back up {}: {}", path.display(), e))?;
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Could not create {}: {}", parent.display(), e))?;
+        }
+        provisioning::write_private(&path, &key)
+            .map_err(|e| format!("Could not write {}: {}", path.display(), e))?;
+        restored.push("Provisioning report key".to_string());
+    }
+
+    for (serial, key_hex) in &data.secure_lock_keys {
+        let key = hex::decode(key_hex)
+            .ok()
+            .and_then(|key| secure_lock::HostKey::try_from(key.as_slice()).ok())
+            .ok_or_else(|| format!("Archive host key for {} is corrupted", serial))?;
+        secure_lock::store_key(serial, &key)?;
+    }
+    if !data.secure_lock_keys.is_empty() {
+        restored.push(format!(
+            "Secure lock host keys ({})",
+            data.secure_lock_keys.len()
+        ));
+    }
+
+    if let Some(imported) = &data.vault {
+        vault::replace(imported)?;
+        restored.push(format!("Notes vault (key {})", imported.serial));
+    }
+
+    if let Some(audit) = &data.audit_log {
+        match audit_log_dir() {
+            Some(dir) => {
+                let path = dir.join(format!("audit-imported-{}.log", data.created_at));
+                fs::write(&path, audit)
+                    .map_err(|e| format!("Could not write {}: {}", path.display(), e))?;
+                restored.push(format!("Audit log (as {})", path.display()));
+            }
+            None => log::warn!("File logging is disabled; skipping imported audit log"),
+        }
+    }
+
+    log::info!(
+        target: logging::AUDIT_TARGET,
+        "Imported application data created {} by PicoForge {}: {}",
+        data.created_at,
+        data.app_version,
+        restored.join(", ")
+    );
+
+    Ok(restored)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> AppData {
+        AppData {
+            created_at: 1_700_000_000,
+            app_version: "0.0.0".into(),
+            settings: Some(Settings::default()),
+            inventory: Some(Inventory::default()),
+            provisioning_key: Some("00".repeat(32)),
+            secure_lock_keys: BTreeMap::from([("E6614C31".into(), "11".repeat(32))]),
+            vault: None,
+            audit_log: Some("serial 1234 configured\n".into()),
+        }
+    }
+
+    #[test]
+    fn test_archive_round_trip() {
+        let archive = encrypt_with(&sample(), "correct horse", 1_000).unwrap();
+        assert!(!archive.contains("1234"));
+        assert!(!archive.contains("E6614C31"));
+        assert_eq!(decrypt(&archive, "correct horse").unwrap(), sample());
+    }
+
+    #[test]
+    fn test_archive_wrong_passphrase() {
+        let archive = encrypt_with(&sample(), "correct horse", 1_000).unwrap();
+        assert!(decrypt(&archive, "battery staple").is_err());
+    }
+
+    #[test]
+    fn test_decrypt_rejects_excessive_iterations() {
+        let archive = encrypt_with(&sample(), "correct horse", 1_000).unwrap();
+        let archive = archive.replace("\"iterations\": 1000", "\"iterations\": 4000000000");
+        assert!(
+            decrypt(&archive, "correct horse")
+                .unwrap_err()
+                .contains("KDF iterations")
+        );
+    }
+
+    #[test]
+    fn test_encrypt_rejects_short_passphrase() {
+        assert!(encrypt(&sample(), "short").is_err());
+    }
+}
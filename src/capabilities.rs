@@ -0,0 +1,376 @@
+//! Capability snapshots for firmware regression tracking.
+//!
+//! A snapshot is the GetInfo answer plus what PicoForge knows about the
+//! vendor side (firmware family, vendor config commands, certifications,
+//! smart-card applets, GetInfo members it cannot interpret yet) as one
+//! canonical JSON document. Lists are sorted and sets deduplicated, and
+//! values that change with use rather than with the firmware (serial,
+//! remaining credentials, minimum PIN length, whether a PIN or fingerprint is
+//! set) are left out, so two keys running the same build give identical
+//! files.
+//!
+//! [`diff`] compares two snapshots. Anything that disappears, turns from
+//! `true` to `false` or whose limit shrinks is reported as a regression;
+//! `picoforge capabilities diff` exits non-zero when it finds one.
+
+use crate::hal::io;
+use crate::hal::types::{AppletPresence, FidoDeviceInfo, FullDeviceStatus};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+/// Version of the snapshot layout. Bumped when fields are renamed or removed.
+pub const SNAPSHOT_SCHEMA: u32 = 1;
+
+/// GetInfo options whose value reflects the key's state (PIN set, fingerprints
+/// enrolled, a user toggle) rather than the firmware. Only their presence is
+/// recorded: they are always `true` in a snapshot.
+const STATEFUL_OPTIONS: &[&str] = &[
+    "clientPin",
+    "uv",
+    "bioEnroll",
+    "uvBioEnroll",
+    "alwaysUv",
+    "ep",
+];
+
+/// Firmware capabilities of one key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CapabilitySnapshot {
+    /// [`SNAPSHOT_SCHEMA`] at the time of writing.
+    pub schema: u32,
+    /// Firmware family (`Pico FIDO`, `RS-Key`, ...).
+    pub firmware_type: String,
+    /// Firmware version as read from the device.
+    pub firmware_version: String,
+    /// AAGUID, uppercase hex.
+    pub aaguid: String,
+    /// CTAP versions.
+    pub versions: BTreeSet<String>,
+    /// CTAP extensions.
+    pub extensions: BTreeSet<String>,
+    /// GetInfo options; see [`STATEFUL_OPTIONS`] for the ones kept as presence only.
+    pub options: BTreeMap<String, bool>,
+    /// COSE algorithm names.
+    pub algorithms: BTreeSet<String>,
+    /// PIN/UV auth protocol versions.
+    pub pin_protocols: BTreeSet<u32>,
+    /// Size and count limits advertised in GetInfo, by their CTAP names.
+    pub limits: BTreeMap<String, i64>,
+    /// Vendor config commands advertised in GetInfo.
+    pub vendor_config_commands: BTreeSet<String>,
+    /// Certifications, when the firmware reports them.
+    pub certifications: BTreeMap<String, bool>,
+    /// Smart-card applets that answered a SELECT.
+    pub applets: BTreeSet<String>,
+    /// GetInfo members this version cannot interpret, as key label → hex
+    /// CBOR, so tools reading snapshots see data from newer firmware.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub unknown_fields: BTreeMap<String, String>,
+}
+
+impl CapabilitySnapshot {
+    /// Build a snapshot from what the device reported.
+    pub fn new(status: &FullDeviceStatus, fido: &FidoDeviceInfo, applets: AppletPresence) -> Self {
+        let options = fido
+            .options
+            .iter()
+            .map(|(name, value)| {
+                let value = *value || STATEFUL_OPTIONS.contains(&name.as_str());
+                (name.clone(), value)
+            })
+            .collect();
+
+        let limits = [
+            ("maxMsgSize", Some(fido.max_msg_size)),
+            (
+                "maxCredentialCountInList",
+                fido.max_credential_count_in_list,
+            ),
+            ("maxCredentialIdLength", fido.max_credential_id_length),
+            (
+                "maxSerializedLargeBlobArray",
+                fido.max_serialized_large_blob_array,
+            ),
+            ("maxCredBlobLength", fido.max_cred_blob_length),
+            (
+                "maxRPIDsForSetMinPINLength",
+                fido.max_rpids_for_min_pin_length,
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| Some((name.to_string(), i64::try_from(value?).ok()?)))
+        .collect();
+
+        let applets = [("openpgp", applets.openpgp), ("oath", applets.oath)]
+            .into_iter()
+            .filter(|(_, present)| *present)
+            .map(|(name, _)| name.to_string())
+            .collect();
+
+        Self {
+            schema: SNAPSHOT_SCHEMA,
+            firmware_type: status.firmware_type.to_string(),
+            firmware_version: status.info.firmware_version.clone(),
+            aaguid: fido.aaguid.to_uppercase(),
+            versions: fido.versions.iter().cloned().collect(),
+            extensions: fido.extensions.iter().cloned().collect(),
+            options,
+            algorithms: fido.algorithms.iter().cloned().collect(),
+            pin_protocols: fido.pin_protocols.iter().copied().collect(),
+            limits,
+            vendor_config_commands: fido.vendor_config_commands.iter().cloned().collect(),
+            certifications: fido
+                .certifications
+                .iter()
+                .map(|(k, v)| (k.clone(), *v))
+                .collect(),
+            applets,
+            unknown_fields: fido
+                .unknown_fields
+                .iter()
+                .map(|field| (field.label(), hex::encode_upper(&field.cbor)))
+                .collect(),
+        }
+    }
+
+    /// Canonical JSON: pretty-printed, keys in a fixed order, newline at the end.
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(self)
+            .map(|json| json + "\n")
+            .map_err(|e| format!("Failed to serialize capabilities: {}", e))
+    }
+
+    /// Parse a snapshot written by [`CapabilitySnapshot::to_json`].
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let snapshot: Self =
+            serde_json::from_str(json).map_err(|e| format!("Not a capability snapshot: {}", e))?;
+        if snapshot.schema > SNAPSHOT_SCHEMA {
+            return Err(format!(
+                "Snapshot schema {} is newer than this PicoForge understands ({})",
+                snapshot.schema, SNAPSHOT_SCHEMA
+            ));
+        }
+        Ok(snapshot)
+    }
+
+    /// Every capability as dotted path → value, the form [`diff`] compares.
+    pub fn entries(&self) -> BTreeMap<String, String> {
+        flatten(self)
+    }
+}
+
+/// Read the connected key's capability snapshot.
+pub fn read_capabilities() -> Result<CapabilitySnapshot, String> {
+    let status = io::read_device_details().map_err(|e| e.to_string())?;
+    let fido = io::get_fido_info()?;
+    let applets = io::probe_applets();
+    Ok(CapabilitySnapshot::new(&status, &fido, applets))
+}
+
+/// How one capability differs between two snapshots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ChangeKind {
+    /// Only in the new snapshot.
+    Added,
+    /// Only in the old snapshot.
+    Removed,
+    /// In both, with different values.
+    Changed,
+}
+
+/// One entry of [`diff`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CapabilityChange {
+    /// What happened.
+    pub kind: ChangeKind,
+    /// Dotted path, e.g. `extensions.credProtect` or `limits.maxMsgSize`.
+    pub path: String,
+    /// Value in the old snapshot.
+    pub old: Option<String>,
+    /// Value in the new snapshot.
+    pub new: Option<String>,
+}
+
+impl CapabilityChange {
+    /// Whether the new firmware lost something the old one had.
+    pub fn is_regression(&self) -> bool {
+        match (self.kind, self.old.as_deref(), self.new.as_deref()) {
+            (ChangeKind::Removed, _, _) => true,
+            (ChangeKind::Changed, Some("true"), Some("false")) => true,
+            (ChangeKind::Changed, Some(old), Some(new)) if self.path.starts_with("limits.") => {
+                matches!((old.parse::<i64>(), new.parse::<i64>()), (Ok(o), Ok(n)) if n < o)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for CapabilityChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mark = if self.is_regression() { "!" } else { " " };
+        let old = self.old.as_deref().unwrap_or("");
+        let new = self.new.as_deref().unwrap_or("");
+        match self.kind {
+            ChangeKind::Added => write!(f, "{}+ {} = {}", mark, self.path, new),
+            ChangeKind::Removed => write!(f, "{}- {} = {}", mark, self.path, old),
+            ChangeKind::Changed => write!(f, "{}~ {}: {} -> {}", mark, self.path, old, new),
+        }
+    }
+}
+
+/// Compare two snapshots, sorted by path. The schema field is ignored.
+pub fn diff(old: &CapabilitySnapshot, new: &CapabilitySnapshot) -> Vec<CapabilityChange> {
+    let old = flatten(old);
+    let new = flatten(new);
+    let paths: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+
+    paths
+        .into_iter()
+        .filter_map(|path| {
+            let (before, after) = (old.get(path), new.get(path));
+            let kind = match (before, after) {
+                (Some(a), Some(b)) if a == b => return None,
+                (Some(_), Some(_)) => ChangeKind::Changed,
+                (Some(_), None) => ChangeKind::Removed,
+                (None, Some(_)) => ChangeKind::Added,
+                (None, None) => return None,
+            };
+            Some(CapabilityChange {
+                kind,
+                path: path.clone(),
+                old: before.cloned(),
+                new: after.cloned(),
+            })
+        })
+        .collect()
+}
+
+/// Dotted path → value for every leaf. Set members become `set.member = true`
+/// so an added or dropped member shows up as its own entry.
+fn flatten(snapshot: &CapabilitySnapshot) -> BTreeMap<String, String> {
+    fn walk(prefix: &str, value: &serde_json::Value, out: &mut BTreeMap<String, String>) {
+        match value {
+            serde_json::Value::Object(map) => {
+                for (key, value) in map {
+                    let path = if prefix.is_empty() {
+                        key.clone()
+                    } else {
+                        format!("{}.{}", prefix, key)
+                    };
+                    walk(&path, value, out);
+                }
+            }
+            serde_json::Value::Array(items) => {
+                for item in items {
+                    let member = match item {
+                        serde_json::Value::String(s) => s.clone(),
+                        other => other.to_string(),
+                    };
+                    out.insert(format!("{}.{}", prefix, member), "true".into());
+                }
+            }
+            serde_json::Value::String(s) => {
+                out.insert(prefix.to_string(), s.clone());
+            }
+            other => {
+                out.insert(prefix.to_string(), other.to_string());
+            }
+        }
+    }
+
+    let mut out = BTreeMap::new();
+    if let Ok(value) = serde_json::to_value(snapshot) {
+        walk("", &value, &mut out);
+    }
+    out.remove("schema");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot() -> CapabilitySnapshot {
+        CapabilitySnapshot {
+            schema: SNAPSHOT_SCHEMA,
+            firmware_type: "Pico FIDO".into(),
+            firmware_version: "7.4".into(),
+            aaguid: "89FB94B706C936736D9AF9F6A4F79B32".into(),
+            versions: ["FIDO_2_0".to_string(), "FIDO_2_1".to_string()].into(),
+            extensions: ["credProtect".to_string(), "hmac-secret".to_string()].into(),
+            options: [("rk".to_string(), true), ("clientPin".to_string(), true)].into(),
+            algorithms: ["ES256".to_string()].into(),
+            pin_protocols: [1, 2].into(),
+            limits: [("maxMsgSize".to_string(), 1200)].into(),
+            vendor_config_commands: BTreeSet::new(),
+            certifications: BTreeMap::new(),
+            applets: ["openpgp".to_string()].into(),
+            unknown_fields: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_snapshot_round_trips() {
+        let json = snapshot().to_json().unwrap();
+        assert_eq!(CapabilitySnapshot::from_json(&json).unwrap(), snapshot());
+        assert!(json.ends_with("}\n"));
+    }
+
+    #[test]
+    fn test_unknown_fields_are_optional() {
+        let json = snapshot().to_json().unwrap();
+        assert!(!json.contains("unknownFields"));
+
+        let mut newer_firmware = snapshot();
+        newer_firmware
+            .unknown_fields
+            .insert("0x1D".into(), "A1636F7470F5".into());
+        let json = newer_firmware.to_json().unwrap();
+        assert_eq!(
+            CapabilitySnapshot::from_json(&json).unwrap(),
+            newer_firmware
+        );
+    }
+
+    #[test]
+    fn test_newer_schema_is_rejected() {
+        let mut newer = snapshot();
+        newer.schema = SNAPSHOT_SCHEMA + 1;
+        assert!(CapabilitySnapshot::from_json(&newer.to_json().unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_identical_snapshots_have_no_diff() {
+        assert!(diff(&snapshot(), &snapshot()).is_empty());
+    }
+
+    #[test]
+    fn test_diff_flags_regressions() {
+        let old = snapshot();
+        let mut new = snapshot();
+        new.extensions.remove("credProtect");
+        new.extensions.insert("largeBlobKey".into());
+        new.options.insert("rk".into(), false);
+        new.limits.insert("maxMsgSize".into(), 1024);
+        new.firmware_version = "7.5".into();
+
+        let changes = diff(&old, &new);
+        let find = |path: &str| changes.iter().find(|c| c.path == path).unwrap();
+
+        assert_eq!(find("extensions.credProtect").kind, ChangeKind::Removed);
+        assert!(find("extensions.credProtect").is_regression());
+        assert_eq!(find("extensions.largeBlobKey").kind, ChangeKind::Added);
+        assert!(!find("extensions.largeBlobKey").is_regression());
+        assert!(find("options.rk").is_regression());
+        assert!(find("limits.maxMsgSize").is_regression());
+        assert!(!find("firmwareVersion").is_regression());
+        assert_eq!(
+            find("limits.maxMsgSize").to_string(),
+            "!~ limits.maxMsgSize: 1200 -> 1024"
+        );
+    }
+}
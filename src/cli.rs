@@ -0,0 +1,596 @@
+//! Command-line entry points.
+//!
+//! Without arguments PicoForge starts the desktop app. A small set of
+//! headless commands is available for scripts that manage fleets of keys:
+//!
+//! ```text
+//! picoforge [--trace-wire] [--safe-mode] [--events <file>]
+//!                                                start the desktop app
+//! picoforge status [--output json|table]         print the connected device
+//! picoforge capabilities [--output json|table]   print a capability snapshot
+//! picoforge capabilities diff <old> <new> [--output json|table]
+//!                                                compare two snapshots
+//! picoforge completions <bash|zsh|fish>          print a shell completion script
+//! picoforge --help | --version
+//! ```
+//!
+//! `--dry-run` makes a headless command print each CTAPHID request and APDU
+//! it would send to stderr instead of sending it. Nothing reaches the key, so
+//! the command stops at the first request whose answer it needs; the requests
+//! that would follow from that answer are not shown.
+//!
+//! JSON output uses the same camelCase field names as the serialized HAL
+//! types and is considered stable: fields may be added, but existing ones are
+//! not renamed or removed without a major version bump.
+//!
+//! Commands that talk to a key claim it in the [`crate::registry`] first, so
+//! a running desktop app pauses its polling instead of racing them. A dry run
+//! sends nothing and leaves the desktop app alone.
+//!
+//! Headless commands do not initialise the logger, so `--trace-wire` only
+//! affects the GUI, as do `--safe-mode` and `--events`. Release builds on Windows use the GUI subsystem and have
+//! no attached console; run them with output redirected to a file.
+
+use crate::capabilities::{self, CapabilitySnapshot};
+use crate::hal::io;
+use crate::logging;
+use crate::registry;
+use serde::Serialize;
+use std::fmt::Write;
+
+/// Output format for headless commands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// Aligned key/value text for humans.
+    #[default]
+    Table,
+    /// A single JSON document.
+    Json,
+}
+
+/// Shells a completion script can be generated for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    /// GNU Bash.
+    Bash,
+    /// Z shell.
+    Zsh,
+    /// fish.
+    Fish,
+}
+
+/// What the process was asked to do.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Start the desktop app.
+    Gui,
+    /// Print the connected device's status.
+    Status(OutputFormat),
+    /// Print the connected device's capability snapshot.
+    Capabilities(OutputFormat),
+    /// Compare two capability snapshot files (old, new).
+    CapabilitiesDiff(String, String, OutputFormat),
+    /// Print a completion script.
+    Completions(Shell),
+    /// Print usage.
+    Help,
+    /// Print the version.
+    Version,
+}
+
+/// Parsed command line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Args {
+    /// Command to run.
+    pub command: Command,
+    /// Log raw protocol traffic (see [`crate::logging::set_trace_wire`]).
+    pub trace_wire: bool,
+    /// Start without device I/O (see [`crate::startup::set_safe_mode`]).
+    pub safe_mode: bool,
+    /// Append NDJSON events to this file (see [`crate::events`]).
+    pub events: Option<String>,
+    /// Print requests instead of sending them (see [`crate::logging::set_dry_run`]).
+    pub dry_run: bool,
+}
+
+const USAGE: &str = "\
+Usage:
+  picoforge [--trace-wire] [--safe-mode] [--events <file>]
+                                           Start the desktop app
+  picoforge status [--output json|table]   Print the connected device
+  picoforge capabilities [--output json|table]
+                                           Print a capability snapshot (default: json)
+  picoforge capabilities diff <old> <new> [--output json|table]
+                                           Compare two snapshots; exits 1 on a regression
+  picoforge completions <bash|zsh|fish>    Print a shell completion script
+  picoforge --help | --version
+
+Options:
+  --output <json|table>  Output format for status and capabilities (default: table,
+                         json for a capability snapshot)
+  --trace-wire           Log every CTAPHID message and APDU in hex
+  --safe-mode            Start without touching any key until asked to connect
+  --events <file>        Append device events, progress and log records as NDJSON
+  --dry-run              Print the CTAP/APDU requests a headless command would send
+                         instead of sending them
+";
+
+/// Parse the arguments after the program name.
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Args, String> {
+    let mut command = None;
+    let mut output = None;
+    let mut trace_wire = false;
+    let mut safe_mode = false;
+    let mut events = None;
+    let mut dry_run = false;
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--help" | "-h" => command = Some(Command::Help),
+            "--version" | "-V" => command = Some(Command::Version),
+            "--trace-wire" => trace_wire = true,
+            "--safe-mode" => safe_mode = true,
+            "--dry-run" => dry_run = true,
+            "--events" => {
+                let value = args.next().ok_or("--events needs a file name")?;
+                events = Some(value);
+            }
+            "--output" | "-o" => {
+                let value = args
+                    .next()
+                    .ok_or("--output needs a value (json or table)")?;
+                output = Some(match value.as_str() {
+                    "json" => OutputFormat::Json,
+                    "table" => OutputFormat::Table,
+                    other => return Err(format!("Unknown output format \"{}\"", other)),
+                });
+            }
+            "status" if command.is_none() => {
+                command = Some(Command::Status(OutputFormat::default()));
+            }
+            "capabilities" if command.is_none() => {
+                command = Some(Command::Capabilities(OutputFormat::Json));
+            }
+            "diff" if matches!(command, Some(Command::Capabilities(_))) => {
+                let old = args
+                    .next()
+                    .ok_or("capabilities diff needs two snapshot files")?;
+                let new = args
+                    .next()
+                    .ok_or("capabilities diff needs two snapshot files")?;
+                command = Some(Command::CapabilitiesDiff(old, new, OutputFormat::default()));
+            }
+            "completions" if command.is_none() => {
+                let value = args.next().ok_or("completions needs a shell name")?;
+                let shell = match value.as_str() {
+                    "bash" => Shell::Bash,
+                    "zsh" => Shell::Zsh,
+                    "fish" => Shell::Fish,
+                    other => return Err(format!("Unsupported shell \"{}\"", other)),
+                };
+                command = Some(Command::Completions(shell));
+            }
+            // Platforms may pass extra arguments to GUI apps (e.g. macOS -psn_*).
+            other if other.starts_with("-psn_") => {}
+            other => return Err(format!("Unknown argument \"{}\"", other)),
+        }
+    }
+
+    let command = match command.unwrap_or(Command::Gui) {
+        Command::Gui if output.is_some() => {
+            return Err("--output only applies to headless commands".into());
+        }
+        Command::Gui if dry_run => {
+            return Err("--dry-run only applies to headless commands".into());
+        }
+        Command::Gui => Command::Gui,
+        _ if events.is_some() => {
+            return Err("--events only applies to the desktop app".into());
+        }
+        Command::Status(_) => Command::Status(output.unwrap_or_default()),
+        // A snapshot is meant to be saved and diffed, so it stays JSON
+        // unless asked otherwise.
+        Command::Capabilities(_) => Command::Capabilities(output.unwrap_or(OutputFormat::Json)),
+        Command::CapabilitiesDiff(old, new, _) => {
+            Command::CapabilitiesDiff(old, new, output.unwrap_or_default())
+        }
+        Command::Completions(_) if output.is_some() => {
+            return Err("--output does not apply to completions".into());
+        }
+        other => other,
+    };
+
+    Ok(Args {
+        command,
+        trace_wire,
+        safe_mode,
+        events,
+        dry_run,
+    })
+}
+
+/// Run a headless command and return the process exit code.
+///
+/// Must not be called with [`Command::Gui`].
+pub fn run(command: &Command) -> i32 {
+    match command {
+        Command::Gui => unreachable!("the GUI is started by main"),
+        Command::Help => {
+            print!("{}", USAGE);
+            0
+        }
+        Command::Version => {
+            println!("picoforge {}", env!("CARGO_PKG_VERSION"));
+            0
+        }
+        Command::Completions(shell) => {
+            print!("{}", completion_script(*shell));
+            0
+        }
+        Command::Status(format) => status(*format),
+        Command::Capabilities(format) => print_capabilities(*format),
+        Command::CapabilitiesDiff(old, new, format) => diff_capabilities(old, new, *format),
+    }
+}
+
+/// Print the usage text with an error and return the exit code for bad arguments.
+pub fn usage_error(error: &str) -> i32 {
+    eprintln!("picoforge: {}\n\n{}", error, USAGE);
+    2
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StatusOutput {
+    device: Option<crate::hal::types::FullDeviceStatus>,
+    fido: Option<crate::hal::types::FidoDeviceInfo>,
+    error: Option<String>,
+}
+
+fn status(format: OutputFormat) -> i32 {
+    let device = io::device_fingerprint().filter(|_| !logging::dry_run());
+    if let Some(device) = &device
+        && let Err(e) = registry::claim_for_cli(device)
+    {
+        eprintln!("picoforge: {}", e);
+        return 1;
+    }
+    let output = match io::read_device_details() {
+        Ok(device) => StatusOutput {
+            device: Some(device),
+            fido: io::get_fido_info().ok(),
+            error: None,
+        },
+        Err(e) => StatusOutput {
+            device: None,
+            fido: None,
+            error: Some(e.to_string()),
+        },
+    };
+    if device.is_some() {
+        registry::release();
+    }
+    let code = if output.error.is_some() { 1 } else { 0 };
+
+    match format {
+        OutputFormat::Json => match serde_json::to_string_pretty(&output) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                eprintln!("picoforge: {}", e);
+                return 1;
+            }
+        },
+        OutputFormat::Table => print!("{}", status_table(&output)),
+    }
+    code
+}
+
+fn print_capabilities(format: OutputFormat) -> i32 {
+    let device = io::device_fingerprint().filter(|_| !logging::dry_run());
+    if let Some(device) = &device
+        && let Err(e) = registry::claim_for_cli(device)
+    {
+        eprintln!("picoforge: {}", e);
+        return 1;
+    }
+    let result = capabilities::read_capabilities();
+    if device.is_some() {
+        registry::release();
+    }
+    let text = result.and_then(|snapshot| match format {
+        OutputFormat::Json => snapshot.to_json(),
+        OutputFormat::Table => Ok(key_value_table(snapshot.entries())),
+    });
+    match text {
+        Ok(text) => {
+            print!("{}", text);
+            0
+        }
+        Err(e) => {
+            eprintln!("picoforge: {}", e);
+            1
+        }
+    }
+}
+
+/// One entry of the JSON diff: the change and whether it is a regression.
+#[derive(Serialize)]
+struct DiffEntry<'a> {
+    #[serde(flatten)]
+    change: &'a capabilities::CapabilityChange,
+    regression: bool,
+}
+
+#[derive(Serialize)]
+struct DiffOutput<'a> {
+    changes: Vec<DiffEntry<'a>>,
+    regressions: usize,
+}
+
+fn diff_capabilities(old: &str, new: &str, format: OutputFormat) -> i32 {
+    let read = |path: &str| {
+        std::fs::read_to_string(path)
+            .map_err(|e| format!("{}: {}", path, e))
+            .and_then(|json| {
+                CapabilitySnapshot::from_json(&json).map_err(|e| format!("{}: {}", path, e))
+            })
+    };
+    let (old, new) = match (read(old), read(new)) {
+        (Ok(old), Ok(new)) => (old, new),
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("picoforge: {}", e);
+            return 2;
+        }
+    };
+
+    let changes = capabilities::diff(&old, &new);
+    let regressions = changes.iter().filter(|c| c.is_regression()).count();
+    if format == OutputFormat::Json {
+        let output = DiffOutput {
+            changes: changes
+                .iter()
+                .map(|change| DiffEntry {
+                    change,
+                    regression: change.is_regression(),
+                })
+                .collect(),
+            regressions,
+        };
+        match serde_json::to_string_pretty(&output) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                eprintln!("picoforge: {}", e);
+                return 2;
+            }
+        }
+        return if regressions > 0 { 1 } else { 0 };
+    }
+
+    for change in &changes {
+        println!("{}", change);
+    }
+    if changes.is_empty() {
+        println!("No capability changes.");
+    } else {
+        println!(
+            "{} change(s), {} regression(s) (marked !).",
+            changes.len(),
+            regressions
+        );
+    }
+    if regressions > 0 { 1 } else { 0 }
+}
+
+fn status_table(output: &StatusOutput) -> String {
+    let mut rows: Vec<(&str, String)> = Vec::new();
+    if let Some(error) = &output.error {
+        rows.push(("error", error.clone()));
+    }
+    if let Some(device) = &output.device {
+        rows.push(("serial", device.info.serial.clone()));
+        rows.push(("firmwareType", device.firmware_type.to_string()));
+        rows.push(("firmwareVersion", device.info.firmware_version.clone()));
+        rows.push((
+            "vidPid",
+            format!("{}:{}", device.config.vid, device.config.pid),
+        ));
+        rows.push(("productName", device.config.product_name.clone()));
+        rows.push(("method", format!("{:?}", device.method)));
+        rows.push(("secureBoot", device.secure_boot.to_string()));
+        rows.push(("secureBootLocked", device.secure_boot_locked.to_string()));
+        rows.push(("secureLock", device.secure_lock.to_string()));
+    }
+    if let Some(fido) = &output.fido {
+        rows.push(("aaguid", fido.aaguid.clone()));
+        rows.push(("ctapVersions", fido.versions.join(",")));
+        rows.push((
+            "clientPin",
+            fido.options
+                .get("clientPin")
+                .copied()
+                .unwrap_or(false)
+                .to_string(),
+        ));
+    }
+
+    key_value_table(rows)
+}
+
+/// Keys padded to a common width, one `key  value` row per line.
+fn key_value_table<K: AsRef<str>>(rows: impl IntoIterator<Item = (K, String)>) -> String {
+    let rows: Vec<_> = rows.into_iter().collect();
+    let width = rows
+        .iter()
+        .map(|(k, _)| k.as_ref().len())
+        .max()
+        .unwrap_or(0);
+    let mut text = String::new();
+    for (key, value) in rows {
+        let _ = writeln!(text, "{:width$}  {}", key.as_ref(), value, width = width);
+    }
+    text
+}
+
+fn completion_script(shell: Shell) -> &'static str {
+    match shell {
+        Shell::Bash => {
+            r#"_picoforge() {
+    local cur prev
+    cur="${COMP_WORDS[COMP_CWORD]}"
+    prev="${COMP_WORDS[COMP_CWORD-1]}"
+    case "$prev" in
+        --output|-o) COMPREPLY=($(compgen -W "json table" -- "$cur")); return ;;
+        --events) COMPREPLY=($(compgen -f -- "$cur")); return ;;
+        completions) COMPREPLY=($(compgen -W "bash zsh fish" -- "$cur")); return ;;
+        capabilities) COMPREPLY=($(compgen -W "diff" -- "$cur")); return ;;
+        diff) COMPREPLY=($(compgen -f -- "$cur")); return ;;
+    esac
+    COMPREPLY=($(compgen -W "status capabilities completions --output --trace-wire --safe-mode --events --dry-run --help --version" -- "$cur"))
+}
+complete -F _picoforge picoforge
+"#
+        }
+        Shell::Zsh => {
+            r#"#compdef picoforge
+_arguments \
+    '--output[output format]:format:(json table)' \
+    '--trace-wire[log protocol traffic in hex]' \
+    '--safe-mode[start without connecting to a key]' \
+    '--events[stream events as NDJSON]:file:_files' \
+    '--dry-run[print requests instead of sending them]' \
+    '--help[show usage]' \
+    '--version[show version]' \
+    '1:command:(status capabilities completions)' \
+    '2:argument:(bash zsh fish diff)' \
+    '*:snapshot:_files'
+"#
+        }
+        Shell::Fish => {
+            r#"complete -c picoforge -f
+complete -c picoforge -n __fish_use_subcommand -a status -d 'Print the connected device'
+complete -c picoforge -n __fish_use_subcommand -a capabilities -d 'Print or compare capability snapshots'
+complete -c picoforge -n __fish_use_subcommand -a completions -d 'Print a shell completion script'
+complete -c picoforge -n '__fish_seen_subcommand_from capabilities' -a diff -d 'Compare two snapshots'
+complete -c picoforge -n '__fish_seen_subcommand_from diff' -F
+complete -c picoforge -n '__fish_seen_subcommand_from completions' -a 'bash zsh fish'
+complete -c picoforge -l output -s o -x -a 'json table' -d 'Output format'
+complete -c picoforge -l trace-wire -d 'Log protocol traffic in hex'
+complete -c picoforge -l safe-mode -d 'Start without connecting to a key'
+complete -c picoforge -l events -r -F -d 'Stream events as NDJSON'
+complete -c picoforge -l dry-run -d 'Print requests instead of sending them'
+complete -c picoforge -l help -s h -d 'Show usage'
+complete -c picoforge -l version -s V -d 'Show version'
+"#
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Result<Args, String> {
+        parse(list.iter().map(|s| s.to_string()))
+    }
+
+    #[test]
+    fn test_no_arguments_starts_gui() {
+        assert_eq!(
+            args(&[]).unwrap(),
+            Args {
+                command: Command::Gui,
+                trace_wire: false,
+                safe_mode: false,
+                events: None,
+                dry_run: false
+            }
+        );
+        assert!(args(&["--trace-wire"]).unwrap().trace_wire);
+        assert!(args(&["--safe-mode"]).unwrap().safe_mode);
+    }
+
+    #[test]
+    fn test_events_stream() {
+        assert_eq!(
+            args(&["--events", "events.ndjson"])
+                .unwrap()
+                .events
+                .as_deref(),
+            Some("events.ndjson")
+        );
+        assert!(args(&["--events"]).is_err());
+        assert!(args(&["status", "--events", "out.ndjson"]).is_err());
+    }
+
+    #[test]
+    fn test_dry_run_is_headless_only() {
+        let parsed = args(&["status", "--dry-run"]).unwrap();
+        assert!(parsed.dry_run);
+        assert_eq!(parsed.command, Command::Status(OutputFormat::Table));
+        assert!(args(&["capabilities", "--dry-run"]).unwrap().dry_run);
+        assert!(args(&["--dry-run"]).is_err());
+    }
+
+    #[test]
+    fn test_status_output_format() {
+        assert_eq!(
+            args(&["status"]).unwrap().command,
+            Command::Status(OutputFormat::Table)
+        );
+        assert_eq!(
+            args(&["status", "--output", "json"]).unwrap().command,
+            Command::Status(OutputFormat::Json)
+        );
+        assert_eq!(
+            args(&["--output", "json", "status"]).unwrap().command,
+            Command::Status(OutputFormat::Json)
+        );
+        assert!(args(&["status", "--output", "yaml"]).is_err());
+        assert!(args(&["--output", "json"]).is_err());
+    }
+
+    #[test]
+    fn test_completions() {
+        assert_eq!(
+            args(&["completions", "fish"]).unwrap().command,
+            Command::Completions(Shell::Fish)
+        );
+        assert!(args(&["completions"]).is_err());
+        assert!(args(&["completions", "tcsh"]).is_err());
+    }
+
+    #[test]
+    fn test_capabilities() {
+        assert_eq!(
+            args(&["capabilities"]).unwrap().command,
+            Command::Capabilities(OutputFormat::Json)
+        );
+        assert_eq!(
+            args(&["capabilities", "--output", "table"])
+                .unwrap()
+                .command,
+            Command::Capabilities(OutputFormat::Table)
+        );
+        assert_eq!(
+            args(&["capabilities", "diff", "a.json", "b.json"])
+                .unwrap()
+                .command,
+            Command::CapabilitiesDiff("a.json".into(), "b.json".into(), OutputFormat::Table)
+        );
+        assert_eq!(
+            args(&["-o", "json", "capabilities", "diff", "a.json", "b.json"])
+                .unwrap()
+                .command,
+            Command::CapabilitiesDiff("a.json".into(), "b.json".into(), OutputFormat::Json)
+        );
+        assert!(args(&["completions", "bash", "--output", "json"]).is_err());
+        assert!(args(&["capabilities", "diff", "a.json"]).is_err());
+        assert!(args(&["diff", "a.json", "b.json"]).is_err());
+    }
+
+    #[test]
+    fn test_unknown_argument() {
+        assert!(args(&["--frobnicate"]).is_err());
+    }
+}
@@ -0,0 +1,235 @@
+//! Firmware default configuration and how a device differs from it.
+//!
+//! A key with an empty phy area behaves as built by pico-keys-sdk (RS-Key
+//! uses the same phy layout): the generic `FEFF:FCFD` identity, the
+//! "Pico Key" product string, the 30 s touch timeout and an options word of
+//! zero (LED not dimmable, not steady, power-reset protection on).
+//!
+//! The LED GPIO, driver and brightness defaults are chosen per board at
+//! build time and are not reported back, so they have no documented value
+//! here. They are never flagged and a reset leaves them as they are.
+
+use crate::hal::types::AppConfig;
+
+/// USB vendor ID of a key with no phy override.
+pub const DEFAULT_VID: &str = "FEFF";
+/// USB product ID of a key with no phy override.
+pub const DEFAULT_PID: &str = "FCFD";
+/// USB product string of a key with no phy override.
+pub const DEFAULT_PRODUCT_NAME: &str = "Pico Key";
+/// Touch timeout to write for the firmware default; `0` means 30 s.
+pub const DEFAULT_TOUCH_TIMEOUT: u8 = 0;
+/// Longest touch timeout the phy record can hold, in seconds.
+pub const MAX_TOUCH_TIMEOUT: u8 = u8::MAX;
+/// `LED_DIMMABLE` is clear in the default options word.
+pub const DEFAULT_LED_DIMMABLE: bool = false;
+/// `LED_STEADY` is clear in the default options word.
+pub const DEFAULT_LED_STEADY: bool = false;
+/// `DISABLE_POWER_RESET` is clear, so power-reset protection is on.
+pub const DEFAULT_POWER_CYCLE_ON_RESET: bool = true;
+
+/// Parse the touch timeout field. Empty is `None` (no value entered);
+/// otherwise whole seconds from `0` (the firmware default) to
+/// [`MAX_TOUCH_TIMEOUT`].
+pub fn parse_touch_timeout(text: &str) -> Result<Option<u8>, String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Ok(None);
+    }
+    text.parse::<u8>().map(Some).map_err(|_| {
+        format!(
+            "Touch timeout must be a whole number of seconds from 0 to {} \
+             (0 or empty for the 30 s firmware default).",
+            MAX_TOUCH_TIMEOUT
+        )
+    })
+}
+
+/// Card of the Configuration screen a setting belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConfigSection {
+    Identity,
+    Led,
+    Touch,
+    Options,
+}
+
+impl ConfigSection {
+    /// Every section with documented defaults, in screen order.
+    pub const ALL: [ConfigSection; 4] = [
+        ConfigSection::Identity,
+        ConfigSection::Led,
+        ConfigSection::Touch,
+        ConfigSection::Options,
+    ];
+
+    /// Card title, as shown on the Configuration screen.
+    pub fn label(self) -> &'static str {
+        match self {
+            ConfigSection::Identity => "Identity",
+            ConfigSection::Led => "LED Settings",
+            ConfigSection::Touch => "Touch & Timing",
+            ConfigSection::Options => "Device Options",
+        }
+    }
+}
+
+/// A setting whose value on the device is not the firmware default.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deviation {
+    pub section: ConfigSection,
+    /// Field label, as shown on the Configuration screen.
+    pub field: &'static str,
+    pub current: String,
+    pub default: String,
+}
+
+fn on_off(on: bool) -> String {
+    if on { "On" } else { "Off" }.to_string()
+}
+
+/// Settings of `config` that differ from the firmware defaults.
+///
+/// Option switches are only compared when the options word was read back,
+/// since otherwise `config` holds placeholders rather than device state.
+pub fn deviations(config: &AppConfig) -> Vec<Deviation> {
+    let mut found = Vec::new();
+    let mut check = |section, field, differs: bool, current: String, default: String| {
+        if differs {
+            found.push(Deviation {
+                section,
+                field,
+                current,
+                default,
+            });
+        }
+    };
+
+    check(
+        ConfigSection::Identity,
+        "Vendor / Product ID",
+        !config.vid.eq_ignore_ascii_case(DEFAULT_VID)
+            || !config.pid.eq_ignore_ascii_case(DEFAULT_PID),
+        format!("{}:{}", config.vid, config.pid).to_uppercase(),
+        format!("{}:{}", DEFAULT_VID, DEFAULT_PID),
+    );
+    check(
+        ConfigSection::Identity,
+        "Product Name",
+        config.product_name != DEFAULT_PRODUCT_NAME,
+        config.product_name.clone(),
+        DEFAULT_PRODUCT_NAME.to_string(),
+    );
+
+    let timeout = config.touch_timeout.unwrap_or(DEFAULT_TOUCH_TIMEOUT);
+    check(
+        ConfigSection::Touch,
+        "Touch Timeout",
+        timeout != DEFAULT_TOUCH_TIMEOUT,
+        format!("{} s", timeout),
+        "Firmware default (30 s)".to_string(),
+    );
+
+    if config.raw_options.is_some() {
+        check(
+            ConfigSection::Led,
+            "LED Dimmable",
+            config.led_dimmable != DEFAULT_LED_DIMMABLE,
+            on_off(config.led_dimmable),
+            on_off(DEFAULT_LED_DIMMABLE),
+        );
+        check(
+            ConfigSection::Led,
+            "LED Steady Mode",
+            config.led_steady != DEFAULT_LED_STEADY,
+            on_off(config.led_steady),
+            on_off(DEFAULT_LED_STEADY),
+        );
+        check(
+            ConfigSection::Options,
+            "Power Cycle on Reset",
+            config.power_cycle_on_reset != DEFAULT_POWER_CYCLE_ON_RESET,
+            on_off(config.power_cycle_on_reset),
+            on_off(DEFAULT_POWER_CYCLE_ON_RESET),
+        );
+    }
+
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn factory() -> AppConfig {
+        AppConfig {
+            vid: "feff".into(),
+            pid: "FCFD".into(),
+            product_name: DEFAULT_PRODUCT_NAME.into(),
+            power_cycle_on_reset: true,
+            raw_options: Some(0),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_factory_config_has_no_deviations() {
+        assert!(deviations(&factory()).is_empty());
+
+        let mut zero_timeout = factory();
+        zero_timeout.touch_timeout = Some(0);
+        assert!(deviations(&zero_timeout).is_empty());
+
+        // Board defaults have no documented value and are never flagged.
+        let mut board = factory();
+        board.led_gpio = Some(25);
+        board.led_brightness = Some(3);
+        board.led_driver = Some(3);
+        assert!(deviations(&board).is_empty());
+    }
+
+    #[test]
+    fn test_parse_touch_timeout() {
+        assert_eq!(parse_touch_timeout(""), Ok(None));
+        assert_eq!(parse_touch_timeout(" 15 "), Ok(Some(15)));
+        assert_eq!(parse_touch_timeout("0"), Ok(Some(DEFAULT_TOUCH_TIMEOUT)));
+        assert_eq!(parse_touch_timeout("255"), Ok(Some(MAX_TOUCH_TIMEOUT)));
+        for bad in ["256", "-1", "1.5", "ten"] {
+            assert!(parse_touch_timeout(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_deviations_by_section() {
+        let mut config = factory();
+        config.vid = "1D50".into();
+        config.pid = "619B".into();
+        config.touch_timeout = Some(15);
+        config.led_steady = true;
+        config.power_cycle_on_reset = false;
+
+        let found = deviations(&config);
+        let sections: Vec<_> = found.iter().map(|d| d.section).collect();
+        assert_eq!(
+            sections,
+            [
+                ConfigSection::Identity,
+                ConfigSection::Touch,
+                ConfigSection::Led,
+                ConfigSection::Options
+            ]
+        );
+        assert_eq!(found[0].current, "1D50:619B");
+        assert_eq!(found[0].default, "FEFF:FCFD");
+        assert_eq!(found[1].current, "15 s");
+    }
+
+    #[test]
+    fn test_unknown_options_are_not_compared() {
+        let mut config = factory();
+        config.raw_options = None;
+        config.led_dimmable = true;
+        config.power_cycle_on_reset = false;
+        assert!(deviations(&config).is_empty());
+    }
+}
@@ -0,0 +1,157 @@
+//! Optional sound cues for moments when the user is looking at the key
+//! rather than the screen.
+//!
+//! Off by default; switched on in About → Appearance and saved in
+//! [`Settings::sound_cues`](crate::settings::Settings::sound_cues). A cue is
+//! played by the platform's own sound player ([`player`]) on a short-lived
+//! thread, so PicoForge needs no audio stack and a machine without one just
+//! stays silent. Desktop hardware offers no portable haptics, so these are
+//! sound only.
+
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+/// Something worth a sound.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cue {
+    /// The key is waiting for a touch.
+    TouchNeeded,
+    /// An operation finished.
+    Success,
+    /// An operation failed.
+    Failure,
+}
+
+/// The same cue is not repeated within this window, so a flapping touch
+/// prompt or a burst of errors does not chatter.
+const REPEAT_GAP: Duration = Duration::from_millis(1500);
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static LAST_PLAYED: Mutex<Option<(Cue, Instant)>> = Mutex::new(None);
+
+/// Turn cues on or off for this session (from settings at startup, and when
+/// the user flips the switch).
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether cues are on.
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Whether `cue` may play at `now`, given the last one played.
+fn due(last: Option<(Cue, Instant)>, cue: Cue, now: Instant) -> bool {
+    !last.is_some_and(|(previous, at)| previous == cue && now.duration_since(at) < REPEAT_GAP)
+}
+
+/// Play `cue` in the background. No-op unless cues are enabled.
+pub fn play(cue: Cue) {
+    if !enabled() {
+        return;
+    }
+    let now = Instant::now();
+    {
+        let mut last = LAST_PLAYED.lock().unwrap_or_else(|e| e.into_inner());
+        if !due(*last, cue, now) {
+            return;
+        }
+        *last = Some((cue, now));
+    }
+    let spawned = std::thread::Builder::new()
+        .name("picoforge-cue".into())
+        .spawn(move || {
+            for mut command in player(cue) {
+                let status = command
+                    .stdin(Stdio::null())
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .status();
+                match status {
+                    Ok(status) if status.success() => return,
+                    Ok(_) => {}
+                    Err(e) => log::debug!("Sound cue player unavailable: {}", e),
+                }
+            }
+            log::debug!("Could not play the {:?} sound cue", cue);
+        });
+    if let Err(e) = spawned {
+        log::debug!("Could not start the sound cue thread: {}", e);
+    }
+}
+
+/// Commands that play `cue`, tried in order until one succeeds.
+#[cfg(target_os = "macos")]
+fn player(cue: Cue) -> Vec<Command> {
+    let sound = match cue {
+        Cue::TouchNeeded => "Tink",
+        Cue::Success => "Glass",
+        Cue::Failure => "Basso",
+    };
+    let mut afplay = Command::new("afplay");
+    afplay.arg(format!("/System/Library/Sounds/{}.aiff", sound));
+    vec![afplay]
+}
+
+/// Commands that play `cue`, tried in order until one succeeds.
+#[cfg(target_os = "windows")]
+fn player(cue: Cue) -> Vec<Command> {
+    use std::os::windows::process::CommandExt;
+    /// Keeps PowerShell from flashing a console window.
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    let sound = match cue {
+        Cue::TouchNeeded => "Exclamation",
+        Cue::Success => "Asterisk",
+        Cue::Failure => "Hand",
+    };
+    let mut powershell = Command::new("powershell");
+    powershell
+        .args(["-NoProfile", "-NonInteractive", "-Command"])
+        .arg(format!("[System.Media.SystemSounds]::{}.Play()", sound))
+        .creation_flags(CREATE_NO_WINDOW);
+    vec![powershell]
+}
+
+/// Commands that play `cue`, tried in order until one succeeds: the
+/// freedesktop sound theme through libcanberra, then PulseAudio/PipeWire.
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn player(cue: Cue) -> Vec<Command> {
+    let event = match cue {
+        Cue::TouchNeeded => "message",
+        Cue::Success => "complete",
+        Cue::Failure => "dialog-error",
+    };
+    let mut canberra = Command::new("canberra-gtk-play");
+    canberra.args(["-i", event, "-d", "PicoForge"]);
+    let mut paplay = Command::new("paplay");
+    paplay.arg(format!(
+        "/usr/share/sounds/freedesktop/stereo/{}.oga",
+        event
+    ));
+    vec![canberra, paplay]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_cue_is_not_repeated_within_the_gap() {
+        let start = Instant::now();
+        assert!(due(None, Cue::Success, start));
+        let last = Some((Cue::Success, start));
+        assert!(!due(last, Cue::Success, start + Duration::from_millis(200)));
+        assert!(due(last, Cue::Failure, start + Duration::from_millis(200)));
+        assert!(due(last, Cue::Success, start + REPEAT_GAP));
+    }
+
+    #[test]
+    fn test_every_cue_has_a_player() {
+        for cue in [Cue::TouchNeeded, Cue::Success, Cue::Failure] {
+            assert!(!player(cue).is_empty());
+        }
+    }
+}
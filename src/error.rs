@@ -1,9 +1,13 @@
 //! Application-wide error types.
 //!
-//! `PFError` is a single enum covering the four failure modes
+//! `PFError` is a single enum covering the failure modes
 //! encountered during device discovery, communication, and I/O.
 //! Each variant carries enough context to render a user-facing message
-//! and to serialize through the UI layer.
+//! and to serialize through the UI layer. A status the authenticator
+//! answered a CTAP command with is kept as [`Ctap2Error`], so callers
+//! match on the code instead of searching the message for it.
+
+use crate::hal::fido::constants::Ctap2Error;
 
 /// Custom error types for PicoForge operations.
 #[derive(Debug, thiserror::Error)]
@@ -20,6 +24,30 @@ pub enum PFError {
     /// A device-level error returned by the firmware or transport layer.
     #[error("Device Error: {0}")]
     Device(String),
+    /// A non-zero CTAP status the authenticator answered a command with.
+    #[error("{0}")]
+    CtapError(Ctap2Error),
+}
+
+impl PFError {
+    /// Variant name, for grouping errors without their message.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            PFError::NoDevice => "NoDevice",
+            PFError::Pcsc(_) => "Pcsc",
+            PFError::Io(_) => "Io",
+            PFError::Device(_) => "Device",
+            PFError::CtapError(_) => "Ctap",
+        }
+    }
+
+    /// The CTAP status, if the authenticator answered with one.
+    pub fn ctap(&self) -> Option<Ctap2Error> {
+        match self {
+            PFError::CtapError(code) => Some(*code),
+            _ => None,
+        }
+    }
 }
 
 impl serde::Serialize for PFError {
@@ -31,21 +59,25 @@ impl serde::Serialize for PFError {
         let mut state = serializer.serialize_struct("PFError", 2)?;
         match self {
             PFError::NoDevice => {
-                state.serialize_field("type", "NoDevice")?;
+                state.serialize_field("type", self.kind())?;
                 state.serialize_field("message", "No device found")?;
             }
             PFError::Pcsc(err) => {
-                state.serialize_field("type", "Pcsc")?;
+                state.serialize_field("type", self.kind())?;
                 state.serialize_field("message", &err.to_string())?;
             }
             PFError::Io(msg) => {
-                state.serialize_field("type", "Io")?;
+                state.serialize_field("type", self.kind())?;
                 state.serialize_field("message", msg)?;
             }
             PFError::Device(msg) => {
-                state.serialize_field("type", "Device")?;
+                state.serialize_field("type", self.kind())?;
                 state.serialize_field("message", msg)?;
             }
+            PFError::CtapError(code) => {
+                state.serialize_field("type", self.kind())?;
+                state.serialize_field("message", &code.to_string())?;
+            }
         }
         state.end()
     }
@@ -0,0 +1,154 @@
+//! Machine-readable event stream (NDJSON).
+//!
+//! Started with `--events <file>`, the desktop app appends one JSON object
+//! per line for device events, operation progress and log records, so
+//! another process (a dashboard, a test harness, a different frontend) can
+//! follow it live instead of polling or scraping the log. A named pipe works
+//! as the file; stdout is not offered because console logging uses it.
+//!
+//! Every line carries `schema` ([`SCHEMA`]), `ts` (Unix time in
+//! milliseconds), `type` and, inside a user action, `action` (the
+//! [`ActionId`](crate::logging::ActionId) of the log lines). The layout is
+//! stable in the same way as the CLI's JSON output: fields may be added, but
+//! none are renamed or removed without bumping [`SCHEMA`].
+//!
+//! The stream is off by default and [`emit`] does nothing until [`open`]
+//! succeeds. A write failure closes the stream rather than the app.
+
+use crate::logging;
+use serde::Serialize;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Version of the line layout.
+pub const SCHEMA: u32 = 1;
+
+static SINK: Mutex<Option<Box<dyn Write + Send>>> = Mutex::new(None);
+
+/// One event.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    /// A key was read for the first time since it was plugged in or selected.
+    DeviceConnected { serial: String, firmware: String },
+    /// The key last read is gone.
+    DeviceDisconnected { serial: String },
+    /// The identity check armed before a replug ran.
+    ReplugVerified { ok: bool, message: String },
+    /// A long-running operation moved on.
+    Progress {
+        operation: String,
+        stage: String,
+        done: usize,
+        total: usize,
+    },
+    /// A log record of the app.
+    Log {
+        level: String,
+        target: String,
+        message: String,
+    },
+}
+
+#[derive(Serialize)]
+struct Line<'a> {
+    schema: u32,
+    ts: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    action: Option<String>,
+    #[serde(flatten)]
+    event: &'a Event,
+}
+
+/// Start appending events to the file at `path`.
+pub fn open(path: &Path) -> Result<(), String> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("Could not open event stream {}: {}", path.display(), e))?;
+    *SINK.lock().unwrap_or_else(|e| e.into_inner()) = Some(Box::new(file));
+    Ok(())
+}
+
+/// Whether the stream is open.
+pub fn enabled() -> bool {
+    SINK.lock().unwrap_or_else(|e| e.into_inner()).is_some()
+}
+
+/// Write `event` to the stream, if it is open.
+///
+/// Must not log: log records are themselves forwarded here.
+pub fn emit(event: Event) {
+    let mut sink = SINK.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(writer) = sink.as_mut() else {
+        return;
+    };
+    let line = encode(&event, logging::current_action().map(|id| id.to_string()));
+    if let Err(e) = writer
+        .write_all(line.as_bytes())
+        .and_then(|_| writer.flush())
+    {
+        eprintln!("Event stream closed: {}", e);
+        *sink = None;
+    }
+}
+
+/// `event` as one NDJSON line, newline included.
+fn encode(event: &Event, action: Option<String>) -> String {
+    let ts = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default();
+    let line = Line {
+        schema: SCHEMA,
+        ts,
+        action,
+        event,
+    };
+    let mut text = serde_json::to_string(&line).unwrap_or_default();
+    text.push('\n');
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_is_flat_tagged_json() {
+        let event = Event::Progress {
+            operation: "backup".into(),
+            stage: "WritingFile".into(),
+            done: 3,
+            total: 8,
+        };
+        let line = encode(&event, Some("1a-0002".into()));
+        assert!(line.ends_with('\n'));
+        assert_eq!(line.lines().count(), 1);
+
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(value["schema"], SCHEMA);
+        assert_eq!(value["type"], "progress");
+        assert_eq!(value["action"], "1a-0002");
+        assert_eq!(value["operation"], "backup");
+        assert_eq!(value["done"], 3);
+        assert!(value["ts"].as_u64().is_some());
+    }
+
+    #[test]
+    fn test_action_omitted_outside_actions() {
+        let line = encode(
+            &Event::DeviceDisconnected {
+                serial: "E6614C31".into(),
+            },
+            None,
+        );
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(value["type"], "device_disconnected");
+        assert!(value.get("action").is_none());
+    }
+}
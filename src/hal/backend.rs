@@ -0,0 +1,453 @@
+//! One interface over the two ways PicoForge talks to a key.
+//!
+//! [`DeviceBackend`] covers what both transports can do — read the status,
+//! write the configuration — and what each one needs to do it.
+//! [`FidoBackend`] goes over CTAPHID, [`RescueBackend`] over the Rescue
+//! applet on PC/SC. [`ordered`] lists them in `transport.order` from
+//! `picoforge.toml`, which [`io::read_device_details`](crate::hal::io::read_device_details)
+//! walks to fall back from one to the other; [`for_method`] picks the one a
+//! write goes to. Writes are planned as a [`ConfigPlan`] for the backend's
+//! write path; [`check_write`] compares what the plan wrote with what the
+//! backend reads back from the same record.
+
+use crate::error::PFError;
+use crate::hal::config_plan::{AppliedPlan, ConfigPlan, WritePath};
+use crate::hal::rescue::constants::{RescueCurves, UsbInterfaces};
+use crate::hal::types::{
+    AppConfig, AppConfigInput, ConfigApplyReport, ConfigFieldReadBack, DeviceMethod,
+    FullDeviceStatus,
+};
+use crate::hal::{fido, rescue};
+use crate::tunables::Transport;
+
+/// How a backend differs from the others in what it needs or offers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BackendCapabilities {
+    /// Configuration writes need the FIDO PIN.
+    pub config_needs_pin: bool,
+}
+
+/// A transport that can read and configure a key. Every call blocks.
+pub trait DeviceBackend {
+    /// The method recorded in statuses read through this backend.
+    fn method(&self) -> DeviceMethod;
+
+    /// Name used in log messages.
+    fn name(&self) -> &'static str;
+
+    /// Read the full status. [`PFError::NoDevice`] when no key answers on
+    /// this transport.
+    fn read_status(&self) -> Result<FullDeviceStatus, PFError>;
+
+    /// Plan the fields set in `config` for this backend's write path and
+    /// apply the plan. `pin` is ignored by backends that do not need it.
+    fn write_config(
+        &self,
+        config: AppConfigInput,
+        pin: Option<String>,
+    ) -> Result<AppliedPlan, PFError>;
+
+    /// Read back the configuration record writes go to, without the live
+    /// USB descriptor. Fields the record does not carry keep their
+    /// defaults.
+    fn read_stored_config(&self) -> Result<AppConfig, PFError>;
+
+    fn capabilities(&self) -> BackendCapabilities;
+}
+
+/// CTAPHID over USB HID, through the shared connection.
+pub struct FidoBackend;
+
+impl DeviceBackend for FidoBackend {
+    fn method(&self) -> DeviceMethod {
+        DeviceMethod::Fido
+    }
+
+    fn name(&self) -> &'static str {
+        "FIDO"
+    }
+
+    fn read_status(&self) -> Result<FullDeviceStatus, PFError> {
+        fido::read_device_details()
+    }
+
+    fn write_config(
+        &self,
+        config: AppConfigInput,
+        pin: Option<String>,
+    ) -> Result<AppliedPlan, PFError> {
+        fido::write_config(config, pin)
+    }
+
+    fn read_stored_config(&self) -> Result<AppConfig, PFError> {
+        fido::read_stored_config()
+    }
+
+    fn capabilities(&self) -> BackendCapabilities {
+        BackendCapabilities {
+            config_needs_pin: true,
+        }
+    }
+}
+
+/// The Rescue applet over PC/SC.
+pub struct RescueBackend;
+
+impl DeviceBackend for RescueBackend {
+    fn method(&self) -> DeviceMethod {
+        DeviceMethod::Rescue
+    }
+
+    fn name(&self) -> &'static str {
+        "Rescue"
+    }
+
+    fn read_status(&self) -> Result<FullDeviceStatus, PFError> {
+        rescue::read_device_details()
+    }
+
+    fn write_config(
+        &self,
+        config: AppConfigInput,
+        _pin: Option<String>,
+    ) -> Result<AppliedPlan, PFError> {
+        let plan = ConfigPlan::new(&config, WritePath::RescueRecord)?;
+        let note = rescue::write_config(plan.record_input())?;
+        Ok(AppliedPlan { plan, note })
+    }
+
+    fn read_stored_config(&self) -> Result<AppConfig, PFError> {
+        rescue::read_stored_config()
+    }
+
+    fn capabilities(&self) -> BackendCapabilities {
+        BackendCapabilities {
+            config_needs_pin: false,
+        }
+    }
+}
+
+/// Write `config` through `backend` and check every field its plan wrote
+/// against the record read back afterwards. A failed read-back leaves every
+/// field unchecked rather than failing the write, which already went
+/// through. Fields the write path could not carry were never sent; they
+/// are listed in [`ConfigApplyReport::skipped`] alongside the unreported
+/// ones.
+pub fn write_and_verify(
+    backend: &dyn DeviceBackend,
+    config: AppConfigInput,
+    pin: Option<String>,
+) -> Result<ConfigApplyReport, PFError> {
+    let AppliedPlan { plan, note } = backend.write_config(config, pin)?;
+    let read_back = backend
+        .read_stored_config()
+        .inspect_err(|e| {
+            log::warn!(
+                "Could not read the configuration back over {}: {}",
+                backend.name(),
+                e
+            )
+        })
+        .ok();
+    let mut report = check_write(&plan.record_input(), read_back.as_ref(), note);
+    report.replug_required = plan.needs_replug();
+    report
+        .skipped
+        .extend(plan.dropped.iter().map(|op| op.label().to_string()));
+    for field in report.mismatched() {
+        log::warn!(
+            "{} was written as {} but reads back as {}",
+            field.field,
+            field.written,
+            field.read_back.as_deref().unwrap_or("nothing")
+        );
+    }
+    log::info!(
+        "Configuration write: {} fields applied, {} verified, {} not reported",
+        report.applied.len(),
+        report.verified.len(),
+        report.skipped.len()
+    );
+    Ok(report)
+}
+
+/// Compare the fields set in `config` with `read_back`. A field the
+/// read-back does not report — an empty string, a `None`, or option flags
+/// without the options word — is skipped, not counted as a mismatch.
+pub fn check_write(
+    config: &AppConfigInput,
+    read_back: Option<&AppConfig>,
+    note: String,
+) -> ConfigApplyReport {
+    let mut report = ConfigApplyReport {
+        note,
+        ..Default::default()
+    };
+    let mut field = |name: &str, written: Option<String>, read: Option<String>| {
+        let Some(written) = written else {
+            return;
+        };
+        match &read {
+            None => report.skipped.push(name.to_string()),
+            Some(read) if *read == written => report.verified.push(name.to_string()),
+            Some(_) => {}
+        }
+        report.applied.push(ConfigFieldReadBack {
+            field: name.to_string(),
+            written,
+            read_back: read,
+        });
+    };
+    // The flags are only device state when the options word was read.
+    let option = |flag: fn(&AppConfig) -> bool| {
+        read_back
+            .filter(|c| c.raw_options.is_some())
+            .map(|c| flag(c).to_string())
+    };
+
+    if let (Some(vid), Some(pid)) = (&config.vid, &config.pid) {
+        field(
+            "VID:PID",
+            Some(format!("{}:{}", usb_id(vid), usb_id(pid))),
+            read_back
+                .filter(|c| !c.vid.is_empty() && !c.pid.is_empty())
+                .map(|c| format!("{}:{}", usb_id(&c.vid), usb_id(&c.pid))),
+        );
+    }
+    field(
+        "Product name",
+        config.product_name.clone().filter(|n| !n.is_empty()),
+        read_back.and_then(|c| (!c.product_name.is_empty()).then(|| c.product_name.clone())),
+    );
+    field(
+        "LED GPIO",
+        config.led_gpio.map(|v| v.to_string()),
+        read_back.and_then(|c| c.led_gpio).map(|v| v.to_string()),
+    );
+    field(
+        "LED brightness",
+        config.led_brightness.map(|v| v.to_string()),
+        read_back
+            .and_then(|c| c.led_brightness)
+            .map(|v| v.to_string()),
+    );
+    field(
+        "Touch timeout",
+        config.touch_timeout.map(|v| v.to_string()),
+        read_back
+            .and_then(|c| c.touch_timeout)
+            .map(|v| v.to_string()),
+    );
+    field(
+        "LED driver",
+        config.led_driver.map(|v| v.to_string()),
+        read_back.and_then(|c| c.led_driver).map(|v| v.to_string()),
+    );
+    field(
+        "LED dimmable",
+        config.led_dimmable.map(|v| v.to_string()),
+        option(|c| c.led_dimmable),
+    );
+    field(
+        "Power-cycle on reset",
+        config.power_cycle_on_reset.map(|v| v.to_string()),
+        option(|c| c.power_cycle_on_reset),
+    );
+    field(
+        "LED steady",
+        config.led_steady.map(|v| v.to_string()),
+        option(|c| c.led_steady),
+    );
+    // A full mask is checked as the mask written, the secp256k1 switch on
+    // its own as the one bit it changes.
+    let curves = read_back.and_then(|c| c.raw_curves_mask);
+    match config.raw_curves_mask {
+        Some(mask) => field(
+            "Curves",
+            Some(format!(
+                "0x{:08X}",
+                RescueCurves::merge(mask, config.enable_secp256k1)
+            )),
+            curves.map(|mask| format!("0x{:08X}", mask)),
+        ),
+        None => field(
+            "secp256k1",
+            config.enable_secp256k1.map(|v| v.to_string()),
+            read_back
+                .filter(|c| c.raw_curves_mask.is_some())
+                .map(|c| c.enable_secp256k1.to_string()),
+        ),
+    }
+    field(
+        "LED order",
+        config.led_order.map(|v| v.to_string()),
+        read_back.and_then(|c| c.led_order).map(|v| v.to_string()),
+    );
+    // The rescue write forces CCID on, so that bit is not compared.
+    let ccid = UsbInterfaces::CCID.bits();
+    field(
+        "USB interfaces",
+        config
+            .enabled_usb_itf
+            .map(|v| format!("0x{:02X}", v | ccid)),
+        read_back
+            .and_then(|c| c.enabled_usb_itf)
+            .map(|v| format!("0x{:02X}", v | ccid)),
+    );
+    field(
+        "LED count",
+        config.led_num.map(|v| v.to_string()),
+        read_back.and_then(|c| c.led_num).map(|v| v.to_string()),
+    );
+    report
+}
+
+/// A VID or PID as four upper-case hex digits, however it was typed.
+fn usb_id(value: &str) -> String {
+    u16::from_str_radix(value, 16)
+        .map(|id| format!("{:04X}", id))
+        .unwrap_or_else(|_| value.to_uppercase())
+}
+
+/// The backend behind `transport`.
+pub fn for_transport(transport: Transport) -> Box<dyn DeviceBackend> {
+    match transport {
+        Transport::Fido => Box::new(FidoBackend),
+        Transport::Rescue => Box::new(RescueBackend),
+    }
+}
+
+/// The backend that reads and writes as `method`.
+pub fn for_method(method: DeviceMethod) -> Box<dyn DeviceBackend> {
+    match method {
+        DeviceMethod::Fido => Box::new(FidoBackend),
+        DeviceMethod::Rescue => Box::new(RescueBackend),
+    }
+}
+
+/// Backends for `order`, first to try first. A transport left out is not
+/// included.
+pub fn ordered(order: &[Transport]) -> Vec<Box<dyn DeviceBackend>> {
+    order.iter().copied().map(for_transport).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ordered_follows_transport_order() {
+        let methods: Vec<_> = ordered(&[Transport::Fido, Transport::Rescue])
+            .iter()
+            .map(|b| b.method())
+            .collect();
+        assert_eq!(methods, [DeviceMethod::Fido, DeviceMethod::Rescue]);
+
+        let only_rescue = ordered(&[Transport::Rescue]);
+        assert_eq!(only_rescue.len(), 1);
+        assert_eq!(only_rescue[0].method(), DeviceMethod::Rescue);
+    }
+
+    fn input() -> AppConfigInput {
+        AppConfigInput {
+            vid: None,
+            pid: None,
+            product_name: None,
+            led_gpio: None,
+            led_brightness: None,
+            touch_timeout: None,
+            led_driver: None,
+            led_dimmable: None,
+            power_cycle_on_reset: None,
+            led_steady: None,
+            enable_secp256k1: None,
+            raw_curves_mask: None,
+            led_order: None,
+            enabled_usb_itf: None,
+            led_num: None,
+        }
+    }
+
+    #[test]
+    fn test_check_write_verifies_what_reads_back() {
+        let config = AppConfigInput {
+            vid: Some("cafe".into()),
+            pid: Some("1".into()),
+            led_brightness: Some(10),
+            led_steady: Some(true),
+            touch_timeout: Some(5),
+            ..input()
+        };
+        let read_back = AppConfig {
+            vid: "CAFE".into(),
+            pid: "0001".into(),
+            led_brightness: Some(10),
+            led_steady: true,
+            raw_options: Some(0x08),
+            ..Default::default()
+        };
+
+        let report = check_write(&config, Some(&read_back), String::new());
+        assert_eq!(report.applied.len(), 4);
+        assert_eq!(report.verified, ["VID:PID", "LED brightness", "LED steady"]);
+        assert_eq!(report.skipped, ["Touch timeout"]);
+        assert!(report.is_consistent());
+    }
+
+    #[test]
+    fn test_check_write_reports_mismatches() {
+        let config = AppConfigInput {
+            led_brightness: Some(10),
+            power_cycle_on_reset: Some(false),
+            ..input()
+        };
+        let read_back = AppConfig {
+            led_brightness: Some(3),
+            power_cycle_on_reset: false,
+            raw_options: Some(0x04),
+            ..Default::default()
+        };
+
+        let report = check_write(&config, Some(&read_back), String::new());
+        let mismatched: Vec<_> = report.mismatched().collect();
+        assert_eq!(mismatched.len(), 1);
+        assert_eq!(mismatched[0].field, "LED brightness");
+        assert_eq!(mismatched[0].read_back.as_deref(), Some("3"));
+        assert_eq!(report.verified, ["Power-cycle on reset"]);
+    }
+
+    #[test]
+    fn test_check_write_skips_unreported_fields() {
+        let config = AppConfigInput {
+            led_dimmable: Some(true),
+            enable_secp256k1: Some(true),
+            ..input()
+        };
+
+        // Options and curves were not read, so their defaults prove nothing.
+        let report = check_write(&config, Some(&AppConfig::default()), String::new());
+        assert_eq!(report.skipped, ["LED dimmable", "secp256k1"]);
+        assert!(report.verified.is_empty());
+
+        let report = check_write(&config, None, String::new());
+        assert_eq!(report.skipped.len(), 2);
+        assert!(report.is_consistent());
+    }
+
+    #[test]
+    fn test_for_method_round_trips() {
+        for method in [DeviceMethod::Fido, DeviceMethod::Rescue] {
+            assert_eq!(for_method(method.clone()).method(), method);
+        }
+        assert!(
+            for_method(DeviceMethod::Fido)
+                .capabilities()
+                .config_needs_pin
+        );
+        assert!(
+            !for_method(DeviceMethod::Rescue)
+                .capabilities()
+                .config_needs_pin
+        );
+    }
+}
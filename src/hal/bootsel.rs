@@ -0,0 +1,543 @@
+//! Board identification for keys sitting in the RP2040/RP2350 BOOTSEL loader.
+//!
+//! A key in BOOTSEL mode has no FIDO or CCID interface, only the boot ROM's
+//! USB mass-storage drive and its PICOBOOT vendor interface. The board is
+//! identified from `INFO_UF2.TXT` on the mounted drive:
+//!
+//! ```text
+//! UF2 Bootloader v3.0
+//! Model: Raspberry Pi RP2
+//! Board-ID: RPI-RP2
+//! ```
+//!
+//! That gives the chip family and whether an RP2350 has been whitelabelled
+//! (the OTP whitelabel block replaces the model and board ID). Flash size and
+//! chip revision come from PICOBOOT ([`picoboot`]). A drive and a PICOBOOT
+//! interface cannot be told apart between several boards, so the two are
+//! only combined when exactly one of each is attached.
+//!
+//! [`Whitelabel`] validates a custom identity for an RP2350 and
+//! [`write_whitelabel`] burns it into OTP over PICOBOOT (RP2350 datasheet
+//! §5.7, "USB white-labelling"): a block of ECC rows at
+//! [`WHITELABEL_BASE_ROW`] with one descriptor row per field and the strings
+//! after them, its row number in `USB_WHITE_LABEL_ADDR`, and one flag per
+//! field in the three redundant `USB_BOOT_FLAGS` rows. The flags go last, so
+//! a write that fails part way leaves the boot ROM on its defaults.
+
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::PFError;
+use crate::hal::transport::picoboot::{self, PicobootInfo, PicobootTransport, RomChip};
+
+/// Name of the info file every UF2 boot drive exposes.
+const INFO_FILE: &str = "INFO_UF2.TXT";
+
+const RP2040_MODEL: &str = "Raspberry Pi RP2";
+const RP2040_BOARD_ID: &str = "RPI-RP2";
+const RP2350_MODEL: &str = "Raspberry Pi RP2350";
+const RP2350_BOARD_ID: &str = "RP2350";
+
+/// Chip family behind a boot drive.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BootselChip {
+    /// RP2040 boot ROM (no whitelabel support).
+    Rp2040,
+    /// RP2350 boot ROM.
+    Rp2350,
+    /// Model and board ID are not the boot ROM defaults: a whitelabelled
+    /// RP2350 or another UF2 bootloader.
+    Unknown,
+}
+
+/// What a boot drive reports about the board.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct BootselBoard {
+    /// Where the boot drive is mounted; `None` for a board only found over
+    /// PICOBOOT (drive not mounted yet).
+    pub mount_point: Option<PathBuf>,
+    /// First line of `INFO_UF2.TXT`, e.g. `UF2 Bootloader v3.0`.
+    pub bootloader: String,
+    /// `Model:` line.
+    pub model: String,
+    /// `Board-ID:` line.
+    pub board_id: String,
+    /// Chip family derived from the model and board ID.
+    pub chip: BootselChip,
+    /// `Some(true)` when an RP2350 reports a customised identity, `Some(false)`
+    /// for stock boot ROM strings, `None` when it cannot be told.
+    pub whitelabel: Option<bool>,
+    /// Silicon revision read over PICOBOOT, e.g. `B2` or `A4`.
+    pub chip_revision: Option<String>,
+    /// Flash size in bytes read over PICOBOOT.
+    pub flash_size: Option<u32>,
+}
+
+impl BootselBoard {
+    /// A board only PICOBOOT answered for.
+    fn from_picoboot(info: PicobootInfo) -> Self {
+        let (model, chip) = match info.chip {
+            RomChip::Rp2040 => (RP2040_MODEL, BootselChip::Rp2040),
+            RomChip::Rp2350 => (RP2350_MODEL, BootselChip::Rp2350),
+        };
+        Self {
+            mount_point: None,
+            bootloader: String::new(),
+            model: model.to_string(),
+            board_id: String::new(),
+            chip,
+            whitelabel: None,
+            chip_revision: Some(info.revision),
+            flash_size: info.flash_size,
+        }
+    }
+
+    /// Take revision and flash size from `info` if it is the same chip.
+    fn add_picoboot(&mut self, info: PicobootInfo) {
+        let chip = match info.chip {
+            RomChip::Rp2040 => BootselChip::Rp2040,
+            RomChip::Rp2350 => BootselChip::Rp2350,
+        };
+        if self.chip != BootselChip::Unknown && self.chip != chip {
+            log::warn!(
+                "PICOBOOT at {} reports {:?}, the boot drive {:?}; not combining them",
+                info.location,
+                chip,
+                self.chip
+            );
+            return;
+        }
+        self.chip = chip;
+        self.chip_revision = Some(info.revision);
+        self.flash_size = info.flash_size;
+    }
+}
+
+/// Parse the contents of `INFO_UF2.TXT`. `None` if it has no model line.
+pub fn parse_info_uf2(mount_point: &Path, text: &str) -> Option<BootselBoard> {
+    let mut lines = text.lines().map(str::trim);
+    let bootloader = lines.next().unwrap_or_default().to_string();
+    let mut model = None;
+    let mut board_id = String::new();
+    for line in lines {
+        if let Some(value) = line.strip_prefix("Model:") {
+            model = Some(value.trim().to_string());
+        } else if let Some(value) = line.strip_prefix("Board-ID:") {
+            board_id = value.trim().to_string();
+        }
+    }
+    let model = model?;
+
+    let (chip, whitelabel) = if model == RP2040_MODEL && board_id == RP2040_BOARD_ID {
+        (BootselChip::Rp2040, Some(false))
+    } else if model == RP2350_MODEL && board_id == RP2350_BOARD_ID {
+        (BootselChip::Rp2350, Some(false))
+    } else if board_id.starts_with(RP2350_BOARD_ID) || model.contains(RP2350_BOARD_ID) {
+        (BootselChip::Rp2350, Some(true))
+    } else {
+        (BootselChip::Unknown, None)
+    };
+
+    Some(BootselBoard {
+        mount_point: Some(mount_point.to_path_buf()),
+        bootloader,
+        model,
+        board_id,
+        chip,
+        whitelabel,
+        chip_revision: None,
+        flash_size: None,
+    })
+}
+
+/// Longest USB string descriptor (manufacturer, product, serial) we accept.
+pub const WHITELABEL_MAX_USB_STRING: usize = 30;
+/// Longest FAT volume label.
+pub const WHITELABEL_MAX_VOLUME_LABEL: usize = 11;
+
+/// `USB_BOOT_FLAGS` and its two redundant copies (raw rows).
+const OTP_USB_BOOT_FLAGS: u16 = 0x059;
+const OTP_USB_BOOT_FLAGS_COPIES: usize = 3;
+/// `USB_WHITE_LABEL_ADDR`: row of the whitelabel block (ECC row).
+const OTP_USB_WHITE_LABEL_ADDR: u16 = 0x05C;
+/// `USB_BOOT_FLAGS.WHITE_LABEL_ADDR_VALID`.
+const FLAG_WHITE_LABEL_ADDR_VALID: u32 = 1 << 22;
+
+/// Row the whitelabel block is burned at: the start of the user pages, as in
+/// the picotool documentation.
+pub const WHITELABEL_BASE_ROW: u16 = 0x100;
+
+/// Descriptor rows at the start of the block, one per boot ROM field.
+const WL_DESCRIPTOR_ROWS: usize = 16;
+/// Index of each field's descriptor row, and its `USB_BOOT_FLAGS` bit.
+const WL_VID: usize = 0;
+const WL_PID: usize = 1;
+const WL_MANUFACTURER: usize = 4;
+const WL_PRODUCT: usize = 5;
+const WL_SERIAL_NUMBER: usize = 6;
+const WL_VOLUME_LABEL: usize = 8;
+/// String descriptor: character count in bits 0-6, UTF-16 in bit 7, row
+/// offset from the start of the block in bits 8-15.
+const WL_STRDEF_UNICODE: u16 = 1 << 7;
+
+/// Rows to burn for one whitelabel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WhitelabelRows {
+    /// ECC rows of the block, from [`WHITELABEL_BASE_ROW`].
+    pub block: Vec<u16>,
+    /// `USB_BOOT_FLAGS` bits to set, including `WHITE_LABEL_ADDR_VALID`.
+    pub flags: u32,
+}
+
+/// Custom boot ROM identity for an RP2350's OTP whitelabel block.
+///
+/// Empty strings and `None` IDs are left out of the block, so the boot ROM
+/// keeps its default for them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Whitelabel {
+    /// USB vendor ID.
+    pub vid: Option<u16>,
+    /// USB product ID.
+    pub pid: Option<u16>,
+    /// USB manufacturer string.
+    pub manufacturer: String,
+    /// USB product string.
+    pub product: String,
+    /// USB serial number string.
+    pub serial_number: String,
+    /// Label of the BOOTSEL drive.
+    pub volume_label: String,
+}
+
+impl Whitelabel {
+    /// Check lengths and character sets. Returns one message per problem.
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        for (name, value) in [
+            ("Manufacturer", &self.manufacturer),
+            ("Product", &self.product),
+            ("Serial number", &self.serial_number),
+        ] {
+            if value.chars().count() > WHITELABEL_MAX_USB_STRING {
+                problems.push(format!(
+                    "{} is longer than {} characters",
+                    name, WHITELABEL_MAX_USB_STRING
+                ));
+            }
+        }
+        if self.volume_label.len() > WHITELABEL_MAX_VOLUME_LABEL {
+            problems.push(format!(
+                "Volume label is longer than {} characters",
+                WHITELABEL_MAX_VOLUME_LABEL
+            ));
+        }
+        if !self
+            .volume_label
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == ' ' || c == '-' || c == '_')
+        {
+            problems.push("Volume label may only use A-Z, 0-9, space, - and _".into());
+        }
+        if self.vid.is_some() != self.pid.is_some() {
+            problems.push("Set both VID and PID, or neither".into());
+        }
+        if self.is_empty() {
+            problems.push("Nothing to write: every field is empty".into());
+        }
+        problems
+    }
+
+    fn is_empty(&self) -> bool {
+        self.vid.is_none()
+            && self.pid.is_none()
+            && self.manufacturer.is_empty()
+            && self.product.is_empty()
+            && self.serial_number.is_empty()
+            && self.volume_label.is_empty()
+    }
+
+    /// Lay the identity out as OTP rows. Strings that are plain ASCII are
+    /// packed two characters to a row; others take a row per UTF-16 unit.
+    pub fn to_otp_rows(&self) -> WhitelabelRows {
+        let mut block = vec![0u16; WL_DESCRIPTOR_ROWS];
+        let mut flags = FLAG_WHITE_LABEL_ADDR_VALID;
+        if let (Some(vid), Some(pid)) = (self.vid, self.pid) {
+            block[WL_VID] = vid;
+            block[WL_PID] = pid;
+            flags |= 1 << WL_VID | 1 << WL_PID;
+        }
+        for (index, value) in [
+            (WL_MANUFACTURER, &self.manufacturer),
+            (WL_PRODUCT, &self.product),
+            (WL_SERIAL_NUMBER, &self.serial_number),
+            (WL_VOLUME_LABEL, &self.volume_label),
+        ] {
+            if value.is_empty() {
+                continue;
+            }
+            let offset = (block.len() as u16) << 8;
+            if value.is_ascii() {
+                block[index] = offset | value.len() as u16;
+                block.extend(
+                    value
+                        .as_bytes()
+                        .chunks(2)
+                        .map(|pair| u16::from_le_bytes([pair[0], *pair.get(1).unwrap_or(&0)])),
+                );
+            } else {
+                let units: Vec<u16> = value.encode_utf16().collect();
+                block[index] = offset | WL_STRDEF_UNICODE | units.len() as u16;
+                block.extend(units);
+            }
+            flags |= 1 << index;
+        }
+        WhitelabelRows { block, flags }
+    }
+}
+
+/// Burn `label` into the OTP of the one RP2350 in BOOTSEL mode, then read it
+/// back. Refuses boards that already carry a whitelabel or whose target rows
+/// are not blank. Irreversible; returns a summary for the audit log.
+pub fn write_whitelabel(label: &Whitelabel) -> Result<String, PFError> {
+    let problems = label.validate();
+    if !problems.is_empty() {
+        return Err(PFError::Device(problems.join(". ")));
+    }
+    let rows = label.to_otp_rows();
+    let mut transport = PicobootTransport::open_rp2350()?;
+
+    let flags = transport.otp_read_raw(OTP_USB_BOOT_FLAGS, OTP_USB_BOOT_FLAGS_COPIES)?;
+    let addr = transport.otp_read_raw(OTP_USB_WHITE_LABEL_ADDR, 1)?;
+    if flags.iter().any(|f| f & FLAG_WHITE_LABEL_ADDR_VALID != 0) || addr[0] != 0 {
+        return Err(PFError::Device(
+            "This board already has a whitelabel in OTP; it cannot be changed".into(),
+        ));
+    }
+    let target = transport.otp_read_raw(WHITELABEL_BASE_ROW, rows.block.len())?;
+    if let Some(used) = target.iter().position(|&row| row != 0) {
+        return Err(PFError::Device(format!(
+            "OTP row 0x{:03X} is already programmed; nothing was written",
+            WHITELABEL_BASE_ROW as usize + used
+        )));
+    }
+
+    log::info!(
+        "Burning {} whitelabel rows at 0x{:03X} on {}",
+        rows.block.len(),
+        WHITELABEL_BASE_ROW,
+        transport.location()
+    );
+    transport.otp_write_ecc(WHITELABEL_BASE_ROW, &rows.block)?;
+    if transport.otp_read_ecc(WHITELABEL_BASE_ROW, rows.block.len())? != rows.block {
+        return Err(PFError::Device(
+            "The whitelabel block did not read back as written; the boot ROM keeps its \
+             defaults because its flags were not set"
+                .into(),
+        ));
+    }
+    transport.otp_write_ecc(OTP_USB_WHITE_LABEL_ADDR, &[WHITELABEL_BASE_ROW])?;
+    let new_flags: Vec<u32> = flags.iter().map(|f| f | rows.flags).collect();
+    transport.otp_write_raw(OTP_USB_BOOT_FLAGS, &new_flags)?;
+    if transport.otp_read_raw(OTP_USB_BOOT_FLAGS, OTP_USB_BOOT_FLAGS_COPIES)? != new_flags {
+        return Err(PFError::Device(
+            "USB_BOOT_FLAGS did not read back as written".into(),
+        ));
+    }
+
+    Ok(format!(
+        "{} OTP rows at 0x{:03X}, USB_BOOT_FLAGS {:06X}",
+        rows.block.len(),
+        WHITELABEL_BASE_ROW,
+        new_flags[0]
+    ))
+}
+
+/// Directories under which removable drives are mounted on this platform.
+fn volume_roots() -> Vec<PathBuf> {
+    let mut roots = Vec::new();
+    if cfg!(target_os = "windows") {
+        roots.extend((b'D'..=b'Z').map(|letter| PathBuf::from(format!("{}:\\", letter as char))));
+    } else if cfg!(target_os = "macos") {
+        roots.push(PathBuf::from("/Volumes"));
+    } else {
+        roots.push(PathBuf::from("/media"));
+        roots.push(PathBuf::from("/run/media"));
+        if let Ok(user) = std::env::var("USER") {
+            roots.push(PathBuf::from("/media").join(&user));
+            roots.push(PathBuf::from("/run/media").join(user));
+        }
+    }
+    roots
+}
+
+fn candidate_mounts() -> Vec<PathBuf> {
+    if cfg!(target_os = "windows") {
+        return volume_roots();
+    }
+    volume_roots()
+        .into_iter()
+        .filter_map(|root| fs::read_dir(root).ok())
+        .flat_map(|entries| entries.filter_map(|e| e.ok()).map(|e| e.path()))
+        .collect()
+}
+
+/// Find mounted BOOTSEL drives. Returns an empty list when none are mounted
+/// (the OS may take a moment to mount the drive after the reboot).
+pub fn scan_boot_drives() -> Vec<BootselBoard> {
+    let mut boards: Vec<BootselBoard> = candidate_mounts()
+        .into_iter()
+        .filter_map(|mount| {
+            let text = fs::read_to_string(mount.join(INFO_FILE)).ok()?;
+            parse_info_uf2(&mount, &text)
+        })
+        .collect();
+    boards.sort_by(|a, b| a.mount_point.cmp(&b.mount_point));
+    boards.dedup_by(|a, b| a.mount_point == b.mount_point);
+    for board in &boards {
+        log::info!(
+            "BOOTSEL drive at {}: {} / {} ({})",
+            board
+                .mount_point
+                .as_deref()
+                .unwrap_or(Path::new("?"))
+                .display(),
+            board.model,
+            board.board_id,
+            board.bootloader
+        );
+    }
+    boards
+}
+
+/// Find boards in BOOTSEL mode from their boot drives and PICOBOOT
+/// interfaces. Blocking: probes every drive letter on Windows and talks to
+/// each board over USB.
+pub fn scan() -> Vec<BootselBoard> {
+    combine(scan_boot_drives(), picoboot::scan())
+}
+
+/// Merge drives and PICOBOOT answers: one of each is the same board; with
+/// no drive mounted the PICOBOOT boards stand alone; several of each cannot
+/// be paired, so only the drives are shown.
+fn combine(mut drives: Vec<BootselBoard>, picoboot: Vec<PicobootInfo>) -> Vec<BootselBoard> {
+    match (drives.len(), picoboot.len()) {
+        (_, 0) => drives,
+        (0, _) => picoboot
+            .into_iter()
+            .map(BootselBoard::from_picoboot)
+            .collect(),
+        (1, 1) => {
+            if let Some(info) = picoboot.into_iter().next() {
+                drives[0].add_picoboot(info);
+            }
+            drives
+        }
+        (d, p) => {
+            log::info!(
+                "{} boot drive(s) and {} PICOBOOT interface(s) attached; not pairing them",
+                d,
+                p
+            );
+            drives
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rp2040() {
+        let text = "UF2 Bootloader v3.0\r\nModel: Raspberry Pi RP2\r\nBoard-ID: RPI-RP2\r\n";
+        let board = parse_info_uf2(Path::new("/media/RPI-RP2"), text).unwrap();
+        assert_eq!(board.chip, BootselChip::Rp2040);
+        assert_eq!(board.whitelabel, Some(false));
+        assert_eq!(board.bootloader, "UF2 Bootloader v3.0");
+    }
+
+    #[test]
+    fn test_parse_whitelabelled_rp2350() {
+        let text = "UF2 Bootloader v1.0\nModel: ACME Key\nBoard-ID: RP2350-ACME\n";
+        let board = parse_info_uf2(Path::new("/Volumes/ACME"), text).unwrap();
+        assert_eq!(board.chip, BootselChip::Rp2350);
+        assert_eq!(board.whitelabel, Some(true));
+    }
+
+    fn picoboot_info(chip: RomChip) -> PicobootInfo {
+        PicobootInfo {
+            location: "bus 1 address 2".into(),
+            chip,
+            revision: "B2".into(),
+            flash_size: Some(2 * 1024 * 1024),
+        }
+    }
+
+    #[test]
+    fn test_combine_pairs_one_drive_with_one_picoboot() {
+        let text = "UF2 Bootloader v3.0\nModel: Raspberry Pi RP2\nBoard-ID: RPI-RP2\n";
+        let drive = parse_info_uf2(Path::new("/media/RPI-RP2"), text).unwrap();
+
+        let boards = combine(vec![drive.clone()], vec![picoboot_info(RomChip::Rp2040)]);
+        assert_eq!(boards[0].chip_revision.as_deref(), Some("B2"));
+        assert_eq!(boards[0].flash_size, Some(2 * 1024 * 1024));
+
+        let boards = combine(vec![drive.clone()], vec![picoboot_info(RomChip::Rp2350)]);
+        assert_eq!(boards[0].chip_revision, None);
+
+        let boards = combine(
+            vec![drive.clone(), drive],
+            vec![picoboot_info(RomChip::Rp2040); 2],
+        );
+        assert!(boards.iter().all(|b| b.flash_size.is_none()));
+
+        let boards = combine(Vec::new(), vec![picoboot_info(RomChip::Rp2040)]);
+        assert_eq!(boards[0].mount_point, None);
+        assert_eq!(boards[0].chip, BootselChip::Rp2040);
+    }
+
+    #[test]
+    fn test_parse_rejects_other_files() {
+        assert_eq!(parse_info_uf2(Path::new("/"), "hello\n"), None);
+    }
+
+    #[test]
+    fn test_whitelabel_rows_layout() {
+        let label = Whitelabel {
+            vid: Some(0x1d50),
+            pid: Some(0x619b),
+            product: "ACME".into(),
+            serial_number: "Ä1".into(),
+            ..Default::default()
+        };
+        assert!(label.validate().is_empty());
+
+        let rows = label.to_otp_rows();
+        assert_eq!(rows.flags, FLAG_WHITE_LABEL_ADDR_VALID | 0b110_0011);
+        assert_eq!(rows.block.len(), WL_DESCRIPTOR_ROWS + 2 + 2);
+        assert_eq!(rows.block[WL_VID], 0x1d50);
+        assert_eq!(rows.block[WL_PID], 0x619b);
+        assert_eq!(rows.block[WL_MANUFACTURER], 0);
+        // "ACME": offset 16, 4 ASCII characters, two to a row.
+        assert_eq!(rows.block[WL_PRODUCT], 0x10 << 8 | 4);
+        assert_eq!(&rows.block[16..18], &[0x4341, 0x454D]);
+        // "Ä1": offset 18, UTF-16.
+        assert_eq!(rows.block[WL_SERIAL_NUMBER], 0x12 << 8 | 0x80 | 2);
+        assert_eq!(&rows.block[18..20], &[0x00C4, 0x0031]);
+    }
+
+    #[test]
+    fn test_whitelabel_validation() {
+        assert_eq!(Whitelabel::default().validate().len(), 1);
+
+        let label = Whitelabel {
+            vid: Some(0x1d50),
+            manufacturer: "x".repeat(WHITELABEL_MAX_USB_STRING + 1),
+            volume_label: "ACME/KEY".into(),
+            ..Default::default()
+        };
+        assert_eq!(label.validate().len(), 3);
+    }
+}
@@ -136,6 +136,86 @@ pub enum CoseCurve {
     BP512R1 = 11,
 }
 
+impl CoseCurve {
+    /// Decode the `crv` parameter of a COSE key.
+    pub fn from_i128(val: i128) -> Option<Self> {
+        match val {
+            1 => Some(Self::P256),
+            2 => Some(Self::P384),
+            3 => Some(Self::P521),
+            4 => Some(Self::X25519),
+            5 => Some(Self::X448),
+            6 => Some(Self::Ed25519),
+            7 => Some(Self::Ed448),
+            8 => Some(Self::P256K1),
+            9 => Some(Self::BP256R1),
+            10 => Some(Self::BP384R1),
+            11 => Some(Self::BP512R1),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for CoseCurve {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::P256 => write!(f, "P-256"),
+            Self::P384 => write!(f, "P-384"),
+            Self::P521 => write!(f, "P-521"),
+            Self::X25519 => write!(f, "X25519"),
+            Self::X448 => write!(f, "X448"),
+            Self::Ed25519 => write!(f, "Ed25519"),
+            Self::Ed448 => write!(f, "Ed448"),
+            Self::P256K1 => write!(f, "secp256k1"),
+            Self::BP256R1 => write!(f, "brainpoolP256r1"),
+            Self::BP384R1 => write!(f, "brainpoolP384r1"),
+            Self::BP512R1 => write!(f, "brainpoolP512r1"),
+        }
+    }
+}
+
+/// COSE key types (`kty`) from the IANA COSE Key Types registry.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoseKeyType {
+    /// Octet key pair (EdDSA / X25519 / X448).
+    Okp = 1,
+    /// Elliptic curve key with x and y coordinates (ECDSA).
+    Ec2 = 2,
+    /// RSA key.
+    Rsa = 3,
+    /// Symmetric key.
+    Symmetric = 4,
+    /// Algorithm key pair (ML-DSA).
+    Akp = 7,
+}
+
+impl CoseKeyType {
+    /// Decode the `kty` parameter of a COSE key.
+    pub fn from_i128(val: i128) -> Option<Self> {
+        match val {
+            1 => Some(Self::Okp),
+            2 => Some(Self::Ec2),
+            3 => Some(Self::Rsa),
+            4 => Some(Self::Symmetric),
+            7 => Some(Self::Akp),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for CoseKeyType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Okp => write!(f, "OKP"),
+            Self::Ec2 => write!(f, "EC2"),
+            Self::Rsa => write!(f, "RSA"),
+            Self::Symmetric => write!(f, "Symmetric"),
+            Self::Akp => write!(f, "AKP"),
+        }
+    }
+}
+
 /// COSE key-parameter labels from RFC 8152 §7.1 / IANA.
 #[repr(i32)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -1,9 +1,10 @@
 //! Shared COSE algorithm/curve/key-parameter definitions, firmware-version
-//! parsing, and the RS-Key LED status-block codec.
+//! parsing, the RS-Key LED status-block codec, and X.509 certificate summaries.
 
 pub mod cose;
 pub mod led;
 pub mod version;
+pub mod x509;
 
 pub use led::parse_led_block;
 pub use version::FirmwareVersion;
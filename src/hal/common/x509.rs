@@ -0,0 +1,346 @@
+//! Just enough DER to summarize an X.509 certificate: serial number, subject
+//! and end of validity (RFC 5280 §4.1).
+//!
+//! Used for the enterprise attestation certificate, which is only ever seen
+//! as DER (from the upload file or an attestation statement's `x5c`).
+//! [`parse`] also splits out the signed part, the public key and the FIDO
+//! AAGUID extension for [`crate::hal::fido::attestation`]; nothing in this
+//! file verifies a signature.
+
+use crate::error::PFError;
+use crate::hal::types::AttestationCertInfo;
+
+const TAG_BOOLEAN: u8 = 0x01;
+const TAG_INTEGER: u8 = 0x02;
+const TAG_BIT_STRING: u8 = 0x03;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_OID: u8 = 0x06;
+const TAG_UTF8_STRING: u8 = 0x0C;
+const TAG_PRINTABLE_STRING: u8 = 0x13;
+const TAG_IA5_STRING: u8 = 0x16;
+const TAG_UTC_TIME: u8 = 0x17;
+const TAG_GENERALIZED_TIME: u8 = 0x18;
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_SET: u8 = 0x31;
+/// `[0] EXPLICIT Version` in front of the serial number.
+const TAG_VERSION: u8 = 0xA0;
+/// `[3] EXPLICIT Extensions` at the end of the TBSCertificate.
+const TAG_EXTENSIONS: u8 = 0xA3;
+
+/// `id-fido-gen-ce-aaguid` (1.3.6.1.4.1.45724.1.1.4): the AAGUID of the
+/// authenticator model an attestation certificate was issued for.
+const OID_FIDO_AAGUID: &[u8] = &[
+    0x2B, 0x06, 0x01, 0x04, 0x01, 0x82, 0xE5, 0x1C, 0x01, 0x01, 0x04,
+];
+
+/// The parts of a certificate that attestation verification reads.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Certificate<'a> {
+    /// The DER `TBSCertificate`, which is what the issuer signed.
+    pub tbs: &'a [u8],
+    /// `signatureAlgorithm` OID.
+    pub signature_algorithm: &'a [u8],
+    /// `signatureValue`, without the unused-bits byte.
+    pub signature: &'a [u8],
+    /// Raw issuer `Name`, compared with `subject` to spot self-signed certificates.
+    pub issuer: &'a [u8],
+    /// Raw subject `Name`.
+    pub subject: &'a [u8],
+    /// `subjectPublicKeyInfo` algorithm OID.
+    pub key_algorithm: &'a [u8],
+    /// Algorithm parameter when it is an OID (the curve of an EC key).
+    pub key_parameter: Option<&'a [u8]>,
+    /// `subjectPublicKey`, without the unused-bits byte.
+    pub public_key: &'a [u8],
+    /// Value of the FIDO AAGUID extension, when present.
+    pub aaguid: Option<&'a [u8]>,
+}
+
+/// Short names of the attribute types shown in a subject, by DER-encoded OID.
+const NAME_ATTRIBUTES: &[(&[u8], &str)] = &[
+    (&[0x55, 0x04, 0x03], "CN"),
+    (&[0x55, 0x04, 0x06], "C"),
+    (&[0x55, 0x04, 0x07], "L"),
+    (&[0x55, 0x04, 0x08], "ST"),
+    (&[0x55, 0x04, 0x0A], "O"),
+    (&[0x55, 0x04, 0x0B], "OU"),
+];
+
+fn invalid(what: &str) -> PFError {
+    PFError::Io(format!("Invalid certificate: {}", what))
+}
+
+/// One DER element: its tag, contents, and whatever follows it.
+fn read_tlv(data: &[u8]) -> Result<(u8, &[u8], &[u8]), PFError> {
+    let (&tag, rest) = data.split_first().ok_or_else(|| invalid("truncated"))?;
+    let (&first, rest) = rest.split_first().ok_or_else(|| invalid("truncated"))?;
+    let (len, rest) = if first & 0x80 == 0 {
+        (first as usize, rest)
+    } else {
+        let count = (first & 0x7F) as usize;
+        if count == 0 || count > 4 || rest.len() < count {
+            return Err(invalid("bad length"));
+        }
+        let len = rest[..count]
+            .iter()
+            .fold(0usize, |len, b| (len << 8) | *b as usize);
+        (len, &rest[count..])
+    };
+    if rest.len() < len {
+        return Err(invalid("truncated"));
+    }
+    Ok((tag, &rest[..len], &rest[len..]))
+}
+
+fn expect(data: &[u8], tag: u8, what: &str) -> Result<(&[u8], &[u8]), PFError> {
+    match read_tlv(data)? {
+        (found, contents, rest) if found == tag => Ok((contents, rest)),
+        _ => Err(invalid(what)),
+    }
+}
+
+/// Contents of a `BIT STRING` with no unused bits.
+fn bit_string(contents: &[u8]) -> Result<&[u8], PFError> {
+    match contents {
+        [0, bits @ ..] => Ok(bits),
+        _ => Err(invalid("bit string")),
+    }
+}
+
+/// `Name` as `CN=…, O=…`, in certificate order, keeping the attributes in
+/// [`NAME_ATTRIBUTES`].
+fn format_name(mut rdns: &[u8]) -> Result<String, PFError> {
+    let mut parts = Vec::new();
+    while !rdns.is_empty() {
+        let (mut set, rest) = expect(rdns, TAG_SET, "subject")?;
+        rdns = rest;
+        while !set.is_empty() {
+            let (attribute, rest) = expect(set, TAG_SEQUENCE, "subject")?;
+            set = rest;
+            let (oid, value) = expect(attribute, TAG_OID, "subject")?;
+            let (tag, value, _) = read_tlv(value)?;
+            let short = NAME_ATTRIBUTES.iter().find(|(known, _)| *known == oid);
+            let text = matches!(tag, TAG_UTF8_STRING | TAG_PRINTABLE_STRING | TAG_IA5_STRING);
+            if let (Some((_, short)), true) = (short, text) {
+                parts.push(format!("{}={}", short, String::from_utf8_lossy(value)));
+            }
+        }
+    }
+    Ok(parts.join(", "))
+}
+
+/// `UTCTime` / `GeneralizedTime` as `YYYY-MM-DD HH:MM:SS UTC`.
+fn format_time(tag: u8, value: &[u8]) -> Result<String, PFError> {
+    let text = std::str::from_utf8(value).map_err(|_| invalid("validity"))?;
+    let digits = text.trim_end_matches('Z');
+    let full = match (tag, digits.len()) {
+        // RFC 5280 §4.1.2.5.1: YY below 50 is 20YY.
+        (TAG_UTC_TIME, 12) => {
+            let century = if &digits[..2] < "50" { "20" } else { "19" };
+            format!("{}{}", century, digits)
+        }
+        (TAG_GENERALIZED_TIME, 14) => digits.to_string(),
+        _ => return Err(invalid("validity")),
+    };
+    if !full.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(invalid("validity"));
+    }
+    Ok(format!(
+        "{}-{}-{} {}:{}:{} UTC",
+        &full[..4],
+        &full[4..6],
+        &full[6..8],
+        &full[8..10],
+        &full[10..12],
+        &full[12..14]
+    ))
+}
+
+/// Serial number, subject and expiry of a DER certificate.
+pub fn summarize(der: &[u8]) -> Result<AttestationCertInfo, PFError> {
+    let (certificate, _) = expect(der, TAG_SEQUENCE, "not a certificate")?;
+    let (tbs, _) = expect(certificate, TAG_SEQUENCE, "no TBSCertificate")?;
+
+    let mut fields = tbs;
+    if fields.first() == Some(&TAG_VERSION) {
+        fields = read_tlv(fields)?.2;
+    }
+    let (serial, fields) = expect(fields, TAG_INTEGER, "serial number")?;
+    let (_signature, fields) = expect(fields, TAG_SEQUENCE, "signature algorithm")?;
+    let (_issuer, fields) = expect(fields, TAG_SEQUENCE, "issuer")?;
+    let (validity, fields) = expect(fields, TAG_SEQUENCE, "validity")?;
+    let (subject, _) = expect(fields, TAG_SEQUENCE, "subject")?;
+
+    let (_, _not_before, rest) = read_tlv(validity)?;
+    let (tag, not_after, _) = read_tlv(rest)?;
+
+    // Positive serials get a leading zero byte when the top bit is set.
+    let serial = match serial {
+        [0, rest @ ..] if !rest.is_empty() => rest,
+        serial => serial,
+    };
+    Ok(AttestationCertInfo {
+        serial: hex::encode_upper(serial),
+        subject: format_name(subject)?,
+        not_after: format_time(tag, not_after)?,
+    })
+}
+
+/// Split a DER certificate into the parts listed on [`Certificate`].
+pub fn parse(der: &[u8]) -> Result<Certificate<'_>, PFError> {
+    let (certificate, _) = expect(der, TAG_SEQUENCE, "not a certificate")?;
+    let (tbs_fields, rest) = expect(certificate, TAG_SEQUENCE, "no TBSCertificate")?;
+    let tbs = &certificate[..certificate.len() - rest.len()];
+    let (algorithm, rest) = expect(rest, TAG_SEQUENCE, "signature algorithm")?;
+    let (signature_algorithm, _) = expect(algorithm, TAG_OID, "signature algorithm")?;
+    let (signature, _) = expect(rest, TAG_BIT_STRING, "signature")?;
+
+    let mut fields = tbs_fields;
+    if fields.first() == Some(&TAG_VERSION) {
+        fields = read_tlv(fields)?.2;
+    }
+    let (_serial, fields) = expect(fields, TAG_INTEGER, "serial number")?;
+    let (_signature, fields) = expect(fields, TAG_SEQUENCE, "signature algorithm")?;
+    let (issuer, fields) = expect(fields, TAG_SEQUENCE, "issuer")?;
+    let (_validity, fields) = expect(fields, TAG_SEQUENCE, "validity")?;
+    let (subject, fields) = expect(fields, TAG_SEQUENCE, "subject")?;
+    let (key_info, mut fields) = expect(fields, TAG_SEQUENCE, "public key")?;
+    let (key_algorithm, public_key) = expect(key_info, TAG_SEQUENCE, "public key algorithm")?;
+    let (key_algorithm, parameter) = expect(key_algorithm, TAG_OID, "public key algorithm")?;
+    let key_parameter = match read_tlv(parameter) {
+        Ok((TAG_OID, oid, _)) => Some(oid),
+        _ => None,
+    };
+    let (public_key, _) = expect(public_key, TAG_BIT_STRING, "public key")?;
+
+    // Unique IDs ([1], [2]) may come before the extensions; skip them.
+    let mut aaguid = None;
+    while !fields.is_empty() {
+        let (tag, contents, rest) = read_tlv(fields)?;
+        fields = rest;
+        if tag != TAG_EXTENSIONS {
+            continue;
+        }
+        let (mut extensions, _) = expect(contents, TAG_SEQUENCE, "extensions")?;
+        while !extensions.is_empty() {
+            let (extension, rest) = expect(extensions, TAG_SEQUENCE, "extension")?;
+            extensions = rest;
+            let (oid, value) = expect(extension, TAG_OID, "extension")?;
+            let value = match read_tlv(value)? {
+                (TAG_BOOLEAN, _, rest) => rest,
+                _ => value,
+            };
+            let (value, _) = expect(value, TAG_OCTET_STRING, "extension value")?;
+            if oid == OID_FIDO_AAGUID {
+                aaguid = Some(expect(value, TAG_OCTET_STRING, "AAGUID extension")?.0);
+            }
+        }
+    }
+
+    Ok(Certificate {
+        tbs,
+        signature_algorithm,
+        signature: bit_string(signature)?,
+        issuer,
+        subject,
+        key_algorithm,
+        key_parameter,
+        public_key: bit_string(public_key)?,
+        aaguid,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tlv(tag: u8, contents: &[u8]) -> Vec<u8> {
+        let mut out = vec![tag];
+        if contents.len() < 0x80 {
+            out.push(contents.len() as u8);
+        } else {
+            out.extend([0x82, (contents.len() >> 8) as u8, contents.len() as u8]);
+        }
+        out.extend(contents);
+        out
+    }
+
+    fn name(attributes: &[(&[u8], u8, &str)]) -> Vec<u8> {
+        let rdns: Vec<u8> = attributes
+            .iter()
+            .flat_map(|(oid, tag, value)| {
+                let attribute = [tlv(TAG_OID, oid), tlv(*tag, value.as_bytes())].concat();
+                tlv(TAG_SET, &tlv(TAG_SEQUENCE, &attribute))
+            })
+            .collect();
+        tlv(TAG_SEQUENCE, &rdns)
+    }
+
+    fn certificate(serial: &[u8], not_after: (u8, &str)) -> Vec<u8> {
+        let tbs = [
+            tlv(TAG_VERSION, &tlv(TAG_INTEGER, &[2])),
+            tlv(TAG_INTEGER, serial),
+            tlv(TAG_SEQUENCE, &tlv(TAG_OID, &[0x2A, 0x86, 0x48])),
+            name(&[(&[0x55, 0x04, 0x03], TAG_UTF8_STRING, "Example CA")]),
+            tlv(
+                TAG_SEQUENCE,
+                &[
+                    tlv(TAG_UTC_TIME, b"250101000000Z"),
+                    tlv(not_after.0, not_after.1.as_bytes()),
+                ]
+                .concat(),
+            ),
+            name(&[
+                (&[0x55, 0x04, 0x0A], TAG_PRINTABLE_STRING, "Example Corp"),
+                (&[0x55, 0x04, 0x03], TAG_UTF8_STRING, "Fleet Key 42"),
+                // Email address: not shown.
+                (
+                    &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x09, 0x01],
+                    TAG_IA5_STRING,
+                    "it@example.com",
+                ),
+            ]),
+            tlv(TAG_SEQUENCE, &[0u8; 200]),
+        ]
+        .concat();
+        tlv(
+            TAG_SEQUENCE,
+            &[
+                tlv(TAG_SEQUENCE, &tbs),
+                tlv(TAG_SEQUENCE, &tlv(TAG_OID, &[0x2A])),
+                tlv(0x03, &[0, 1, 2]),
+            ]
+            .concat(),
+        )
+    }
+
+    #[test]
+    fn test_summarize_certificate() {
+        let der = certificate(&[0x00, 0x9A, 0x01], (TAG_UTC_TIME, "350630120000Z"));
+        let info = summarize(&der).unwrap();
+        assert_eq!(info.serial, "9A01");
+        assert_eq!(info.subject, "O=Example Corp, CN=Fleet Key 42");
+        assert_eq!(info.not_after, "2035-06-30 12:00:00 UTC");
+
+        let der = certificate(&[0x05], (TAG_GENERALIZED_TIME, "20600101000000Z"));
+        let info = summarize(&der).unwrap();
+        assert_eq!(info.serial, "05");
+        assert_eq!(info.not_after, "2060-01-01 00:00:00 UTC");
+    }
+
+    #[test]
+    fn test_utc_time_century() {
+        assert_eq!(
+            format_time(TAG_UTC_TIME, b"991231235959Z").unwrap(),
+            "1999-12-31 23:59:59 UTC"
+        );
+        assert!(format_time(TAG_UTC_TIME, b"9912Z").is_err());
+    }
+
+    #[test]
+    fn test_rejects_truncated_certificate() {
+        let der = certificate(&[0x01], (TAG_UTC_TIME, "350630120000Z"));
+        assert!(summarize(&der[..40]).is_err());
+        assert!(summarize(b"not der").is_err());
+    }
+}
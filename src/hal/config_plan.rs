@@ -0,0 +1,403 @@
+//! Configuration writes as a plan of discrete operations.
+//!
+//! An [`AppConfigInput`] is split into one [`ConfigOperation`] per setting
+//! (VID/PID, product name, LED GPIO, ...). Each operation knows which
+//! [`WritePath`]s can carry it, whether it only takes effect after a replug,
+//! and the label the apply report uses for it. [`ConfigPlan::new`] checks
+//! every operation against the path a backend writes through, refuses the
+//! write before anything is sent when one cannot go, and orders the rest so
+//! settings that need a replug go last. The backends then apply the plan:
+//! the Rescue applet and RS-Key write it as one record, pico-fido sends one
+//! vendor command per operation under a single PIN token.
+//!
+//! The same plan serves the config screen, the CLI, profiles and
+//! provisioning, which all write through
+//! [`io::write_config`](crate::hal::io::write_config).
+
+use crate::error::PFError;
+use crate::hal::fido::constants::{PinUvAuthTokenPermissions, VendorConfigCommand};
+use crate::hal::types::AppConfigInput;
+
+/// Error returned when a product-name change is sent over FIDO to firmware
+/// that can only take it through the rescue applet.
+pub const PRODUCT_NAME_UNSUPPORTED_OVER_FIDO: &str = "Changing the product name is not supported over FIDO on this firmware. \
+     Connect in rescue mode (CCID/PCSC) to write it.";
+
+/// Error returned when a curves change is sent over FIDO to firmware whose
+/// vendor commands have no TAG_CURVES counterpart.
+pub const CURVES_UNSUPPORTED_OVER_FIDO: &str = "Changing the enabled curves is not supported over FIDO on this firmware. \
+     Connect in rescue mode (CCID/PCSC) to write them.";
+
+/// Error returned when an LED driver change is sent over FIDO to firmware
+/// whose vendor commands have no driver counterpart.
+pub const LED_DRIVER_UNSUPPORTED_OVER_FIDO: &str = "Changing the LED driver is not supported over FIDO on this firmware. \
+     Connect in rescue mode (CCID/PCSC) to write it.";
+
+/// Error returned when a touch timeout change is sent over FIDO to firmware
+/// whose vendor commands have no presence-timeout counterpart.
+pub const TOUCH_TIMEOUT_UNSUPPORTED_OVER_FIDO: &str = "Changing the touch timeout is not supported over FIDO on this firmware. \
+     Connect in rescue mode (CCID/PCSC) to write it.";
+
+/// How a configuration reaches the key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WritePath {
+    /// pico-fido vendor commands over `authenticatorConfig`, one per setting.
+    VendorConfig,
+    /// The RS-Key PHY record, written whole over CTAPHID `CONFIG_WRITE`.
+    RsKeyRecord,
+    /// The PHY record, written whole through the Rescue applet.
+    RescueRecord,
+}
+
+impl WritePath {
+    /// Permission the PIN token for this path needs, or `None` when the path
+    /// takes no PIN.
+    pub fn permission(self) -> Option<PinUvAuthTokenPermissions> {
+        match self {
+            WritePath::VendorConfig | WritePath::RsKeyRecord => {
+                Some(PinUvAuthTokenPermissions::AUTHENTICATOR_CONFIG)
+            }
+            WritePath::RescueRecord => None,
+        }
+    }
+}
+
+/// What a write path does with an operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Support {
+    Carried,
+    /// Left out without an error: the path has no way to write it, and the
+    /// config screen always sends it with its current value.
+    Dropped,
+    /// Refused with this message; nothing is written.
+    Refused(&'static str),
+}
+
+/// One setting to write.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigOperation {
+    /// USB VID and PID, as typed hex. Either may be missing; only the
+    /// records can write one without the other.
+    SetVidPid {
+        vid: Option<String>,
+        pid: Option<String>,
+    },
+    SetProductName(String),
+    SetLedGpio(u8),
+    SetBrightness(u8),
+    SetTouchTimeout(u8),
+    SetLedDriver(u8),
+    /// Option flags; unset flags keep their current value.
+    SetOptions {
+        led_dimmable: Option<bool>,
+        power_cycle_on_reset: Option<bool>,
+        led_steady: Option<bool>,
+    },
+    SetCurves {
+        raw_curves_mask: Option<u32>,
+        enable_secp256k1: Option<bool>,
+    },
+    SetLedOrder(u8),
+    SetUsbInterfaces(u8),
+    SetLedCount(u8),
+}
+
+impl ConfigOperation {
+    /// The operations for the fields set in `config`, in field order.
+    pub fn from_input(config: &AppConfigInput) -> Vec<Self> {
+        let mut ops = Vec::new();
+        if config.vid.is_some() || config.pid.is_some() {
+            ops.push(Self::SetVidPid {
+                vid: config.vid.clone(),
+                pid: config.pid.clone(),
+            });
+        }
+        if let Some(name) = &config.product_name {
+            ops.push(Self::SetProductName(name.clone()));
+        }
+        ops.extend(config.led_gpio.map(Self::SetLedGpio));
+        ops.extend(config.led_brightness.map(Self::SetBrightness));
+        ops.extend(config.touch_timeout.map(Self::SetTouchTimeout));
+        ops.extend(config.led_driver.map(Self::SetLedDriver));
+        if config.led_dimmable.is_some()
+            || config.power_cycle_on_reset.is_some()
+            || config.led_steady.is_some()
+        {
+            ops.push(Self::SetOptions {
+                led_dimmable: config.led_dimmable,
+                power_cycle_on_reset: config.power_cycle_on_reset,
+                led_steady: config.led_steady,
+            });
+        }
+        if config.raw_curves_mask.is_some() || config.enable_secp256k1.is_some() {
+            ops.push(Self::SetCurves {
+                raw_curves_mask: config.raw_curves_mask,
+                enable_secp256k1: config.enable_secp256k1,
+            });
+        }
+        ops.extend(config.led_order.map(Self::SetLedOrder));
+        ops.extend(config.enabled_usb_itf.map(Self::SetUsbInterfaces));
+        ops.extend(config.led_num.map(Self::SetLedCount));
+        ops
+    }
+
+    /// Name of the setting, as the config screen and apply report show it.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::SetVidPid { .. } => "VID:PID",
+            Self::SetProductName(_) => "Product name",
+            Self::SetLedGpio(_) => "LED GPIO",
+            Self::SetBrightness(_) => "LED brightness",
+            Self::SetTouchTimeout(_) => "Touch timeout",
+            Self::SetLedDriver(_) => "LED driver",
+            Self::SetOptions { .. } => "Options",
+            Self::SetCurves { .. } => "Curves",
+            Self::SetLedOrder(_) => "LED order",
+            Self::SetUsbInterfaces(_) => "USB interfaces",
+            Self::SetLedCount(_) => "LED count",
+        }
+    }
+
+    /// Whether the setting only takes effect once the key re-enumerates:
+    /// it is part of the USB descriptors the host reads on plug-in.
+    pub fn needs_replug(&self) -> bool {
+        matches!(
+            self,
+            Self::SetVidPid { .. } | Self::SetProductName(_) | Self::SetUsbInterfaces(_)
+        )
+    }
+
+    fn support(&self, path: WritePath) -> Support {
+        if path != WritePath::VendorConfig {
+            return Support::Carried;
+        }
+        // The pico-fido vendor commands cover VID/PID, LED GPIO, brightness
+        // and the options word. The rest only the rescue applet and RS-Key's
+        // PHY record carry.
+        match self {
+            Self::SetVidPid {
+                vid: Some(_),
+                pid: Some(_),
+            }
+            | Self::SetLedGpio(_)
+            | Self::SetBrightness(_)
+            | Self::SetOptions { .. } => Support::Carried,
+            Self::SetVidPid { .. } => Support::Dropped,
+            Self::SetProductName(_) => Support::Refused(PRODUCT_NAME_UNSUPPORTED_OVER_FIDO),
+            Self::SetTouchTimeout(_) => Support::Refused(TOUCH_TIMEOUT_UNSUPPORTED_OVER_FIDO),
+            Self::SetLedDriver(_) => Support::Refused(LED_DRIVER_UNSUPPORTED_OVER_FIDO),
+            Self::SetCurves { .. } => Support::Refused(CURVES_UNSUPPORTED_OVER_FIDO),
+            Self::SetLedOrder(_) | Self::SetUsbInterfaces(_) | Self::SetLedCount(_) => {
+                Support::Dropped
+            }
+        }
+    }
+
+    /// The vendor command that carries this operation on
+    /// [`WritePath::VendorConfig`].
+    pub fn vendor_command(&self) -> Option<VendorConfigCommand> {
+        match self {
+            Self::SetVidPid {
+                vid: Some(_),
+                pid: Some(_),
+            } => Some(VendorConfigCommand::PhysicalVidPid),
+            Self::SetLedGpio(_) => Some(VendorConfigCommand::PhysicalLedGpio),
+            Self::SetBrightness(_) => Some(VendorConfigCommand::PhysicalLedBrightness),
+            Self::SetOptions { .. } => Some(VendorConfigCommand::PhysicalOptions),
+            _ => None,
+        }
+    }
+
+    /// Set this operation's fields in `config`.
+    fn fill(&self, config: &mut AppConfigInput) {
+        match self {
+            Self::SetVidPid { vid, pid } => {
+                config.vid = vid.clone();
+                config.pid = pid.clone();
+            }
+            Self::SetProductName(name) => config.product_name = Some(name.clone()),
+            Self::SetLedGpio(v) => config.led_gpio = Some(*v),
+            Self::SetBrightness(v) => config.led_brightness = Some(*v),
+            Self::SetTouchTimeout(v) => config.touch_timeout = Some(*v),
+            Self::SetLedDriver(v) => config.led_driver = Some(*v),
+            Self::SetOptions {
+                led_dimmable,
+                power_cycle_on_reset,
+                led_steady,
+            } => {
+                config.led_dimmable = *led_dimmable;
+                config.power_cycle_on_reset = *power_cycle_on_reset;
+                config.led_steady = *led_steady;
+            }
+            Self::SetCurves {
+                raw_curves_mask,
+                enable_secp256k1,
+            } => {
+                config.raw_curves_mask = *raw_curves_mask;
+                config.enable_secp256k1 = *enable_secp256k1;
+            }
+            Self::SetLedOrder(v) => config.led_order = Some(*v),
+            Self::SetUsbInterfaces(v) => config.enabled_usb_itf = Some(*v),
+            Self::SetLedCount(v) => config.led_num = Some(*v),
+        }
+    }
+}
+
+/// The operations a write sends over one path, in the order it sends them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigPlan {
+    pub path: WritePath,
+    pub steps: Vec<ConfigOperation>,
+    /// Operations the path has no way to write. Nothing is sent for them;
+    /// the write report lists them as skipped.
+    pub dropped: Vec<ConfigOperation>,
+}
+
+impl ConfigPlan {
+    /// Plan `config` for `path`. Fails with the reason of the first
+    /// operation the path refuses; operations it cannot write but need not
+    /// refuse are kept in [`dropped`](Self::dropped). Operations that need a replug go last, so a write cut short
+    /// by the key re-enumerating has already applied the others.
+    pub fn new(config: &AppConfigInput, path: WritePath) -> Result<Self, PFError> {
+        let mut steps = Vec::new();
+        let mut dropped = Vec::new();
+        for op in ConfigOperation::from_input(config) {
+            match op.support(path) {
+                Support::Carried => steps.push(op),
+                Support::Dropped => {
+                    log::debug!("{} cannot be written over {:?}", op.label(), path);
+                    dropped.push(op);
+                }
+                Support::Refused(reason) => return Err(PFError::Device(reason.into())),
+            }
+        }
+        steps.sort_by_key(ConfigOperation::needs_replug);
+        Ok(Self {
+            path,
+            steps,
+            dropped,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Whether the key has to be re-plugged for the plan to take effect.
+    pub fn needs_replug(&self) -> bool {
+        self.steps.iter().any(ConfigOperation::needs_replug)
+    }
+
+    /// The plan as a partial input, with only the fields it writes set.
+    /// What the record paths encode and what the read-back is checked
+    /// against.
+    pub fn record_input(&self) -> AppConfigInput {
+        let mut config = AppConfigInput::default();
+        for op in &self.steps {
+            op.fill(&mut config);
+        }
+        config
+    }
+
+    /// What to tell the user once the plan went through.
+    pub fn success_note(&self) -> String {
+        if self.is_empty() {
+            "No configuration changes were needed.".to_string()
+        } else if self.needs_replug() {
+            "Configuration updated successfully! Unplug and re-plug the device to apply changes."
+                .to_string()
+        } else {
+            "Configuration updated successfully!".to_string()
+        }
+    }
+}
+
+/// A plan a backend carried out, and what it said about it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppliedPlan {
+    pub plan: ConfigPlan,
+    pub note: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input() -> AppConfigInput {
+        AppConfigInput::default()
+    }
+
+    #[test]
+    fn test_replug_operations_go_last() {
+        let config = AppConfigInput {
+            vid: Some("CAFE".into()),
+            pid: Some("0001".into()),
+            led_gpio: Some(25),
+            enabled_usb_itf: Some(0x05),
+            led_brightness: Some(8),
+            ..input()
+        };
+        let plan = ConfigPlan::new(&config, WritePath::RescueRecord).unwrap();
+        let labels: Vec<_> = plan.steps.iter().map(ConfigOperation::label).collect();
+        assert_eq!(
+            labels,
+            ["LED GPIO", "LED brightness", "VID:PID", "USB interfaces"]
+        );
+        assert!(plan.needs_replug());
+        assert_eq!(plan.path.permission(), None);
+    }
+
+    #[test]
+    fn test_vendor_path_refuses_and_drops() {
+        let config = AppConfigInput {
+            touch_timeout: Some(15),
+            ..input()
+        };
+        let err = ConfigPlan::new(&config, WritePath::VendorConfig)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains(TOUCH_TIMEOUT_UNSUPPORTED_OVER_FIDO));
+        assert!(ConfigPlan::new(&config, WritePath::RsKeyRecord).is_ok());
+
+        // A lone VID and the record-only fields are left out, not refused.
+        let config = AppConfigInput {
+            vid: Some("CAFE".into()),
+            led_order: Some(1),
+            led_steady: Some(true),
+            ..input()
+        };
+        let plan = ConfigPlan::new(&config, WritePath::VendorConfig).unwrap();
+        let commands: Vec<_> = plan
+            .steps
+            .iter()
+            .filter_map(ConfigOperation::vendor_command)
+            .collect();
+        assert_eq!(commands, [VendorConfigCommand::PhysicalOptions]);
+        assert!(!plan.needs_replug());
+        assert_eq!(plan.record_input().led_steady, Some(true));
+        assert_eq!(plan.record_input().led_order, None);
+        let dropped: Vec<_> = plan.dropped.iter().map(ConfigOperation::label).collect();
+        assert_eq!(dropped, ["VID:PID", "LED order"]);
+    }
+
+    #[test]
+    fn test_record_input_round_trips() {
+        let config = AppConfigInput {
+            vid: Some("CAFE".into()),
+            pid: Some("0001".into()),
+            product_name: Some("Pico Key".into()),
+            led_driver: Some(3),
+            power_cycle_on_reset: Some(false),
+            raw_curves_mask: Some(0x09),
+            led_num: Some(4),
+            ..input()
+        };
+        let plan = ConfigPlan::new(&config, WritePath::RsKeyRecord).unwrap();
+        assert_eq!(plan.record_input(), config);
+        assert!(
+            ConfigPlan::new(&input(), WritePath::VendorConfig)
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,684 @@
+//! Local verification of `packed` attestation statements (WebAuthn §8.2.1),
+//! the checks a relying party runs on a registration.
+//!
+//! The diagnostics test credential is created with a client data hash
+//! PicoForge chose, so its attestation can be verified end to end:
+//!
+//! - **Signature**: `sig` over `authenticatorData || clientDataHash`, with
+//!   the key of the first `x5c` certificate (basic attestation) or, without
+//!   `x5c`, the new credential's own key (self attestation, where `alg` must
+//!   also match the credential key).
+//! - **AAGUID**: when the attestation certificate carries the
+//!   `id-fido-gen-ce-aaguid` extension, it must equal the AAGUID in the
+//!   attested credential data.
+//! - **Chain**: every `x5c` certificate must be signed by the next one, and
+//!   a self-signed last certificate by itself. There is no trust store, so
+//!   the chain is not anchored to a vendor root: a valid result means the key
+//!   signed this registration with the certificate it presented, not that the
+//!   certificate comes from a known manufacturer.
+//!
+//! [`verify_assertion`] applies the same signature check to a sign-in: a
+//! GetAssertion response from one stored passkey, verified with the public
+//! key credential management reported for it.
+//!
+//! ES256, ES384 and EdDSA are covered, which is everything pico-fido and
+//! RS-Key attest with. Anything else is reported as a failed check rather
+//! than silently passed.
+
+use super::constants::AuthenticatorFlags;
+use super::diag::{key, parse_auth_data, text};
+use crate::error::PFError;
+use crate::hal::common::cose::{CoseAlgorithm, CoseCurve, CoseKeyParam, CoseKeyType};
+use crate::hal::common::x509::{self, Certificate};
+use crate::hal::types::{AttestationVerification, SignInTest, VerificationCheck};
+use ring::digest;
+use ring::signature::{self, UnparsedPublicKey, VerificationAlgorithm};
+use serde_cbor_2::{Value, from_slice};
+use std::collections::BTreeMap;
+
+/// `ecdsa-with-SHA256` (RFC 5758 §3.2).
+const OID_ECDSA_SHA256: &[u8] = &[0x2A, 0x86, 0x48, 0xCE, 0x3D, 0x04, 0x03, 0x02];
+/// `ecdsa-with-SHA384` (RFC 5758 §3.2).
+const OID_ECDSA_SHA384: &[u8] = &[0x2A, 0x86, 0x48, 0xCE, 0x3D, 0x04, 0x03, 0x03];
+/// `id-Ed25519` (RFC 8410), both the key and the signature algorithm.
+const OID_ED25519: &[u8] = &[0x2B, 0x65, 0x70];
+/// `id-ecPublicKey` (RFC 5480).
+const OID_EC_PUBLIC_KEY: &[u8] = &[0x2A, 0x86, 0x48, 0xCE, 0x3D, 0x02, 0x01];
+/// `prime256v1` (P-256).
+const OID_P256: &[u8] = &[0x2A, 0x86, 0x48, 0xCE, 0x3D, 0x03, 0x01, 0x07];
+/// `secp384r1` (P-384).
+const OID_P384: &[u8] = &[0x2B, 0x81, 0x04, 0x00, 0x22];
+
+/// Signature schemes the verifier knows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scheme {
+    EcdsaSha256,
+    EcdsaSha384,
+    Ed25519,
+}
+
+impl Scheme {
+    /// From the COSE `alg` of an attestation statement or credential key.
+    fn from_cose(alg: i128) -> Option<Self> {
+        match CoseAlgorithm::from_i128(alg)? {
+            CoseAlgorithm::ES256 | CoseAlgorithm::ESP256 => Some(Self::EcdsaSha256),
+            CoseAlgorithm::ES384 | CoseAlgorithm::ESP384 => Some(Self::EcdsaSha384),
+            CoseAlgorithm::EdDSA | CoseAlgorithm::Ed25519 => Some(Self::Ed25519),
+            _ => None,
+        }
+    }
+
+    /// From a certificate's `signatureAlgorithm`.
+    fn from_oid(oid: &[u8]) -> Option<Self> {
+        if oid == OID_ECDSA_SHA256 {
+            Some(Self::EcdsaSha256)
+        } else if oid == OID_ECDSA_SHA384 {
+            Some(Self::EcdsaSha384)
+        } else if oid == OID_ED25519 {
+            Some(Self::Ed25519)
+        } else {
+            None
+        }
+    }
+}
+
+/// Key types the verifier knows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum KeyKind {
+    P256,
+    P384,
+    Ed25519,
+}
+
+/// A public key the way ring takes it: the uncompressed point for ECDSA,
+/// the 32 raw bytes for Ed25519.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PublicKey {
+    kind: KeyKind,
+    bytes: Vec<u8>,
+}
+
+impl PublicKey {
+    /// The subject public key of a certificate.
+    fn from_certificate(certificate: &Certificate) -> Result<Self, String> {
+        let kind = if certificate.key_algorithm == OID_ED25519 {
+            KeyKind::Ed25519
+        } else if certificate.key_algorithm != OID_EC_PUBLIC_KEY {
+            return Err("the certificate key type is not supported".into());
+        } else if certificate.key_parameter == Some(OID_P256) {
+            KeyKind::P256
+        } else if certificate.key_parameter == Some(OID_P384) {
+            KeyKind::P384
+        } else {
+            return Err("the certificate key curve is not supported".into());
+        };
+        Ok(Self {
+            kind,
+            bytes: certificate.public_key.to_vec(),
+        })
+    }
+
+    /// A credential public key in COSE form.
+    fn from_cose(key: &BTreeMap<Value, Value>) -> Result<Self, String> {
+        let int = |param: CoseKeyParam| match key.get(&Value::Integer(param as i128)) {
+            Some(Value::Integer(value)) => Some(*value),
+            _ => None,
+        };
+        let bytes = |param: CoseKeyParam| match key.get(&Value::Integer(param as i128)) {
+            Some(Value::Bytes(value)) => Some(value.as_slice()),
+            _ => None,
+        };
+        let kty = int(CoseKeyParam::Kty).and_then(CoseKeyType::from_i128);
+        let crv = int(CoseKeyParam::Crv).and_then(CoseCurve::from_i128);
+        let (kind, bytes) = match (kty, crv, bytes(CoseKeyParam::X), bytes(CoseKeyParam::Y)) {
+            (Some(CoseKeyType::Ec2), Some(CoseCurve::P256), Some(x), Some(y)) => {
+                (KeyKind::P256, [&[0x04u8][..], x, y].concat())
+            }
+            (Some(CoseKeyType::Ec2), Some(CoseCurve::P384), Some(x), Some(y)) => {
+                (KeyKind::P384, [&[0x04u8][..], x, y].concat())
+            }
+            (Some(CoseKeyType::Okp), Some(CoseCurve::Ed25519), Some(x), _) => {
+                (KeyKind::Ed25519, x.to_vec())
+            }
+            _ => return Err("the credential key type is not supported".into()),
+        };
+        Ok(Self { kind, bytes })
+    }
+}
+
+fn algorithm(scheme: Scheme, kind: KeyKind) -> Option<&'static dyn VerificationAlgorithm> {
+    match (scheme, kind) {
+        (Scheme::EcdsaSha256, KeyKind::P256) => Some(&signature::ECDSA_P256_SHA256_ASN1),
+        (Scheme::EcdsaSha384, KeyKind::P256) => Some(&signature::ECDSA_P256_SHA384_ASN1),
+        (Scheme::EcdsaSha256, KeyKind::P384) => Some(&signature::ECDSA_P384_SHA256_ASN1),
+        (Scheme::EcdsaSha384, KeyKind::P384) => Some(&signature::ECDSA_P384_SHA384_ASN1),
+        (Scheme::Ed25519, KeyKind::Ed25519) => Some(&signature::ED25519),
+        _ => None,
+    }
+}
+
+/// Check `sig` over `message` with `key`.
+fn verify(scheme: Scheme, key: &PublicKey, message: &[u8], sig: &[u8]) -> Result<(), String> {
+    let algorithm = algorithm(scheme, key.kind)
+        .ok_or_else(|| format!("a {:?} key cannot make {:?} signatures", key.kind, scheme))?;
+    UnparsedPublicKey::new(algorithm, &key.bytes)
+        .verify(message, sig)
+        .map_err(|_| "the signature does not match".to_string())
+}
+
+/// Check that `certificate` was signed by `issuer`.
+fn verify_certificate(certificate: &Certificate, issuer: &Certificate) -> Result<(), String> {
+    let scheme = Scheme::from_oid(certificate.signature_algorithm)
+        .ok_or("the certificate signature algorithm is not supported")?;
+    let key = PublicKey::from_certificate(issuer)?;
+    verify(scheme, &key, certificate.tbs, certificate.signature)
+}
+
+fn check(name: &str, result: Result<String, String>) -> VerificationCheck {
+    let (passed, detail) = match result {
+        Ok(detail) => (true, detail),
+        Err(detail) => (false, detail),
+    };
+    VerificationCheck {
+        name: name.to_string(),
+        passed,
+        detail,
+    }
+}
+
+/// Verify a `packed` attestation statement against the raw
+/// `authenticatorData` it came with and the `client_data_hash` the request
+/// was sent with. Malformed input is reported as a failed check.
+pub(crate) fn verify_packed(
+    statement: &BTreeMap<Value, Value>,
+    auth_data: &[u8],
+    client_data_hash: &[u8],
+) -> AttestationVerification {
+    let mut checks = Vec::new();
+    let aaguid = parse_auth_data(auth_data)
+        .ok()
+        .and_then(|parsed| parsed.aaguid);
+    if let Err(e) = run_checks(statement, auth_data, client_data_hash, &mut checks) {
+        checks.push(check("Attestation statement", Err(e)));
+    }
+    AttestationVerification {
+        valid: !checks.is_empty() && checks.iter().all(|c| c.passed),
+        self_attestation: !statement.contains_key(&text("x5c")),
+        aaguid: aaguid.map(hex::encode).unwrap_or_default(),
+        checks,
+    }
+}
+
+fn run_checks(
+    statement: &BTreeMap<Value, Value>,
+    auth_data: &[u8],
+    client_data_hash: &[u8],
+    checks: &mut Vec<VerificationCheck>,
+) -> Result<(), String> {
+    let parsed = parse_auth_data(auth_data).map_err(|e| e.to_string())?;
+    let alg = match statement.get(&text("alg")) {
+        Some(Value::Integer(alg)) => *alg,
+        _ => return Err("the statement has no alg".into()),
+    };
+    let sig = match statement.get(&text("sig")) {
+        Some(Value::Bytes(sig)) => sig,
+        _ => return Err("the statement has no sig".into()),
+    };
+    let scheme =
+        Scheme::from_cose(alg).ok_or_else(|| format!("algorithm {} is not supported", alg))?;
+    let signed = [auth_data, client_data_hash].concat();
+
+    let chain = match statement.get(&text("x5c")) {
+        None => None,
+        Some(Value::Array(chain)) => Some(
+            chain
+                .iter()
+                .map(|entry| match entry {
+                    Value::Bytes(der) => x509::parse(der).map_err(|e| e.to_string()),
+                    _ => Err("an x5c entry is not a certificate".to_string()),
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+        ),
+        Some(_) => return Err("x5c is not an array".into()),
+    };
+
+    let Some(chain) = chain else {
+        let Some(Value::Map(cose_key)) = &parsed.public_key else {
+            return Err("authenticatorData has no credential key".into());
+        };
+        let key_alg = match cose_key.get(&Value::Integer(CoseKeyParam::Alg as i128)) {
+            Some(Value::Integer(key_alg)) => Some(*key_alg),
+            _ => None,
+        };
+        checks.push(check(
+            "Algorithm",
+            if key_alg == Some(alg) {
+                Ok(format!("alg {} matches the credential key", alg))
+            } else {
+                Err(format!(
+                    "alg {} does not match the credential key ({:?})",
+                    alg, key_alg
+                ))
+            },
+        ));
+        let key = PublicKey::from_cose(cose_key)?;
+        checks.push(check(
+            "Self-attestation signature",
+            verify(scheme, &key, &signed, sig)
+                .map(|()| "authenticatorData || clientDataHash, credential key".to_string()),
+        ));
+        return Ok(());
+    };
+
+    let leaf = chain.first().ok_or("x5c is empty")?;
+    let key = PublicKey::from_certificate(leaf)?;
+    checks.push(check(
+        "Attestation signature",
+        verify(scheme, &key, &signed, sig).map(|()| {
+            "authenticatorData || clientDataHash, attestation certificate key".to_string()
+        }),
+    ));
+
+    checks.push(check(
+        "Certificate AAGUID",
+        match (leaf.aaguid, parsed.aaguid) {
+            (Some(cert), Some(data)) if cert == data => {
+                Ok(format!("matches {}", hex::encode(data)))
+            }
+            (Some(cert), Some(data)) => Err(format!(
+                "certificate has {}, authenticatorData has {}",
+                hex::encode(cert),
+                hex::encode(data)
+            )),
+            (Some(_), None) => Err("authenticatorData has no AAGUID".into()),
+            (None, _) => Ok("no AAGUID extension in the certificate".into()),
+        },
+    ));
+
+    let mut links = 0;
+    let mut result = Ok(());
+    for (ix, pair) in chain.windows(2).enumerate() {
+        if let Err(e) = verify_certificate(&pair[0], &pair[1]) {
+            result = Err(format!(
+                "certificate {} is not signed by certificate {}: {}",
+                ix + 1,
+                ix + 2,
+                e
+            ));
+            break;
+        }
+        links += 1;
+    }
+    let last = &chain[chain.len() - 1];
+    let self_signed = last.issuer == last.subject;
+    if result.is_ok() && self_signed {
+        result = verify_certificate(last, last)
+            .map_err(|e| format!("the self-signed certificate does not verify: {}", e));
+        links += 1;
+    }
+    checks.push(check(
+        "Certificate chain",
+        result.map(|()| match (links, self_signed) {
+            (0, _) => "issuer not included; not checked".to_string(),
+            (links, true) => format!(
+                "{} signature(s) verified, ending self-signed; no trusted root",
+                links
+            ),
+            (links, false) => format!("{} signature(s) verified; no trusted root", links),
+        }),
+    ));
+    Ok(())
+}
+
+/// Check `sig` over `message` with a CBOR-encoded COSE key, using the
+/// algorithm the key names.
+fn verify_with_stored_key(public_key: &[u8], message: &[u8], sig: &[u8]) -> Result<String, String> {
+    let Ok(Value::Map(cose_key)) = from_slice::<Value>(public_key) else {
+        return Err("no stored public key to check it with".into());
+    };
+    let scheme = match cose_key.get(&Value::Integer(CoseKeyParam::Alg as i128)) {
+        Some(Value::Integer(alg)) => {
+            Scheme::from_cose(*alg).ok_or_else(|| format!("algorithm {} is not supported", alg))?
+        }
+        _ => return Err("the stored public key names no algorithm".into()),
+    };
+    let key = PublicKey::from_cose(&cose_key)?;
+    verify(scheme, &key, message, sig)
+        .map(|()| "authenticatorData || clientDataHash, stored public key".to_string())
+}
+
+/// Verify a GetAssertion `response` for the stored credential
+/// `credential_id` of `rp_id`, sent with `client_data_hash`: the RP ID hash,
+/// user presence, which credential answered, and the signature against the
+/// stored COSE `public_key` (CBOR). Errors only when the response has no
+/// authenticator data or signature to check.
+pub(crate) fn verify_assertion(
+    rp_id: &str,
+    credential_id: &[u8],
+    public_key: &[u8],
+    response: &BTreeMap<Value, Value>,
+    client_data_hash: &[u8],
+) -> Result<SignInTest, PFError> {
+    let Some(Value::Bytes(auth_data)) = response.get(&key(0x02)) else {
+        return Err(PFError::Io("Response has no authenticatorData".into()));
+    };
+    let Some(Value::Bytes(sig)) = response.get(&key(0x03)) else {
+        return Err(PFError::Io("Response has no signature".into()));
+    };
+    let parsed = parse_auth_data(auth_data)?;
+    let has_flag = |flag: AuthenticatorFlags| parsed.flags & flag.bits() != 0;
+
+    let rp_id_hash = digest::digest(&digest::SHA256, rp_id.as_bytes());
+    let answered = match response.get(&key(0x01)) {
+        Some(Value::Map(descriptor)) => match descriptor.get(&text("id")) {
+            Some(Value::Bytes(id)) => Some(id.as_slice()),
+            _ => None,
+        },
+        _ => None,
+    };
+    let checks = vec![
+        check(
+            "RP ID hash",
+            if auth_data[..32] == *rp_id_hash.as_ref() {
+                Ok(format!("SHA-256 of {}", rp_id))
+            } else {
+                Err(format!("not the SHA-256 of {}", rp_id))
+            },
+        ),
+        check(
+            "User presence",
+            if has_flag(AuthenticatorFlags::USER_PRESENT) {
+                Ok("UP flag set".into())
+            } else {
+                Err("UP flag not set".into())
+            },
+        ),
+        check(
+            "Credential",
+            match answered {
+                Some(id) if id != credential_id => {
+                    Err(format!("another credential answered ({})", hex::encode(id)))
+                }
+                _ => Ok("this passkey answered".into()),
+            },
+        ),
+        check(
+            "Signature",
+            verify_with_stored_key(
+                public_key,
+                &[&auth_data[..], client_data_hash].concat(),
+                sig,
+            ),
+        ),
+    ];
+
+    Ok(SignInTest {
+        valid: checks.iter().all(|c| c.passed),
+        sign_count: parsed.sign_count,
+        user_verified: has_flag(AuthenticatorFlags::USER_VERIFIED),
+        checks,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ring::rand::SystemRandom;
+    use ring::signature::{ECDSA_P256_SHA256_ASN1_SIGNING, EcdsaKeyPair, KeyPair};
+    use serde_cbor_2::to_vec;
+
+    const AAGUID: [u8; 16] = [0x11; 16];
+    const CLIENT_DATA_HASH: [u8; 32] = [0x22; 32];
+
+    fn tlv(tag: u8, contents: &[u8]) -> Vec<u8> {
+        let mut out = vec![tag];
+        if contents.len() < 0x80 {
+            out.push(contents.len() as u8);
+        } else {
+            out.extend([0x82, (contents.len() >> 8) as u8, contents.len() as u8]);
+        }
+        out.extend(contents);
+        out
+    }
+
+    fn key_pair() -> EcdsaKeyPair {
+        let rng = SystemRandom::new();
+        let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, &rng).unwrap();
+        EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, pkcs8.as_ref(), &rng).unwrap()
+    }
+
+    fn sign(key: &EcdsaKeyPair, message: &[u8]) -> Vec<u8> {
+        key.sign(&SystemRandom::new(), message)
+            .unwrap()
+            .as_ref()
+            .to_vec()
+    }
+
+    /// A self-signed P-256 certificate, with the AAGUID extension when given.
+    fn certificate(key: &EcdsaKeyPair, aaguid: Option<[u8; 16]>) -> Vec<u8> {
+        let name = tlv(
+            0x30,
+            &tlv(
+                0x31,
+                &tlv(
+                    0x30,
+                    &[
+                        tlv(0x06, &[0x55, 0x04, 0x03]),
+                        tlv(0x0C, b"Test Attestation"),
+                    ]
+                    .concat(),
+                ),
+            ),
+        );
+        let key_info = [
+            tlv(
+                0x30,
+                &[tlv(0x06, OID_EC_PUBLIC_KEY), tlv(0x06, OID_P256)].concat(),
+            ),
+            tlv(0x03, &[&[0u8][..], key.public_key().as_ref()].concat()),
+        ]
+        .concat();
+        let mut tbs = [
+            tlv(0xA0, &tlv(0x02, &[2])),
+            tlv(0x02, &[1]),
+            tlv(0x30, &tlv(0x06, OID_ECDSA_SHA256)),
+            name.clone(),
+            tlv(
+                0x30,
+                &[tlv(0x17, b"250101000000Z"), tlv(0x17, b"450101000000Z")].concat(),
+            ),
+            name,
+            tlv(0x30, &key_info),
+        ]
+        .concat();
+        if let Some(aaguid) = aaguid {
+            let oid = [
+                0x2B, 0x06, 0x01, 0x04, 0x01, 0x82, 0xE5, 0x1C, 0x01, 0x01, 0x04,
+            ];
+            let extension = [tlv(0x06, &oid), tlv(0x04, &tlv(0x04, &aaguid))].concat();
+            tbs.extend(tlv(0xA3, &tlv(0x30, &tlv(0x30, &extension))));
+        }
+        let tbs = tlv(0x30, &tbs);
+        let signature = sign(key, &tbs);
+        tlv(
+            0x30,
+            &[
+                tbs,
+                tlv(0x30, &tlv(0x06, OID_ECDSA_SHA256)),
+                tlv(0x03, &[&[0u8][..], &signature[..]].concat()),
+            ]
+            .concat(),
+        )
+    }
+
+    /// `authenticatorData` attesting `credential` under [`AAGUID`].
+    fn auth_data(credential: &EcdsaKeyPair) -> Vec<u8> {
+        let point = credential.public_key().as_ref();
+        let cose_key = to_vec(&Value::Map(BTreeMap::from([
+            (Value::Integer(1), Value::Integer(2)),
+            (Value::Integer(3), Value::Integer(-7)),
+            (Value::Integer(-1), Value::Integer(1)),
+            (Value::Integer(-2), Value::Bytes(point[1..33].to_vec())),
+            (Value::Integer(-3), Value::Bytes(point[33..].to_vec())),
+        ])))
+        .unwrap();
+        let mut data = vec![0xAA; 32];
+        data.push(0x41);
+        data.extend(0u32.to_be_bytes());
+        data.extend(AAGUID);
+        data.extend(2u16.to_be_bytes());
+        data.extend([9, 9]);
+        data.extend(cose_key);
+        data
+    }
+
+    fn statement(sig: Vec<u8>, x5c: Option<Vec<u8>>) -> BTreeMap<Value, Value> {
+        let mut statement = BTreeMap::from([
+            (text("alg"), Value::Integer(-7)),
+            (text("sig"), Value::Bytes(sig)),
+        ]);
+        if let Some(der) = x5c {
+            statement.insert(text("x5c"), Value::Array(vec![Value::Bytes(der)]));
+        }
+        statement
+    }
+
+    #[test]
+    fn test_basic_attestation_verifies() {
+        let attestation = key_pair();
+        let data = auth_data(&key_pair());
+        let sig = sign(&attestation, &[&data[..], &CLIENT_DATA_HASH[..]].concat());
+        let statement = statement(sig, Some(certificate(&attestation, Some(AAGUID))));
+
+        let result = verify_packed(&statement, &data, &CLIENT_DATA_HASH);
+        assert!(result.valid, "{:?}", result.checks);
+        assert!(!result.self_attestation);
+        assert_eq!(result.aaguid, hex::encode(AAGUID));
+        assert_eq!(result.checks.len(), 3);
+
+        // Signed over a different client data hash.
+        let result = verify_packed(&statement, &data, &[0x33; 32]);
+        assert!(!result.valid);
+        assert!(!result.checks[0].passed);
+    }
+
+    #[test]
+    fn test_certificate_aaguid_must_match() {
+        let attestation = key_pair();
+        let data = auth_data(&key_pair());
+        let sig = sign(&attestation, &[&data[..], &CLIENT_DATA_HASH[..]].concat());
+        let statement = statement(sig, Some(certificate(&attestation, Some([0x99; 16]))));
+
+        let result = verify_packed(&statement, &data, &CLIENT_DATA_HASH);
+        assert!(!result.valid);
+        let aaguid = result
+            .checks
+            .iter()
+            .find(|c| c.name == "Certificate AAGUID")
+            .unwrap();
+        assert!(!aaguid.passed);
+    }
+
+    #[test]
+    fn test_self_attestation_uses_the_credential_key() {
+        let credential = key_pair();
+        let data = auth_data(&credential);
+        let signed = [&data[..], &CLIENT_DATA_HASH[..]].concat();
+
+        let result = verify_packed(
+            &statement(sign(&credential, &signed), None),
+            &data,
+            &CLIENT_DATA_HASH,
+        );
+        assert!(result.valid, "{:?}", result.checks);
+        assert!(result.self_attestation);
+
+        let result = verify_packed(
+            &statement(sign(&key_pair(), &signed), None),
+            &data,
+            &CLIENT_DATA_HASH,
+        );
+        assert!(!result.valid);
+    }
+
+    #[test]
+    fn test_assertion_verifies_with_the_stored_key() {
+        let credential = key_pair();
+        let point = credential.public_key().as_ref();
+        let stored_key = to_vec(&Value::Map(BTreeMap::from([
+            (Value::Integer(1), Value::Integer(2)),
+            (Value::Integer(3), Value::Integer(-7)),
+            (Value::Integer(-1), Value::Integer(1)),
+            (Value::Integer(-2), Value::Bytes(point[1..33].to_vec())),
+            (Value::Integer(-3), Value::Bytes(point[33..].to_vec())),
+        ])))
+        .unwrap();
+        let mut data = digest::digest(&digest::SHA256, b"example.com")
+            .as_ref()
+            .to_vec();
+        data.push(0x05);
+        data.extend(9u32.to_be_bytes());
+        let response = |sig: Vec<u8>| {
+            BTreeMap::from([
+                (
+                    key(0x01),
+                    Value::Map(BTreeMap::from([(text("id"), Value::Bytes(vec![9, 9]))])),
+                ),
+                (key(0x02), Value::Bytes(data.clone())),
+                (key(0x03), Value::Bytes(sig)),
+            ])
+        };
+        let sig = sign(&credential, &[&data[..], &CLIENT_DATA_HASH[..]].concat());
+
+        let result = verify_assertion(
+            "example.com",
+            &[9, 9],
+            &stored_key,
+            &response(sig.clone()),
+            &CLIENT_DATA_HASH,
+        )
+        .unwrap();
+        assert!(result.valid, "{:?}", result.checks);
+        assert_eq!(result.sign_count, 9);
+        assert!(result.user_verified);
+
+        // Another RP, another credential and another key all fail.
+        let result = verify_assertion(
+            "example.org",
+            &[9, 9],
+            &stored_key,
+            &response(sig.clone()),
+            &CLIENT_DATA_HASH,
+        )
+        .unwrap();
+        assert!(!result.checks[0].passed);
+        let result = verify_assertion(
+            "example.com",
+            &[1],
+            &stored_key,
+            &response(sig),
+            &CLIENT_DATA_HASH,
+        )
+        .unwrap();
+        assert!(!result.checks[2].passed);
+        let forged = sign(&key_pair(), &[&data[..], &CLIENT_DATA_HASH[..]].concat());
+        let result = verify_assertion(
+            "example.com",
+            &[9, 9],
+            &stored_key,
+            &response(forged),
+            &CLIENT_DATA_HASH,
+        )
+        .unwrap();
+        assert!(!result.valid);
+        assert!(!result.checks[3].passed);
+    }
+
+    #[test]
+    fn test_malformed_statement_fails() {
+        let data = auth_data(&key_pair());
+        let result = verify_packed(&BTreeMap::new(), &data, &CLIENT_DATA_HASH);
+        assert!(!result.valid);
+        assert_eq!(result.checks[0].name, "Attestation statement");
+    }
+}
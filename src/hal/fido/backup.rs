@@ -0,0 +1,265 @@
+//! Encrypted device backups through the pico-fido vendor Backup command.
+//!
+//! `GetEncryptedBackup` returns a blob the firmware has already encrypted;
+//! `RestoreEncryptedBackup` hands one back. PicoForge never looks inside the
+//! blob: it wraps it in a small file container (magic, version, length,
+//! SHA-256 of the blob) so a truncated or foreign file is refused before
+//! anything is sent to a key.
+//!
+//! Firmware that mandates a secure channel refuses both commands until an
+//! MSE key agreement has run ([`super::mse`]); they are retried once after
+//! one.
+//!
+//! The blob travels to and from the key in a single CTAPHID message, so the
+//! progress reported for the key is per stage; the file side is written and
+//! read in chunks and reports bytes.
+
+use super::mse::with_channel_fallback;
+use super::ops::FidoOperations;
+use crate::error::PFError;
+use crate::hal::transport::fido::HidTransport;
+use ring::digest;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+
+/// First bytes of a backup file.
+const MAGIC: &[u8; 8] = b"PFBACKUP";
+
+/// Container version written by this build.
+const VERSION: u8 = 1;
+
+/// Magic, version byte and big-endian blob length.
+const HEADER_LEN: usize = MAGIC.len() + 1 + 4;
+
+/// Length of the trailing SHA-256.
+const CHECKSUM_LEN: usize = 32;
+
+/// Bytes written or read per step, between two progress reports.
+const CHUNK_LEN: usize = 4096;
+
+/// Stage of a backup or restore.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackupStage {
+    /// Asking the key for its backup blob.
+    ReadingKey,
+    /// Writing the backup file.
+    WritingFile,
+    /// Reading the backup file.
+    ReadingFile,
+    /// Sending the blob to the key.
+    WritingKey,
+    Done,
+}
+
+impl BackupStage {
+    pub fn label(&self) -> &'static str {
+        match self {
+            BackupStage::ReadingKey => "Reading the backup from the key…",
+            BackupStage::WritingFile => "Writing the backup file…",
+            BackupStage::ReadingFile => "Reading the backup file…",
+            BackupStage::WritingKey => "Restoring the backup to the key…",
+            BackupStage::Done => "Done",
+        }
+    }
+}
+
+/// One progress report: the stage, and bytes done of `total` where the stage
+/// moves bytes (0 of 0 otherwise).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BackupProgress {
+    pub stage: BackupStage,
+    pub done: usize,
+    pub total: usize,
+}
+
+impl BackupProgress {
+    fn stage(stage: BackupStage) -> Self {
+        Self {
+            stage,
+            done: 0,
+            total: 0,
+        }
+    }
+
+    /// Overall completion in `0.0..=1.0`. Each direction has two stages; the
+    /// key stage counts as the first half of a backup and the second half of
+    /// a restore.
+    pub fn fraction(&self) -> f32 {
+        let within = if self.total == 0 {
+            0.0
+        } else {
+            self.done as f32 / self.total as f32
+        };
+        match self.stage {
+            BackupStage::ReadingKey | BackupStage::ReadingFile => within * 0.5,
+            BackupStage::WritingFile => 0.5 + within * 0.5,
+            BackupStage::WritingKey => 0.5,
+            BackupStage::Done => 1.0,
+        }
+    }
+}
+
+/// Wrap `blob` in the backup file container.
+pub fn encode(blob: &[u8]) -> Result<Vec<u8>, PFError> {
+    let len =
+        u32::try_from(blob.len()).map_err(|_| PFError::Io("Backup blob is too large".into()))?;
+    let mut out = Vec::with_capacity(HEADER_LEN + blob.len() + CHECKSUM_LEN);
+    out.extend_from_slice(MAGIC);
+    out.push(VERSION);
+    out.extend_from_slice(&len.to_be_bytes());
+    out.extend_from_slice(blob);
+    out.extend_from_slice(digest::digest(&digest::SHA256, blob).as_ref());
+    Ok(out)
+}
+
+/// Unwrap the blob from a backup file, checking magic, version, length and
+/// checksum.
+pub fn decode(file: &[u8]) -> Result<Vec<u8>, PFError> {
+    if file.len() < HEADER_LEN || !file.starts_with(MAGIC) {
+        return Err(PFError::Io("Not a PicoForge backup file".into()));
+    }
+    let version = file[MAGIC.len()];
+    if version != VERSION {
+        return Err(PFError::Io(format!(
+            "Unsupported backup file version {}",
+            version
+        )));
+    }
+    let len_bytes: [u8; 4] = file[MAGIC.len() + 1..HEADER_LEN]
+        .try_into()
+        .map_err(|_| PFError::Io("Not a PicoForge backup file".into()))?;
+    let len = u32::from_be_bytes(len_bytes) as usize;
+    if file.len() != HEADER_LEN + len + CHECKSUM_LEN {
+        return Err(PFError::Io(
+            "The backup file is truncated or has trailing data".into(),
+        ));
+    }
+    let blob = &file[HEADER_LEN..HEADER_LEN + len];
+    let checksum = &file[HEADER_LEN + len..];
+    if digest::digest(&digest::SHA256, blob).as_ref() != checksum {
+        return Err(PFError::Io("The backup file is corrupted".into()));
+    }
+    Ok(blob.to_vec())
+}
+
+/// Read the encrypted backup from the connected key and write it to `path`.
+/// Returns the size of the blob.
+pub fn backup_device(
+    path: &Path,
+    mut progress: impl FnMut(BackupProgress),
+) -> Result<usize, PFError> {
+    progress(BackupProgress::stage(BackupStage::ReadingKey));
+    let transport = HidTransport::open()?;
+    let blob = with_channel_fallback(&transport, "Backup", |t| t.get_encrypted_backup())?;
+    let encoded = encode(&blob)?;
+
+    let total = encoded.len();
+    let mut file = File::create(path)
+        .map_err(|e| PFError::Io(format!("Could not create {}: {}", path.display(), e)))?;
+    let mut done = 0;
+    for chunk in encoded.chunks(CHUNK_LEN) {
+        file.write_all(chunk)
+            .map_err(|e| PFError::Io(format!("Could not write {}: {}", path.display(), e)))?;
+        done += chunk.len();
+        progress(BackupProgress {
+            stage: BackupStage::WritingFile,
+            done,
+            total,
+        });
+    }
+    file.sync_all()
+        .map_err(|e| PFError::Io(format!("Could not write {}: {}", path.display(), e)))?;
+
+    log::info!(
+        "Encrypted backup ({} bytes) saved to {}",
+        blob.len(),
+        path.display()
+    );
+    progress(BackupProgress::stage(BackupStage::Done));
+    Ok(blob.len())
+}
+
+/// Read a backup file from `path` and restore it to the connected key.
+/// Returns the size of the blob.
+pub fn restore_device(
+    path: &Path,
+    mut progress: impl FnMut(BackupProgress),
+) -> Result<usize, PFError> {
+    let mut file = File::open(path)
+        .map_err(|e| PFError::Io(format!("Could not open {}: {}", path.display(), e)))?;
+    let total = file.metadata().map(|m| m.len() as usize).unwrap_or(0);
+    let mut contents = Vec::with_capacity(total);
+    let mut chunk = [0u8; CHUNK_LEN];
+    loop {
+        let n = file
+            .read(&mut chunk)
+            .map_err(|e| PFError::Io(format!("Could not read {}: {}", path.display(), e)))?;
+        if n == 0 {
+            break;
+        }
+        contents.extend_from_slice(&chunk[..n]);
+        progress(BackupProgress {
+            stage: BackupStage::ReadingFile,
+            done: contents.len(),
+            total: total.max(contents.len()),
+        });
+    }
+    let blob = decode(&contents)?;
+
+    progress(BackupProgress::stage(BackupStage::WritingKey));
+    let transport = HidTransport::open()?;
+    with_channel_fallback(&transport, "Restore", |t| t.restore_encrypted_backup(&blob))?;
+
+    log::info!(
+        "Encrypted backup ({} bytes) restored from {}",
+        blob.len(),
+        path.display()
+    );
+    progress(BackupProgress::stage(BackupStage::Done));
+    Ok(blob.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_container_round_trip() {
+        let blob = vec![0xA5; 5000];
+        let file = encode(&blob).unwrap();
+        assert_eq!(&file[..8], b"PFBACKUP");
+        assert_eq!(file.len(), HEADER_LEN + blob.len() + CHECKSUM_LEN);
+        assert_eq!(decode(&file).unwrap(), blob);
+    }
+
+    #[test]
+    fn test_rejects_damaged_files() {
+        let file = encode(&[1, 2, 3, 4]).unwrap();
+        assert!(decode(b"not a backup").is_err());
+        assert!(decode(&file[..file.len() - 1]).is_err());
+
+        let mut flipped = file.clone();
+        flipped[HEADER_LEN] ^= 0xFF;
+        assert!(decode(&flipped).is_err());
+
+        let mut future = file;
+        future[MAGIC.len()] = VERSION + 1;
+        assert!(decode(&future).is_err());
+    }
+
+    #[test]
+    fn test_progress_fraction() {
+        let writing = BackupProgress {
+            stage: BackupStage::WritingFile,
+            done: 50,
+            total: 100,
+        };
+        assert_eq!(writing.fraction(), 0.75);
+        assert_eq!(
+            BackupProgress::stage(BackupStage::ReadingKey).fraction(),
+            0.0
+        );
+        assert_eq!(BackupProgress::stage(BackupStage::Done).fraction(), 1.0);
+    }
+}
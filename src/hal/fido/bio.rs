@@ -0,0 +1,450 @@
+//! Fingerprint enrollment (`authenticatorBioEnrollment`, CTAP 2.1 §6.7).
+//!
+//! Keys with a fingerprint sensor advertise `bioEnroll` in GetInfo (`false`
+//! while nothing is enrolled); FIDO_2_1_PRE firmware uses
+//! `userVerificationMgmtPreview` and command `0x40` instead of `0x09`.
+//!
+//! Everything except the sensor info needs a PIN token with the
+//! `BIO_ENROLLMENT` permission. An enrollment is several exchanges on the
+//! same token: `enrollBegin` captures the first sample and returns the template ID,
+//! then `enrollCaptureNextSample` is repeated until no samples remain.
+//! [`BioEnrollSession`] keeps the transport and token between samples so the
+//! UI can show each sample's status and cancel in between.
+
+use super::constants::*;
+use super::diag::{key, pin_uv_auth_param, response_map};
+use super::ops::FidoOperations;
+use crate::error::PFError;
+use crate::hal::transport::fido::{CTAPHID_CBOR, HidTransport, user_presence_timeout_ms};
+use crate::hal::transport::manager::SharedTransport;
+use crate::hal::types::{BioEnrollSample, BioSensorInfo, BioTemplate};
+use crate::tunables;
+use serde_cbor_2::{Value, to_vec};
+use std::collections::BTreeMap;
+
+/// How long the key waits for a finger per sample. Shorter than the transport
+/// timeout so an idle sensor answers with `NoUserActivity` instead of timing out.
+const SAMPLE_TIMEOUT_MS: u32 = 10_000;
+
+/// Timeout for exchanges that do not wait for a finger.
+fn command_timeout_ms() -> i32 {
+    tunables::hid().total_timeout_ms as i32
+}
+
+/// The command number this key answers to. Fails when it has no sensor.
+fn bio_command(transport: &HidTransport) -> Result<CtapCommand, PFError> {
+    let info = super::read_device_info(transport)?;
+    if info.options.contains_key("bioEnroll") {
+        Ok(CtapCommand::BioEnrollment)
+    } else if info.options.contains_key("userVerificationMgmtPreview") {
+        Ok(CtapCommand::BioEnrollmentPreview)
+    } else {
+        Err(PFError::Device(
+            "This key does not support fingerprint enrollment".into(),
+        ))
+    }
+}
+
+/// Build a request map. `pin_token` signs `modality || subCommand ||
+/// subCommandParams` as §6.7 requires.
+pub(crate) fn encode_request(
+    sub_command: BioEnrollmentSubCommand,
+    params: Option<BTreeMap<Value, Value>>,
+    pin_token: Option<&[u8]>,
+) -> Result<Value, PFError> {
+    let mut map = BTreeMap::new();
+    map.insert(
+        key(BioEnrollmentParam::Modality as u8),
+        Value::Integer(BIO_MODALITY_FINGERPRINT as i128),
+    );
+    map.insert(
+        key(BioEnrollmentParam::SubCommand as u8),
+        Value::Integer(sub_command as i128),
+    );
+
+    let mut message = vec![BIO_MODALITY_FINGERPRINT, sub_command as u8];
+    if let Some(params) = params {
+        let params = Value::Map(params);
+        message.extend(to_vec(&params).map_err(|e| PFError::Io(e.to_string()))?);
+        map.insert(key(BioEnrollmentParam::SubCommandParams as u8), params);
+    }
+    if let Some(token) = pin_token {
+        map.insert(
+            key(BioEnrollmentParam::PinUvAuthProtocol as u8),
+            Value::Integer(1),
+        );
+        map.insert(
+            key(BioEnrollmentParam::PinUvAuthParam as u8),
+            Value::Bytes(pin_uv_auth_param(token, &message)),
+        );
+    }
+    Ok(Value::Map(map))
+}
+
+fn send(
+    transport: &HidTransport,
+    command: CtapCommand,
+    request: &Value,
+    timeout_ms: i32,
+) -> Result<BTreeMap<Value, Value>, PFError> {
+    let mut payload = vec![command as u8];
+    payload.extend(to_vec(request).map_err(|e| PFError::Io(e.to_string()))?);
+    let response = transport.send_cbor_with_timeout(CTAPHID_CBOR, &payload, timeout_ms)?;
+    if response.is_empty() {
+        return Ok(BTreeMap::new());
+    }
+    response_map(&response)
+}
+
+fn uint(map: &BTreeMap<Value, Value>, k: BioEnrollmentResponseParam) -> Option<u64> {
+    match map.get(&key(k as u8)) {
+        Some(Value::Integer(v)) => u64::try_from(*v).ok(),
+        _ => None,
+    }
+}
+
+/// Parse an `enrollBegin` or `enrollCaptureNextSample` response.
+pub(crate) fn parse_sample(map: &BTreeMap<Value, Value>) -> Result<BioEnrollSample, PFError> {
+    let status = uint(map, BioEnrollmentResponseParam::LastEnrollSampleStatus)
+        .ok_or_else(|| PFError::Io("Response has no lastEnrollSampleStatus".into()))?;
+    let remaining = uint(map, BioEnrollmentResponseParam::RemainingSamples)
+        .ok_or_else(|| PFError::Io("Response has no remainingSamples".into()))?;
+    Ok(BioEnrollSample {
+        status: status as u8,
+        remaining: remaining as u32,
+    })
+}
+
+/// Parse the `templateInfos` of an `enumerateEnrollments` response.
+pub(crate) fn parse_templates(map: &BTreeMap<Value, Value>) -> Vec<BioTemplate> {
+    let Some(Value::Array(infos)) = map.get(&key(BioEnrollmentResponseParam::TemplateInfos as u8))
+    else {
+        return Vec::new();
+    };
+    infos
+        .iter()
+        .filter_map(|info| {
+            let Value::Map(info) = info else {
+                return None;
+            };
+            let Some(Value::Bytes(id)) =
+                info.get(&key(BioEnrollmentSubCommandParam::TemplateId as u8))
+            else {
+                return None;
+            };
+            let name = match info.get(&key(
+                BioEnrollmentSubCommandParam::TemplateFriendlyName as u8
+            )) {
+                Some(Value::Text(name)) => name.clone(),
+                _ => String::new(),
+            };
+            Some(BioTemplate {
+                template_id: hex::encode(id),
+                name,
+            })
+        })
+        .collect()
+}
+
+fn template_param(template_id: &[u8]) -> BTreeMap<Value, Value> {
+    let mut params = BTreeMap::new();
+    params.insert(
+        key(BioEnrollmentSubCommandParam::TemplateId as u8),
+        Value::Bytes(template_id.to_vec()),
+    );
+    params
+}
+
+fn open() -> Result<(SharedTransport, CtapCommand), String> {
+    let transport =
+        HidTransport::open().map_err(|e| format!("Could not open HID transport: {}", e))?;
+    let command = bio_command(&transport).map_err(|e| e.to_string())?;
+    Ok((transport, command))
+}
+
+fn bio_token(transport: &HidTransport, pin: &str) -> Result<Vec<u8>, String> {
+    transport
+        .get_pin_token_with_permission(pin, PinUvAuthTokenPermissions::BIO_ENROLLMENT, None)
+        .map_err(|e| format!("Failed to get PIN token: {}", e))
+}
+
+/// Sensor kind and sample count. Needs no PIN.
+pub(crate) fn read_sensor_info() -> Result<BioSensorInfo, String> {
+    let (transport, command) = open()?;
+    let request = encode_request(
+        BioEnrollmentSubCommand::GetFingerprintSensorInfo,
+        None,
+        None,
+    )
+    .map_err(|e| e.to_string())?;
+    let response = send(&transport, command, &request, command_timeout_ms())
+        .map_err(|e| format!("Failed to read sensor info: {}", e))?;
+    Ok(BioSensorInfo {
+        fingerprint_kind: uint(&response, BioEnrollmentResponseParam::FingerprintKind)
+            .map(|v| v as u8),
+        max_samples: uint(
+            &response,
+            BioEnrollmentResponseParam::MaxCaptureSamplesRequiredForEnroll,
+        )
+        .map(|v| v as u32),
+        max_name_length: uint(
+            &response,
+            BioEnrollmentResponseParam::MaxTemplateFriendlyName,
+        )
+        .map(|v| v as usize),
+    })
+}
+
+/// Enrolled fingerprints. A key with none answers `CTAP2_ERR_INVALID_OPTION`,
+/// which is reported as an empty list.
+pub(crate) fn list_fingerprints(pin: String) -> Result<Vec<BioTemplate>, String> {
+    let (transport, command) = open()?;
+    let token = bio_token(&transport, &pin)?;
+    let request = encode_request(
+        BioEnrollmentSubCommand::EnumerateEnrollments,
+        None,
+        Some(&token),
+    )
+    .map_err(|e| e.to_string())?;
+    match send(&transport, command, &request, command_timeout_ms()) {
+        Ok(response) => Ok(parse_templates(&response)),
+        Err(e) if e.ctap() == Some(Ctap2Error::InvalidOption) => {
+            log::info!("No fingerprints enrolled (0x2C)");
+            Ok(Vec::new())
+        }
+        Err(e) => Err(format!("Failed to list fingerprints: {}", e)),
+    }
+}
+
+/// Delete one enrolled fingerprint.
+pub(crate) fn remove_fingerprint(pin: String, template_id_hex: String) -> Result<(), String> {
+    let template_id =
+        hex::decode(&template_id_hex).map_err(|_| "Invalid template ID".to_string())?;
+    let (transport, command) = open()?;
+    let token = bio_token(&transport, &pin)?;
+    let request = encode_request(
+        BioEnrollmentSubCommand::RemoveEnrollment,
+        Some(template_param(&template_id)),
+        Some(&token),
+    )
+    .map_err(|e| e.to_string())?;
+    send(&transport, command, &request, command_timeout_ms())
+        .map_err(|e| format!("Failed to remove fingerprint: {}", e))?;
+    log::info!("Removed fingerprint {}", template_id_hex);
+    Ok(())
+}
+
+/// An enrollment in progress, holding the transport and PIN token between
+/// samples. Dropping it without [`cancel`](Self::cancel) leaves the key to
+/// time the enrollment out on its own.
+pub struct BioEnrollSession {
+    transport: HidTransport,
+    command: CtapCommand,
+    token: Vec<u8>,
+    template_id: Vec<u8>,
+}
+
+impl BioEnrollSession {
+    /// Start an enrollment and capture the first sample; waits for a finger.
+    pub fn begin(pin: String) -> Result<(Self, BioEnrollSample), String> {
+        // The session waits for a finger between calls, so it gets a handle of
+        // its own instead of holding the shared connection.
+        let transport =
+            HidTransport::connect().map_err(|e| format!("Could not open HID transport: {}", e))?;
+        let command = bio_command(&transport).map_err(|e| e.to_string())?;
+        let token = bio_token(&transport, &pin)?;
+
+        let mut params = BTreeMap::new();
+        params.insert(
+            key(BioEnrollmentSubCommandParam::TimeoutMilliseconds as u8),
+            Value::Integer(SAMPLE_TIMEOUT_MS as i128),
+        );
+        let request = encode_request(
+            BioEnrollmentSubCommand::EnrollBegin,
+            Some(params),
+            Some(&token),
+        )
+        .map_err(|e| e.to_string())?;
+        let response = send(&transport, command, &request, user_presence_timeout_ms())
+            .map_err(|e| format!("Failed to start enrollment: {}", e))?;
+
+        let template_id = match response.get(&key(BioEnrollmentResponseParam::TemplateId as u8)) {
+            Some(Value::Bytes(id)) => id.clone(),
+            _ => return Err("Response has no templateId".into()),
+        };
+        let sample = parse_sample(&response).map_err(|e| e.to_string())?;
+        log::info!(
+            "Fingerprint enrollment started ({} samples remaining)",
+            sample.remaining
+        );
+
+        Ok((
+            Self {
+                transport,
+                command,
+                token,
+                template_id,
+            },
+            sample,
+        ))
+    }
+
+    /// Capture the next sample; waits for a finger.
+    pub fn capture_next(&mut self) -> Result<BioEnrollSample, String> {
+        let mut params = template_param(&self.template_id);
+        params.insert(
+            key(BioEnrollmentSubCommandParam::TimeoutMilliseconds as u8),
+            Value::Integer(SAMPLE_TIMEOUT_MS as i128),
+        );
+        let request = encode_request(
+            BioEnrollmentSubCommand::EnrollCaptureNextSample,
+            Some(params),
+            Some(&self.token),
+        )
+        .map_err(|e| e.to_string())?;
+        let response = send(
+            &self.transport,
+            self.command,
+            &request,
+            user_presence_timeout_ms(),
+        )
+        .map_err(|e| format!("Failed to capture sample: {}", e))?;
+        parse_sample(&response).map_err(|e| e.to_string())
+    }
+
+    /// Abandon the enrollment. Needs no PIN token.
+    pub fn cancel(self) -> Result<(), String> {
+        let request = encode_request(BioEnrollmentSubCommand::CancelCurrentEnrollment, None, None)
+            .map_err(|e| e.to_string())?;
+        send(
+            &self.transport,
+            self.command,
+            &request,
+            command_timeout_ms(),
+        )
+        .map_err(|e| format!("Failed to cancel enrollment: {}", e))?;
+        log::info!("Fingerprint enrollment cancelled");
+        Ok(())
+    }
+
+    /// Name the finished template. A blank name leaves it unnamed.
+    pub fn finish(self, name: String) -> Result<BioTemplate, String> {
+        let name = name.trim().to_string();
+        if !name.is_empty() {
+            let mut params = template_param(&self.template_id);
+            params.insert(
+                key(BioEnrollmentSubCommandParam::TemplateFriendlyName as u8),
+                Value::Text(name.clone()),
+            );
+            let request = encode_request(
+                BioEnrollmentSubCommand::SetFriendlyName,
+                Some(params),
+                Some(&self.token),
+            )
+            .map_err(|e| e.to_string())?;
+            send(
+                &self.transport,
+                self.command,
+                &request,
+                command_timeout_ms(),
+            )
+            .map_err(|e| format!("Fingerprint enrolled, but naming it failed: {}", e))?;
+        }
+        let template_id = hex::encode(&self.template_id);
+        log::info!("Fingerprint {} enrolled", template_id);
+        Ok(BioTemplate { template_id, name })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_cbor_2::from_slice;
+
+    #[test]
+    fn test_request_signs_modality_sub_command_and_params() {
+        let token = [0x11u8; 32];
+        let params = template_param(&[0xAA, 0xBB]);
+        let params_cbor = to_vec(&Value::Map(params.clone())).unwrap();
+
+        let request = encode_request(
+            BioEnrollmentSubCommand::RemoveEnrollment,
+            Some(params),
+            Some(&token),
+        )
+        .unwrap();
+        let Value::Map(map) = request else {
+            panic!("request is not a map");
+        };
+
+        let mut message = vec![0x01, 0x06];
+        message.extend(&params_cbor);
+        assert_eq!(
+            map.get(&key(BioEnrollmentParam::PinUvAuthParam as u8)),
+            Some(&Value::Bytes(pin_uv_auth_param(&token, &message)))
+        );
+        assert_eq!(
+            map.get(&key(BioEnrollmentParam::SubCommand as u8)),
+            Some(&Value::Integer(0x06))
+        );
+    }
+
+    #[test]
+    fn test_cancel_request_is_unsigned() {
+        let request =
+            encode_request(BioEnrollmentSubCommand::CancelCurrentEnrollment, None, None).unwrap();
+        let bytes = to_vec(&request).unwrap();
+        // {1: 1, 2: 3}
+        assert_eq!(bytes, vec![0xA2, 0x01, 0x01, 0x02, 0x03]);
+    }
+
+    #[test]
+    fn test_parse_sample() {
+        // {4: h'01', 5: 0, 6: 3}
+        let map = response_map(&[0xA3, 0x04, 0x41, 0x01, 0x05, 0x00, 0x06, 0x03]).unwrap();
+        assert_eq!(
+            parse_sample(&map).unwrap(),
+            BioEnrollSample {
+                status: 0,
+                remaining: 3
+            }
+        );
+        assert!(parse_sample(&BTreeMap::new()).is_err());
+    }
+
+    #[test]
+    fn test_parse_templates() {
+        let mut named = BTreeMap::new();
+        named.insert(key(1), Value::Bytes(vec![0x01, 0x02]));
+        named.insert(key(2), Value::Text("Right thumb".into()));
+        let mut unnamed = BTreeMap::new();
+        unnamed.insert(key(1), Value::Bytes(vec![0x03]));
+        let mut response = BTreeMap::new();
+        response.insert(
+            key(7),
+            Value::Array(vec![
+                Value::Map(named),
+                Value::Map(unnamed),
+                Value::Integer(5),
+            ]),
+        );
+        let bytes = to_vec(&Value::Map(response)).unwrap();
+        let Value::Map(map) = from_slice::<Value>(&bytes).unwrap() else {
+            panic!("not a map");
+        };
+
+        assert_eq!(
+            parse_templates(&map),
+            vec![
+                BioTemplate {
+                    template_id: "0102".into(),
+                    name: "Right thumb".into()
+                },
+                BioTemplate {
+                    template_id: "03".into(),
+                    name: String::new()
+                },
+            ]
+        );
+    }
+}
@@ -27,7 +27,7 @@
 
 use std::fmt;
 
-pub use crate::hal::common::cose::{CoseAlgorithm, CoseCurve, CoseKeyParam};
+pub use crate::hal::common::cose::{CoseAlgorithm, CoseCurve, CoseKeyParam, CoseKeyType};
 
 // ══════════════════════════════════════════════════════════════════════════════
 // CTAP2 STANDARD — FIDO Alliance specification §8.1
@@ -54,6 +54,8 @@ pub enum CtapCommand {
     Reset = 0x07,
     /// Get the next assertion when multiple credentials match (§11.5.6).
     GetNextAssertion = 0x08,
+    /// Fingerprint enrollment (§6.7).
+    BioEnrollment = 0x09,
     /// Credential management operations (§11.5.8).
     CredentialMgmt = 0x0A,
     /// Put the authenticator into a discoverable state (§11.5.7).
@@ -62,6 +64,9 @@ pub enum CtapCommand {
     LargeBlobs = 0x0C,
     /// Authenticator configuration (enterprise attestation, min PIN, etc.) (§11.5.10).
     Config = 0x0D,
+    /// `authenticatorBioEnrollment` under its FIDO_2_1_PRE number, for keys
+    /// that only advertise `userVerificationMgmtPreview`.
+    BioEnrollmentPreview = 0x40,
 }
 
 /// CTAP1/U2F command codes (legacy protocol, U2F Raw Messages spec).
@@ -183,8 +188,8 @@ pub enum ClientPinResponseParam {
     PinToken = 0x02,
     /// Remaining PIN attempts.
     PinRetries = 0x03,
-    /// Continuation message for large payloads.
-    NextMsg = 0x04,
+    /// Whether the authenticator must be power-cycled before the next PIN attempt.
+    PowerCycleState = 0x04,
     /// Remaining UV attempts.
     UvRetries = 0x05,
 }
@@ -251,10 +256,26 @@ pub enum CredentialMgmtParam {
     PinUvAuthParam = 0x04,
 }
 
+/// Keys of the `subCommandParams` map for `authenticatorCredentialManagement`.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CredentialMgmtSubCommandParam {
+    /// SHA-256 hash of the RP ID (`enumerateCredentialsBegin`).
+    RpIdHash = 0x01,
+    /// Credential descriptor (`deleteCredential`, `updateUserInformation`).
+    CredentialId = 0x02,
+    /// Replacement user entity (`updateUserInformation`).
+    User = 0x03,
+}
+
 /// CBOR map keys for `authenticatorCredentialManagement` responses (§11.5.8).
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CredentialMgmtResponseParam {
+    /// Number of discoverable credentials stored (`getCredsMetadata`).
+    ExistingResidentCredentialsCount = 0x01,
+    /// Further discoverable credentials that fit (`getCredsMetadata`).
+    MaxPossibleRemainingResidentCredentialsCount = 0x02,
     /// Relying party object.
     Rp = 0x03,
     /// SHA-256 hash of the RP ID.
@@ -269,6 +290,218 @@ pub enum CredentialMgmtResponseParam {
     PublicKey = 0x08,
     /// Total credentials for the current RP.
     TotalCredentials = 0x09,
+    /// credProtect level the credential was created with (CTAP 2.1).
+    CredProtect = 0x0A,
+}
+
+/// CBOR map keys for `authenticatorLargeBlobs` requests (§11.5.9).
+///
+/// A request carries either `Get` (read that many bytes) or `Set` (write a
+/// fragment); `Offset` is required for both.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LargeBlobsParam {
+    /// Number of bytes to read.
+    Get = 0x01,
+    /// Fragment to write.
+    Set = 0x02,
+    /// Byte offset of the fragment in the serialized array.
+    Offset = 0x03,
+    /// Total length of the new array; only sent with the first fragment.
+    Length = 0x04,
+    /// HMAC over the fragment; required for writes.
+    PinUvAuthParam = 0x05,
+    /// PIN/UV protocol version.
+    PinUvAuthProtocol = 0x06,
+}
+
+/// CBOR map keys for `authenticatorLargeBlobs` responses (§11.5.9).
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LargeBlobsResponseParam {
+    /// Fragment of the serialized large-blob array.
+    Config = 0x01,
+}
+
+/// CBOR map keys for a large-blob array entry (§6.10.3).
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LargeBlobEntryKey {
+    /// AES-256-GCM ciphertext, including the 16-byte tag.
+    Ciphertext = 0x01,
+    /// 12-byte AES-GCM nonce.
+    Nonce = 0x02,
+    /// Length of the data before compression.
+    OrigSize = 0x03,
+}
+
+/// CBOR map keys for the `hmac-secret` getAssertion input (§12.5).
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HmacSecretInputKey {
+    /// Platform key agreement public key (COSE_Key).
+    KeyAgreement = 0x01,
+    /// One or two 32-byte salts, encrypted with the shared secret.
+    SaltEnc = 0x02,
+    /// `LEFT(HMAC-SHA-256(sharedSecret, saltEnc), 16)` for protocol 1.
+    SaltAuth = 0x03,
+    /// PIN/UV auth protocol the salts are encrypted for.
+    PinUvAuthProtocol = 0x04,
+}
+
+/// Modality value for fingerprints, the only one CTAP 2.1 defines (§6.7).
+pub const BIO_MODALITY_FINGERPRINT: u8 = 0x01;
+
+/// CBOR map keys for `authenticatorBioEnrollment` requests (§6.7).
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BioEnrollmentParam {
+    /// User verification modality (see [`BIO_MODALITY_FINGERPRINT`]).
+    Modality = 0x01,
+    /// Sub-command to execute (see [`BioEnrollmentSubCommand`]).
+    SubCommand = 0x02,
+    /// Sub-command parameters (CBOR map).
+    SubCommandParams = 0x03,
+    /// PIN/UV protocol version.
+    PinUvAuthProtocol = 0x04,
+    /// HMAC over `modality || subCommand || subCommandParams`.
+    PinUvAuthParam = 0x05,
+    /// Ask only for the supported modality.
+    GetModality = 0x06,
+}
+
+/// Sub-commands for `authenticatorBioEnrollment` (§6.7).
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BioEnrollmentSubCommand {
+    /// Start a new enrollment and capture the first sample.
+    EnrollBegin = 0x01,
+    /// Capture the next sample of the current enrollment.
+    EnrollCaptureNextSample = 0x02,
+    /// Abandon the current enrollment.
+    CancelCurrentEnrollment = 0x03,
+    /// List enrolled templates.
+    EnumerateEnrollments = 0x04,
+    /// Rename a template.
+    SetFriendlyName = 0x05,
+    /// Delete a template.
+    RemoveEnrollment = 0x06,
+    /// Sensor kind and the number of samples an enrollment needs.
+    GetFingerprintSensorInfo = 0x07,
+}
+
+/// Sub-command parameters for `authenticatorBioEnrollment` (§6.7).
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BioEnrollmentSubCommandParam {
+    /// Template identifier.
+    TemplateId = 0x01,
+    /// Template name chosen by the user.
+    TemplateFriendlyName = 0x02,
+    /// How long to wait for a finger, in milliseconds.
+    TimeoutMilliseconds = 0x03,
+}
+
+/// CBOR map keys for `authenticatorBioEnrollment` responses (§6.7).
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BioEnrollmentResponseParam {
+    /// Supported modality.
+    Modality = 0x01,
+    /// 1 for a touch sensor, 2 for a swipe sensor.
+    FingerprintKind = 0x02,
+    /// Good samples an enrollment needs.
+    MaxCaptureSamplesRequiredForEnroll = 0x03,
+    /// Template being enrolled.
+    TemplateId = 0x04,
+    /// Quality of the last sample (see [`BioSampleStatus`]).
+    LastEnrollSampleStatus = 0x05,
+    /// Good samples still needed.
+    RemainingSamples = 0x06,
+    /// Enrolled templates, each `{1: templateId, 2: friendlyName}`.
+    TemplateInfos = 0x07,
+    /// Longest friendly name the key accepts, in bytes.
+    MaxTemplateFriendlyName = 0x08,
+}
+
+/// `lastEnrollSampleStatus` values (§6.7.4).
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BioSampleStatus {
+    /// Good sample.
+    Good = 0x00,
+    /// Finger too high.
+    TooHigh = 0x01,
+    /// Finger too low.
+    TooLow = 0x02,
+    /// Finger too far left.
+    TooLeft = 0x03,
+    /// Finger too far right.
+    TooRight = 0x04,
+    /// Swiped too fast.
+    TooFast = 0x05,
+    /// Swiped too slow.
+    TooSlow = 0x06,
+    /// Poor quality.
+    PoorQuality = 0x07,
+    /// Finger too skewed.
+    TooSkewed = 0x08,
+    /// Swipe too short.
+    TooShort = 0x09,
+    /// Could not merge with the previous samples.
+    MergeFailure = 0x0A,
+    /// This fingerprint is already enrolled.
+    AlreadyExists = 0x0B,
+    /// No finger was placed before the timeout.
+    NoUserActivity = 0x0D,
+    /// The finger was not lifted between samples.
+    NoUserPresenceTransition = 0x0E,
+}
+
+impl BioSampleStatus {
+    /// Status from its wire value; `None` for reserved values.
+    pub fn from_u8(value: u8) -> Option<Self> {
+        use BioSampleStatus::*;
+        Some(match value {
+            0x00 => Good,
+            0x01 => TooHigh,
+            0x02 => TooLow,
+            0x03 => TooLeft,
+            0x04 => TooRight,
+            0x05 => TooFast,
+            0x06 => TooSlow,
+            0x07 => PoorQuality,
+            0x08 => TooSkewed,
+            0x09 => TooShort,
+            0x0A => MergeFailure,
+            0x0B => AlreadyExists,
+            0x0D => NoUserActivity,
+            0x0E => NoUserPresenceTransition,
+            _ => return None,
+        })
+    }
+
+    /// What to tell the user after this sample.
+    pub fn hint(self) -> &'static str {
+        match self {
+            BioSampleStatus::Good => "Good sample. Lift your finger and touch again.",
+            BioSampleStatus::TooHigh => "Finger too high. Try again.",
+            BioSampleStatus::TooLow => "Finger too low. Try again.",
+            BioSampleStatus::TooLeft => "Finger too far left. Try again.",
+            BioSampleStatus::TooRight => "Finger too far right. Try again.",
+            BioSampleStatus::TooFast => "Too fast. Try again more slowly.",
+            BioSampleStatus::TooSlow => "Too slow. Try again a little faster.",
+            BioSampleStatus::PoorQuality => "Poor quality sample. Clean the sensor and try again.",
+            BioSampleStatus::TooSkewed => "Finger at an angle. Try again straight.",
+            BioSampleStatus::TooShort => "Swipe too short. Try again.",
+            BioSampleStatus::MergeFailure => "Sample did not match the earlier ones. Try again.",
+            BioSampleStatus::AlreadyExists => "This finger is already enrolled.",
+            BioSampleStatus::NoUserActivity => "No finger detected. Touch the sensor.",
+            BioSampleStatus::NoUserPresenceTransition => {
+                "Lift your finger between samples and touch again."
+            }
+        }
+    }
 }
 
 /// Sub-command parameters for `authenticatorConfig` (§11.5.10).
@@ -293,6 +526,12 @@ pub enum AuthenticateControl {
     CheckOnly = 0x07,
 }
 
+/// PIN/UV auth protocol version used for every PIN-authenticated command (§6.5.6).
+///
+/// The ECDH key agreement and HMAC signing in [`super::ops`] implement
+/// protocol one only, so the authenticator must list it in `pinUvAuthProtocols`.
+pub const PIN_UV_AUTH_PROTOCOL_ONE: u32 = 1;
+
 // ── Bitflags (§11.3.2, §11.5.x) ────────────────────────────────────────────
 
 /// Permission bits for `getPinUvAuthTokenUsingPinWithPermissions` (§11.5.4.1).
@@ -350,12 +589,29 @@ bitflags::bitflags! {
 /// CTAP2 error codes (§8.2).
 ///
 /// Returned as the first byte of a `CTAPHID_CBOR` response when the
-/// status code is non-zero. Negative status indicates an error.
+/// status code is non-zero. Negative status indicates an error. The CTAP1
+/// codes below 0x10 share the status byte and are included.
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Ctap2Error {
     /// Operation completed successfully.
     Success = 0x00,
+    /// The command is not a valid CTAP command.
+    InvalidCommand = 0x01,
+    /// The command included an invalid parameter.
+    InvalidParameter = 0x02,
+    /// Invalid message or item length.
+    InvalidLength = 0x03,
+    /// Invalid message sequencing.
+    InvalidSeq = 0x04,
+    /// Message timed out.
+    Timeout = 0x05,
+    /// Channel busy.
+    ChannelBusy = 0x06,
+    /// Command requires channel lock.
+    LockRequired = 0x0A,
+    /// Command not allowed on this channel.
+    InvalidChannel = 0x0B,
     /// CBOR value has an unexpected type.
     CborUnexpectedType = 0x11,
     /// CBOR structure is malformed.
@@ -428,6 +684,130 @@ pub enum Ctap2Error {
     UvInvalid = 0x3F,
     /// Requested permission not authorized.
     UnauthorizedPermission = 0x40,
+    /// Other unspecified error.
+    Other = 0x7F,
+}
+
+impl Ctap2Error {
+    /// Error from its status byte; `None` for codes the spec does not define.
+    pub fn from_u8(value: u8) -> Option<Self> {
+        use Ctap2Error::*;
+        Some(match value {
+            0x00 => Success,
+            0x01 => InvalidCommand,
+            0x02 => InvalidParameter,
+            0x03 => InvalidLength,
+            0x04 => InvalidSeq,
+            0x05 => Timeout,
+            0x06 => ChannelBusy,
+            0x0A => LockRequired,
+            0x0B => InvalidChannel,
+            0x11 => CborUnexpectedType,
+            0x12 => InvalidCbor,
+            0x14 => MissingParameter,
+            0x15 => LimitExceeded,
+            0x17 => FpDatabaseFull,
+            0x18 => LargeBlobStorageFull,
+            0x19 => CredentialExcluded,
+            0x21 => Processing,
+            0x22 => InvalidCredential,
+            0x23 => UserActionPending,
+            0x24 => OperationPending,
+            0x25 => NoOperations,
+            0x26 => UnsupportedAlgorithm,
+            0x27 => OperationDenied,
+            0x28 => KeyStoreFull,
+            0x2B => UnsupportedOption,
+            0x2C => InvalidOption,
+            0x2D => KeepaliveCancel,
+            0x2E => NoCredentials,
+            0x2F => UserActionTimeout,
+            0x30 => NotAllowed,
+            0x31 => PinInvalid,
+            0x32 => PinBlocked,
+            0x33 => PinAuthInvalid,
+            0x34 => PinAuthBlocked,
+            0x35 => PinNotSet,
+            0x36 => PuatRequired,
+            0x37 => PinPolicyViolation,
+            0x39 => RequestTooLarge,
+            0x3A => ActionTimeout,
+            0x3B => UpRequired,
+            0x3C => UvBlocked,
+            0x3D => IntegrityFailure,
+            0x3E => InvalidSubcommand,
+            0x3F => UvInvalid,
+            0x40 => UnauthorizedPermission,
+            0x7F => Other,
+            _ => return None,
+        })
+    }
+
+    /// What the error means, in words a user can act on.
+    pub fn description(self) -> &'static str {
+        use Ctap2Error::*;
+        match self {
+            Success => "Success",
+            InvalidCommand => "The key does not support this command",
+            InvalidParameter => "The key rejected a parameter of the command",
+            InvalidLength => "The message had an invalid length",
+            InvalidSeq => "The message arrived out of sequence",
+            Timeout => "The message timed out",
+            ChannelBusy => "The key is busy with another application",
+            LockRequired => "The command needs a channel lock",
+            InvalidChannel => "The command is not allowed on this channel",
+            CborUnexpectedType => "The key received an unexpected CBOR type",
+            InvalidCbor => "The key received malformed CBOR",
+            MissingParameter => "A required parameter is missing",
+            LimitExceeded => "A limit of the key was exceeded",
+            FpDatabaseFull => "The fingerprint storage is full",
+            LargeBlobStorageFull => "The large blob storage is full",
+            CredentialExcluded => "A matching credential already exists on the key",
+            Processing => "The key is still processing",
+            InvalidCredential => "The credential is invalid or no longer on the key",
+            UserActionPending => "Waiting for the user to touch the key",
+            OperationPending => "Another operation is in progress",
+            NoOperations => "No operation is pending",
+            UnsupportedAlgorithm => "The key does not support the algorithm",
+            OperationDenied => "The operation was denied",
+            KeyStoreFull => "The credential storage is full",
+            UnsupportedOption => "The key does not support the option",
+            InvalidOption => "The option value is invalid",
+            KeepaliveCancel => "The operation was cancelled",
+            NoCredentials => "No matching credentials on the key",
+            UserActionTimeout => "Timed out waiting for a touch",
+            NotAllowed => "The operation is not allowed right now",
+            PinInvalid => "Incorrect PIN",
+            PinBlocked => "The PIN is blocked; reset the key to use it again",
+            PinAuthInvalid => "The PIN token was rejected",
+            PinAuthBlocked => "Too many wrong PINs; replug the key and try again",
+            PinNotSet => "No PIN is set on the key",
+            PuatRequired => "The operation needs the PIN",
+            PinPolicyViolation => "The PIN does not meet the key's PIN policy",
+            RequestTooLarge => "The request is too large for the key",
+            ActionTimeout => "The operation timed out",
+            UpRequired => "The operation needs a touch",
+            UvBlocked => "User verification is blocked; use the PIN",
+            IntegrityFailure => "An integrity check failed",
+            InvalidSubcommand => "The key does not support the subcommand",
+            UvInvalid => "User verification failed",
+            UnauthorizedPermission => "The PIN token lacks the permission",
+            Other => "The key reported an unspecified error",
+        }
+    }
+}
+
+/// The description followed by the status byte, which log readers and
+/// string consumers key on.
+impl fmt::Display for Ctap2Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} (CTAP error 0x{:02X})",
+            self.description(),
+            *self as u8
+        )
+    }
 }
 
 // ══════════════════════════════════════════════════════════════════════════════
@@ -647,6 +1027,14 @@ pub enum MseSubCommand {
     KeyAgreement = 0x01,
 }
 
+/// Unlock sub-commands.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnlockSubCommand {
+    /// Hand the host key, sealed under the MSE channel, to the device.
+    Unlock = 0x01,
+}
+
 /// Enterprise attestation sub-commands.
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -773,6 +1161,8 @@ pub const MAX_MSG_SIZE: usize = 1024;
 pub const MAX_FRAGMENT_LENGTH: usize = MAX_MSG_SIZE - 64;
 /// Maximum large blob array size in bytes.
 pub const MAX_LARGE_BLOB_SIZE: usize = 2048;
+/// Length of the truncated SHA-256 trailer on a serialized large-blob array.
+pub const LARGE_BLOB_TRAILER_LEN: usize = 16;
 
 /// Default AAGUID for pico-fido firmware.
 ///
@@ -829,6 +1219,26 @@ mod tests {
         assert_eq!(CtapCommand::Config as u8, 0x0D);
     }
 
+    #[test]
+    fn test_large_blobs_keys_match_spec() {
+        assert_eq!(LargeBlobsParam::Get as u8, 0x01);
+        assert_eq!(LargeBlobsParam::Set as u8, 0x02);
+        assert_eq!(LargeBlobsParam::Offset as u8, 0x03);
+        assert_eq!(LargeBlobsParam::Length as u8, 0x04);
+        assert_eq!(LargeBlobsParam::PinUvAuthParam as u8, 0x05);
+        assert_eq!(LargeBlobsParam::PinUvAuthProtocol as u8, 0x06);
+        assert_eq!(LargeBlobsResponseParam::Config as u8, 0x01);
+        assert_eq!(LargeBlobEntryKey::OrigSize as u8, 0x03);
+    }
+
+    #[test]
+    fn test_hmac_secret_input_keys() {
+        assert_eq!(HmacSecretInputKey::KeyAgreement as u8, 0x01);
+        assert_eq!(HmacSecretInputKey::SaltEnc as u8, 0x02);
+        assert_eq!(HmacSecretInputKey::SaltAuth as u8, 0x03);
+        assert_eq!(HmacSecretInputKey::PinUvAuthProtocol as u8, 0x04);
+    }
+
     // ── U2F command codes ────────────────────────────────────────────────────
     // Reference: pico-fido src/fido/ctap.h: #define CTAP_REGISTER    0x01
     //                                               CTAP_AUTHENTICATE 0x02
@@ -1036,6 +1446,22 @@ mod tests {
         assert_eq!(CoseCurve::BP512R1 as u8, 11);
     }
 
+    #[test]
+    fn test_cose_curve_and_key_type_decode() {
+        for val in 1..=11 {
+            let crv = CoseCurve::from_i128(val).unwrap();
+            assert_eq!(crv as u8 as i128, val);
+        }
+        assert!(CoseCurve::from_i128(0).is_none());
+        assert_eq!(CoseCurve::P256.to_string(), "P-256");
+        assert_eq!(CoseCurve::P256K1.to_string(), "secp256k1");
+
+        assert_eq!(CoseKeyType::from_i128(2), Some(CoseKeyType::Ec2));
+        assert_eq!(CoseKeyType::from_i128(7), Some(CoseKeyType::Akp));
+        assert!(CoseKeyType::from_i128(5).is_none());
+        assert_eq!(CoseKeyType::Okp.to_string(), "OKP");
+    }
+
     // ── CTAP2 error codes ────────────────────────────────────────────────────
     // Reference: pico-fido src/fido/ctap.h: #define CTAP2_ERR_* defines
 
@@ -1078,6 +1504,24 @@ mod tests {
         assert_eq!(Ctap2Error::InvalidSubcommand as u8, 0x3E);
         assert_eq!(Ctap2Error::UvInvalid as u8, 0x3F);
         assert_eq!(Ctap2Error::UnauthorizedPermission as u8, 0x40);
+        assert_eq!(Ctap2Error::ChannelBusy as u8, 0x06);
+        assert_eq!(Ctap2Error::Other as u8, 0x7F);
+    }
+
+    #[test]
+    fn test_ctap2_error_round_trip_and_display() {
+        for code in 0..=u8::MAX {
+            if let Some(err) = Ctap2Error::from_u8(code) {
+                assert_eq!(err as u8, code);
+            }
+        }
+        assert_eq!(Ctap2Error::from_u8(0x31), Some(Ctap2Error::PinInvalid));
+        assert!(Ctap2Error::from_u8(0x13).is_none());
+        assert!(Ctap2Error::from_u8(0x80).is_none());
+        assert_eq!(
+            Ctap2Error::PinInvalid.to_string(),
+            "Incorrect PIN (CTAP error 0x31)"
+        );
     }
 
     // ── VendorCommand codes ──────────────────────────────────────────────────
@@ -1257,6 +1701,37 @@ mod tests {
         );
         assert_eq!(CredentialMgmtSubCommand::DeleteCredential as u8, 0x06);
         assert_eq!(CredentialMgmtSubCommand::UpdateUserInformation as u8, 0x07);
+        assert_eq!(CredentialMgmtSubCommandParam::CredentialId as u8, 0x02);
+        assert_eq!(CredentialMgmtSubCommandParam::User as u8, 0x03);
+    }
+
+    // ── Bio enrollment ───────────────────────────────────────────────────────
+
+    #[test]
+    fn test_bio_enrollment_values() {
+        assert_eq!(CtapCommand::BioEnrollment as u8, 0x09);
+        assert_eq!(CtapCommand::BioEnrollmentPreview as u8, 0x40);
+        assert_eq!(BioEnrollmentSubCommand::EnrollBegin as u8, 0x01);
+        assert_eq!(BioEnrollmentSubCommand::RemoveEnrollment as u8, 0x06);
+        assert_eq!(
+            BioEnrollmentSubCommand::GetFingerprintSensorInfo as u8,
+            0x07
+        );
+        assert_eq!(BioEnrollmentResponseParam::TemplateInfos as u8, 0x07);
+    }
+
+    #[test]
+    fn test_bio_sample_status_round_trip() {
+        for value in 0u8..=0x10 {
+            if let Some(status) = BioSampleStatus::from_u8(value) {
+                assert_eq!(status as u8, value);
+            }
+        }
+        assert_eq!(BioSampleStatus::from_u8(0x0C), None);
+        assert_eq!(
+            BioSampleStatus::from_u8(0x0E),
+            Some(BioSampleStatus::NoUserPresenceTransition)
+        );
     }
 
     // ── AAGUID ───────────────────────────────────────────────────────────────
@@ -0,0 +1,867 @@
+//! Test credentials for the diagnostics tools.
+//!
+//! Creates a credential under a throwaway RP ID with
+//! `authenticatorMakeCredential` and exercises it with
+//! `authenticatorGetAssertion`, so extension behaviour can be checked against
+//! what the firmware advertises in GetInfo. Nothing here is used for real
+//! logins; the client data hash is random. A `packed` attestation statement
+//! is verified against it locally, see [`super::attestation`].
+//!
+//! Extensions covered:
+//!
+//! - **credBlob** (CTAP 2.1 §12.2): up to `maxCredBlobLength` bytes stored
+//!   with the credential at creation. The authenticator reports `true`/`false`
+//!   in the creation output rather than failing when the blob is too long, and
+//!   returns the bytes for `getCredBlob` during assertions.
+//!
+//! The enterprise attestation certificate has no read command, so it is read
+//! back from a non-discoverable credential created with
+//! `enterpriseAttestation = 2` (platform-managed, CTAP 2.1 §6.1.2). When the
+//! key answers `epAtt: true`, the first `x5c` entry of the attestation
+//! statement is the uploaded certificate.
+//!
+//! The assertion sweep leaves the allow list empty, so every discoverable
+//! credential of the RP matches, and walks the rest with
+//! `authenticatorGetNextAssertion` (CTAP 2.1 §6.3) the way an account picker
+//! would.
+
+use super::attestation;
+use super::constants::*;
+use super::ops::FidoOperations;
+use crate::error::PFError;
+use crate::hal::common::x509;
+use crate::hal::transport::fido::{CTAPHID_CBOR, HidTransport, user_presence_timeout_ms};
+use crate::hal::types::{
+    AssertionSweep, AttestationCertInfo, SignInTest, TestAssertion, TestCredential,
+    TestCredentialRequest,
+};
+use ring::hmac;
+use ring::rand::{SecureRandom, SystemRandom};
+use serde_cbor_2::{Deserializer, Value, from_slice, to_vec};
+use std::collections::BTreeMap;
+
+/// Extension identifier for storing a blob at creation.
+pub const EXT_CRED_BLOB: &str = "credBlob";
+/// Extension identifier for reading the blob back in an assertion.
+pub const EXT_GET_CRED_BLOB: &str = "getCredBlob";
+
+/// `authenticatorGetAssertion` response key carrying the user entity.
+const GET_ASSERTION_USER: u8 = 0x04;
+/// `authenticatorGetAssertion` response key carrying `numberOfCredentials`.
+const GET_ASSERTION_NUMBER_OF_CREDENTIALS: u8 = 0x05;
+
+/// `authenticatorMakeCredential` response key carrying the attestation statement.
+const MAKE_CREDENTIAL_ATT_STMT: u8 = 0x03;
+/// `authenticatorMakeCredential` response key set when enterprise attestation was returned.
+const MAKE_CREDENTIAL_EP_ATT: u8 = 0x04;
+
+/// RP ID the enterprise attestation read-back registers under.
+const EA_PROBE_RP_ID: &str = "picoforge.test";
+
+/// Upper bound on GetNextAssertion calls, whatever the key reports.
+const MAX_SWEEP_ASSERTIONS: u32 = 256;
+
+/// Parsed `authenticatorData` (WebAuthn §6.1).
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct AuthData {
+    pub flags: u8,
+    pub sign_count: u32,
+    /// Authenticator model, from the attested credential data.
+    pub aaguid: Option<[u8; 16]>,
+    /// Present when the attested-credential-data flag is set.
+    pub credential_id: Option<Vec<u8>>,
+    /// COSE key of the new credential, with the attested credential data.
+    pub public_key: Option<Value>,
+    /// Extension outputs, keyed by identifier.
+    pub extensions: BTreeMap<String, Value>,
+}
+
+impl AuthData {
+    fn has_flag(&self, flag: AuthenticatorFlags) -> bool {
+        self.flags & flag.bits() != 0
+    }
+}
+
+/// Split `authenticatorData` into flags, counter, credential ID and extensions.
+pub(crate) fn parse_auth_data(data: &[u8]) -> Result<AuthData, PFError> {
+    if data.len() < 37 {
+        return Err(PFError::Io("authenticatorData is too short".into()));
+    }
+    let flags = data[32];
+    let sign_count = u32::from_be_bytes([data[33], data[34], data[35], data[36]]);
+    let mut rest = &data[37..];
+
+    let mut aaguid = None;
+    let mut credential_id = None;
+    if flags & AuthenticatorFlags::ATTESTED_CREDENTIAL_DATA.bits() != 0 {
+        // aaguid (16) | credentialIdLength (2) | credentialId
+        if rest.len() < 18 {
+            return Err(PFError::Io("Attested credential data is truncated".into()));
+        }
+        let id_len = u16::from_be_bytes([rest[16], rest[17]]) as usize;
+        let id = rest
+            .get(18..18 + id_len)
+            .ok_or_else(|| PFError::Io("Credential ID is truncated".into()))?;
+        aaguid = rest[..16].try_into().ok();
+        credential_id = Some(id.to_vec());
+        rest = &rest[18 + id_len..];
+    }
+
+    // The COSE public key and the extensions map follow as consecutive CBOR items.
+    let mut items = Deserializer::from_slice(rest).into_iter::<Value>();
+    let mut public_key = None;
+    if credential_id.is_some() {
+        public_key = Some(
+            items
+                .next()
+                .ok_or_else(|| PFError::Io("Credential public key is missing".into()))?
+                .map_err(|e| PFError::Io(format!("Invalid credential public key: {}", e)))?,
+        );
+    }
+
+    let mut extensions = BTreeMap::new();
+    if flags & AuthenticatorFlags::EXTENSION_DATA.bits() != 0 {
+        let map = items
+            .next()
+            .ok_or_else(|| PFError::Io("Extension data is missing".into()))?
+            .map_err(|e| PFError::Io(format!("Invalid extension data: {}", e)))?;
+        if let Value::Map(map) = map {
+            for (key, value) in map {
+                if let Value::Text(key) = key {
+                    extensions.insert(key, value);
+                }
+            }
+        }
+    }
+
+    Ok(AuthData {
+        flags,
+        sign_count,
+        aaguid,
+        credential_id,
+        public_key,
+        extensions,
+    })
+}
+
+pub(super) fn random_bytes<const N: usize>() -> Result<[u8; N], PFError> {
+    let mut buf = [0u8; N];
+    SystemRandom::new()
+        .fill(&mut buf)
+        .map_err(|_| PFError::Device("Could not generate random bytes".into()))?;
+    Ok(buf)
+}
+
+/// `pinUvAuthParam` for protocol 1: the first 16 bytes of HMAC-SHA-256 over
+/// the client data hash.
+pub(super) fn pin_uv_auth_param(pin_token: &[u8], client_data_hash: &[u8]) -> Vec<u8> {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, pin_token);
+    hmac::sign(&key, client_data_hash).as_ref()[..16].to_vec()
+}
+
+pub(super) fn text(value: &str) -> Value {
+    Value::Text(value.to_string())
+}
+
+pub(super) fn key(k: u8) -> Value {
+    Value::Integer(k as i128)
+}
+
+fn credential_descriptor(id: &[u8]) -> Value {
+    Value::Map(BTreeMap::from([
+        (text("type"), text("public-key")),
+        (text("id"), Value::Bytes(id.to_vec())),
+    ]))
+}
+
+/// Build the `authenticatorMakeCredential` request map.
+pub(crate) fn encode_make_credential(
+    request: &TestCredentialRequest,
+    client_data_hash: &[u8],
+    user_id: &[u8],
+    pin_auth: Option<Vec<u8>>,
+) -> Value {
+    let mut map = BTreeMap::new();
+    map.insert(
+        key(MakeCredentialParam::ClientDataHash as u8),
+        Value::Bytes(client_data_hash.to_vec()),
+    );
+    map.insert(
+        key(MakeCredentialParam::Rp as u8),
+        Value::Map(BTreeMap::from([
+            (text("id"), text(&request.rp_id)),
+            (text("name"), text(&request.rp_id)),
+        ])),
+    );
+    map.insert(
+        key(MakeCredentialParam::User as u8),
+        Value::Map(BTreeMap::from([
+            (text("id"), Value::Bytes(user_id.to_vec())),
+            (text("name"), text(&request.user_name)),
+            (text("displayName"), text(&request.user_name)),
+        ])),
+    );
+    map.insert(
+        key(MakeCredentialParam::PubKeyCredParams as u8),
+        Value::Array(
+            [CoseAlgorithm::ES256, CoseAlgorithm::EdDSA]
+                .iter()
+                .map(|alg| {
+                    Value::Map(BTreeMap::from([
+                        (text("alg"), Value::Integer(*alg as i128)),
+                        (text("type"), text("public-key")),
+                    ]))
+                })
+                .collect(),
+        ),
+    );
+    if let Some(blob) = &request.cred_blob {
+        map.insert(
+            key(MakeCredentialParam::Extensions as u8),
+            Value::Map(BTreeMap::from([(
+                text(EXT_CRED_BLOB),
+                Value::Bytes(blob.clone()),
+            )])),
+        );
+    }
+    map.insert(
+        key(MakeCredentialParam::Options as u8),
+        Value::Map(BTreeMap::from([(
+            text("rk"),
+            Value::Bool(request.resident_key),
+        )])),
+    );
+    if let Some(pin_auth) = pin_auth {
+        map.insert(
+            key(MakeCredentialParam::PinUvAuthParam as u8),
+            Value::Bytes(pin_auth),
+        );
+        map.insert(
+            key(MakeCredentialParam::PinUvAuthProtocol as u8),
+            Value::Integer(1),
+        );
+    }
+    if let Some(mode) = request.enterprise_attestation {
+        map.insert(
+            key(MakeCredentialParam::EnterpriseAttestation as u8),
+            Value::Integer(mode as i128),
+        );
+    }
+    Value::Map(map)
+}
+
+/// Build the `authenticatorGetAssertion` request map, asking for the boolean
+/// extension `extension` (`getCredBlob`, `largeBlobKey`). Without a
+/// `credential_id` the allow list is left out and any discoverable credential
+/// of the RP matches.
+pub(crate) fn encode_get_assertion(
+    rp_id: &str,
+    client_data_hash: &[u8],
+    credential_id: Option<&[u8]>,
+    extension: &str,
+    pin_auth: Option<Vec<u8>>,
+) -> Value {
+    let mut map = BTreeMap::new();
+    map.insert(key(GetAssertionParam::RpId as u8), text(rp_id));
+    map.insert(
+        key(GetAssertionParam::ClientDataHash as u8),
+        Value::Bytes(client_data_hash.to_vec()),
+    );
+    if let Some(credential_id) = credential_id {
+        map.insert(
+            key(GetAssertionParam::AllowList as u8),
+            Value::Array(vec![credential_descriptor(credential_id)]),
+        );
+    }
+    map.insert(
+        key(GetAssertionParam::Extensions as u8),
+        Value::Map(BTreeMap::from([(text(extension), Value::Bool(true))])),
+    );
+    map.insert(
+        key(GetAssertionParam::Options as u8),
+        Value::Map(BTreeMap::from([(text("up"), Value::Bool(true))])),
+    );
+    if let Some(pin_auth) = pin_auth {
+        map.insert(
+            key(GetAssertionParam::PinUvAuthParam as u8),
+            Value::Bytes(pin_auth),
+        );
+        map.insert(
+            key(GetAssertionParam::PinUvAuthProtocol as u8),
+            Value::Integer(1),
+        );
+    }
+    Value::Map(map)
+}
+
+pub(super) fn response_map(response: &[u8]) -> Result<BTreeMap<Value, Value>, PFError> {
+    match from_slice::<Value>(response) {
+        Ok(Value::Map(map)) => Ok(map),
+        Ok(_) => Err(PFError::Io("Response is not a CBOR map".into())),
+        Err(e) => Err(PFError::Io(format!("Invalid CBOR response: {}", e))),
+    }
+}
+
+fn auth_data_from(map: &BTreeMap<Value, Value>, k: u8) -> Result<AuthData, PFError> {
+    match map.get(&key(k)) {
+        Some(Value::Bytes(data)) => parse_auth_data(data),
+        _ => Err(PFError::Io("Response has no authenticatorData".into())),
+    }
+}
+
+pub(super) fn send_with_touch(
+    transport: &HidTransport,
+    command: CtapCommand,
+    request: &Value,
+) -> Result<Vec<u8>, PFError> {
+    let mut payload = vec![command as u8];
+    payload.extend(to_vec(request).map_err(|e| PFError::Io(e.to_string()))?);
+    transport.send_cbor_with_timeout(CTAPHID_CBOR, &payload, user_presence_timeout_ms())
+}
+
+/// Parse an `authenticatorMakeCredential` response to a request sent with
+/// `client_data_hash`, verifying a `packed` attestation statement.
+pub(crate) fn parse_make_credential(
+    rp_id: &str,
+    response: &[u8],
+    client_data_hash: &[u8],
+) -> Result<TestCredential, PFError> {
+    let map = response_map(response)?;
+    let attestation_format = match map.get(&key(0x01)) {
+        Some(Value::Text(fmt)) => fmt.clone(),
+        _ => String::new(),
+    };
+    let auth_data = auth_data_from(&map, 0x02)?;
+    let credential_id = auth_data
+        .credential_id
+        .as_ref()
+        .ok_or_else(|| PFError::Io("Response has no attested credential".into()))?;
+    let attestation = match (
+        attestation_format.as_str(),
+        map.get(&key(0x02)),
+        map.get(&key(MAKE_CREDENTIAL_ATT_STMT)),
+    ) {
+        ("packed", Some(Value::Bytes(raw)), Some(Value::Map(statement))) => {
+            Some(attestation::verify_packed(statement, raw, client_data_hash))
+        }
+        _ => None,
+    };
+
+    Ok(TestCredential {
+        rp_id: rp_id.to_string(),
+        credential_id: hex::encode(credential_id),
+        attestation_format,
+        sign_count: auth_data.sign_count,
+        user_verified: auth_data.has_flag(AuthenticatorFlags::USER_VERIFIED),
+        cred_blob_stored: match auth_data.extensions.get(EXT_CRED_BLOB) {
+            Some(Value::Bool(stored)) => Some(*stored),
+            _ => None,
+        },
+        attestation,
+    })
+}
+
+/// Parse an `authenticatorGetAssertion` response.
+pub(crate) fn parse_get_assertion(
+    credential_id: &[u8],
+    response: &[u8],
+) -> Result<TestAssertion, PFError> {
+    let map = response_map(response)?;
+    let auth_data = auth_data_from(&map, 0x02)?;
+    // The credential is optional in the response when the allow list had one entry.
+    let credential_id = match map.get(&key(0x01)) {
+        Some(Value::Map(descriptor)) => match descriptor.get(&text("id")) {
+            Some(Value::Bytes(id)) => id.clone(),
+            _ => credential_id.to_vec(),
+        },
+        _ => credential_id.to_vec(),
+    };
+
+    Ok(TestAssertion {
+        credential_id: hex::encode(credential_id),
+        sign_count: auth_data.sign_count,
+        user_present: auth_data.has_flag(AuthenticatorFlags::USER_PRESENT),
+        user_verified: auth_data.has_flag(AuthenticatorFlags::USER_VERIFIED),
+        user_name: match map.get(&key(GET_ASSERTION_USER)) {
+            Some(Value::Map(user)) => {
+                match (user.get(&text("name")), user.get(&text("displayName"))) {
+                    (Some(Value::Text(name)), _) | (None, Some(Value::Text(name))) => {
+                        Some(name.clone())
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        },
+        cred_blob: match auth_data.extensions.get(EXT_CRED_BLOB) {
+            Some(Value::Bytes(blob)) => Some(blob.clone()),
+            _ => None,
+        },
+    })
+}
+
+/// The enterprise attestation certificate from a MakeCredential response, or
+/// `None` when the key did not return enterprise attestation.
+pub(crate) fn parse_enterprise_attestation(
+    response: &[u8],
+) -> Result<Option<AttestationCertInfo>, PFError> {
+    let map = response_map(response)?;
+    if map.get(&key(MAKE_CREDENTIAL_EP_ATT)) != Some(&Value::Bool(true)) {
+        return Ok(None);
+    }
+    let certificate = match map.get(&key(MAKE_CREDENTIAL_ATT_STMT)) {
+        Some(Value::Map(statement)) => match statement.get(&text("x5c")) {
+            Some(Value::Array(chain)) => match chain.first() {
+                Some(Value::Bytes(der)) => der,
+                _ => return Err(PFError::Io("Attestation chain is empty".into())),
+            },
+            _ => return Err(PFError::Io("Attestation statement has no x5c".into())),
+        },
+        _ => return Err(PFError::Io("Response has no attestation statement".into())),
+    };
+    x509::summarize(certificate).map(Some)
+}
+
+/// Send `authenticatorMakeCredential` for `request`, returning the response
+/// and the client data hash it was sent with. Requires a touch; `pin` is
+/// needed once a PIN is set.
+fn make_credential(
+    request: &TestCredentialRequest,
+    pin: Option<&str>,
+) -> Result<(Vec<u8>, [u8; 32]), String> {
+    let transport =
+        HidTransport::open().map_err(|e| format!("Could not open HID transport: {}", e))?;
+
+    let client_data_hash = random_bytes::<32>().map_err(|e| e.to_string())?;
+    let user_id = random_bytes::<16>().map_err(|e| e.to_string())?;
+    let pin_auth = match pin {
+        Some(pin) => {
+            let token = transport
+                .get_pin_token_with_permission(
+                    pin,
+                    PinUvAuthTokenPermissions::MAKE_CREDENTIAL,
+                    Some(request.rp_id.clone()),
+                )
+                .map_err(|e| format!("Failed to get PIN token: {}", e))?;
+            Some(pin_uv_auth_param(&token, &client_data_hash))
+        }
+        None => None,
+    };
+
+    let body = encode_make_credential(request, &client_data_hash, &user_id, pin_auth);
+    let response = send_with_touch(&transport, CtapCommand::MakeCredential, &body)
+        .map_err(|e| format!("makeCredential failed: {}", e))?;
+    Ok((response, client_data_hash))
+}
+
+/// Create a test credential. Requires a touch; `pin` is needed once a PIN is set.
+pub(crate) fn create_test_credential(
+    request: TestCredentialRequest,
+    pin: Option<String>,
+) -> Result<TestCredential, String> {
+    log::info!(
+        "Creating test credential for {} (rk: {}, credBlob: {} bytes)",
+        request.rp_id,
+        request.resident_key,
+        request.cred_blob.as_ref().map_or(0, Vec::len)
+    );
+    let (response, client_data_hash) = make_credential(&request, pin.as_deref())?;
+    let credential = parse_make_credential(&request.rp_id, &response, &client_data_hash)
+        .map_err(|e| e.to_string())?;
+    log::info!(
+        "Test credential created ({} attestation, credBlob stored: {:?})",
+        credential.attestation_format,
+        credential.cred_blob_stored
+    );
+    if let Some(verification) = &credential.attestation {
+        for check in &verification.checks {
+            log::info!(
+                "Attestation check {}: {} ({})",
+                check.name,
+                if check.passed { "passed" } else { "FAILED" },
+                check.detail
+            );
+        }
+        if !verification.valid {
+            log::warn!("The packed attestation statement did not verify");
+        }
+    }
+    Ok(credential)
+}
+
+/// Read back the enterprise attestation certificate with a throwaway,
+/// non-discoverable registration. `None` when enterprise attestation is off
+/// or no certificate was uploaded.
+pub(crate) fn read_enterprise_attestation_cert(
+    pin: Option<String>,
+) -> Result<Option<AttestationCertInfo>, String> {
+    log::info!("Reading the enterprise attestation certificate back...");
+    let request = TestCredentialRequest {
+        rp_id: EA_PROBE_RP_ID.into(),
+        user_name: "picoforge-ea-check".into(),
+        resident_key: false,
+        cred_blob: None,
+        enterprise_attestation: Some(2),
+    };
+    let (response, _) = make_credential(&request, pin.as_deref())?;
+    let cert = parse_enterprise_attestation(&response).map_err(|e| e.to_string())?;
+    match &cert {
+        Some(cert) => log::info!(
+            "Enterprise attestation certificate: {} (serial {}, expires {})",
+            cert.subject,
+            cert.serial,
+            cert.not_after
+        ),
+        None => log::info!("The key did not return enterprise attestation"),
+    }
+    Ok(cert)
+}
+
+/// Get an assertion from a test credential, asking for its `credBlob`.
+pub(crate) fn get_test_assertion(
+    rp_id: String,
+    credential_id_hex: String,
+    pin: Option<String>,
+) -> Result<TestAssertion, String> {
+    let credential_id =
+        hex::decode(&credential_id_hex).map_err(|_| "Invalid credential ID".to_string())?;
+    let transport =
+        HidTransport::open().map_err(|e| format!("Could not open HID transport: {}", e))?;
+
+    let client_data_hash = random_bytes::<32>().map_err(|e| e.to_string())?;
+    let pin_auth = match &pin {
+        Some(pin) => {
+            let token = transport
+                .get_pin_token_with_permission(
+                    pin,
+                    PinUvAuthTokenPermissions::GET_ASSERTION,
+                    Some(rp_id.clone()),
+                )
+                .map_err(|e| format!("Failed to get PIN token: {}", e))?;
+            Some(pin_uv_auth_param(&token, &client_data_hash))
+        }
+        None => None,
+    };
+
+    let body = encode_get_assertion(
+        &rp_id,
+        &client_data_hash,
+        Some(&credential_id),
+        EXT_GET_CRED_BLOB,
+        pin_auth,
+    );
+    let response = send_with_touch(&transport, CtapCommand::GetAssertion, &body)
+        .map_err(|e| format!("getAssertion failed: {}", e))?;
+    parse_get_assertion(&credential_id, &response).map_err(|e| e.to_string())
+}
+
+/// Sign in with one stored passkey: GetAssertion with only `credential_id`
+/// in the allow list, verified with the passkey's stored COSE `public_key`
+/// (CBOR). Requires a touch; `pin` adds user verification, which passkeys
+/// created with `credProtect` level 3 need.
+pub(crate) fn test_sign_in(
+    rp_id: String,
+    credential_id_hex: String,
+    public_key: Vec<u8>,
+    pin: Option<String>,
+) -> Result<SignInTest, String> {
+    let credential_id =
+        hex::decode(&credential_id_hex).map_err(|_| "Invalid credential ID".to_string())?;
+    log::info!(
+        "Testing sign-in with passkey {} of {}",
+        &credential_id_hex[..credential_id_hex.len().min(16)],
+        rp_id
+    );
+    let transport =
+        HidTransport::open().map_err(|e| format!("Could not open HID transport: {}", e))?;
+
+    let client_data_hash = random_bytes::<32>().map_err(|e| e.to_string())?;
+    let pin_auth = match &pin {
+        Some(pin) => {
+            let token = transport
+                .get_pin_token_with_permission(
+                    pin,
+                    PinUvAuthTokenPermissions::GET_ASSERTION,
+                    Some(rp_id.clone()),
+                )
+                .map_err(|e| format!("Failed to get PIN token: {}", e))?;
+            Some(pin_uv_auth_param(&token, &client_data_hash))
+        }
+        None => None,
+    };
+
+    let mut body = encode_get_assertion(
+        &rp_id,
+        &client_data_hash,
+        Some(&credential_id),
+        EXT_GET_CRED_BLOB,
+        pin_auth,
+    );
+    // A plain sign-in: no extension outputs wanted.
+    if let Value::Map(map) = &mut body {
+        map.remove(&key(GetAssertionParam::Extensions as u8));
+    }
+    let response = send_with_touch(&transport, CtapCommand::GetAssertion, &body)
+        .map_err(|e| format!("getAssertion failed: {}", e))?;
+    let map = response_map(&response).map_err(|e| e.to_string())?;
+    let result =
+        attestation::verify_assertion(&rp_id, &credential_id, &public_key, &map, &client_data_hash)
+            .map_err(|e| e.to_string())?;
+    for check in &result.checks {
+        log::info!(
+            "Sign-in check {}: {} ({})",
+            check.name,
+            if check.passed { "passed" } else { "FAILED" },
+            check.detail
+        );
+    }
+    Ok(result)
+}
+
+/// `numberOfCredentials` of a GetAssertion response; 1 when the key leaves it
+/// out, which it does when only one credential matched.
+pub(crate) fn number_of_credentials(response: &[u8]) -> Result<u32, PFError> {
+    match response_map(response)?.get(&key(GET_ASSERTION_NUMBER_OF_CREDENTIALS)) {
+        Some(Value::Integer(n)) if *n >= 1 => Ok((*n).min(u32::MAX as i128) as u32),
+        Some(_) => Err(PFError::Io("Invalid numberOfCredentials".into())),
+        None => Ok(1),
+    }
+}
+
+/// Get an assertion from every discoverable credential of `rp_id`: one
+/// touch for GetAssertion, then GetNextAssertion for each further credential
+/// the key reports.
+pub(crate) fn sweep_test_assertions(
+    rp_id: String,
+    pin: Option<String>,
+) -> Result<AssertionSweep, String> {
+    let transport =
+        HidTransport::open().map_err(|e| format!("Could not open HID transport: {}", e))?;
+
+    let client_data_hash = random_bytes::<32>().map_err(|e| e.to_string())?;
+    let pin_auth = match &pin {
+        Some(pin) => {
+            let token = transport
+                .get_pin_token_with_permission(
+                    pin,
+                    PinUvAuthTokenPermissions::GET_ASSERTION,
+                    Some(rp_id.clone()),
+                )
+                .map_err(|e| format!("Failed to get PIN token: {}", e))?;
+            Some(pin_uv_auth_param(&token, &client_data_hash))
+        }
+        None => None,
+    };
+
+    let body = encode_get_assertion(&rp_id, &client_data_hash, None, EXT_GET_CRED_BLOB, pin_auth);
+    let response = send_with_touch(&transport, CtapCommand::GetAssertion, &body)
+        .map_err(|e| format!("getAssertion failed: {}", e))?;
+    let reported = number_of_credentials(&response).map_err(|e| e.to_string())?;
+    let mut assertions = vec![parse_get_assertion(&[], &response).map_err(|e| e.to_string())?];
+    log::info!(
+        "Assertion sweep for {}: key reports {} credential(s)",
+        rp_id,
+        reported
+    );
+
+    let mut error = None;
+    for n in 1..reported.min(MAX_SWEEP_ASSERTIONS) {
+        let next = transport
+            .send_cbor(CTAPHID_CBOR, &[CtapCommand::GetNextAssertion as u8])
+            .and_then(|response| parse_get_assertion(&[], &response));
+        match next {
+            Ok(assertion) => assertions.push(assertion),
+            Err(e) => {
+                log::warn!("getNextAssertion {} of {} failed: {}", n + 1, reported, e);
+                error = Some(format!(
+                    "getNextAssertion {} of {} failed: {}",
+                    n + 1,
+                    reported,
+                    e
+                ));
+                break;
+            }
+        }
+    }
+
+    Ok(AssertionSweep {
+        reported,
+        assertions,
+        error,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn auth_data(flags: u8, tail: &[u8]) -> Vec<u8> {
+        let mut data = vec![0xAA; 32];
+        data.push(flags);
+        data.extend(7u32.to_be_bytes());
+        data.extend(tail);
+        data
+    }
+
+    fn extensions(entries: Vec<(&str, Value)>) -> Vec<u8> {
+        let map = entries
+            .into_iter()
+            .map(|(k, v)| (text(k), v))
+            .collect::<BTreeMap<_, _>>();
+        to_vec(&Value::Map(map)).unwrap()
+    }
+
+    #[test]
+    fn test_parse_attested_auth_data_with_extensions() {
+        let cose_key = to_vec(&Value::Map(BTreeMap::from([
+            (Value::Integer(1), Value::Integer(2)),
+            (Value::Integer(3), Value::Integer(-7)),
+        ])))
+        .unwrap();
+        let mut tail = vec![0u8; 16];
+        tail.extend(3u16.to_be_bytes());
+        tail.extend([1, 2, 3]);
+        tail.extend(cose_key);
+        tail.extend(extensions(vec![(EXT_CRED_BLOB, Value::Bool(true))]));
+
+        let parsed = parse_auth_data(&auth_data(0x01 | 0x04 | 0x40 | 0x80, &tail)).unwrap();
+        assert_eq!(parsed.sign_count, 7);
+        assert_eq!(parsed.credential_id, Some(vec![1, 2, 3]));
+        assert_eq!(parsed.extensions[EXT_CRED_BLOB], Value::Bool(true));
+    }
+
+    #[test]
+    fn test_parse_assertion_returns_cred_blob() {
+        let tail = extensions(vec![(EXT_CRED_BLOB, Value::Bytes(b"hello".to_vec()))]);
+        let response = to_vec(&Value::Map(BTreeMap::from([(
+            key(0x02),
+            Value::Bytes(auth_data(0x01 | 0x80, &tail)),
+        )])))
+        .unwrap();
+
+        let assertion = parse_get_assertion(&[9, 9], &response).unwrap();
+        assert_eq!(assertion.credential_id, "0909");
+        assert!(assertion.user_present);
+        assert!(!assertion.user_verified);
+        assert_eq!(assertion.cred_blob.as_deref(), Some(&b"hello"[..]));
+    }
+
+    #[test]
+    fn test_make_credential_request_carries_cred_blob() {
+        let request = TestCredentialRequest {
+            rp_id: "picoforge.test".into(),
+            user_name: "test".into(),
+            resident_key: true,
+            cred_blob: Some(vec![0x42; 4]),
+            enterprise_attestation: None,
+        };
+        let Value::Map(map) = encode_make_credential(&request, &[0; 32], &[1; 16], None) else {
+            unreachable!()
+        };
+        let Some(Value::Map(ext)) = map.get(&key(MakeCredentialParam::Extensions as u8)) else {
+            panic!("extensions missing")
+        };
+        assert_eq!(ext[&text(EXT_CRED_BLOB)], Value::Bytes(vec![0x42; 4]));
+        assert!(!map.contains_key(&key(MakeCredentialParam::PinUvAuthParam as u8)));
+    }
+
+    #[test]
+    fn test_parse_discoverable_assertion() {
+        let response = to_vec(&Value::Map(BTreeMap::from([
+            (key(0x01), credential_descriptor(&[0xAB, 0xCD])),
+            (key(0x02), Value::Bytes(auth_data(0x01, &[]))),
+            (
+                key(GET_ASSERTION_USER),
+                Value::Map(BTreeMap::from([
+                    (text("id"), Value::Bytes(vec![1])),
+                    (text("name"), text("alice")),
+                ])),
+            ),
+            (key(GET_ASSERTION_NUMBER_OF_CREDENTIALS), Value::Integer(3)),
+        ])))
+        .unwrap();
+
+        let assertion = parse_get_assertion(&[], &response).unwrap();
+        assert_eq!(assertion.credential_id, "abcd");
+        assert_eq!(assertion.user_name.as_deref(), Some("alice"));
+        assert_eq!(number_of_credentials(&response).unwrap(), 3);
+    }
+
+    #[test]
+    fn test_number_of_credentials_defaults_to_one() {
+        let response = to_vec(&Value::Map(BTreeMap::from([(
+            key(0x02),
+            Value::Bytes(auth_data(0x01, &[])),
+        )])))
+        .unwrap();
+        assert_eq!(number_of_credentials(&response).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_discoverable_request_has_no_allow_list() {
+        let Value::Map(map) = encode_get_assertion("picoforge.test", &[0; 32], None, "x", None)
+        else {
+            unreachable!()
+        };
+        assert!(!map.contains_key(&key(GetAssertionParam::AllowList as u8)));
+    }
+
+    #[test]
+    fn test_short_auth_data_is_rejected() {
+        assert!(parse_auth_data(&[0; 36]).is_err());
+    }
+
+    #[test]
+    fn test_parse_enterprise_attestation() {
+        let response = |entries: Vec<(u8, Value)>| {
+            let map = entries
+                .into_iter()
+                .map(|(k, v)| (key(k), v))
+                .collect::<BTreeMap<_, _>>();
+            to_vec(&Value::Map(map)).unwrap()
+        };
+        let statement = |x5c: Vec<Value>| {
+            Value::Map(BTreeMap::from([
+                (text("alg"), Value::Integer(-7)),
+                (text("x5c"), Value::Array(x5c)),
+            ]))
+        };
+
+        // Regular attestation: the batch certificate is not the EA one.
+        let regular = response(vec![(0x01, text("packed")), (0x03, statement(vec![]))]);
+        assert_eq!(parse_enterprise_attestation(&regular).unwrap(), None);
+
+        let empty_chain = response(vec![
+            (MAKE_CREDENTIAL_ATT_STMT, statement(vec![])),
+            (MAKE_CREDENTIAL_EP_ATT, Value::Bool(true)),
+        ]);
+        assert!(parse_enterprise_attestation(&empty_chain).is_err());
+
+        let not_der = response(vec![
+            (
+                MAKE_CREDENTIAL_ATT_STMT,
+                statement(vec![Value::Bytes(vec![1, 2, 3])]),
+            ),
+            (MAKE_CREDENTIAL_EP_ATT, Value::Bool(true)),
+        ]);
+        assert!(parse_enterprise_attestation(&not_der).is_err());
+    }
+
+    #[test]
+    fn test_make_credential_request_carries_enterprise_attestation() {
+        let request = TestCredentialRequest {
+            rp_id: EA_PROBE_RP_ID.into(),
+            user_name: "test".into(),
+            resident_key: false,
+            cred_blob: None,
+            enterprise_attestation: Some(2),
+        };
+        let Value::Map(map) = encode_make_credential(&request, &[0; 32], &[1; 16], None) else {
+            unreachable!()
+        };
+        assert_eq!(
+            map[&key(MakeCredentialParam::EnterpriseAttestation as u8)],
+            Value::Integer(2)
+        );
+    }
+}
@@ -0,0 +1,324 @@
+//! Key derivation with the hmac-secret extension (CTAP 2.1 §12.5).
+//!
+//! A credential created with `hmac-secret` holds a random secret that never
+//! leaves the key. In a `getAssertion` the platform sends a 32-byte salt,
+//! encrypted under a shared secret from the PIN protocol key agreement, and
+//! the key answers with `HMAC-SHA-256(credRandom, salt)`, encrypted the same
+//! way. The same credential and salt always give the same output, so it can
+//! serve as an encryption key that only exists while the key is plugged in
+//! and touched. [`crate::vault`] uses it for the notes vault.
+//!
+//! The key holds two secrets per credential, one for assertions with user
+//! verification and one without. Both calls here always send a PIN token so
+//! the output is the same from one unlock to the next.
+
+use super::constants::*;
+use super::diag::{
+    encode_get_assertion, encode_make_credential, key, parse_auth_data, pin_uv_auth_param,
+    random_bytes, response_map, send_with_touch, text,
+};
+use super::ops::FidoOperations;
+use crate::error::PFError;
+use crate::hal::transport::fido::HidTransport;
+use crate::hal::types::TestCredentialRequest;
+use cbc::cipher::{Block, BlockModeDecrypt, BlockModeEncrypt, KeyIvInit, block_padding::NoPadding};
+use ring::{agreement, digest};
+use serde_cbor_2::{Value, from_slice};
+use std::collections::BTreeMap;
+
+/// Extension identifier, in GetInfo and in requests.
+pub const EXT_HMAC_SECRET: &str = "hmac-secret";
+
+/// Length of a salt and of the derived secret.
+pub const HMAC_SECRET_LEN: usize = 32;
+
+/// Shared secret of one key agreement, with the platform half to send.
+struct SharedSecret {
+    platform_key: Value,
+    secret: [u8; 32],
+}
+
+/// Run a key agreement with the authenticator (PIN protocol 1): ECDH on
+/// P-256, then `SHA-256` of the shared point's x-coordinate.
+fn key_agreement(transport: &HidTransport) -> Result<SharedSecret, PFError> {
+    let (x, y) = match transport.get_key_agreement()? {
+        Value::Map(m) => match (m.get(&Value::Integer(-2)), m.get(&Value::Integer(-3))) {
+            (Some(Value::Bytes(x)), Some(Value::Bytes(y))) => (x.clone(), y.clone()),
+            _ => return Err(PFError::Device("Invalid KeyAgreement coordinates".into())),
+        },
+        _ => return Err(PFError::Device("Invalid KeyAgreement format".into())),
+    };
+    let mut authenticator_key = vec![0x04];
+    authenticator_key.extend(x);
+    authenticator_key.extend(y);
+
+    let rng = ring::rand::SystemRandom::new();
+    let private_key = agreement::EphemeralPrivateKey::generate(&agreement::ECDH_P256, &rng)
+        .map_err(|_| PFError::Device("Failed to generate platform ephemeral key".into()))?;
+    let public_key = private_key
+        .compute_public_key()
+        .map_err(|_| PFError::Device("Failed to compute platform public key".into()))?;
+
+    let secret = agreement::agree_ephemeral(
+        private_key,
+        &agreement::UnparsedPublicKey::new(&agreement::ECDH_P256, authenticator_key),
+        |material| {
+            let mut secret = [0u8; 32];
+            secret.copy_from_slice(digest::digest(&digest::SHA256, material).as_ref());
+            secret
+        },
+    )
+    .map_err(|_| PFError::Device("ECDH shared secret computation failed".into()))?;
+
+    let cose_key =
+        transport.encode_cose_key(&public_key.as_ref()[1..33], &public_key.as_ref()[33..65]);
+    let platform_key = from_slice(&cose_key).map_err(|e| PFError::Io(e.to_string()))?;
+    Ok(SharedSecret {
+        platform_key,
+        secret,
+    })
+}
+
+/// AES-256-CBC with a zero IV, as PIN protocol 1 encrypts. `data` is a whole
+/// number of blocks.
+fn encrypt(secret: &[u8; 32], data: &[u8]) -> Vec<u8> {
+    let mut encryptor = cbc::Encryptor::<aes::Aes256>::new_from_slices(secret, &[0u8; 16])
+        .expect("32-byte key and 16-byte IV");
+    let mut out = Vec::with_capacity(data.len());
+    for chunk in data.chunks_exact(16) {
+        let mut block = Block::<aes::Aes256>::try_from(chunk).expect("16-byte block");
+        encryptor.encrypt_block(&mut block);
+        out.extend_from_slice(&block);
+    }
+    out
+}
+
+fn decrypt(secret: &[u8; 32], data: &[u8]) -> Result<Vec<u8>, PFError> {
+    let mut buf = data.to_vec();
+    cbc::Decryptor::<aes::Aes256>::new_from_slices(secret, &[0u8; 16])
+        .map_err(|_| PFError::Device("Failed to create decryptor".into()))?
+        .decrypt_padded::<NoPadding>(&mut buf)
+        .map(<[u8]>::to_vec)
+        .map_err(|_| PFError::Device("Failed to decrypt hmac-secret output".into()))
+}
+
+/// The `hmac-secret` getAssertion input for one salt.
+pub(crate) fn encode_input(shared: &[u8; 32], platform_key: Value, salt: &[u8; 32]) -> Value {
+    let salt_enc = encrypt(shared, salt);
+    let salt_auth = pin_uv_auth_param(shared, &salt_enc);
+    Value::Map(BTreeMap::from([
+        (key(HmacSecretInputKey::KeyAgreement as u8), platform_key),
+        (
+            key(HmacSecretInputKey::SaltEnc as u8),
+            Value::Bytes(salt_enc),
+        ),
+        (
+            key(HmacSecretInputKey::SaltAuth as u8),
+            Value::Bytes(salt_auth),
+        ),
+        (
+            key(HmacSecretInputKey::PinUvAuthProtocol as u8),
+            Value::Integer(1),
+        ),
+    ]))
+}
+
+/// Decrypt the `hmac-secret` output of a getAssertion response.
+pub(crate) fn parse_output(
+    shared: &[u8; 32],
+    response: &[u8],
+) -> Result<[u8; HMAC_SECRET_LEN], PFError> {
+    let map = response_map(response)?;
+    let auth_data = match map.get(&key(0x02)) {
+        Some(Value::Bytes(data)) => parse_auth_data(data)?,
+        _ => return Err(PFError::Io("Response has no authenticatorData".into())),
+    };
+    let encrypted = match auth_data.extensions.get(EXT_HMAC_SECRET) {
+        Some(Value::Bytes(encrypted)) => encrypted,
+        _ => {
+            return Err(PFError::Device(
+                "The key returned no hmac-secret output for this credential".into(),
+            ));
+        }
+    };
+    let output = decrypt(shared, encrypted)?;
+    output.try_into().map_err(|output: Vec<u8>| {
+        PFError::Io(format!(
+            "hmac-secret output is {} bytes, expected {}",
+            output.len(),
+            HMAC_SECRET_LEN
+        ))
+    })
+}
+
+/// Fail unless GetInfo lists `hmac-secret`.
+fn require_extension(transport: &HidTransport) -> Result<(), PFError> {
+    let info = super::read_device_info(transport)?;
+    if !info.extensions.iter().any(|e| e == EXT_HMAC_SECRET) {
+        return Err(PFError::Device(
+            "This key does not support the hmac-secret extension".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Create a discoverable credential with `hmac-secret` under `rp_id`.
+/// Waits for a touch. Returns the credential ID, hex-encoded.
+pub(crate) fn create_hmac_secret_credential(
+    rp_id: String,
+    user_name: String,
+    pin: String,
+) -> Result<String, String> {
+    let transport =
+        HidTransport::open().map_err(|e| format!("Could not open HID transport: {}", e))?;
+    require_extension(&transport).map_err(|e| e.to_string())?;
+
+    let client_data_hash = random_bytes::<32>().map_err(|e| e.to_string())?;
+    let user_id = random_bytes::<16>().map_err(|e| e.to_string())?;
+    let token = transport
+        .get_pin_token_with_permission(
+            &pin,
+            PinUvAuthTokenPermissions::MAKE_CREDENTIAL,
+            Some(rp_id.clone()),
+        )
+        .map_err(|e| format!("Failed to get PIN token: {}", e))?;
+
+    let request = TestCredentialRequest {
+        rp_id: rp_id.clone(),
+        user_name,
+        resident_key: true,
+        cred_blob: None,
+        enterprise_attestation: None,
+    };
+    let mut body = encode_make_credential(
+        &request,
+        &client_data_hash,
+        &user_id,
+        Some(pin_uv_auth_param(&token, &client_data_hash)),
+    );
+    if let Value::Map(map) = &mut body {
+        map.insert(
+            key(MakeCredentialParam::Extensions as u8),
+            Value::Map(BTreeMap::from([(text(EXT_HMAC_SECRET), Value::Bool(true))])),
+        );
+    }
+
+    let response = send_with_touch(&transport, CtapCommand::MakeCredential, &body)
+        .map_err(|e| format!("makeCredential failed: {}", e))?;
+    let map = response_map(&response).map_err(|e| e.to_string())?;
+    let auth_data = match map.get(&key(0x02)) {
+        Some(Value::Bytes(data)) => parse_auth_data(data).map_err(|e| e.to_string())?,
+        _ => return Err("Response has no authenticatorData".into()),
+    };
+    if auth_data.extensions.get(EXT_HMAC_SECRET) != Some(&Value::Bool(true)) {
+        return Err("The key created the credential without hmac-secret".into());
+    }
+    let credential_id = auth_data
+        .credential_id
+        .ok_or_else(|| "Response has no attested credential".to_string())?;
+    log::info!("Created hmac-secret credential under {}", rp_id);
+    Ok(hex::encode(credential_id))
+}
+
+/// Derive the secret for `salt` from a credential created by
+/// [`create_hmac_secret_credential`]. Waits for a touch.
+pub(crate) fn derive_hmac_secret(
+    rp_id: String,
+    credential_id_hex: String,
+    salt: [u8; HMAC_SECRET_LEN],
+    pin: String,
+) -> Result<[u8; HMAC_SECRET_LEN], String> {
+    let credential_id =
+        hex::decode(&credential_id_hex).map_err(|_| "Invalid credential ID".to_string())?;
+    let transport =
+        HidTransport::open().map_err(|e| format!("Could not open HID transport: {}", e))?;
+    require_extension(&transport).map_err(|e| e.to_string())?;
+
+    let client_data_hash = random_bytes::<32>().map_err(|e| e.to_string())?;
+    let token = transport
+        .get_pin_token_with_permission(
+            &pin,
+            PinUvAuthTokenPermissions::GET_ASSERTION,
+            Some(rp_id.clone()),
+        )
+        .map_err(|e| format!("Failed to get PIN token: {}", e))?;
+    let shared = key_agreement(&transport).map_err(|e| e.to_string())?;
+
+    let mut body = encode_get_assertion(
+        &rp_id,
+        &client_data_hash,
+        Some(&credential_id),
+        EXT_HMAC_SECRET,
+        Some(pin_uv_auth_param(&token, &client_data_hash)),
+    );
+    if let Value::Map(map) = &mut body {
+        map.insert(
+            key(GetAssertionParam::Extensions as u8),
+            Value::Map(BTreeMap::from([(
+                text(EXT_HMAC_SECRET),
+                encode_input(&shared.secret, shared.platform_key, &salt),
+            )])),
+        );
+    }
+
+    let response = send_with_touch(&transport, CtapCommand::GetAssertion, &body)
+        .map_err(|e| format!("getAssertion failed: {}", e))?;
+    parse_output(&shared.secret, &response).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_cbor_2::to_vec;
+
+    #[test]
+    fn test_cbc_round_trip() {
+        let secret = [0x11u8; 32];
+        let data = [0x5Au8; 32];
+        let encrypted = encrypt(&secret, &data);
+        assert_eq!(encrypted.len(), 32);
+        assert_ne!(encrypted, data);
+        assert_eq!(decrypt(&secret, &encrypted).unwrap(), data);
+    }
+
+    #[test]
+    fn test_input_is_authenticated_with_the_shared_secret() {
+        let shared = [0x22u8; 32];
+        let salt = [0x33u8; 32];
+        let Value::Map(input) = encode_input(&shared, Value::Null, &salt) else {
+            unreachable!()
+        };
+        let Some(Value::Bytes(salt_enc)) = input.get(&key(HmacSecretInputKey::SaltEnc as u8))
+        else {
+            panic!("saltEnc missing")
+        };
+        assert_eq!(decrypt(&shared, salt_enc).unwrap(), salt);
+        assert_eq!(
+            input[&key(HmacSecretInputKey::SaltAuth as u8)],
+            Value::Bytes(pin_uv_auth_param(&shared, salt_enc))
+        );
+    }
+
+    #[test]
+    fn test_parse_output_decrypts_extension() {
+        let shared = [0x44u8; 32];
+        let output = [0x55u8; 32];
+        let extensions = to_vec(&Value::Map(BTreeMap::from([(
+            text(EXT_HMAC_SECRET),
+            Value::Bytes(encrypt(&shared, &output)),
+        )])))
+        .unwrap();
+        let mut auth_data = vec![0u8; 32];
+        auth_data.push(0x01 | 0x04 | 0x80);
+        auth_data.extend(1u32.to_be_bytes());
+        auth_data.extend(extensions);
+        let response = to_vec(&Value::Map(BTreeMap::from([(
+            key(0x02),
+            Value::Bytes(auth_data),
+        )])))
+        .unwrap();
+
+        assert_eq!(parse_output(&shared, &response).unwrap(), output);
+        assert_ne!(parse_output(&[0x45u8; 32], &response).unwrap(), output);
+    }
+}
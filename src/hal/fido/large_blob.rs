@@ -0,0 +1,439 @@
+//! Per-credential large blobs (CTAP 2.1 §6.10, §12.3).
+//!
+//! The authenticator keeps one opaque array, read and written in fragments
+//! with `authenticatorLargeBlobs`. Its serialized form is a CBOR array
+//! followed by the first 16 bytes of the array's SHA-256; a mismatching
+//! trailer means the array is treated as empty.
+//!
+//! Each entry belongs to one credential but does not say which. The platform
+//! asks for the credential's `largeBlobKey` with a `getAssertion` (one touch),
+//! then tries that key on every entry: the entry that opens is the
+//! credential's blob. Entries are `{1: ciphertext, 2: nonce, 3: origSize}`,
+//! encrypted with AES-256-GCM over `"blob" || uint64LE(origSize)` and
+//! DEFLATE-compressed before encryption.
+//!
+//! Writes replace the whole array. Entries that do not open with the key are
+//! kept as they are, so blobs of other credentials survive.
+
+use super::constants::*;
+use super::diag::{
+    encode_get_assertion, key, pin_uv_auth_param, random_bytes, response_map, send_with_touch,
+};
+use super::ops::FidoOperations;
+use crate::error::PFError;
+use crate::hal::transport::fido::HidTransport;
+use crate::hal::types::LargeBlobInfo;
+use flate2::Compression;
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use ring::{aead, digest};
+use serde_cbor_2::{Value, from_slice, to_vec};
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+
+/// Extension identifier that returns the credential's `largeBlobKey`.
+pub const EXT_LARGE_BLOB_KEY: &str = "largeBlobKey";
+
+/// `authenticatorGetAssertion` response key carrying the `largeBlobKey`.
+const GET_ASSERTION_LARGE_BLOB_KEY: u8 = 0x07;
+
+/// Serialized empty array: `0x80` and the truncated SHA-256 of it.
+pub const EMPTY_ARRAY: [u8; 17] = [
+    0x80, 0x76, 0xbe, 0x8b, 0x52, 0x8d, 0x00, 0x75, 0xf7, 0xaa, 0xe9, 0x8d, 0x6f, 0xa5, 0x7a, 0x6d,
+    0x3c,
+];
+
+fn trailer(array: &[u8]) -> [u8; LARGE_BLOB_TRAILER_LEN] {
+    let mut out = [0u8; LARGE_BLOB_TRAILER_LEN];
+    out.copy_from_slice(&digest::digest(&digest::SHA256, array).as_ref()[..LARGE_BLOB_TRAILER_LEN]);
+    out
+}
+
+/// The CBOR part of a serialized array, or `None` when the trailer does not
+/// match.
+pub(crate) fn split_serialized(serialized: &[u8]) -> Option<&[u8]> {
+    let split = serialized.len().checked_sub(LARGE_BLOB_TRAILER_LEN)?;
+    let (array, stored) = serialized.split_at(split);
+    (stored == trailer(array)).then_some(array)
+}
+
+/// Parse the entries of a validated array. Entries are kept as raw CBOR so
+/// ones that belong to other credentials can be written back unchanged.
+pub(crate) fn parse_entries(array: &[u8]) -> Result<Vec<Value>, PFError> {
+    match from_slice::<Value>(array) {
+        Ok(Value::Array(entries)) => Ok(entries),
+        Ok(_) => Err(PFError::Io("Large-blob array is not a CBOR array".into())),
+        Err(e) => Err(PFError::Io(format!("Invalid large-blob array: {}", e))),
+    }
+}
+
+/// Serialize `entries` and append the trailer.
+pub(crate) fn serialize(entries: &[Value]) -> Result<Vec<u8>, PFError> {
+    let mut serialized =
+        to_vec(&Value::Array(entries.to_vec())).map_err(|e| PFError::Io(e.to_string()))?;
+    let trailer = trailer(&serialized);
+    serialized.extend(trailer);
+    Ok(serialized)
+}
+
+fn associated_data(orig_size: u64) -> Vec<u8> {
+    let mut aad = b"blob".to_vec();
+    aad.extend(orig_size.to_le_bytes());
+    aad
+}
+
+fn gcm_key(large_blob_key: &[u8]) -> Result<aead::LessSafeKey, PFError> {
+    aead::UnboundKey::new(&aead::AES_256_GCM, large_blob_key)
+        .map(aead::LessSafeKey::new)
+        .map_err(|_| PFError::Device("largeBlobKey is not 32 bytes".into()))
+}
+
+/// Decrypt and inflate `entry` with `large_blob_key`.
+///
+/// `Ok(None)` means the entry belongs to another credential. An entry that
+/// opens but does not inflate to `origSize` bytes is an error.
+pub(crate) fn open_entry(large_blob_key: &[u8], entry: &Value) -> Result<Option<Vec<u8>>, PFError> {
+    let Value::Map(map) = entry else {
+        return Ok(None);
+    };
+    let (
+        Some(Value::Bytes(ciphertext)),
+        Some(Value::Bytes(nonce)),
+        Some(Value::Integer(orig_size)),
+    ) = (
+        map.get(&key(LargeBlobEntryKey::Ciphertext as u8)),
+        map.get(&key(LargeBlobEntryKey::Nonce as u8)),
+        map.get(&key(LargeBlobEntryKey::OrigSize as u8)),
+    )
+    else {
+        return Ok(None);
+    };
+    let (Ok(nonce), Ok(orig_size)) = (
+        aead::Nonce::try_assume_unique_for_key(nonce),
+        u64::try_from(*orig_size),
+    ) else {
+        return Ok(None);
+    };
+
+    let mut in_out = ciphertext.clone();
+    let Ok(compressed) = gcm_key(large_blob_key)?.open_in_place(
+        nonce,
+        aead::Aad::from(associated_data(orig_size)),
+        &mut in_out,
+    ) else {
+        return Ok(None);
+    };
+
+    let mut data = Vec::new();
+    DeflateDecoder::new(&compressed[..])
+        .take(orig_size.saturating_add(1))
+        .read_to_end(&mut data)
+        .map_err(|e| PFError::Io(format!("Large blob does not inflate: {}", e)))?;
+    if data.len() as u64 != orig_size {
+        return Err(PFError::Io(format!(
+            "Large blob inflates to {} bytes, expected {}",
+            data.len(),
+            orig_size
+        )));
+    }
+    Ok(Some(data))
+}
+
+/// Compress and encrypt `data` into a new entry for `large_blob_key`.
+pub(crate) fn seal_entry(large_blob_key: &[u8], data: &[u8]) -> Result<Value, PFError> {
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(data)
+        .map_err(|e| PFError::Io(e.to_string()))?;
+    let mut in_out = encoder.finish().map_err(|e| PFError::Io(e.to_string()))?;
+
+    let nonce = random_bytes::<12>()?;
+    let orig_size = data.len() as u64;
+    gcm_key(large_blob_key)?
+        .seal_in_place_append_tag(
+            aead::Nonce::assume_unique_for_key(nonce),
+            aead::Aad::from(associated_data(orig_size)),
+            &mut in_out,
+        )
+        .map_err(|_| PFError::Device("Could not encrypt large blob".into()))?;
+
+    Ok(Value::Map(BTreeMap::from([
+        (
+            key(LargeBlobEntryKey::Ciphertext as u8),
+            Value::Bytes(in_out),
+        ),
+        (
+            key(LargeBlobEntryKey::Nonce as u8),
+            Value::Bytes(nonce.to_vec()),
+        ),
+        (
+            key(LargeBlobEntryKey::OrigSize as u8),
+            Value::Integer(orig_size as i128),
+        ),
+    ])))
+}
+
+/// The array as stored: entries, serialized size and whether the trailer held.
+struct StoredArray {
+    entries: Vec<Value>,
+    used_bytes: usize,
+    trailer_valid: bool,
+}
+
+fn read_array(transport: &HidTransport, max_fragment: usize) -> Result<StoredArray, PFError> {
+    let serialized = transport.large_blob_read(max_fragment)?;
+    let used_bytes = serialized.len();
+    match split_serialized(&serialized) {
+        Some(array) => Ok(StoredArray {
+            entries: parse_entries(array)?,
+            used_bytes,
+            trailer_valid: true,
+        }),
+        None => {
+            log::warn!(
+                "Large-blob array trailer does not match ({} bytes); treating it as empty",
+                used_bytes
+            );
+            Ok(StoredArray {
+                entries: Vec::new(),
+                used_bytes,
+                trailer_valid: false,
+            })
+        }
+    }
+}
+
+/// Fragment size and array capacity from GetInfo. Fails when the key does not
+/// advertise `largeBlobs`.
+fn limits(transport: &HidTransport) -> Result<(usize, Option<usize>), PFError> {
+    let info = super::read_device_info(transport)?;
+    if info.options.get("largeBlobs") != Some(&true) {
+        return Err(PFError::Device(
+            "This key does not support large blobs".into(),
+        ));
+    }
+    let max_fragment = match usize::try_from(info.max_msg_size) {
+        Ok(size) if size > 64 => size - 64,
+        _ => MAX_FRAGMENT_LENGTH,
+    };
+    let capacity = info
+        .max_serialized_large_blob_array
+        .and_then(|max| usize::try_from(max).ok());
+    Ok((max_fragment, capacity))
+}
+
+/// Get the credential's `largeBlobKey` with a `getAssertion`; waits for a touch.
+fn large_blob_key(
+    transport: &HidTransport,
+    rp_id: &str,
+    credential_id: &[u8],
+    pin: Option<&str>,
+) -> Result<Vec<u8>, PFError> {
+    let client_data_hash = random_bytes::<32>()?;
+    let pin_auth = match pin {
+        Some(pin) => {
+            let token = transport.get_pin_token_with_permission(
+                pin,
+                PinUvAuthTokenPermissions::GET_ASSERTION,
+                Some(rp_id.to_string()),
+            )?;
+            Some(pin_uv_auth_param(&token, &client_data_hash))
+        }
+        None => None,
+    };
+    let body = encode_get_assertion(
+        rp_id,
+        &client_data_hash,
+        Some(credential_id),
+        EXT_LARGE_BLOB_KEY,
+        pin_auth,
+    );
+    let response = send_with_touch(transport, CtapCommand::GetAssertion, &body)?;
+    match response_map(&response)?.remove(&key(GET_ASSERTION_LARGE_BLOB_KEY)) {
+        Some(Value::Bytes(k)) => Ok(k),
+        _ => Err(PFError::Device(
+            "This passkey was created without a largeBlobKey".into(),
+        )),
+    }
+}
+
+/// Find the entry that opens with `large_blob_key`, if any.
+fn find_blob(large_blob_key: &[u8], entries: &[Value]) -> Result<Option<Vec<u8>>, PFError> {
+    for entry in entries {
+        if let Some(data) = open_entry(large_blob_key, entry)? {
+            return Ok(Some(data));
+        }
+    }
+    Ok(None)
+}
+
+/// Read the array summary without touching any credential (no PIN, no touch).
+pub(crate) fn read_large_blob_array() -> Result<LargeBlobInfo, String> {
+    let transport =
+        HidTransport::open().map_err(|e| format!("Could not open HID transport: {}", e))?;
+    let (max_fragment, capacity) = limits(&transport).map_err(|e| e.to_string())?;
+    let stored = read_array(&transport, max_fragment)
+        .map_err(|e| format!("Failed to read large blobs: {}", e))?;
+    Ok(LargeBlobInfo {
+        entries: stored.entries.len(),
+        used_bytes: stored.used_bytes,
+        capacity,
+        trailer_valid: stored.trailer_valid,
+        blob: None,
+    })
+}
+
+/// Read the blob of one credential. Waits for a touch; `pin` is needed once a
+/// PIN is set.
+pub(crate) fn read_large_blob(
+    rp_id: String,
+    credential_id_hex: String,
+    pin: Option<String>,
+) -> Result<LargeBlobInfo, String> {
+    let credential_id =
+        hex::decode(&credential_id_hex).map_err(|_| "Invalid credential ID".to_string())?;
+    let transport =
+        HidTransport::open().map_err(|e| format!("Could not open HID transport: {}", e))?;
+    let (max_fragment, capacity) = limits(&transport).map_err(|e| e.to_string())?;
+
+    let large_blob_key = large_blob_key(&transport, &rp_id, &credential_id, pin.as_deref())
+        .map_err(|e| format!("Could not get largeBlobKey: {}", e))?;
+    let stored = read_array(&transport, max_fragment)
+        .map_err(|e| format!("Failed to read large blobs: {}", e))?;
+    let blob = find_blob(&large_blob_key, &stored.entries).map_err(|e| e.to_string())?;
+    log::info!(
+        "Read large blob for {}: {}",
+        rp_id,
+        blob.as_ref()
+            .map_or("none stored".to_string(), |b| format!("{} bytes", b.len()))
+    );
+
+    Ok(LargeBlobInfo {
+        entries: stored.entries.len(),
+        used_bytes: stored.used_bytes,
+        capacity,
+        trailer_valid: stored.trailer_valid,
+        blob,
+    })
+}
+
+/// Store `data` as the blob of one credential, replacing any previous one.
+/// Empty `data` removes the blob. Waits for a touch and needs the PIN.
+pub(crate) fn write_large_blob(
+    rp_id: String,
+    credential_id_hex: String,
+    data: Vec<u8>,
+    pin: String,
+) -> Result<LargeBlobInfo, String> {
+    let credential_id =
+        hex::decode(&credential_id_hex).map_err(|_| "Invalid credential ID".to_string())?;
+    let transport =
+        HidTransport::open().map_err(|e| format!("Could not open HID transport: {}", e))?;
+    let (max_fragment, capacity) = limits(&transport).map_err(|e| e.to_string())?;
+
+    let large_blob_key = large_blob_key(&transport, &rp_id, &credential_id, Some(&pin))
+        .map_err(|e| format!("Could not get largeBlobKey: {}", e))?;
+    let stored = read_array(&transport, max_fragment)
+        .map_err(|e| format!("Failed to read large blobs: {}", e))?;
+
+    let mut entries = Vec::with_capacity(stored.entries.len() + 1);
+    for entry in stored.entries {
+        let ours = open_entry(&large_blob_key, &entry)
+            .map_err(|e| e.to_string())?
+            .is_some();
+        if !ours {
+            entries.push(entry);
+        }
+    }
+    if !data.is_empty() {
+        entries.push(seal_entry(&large_blob_key, &data).map_err(|e| e.to_string())?);
+    }
+    let serialized = serialize(&entries).map_err(|e| e.to_string())?;
+    if let Some(capacity) = capacity
+        && serialized.len() > capacity
+    {
+        return Err(format!(
+            "Large-blob storage is full: the new array needs {} bytes, the key holds {}",
+            serialized.len(),
+            capacity
+        ));
+    }
+
+    let token = transport
+        .get_pin_token_with_permission(&pin, PinUvAuthTokenPermissions::LARGE_BLOB_WRITE, None)
+        .map_err(|e| format!("Failed to get PIN token: {}", e))?;
+    transport
+        .large_blob_write(&token, &serialized, max_fragment)
+        .map_err(|e| format!("Failed to write large blobs: {}", e))?;
+    log::info!(
+        "Large blob for {} {} ({} entries, {} bytes)",
+        rp_id,
+        if data.is_empty() { "removed" } else { "stored" },
+        entries.len(),
+        serialized.len()
+    );
+
+    Ok(LargeBlobInfo {
+        entries: entries.len(),
+        used_bytes: serialized.len(),
+        capacity,
+        trailer_valid: true,
+        blob: (!data.is_empty()).then_some(data),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_array_trailer() {
+        assert_eq!(serialize(&[]).unwrap(), EMPTY_ARRAY);
+        assert_eq!(split_serialized(&EMPTY_ARRAY), Some(&[0x80][..]));
+    }
+
+    #[test]
+    fn test_bad_trailer_is_rejected() {
+        let mut serialized = EMPTY_ARRAY;
+        serialized[16] ^= 0x01;
+        assert_eq!(split_serialized(&serialized), None);
+        assert_eq!(split_serialized(&[0x80]), None);
+    }
+
+    #[test]
+    fn test_entry_round_trip() {
+        let key = [0x42u8; 32];
+        let data = b"hello large blob, hello large blob".to_vec();
+        let entry = seal_entry(&key, &data).unwrap();
+
+        assert_eq!(open_entry(&key, &entry).unwrap(), Some(data.clone()));
+        assert_eq!(open_entry(&[0x24u8; 32], &entry).unwrap(), None);
+
+        let serialized = serialize(std::slice::from_ref(&entry)).unwrap();
+        let entries = parse_entries(split_serialized(&serialized).unwrap()).unwrap();
+        assert_eq!(find_blob(&key, &entries).unwrap(), Some(data));
+    }
+
+    #[test]
+    fn test_wrong_orig_size_does_not_open() {
+        // origSize is part of the associated data, so editing it breaks the tag.
+        let key = [0x42u8; 32];
+        let Value::Map(mut map) = seal_entry(&key, b"abc").unwrap() else {
+            unreachable!()
+        };
+        map.insert(
+            super::key(LargeBlobEntryKey::OrigSize as u8),
+            Value::Integer(4),
+        );
+        assert_eq!(open_entry(&key, &Value::Map(map)).unwrap(), None);
+    }
+
+    #[test]
+    fn test_foreign_entries_are_skipped() {
+        assert_eq!(open_entry(&[0u8; 32], &Value::Integer(1)).unwrap(), None);
+        assert_eq!(
+            open_entry(&[0u8; 32], &Value::Map(BTreeMap::new())).unwrap(),
+            None
+        );
+    }
+}
@@ -3,8 +3,18 @@
 //! ```text
 //! fido/
 //! ├── mod.rs       — high-level FIDO2 operations (info, PIN, credentials, config)
+//! ├── attestation.rs — local verification of packed attestation statements
+//! ├── backup.rs    — encrypted device backup and restore (vendor Backup command)
+//! ├── bio.rs       — fingerprint enrollment (authenticatorBioEnrollment)
 //! ├── constants.rs — CTAP2 command codes, CBOR map keys, COSE algorithms, bitflags
-//! └── ops.rs       — FidoOperations trait impl (CTAPHID framing, PIN, credential mgmt)
+//! ├── diag.rs      — test credentials (makeCredential/getAssertion) for diagnostics
+//! ├── hmac_secret.rs — hmac-secret credentials and secret derivation (notes vault)
+//! ├── large_blob.rs — per-credential large blobs (fragmenting, trailer, largeBlobKey)
+//! ├── mse.rs       — MSE key agreement and the secure channel it sets up
+//! ├── ops.rs       — FidoOperations trait impl (CTAPHID framing, PIN, credential mgmt)
+//! ├── pin.rs       — PIN normalization and length rules
+//! ├── reference.rs — searchable CTAP2 reference for keys, options and error codes
+//! └── secure_lock.rs — secure lock enable/disable and unlock over the MSE channel
 //! ```
 //!
 //! # Architecture
@@ -34,8 +44,9 @@
 //! agreement, and CBOR serialization.
 //!
 //! This module contains the public functions called from [`super::io`].
-//! Each function opens an [`HidTransport`], performs the CTAP2 operation,
-//! and parses the CBOR response into the structs defined in [`super::types`].
+//! Each function takes the shared [`HidTransport`] connection, performs the
+//! CTAP2 operation, and parses the CBOR response into the structs defined in
+//! [`super::types`].
 //!
 //! # Vendor extensions
 //!
@@ -54,20 +65,34 @@
 //!    open transport → build CBOR payload → send → parse response → return.
 //! 4. Expose it through [`super::io`].
 
+pub mod attestation;
+pub mod backup;
+pub mod bio;
 pub mod constants;
+pub mod diag;
+pub mod hmac_secret;
+pub mod large_blob;
+pub mod mse;
 pub mod ops;
+pub mod pin;
+pub mod reference;
+pub mod secure_lock;
 use crate::hal::transport::fido::{CTAPHID_CBOR, HidTransport};
 
 use crate::{
     error::PFError,
     hal::{
+        config_plan::{AppliedPlan, ConfigOperation, ConfigPlan, WritePath},
         firmwares::AnyFirmware,
+        rescue::constants::{RescueCurves, RescueOptions},
         types::{
-            AppConfig, AppConfigInput, DeviceInfo, DeviceMethod, FidoDeviceInfo, FirmwareType,
-            FullDeviceStatus, LKONE_AAGUID, LedStatusConfig, PICOFIDO_AAGUID, RSKEY_AAGUID,
-            StoredCredential,
+            AppConfig, AppConfigInput, CredProtectPolicy, CredentialExportFormat,
+            CredentialsMetadata, DeviceInfo, DeviceMethod, FidoDeviceInfo, FirmwareType,
+            FullDeviceStatus, LKONE_AAGUID, LedStatusConfig, PICOFIDO_AAGUID, PinLockout,
+            PinRetries, PinStatus, RSKEY_AAGUID, StoredCredential, UnknownGetInfoField,
         },
     },
+    tunables::{self, VendorRead},
 };
 use base64::{Engine as _, engine::general_purpose};
 use constants::*;
@@ -107,7 +132,7 @@ pub(crate) fn get_fido_info() -> Result<FidoDeviceInfo, String> {
 
     let info_payload = [CtapCommand::GetInfo as u8];
     let info_response = transport
-        .send_cbor(CTAPHID_CBOR, &info_payload)
+        .send_cbor_read(CTAPHID_CBOR, &info_payload)
         .map_err(|e| format!("GetInfo CTAP command failed: {}", e))?;
 
     let info_value: Value =
@@ -152,6 +177,8 @@ fn parse_fido_get_info(info_value: &Value) -> Result<FidoDeviceInfo, String> {
     let mut max_serialized_large_blob_array = None;
     let mut force_pin_change = None;
     let mut max_cred_blob_length = None;
+    let mut max_rpids_for_min_pin_length = None;
+    let mut unknown_fields = Vec::new();
 
     for (key, val) in map {
         let key_num = match key {
@@ -248,10 +275,11 @@ fn parse_fido_get_info(info_value: &Value) -> Result<FidoDeviceInfo, String> {
                             && let Some(Value::Integer(alg_id)) =
                                 alg_map.get(&Value::Text("alg".into()))
                         {
-                            if let Some(alg) = CoseAlgorithm::from_i128(*alg_id) {
-                                algorithms.push(alg.to_string());
-                            } else {
-                                algorithms.push(format!("Unknown ({})", alg_id));
+                            // Unknown IDs are left out: newer firmware may
+                            // add algorithms this version has no name for.
+                            match CoseAlgorithm::from_i128(*alg_id) {
+                                Some(alg) => algorithms.push(alg.to_string()),
+                                None => log::debug!("GetInfo algorithm {} skipped", alg_id),
                             }
                         }
                     }
@@ -302,6 +330,13 @@ fn parse_fido_get_info(info_value: &Value) -> Result<FidoDeviceInfo, String> {
                     );
                 }
             }
+            // 0x10: maxRPIDsForSetMinPINLength
+            0x10 => {
+                if let Value::Integer(max_rpids) = val {
+                    max_rpids_for_min_pin_length = Some(*max_rpids);
+                    log::info!("Device maxRPIDsForSetMinPINLength (0x10): {}", max_rpids);
+                }
+            }
             // Some firmware versions used 0x13 here. Pico-FIDO 7.6 reports
             // vendorPrototypeConfigCommands at 0x15.
             0x13 => {
@@ -325,13 +360,17 @@ fn parse_fido_get_info(info_value: &Value) -> Result<FidoDeviceInfo, String> {
             0x1B | 0x1C => {
                 log::trace!("GetInfo Pico-FIDO extension key 0x{:02X} skipped", key_num);
             }
-            // All other known keys (0x10-0x12, 0x16) - silently skip
-            0x10..=0x12 | 0x16 => {
+            // All other known keys (0x11-0x12, 0x16) - silently skip
+            0x11..=0x12 | 0x16 => {
                 log::trace!("GetInfo key 0x{:02X} skipped", key_num);
             }
-            // Unknown keys
+            // Unknown keys: kept raw for the Capabilities card and snapshots
             _ => {
                 log::debug!("GetInfo: unknown key 0x{:02X}: {:?}", key_num, val);
+                match to_vec(val) {
+                    Ok(cbor) => unknown_fields.push(UnknownGetInfoField { key: key_num, cbor }),
+                    Err(e) => log::warn!("GetInfo key 0x{:02X} not re-encodable: {}", key_num, e),
+                }
             }
         }
     }
@@ -364,6 +403,8 @@ fn parse_fido_get_info(info_value: &Value) -> Result<FidoDeviceInfo, String> {
         max_serialized_large_blob_array,
         force_pin_change,
         max_cred_blob_length,
+        max_rpids_for_min_pin_length,
+        unknown_fields,
     })
 }
 
@@ -431,9 +472,15 @@ pub(crate) fn change_fido_pin(
     }
 }
 
+/// Set the minimum PIN length. `rp_ids` replaces the list of relying parties
+/// allowed to read it (via the minPinLength extension); an empty list leaves
+/// the current list untouched. `force_change_pin` makes the key require a
+/// new PIN before its next use.
 pub(crate) fn set_min_pin_length(
     current_pin: String,
     min_pin_length: u8,
+    rp_ids: Vec<String>,
+    force_change_pin: bool,
 ) -> Result<String, String> {
     log::info!("Starting set_min_pin_length (custom implementation)...");
 
@@ -449,24 +496,70 @@ pub(crate) fn set_min_pin_length(
             None,
         )
         .map_err(|e| {
-            let err_str = e.to_string();
-            log::error!("Failed to get PIN token with ACFG permission: {}", err_str);
-            if err_str.contains("0x2B") {
+            log::error!("Failed to get PIN token with ACFG permission: {}", e);
+            if e.ctap() == Some(Ctap2Error::UnsupportedOption) {
                 return "The device does not support FIDO 2.1 advanced configuration (Error 0x2B). Ensure your device firmware is up to date and supports this feature.".to_string();
             }
-            format!("Failed to obtain PIN token: {}", err_str)
+            format!("Failed to obtain PIN token: {}", e)
         })?;
 
     // 3. Send command using the token because ctap-hid-fido2 has a bug where it sends CBOR map keys out of order (0x01, 0x03, 0x04, 0x02) instead of the required ascending order (0x01, 0x02, 0x03, 0x04). The pico-fido firmware strictly requires ascending order.
 
     transport
-        .send_config_set_min_pin_length(&pin_token, min_pin_length)
+        .send_config_set_min_pin_length(&pin_token, min_pin_length, &rp_ids, force_change_pin)
         .map_err(|e| format!("Failed to set minimum PIN length: {}", e))?;
 
-    Ok(format!(
-        "Minimum PIN length successfully set to {}",
-        min_pin_length
-    ))
+    let mut msg = format!("Minimum PIN length successfully set to {}", min_pin_length);
+    if !rp_ids.is_empty() {
+        msg.push_str(&format!(
+            "; {} relying part{} may read it",
+            rp_ids.len(),
+            if rp_ids.len() == 1 { "y" } else { "ies" }
+        ));
+    }
+    if force_change_pin {
+        msg.push_str("; a PIN change is required before the next use");
+    }
+    Ok(msg)
+}
+
+/// Flip the `alwaysUv` option and return the state the key reports afterwards.
+pub(crate) fn toggle_always_uv(pin: String) -> Result<bool, String> {
+    log::info!("Toggling alwaysUv...");
+
+    let transport =
+        HidTransport::open().map_err(|e| format!("Could not open HID transport: {}", e))?;
+
+    let pin_token = transport
+        .get_pin_token_with_permission(
+            &pin,
+            PinUvAuthTokenPermissions::AUTHENTICATOR_CONFIG,
+            None,
+        )
+        .map_err(|e| {
+            log::error!("Failed to get PIN token: {}", e);
+            if e.ctap() == Some(Ctap2Error::UnsupportedOption) {
+                return "The device does not support FIDO 2.1 advanced configuration (Error 0x2B). Ensure your device firmware is up to date and supports this feature.".to_string();
+            }
+            format!("Failed to obtain PIN token: {}", e)
+        })?;
+
+    transport
+        .toggle_always_uv(&pin_token)
+        .map_err(|e| format!("Failed to toggle always-UV: {}", e))?;
+
+    let info = read_device_info(&transport).map_err(|e| e.to_string())?;
+    Ok(info.options.get("alwaysUv").copied().unwrap_or(false))
+}
+
+/// Read the stored and remaining discoverable credential counts
+/// (`getCredsMetadata`).
+pub(crate) fn get_credentials_metadata(pin: String) -> Result<CredentialsMetadata, String> {
+    let transport =
+        HidTransport::open().map_err(|e| format!("Could not open HID transport: {}", e))?;
+    transport
+        .credential_management_get_metadata(&pin)
+        .map_err(|e| format!("Failed to read credential metadata: {}", e))
 }
 
 pub(crate) fn get_credentials(pin: String) -> Result<Vec<StoredCredential>, String> {
@@ -514,6 +607,11 @@ pub(crate) fn get_credentials(pin: String) -> Result<Vec<StoredCredential>, Stri
                 user_name: "".to_string(),
                 user_display_name: "".to_string(),
                 user_id: "".to_string(),
+                algorithm: None,
+                key_type: None,
+                curve: None,
+                cred_protect: cred.cred_protect.and_then(CredProtectPolicy::from_u8),
+                public_key: to_vec(&cred.public_key).unwrap_or_default(),
             };
 
             // Parse User Map
@@ -536,6 +634,12 @@ pub(crate) fn get_credentials(pin: String) -> Result<Vec<StoredCredential>, Stri
                 stored_cred.credential_id = hex::encode(b);
             }
 
+            (
+                stored_cred.key_type,
+                stored_cred.algorithm,
+                stored_cred.curve,
+            ) = cose_key_params(&cred.public_key);
+
             all_credentials.push(stored_cred);
         }
     }
@@ -543,25 +647,269 @@ pub(crate) fn get_credentials(pin: String) -> Result<Vec<StoredCredential>, Stri
     Ok(all_credentials)
 }
 
-pub(crate) fn delete_credential(pin: String, credential_id_hex: String) -> Result<String, String> {
-    log::info!("Deleting FIDO credential via custom implementation...");
+/// `kty`, `alg` and `crv` of a COSE_Key map. Each is `None` when absent or
+/// not an integer (`crv` is absent for RSA keys).
+fn cose_key_params(public_key: &Value) -> (Option<i32>, Option<i32>, Option<i32>) {
+    let Value::Map(m) = public_key else {
+        return (None, None, None);
+    };
+    let param = |label: CoseKeyParam| match m.get(&Value::Integer(label as i128)) {
+        Some(Value::Integer(v)) => i32::try_from(*v).ok(),
+        _ => None,
+    };
+    (
+        param(CoseKeyParam::Kty),
+        param(CoseKeyParam::Alg),
+        param(CoseKeyParam::Crv),
+    )
+}
+
+/// Read the credential list and serialize it for an inventory export.
+///
+/// CTAP does not report when a credential was created, so the export carries
+/// the time it was taken instead.
+pub(crate) fn export_credentials(
+    pin: String,
+    format: CredentialExportFormat,
+) -> Result<String, String> {
+    let credentials = get_credentials(pin)?;
+    let exported_at = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    log::info!(
+        "Exporting {} credentials as {}",
+        credentials.len(),
+        format.extension()
+    );
+    serialize_credentials(&credentials, format, exported_at)
+}
+
+/// Columns of the CSV export, in order.
+const CREDENTIAL_EXPORT_COLUMNS: [&str; 8] = [
+    "rp_id",
+    "rp_name",
+    "user_name",
+    "user_display_name",
+    "user_id",
+    "credential_id",
+    "algorithm",
+    "exported_at",
+];
+
+fn serialize_credentials(
+    credentials: &[StoredCredential],
+    format: CredentialExportFormat,
+    exported_at: u64,
+) -> Result<String, String> {
+    let algorithm = |cred: &StoredCredential| {
+        cred.algorithm
+            .map(|alg| match CoseAlgorithm::from_i128(alg as i128) {
+                Some(name) => name.to_string(),
+                None => alg.to_string(),
+            })
+            .unwrap_or_default()
+    };
+
+    match format {
+        CredentialExportFormat::Json => {
+            let entries: Vec<serde_json::Value> = credentials
+                .iter()
+                .map(|cred| {
+                    serde_json::json!({
+                        "rpId": cred.rp_id,
+                        "rpName": cred.rp_name,
+                        "userName": cred.user_name,
+                        "userDisplayName": cred.user_display_name,
+                        "userId": cred.user_id,
+                        "credentialId": cred.credential_id,
+                        "algorithm": algorithm(cred),
+                    })
+                })
+                .collect();
+            serde_json::to_string_pretty(&serde_json::json!({
+                "exportedAt": exported_at,
+                "credentials": entries,
+            }))
+            .map_err(|e| format!("Failed to serialize credentials: {}", e))
+        }
+        CredentialExportFormat::Csv => {
+            let mut out = CREDENTIAL_EXPORT_COLUMNS.join(",");
+            out.push_str("\r\n");
+            for cred in credentials {
+                let row = [
+                    cred.rp_id.as_str(),
+                    &cred.rp_name,
+                    &cred.user_name,
+                    &cred.user_display_name,
+                    &cred.user_id,
+                    &cred.credential_id,
+                    &algorithm(cred),
+                    &exported_at.to_string(),
+                ]
+                .map(csv_field);
+                out.push_str(&row.join(","));
+                out.push_str("\r\n");
+            }
+            Ok(out)
+        }
+    }
+}
+
+/// Quote a CSV field when needed. Names come from relying parties, so a
+/// leading formula character is escaped to keep spreadsheets from running it.
+fn csv_field(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+    if value.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+/// Delete several credentials with a single `CREDENTIAL_MANAGEMENT` PIN token,
+/// so the PIN is sent (and a retry consumed on a typo) only once.
+///
+/// Every ID is attempted. Returns the IDs that could not be deleted with the
+/// reason; `Err` means nothing was attempted (no device or wrong PIN).
+pub(crate) fn delete_credentials_bulk(
+    pin: String,
+    credential_ids_hex: Vec<String>,
+) -> Result<Vec<(String, String)>, String> {
+    log::info!("Deleting {} FIDO credentials...", credential_ids_hex.len());
 
     let transport =
         HidTransport::open().map_err(|e| format!("Could not open HID transport: {}", e))?;
+    let pin_token = transport
+        .get_pin_token_with_permission(&pin, PinUvAuthTokenPermissions::CREDENTIAL_MANAGEMENT, None)
+        .map_err(|e| format!("PIN verification failed: {}", e))?;
+
+    let failures = credential_ids_hex
+        .into_iter()
+        .filter_map(|credential_id| {
+            let result = hex::decode(&credential_id)
+                .map_err(|_| "Invalid Credential ID Hex string".to_string())
+                .and_then(|bytes| {
+                    transport
+                        .credential_management_delete_with_token(
+                            &pin_token,
+                            credential_descriptor(bytes),
+                        )
+                        .map_err(|e| format!("Failed to delete credential: {}", e))
+                });
+            result.err().map(|e| (credential_id, e))
+        })
+        .collect();
+    Ok(failures)
+}
 
-    let cred_id_bytes = hex::decode(&credential_id_hex)
-        .map_err(|_| "Invalid Credential ID Hex string".to_string())?;
+/// Shortest credential ID accepted when pasted; no authenticator issues
+/// shorter ones.
+const MIN_CREDENTIAL_ID_LEN: usize = 16;
+
+/// Longest credential ID CTAP2 allows.
+const MAX_CREDENTIAL_ID_LEN: usize = 1023;
+
+/// Parse a credential ID pasted from browser devtools or server logs: hex
+/// (`0x` prefix, spaces and colons allowed) or base64url / base64 (padding
+/// optional). Input made only of an even number of hex digits is read as
+/// hex. Returns the ID as lowercase hex, as the credential list holds it.
+pub fn parse_credential_id(input: &str) -> Result<String, String> {
+    let trimmed = input.trim();
+    if trimmed.is_empty() {
+        return Err("Enter a credential ID.".to_string());
+    }
+    let hex_digits: String = trimmed
+        .strip_prefix("0x")
+        .or_else(|| trimmed.strip_prefix("0X"))
+        .unwrap_or(trimmed)
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != ':')
+        .collect();
+    let bytes = if hex_digits.len() % 2 == 0 && hex_digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        hex::decode(&hex_digits).map_err(|e| e.to_string())?
+    } else {
+        let b64: String = trimmed.chars().filter(|c| !c.is_whitespace()).collect();
+        let b64 = b64.trim_end_matches('=');
+        general_purpose::URL_SAFE_NO_PAD
+            .decode(b64)
+            .or_else(|_| general_purpose::STANDARD_NO_PAD.decode(b64))
+            .map_err(|_| "Not a hex or base64url credential ID.".to_string())?
+    };
+    if !(MIN_CREDENTIAL_ID_LEN..=MAX_CREDENTIAL_ID_LEN).contains(&bytes.len()) {
+        return Err(format!(
+            "A credential ID is {} to {} bytes long; this one is {}.",
+            MIN_CREDENTIAL_ID_LEN,
+            MAX_CREDENTIAL_ID_LEN,
+            bytes.len()
+        ));
+    }
+    Ok(hex::encode(bytes))
+}
 
-    // Create PublicKeyCredentialDescriptor map: { "type": "public-key", "id": <bytes> }
+/// PublicKeyCredentialDescriptor map: `{ "type": "public-key", "id": <bytes> }`.
+fn credential_descriptor(credential_id: Vec<u8>) -> Value {
     let mut descriptor = BTreeMap::new();
     descriptor.insert(Value::Text("type".into()), Value::Text("public-key".into()));
-    descriptor.insert(Value::Text("id".into()), Value::Bytes(cred_id_bytes));
+    descriptor.insert(Value::Text("id".into()), Value::Bytes(credential_id));
+    Value::Map(descriptor)
+}
+
+/// PublicKeyCredentialUserEntity for `updateUserInformation`. Empty names are
+/// left out, which clears them on the key.
+fn user_entity(user_id: Vec<u8>, name: &str, display_name: &str) -> Value {
+    let mut user = BTreeMap::new();
+    user.insert(Value::Text("id".into()), Value::Bytes(user_id));
+    if !name.is_empty() {
+        user.insert(Value::Text("name".into()), Value::Text(name.into()));
+    }
+    if !display_name.is_empty() {
+        user.insert(
+            Value::Text("displayName".into()),
+            Value::Text(display_name.into()),
+        );
+    }
+    Value::Map(user)
+}
+
+/// Replace the user name and display name stored with a credential. The user
+/// ID is kept, as the authenticator only accepts an entity with the same ID.
+pub(crate) fn update_credential_user(
+    pin: String,
+    credential_id_hex: String,
+    user_id_hex: String,
+    name: String,
+    display_name: String,
+) -> Result<String, String> {
+    let transport =
+        HidTransport::open().map_err(|e| format!("Could not open HID transport: {}", e))?;
+
+    let cred_id_bytes = hex::decode(&credential_id_hex)
+        .map_err(|_| "Invalid Credential ID Hex string".to_string())?;
+    let user_id =
+        hex::decode(&user_id_hex).map_err(|_| "Invalid User ID Hex string".to_string())?;
 
     transport
-        .credential_management_delete_credential(&pin, Value::Map(descriptor))
-        .map_err(|e| format!("Failed to delete credential: {}", e))?;
+        .credential_management_update_user_info(
+            &pin,
+            credential_descriptor(cred_id_bytes),
+            user_entity(user_id, name.trim(), display_name.trim()),
+        )
+        .map_err(|e| {
+            if matches!(
+                e.ctap(),
+                Some(Ctap2Error::InvalidCommand | Ctap2Error::InvalidParameter)
+            ) {
+                return "This key does not support updating user information.".to_string();
+            }
+            format!("Failed to update user information: {}", e)
+        })?;
 
-    Ok("Credential deleted successfully".into())
+    Ok("User information updated".into())
 }
 
 pub(crate) fn reset_device() -> Result<String, String> {
@@ -571,19 +919,129 @@ pub(crate) fn reset_device() -> Result<String, String> {
         HidTransport::open().map_err(|e| format!("Could not open HID transport: {}", e))?;
 
     transport.reset().map_err(|e| {
-        let error_text = e.to_string();
-        if error_text.contains("0x30") {
+        if e.ctap() == Some(Ctap2Error::NotAllowed) {
             return "Reset not allowed. The device must be unplugged and re-plugged within 10 seconds before sending the reset command.".to_string();
         }
-        if error_text.contains("0x27") {
+        if e.ctap() == Some(Ctap2Error::OperationDenied) {
             return "Reset declined. Touch was not confirmed on the device.".to_string();
         }
-        format!("Reset failed: {}", error_text)
+        format!("Reset failed: {}", e)
     })?;
 
     Ok("Device has been factory reset. All credentials and PIN have been erased.".to_string())
 }
 
+/// Confirm a USB identity (VID/PID) change with a touch and the device PIN.
+///
+/// Sends authenticatorSelection so the user must physically tap the key, then
+/// proves PIN knowledge by deriving a PIN token. Nothing is written; callers run
+/// this before the actual config write when identity protection is enabled and
+/// the key was opened over FIDO — over Rescue the HID interface may belong to
+/// another key or not exist at all.
+pub(crate) fn confirm_identity_change(pin: String) -> Result<String, String> {
+    log::info!("Confirming USB identity change (touch + PIN)...");
+
+    let transport =
+        HidTransport::open().map_err(|e| format!("Could not open HID transport: {}", e))?;
+
+    transport.selection().map_err(|e| {
+        if matches!(
+            e.ctap(),
+            Some(Ctap2Error::UserActionTimeout | Ctap2Error::OperationDenied)
+        ) {
+            return "Identity change not confirmed. Touch was not detected on the device."
+                .to_string();
+        }
+        format!("Touch confirmation failed: {}", e)
+    })?;
+
+    transport
+        .get_pin_token(&pin)
+        .map_err(|e| format!("PIN verification failed: {}", e))?;
+
+    Ok("Identity change confirmed".into())
+}
+
+/// Read the remaining PIN attempts and whether a power cycle is pending.
+pub(crate) fn get_pin_retries() -> Result<PinRetries, String> {
+    let transport =
+        HidTransport::open().map_err(|e| format!("Could not open HID transport: {}", e))?;
+
+    transport
+        .get_pin_retries()
+        .map_err(|e| format!("Failed to read PIN retries: {}", e))
+}
+
+/// Read the remaining PIN attempts and, on keys with built-in UV, the
+/// remaining fingerprint attempts.
+pub(crate) fn get_pin_status() -> Result<PinStatus, String> {
+    let transport =
+        HidTransport::open().map_err(|e| format!("Could not open HID transport: {}", e))?;
+
+    let pin = transport
+        .get_pin_retries()
+        .map_err(|e| format!("Failed to read PIN retries: {}", e))?;
+    let has_builtin_uv = read_device_info(&transport)
+        .map(|info| info.options.contains_key("uv"))
+        .unwrap_or(false);
+    let uv_retries = if has_builtin_uv {
+        transport
+            .get_uv_retries()
+            .map_err(|e| log::debug!("Could not read UV retries: {}", e))
+            .ok()
+    } else {
+        None
+    };
+
+    Ok(PinStatus { pin, uv_retries })
+}
+
+/// The PIN lockout a CTAP error reports, if it is one.
+pub fn pin_lockout_from_error(error: Ctap2Error) -> Option<PinLockout> {
+    match error {
+        Ctap2Error::PinBlocked => Some(PinLockout::Blocked),
+        Ctap2Error::PinAuthBlocked => Some(PinLockout::PowerCycleRequired),
+        _ => None,
+    }
+}
+
+/// Derive the lockout state from a `getPinRetries` answer.
+pub fn pin_lockout_from_retries(retries: &PinRetries) -> Option<PinLockout> {
+    if retries.retries == 0 {
+        Some(PinLockout::Blocked)
+    } else if retries.power_cycle_required {
+        Some(PinLockout::PowerCycleRequired)
+    } else {
+        None
+    }
+}
+
+/// Whether the key speaks the pico-fido/RS-Key vendor commands.
+///
+/// Generic FIDO keys (YubiKey, SoloKey, ...) answer GetInfo but reject every
+/// vendor command, typically with `CTAP1_ERR_INVALID_COMMAND` or `0x2B`
+/// (unsupported option). A key counts as pico-based when its AAGUID is known
+/// or it lists `vendorPrototypeConfigCommands` in GetInfo.
+pub fn is_pico_device(firmware_type: &FirmwareType, info: &FidoDeviceInfo) -> bool {
+    *firmware_type != FirmwareType::Unknown || !info.vendor_config_commands.is_empty()
+}
+
+/// Whether a config write may send `command` to this key.
+///
+/// Only pico-fido firmware takes hardware settings as vendor config commands
+/// (RS-Key writes its config record), and its advertised list is only
+/// authoritative when GetInfo carries one: older builds list nothing and
+/// accept them all.
+pub fn advertises_vendor_config(
+    firmware_type: &FirmwareType,
+    info: &FidoDeviceInfo,
+    command: VendorConfigCommand,
+) -> bool {
+    *firmware_type != FirmwareType::PicoFido
+        || info.vendor_config_commands.is_empty()
+        || info.vendor_config_commands.contains(&command.to_string())
+}
+
 // Custom Fido functions ( works only with pico-fido firmware )
 
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
@@ -631,45 +1089,78 @@ pub fn read_device_details() -> Result<FullDeviceStatus, PFError> {
         fido_info.firmware_version
     );
 
-    let firmware_type = if fido_info.aaguid == RSKEY_AAGUID {
-        FirmwareType::RSKey
-    } else if fido_info.aaguid == PICOFIDO_AAGUID || fido_info.aaguid == LKONE_AAGUID {
-        FirmwareType::PicoFido
+    let firmware_type = firmware_type_of(&fido_info);
+    // Every vendor exchange goes out back to back on the one open transport.
+    // The legacy probe already is a PhysicalOptions read, so its response
+    // doubles as the physical config instead of asking the key twice.
+    let legacy_phy_raw = if firmware_type == FirmwareType::PicoFido
+        && tunables::vendor_read_enabled(VendorRead::PhysicalOptions)
+    {
+        exchange_legacy_physical_options(&transport)
     } else {
-        FirmwareType::Unknown
+        None
     };
-    let has_legacy_vendor =
-        firmware_type == FirmwareType::PicoFido && probe_legacy_vendor_support(&transport);
     let firmware = AnyFirmware::new_with_legacy(
         firmware_type.clone(),
         &fido_info.firmware_version,
-        has_legacy_vendor,
+        legacy_phy_raw.is_some(),
     );
     let supports_legacy_hardware_config = firmware.supports_legacy_fido_hardware_config();
-    let management = read_management_info(&transport);
-    let config = AppConfig {
-        vid: format!("{:04X}", transport.vid),
-        pid: format!("{:04X}", transport.pid),
-        product_name: transport.product_name.clone(),
-        ..Default::default()
+    let management_raw = if is_pico_device(&firmware_type, &fido_info) {
+        exchange_management_info(&transport)
+    } else {
+        log::info!(
+            "AAGUID {} is not a pico-fido build and lists no vendor commands; \
+             skipping vendor reads",
+            fido_info.aaguid
+        );
+        None
     };
-    let config = if firmware_type == FirmwareType::RSKey {
+    let rskey_phy_raw = if firmware_type == FirmwareType::RSKey
+        && tunables::vendor_read_enabled(VendorRead::RsKeyConfig)
+    {
         // RS-Key uses 0x41 CONFIG_READ via CTAPHID_CBOR — not the
         // legacy 0xC1 vendor command. Always attempt it; pre-v0.3.1
         // firmware gracefully returns the config unchanged with a log.
-        read_rskey_physical_config(&transport, config)
-    } else if supports_legacy_hardware_config {
-        read_legacy_physical_config(&transport, config)
+        transport
+            .rs_key_config_read(RSKEY_CFG_TARGET_PHY)
+            .map_err(|_| {
+                log::info!(
+                    "RS-Key FIDO config read unavailable (pre-v0.3.1 firmware or transport error)"
+                )
+            })
+            .ok()
     } else {
-        config
+        None
     };
-    let mem_stats = if supports_legacy_hardware_config {
-        read_legacy_memory_stats(&transport).unwrap_or_else(|e| {
+    let mem_raw =
+        if supports_legacy_hardware_config && tunables::vendor_read_enabled(VendorRead::Memory) {
+            exchange_legacy_memory_stats(&transport)
+                .map_err(|e| log::info!("Legacy FIDO memory stats unavailable: {}", e))
+                .ok()
+        } else {
+            None
+        };
+
+    let config = AppConfig {
+        vid: format!("{:04X}", transport.vid),
+        pid: format!("{:04X}", transport.pid),
+        product_name: transport.product_name.clone(),
+        ..Default::default()
+    };
+    let management = management_raw.as_deref().and_then(management_info_from_raw);
+    let mem_stats = mem_raw.as_deref().and_then(|raw| {
+        parse_legacy_memory_stats(raw).unwrap_or_else(|e| {
             log::info!("Legacy FIDO memory stats unavailable: {}", e);
             None
         })
-    } else {
-        None
+    });
+    let config = match (&rskey_phy_raw, &legacy_phy_raw) {
+        (Some(raw), _) => apply_rskey_physical_config(raw, config),
+        (None, Some(raw)) if supports_legacy_hardware_config => {
+            apply_legacy_physical_options(raw, config)
+        }
+        _ => config,
     };
 
     log::info!("Successfully read all device details.");
@@ -693,18 +1184,61 @@ pub fn read_device_details() -> Result<FullDeviceStatus, PFError> {
             firmware_version,
         },
         config,
+        // Secure boot lives in the boot ROM's OTP and is only readable
+        // through the Rescue applet; io::read_device_details merges it in.
+        // Secure lock is only readable here.
         secure_boot: false,
-        secure_lock: false,
+        secure_boot_locked: false,
+        secure_lock: fido_info.certifications.get("Auth Encryption") == Some(&true),
         method: DeviceMethod::Fido,
         firmware_type: firmware.firmware_type(),
     })
 }
 
+/// Read flash usage as `(used, total)` bytes with the legacy vendor Memory
+/// command. `None` when the firmware answers with an empty response.
+pub fn read_memory_stats() -> Result<Option<(u32, u32)>, PFError> {
+    let transport = HidTransport::open()?;
+    let raw = exchange_legacy_memory_stats(&transport)?;
+    parse_legacy_memory_stats(&raw)
+}
+
+/// Firmware family of a key, from the AAGUID in its GetInfo.
+fn firmware_type_of(info: &FidoDeviceInfo) -> FirmwareType {
+    if info.aaguid == RSKEY_AAGUID {
+        FirmwareType::RSKey
+    } else if info.aaguid == PICOFIDO_AAGUID || info.aaguid == LKONE_AAGUID {
+        FirmwareType::PicoFido
+    } else {
+        FirmwareType::Unknown
+    }
+}
+
+/// Read back the stored hardware configuration alone, for checking a write.
+///
+/// RS-Key returns its whole PHY record; pico-fido's legacy vendor command
+/// only reports the options word. The USB descriptor is not consulted, so
+/// a VID/PID or product name that only takes effect after re-plugging is
+/// read from the record it was written to, or not at all.
+pub fn read_stored_config() -> Result<AppConfig, PFError> {
+    let transport = HidTransport::open()?;
+    let fido_info = read_device_info(&transport)?;
+    let config = AppConfig::default();
+    match firmware_type_of(&fido_info) {
+        FirmwareType::RSKey => {
+            let raw = transport.rs_key_config_read(RSKEY_CFG_TARGET_PHY)?;
+            Ok(apply_rskey_physical_config(&raw, config))
+        }
+        FirmwareType::PicoFido => Ok(read_legacy_physical_config(&transport, config)),
+        _ => Ok(config),
+    }
+}
+
 fn read_device_info(transport: &HidTransport) -> Result<FidoDeviceInfo, PFError> {
     log::debug!("Sending GetInfo command (0x04)...");
     let info_payload = [CtapCommand::GetInfo as u8];
     let info_res = transport
-        .send_cbor(CTAPHID_CBOR, &info_payload[..])
+        .send_cbor_read(CTAPHID_CBOR, &info_payload[..])
         .map_err(|e| {
             log::error!("GetInfo CTAP command failed: {}", e);
             PFError::Device(format!("GetInfo failed: {}", e))
@@ -721,26 +1255,25 @@ fn read_device_info(transport: &HidTransport) -> Result<FidoDeviceInfo, PFError>
 }
 
 fn read_management_info(transport: &HidTransport) -> Option<ManagementInfo> {
+    exchange_management_info(transport)
+        .as_deref()
+        .and_then(management_info_from_raw)
+}
+
+/// Send vendor command 0xC2 and return the raw TLV response, if the key
+/// answered with one.
+fn exchange_management_info(transport: &HidTransport) -> Option<Vec<u8>> {
     // pico-fido v7.6 src/fido/cbor.c handles HID cmd 0xC2 as raw
     // man_get_config() TLV bytes, not as CTAP CBOR with a status byte.
     match transport.send_raw(CTAP_VENDOR_CONFIG_CMD, &[]) {
-        Ok(raw) => {
-            if raw.len() == 1 {
-                log::info!(
-                    "FIDO management config is not available (CTAP error 0x{:02X})",
-                    raw[0]
-                );
-                None
-            } else {
-                match parse_management_info(&raw) {
-                    Ok(info) => Some(info),
-                    Err(e) => {
-                        log::warn!("Failed to parse FIDO management config: {}", e);
-                        None
-                    }
-                }
-            }
+        Ok(raw) if raw.len() == 1 => {
+            log::info!(
+                "FIDO management config is not available (CTAP error 0x{:02X})",
+                raw[0]
+            );
+            None
         }
+        Ok(raw) => Some(raw),
         Err(e) => {
             log::info!("FIDO management config is not available: {}", e);
             None
@@ -748,6 +1281,16 @@ fn read_management_info(transport: &HidTransport) -> Option<ManagementInfo> {
     }
 }
 
+fn management_info_from_raw(raw: &[u8]) -> Option<ManagementInfo> {
+    match parse_management_info(raw) {
+        Ok(info) => Some(info),
+        Err(e) => {
+            log::warn!("Failed to parse FIDO management config: {}", e);
+            None
+        }
+    }
+}
+
 fn parse_management_info(raw: &[u8]) -> Result<ManagementInfo, String> {
     let data = if raw.first().map(|len| *len as usize) == Some(raw.len().saturating_sub(1)) {
         &raw[1..]
@@ -802,7 +1345,7 @@ fn parse_management_u16(val: &[u8]) -> Option<u16> {
     }
 }
 
-fn read_legacy_memory_stats(transport: &HidTransport) -> Result<Option<(u32, u32)>, PFError> {
+fn exchange_legacy_memory_stats(transport: &HidTransport) -> Result<Vec<u8>, PFError> {
     let mut mem_req = BTreeMap::new();
     mem_req.insert(
         Value::Integer(1),
@@ -813,13 +1356,16 @@ fn read_legacy_memory_stats(transport: &HidTransport) -> Result<Option<(u32, u32
     let mut mem_payload = vec![VendorCommand::Memory as u8];
     mem_payload.extend(mem_cbor);
 
-    let mem_res = transport.send_cbor(CTAP_VENDOR_CBOR_CMD, &mem_payload)?;
+    transport.send_cbor_read(CTAP_VENDOR_CBOR_CMD, &mem_payload)
+}
+
+fn parse_legacy_memory_stats(mem_res: &[u8]) -> Result<Option<(u32, u32)>, PFError> {
     if mem_res.is_empty() {
         return Ok(None);
     }
 
     let mem_map: BTreeMap<i128, i128> =
-        from_slice(&mem_res).map_err(|e| PFError::Io(e.to_string()))?;
+        from_slice(mem_res).map_err(|e| PFError::Io(e.to_string()))?;
     let used = mem_map
         .get(&(MemoryResponseKey::UsedSpace as i128))
         .copied()
@@ -838,41 +1384,35 @@ fn read_legacy_memory_stats(transport: &HidTransport) -> Result<Option<(u32, u32
 ///
 /// Sends a single PhysicalOptions read CBOR command — a cheap round-trip.
 fn probe_legacy_vendor_support(transport: &HidTransport) -> bool {
+    exchange_legacy_physical_options(transport).is_some()
+}
+
+/// Send a PhysicalOptions read and return the response if it is CBOR, i.e.
+/// the legacy handler exists.
+fn exchange_legacy_physical_options(transport: &HidTransport) -> Option<Vec<u8>> {
     let mut params = BTreeMap::new();
     params.insert(
         Value::Integer(1),
         Value::Integer(PhysicalOptionsSubCommand::GetOptions as i128),
     );
-    let Ok(phy_cbor) = to_vec(&Value::Map(params)) else {
-        return false;
-    };
+    let phy_cbor = to_vec(&Value::Map(params)).ok()?;
     let mut phy_payload = vec![VendorCommand::PhysicalOptions as u8];
     phy_payload.extend(phy_cbor);
-    match transport.send_cbor(CTAP_VENDOR_CBOR_CMD, &phy_payload) {
-        Ok(resp) => from_slice::<Value>(&resp).is_ok(),
-        Err(_) => false,
-    }
+    let resp = transport
+        .send_cbor_read(CTAP_VENDOR_CBOR_CMD, &phy_payload)
+        .ok()?;
+    from_slice::<Value>(&resp).is_ok().then_some(resp)
 }
 
-fn read_legacy_physical_config(transport: &HidTransport, mut config: AppConfig) -> AppConfig {
-    let mut phy_params = BTreeMap::new();
-    phy_params.insert(
-        Value::Integer(1),
-        Value::Integer(PhysicalOptionsSubCommand::GetOptions as i128),
-    );
-
-    let Ok(phy_cbor) = to_vec(&Value::Map(phy_params)) else {
-        return config;
-    };
-
-    let mut phy_payload = vec![VendorCommand::PhysicalOptions as u8];
-    phy_payload.extend(phy_cbor);
-
-    let Ok(phy_res) = transport.send_cbor(CTAP_VENDOR_CBOR_CMD, &phy_payload) else {
-        return config;
-    };
+fn read_legacy_physical_config(transport: &HidTransport, config: AppConfig) -> AppConfig {
+    match exchange_legacy_physical_options(transport) {
+        Some(raw) => apply_legacy_physical_options(&raw, config),
+        None => config,
+    }
+}
 
-    let Ok(Value::Map(m)) = from_slice::<Value>(&phy_res) else {
+fn apply_legacy_physical_options(phy_res: &[u8], mut config: AppConfig) -> AppConfig {
+    let Ok(Value::Map(m)) = from_slice::<Value>(phy_res) else {
         return config;
     };
 
@@ -881,24 +1421,18 @@ fn read_legacy_physical_config(transport: &HidTransport, mut config: AppConfig)
         config.led_dimmable = opts & LEGACY_PHY_OPT_DIMMABLE != 0;
         config.power_cycle_on_reset = opts & LEGACY_PHY_OPT_DISABLE_POWER_RESET == 0;
         config.led_steady = opts & LEGACY_PHY_OPT_LED_STEADY != 0;
+        config.raw_options = Some(opts);
     }
 
     config
 }
 
-/// Read PHY configuration from an RS-Key via CTAPHID 0x41 CONFIG_READ.
+/// Apply an RS-Key `EF_PHY` record read via CTAPHID 0x41 CONFIG_READ.
 ///
-/// Falls back to returning the unchanged config if the command is not
-/// supported by the device (pre-v0.3.1 firmware) or the transport errors.
-fn read_rskey_physical_config(transport: &HidTransport, mut config: AppConfig) -> AppConfig {
+/// Tags the record does not carry leave the config unchanged.
+fn apply_rskey_physical_config(data: &[u8], mut config: AppConfig) -> AppConfig {
     // `rs_key_config_read` unwraps the CBOR `{1: blob}` and returns the raw
     // `EF_PHY` TLV record — a bare `TAG LEN VALUE` sequence, no length prefix.
-    let Ok(data) = transport.rs_key_config_read(RSKEY_CFG_TARGET_PHY) else {
-        log::info!("RS-Key FIDO config read unavailable (pre-v0.3.1 firmware or transport error)");
-        return config;
-    };
-
-    let data = &data[..];
     let mut i = 0;
     while i + 1 < data.len() {
         if i + 2 > data.len() {
@@ -937,6 +1471,7 @@ fn read_rskey_physical_config(transport: &HidTransport, mut config: AppConfig) -
                 config.led_dimmable = opts & RSKEY_OPT_DIMMABLE != 0;
                 config.power_cycle_on_reset = opts & RSKEY_OPT_DISABLE_POWER_RESET == 0;
                 config.led_steady = opts & RSKEY_OPT_LED_STEADY != 0;
+                config.raw_options = Some(opts);
             }
             RSKEY_PHY_TAG_CURVES if field_data.len() == 4 => {
                 let mask = u32::from_be_bytes([
@@ -964,16 +1499,42 @@ fn read_rskey_physical_config(transport: &HidTransport, mut config: AppConfig) -
         }
         i += len;
     }
+    if config.raw_options.is_none() {
+        // No options tag: the firmware runs with every option bit clear.
+        config.raw_options = Some(0);
+        config.power_cycle_on_reset = true;
+    }
 
     config
 }
 
+/// Options word from a raw RS-Key `EF_PHY` TLV record, if it has one.
+fn rskey_phy_options(data: &[u8]) -> Option<u16> {
+    let mut i = 0;
+    while i + 2 <= data.len() {
+        let (tag, len) = (data[i], data[i + 1] as usize);
+        i += 2;
+        if i + len > data.len() {
+            break;
+        }
+        if tag == RSKEY_PHY_TAG_OPTS && len >= 2 {
+            return Some(u16::from_be_bytes([data[i], data[i + 1]]));
+        }
+        i += len;
+    }
+    None
+}
+
 /// Build a PHY TLV blob from `AppConfigInput` for RS-Key CONFIG_WRITE.
 ///
 /// The TLV format matches the Rescue PHY record and is sent as-is
-/// to the RS-Key 0x41 CONFIG_WRITE handler. Errors if a field can't fit
-/// the record (e.g. an over-long product name).
-fn build_rskey_phy_tlv(config: &AppConfigInput) -> Result<Vec<u8>, PFError> {
+/// to the RS-Key 0x41 CONFIG_WRITE handler. `current_options` is the options
+/// word already on the device; the option switches are merged into it.
+/// Errors if a field can't fit the record (e.g. an over-long product name).
+fn build_rskey_phy_tlv(
+    config: &AppConfigInput,
+    current_options: Option<u16>,
+) -> Result<Vec<u8>, PFError> {
     let mut tlv = Vec::new();
 
     if let (Some(vid_str), Some(pid_str)) = (&config.vid, &config.pid)
@@ -1000,21 +1561,16 @@ fn build_rskey_phy_tlv(config: &AppConfigInput) -> Result<Vec<u8>, PFError> {
         tlv.push(val);
     }
 
-    if let (Some(dim), Some(cycle), Some(steady)) = (
-        config.led_dimmable,
-        config.power_cycle_on_reset,
-        config.led_steady,
-    ) {
-        let mut opts = 0u16;
-        if dim {
-            opts |= RSKEY_OPT_DIMMABLE;
-        }
-        if !cycle {
-            opts |= RSKEY_OPT_DISABLE_POWER_RESET;
-        }
-        if steady {
-            opts |= RSKEY_OPT_LED_STEADY;
-        }
+    if config.led_dimmable.is_some()
+        || config.power_cycle_on_reset.is_some()
+        || config.led_steady.is_some()
+    {
+        let opts = RescueOptions::merge(
+            current_options.unwrap_or(0),
+            config.led_dimmable,
+            config.power_cycle_on_reset,
+            config.led_steady,
+        );
         tlv.push(RSKEY_PHY_TAG_OPTS);
         tlv.push(0x02);
         tlv.extend_from_slice(&opts.to_be_bytes());
@@ -1042,14 +1598,8 @@ fn build_rskey_phy_tlv(config: &AppConfigInput) -> Result<Vec<u8>, PFError> {
     }
 
     if config.enable_secp256k1.is_some() || config.raw_curves_mask.is_some() {
-        let mut mask = config.raw_curves_mask.unwrap_or(0);
-        if let Some(enabled) = config.enable_secp256k1 {
-            if enabled {
-                mask |= 0x08; // SECP256K1
-            } else {
-                mask &= !0x08u32;
-            }
-        }
+        let mask =
+            RescueCurves::merge(config.raw_curves_mask.unwrap_or(0), config.enable_secp256k1);
         tlv.push(RSKEY_PHY_TAG_CURVES);
         tlv.push(0x04);
         tlv.extend_from_slice(&mask.to_be_bytes());
@@ -1082,23 +1632,20 @@ fn build_rskey_phy_tlv(config: &AppConfigInput) -> Result<Vec<u8>, PFError> {
     Ok(tlv)
 }
 
-/// Write PHY config to an RS-Key via CTAPHID 0x41 CONFIG_WRITE.
+/// Write `plan` to an RS-Key as one PHY record via CTAPHID 0x41 CONFIG_WRITE.
 fn write_rskey_config(
     transport: &HidTransport,
-    config: &AppConfigInput,
+    plan: &ConfigPlan,
     pin: &str,
+    permission: PinUvAuthTokenPermissions,
 ) -> Result<String, PFError> {
-    let tlv = build_rskey_phy_tlv(config)?;
-    if tlv.is_empty() {
-        return Ok("No RS-Key configuration changes were needed.".to_string());
-    }
-
     // Probe: CONFIG_READ (0x41 subcommand 0x0D) is ungated and, on success,
     // confirms the device supports the 0x41 CONFIG_WRITE/CONFIG_READ commands
     // (RS-Key v0.3.1+). Pre-v0.3.1 firmware rejects it with a CTAP error, which
     // surfaces here as `Err`. A supported device may legitimately return an
     // empty PHY blob, so success alone is the signal — never the blob length.
-    transport
+    // The blob also provides the current options word for the merge below.
+    let current = transport
         .rs_key_config_read(RSKEY_CFG_TARGET_PHY)
         .map_err(|_| {
             PFError::Device(
@@ -1108,8 +1655,13 @@ fn write_rskey_config(
             )
         })?;
 
+    let tlv = build_rskey_phy_tlv(&plan.record_input(), rskey_phy_options(&current))?;
+    if tlv.is_empty() {
+        return Ok("No RS-Key configuration changes were needed.".to_string());
+    }
+
     let pin_token = transport
-        .get_pin_token_with_permission(pin, PinUvAuthTokenPermissions::AUTHENTICATOR_CONFIG, None)
+        .get_pin_token_with_permission(pin, permission, None)
         .or_else(|e| {
             log::warn!(
                 "Failed to get PIN token with ACFG permission: {}. Falling back.",
@@ -1128,14 +1680,22 @@ fn write_rskey_config(
 
 /// Write device configuration over the FIDO HID transport.
 ///
-/// Applies the partial [`AppConfigInput`] to the authenticator using
-/// the appropriate vendor or standard config command path for the
-/// detected firmware type. Requires the PIN for config write operations.
-pub fn write_config(config: AppConfigInput, pin: Option<String>) -> Result<String, PFError> {
+/// Plans the partial [`AppConfigInput`] for the write path of the detected
+/// firmware (RS-Key's PHY record or the pico-fido vendor commands), checks
+/// that the device can take every step before anything is sent, then
+/// applies the plan. Requires the PIN for config write operations.
+pub fn write_config(config: AppConfigInput, pin: Option<String>) -> Result<AppliedPlan, PFError> {
     log::info!("Starting FIDO write_config...");
 
     if is_empty_config_input(&config) {
-        return Ok("No FIDO-only hardware configuration changes were needed.".to_string());
+        return Ok(AppliedPlan {
+            plan: ConfigPlan {
+                path: WritePath::VendorConfig,
+                steps: Vec::new(),
+                dropped: Vec::new(),
+            },
+            note: "No FIDO-only hardware configuration changes were needed.".to_string(),
+        });
     }
 
     let transport = HidTransport::open().map_err(|e| {
@@ -1143,22 +1703,19 @@ pub fn write_config(config: AppConfigInput, pin: Option<String>) -> Result<Strin
         PFError::Device(format!("Could not open HID transport: {}", e))
     })?;
     let fido_info = read_device_info(&transport)?;
-    let firmware_type = if fido_info.aaguid == RSKEY_AAGUID {
-        FirmwareType::RSKey
-    } else if fido_info.aaguid == PICOFIDO_AAGUID || fido_info.aaguid == LKONE_AAGUID {
-        FirmwareType::PicoFido
-    } else {
-        FirmwareType::Unknown
-    };
-    let has_legacy_vendor =
-        firmware_type == FirmwareType::PicoFido && probe_legacy_vendor_support(&transport);
+    let firmware_type = firmware_type_of(&fido_info);
+    let has_legacy_vendor = firmware_type == FirmwareType::PicoFido
+        && tunables::vendor_read_enabled(VendorRead::PhysicalOptions)
+        && probe_legacy_vendor_support(&transport);
     let firmware = AnyFirmware::new_with_legacy(
         firmware_type.clone(),
         &fido_info.firmware_version,
         has_legacy_vendor,
     );
 
-    validate_fido_config_changes(&config, &firmware)?;
+    let plan =
+        validate_fido_config_changes(&config, &firmware)?.ok_or_else(fido_config_unsupported)?;
+    preflight_fido_config_write(&config, &fido_info, &firmware_type)?;
 
     let pin_val = pin.as_deref().ok_or_else(|| {
         log::error!("write_config called without any security PIN provided");
@@ -1167,88 +1724,179 @@ pub fn write_config(config: AppConfigInput, pin: Option<String>) -> Result<Strin
         )
     })?;
 
-    match firmware_type {
-        FirmwareType::RSKey => write_rskey_config(&transport, &config, pin_val),
-        FirmwareType::PicoFido if firmware.supports_fido_config_write() => {
-            write_legacy_hardware_config(&transport, &config, pin_val)
-        }
-        _ => {
-            log::error!(
-                "write_config called on unsupported firmware (pico-fido requires rescue mode)"
-            );
-            Err(PFError::Device(
-                "Hardware configuration over FIDO is not supported on this device. \
-                 Use rescue mode (CCID/PCSC) instead."
-                    .into(),
-            ))
-        }
-    }
+    let permission = plan.path.permission().ok_or_else(fido_config_unsupported)?;
+    let note = match plan.path {
+        WritePath::RsKeyRecord => write_rskey_config(&transport, &plan, pin_val, permission)?,
+        _ => write_legacy_hardware_config(&transport, &plan, pin_val, permission)?,
+    };
+    Ok(AppliedPlan { plan, note })
 }
 
 fn is_empty_config_input(config: &AppConfigInput) -> bool {
-    config.vid.is_none()
-        && config.pid.is_none()
-        && config.product_name.is_none()
-        && config.led_gpio.is_none()
-        && config.led_brightness.is_none()
-        && config.touch_timeout.is_none()
-        && config.led_driver.is_none()
-        && config.led_dimmable.is_none()
-        && config.power_cycle_on_reset.is_none()
-        && config.led_steady.is_none()
-        && config.enable_secp256k1.is_none()
-        // RS-Key-only PHY fields — `build_rskey_phy_tlv` writes these, so a
-        // change to any of them alone must not be treated as a no-op.
-        && config.raw_curves_mask.is_none()
-        && config.led_order.is_none()
-        && config.enabled_usb_itf.is_none()
-        && config.led_num.is_none()
+    // RS-Key-only PHY fields count too — `build_rskey_phy_tlv` writes them,
+    // so a change to any of them alone must not be treated as a no-op.
+    ConfigOperation::from_input(config).is_empty()
+}
+
+fn fido_config_unsupported() -> PFError {
+    log::error!("write_config called on unsupported firmware (pico-fido requires rescue mode)");
+    PFError::Device(
+        "This firmware does not support hardware configuration over FIDO. \
+         Use rescue mode for hardware changes."
+            .into(),
+    )
+}
+
+/// The path a FIDO configuration write takes on `firmware`, or `None` when
+/// the firmware can only be configured in rescue mode.
+fn fido_write_path(firmware: &AnyFirmware) -> Option<WritePath> {
+    if firmware.supports_rs_key_vendor_command() {
+        Some(WritePath::RsKeyRecord)
+    } else if firmware.supports_legacy_fido_hardware_config()
+        && firmware.supports_fido_config_write()
+    {
+        Some(WritePath::VendorConfig)
+    } else {
+        None
+    }
 }
 
+/// Plan `config` for the FIDO write path of `firmware`. `None` for an empty
+/// config on firmware that has no such path.
+///
+/// RS-Key's `CONFIG_WRITE` takes the full PHY TLV, so every field goes. The
+/// pico-fido vendor commands have no counterpart for the product string,
+/// the LED driver, the presence timeout or the curves; the plan refuses
+/// those rather than report success for a setting that was never sent.
 fn validate_fido_config_changes(
     config: &AppConfigInput,
     firmware: &AnyFirmware,
+) -> Result<Option<ConfigPlan>, PFError> {
+    match fido_write_path(firmware) {
+        Some(path) => ConfigPlan::new(config, path).map(Some),
+        None if is_empty_config_input(config) => Ok(None),
+        None => Err(fido_config_unsupported()),
+    }
+}
+
+/// Check that the authenticator can accept every command a config write will send.
+///
+/// Runs before the first write so an incompatible device fails fast with one
+/// consolidated explanation, instead of the first vendor command bouncing with
+/// `0x2B` (unsupported option) after part of the config was already applied.
+/// Checks the PIN/UV auth protocol, that a PIN is set, that `authenticatorConfig`
+/// is available (pico-fido vendor path only), and that every vendor command the
+/// apply needs is in the advertised vendor list (when the firmware advertises one).
+fn preflight_fido_config_write(
+    config: &AppConfigInput,
+    fido_info: &FidoDeviceInfo,
+    firmware_type: &FirmwareType,
 ) -> Result<(), PFError> {
-    let allow_write = firmware.supports_legacy_fido_hardware_config()
-        || firmware.supports_rs_key_vendor_command();
-    if !allow_write {
-        if config.vid.is_some()
-            || config.pid.is_some()
-            || config.product_name.is_some()
-            || config.led_gpio.is_some()
-            || config.led_brightness.is_some()
-            || config.touch_timeout.is_some()
-            || config.led_driver.is_some()
-            || config.led_dimmable.is_some()
-            || config.power_cycle_on_reset.is_some()
-            || config.led_steady.is_some()
-            || config.enable_secp256k1.is_some()
-        {
-            return Err(PFError::Device(
-                "This firmware does not support hardware configuration over FIDO. \
-                 Use rescue mode for hardware changes."
+    let mut problems = Vec::new();
+
+    if !fido_info.pin_protocols.contains(&PIN_UV_AUTH_PROTOCOL_ONE) {
+        problems.push(format!(
+            "PIN/UV auth protocol {} is not supported (device reports {:?}).",
+            PIN_UV_AUTH_PROTOCOL_ONE, fido_info.pin_protocols
+        ));
+    }
+
+    if fido_info.options.get("clientPin") != Some(&true) {
+        problems
+            .push("No PIN is set on the device. Set a PIN from the Security page first.".into());
+    }
+
+    if *firmware_type == FirmwareType::PicoFido {
+        if fido_info.options.get("authnrCfg") != Some(&true) {
+            problems.push(
+                "The authenticatorConfig command (authnrCfg) is not available, so a PIN token \
+                 with the ACFG permission cannot be used."
                     .into(),
+            );
+        }
+
+        let missing: Vec<String> = ConfigOperation::from_input(config)
+            .iter()
+            .filter_map(ConfigOperation::vendor_command)
+            .filter(|cmd| !advertises_vendor_config(firmware_type, fido_info, *cmd))
+            .map(|cmd| cmd.to_string())
+            .collect();
+        if !missing.is_empty() {
+            problems.push(format!(
+                "The firmware does not advertise these vendor commands: {}.",
+                missing.join(", ")
             ));
         }
+    }
+
+    if problems.is_empty() {
         return Ok(());
     }
 
-    // RS-Key: 0x41 CONFIG_WRITE supports the full PHY TLV — no field restrictions.
-    Ok(())
+    log::error!("Config write pre-flight failed: {:?}", problems);
+    Err(PFError::Device(format!(
+        "This device cannot apply the configuration over FIDO. Nothing was written.\n- {}",
+        problems.join("\n- ")
+    )))
+}
+
+/// Whether `e` is the key refusing the PIN token
+/// (`CTAP2_ERR_PIN_AUTH_INVALID`), e.g. because it expired mid-plan.
+fn token_rejected(e: &PFError) -> bool {
+    e.ctap() == Some(Ctap2Error::PinAuthInvalid)
 }
 
+/// The vendor command and parameter that carry `op`, or `None` for an
+/// operation the vendor commands do not cover.
+fn legacy_vendor_param(
+    transport: &HidTransport,
+    op: &ConfigOperation,
+) -> Result<Option<(VendorConfigCommand, Value)>, PFError> {
+    let param = match op {
+        ConfigOperation::SetVidPid {
+            vid: Some(vid_str),
+            pid: Some(pid_str),
+        } => {
+            let vid = u16::from_str_radix(vid_str, 16).map_err(|e| PFError::Io(e.to_string()))?;
+            let pid = u16::from_str_radix(pid_str, 16).map_err(|e| PFError::Io(e.to_string()))?;
+            ((vid as u32) << 16) | (pid as u32)
+        }
+        ConfigOperation::SetLedGpio(gpio) => *gpio as u32,
+        ConfigOperation::SetBrightness(brightness) => *brightness as u32,
+        ConfigOperation::SetOptions {
+            led_dimmable,
+            power_cycle_on_reset,
+            led_steady,
+        } => {
+            // Read-modify-write: bits without a switch must survive the write.
+            let current = read_legacy_physical_config(transport, AppConfig::default())
+                .raw_options
+                .ok_or_else(|| {
+                    PFError::Device(
+                        "Could not read the current device options; they were not changed.".into(),
+                    )
+                })?;
+            RescueOptions::merge(current, *led_dimmable, *power_cycle_on_reset, *led_steady) as u32
+        }
+        _ => return Ok(None),
+    };
+    Ok(op
+        .vendor_command()
+        .map(|command| (command, Value::Integer(param as i128))))
+}
+
+/// Send `plan` as pico-fido vendor commands, one per step, under one PIN
+/// token. A new token is only fetched when the key stops accepting the
+/// current one.
 fn write_legacy_hardware_config(
     transport: &HidTransport,
-    config: &AppConfigInput,
+    plan: &ConfigPlan,
     pin: &str,
+    permission: PinUvAuthTokenPermissions,
 ) -> Result<String, PFError> {
     let get_fresh_token = || -> Result<Vec<u8>, PFError> {
         transport
-            .get_pin_token_with_permission(
-                pin,
-                PinUvAuthTokenPermissions::AUTHENTICATOR_CONFIG,
-                None,
-            )
+            .get_pin_token_with_permission(pin, permission, None)
             .or_else(|e| {
                 log::warn!(
                     "Failed to get PIN token with ACFG permission (Error: {:?}). Falling back to standard token.",
@@ -1258,70 +1906,23 @@ fn write_legacy_hardware_config(
             })
     };
 
-    if let (Some(vid_str), Some(pid_str)) = (&config.vid, &config.pid) {
-        let vid = u16::from_str_radix(vid_str, 16).map_err(|e| PFError::Io(e.to_string()))?;
-        let pid = u16::from_str_radix(pid_str, 16).map_err(|e| PFError::Io(e.to_string()))?;
-        let vidpid = ((vid as u32) << 16) | (pid as u32);
-        transport.send_vendor_config(
-            &get_fresh_token()?,
-            VendorConfigCommand::PhysicalVidPid,
-            Value::Integer(vidpid as i128),
-        )?;
-    }
-
-    if let Some(gpio) = config.led_gpio {
-        transport.send_vendor_config(
-            &get_fresh_token()?,
-            VendorConfigCommand::PhysicalLedGpio,
-            Value::Integer(gpio as i128),
-        )?;
-    }
-
-    if let Some(brightness) = config.led_brightness {
-        transport.send_vendor_config(
-            &get_fresh_token()?,
-            VendorConfigCommand::PhysicalLedBrightness,
-            Value::Integer(brightness as i128),
-        )?;
-    }
-
-    if config.led_dimmable.is_some()
-        || config.power_cycle_on_reset.is_some()
-        || config.led_steady.is_some()
-    {
-        let current_config = read_legacy_physical_config(transport, AppConfig::default());
-        let mut opts = 0u16;
-        if config.led_dimmable.unwrap_or(current_config.led_dimmable) {
-            opts |= LEGACY_PHY_OPT_DIMMABLE;
-        }
-        if !config
-            .power_cycle_on_reset
-            .unwrap_or(current_config.power_cycle_on_reset)
-        {
-            opts |= LEGACY_PHY_OPT_DISABLE_POWER_RESET;
-        }
-        if config.led_steady.unwrap_or(current_config.led_steady) {
-            opts |= LEGACY_PHY_OPT_LED_STEADY;
+    let mut token = get_fresh_token()?;
+    for op in &plan.steps {
+        let Some((command, param)) = legacy_vendor_param(transport, op)? else {
+            continue;
+        };
+        log::info!("Applying {} ({})", op.label(), command);
+        match transport.send_vendor_config(&token, command, param.clone()) {
+            Err(e) if token_rejected(&e) => {
+                log::warn!("PIN token rejected during {}; getting a new one", command);
+                token = get_fresh_token()?;
+                transport.send_vendor_config(&token, command, param)?;
+            }
+            result => result?,
         }
-
-        transport.send_vendor_config(
-            &get_fresh_token()?,
-            VendorConfigCommand::PhysicalOptions,
-            Value::Integer(opts as i128),
-        )?;
-    }
-
-    if config.touch_timeout.is_some()
-        || config.led_driver.is_some()
-        || config.enable_secp256k1.is_some()
-    {
-        log::warn!(
-            "Legacy hardware config does not support touch_timeout, led_driver, or enable_secp256k1 \
-             fields. These were silently ignored. If your device supports them, file a feature request."
-        );
     }
 
-    Ok("Configuration updated successfully! Unplug and re-plug the device to apply VID/PID changes.".to_string())
+    Ok(plan.success_note())
 }
 
 /// Parse raw bytes from a certificate file into DER format.
@@ -1357,9 +1958,14 @@ pub(crate) fn upload_enterprise_attestation_cert(
         .map_err(|e| format!("Cannot read certificate file \"{}\": {}", cert_path, e))?;
 
     let cert_der = parse_cert_bytes(raw)?;
+    let summary = crate::hal::common::x509::summarize(&cert_der)
+        .map_err(|e| format!("Not an X.509 certificate: {}", e))?;
     log::info!(
-        "Certificate parsed ({} bytes). Uploading to device...",
-        cert_der.len()
+        "Certificate parsed ({} bytes, {}, serial {}, expires {}). Uploading to device...",
+        cert_der.len(),
+        summary.subject,
+        summary.serial,
+        summary.not_after
     );
 
     let transport =
@@ -1372,11 +1978,10 @@ pub(crate) fn upload_enterprise_attestation_cert(
             None,
         )
         .map_err(|e| {
-            let error_text = e.to_string();
-            if error_text.contains("0x2B") {
+            if e.ctap() == Some(Ctap2Error::UnsupportedOption) {
                 return "Device does not support enterprise attestation (0x2B). Ensure firmware is up to date.".to_string();
             }
-            format!("Failed to obtain PIN token: {}", error_text)
+            format!("Failed to obtain PIN token: {}", e)
         })?;
 
     transport
@@ -1388,7 +1993,10 @@ pub(crate) fn upload_enterprise_attestation_cert(
         .map_err(|e| format!("Failed to upload certificate: {}", e))?;
 
     log::info!("Enterprise attestation certificate uploaded successfully.");
-    Ok("Enterprise attestation certificate uploaded successfully.".to_string())
+    Ok(format!(
+        "Enterprise attestation certificate uploaded successfully ({}, expires {}).",
+        summary.subject, summary.not_after
+    ))
 }
 
 pub(crate) fn enable_enterprise_attestation(pin: String) -> Result<String, String> {
@@ -1404,18 +2012,24 @@ pub(crate) fn enable_enterprise_attestation(pin: String) -> Result<String, Strin
             None,
         )
         .map_err(|e| {
-            let error_text = e.to_string();
-            log::error!("Failed to get PIN token: {}", error_text);
-            if error_text.contains("0x2B") {
+            log::error!("Failed to get PIN token: {}", e);
+            if e.ctap() == Some(Ctap2Error::UnsupportedOption) {
                 return "Device does not support enterprise attestation (0x2B). Ensure firmware is up to date.".to_string();
             }
-            format!("Failed to obtain PIN token: {}", error_text)
+            format!("Failed to obtain PIN token: {}", e)
         })?;
 
     transport
-        .send_config_enable_ea(&pin_token)
+        .enable_enterprise_attestation(&pin_token)
         .map_err(|e| format!("Failed to enable enterprise attestation: {}", e))?;
 
+    let info = read_device_info(&transport).map_err(|e| e.to_string())?;
+    if info.options.get("ep") != Some(&true) {
+        return Err("The key accepted the command but still reports enterprise \
+                    attestation as off."
+            .into());
+    }
+
     Ok("Enterprise attestation enabled successfully.".into())
 }
 
@@ -1561,6 +2175,10 @@ pub(crate) fn write_rskey_dev_config(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::hal::config_plan::{
+        CURVES_UNSUPPORTED_OVER_FIDO, LED_DRIVER_UNSUPPORTED_OVER_FIDO,
+        PRODUCT_NAME_UNSUPPORTED_OVER_FIDO, TOUCH_TIMEOUT_UNSUPPORTED_OVER_FIDO,
+    };
     use serde_cbor_2::Value;
     use std::collections::BTreeMap;
 
@@ -1584,6 +2202,114 @@ mod tests {
         }
     }
 
+    fn stored_credential(rp_name: &str) -> StoredCredential {
+        StoredCredential {
+            rp_id: "example.com".into(),
+            rp_name: rp_name.into(),
+            user_name: "alice".into(),
+            user_display_name: String::new(),
+            user_id: "0102".into(),
+            credential_id: "abcd".into(),
+            algorithm: Some(-7),
+            key_type: Some(2),
+            curve: Some(1),
+            cred_protect: Some(CredProtectPolicy::UserVerificationRequired),
+            public_key: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_cose_key_params() {
+        let mut key = BTreeMap::new();
+        key.insert(Value::Integer(1), Value::Integer(2));
+        key.insert(Value::Integer(3), Value::Integer(-7));
+        key.insert(Value::Integer(-1), Value::Integer(1));
+        key.insert(Value::Integer(-2), Value::Bytes(vec![0; 32]));
+        assert_eq!(
+            cose_key_params(&Value::Map(key)),
+            (Some(2), Some(-7), Some(1))
+        );
+        assert_eq!(cose_key_params(&Value::Null), (None, None, None));
+
+        let cred = stored_credential("Example");
+        assert_eq!(cred.key_description(), "ES256 / P-256");
+        let rsa = StoredCredential {
+            algorithm: Some(-257),
+            key_type: Some(3),
+            curve: None,
+            ..cred.clone()
+        };
+        assert_eq!(rsa.key_description(), "RS256 / RSA");
+        let unknown = StoredCredential {
+            algorithm: Some(-999),
+            key_type: None,
+            curve: None,
+            ..cred
+        };
+        assert_eq!(unknown.key_description(), "alg -999");
+    }
+
+    #[test]
+    fn test_cred_protect_policy_levels() {
+        assert_eq!(
+            CredProtectPolicy::from_u8(1),
+            Some(CredProtectPolicy::UserVerificationOptional)
+        );
+        assert_eq!(
+            CredProtectPolicy::from_u8(3).map(CredProtectPolicy::label),
+            Some("UV required")
+        );
+        assert_eq!(CredProtectPolicy::from_u8(0), None);
+        assert_eq!(CredProtectPolicy::from_u8(4), None);
+    }
+
+    #[test]
+    fn test_export_credentials_csv_quotes_and_escapes() {
+        let credentials = [
+            stored_credential("Example, Inc."),
+            stored_credential("=cmd"),
+        ];
+        let csv = serialize_credentials(&credentials, CredentialExportFormat::Csv, 1_700_000_000)
+            .unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], CREDENTIAL_EXPORT_COLUMNS.join(","));
+        assert_eq!(
+            lines[1],
+            "example.com,\"Example, Inc.\",alice,,0102,abcd,ES256,1700000000"
+        );
+        assert!(lines[2].starts_with("example.com,'=cmd,"));
+    }
+
+    #[test]
+    fn test_export_credentials_json() {
+        let json = serialize_credentials(
+            &[stored_credential("Example")],
+            CredentialExportFormat::Json,
+            42,
+        )
+        .unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["exportedAt"], 42);
+        assert_eq!(value["credentials"][0]["rpId"], "example.com");
+        assert_eq!(value["credentials"][0]["algorithm"], "ES256");
+    }
+
+    #[test]
+    fn test_user_entity_keeps_id_and_drops_empty_names() {
+        let Value::Map(user) = user_entity(vec![1, 2], "alice", "") else {
+            unreachable!()
+        };
+        assert_eq!(
+            user.get(&Value::Text("id".into())),
+            Some(&Value::Bytes(vec![1, 2]))
+        );
+        assert_eq!(
+            user.get(&Value::Text("name".into())),
+            Some(&Value::Text("alice".into()))
+        );
+        assert!(!user.contains_key(&Value::Text("displayName".into())));
+    }
+
     #[test]
     fn test_parse_get_info_pico_fido_76_vendor_commands_at_0x15() {
         let mut map = BTreeMap::new();
@@ -1716,6 +2442,43 @@ mod tests {
         assert!(parse_management_info(&[0x01, 0x02, 0xAA]).is_err());
     }
 
+    #[test]
+    fn test_apply_legacy_physical_options() {
+        let mut map = BTreeMap::new();
+        map.insert(
+            Value::Integer(1),
+            Value::Integer((LEGACY_PHY_OPT_DIMMABLE | LEGACY_PHY_OPT_DISABLE_POWER_RESET) as i128),
+        );
+        let raw = serde_cbor_2::to_vec(&Value::Map(map)).unwrap();
+        let config = apply_legacy_physical_options(&raw, AppConfig::default());
+        assert!(config.led_dimmable);
+        assert!(!config.power_cycle_on_reset);
+        assert!(!config.led_steady);
+        assert_eq!(config.raw_options, Some(0x06));
+    }
+
+    #[test]
+    fn test_apply_rskey_physical_config_defaults_missing_options() {
+        let raw = [RSKEY_PHY_TAG_LED_GPIO, 0x01, 25];
+        let config = apply_rskey_physical_config(&raw, AppConfig::default());
+        assert_eq!(config.led_gpio, Some(25));
+        assert_eq!(config.raw_options, Some(0));
+        assert!(config.power_cycle_on_reset);
+    }
+
+    #[test]
+    fn test_parse_legacy_memory_stats() {
+        let mut map = BTreeMap::new();
+        map.insert(MemoryResponseKey::UsedSpace as i128, 4096i128);
+        map.insert(MemoryResponseKey::TotalSpace as i128, 1_048_576i128);
+        let raw = serde_cbor_2::to_vec(&map).unwrap();
+        assert_eq!(
+            parse_legacy_memory_stats(&raw).unwrap(),
+            Some((4096, 1_048_576))
+        );
+        assert_eq!(parse_legacy_memory_stats(&[]).unwrap(), None);
+    }
+
     #[test]
     fn test_firmware_supports_legacy_fido_hardware_config() {
         let check = |v: &str| -> bool {
@@ -1769,17 +2532,79 @@ mod tests {
 
     #[test]
     fn test_validate_fido_config_changes_accepts_all_common_fields_in_legacy_mode() {
-        // With legacy vendor support, all fields are accepted — no LkOne-style
-        // VID/PID-only restriction exists for the CONFIG_WRITE path.
+        // With legacy vendor support, the common fields are accepted — no
+        // LkOne-style VID/PID-only restriction exists for the CONFIG_WRITE path.
         let mut config = empty_config_input();
         config.led_gpio = Some(25);
-        config.product_name = Some("Pico Key".to_string());
-        config.touch_timeout = Some(30);
+        config.led_brightness = Some(8);
 
         let fw = AnyFirmware::new_with_legacy(FirmwareType::PicoFido, "7.6", true);
         assert!(validate_fido_config_changes(&config, &fw).is_ok());
     }
 
+    #[test]
+    fn test_validate_fido_config_changes_rejects_product_name_on_legacy_path() {
+        let mut config = empty_config_input();
+        config.product_name = Some("Pico Key".to_string());
+
+        let fw = AnyFirmware::new_with_legacy(FirmwareType::PicoFido, "7.2", true);
+        let err = validate_fido_config_changes(&config, &fw)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains(PRODUCT_NAME_UNSUPPORTED_OVER_FIDO));
+
+        let rskey = AnyFirmware::new(FirmwareType::RSKey, "5.7");
+        assert!(validate_fido_config_changes(&config, &rskey).is_ok());
+    }
+
+    #[test]
+    fn test_validate_fido_config_changes_rejects_curves_on_legacy_path() {
+        let fw = AnyFirmware::new_with_legacy(FirmwareType::PicoFido, "7.2", true);
+        let rskey = AnyFirmware::new(FirmwareType::RSKey, "5.7");
+
+        let mut config = empty_config_input();
+        config.enable_secp256k1 = Some(true);
+        let err = validate_fido_config_changes(&config, &fw)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains(CURVES_UNSUPPORTED_OVER_FIDO));
+        assert!(validate_fido_config_changes(&config, &rskey).is_ok());
+
+        let mut config = empty_config_input();
+        config.raw_curves_mask = Some(0x09);
+        assert!(validate_fido_config_changes(&config, &fw).is_err());
+    }
+
+    #[test]
+    fn test_validate_fido_config_changes_rejects_led_driver_on_legacy_path() {
+        let mut config = empty_config_input();
+        config.led_driver = Some(3);
+
+        let fw = AnyFirmware::new_with_legacy(FirmwareType::PicoFido, "7.2", true);
+        let err = validate_fido_config_changes(&config, &fw)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains(LED_DRIVER_UNSUPPORTED_OVER_FIDO));
+
+        let rskey = AnyFirmware::new(FirmwareType::RSKey, "5.7");
+        assert!(validate_fido_config_changes(&config, &rskey).is_ok());
+    }
+
+    #[test]
+    fn test_validate_fido_config_changes_rejects_touch_timeout_on_legacy_path() {
+        let mut config = empty_config_input();
+        config.touch_timeout = Some(15);
+
+        let fw = AnyFirmware::new_with_legacy(FirmwareType::PicoFido, "7.2", true);
+        let err = validate_fido_config_changes(&config, &fw)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains(TOUCH_TIMEOUT_UNSUPPORTED_OVER_FIDO));
+
+        let rskey = AnyFirmware::new(FirmwareType::RSKey, "5.7");
+        assert!(validate_fido_config_changes(&config, &rskey).is_ok());
+    }
+
     #[test]
     fn test_validate_fido_config_changes_accepts_rskey_all_fields() {
         // RS-Key accepts all fields via CONFIG_WRITE TLV.
@@ -1880,6 +2705,25 @@ mod tests {
         assert_eq!(info.max_msg_size, 1024);
     }
 
+    #[test]
+    fn test_parse_get_info_keeps_unknown_keys_raw() {
+        let mut map = BTreeMap::new();
+        map.insert(Value::Integer(0x05), Value::Integer(1024));
+        // Known but unused keys are not reported.
+        map.insert(Value::Integer(0x1B), Value::Integer(4));
+        let vendor = Value::Map(BTreeMap::from([(
+            Value::Text("otp".into()),
+            Value::Bool(true),
+        )]));
+        map.insert(Value::Integer(0x1D), vendor.clone());
+
+        let info = parse_fido_get_info(&Value::Map(map)).unwrap();
+        assert_eq!(info.unknown_fields.len(), 1);
+        let field = &info.unknown_fields[0];
+        assert_eq!(field.label(), "0x1D");
+        assert_eq!(from_slice::<Value>(&field.cbor).unwrap(), vendor);
+    }
+
     #[test]
     fn test_parse_get_info_minimal_response() {
         let mut map = BTreeMap::new();
@@ -1968,9 +2812,9 @@ mod tests {
     fn test_build_rskey_phy_tlv_rejects_overlong_product_name() {
         let mut c = empty_config_input();
         c.product_name = Some("x".repeat(32)); // 32 + NUL = 33, the firmware max
-        assert!(build_rskey_phy_tlv(&c).is_ok());
+        assert!(build_rskey_phy_tlv(&c, None).is_ok());
         c.product_name = Some("x".repeat(33)); // 33 + NUL = 34, over the limit
-        assert!(build_rskey_phy_tlv(&c).is_err());
+        assert!(build_rskey_phy_tlv(&c, None).is_err());
     }
 
     #[test]
@@ -1978,9 +2822,240 @@ mod tests {
         let mut c = empty_config_input();
         c.led_num = Some(3);
         c.led_order = Some(1);
-        let tlv = build_rskey_phy_tlv(&c).unwrap();
+        let tlv = build_rskey_phy_tlv(&c, None).unwrap();
         // tag 0x0E len 0x01 val 0x03, and tag 0x0D len 0x01 val 0x01
         assert!(tlv.windows(3).any(|w| w == [0x0E, 0x01, 0x03]));
         assert!(tlv.windows(3).any(|w| w == [0x0D, 0x01, 0x01]));
     }
+
+    #[test]
+    fn test_build_rskey_phy_tlv_preserves_unknown_option_bits() {
+        let mut c = empty_config_input();
+        c.led_dimmable = Some(true);
+        // WCID plus a bit no current firmware defines, power-reset protection off.
+        let tlv = build_rskey_phy_tlv(&c, Some(0x0105)).unwrap();
+        assert_eq!(tlv, vec![RSKEY_PHY_TAG_OPTS, 0x02, 0x01, 0x07]);
+
+        c.power_cycle_on_reset = Some(true);
+        let tlv = build_rskey_phy_tlv(&c, Some(0x0105)).unwrap();
+        assert_eq!(tlv, vec![RSKEY_PHY_TAG_OPTS, 0x02, 0x01, 0x03]);
+    }
+
+    #[test]
+    fn test_build_rskey_phy_tlv_skips_untouched_options() {
+        let mut c = empty_config_input();
+        c.led_num = Some(3);
+        let tlv = build_rskey_phy_tlv(&c, Some(0x0004)).unwrap();
+        assert!(!tlv.contains(&RSKEY_PHY_TAG_OPTS));
+    }
+
+    #[test]
+    fn test_rskey_phy_options() {
+        assert_eq!(
+            rskey_phy_options(&[0x04, 0x01, 0x19, 0x06, 0x02, 0x00, 0x0A]),
+            Some(0x000A)
+        );
+        assert_eq!(rskey_phy_options(&[0x04, 0x01, 0x19]), None);
+        assert_eq!(rskey_phy_options(&[0x06, 0x02, 0x00]), None);
+    }
+
+    fn preflight_info(
+        protocols: &[i128],
+        options: &[(&str, bool)],
+        vendor: &[u64],
+    ) -> FidoDeviceInfo {
+        let mut map = BTreeMap::new();
+        map.insert(
+            Value::Integer(0x06),
+            Value::Array(protocols.iter().map(|p| Value::Integer(*p)).collect()),
+        );
+        let opts = options
+            .iter()
+            .map(|(k, v)| (Value::Text(k.to_string()), Value::Bool(*v)))
+            .collect();
+        map.insert(Value::Integer(0x04), Value::Map(opts));
+        if !vendor.is_empty() {
+            map.insert(
+                Value::Integer(0x15),
+                Value::Array(vendor.iter().map(|v| Value::Integer(*v as i128)).collect()),
+            );
+        }
+        parse_fido_get_info(&Value::Map(map)).unwrap()
+    }
+
+    #[test]
+    fn test_is_pico_device() {
+        let generic = preflight_info(&[2, 1], &[("clientPin", true)], &[]);
+        assert!(!is_pico_device(&FirmwareType::Unknown, &generic));
+        assert!(is_pico_device(&FirmwareType::PicoFido, &generic));
+
+        let custom_build = preflight_info(
+            &[2, 1],
+            &[("clientPin", true)],
+            &[VendorConfigCommand::PhysicalVidPid as u64],
+        );
+        assert!(is_pico_device(&FirmwareType::Unknown, &custom_build));
+    }
+
+    #[test]
+    fn test_advertises_vendor_config() {
+        let listed = preflight_info(
+            &[2, 1],
+            &[("clientPin", true)],
+            &[VendorConfigCommand::PhysicalVidPid as u64],
+        );
+        let unlisted = preflight_info(&[2, 1], &[("clientPin", true)], &[]);
+        let led = VendorConfigCommand::PhysicalLedGpio;
+
+        assert!(advertises_vendor_config(
+            &FirmwareType::PicoFido,
+            &listed,
+            VendorConfigCommand::PhysicalVidPid
+        ));
+        assert!(!advertises_vendor_config(
+            &FirmwareType::PicoFido,
+            &listed,
+            led
+        ));
+        assert!(advertises_vendor_config(
+            &FirmwareType::PicoFido,
+            &unlisted,
+            led
+        ));
+        assert!(advertises_vendor_config(&FirmwareType::RSKey, &listed, led));
+    }
+
+    fn vidpid_config() -> AppConfigInput {
+        let mut c = empty_config_input();
+        c.vid = Some("FEFF".to_string());
+        c.pid = Some("FCFD".to_string());
+        c
+    }
+
+    #[test]
+    fn test_preflight_accepts_capable_pico_fido() {
+        let info = preflight_info(
+            &[2, 1],
+            &[("clientPin", true), ("authnrCfg", true)],
+            &[VendorConfigCommand::PhysicalVidPid as u64],
+        );
+        assert!(
+            preflight_fido_config_write(&vidpid_config(), &info, &FirmwareType::PicoFido).is_ok()
+        );
+    }
+
+    #[test]
+    fn test_preflight_skips_vendor_check_when_list_not_advertised() {
+        let info = preflight_info(&[1], &[("clientPin", true), ("authnrCfg", true)], &[]);
+        assert!(
+            preflight_fido_config_write(&vidpid_config(), &info, &FirmwareType::PicoFido).is_ok()
+        );
+    }
+
+    #[test]
+    fn test_preflight_consolidates_every_problem() {
+        let mut config = vidpid_config();
+        config.led_gpio = Some(25);
+        let info = preflight_info(
+            &[2],
+            &[("clientPin", false)],
+            &[VendorConfigCommand::PhysicalVidPid as u64],
+        );
+
+        let err = preflight_fido_config_write(&config, &info, &FirmwareType::PicoFido)
+            .unwrap_err()
+            .to_string();
+
+        assert!(err.contains("Nothing was written"));
+        assert!(err.contains("protocol 1"));
+        assert!(err.contains("No PIN is set"));
+        assert!(err.contains("authnrCfg"));
+        assert!(err.contains("PhysicalLedGpio"));
+        assert!(!err.contains("PhysicalVidPid"));
+    }
+
+    #[test]
+    fn test_preflight_rskey_does_not_require_authnr_cfg() {
+        let info = preflight_info(&[1], &[("clientPin", true)], &[]);
+        assert!(preflight_fido_config_write(&vidpid_config(), &info, &FirmwareType::RSKey).is_ok());
+    }
+
+    #[test]
+    fn test_pin_lockout_from_error() {
+        assert_eq!(
+            pin_lockout_from_error(Ctap2Error::PinBlocked),
+            Some(PinLockout::Blocked)
+        );
+        assert_eq!(
+            pin_lockout_from_error(Ctap2Error::PinAuthBlocked),
+            Some(PinLockout::PowerCycleRequired)
+        );
+        assert_eq!(pin_lockout_from_error(Ctap2Error::PinInvalid), None);
+    }
+
+    #[test]
+    fn test_parse_credential_id_hex() {
+        let id = "f0f1f2f3f4f5f6f7f8f9fafbfcfdfefffbff3e";
+        assert_eq!(parse_credential_id(id).unwrap(), id);
+        assert_eq!(
+            parse_credential_id("  0xF0F1F2F3 F4F5F6F7 F8F9FAFB FCFDFEFF FBFF3E ").unwrap(),
+            id
+        );
+        assert_eq!(
+            parse_credential_id("f0:f1:f2:f3:f4:f5:f6:f7:f8:f9:fa:fb:fc:fd:fe:ff:fb:ff:3e")
+                .unwrap(),
+            id
+        );
+    }
+
+    #[test]
+    fn test_parse_credential_id_base64() {
+        let id = "f0f1f2f3f4f5f6f7f8f9fafbfcfdfefffbff3e";
+        assert_eq!(
+            parse_credential_id("8PHy8_T19vf4-fr7_P3-__v_Pg").unwrap(),
+            id
+        );
+        assert_eq!(
+            parse_credential_id("8PHy8_T19vf4-fr7_P3-__v_Pg==").unwrap(),
+            id
+        );
+        assert_eq!(
+            parse_credential_id("8PHy8/T19vf4+fr7/P3+//v/Pg==").unwrap(),
+            id
+        );
+    }
+
+    #[test]
+    fn test_parse_credential_id_rejects_bad_input() {
+        assert!(parse_credential_id("").is_err());
+        assert!(parse_credential_id("not a credential!").is_err());
+        // Valid hex, but too short to be a credential ID.
+        assert!(parse_credential_id("deadbeef").is_err());
+        assert!(parse_credential_id(&"ab".repeat(1024)).is_err());
+    }
+
+    #[test]
+    fn test_pin_lockout_from_retries() {
+        let blocked = PinRetries {
+            retries: 0,
+            power_cycle_required: true,
+        };
+        let soft = PinRetries {
+            retries: 5,
+            power_cycle_required: true,
+        };
+        let ok = PinRetries {
+            retries: 8,
+            power_cycle_required: false,
+        };
+        assert_eq!(
+            pin_lockout_from_retries(&blocked),
+            Some(PinLockout::Blocked)
+        );
+        assert_eq!(
+            pin_lockout_from_retries(&soft),
+            Some(PinLockout::PowerCycleRequired)
+        );
+        assert_eq!(pin_lockout_from_retries(&ok), None);
+    }
 }
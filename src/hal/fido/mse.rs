@@ -0,0 +1,184 @@
+//! Vendor MSE (Manage Security Environment) key agreement.
+//!
+//! pico-fido keeps host secrets off the wire in the clear by running an ECDH
+//! handshake first (`VendorCommand::ManageSecurityEnvironment`,
+//! `MseSubCommand::KeyAgreement`). The host sends a fresh P-256 platform key
+//! as a COSE key and gets the device's back; both sides derive the same
+//! channel from the shared point. Commands that carry a secret (secure lock
+//! enable and unlock) send it sealed under that channel, and firmware that
+//! mandates a secure channel for other vendor commands (the encrypted
+//! backup) only answers them once an agreement has run in the session.
+//!
+//! The channel is ECDH on P-256. HKDF-SHA-256 over the x-coordinate of the
+//! shared point, with no salt and the platform's uncompressed public point
+//! as info, yields 44 bytes: a 12-byte nonce and a 32-byte
+//! ChaCha20-Poly1305 key. The platform point is also the AAD. One channel
+//! seals one message; every command runs its own agreement.
+
+use super::constants::Ctap2Error;
+use super::ops::FidoOperations;
+use crate::error::PFError;
+use crate::hal::transport::fido::HidTransport;
+use ring::{aead, agreement, hkdf};
+use serde_cbor_2::{Value, from_slice};
+
+/// Output of the channel KDF: nonce, then key.
+const CHANNEL_OKM_LEN: usize = 12 + 32;
+
+/// One MSE channel.
+pub(super) struct SecureChannel {
+    key: [u8; 32],
+    nonce: [u8; 12],
+    /// Uncompressed platform point (`0x04 || x || y`), the AAD.
+    platform_point: Vec<u8>,
+}
+
+struct OkmLen(usize);
+
+impl hkdf::KeyType for OkmLen {
+    fn len(&self) -> usize {
+        self.0
+    }
+}
+
+impl SecureChannel {
+    /// Run the key agreement with the key and derive the channel.
+    pub(super) fn establish(transport: &HidTransport) -> Result<Self, PFError> {
+        let rng = ring::rand::SystemRandom::new();
+        let private_key = agreement::EphemeralPrivateKey::generate(&agreement::ECDH_P256, &rng)
+            .map_err(|_| PFError::Device("Failed to generate platform ephemeral key".into()))?;
+        let public_key = private_key
+            .compute_public_key()
+            .map_err(|_| PFError::Device("Failed to compute platform public key".into()))?;
+        let platform_point = public_key.as_ref().to_vec();
+
+        let cose_key = transport.encode_cose_key(&platform_point[1..33], &platform_point[33..65]);
+        let platform_key = from_slice(&cose_key).map_err(|e| PFError::Io(e.to_string()))?;
+        let (x, y) = match transport.mse_key_agreement(platform_key)? {
+            Value::Map(m) => match (m.get(&Value::Integer(-2)), m.get(&Value::Integer(-3))) {
+                (Some(Value::Bytes(x)), Some(Value::Bytes(y))) => (x.clone(), y.clone()),
+                _ => return Err(PFError::Device("Invalid MSE key coordinates".into())),
+            },
+            _ => return Err(PFError::Device("Invalid MSE key format".into())),
+        };
+        let mut device_point = vec![0x04];
+        device_point.extend(x);
+        device_point.extend(y);
+
+        let shared_x = agreement::agree_ephemeral(
+            private_key,
+            &agreement::UnparsedPublicKey::new(&agreement::ECDH_P256, device_point),
+            |material| material.to_vec(),
+        )
+        .map_err(|_| PFError::Device("ECDH shared secret computation failed".into()))?;
+        Self::derive(&shared_x, &platform_point)
+    }
+
+    /// Derive the channel from the shared x-coordinate and the platform
+    /// point.
+    fn derive(shared_x: &[u8], platform_point: &[u8]) -> Result<Self, PFError> {
+        let info = [platform_point];
+        let mut okm = [0u8; CHANNEL_OKM_LEN];
+        hkdf::Salt::new(hkdf::HKDF_SHA256, &[])
+            .extract(shared_x)
+            .expand(&info, OkmLen(CHANNEL_OKM_LEN))
+            .and_then(|okm_material| okm_material.fill(&mut okm))
+            .map_err(|_| PFError::Device("Could not derive the MSE channel keys".into()))?;
+
+        let mut nonce = [0u8; 12];
+        let mut key = [0u8; 32];
+        nonce.copy_from_slice(&okm[..12]);
+        key.copy_from_slice(&okm[12..]);
+        Ok(Self {
+            key,
+            nonce,
+            platform_point: platform_point.to_vec(),
+        })
+    }
+
+    /// ChaCha20-Poly1305 of `plaintext`, tag appended. Consumes the channel:
+    /// its nonce must not seal a second message.
+    pub(super) fn seal(self, plaintext: &[u8]) -> Result<Vec<u8>, PFError> {
+        let key = aead::UnboundKey::new(&aead::CHACHA20_POLY1305, &self.key)
+            .map(aead::LessSafeKey::new)
+            .map_err(|_| PFError::Device("Invalid MSE channel key".into()))?;
+        let mut data = plaintext.to_vec();
+        key.seal_in_place_append_tag(
+            aead::Nonce::assume_unique_for_key(self.nonce),
+            aead::Aad::from(self.platform_point.as_slice()),
+            &mut data,
+        )
+        .map_err(|_| PFError::Device("Could not seal data for the MSE channel".into()))?;
+        Ok(data)
+    }
+}
+
+/// Status pico-fido answers a vendor command with when it mandates a secure
+/// channel and no key agreement has run in the session.
+const SECURE_CHANNEL_REQUIRED: Ctap2Error = Ctap2Error::NotAllowed;
+
+/// Run `command`; if the key refuses it because it wants a secure channel,
+/// run the key agreement and try once more. Firmware that does not mandate
+/// one answers the first attempt, so the handshake only costs a round trip
+/// where it is needed. Any other failure, including a transport error after
+/// the command may have reached the key, is returned as is: the command is
+/// never sent twice unless the key turned it down without acting on it.
+pub(super) fn with_channel_fallback<T>(
+    transport: &HidTransport,
+    what: &str,
+    command: impl Fn(&HidTransport) -> Result<T, PFError>,
+) -> Result<T, PFError> {
+    match command(transport) {
+        Err(e) if e.ctap() == Some(SECURE_CHANNEL_REQUIRED) => {
+            log::info!("{} refused ({}); retrying after MSE key agreement", what, e);
+            SecureChannel::establish(transport)?;
+            command(transport)
+        }
+        result => result,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(fill: u8) -> Vec<u8> {
+        let mut point = vec![0x04];
+        point.extend([fill; 64]);
+        point
+    }
+
+    #[test]
+    fn test_channel_depends_on_secret_and_point() {
+        let a = SecureChannel::derive(&[1; 32], &point(2)).unwrap();
+        let b = SecureChannel::derive(&[1; 32], &point(3)).unwrap();
+        let c = SecureChannel::derive(&[4; 32], &point(2)).unwrap();
+        assert_ne!(a.key, b.key);
+        assert_ne!(a.nonce, b.nonce);
+        assert_ne!(a.key, c.key);
+        assert_eq!(
+            a.key,
+            SecureChannel::derive(&[1; 32], &point(2)).unwrap().key
+        );
+    }
+
+    #[test]
+    fn test_sealed_data_opens_with_channel_key_and_aad() {
+        let channel = SecureChannel::derive(&[9; 32], &point(7)).unwrap();
+        let (key, nonce) = (channel.key, channel.nonce);
+        let secret = [0x5A; 32];
+        let mut sealed = channel.seal(&secret).unwrap();
+        assert_eq!(sealed.len(), secret.len() + 16);
+
+        let key =
+            aead::LessSafeKey::new(aead::UnboundKey::new(&aead::CHACHA20_POLY1305, &key).unwrap());
+        let opened = key
+            .open_in_place(
+                aead::Nonce::assume_unique_for_key(nonce),
+                aead::Aad::from(point(7).as_slice()),
+                &mut sealed,
+            )
+            .unwrap();
+        assert_eq!(opened, &secret);
+    }
+}
@@ -12,7 +12,9 @@ use std::collections::BTreeMap;
 
 use crate::error::PFError;
 use crate::hal::fido::constants::*;
+use crate::hal::fido::pin;
 use crate::hal::transport::fido::{CTAPHID_CBOR, HidTransport};
+use crate::hal::types::{CredentialsMetadata, PinRetries};
 
 /// Returned by [`HidTransport::credential_management_enumerate_rps`]. Each entry
 /// represents one RP stored on the authenticator.
@@ -36,6 +38,107 @@ pub struct EnumerateCredentialResponse {
     pub public_key: Value,
     #[allow(dead_code)]
     pub total_credentials: Option<usize>,
+    /// `credProtect` level (1–3), absent on CTAP 2.0 authenticators.
+    pub cred_protect: Option<u8>,
+}
+
+/// The optional `credProtect` member of an enumerateCredentials response.
+fn parse_cred_protect(m: &BTreeMap<Value, Value>) -> Option<u8> {
+    match m.get(&Value::Integer(
+        CredentialMgmtResponseParam::CredProtect as i128,
+    )) {
+        Some(Value::Integer(level)) => u8::try_from(*level).ok(),
+        _ => None,
+    }
+}
+
+/// Parse a `getPinRetries` response map.
+fn parse_pin_retries(val: &Value) -> Result<PinRetries, PFError> {
+    let Value::Map(m) = val else {
+        return Err(PFError::Device(
+            "Unexpected response for GetPinRetries".into(),
+        ));
+    };
+
+    let retries = match m.get(&Value::Integer(ClientPinResponseParam::PinRetries as i128)) {
+        Some(Value::Integer(n)) if *n >= 0 => *n as u32,
+        _ => {
+            return Err(PFError::Device("PinRetries not found in response".into()));
+        }
+    };
+    let power_cycle_required = matches!(
+        m.get(&Value::Integer(
+            ClientPinResponseParam::PowerCycleState as i128
+        )),
+        Some(Value::Bool(true))
+    );
+
+    Ok(PinRetries {
+        retries,
+        power_cycle_required,
+    })
+}
+
+/// Parse a `getUVRetries` response map.
+fn parse_uv_retries(val: &Value) -> Result<u32, PFError> {
+    let Value::Map(m) = val else {
+        return Err(PFError::Device(
+            "Unexpected response for GetUvRetries".into(),
+        ));
+    };
+    match m.get(&Value::Integer(ClientPinResponseParam::UvRetries as i128)) {
+        Some(Value::Integer(n)) if *n >= 0 => Ok(*n as u32),
+        _ => Err(PFError::Device("UvRetries not found in response".into())),
+    }
+}
+
+/// Parse a `getCredsMetadata` response map.
+fn parse_creds_metadata(val: &Value) -> Result<CredentialsMetadata, PFError> {
+    let Value::Map(m) = val else {
+        return Err(PFError::Device(
+            "Unexpected response for GetCredsMetadata".into(),
+        ));
+    };
+    let count = |param: CredentialMgmtResponseParam| match m.get(&Value::Integer(param as i128)) {
+        Some(Value::Integer(n)) if *n >= 0 => Ok(*n as u32),
+        _ => Err(PFError::Device(format!(
+            "{:?} not found in GetCredsMetadata response",
+            param
+        ))),
+    };
+    Ok(CredentialsMetadata {
+        existing: count(CredentialMgmtResponseParam::ExistingResidentCredentialsCount)?,
+        remaining: count(
+            CredentialMgmtResponseParam::MaxPossibleRemainingResidentCredentialsCount,
+        )?,
+    })
+}
+
+/// subCommandParams for setMinPINLength: `{0x01: len, 0x02: [rpIds], 0x03: true}`,
+/// with the optional entries left out when unused.
+fn min_pin_length_params(
+    new_min_pin_length: u8,
+    rp_ids: &[String],
+    force_change_pin: bool,
+) -> Value {
+    let mut params = BTreeMap::new();
+    params.insert(
+        Value::Integer(ConfigSubCommandParam::NewMinPinLength as i128),
+        Value::Integer(new_min_pin_length as i128),
+    );
+    if !rp_ids.is_empty() {
+        params.insert(
+            Value::Integer(ConfigSubCommandParam::MinPinLengthRPIDs as i128),
+            Value::Array(rp_ids.iter().map(|id| Value::Text(id.clone())).collect()),
+        );
+    }
+    if force_change_pin {
+        params.insert(
+            Value::Integer(ConfigSubCommandParam::ForceChangePin as i128),
+            Value::Bool(true),
+        );
+    }
+    Value::Map(params)
 }
 
 /// Low-level CTAP2 operations implemented on the FIDO HID transport.
@@ -54,6 +157,16 @@ pub trait FidoOperations {
     ) -> Result<(), PFError>;
     /// Retrieve the enterprise attestation CSR from the authenticator.
     fn get_enterprise_attestation_csr(&self) -> Result<Vec<u8>, PFError>;
+    /// Export the key's encrypted backup blob (pico-fido vendor Backup).
+    fn get_encrypted_backup(&self) -> Result<Vec<u8>, PFError>;
+    /// Hand an encrypted backup blob back to the key to restore it.
+    fn restore_encrypted_backup(&self, blob: &[u8]) -> Result<(), PFError>;
+    /// Run the vendor MSE key agreement: send the platform COSE key and
+    /// return the device's.
+    fn mse_key_agreement(&self, platform_key: Value) -> Result<Value, PFError>;
+    /// Unlock a secure-locked key with the host key sealed under the MSE
+    /// channel.
+    fn unlock(&self, sealed_key: &[u8]) -> Result<(), PFError>;
     /// Send an `authenticatorConfig` sub-command.
     fn send_config(
         &self,
@@ -61,16 +174,31 @@ pub trait FidoOperations {
         pin_token: &[u8],
         sub_params: Option<Value>,
     ) -> Result<Vec<u8>, PFError>;
-    /// Enable enterprise attestation via config sub-command.
-    fn send_config_enable_ea(&self, pin_token: &[u8]) -> Result<(), PFError>;
-    /// Set the minimum PIN length via config sub-command.
+    /// Enable enterprise attestation (the `ep` option) via config sub-command.
+    fn enable_enterprise_attestation(&self, pin_token: &[u8]) -> Result<(), PFError>;
+    /// Set the minimum PIN length, the RP IDs allowed to read it and the
+    /// forceChangePin flag via config sub-command.
     fn send_config_set_min_pin_length(
         &self,
         pin_token: &[u8],
         new_min_pin_length: u8,
+        rp_ids: &[String],
+        force_change_pin: bool,
     ) -> Result<(), PFError>;
+    /// Flip the `alwaysUv` option via config sub-command.
+    fn toggle_always_uv(&self, pin_token: &[u8]) -> Result<(), PFError>;
     /// Retrieve the authenticator's ECDH P-256 public key for PIN token exchange.
     fn get_key_agreement(&self) -> Result<Value, PFError>;
+
+    /// Query the remaining PIN attempts and whether a power cycle is pending
+    /// (`getPinRetries`). Needs no PIN and still answers while PIN entry is
+    /// blocked.
+    fn get_pin_retries(&self) -> Result<PinRetries, PFError>;
+    /// Query the remaining built-in UV attempts (`getUVRetries`).
+    fn get_uv_retries(&self) -> Result<u32, PFError>;
+    /// Send a ClientPIN sub-command that takes no parameters and return the
+    /// response map.
+    fn client_pin_query(&self, sub_cmd: ClientPinSubCommand) -> Result<Value, PFError>;
     /// Derive a PIN token from the user-supplied PIN.
     fn get_pin_token(&self, pin: &str) -> Result<Vec<u8>, PFError>;
     /// Derive a PIN token scoped to specific permissions (e.g. credential management).
@@ -102,6 +230,9 @@ pub trait FidoOperations {
         permissions: Option<u8>,
         rp_id: Option<String>,
     ) -> Vec<u8>;
+    /// Read the stored and remaining discoverable credential counts.
+    fn credential_management_get_metadata(&self, pin: &str)
+    -> Result<CredentialsMetadata, PFError>;
     /// Enumerate all relying parties stored on the authenticator.
     fn credential_management_enumerate_rps(
         &self,
@@ -113,11 +244,18 @@ pub trait FidoOperations {
         pin: &str,
         rp_id_hash: &[u8],
     ) -> Result<Vec<EnumerateCredentialResponse>, PFError>;
-    /// Delete a credential from the authenticator.
-    fn credential_management_delete_credential(
+    /// Delete a credential with a token already scoped to credential management.
+    fn credential_management_delete_with_token(
+        &self,
+        pin_token: &[u8],
+        credential_id_map: Value,
+    ) -> Result<(), PFError>;
+    /// Replace the user entity stored with a credential.
+    fn credential_management_update_user_info(
         &self,
         pin: &str,
         credential_id_map: Value,
+        user: Value,
     ) -> Result<(), PFError>;
     /// Read RS-Key configuration via the 0x41 CONFIG_READ vendor command.
     fn rs_key_config_read(&self, target: u8) -> Result<Vec<u8>, PFError>;
@@ -131,6 +269,15 @@ pub trait FidoOperations {
         sub_cmd: u8,
         sub_params_bytes: Option<&[u8]>,
     ) -> Vec<u8>;
+    /// Read the serialized large-blob array, `max_fragment` bytes at a time.
+    fn large_blob_read(&self, max_fragment: usize) -> Result<Vec<u8>, PFError>;
+    /// Replace the serialized large-blob array, one signed fragment at a time.
+    fn large_blob_write(
+        &self,
+        pin_token: &[u8],
+        serialized: &[u8],
+        max_fragment: usize,
+    ) -> Result<(), PFError>;
 }
 
 impl FidoOperations for HidTransport {
@@ -270,6 +417,142 @@ impl FidoOperations for HidTransport {
         }
     }
 
+    /// Send CTAP_VENDOR_BACKUP (0x01) with GetEncryptedBackup and return the blob.
+    ///
+    /// The blob is encrypted by the firmware; PicoForge stores it as-is and
+    /// never looks inside.
+    fn get_encrypted_backup(&self) -> Result<Vec<u8>, PFError> {
+        log::debug!("Requesting encrypted backup (CTAP_VENDOR_BACKUP)...");
+
+        let mut req = BTreeMap::new();
+        req.insert(
+            Value::Integer(1),
+            Value::Integer(BackupSubCommand::GetEncryptedBackup as i128),
+        );
+
+        let cbor = to_vec(&Value::Map(req)).map_err(|e| PFError::Io(e.to_string()))?;
+        let mut payload = vec![VendorCommand::Backup as u8];
+        payload.extend(cbor);
+
+        let response = self
+            .send_cbor(CTAP_VENDOR_CBOR_CMD, &payload)
+            .map_err(|e| match e {
+                // Keep the status: the MSE fallback retries on it.
+                PFError::CtapError(_) => e,
+                e => PFError::Device(format!("Backup request failed: {}", e)),
+            })?;
+
+        match from_slice::<Value>(&response) {
+            Ok(Value::Bytes(b)) if !b.is_empty() => Ok(b),
+            Ok(Value::Map(m)) => match m.get(&Value::Integer(1)) {
+                Some(Value::Bytes(b)) if !b.is_empty() => Ok(b.clone()),
+                _ => Err(PFError::Device(
+                    "Backup response did not contain a backup blob".into(),
+                )),
+            },
+            _ => Err(PFError::Device(
+                "Empty or malformed response to backup request".into(),
+            )),
+        }
+    }
+
+    /// Send CTAP_VENDOR_BACKUP (0x01) with RestoreEncryptedBackup and the blob
+    /// under sub-parameter 0x01.
+    fn restore_encrypted_backup(&self, blob: &[u8]) -> Result<(), PFError> {
+        log::debug!(
+            "Restoring encrypted backup ({} bytes, CTAP_VENDOR_BACKUP)...",
+            blob.len()
+        );
+
+        let mut sub_params = BTreeMap::new();
+        sub_params.insert(
+            Value::Integer(VendorSubParam::VendorParam as i128),
+            Value::Bytes(blob.to_vec()),
+        );
+        let mut req = BTreeMap::new();
+        req.insert(
+            Value::Integer(1),
+            Value::Integer(BackupSubCommand::RestoreEncryptedBackup as i128),
+        );
+        req.insert(Value::Integer(2), Value::Map(sub_params));
+
+        let cbor = to_vec(&Value::Map(req)).map_err(|e| PFError::Io(e.to_string()))?;
+        let mut payload = vec![VendorCommand::Backup as u8];
+        payload.extend(cbor);
+
+        self.send_cbor(CTAP_VENDOR_CBOR_CMD, &payload)
+            .map_err(|e| match e {
+                // Keep the status: the MSE fallback retries on it.
+                PFError::CtapError(_) => e,
+                e => PFError::Device(format!("Restore failed: {}", e)),
+            })?;
+        Ok(())
+    }
+
+    /// Send CTAP_VENDOR_MSE (0x02) with KeyAgreement and the platform key
+    /// under sub-parameter 0x02; the device answers with its own key at 0x01.
+    fn mse_key_agreement(&self, platform_key: Value) -> Result<Value, PFError> {
+        log::debug!("Starting MSE key agreement (CTAP_VENDOR_MSE)...");
+
+        let mut sub_params = BTreeMap::new();
+        sub_params.insert(
+            Value::Integer(VendorSubParam::CoseKey as i128),
+            platform_key,
+        );
+        let mut req = BTreeMap::new();
+        req.insert(
+            Value::Integer(1),
+            Value::Integer(MseSubCommand::KeyAgreement as i128),
+        );
+        req.insert(Value::Integer(2), Value::Map(sub_params));
+
+        let cbor = to_vec(&Value::Map(req)).map_err(|e| PFError::Io(e.to_string()))?;
+        let mut payload = vec![VendorCommand::ManageSecurityEnvironment as u8];
+        payload.extend(cbor);
+
+        let response = self
+            .send_cbor(CTAP_VENDOR_CBOR_CMD, &payload)
+            .map_err(|e| PFError::Device(format!("MSE key agreement failed: {}", e)))?;
+
+        match from_slice::<Value>(&response) {
+            Ok(Value::Map(m)) => match m.get(&Value::Integer(1)) {
+                Some(key @ Value::Map(_)) => Ok(key.clone()),
+                _ => Err(PFError::Device(
+                    "MSE response did not contain a device key".into(),
+                )),
+            },
+            _ => Err(PFError::Device(
+                "Empty or malformed response to MSE key agreement".into(),
+            )),
+        }
+    }
+
+    /// Send CTAP_VENDOR_UNLOCK (0x03) with the sealed host key under
+    /// sub-parameter 0x01.
+    fn unlock(&self, sealed_key: &[u8]) -> Result<(), PFError> {
+        log::debug!("Unlocking the device (CTAP_VENDOR_UNLOCK)...");
+
+        let mut sub_params = BTreeMap::new();
+        sub_params.insert(
+            Value::Integer(VendorSubParam::VendorParam as i128),
+            Value::Bytes(sealed_key.to_vec()),
+        );
+        let mut req = BTreeMap::new();
+        req.insert(
+            Value::Integer(1),
+            Value::Integer(UnlockSubCommand::Unlock as i128),
+        );
+        req.insert(Value::Integer(2), Value::Map(sub_params));
+
+        let cbor = to_vec(&Value::Map(req)).map_err(|e| PFError::Io(e.to_string()))?;
+        let mut payload = vec![VendorCommand::Unlock as u8];
+        payload.extend(cbor);
+
+        self.send_cbor(CTAP_VENDOR_CBOR_CMD, &payload)
+            .map_err(|e| PFError::Device(format!("Unlock failed: {}", e)))?;
+        Ok(())
+    }
+
     /// Send authenticatorConfig command.
     ///
     /// This bypasses the ctap-hid-fido2 library which has a bug where it sends
@@ -330,7 +613,9 @@ impl FidoOperations for HidTransport {
     /// Calls the EnableEnterpriseAttestation sub-command (0x01) via [`send_config`](HidTransport::send_config).
     /// Enterprise attestation allows RPs to receive a per-device attestation certificate
     /// during MakeCredential, enabling enterprise device identification.
-    fn send_config_enable_ea(&self, pin_token: &[u8]) -> Result<(), PFError> {
+    /// The key must list the `ep` option in GetInfo; once on, only a reset
+    /// turns it off again.
+    fn enable_enterprise_attestation(&self, pin_token: &[u8]) -> Result<(), PFError> {
         log::debug!("Sending Enterprise Attestation enable config command...");
         match self.send_config(
             ConfigSubCommand::EnableEnterpriseAttestation,
@@ -342,8 +627,15 @@ impl FidoOperations for HidTransport {
                 Ok(())
             }
             Err(e) => {
-                let error_string = e.to_string();
-                log::error!("Failed to enable Enterprise Attestation: {}", error_string);
+                log::error!("Failed to enable Enterprise Attestation: {}", e);
+                if matches!(
+                    e.ctap(),
+                    Some(Ctap2Error::InvalidParameter | Ctap2Error::UnsupportedOption)
+                ) {
+                    return Err(PFError::Device(
+                        "The authenticator does not support enterprise attestation.".into(),
+                    ));
+                }
                 Err(PFError::Device(format!(
                     "EnableEnterpriseAttestation failed: {}",
                     e
@@ -357,23 +649,23 @@ impl FidoOperations for HidTransport {
     /// Calls the SetMinPinLength sub-command (0x03) via [`send_config`](HidTransport::send_config).
     /// The minimum PIN length can only be increased; attempting to decrease it returns
     /// `PIN_POLICY_VIOLATION` (0x37). A device reset is required to lower the minimum.
+    /// `rp_ids` (minPinLengthRPIDs) is only sent when non-empty and may not exceed
+    /// `maxRPIDsForSetMinPINLength`; `forceChangePin` is only sent when set.
     fn send_config_set_min_pin_length(
         &self,
         pin_token: &[u8],
         new_min_pin_length: u8,
+        rp_ids: &[String],
+        force_change_pin: bool,
     ) -> Result<(), PFError> {
         log::debug!(
-            "Sending setMinPINLength config command (new length: {})...",
-            new_min_pin_length
+            "Sending setMinPINLength config command (new length: {}, {} RP IDs, force change: {})...",
+            new_min_pin_length,
+            rp_ids.len(),
+            force_change_pin
         );
 
-        // Build subCommandParams (Key 0x02): { 0x01: newMinPINLength }
-        let mut sub_params_map = BTreeMap::new();
-        sub_params_map.insert(
-            Value::Integer(ConfigSubCommandParam::NewMinPinLength as i128),
-            Value::Integer(new_min_pin_length as i128),
-        );
-        let sub_params = Value::Map(sub_params_map);
+        let sub_params = min_pin_length_params(new_min_pin_length, rp_ids, force_change_pin);
         match self.send_config(
             ConfigSubCommand::SetMinPinLength,
             pin_token,
@@ -387,11 +679,10 @@ impl FidoOperations for HidTransport {
                 Ok(())
             }
             Err(e) => {
-                let error_string = e.to_string();
-                log::error!("Failed to send setMinPINLength config: {}", error_string);
+                log::error!("Failed to send setMinPINLength config: {}", e);
 
                 // Check for PIN policy violation (0x37) - cannot decrease min PIN length
-                if error_string.contains("0x37") {
+                if e.ctap() == Some(Ctap2Error::PinPolicyViolation) {
                     return Err(PFError::Device(
                         "Cannot decrease minimum PIN length. The FIDO2 security policy only allows increasing the minimum PIN length, not decreasing it. A device reset is required to lower the minimum.".into()
                     ));
@@ -402,6 +693,31 @@ impl FidoOperations for HidTransport {
         }
     }
 
+    /// Send authenticatorConfig command to toggle the `alwaysUv` option.
+    ///
+    /// Calls the ToggleAlwaysUv sub-command (0x02) via [`send_config`](HidTransport::send_config).
+    /// The command carries no parameters and flips the current state; read
+    /// `alwaysUv` from GetInfo afterwards to learn the new one. Authenticators
+    /// that cannot turn it off (e.g. FIPS builds) answer `OPERATION_DENIED` (0x27).
+    fn toggle_always_uv(&self, pin_token: &[u8]) -> Result<(), PFError> {
+        log::debug!("Sending toggleAlwaysUv config command...");
+        match self.send_config(ConfigSubCommand::ToggleAlwaysUv, pin_token, None) {
+            Ok(_) => {
+                log::info!("Successfully toggled alwaysUv");
+                Ok(())
+            }
+            Err(e) => {
+                log::error!("Failed to toggle alwaysUv: {}", e);
+                if e.ctap() == Some(Ctap2Error::OperationDenied) {
+                    return Err(PFError::Device(
+                        "The authenticator does not allow changing the always-UV policy.".into(),
+                    ));
+                }
+                Err(PFError::Device(format!("toggleAlwaysUv failed: {}", e)))
+            }
+        }
+    }
+
     /// Request the authenticator's P-256 ECDH public key for PIN protocol v1.
     ///
     /// Sends a `getClientPin` command with `getKeyAgreement` sub-command (0x02).
@@ -422,7 +738,7 @@ impl FidoOperations for HidTransport {
         payload.extend(to_vec(&Value::Map(map)).map_err(|e| PFError::Io(e.to_string()))?);
 
         log::debug!("Sending GetKeyAgreement command...");
-        let response = self.send_cbor(CTAPHID_CBOR, &payload)?;
+        let response = self.send_cbor_read(CTAPHID_CBOR, &payload)?;
         let val: Value = from_slice(&response).map_err(|e| PFError::Io(e.to_string()))?;
 
         if let Value::Map(m) = val {
@@ -439,6 +755,43 @@ impl FidoOperations for HidTransport {
         }
     }
 
+    /// Query the remaining PIN attempts.
+    ///
+    /// Sends `getPinRetries` (sub-command 0x01). This needs no PIN and still
+    /// answers while PIN entry is blocked, so it is safe to call after a
+    /// 0x32/0x34 error to find out which lockout the key is in.
+    fn get_pin_retries(&self) -> Result<PinRetries, PFError> {
+        log::debug!("Sending GetPinRetries command...");
+        parse_pin_retries(&self.client_pin_query(ClientPinSubCommand::GetPinRetries)?)
+    }
+
+    /// Query the remaining built-in user verification attempts.
+    ///
+    /// Sends `getUVRetries` (sub-command 0x07). Only meaningful on keys with
+    /// built-in UV (a fingerprint sensor), which list the `uv` option.
+    fn get_uv_retries(&self) -> Result<u32, PFError> {
+        log::debug!("Sending GetUvRetries command...");
+        parse_uv_retries(&self.client_pin_query(ClientPinSubCommand::GetUvRetries)?)
+    }
+
+    fn client_pin_query(&self, sub_cmd: ClientPinSubCommand) -> Result<Value, PFError> {
+        let mut map = BTreeMap::new();
+        map.insert(
+            Value::Integer(ClientPinParam::PinUvAuthProtocol as i128),
+            Value::Integer(1),
+        );
+        map.insert(
+            Value::Integer(ClientPinParam::SubCommand as i128),
+            Value::Integer(sub_cmd as i128),
+        );
+
+        let mut payload = vec![CtapCommand::ClientPin as u8];
+        payload.extend(to_vec(&Value::Map(map)).map_err(|e| PFError::Io(e.to_string()))?);
+
+        let response = self.send_cbor_read(CTAPHID_CBOR, &payload)?;
+        from_slice(&response).map_err(|e| PFError::Io(e.to_string()))
+    }
+
     /// Obtain an encrypted PIN token using the standard getPinToken flow.
     ///
     /// Implements the full CTAP2 §11.5.4 PIN token acquisition:
@@ -495,7 +848,7 @@ impl FidoOperations for HidTransport {
             .map_err(|_| PFError::Device("Inner ECDH shared secret computation failed".into()))?;
 
         // 5. Encrypt PIN Hash
-        let pin_hash = digest::digest(&digest::SHA256, pin.as_bytes());
+        let pin_hash = digest::digest(&digest::SHA256, pin::normalize(pin).as_bytes());
         let pin_hash_16 = &pin_hash.as_ref()[0..16];
 
         let iv = [0u8; 16];
@@ -527,7 +880,7 @@ impl FidoOperations for HidTransport {
         payload.extend(payload_cbor);
 
         log::debug!("Sending getPinToken command...");
-        let response = self.send_cbor(CTAPHID_CBOR, &payload)?;
+        let response = pin::attempt(|| self.send_cbor(CTAPHID_CBOR, &payload))?;
         let val: Value = from_slice(&response).map_err(|e| PFError::Io(e.to_string()))?;
 
         if let Value::Map(m) = val {
@@ -613,7 +966,7 @@ impl FidoOperations for HidTransport {
             .map_err(|_| PFError::Device("Inner ECDH shared secret computation failed".into()))?;
 
         // 5. Encrypt PIN Hash
-        let pin_hash = digest::digest(&digest::SHA256, pin.as_bytes());
+        let pin_hash = digest::digest(&digest::SHA256, pin::normalize(pin).as_bytes());
         let pin_hash_16 = &pin_hash.as_ref()[0..16];
 
         let iv = [0u8; 16];
@@ -649,7 +1002,7 @@ impl FidoOperations for HidTransport {
         payload.extend(payload_cbor);
 
         log::debug!("Sending getPinUvAuthTokenUsingPinWithPermissions command...");
-        let response = self.send_cbor(CTAPHID_CBOR, &payload)?;
+        let response = pin::attempt(|| self.send_cbor(CTAPHID_CBOR, &payload))?;
         log::debug!(
             "getPinUvAuthTokenUsingPinWithPermissions response: {:?}",
             response
@@ -687,19 +1040,13 @@ impl FidoOperations for HidTransport {
     /// 3. Computes `HMAC-SHA-256(shared_secret, newPinEnc)[0..16]` as pinUvAuthParam.
     /// 4. Sends the SetPin command with the platform's public key, encrypted PIN, and HMAC.
     ///
-    /// The PIN must be 4–63 characters. Fails with `PIN_POLICY_VIOLATION` (0x37) if
+    /// The PIN is NFC-normalized and must be at least 4 code points and at
+    /// most 63 bytes (see [`pin`]). Fails with `PIN_POLICY_VIOLATION` (0x37) if
     /// the PIN is too short.
     fn set_pin(&self, new_pin: &str) -> Result<(), PFError> {
         log::info!("Starting custom set_pin (Subcommand 0x03)...");
 
-        if new_pin.len() < 4 {
-            return Err(PFError::Device("PIN must be at least 4 characters".into()));
-        }
-        if new_pin.len() > 63 {
-            return Err(PFError::Device(
-                "PIN must be less than 64 characters".into(),
-            ));
-        }
+        let new_pin = pin::prepare_new(new_pin).map_err(PFError::Device)?;
 
         // 1. Get Authenticator Key Agreement
         let auth_key_agreement = self.get_key_agreement()?;
@@ -796,9 +1143,8 @@ impl FidoOperations for HidTransport {
                 Ok(())
             }
             Err(e) => {
-                let error_string = e.to_string();
-                log::error!("Failed to send setPin config: {}", error_string);
-                if error_string.contains("0x37") {
+                log::error!("Failed to send setPin config: {}", e);
+                if e.ctap() == Some(Ctap2Error::PinPolicyViolation) {
                     return Err(PFError::Device(
                         "New PIN violates policy (e.g. too short).".into(),
                     ));
@@ -823,14 +1169,7 @@ impl FidoOperations for HidTransport {
     fn change_pin(&self, current_pin: &str, new_pin: &str) -> Result<(), PFError> {
         log::info!("Starting custom change_pin (Subcommand 0x04)...");
 
-        if new_pin.len() < 4 {
-            return Err(PFError::Device("PIN must be at least 4 characters".into()));
-        }
-        if new_pin.len() > 63 {
-            return Err(PFError::Device(
-                "PIN must be less than 64 characters".into(),
-            ));
-        }
+        let new_pin = pin::prepare_new(new_pin).map_err(PFError::Device)?;
 
         // 1. Get Authenticator Key Agreement
         let auth_key_agreement = self.get_key_agreement()?;
@@ -879,7 +1218,7 @@ impl FidoOperations for HidTransport {
         let shared_secret_bytes = shared_secret.as_ref();
 
         // 5. Encrypt current_pin hash
-        let pin_hash = digest::digest(&digest::SHA256, current_pin.as_bytes());
+        let pin_hash = digest::digest(&digest::SHA256, pin::normalize(current_pin).as_bytes());
         let pin_hash_16 = &pin_hash.as_ref()[0..16];
         let iv = [0u8; 16];
         let mut block = Block::<aes::Aes256>::try_from(pin_hash_16).unwrap();
@@ -937,23 +1276,25 @@ impl FidoOperations for HidTransport {
         payload.extend(payload_cbor);
 
         log::debug!("Sending changePin command...");
-        match self.send_cbor(CTAPHID_CBOR, &payload) {
+        match pin::attempt(|| self.send_cbor(CTAPHID_CBOR, &payload)) {
             Ok(_) => {
                 log::info!("Successfully changed PIN.");
                 Ok(())
             }
             Err(e) => {
-                let error_string = e.to_string();
-                log::error!("Failed to send changePin config: {}", error_string);
-                if error_string.contains("0x31") {
-                    return Err(PFError::Device("Invalid current PIN (0x31). Please check that you entered the correct PIN.".into()));
-                }
-                if error_string.contains("0x32") {
-                    return Err(PFError::Device(
-                        "PIN blocked (0x32). Device reset may be required.".into(),
-                    ));
+                log::error!("Failed to send changePin config: {}", e);
+                // Passed on as they are, so callers can tell a lockout apart.
+                if matches!(
+                    e.ctap(),
+                    Some(
+                        Ctap2Error::PinInvalid
+                            | Ctap2Error::PinBlocked
+                            | Ctap2Error::PinAuthBlocked
+                    )
+                ) {
+                    return Err(e);
                 }
-                if error_string.contains("0x37") {
+                if e.ctap() == Some(Ctap2Error::PinPolicyViolation) {
                     return Err(PFError::Device(
                         "New PIN violates policy (e.g. too short).".into(),
                     ));
@@ -1046,6 +1387,48 @@ impl FidoOperations for HidTransport {
         bytes
     }
 
+    /// Read how many discoverable credentials are stored and how many more fit.
+    ///
+    /// Sends `GetCredsMetadata` (sub-command 0x01) with a PIN token carrying
+    /// the `CREDENTIAL_MANAGEMENT` permission.
+    fn credential_management_get_metadata(
+        &self,
+        pin: &str,
+    ) -> Result<CredentialsMetadata, PFError> {
+        let pin_token = self.get_pin_token_with_permission(
+            pin,
+            PinUvAuthTokenPermissions::CREDENTIAL_MANAGEMENT,
+            None,
+        )?;
+
+        let pin_auth = self.sign_credential_mgmt_command(
+            &pin_token,
+            CredentialMgmtSubCommand::GetCredsMetadata as u8,
+            None,
+        );
+
+        let mut mgmt_map = BTreeMap::new();
+        mgmt_map.insert(
+            Value::Integer(CredentialMgmtParam::SubCommand as i128),
+            Value::Integer(CredentialMgmtSubCommand::GetCredsMetadata as i128),
+        );
+        mgmt_map.insert(
+            Value::Integer(CredentialMgmtParam::PinUvAuthProtocol as i128),
+            Value::Integer(1),
+        );
+        mgmt_map.insert(
+            Value::Integer(CredentialMgmtParam::PinUvAuthParam as i128),
+            Value::Bytes(pin_auth),
+        );
+
+        let mut payload = vec![CtapCommand::CredentialMgmt as u8];
+        payload.extend(to_vec(&Value::Map(mgmt_map)).map_err(|e| PFError::Io(e.to_string()))?);
+
+        let response = self.send_cbor_read(CTAPHID_CBOR, &payload)?;
+        let val: Value = from_slice(&response).map_err(|e| PFError::Io(e.to_string()))?;
+        parse_creds_metadata(&val)
+    }
+
     /// Enumerate all Relying Parties stored on the authenticator.
     ///
     /// Performs the CTAP2 credential management enumeration flow:
@@ -1096,10 +1479,10 @@ impl FidoOperations for HidTransport {
         let mut payload = vec![CtapCommand::CredentialMgmt as u8];
         payload.extend(to_vec(&Value::Map(mgmt_map)).map_err(|e| PFError::Io(e.to_string()))?);
 
-        let response = match self.send_cbor(CTAPHID_CBOR, &payload) {
+        let response = match self.send_cbor_read(CTAPHID_CBOR, &payload) {
             Ok(r) => r,
             Err(e) => {
-                if e.to_string().contains("0x2E") {
+                if e.ctap() == Some(Ctap2Error::NoCredentials) {
                     log::info!("No credentials found on device (0x2E)");
                     return Ok(Vec::new());
                 }
@@ -1182,7 +1565,7 @@ impl FidoOperations for HidTransport {
                     }
                 }
                 Err(e) => {
-                    if e.to_string().contains("0x2E") {
+                    if e.ctap() == Some(Ctap2Error::NoCredentials) {
                         break;
                     }
                     return Err(e);
@@ -1252,10 +1635,10 @@ impl FidoOperations for HidTransport {
         let mut payload = vec![CtapCommand::CredentialMgmt as u8];
         payload.extend(to_vec(&Value::Map(mgmt_map)).map_err(|e| PFError::Io(e.to_string()))?);
 
-        let response = match self.send_cbor(CTAPHID_CBOR, &payload) {
+        let response = match self.send_cbor_read(CTAPHID_CBOR, &payload) {
             Ok(r) => r,
             Err(e) => {
-                if e.to_string().contains("0x2E") {
+                if e.ctap() == Some(Ctap2Error::NoCredentials) {
                     return Ok(Vec::new());
                 }
                 return Err(e);
@@ -1303,6 +1686,7 @@ impl FidoOperations for HidTransport {
                 credential_id,
                 public_key,
                 total_credentials: total_creds,
+                cred_protect: parse_cred_protect(m),
             });
         }
 
@@ -1357,11 +1741,12 @@ impl FidoOperations for HidTransport {
                             credential_id,
                             public_key,
                             total_credentials: total_creds,
+                            cred_protect: parse_cred_protect(&m),
                         });
                     }
                 }
                 Err(e) => {
-                    if e.to_string().contains("0x2E") {
+                    if e.ctap() == Some(Ctap2Error::NoCredentials) {
                         break;
                     }
                     return Err(e);
@@ -1374,42 +1759,95 @@ impl FidoOperations for HidTransport {
 
     /// Delete a specific credential from the authenticator.
     ///
-    /// Obtains a PIN token with `CREDENTIAL_MANAGEMENT` permission, then sends
-    /// the `DeleteCredential` command (sub-command 0x06) with the credential ID
-    /// descriptor map. The `credential_id_map` must be a CBOR map with key 0x02
-    /// containing the credential ID.
-    fn credential_management_delete_credential(
+    /// Sends the `DeleteCredential` command (sub-command 0x06) with the
+    /// credential ID descriptor map, signed with `pin_token`. The token must
+    /// carry the `CREDENTIAL_MANAGEMENT` permission; taking it as an argument
+    /// lets a caller deleting several credentials derive it once.
+    fn credential_management_delete_with_token(
+        &self,
+        pin_token: &[u8],
+        credential_id_map: Value,
+    ) -> Result<(), PFError> {
+        let mut sub_params = BTreeMap::new();
+        sub_params.insert(
+            Value::Integer(0x02), // credentialId descriptor map
+            credential_id_map,
+        );
+        let sub_params_bytes = to_vec(&Value::Map(sub_params.clone())).unwrap();
+
+        let pin_auth = self.sign_credential_mgmt_command(
+            pin_token,
+            CredentialMgmtSubCommand::DeleteCredential as u8,
+            Some(&sub_params_bytes),
+        );
+
+        let mut mgmt_map = BTreeMap::new();
+        mgmt_map.insert(
+            Value::Integer(CredentialMgmtParam::SubCommand as i128),
+            Value::Integer(CredentialMgmtSubCommand::DeleteCredential as i128),
+        );
+        mgmt_map.insert(
+            Value::Integer(CredentialMgmtParam::SubCommandParams as i128),
+            Value::Map(sub_params),
+        );
+        mgmt_map.insert(
+            Value::Integer(CredentialMgmtParam::PinUvAuthProtocol as i128),
+            Value::Integer(1),
+        );
+        mgmt_map.insert(
+            Value::Integer(CredentialMgmtParam::PinUvAuthParam as i128),
+            Value::Bytes(pin_auth),
+        );
+
+        let mut payload = vec![CtapCommand::CredentialMgmt as u8];
+        payload.extend(to_vec(&Value::Map(mgmt_map)).map_err(|e| PFError::Io(e.to_string()))?);
+
+        self.send_cbor(CTAPHID_CBOR, &payload)?;
+
+        Ok(())
+    }
+
+    /// Replace the user name and display name of a stored credential.
+    ///
+    /// Sends `UpdateUserInformation` (sub-command 0x07) with the credential
+    /// descriptor and the new user entity. The entity's `id` must match the
+    /// stored one; the authenticator rejects the update otherwise.
+    fn credential_management_update_user_info(
         &self,
         pin: &str,
         credential_id_map: Value,
+        user: Value,
     ) -> Result<(), PFError> {
-        log::info!("Starting custom credential_management_delete_credential...");
+        log::info!("Starting credential_management_update_user_info...");
 
-        // 1. Get PIN token with CREDENTIAL_MANAGEMENT permission
         let pin_token = self.get_pin_token_with_permission(
             pin,
             PinUvAuthTokenPermissions::CREDENTIAL_MANAGEMENT,
             None,
         )?;
 
-        // 2. DeleteCredential (Subcommand 0x06)
         let mut sub_params = BTreeMap::new();
         sub_params.insert(
-            Value::Integer(0x02), // credentialId descriptor map
+            Value::Integer(CredentialMgmtSubCommandParam::CredentialId as i128),
             credential_id_map,
         );
-        let sub_params_bytes = to_vec(&Value::Map(sub_params.clone())).unwrap();
+        sub_params.insert(
+            Value::Integer(CredentialMgmtSubCommandParam::User as i128),
+            user,
+        );
+        let sub_params_bytes =
+            to_vec(&Value::Map(sub_params.clone())).map_err(|e| PFError::Io(e.to_string()))?;
 
         let pin_auth = self.sign_credential_mgmt_command(
             &pin_token,
-            CredentialMgmtSubCommand::DeleteCredential as u8,
+            CredentialMgmtSubCommand::UpdateUserInformation as u8,
             Some(&sub_params_bytes),
         );
 
         let mut mgmt_map = BTreeMap::new();
         mgmt_map.insert(
             Value::Integer(CredentialMgmtParam::SubCommand as i128),
-            Value::Integer(CredentialMgmtSubCommand::DeleteCredential as i128),
+            Value::Integer(CredentialMgmtSubCommand::UpdateUserInformation as i128),
         );
         mgmt_map.insert(
             Value::Integer(CredentialMgmtParam::SubCommandParams as i128),
@@ -1454,7 +1892,7 @@ impl FidoOperations for HidTransport {
 
         let mut full_payload = vec![RSKEY_CTAPHID_VENDOR_CMD];
         full_payload.extend(inner);
-        let resp = self.send_cbor(CTAPHID_CBOR, &full_payload)?;
+        let resp = self.send_cbor_read(CTAPHID_CBOR, &full_payload)?;
 
         // Response is CBOR `{1: blob(bstr)}` — unwrap key 1 to the raw record.
         match from_slice::<Value>(&resp) {
@@ -1551,6 +1989,123 @@ impl FidoOperations for HidTransport {
         let sig = hmac::sign(&hmac_key, &message);
         sig.as_ref()[0..16].to_vec()
     }
+
+    /// Read the whole serialized large-blob array (§6.10.2).
+    ///
+    /// Asks for `max_fragment` bytes at increasing offsets until the
+    /// authenticator returns a shorter fragment. Reads need no PIN token. The
+    /// result still ends with its 16-byte trailer; check it with
+    /// [`large_blob::split_serialized`](super::large_blob::split_serialized).
+    fn large_blob_read(&self, max_fragment: usize) -> Result<Vec<u8>, PFError> {
+        // Guards against firmware that keeps returning full fragments.
+        const LARGE_BLOB_READ_LIMIT: usize = 64 * 1024;
+
+        let mut serialized = Vec::new();
+        loop {
+            let mut request = BTreeMap::new();
+            request.insert(
+                Value::Integer(LargeBlobsParam::Get as i128),
+                Value::Integer(max_fragment as i128),
+            );
+            request.insert(
+                Value::Integer(LargeBlobsParam::Offset as i128),
+                Value::Integer(serialized.len() as i128),
+            );
+            let mut payload = vec![CtapCommand::LargeBlobs as u8];
+            payload.extend(to_vec(&Value::Map(request)).map_err(|e| PFError::Io(e.to_string()))?);
+
+            let resp = self.send_cbor_read(CTAPHID_CBOR, &payload)?;
+            let fragment = match from_slice::<Value>(&resp) {
+                Ok(Value::Map(m)) => {
+                    match m.get(&Value::Integer(LargeBlobsResponseParam::Config as i128)) {
+                        Some(Value::Bytes(b)) => b.clone(),
+                        _ => {
+                            return Err(PFError::Device(
+                                "largeBlobs response missing fragment (key 1)".into(),
+                            ));
+                        }
+                    }
+                }
+                _ => {
+                    return Err(PFError::Device(
+                        "largeBlobs response is not a CBOR map".into(),
+                    ));
+                }
+            };
+
+            let last = fragment.len() < max_fragment;
+            serialized.extend(fragment);
+            if last {
+                break;
+            }
+            if serialized.len() > LARGE_BLOB_READ_LIMIT {
+                return Err(PFError::Device(format!(
+                    "Large-blob array exceeds {} bytes",
+                    LARGE_BLOB_READ_LIMIT
+                )));
+            }
+        }
+        log::debug!("Read {} bytes of large-blob array", serialized.len());
+        Ok(serialized)
+    }
+
+    /// Write a new serialized large-blob array (§6.10.2).
+    ///
+    /// The first fragment carries the total `length`; the authenticator only
+    /// commits the array once the last byte arrives and its trailer checks
+    /// out. `pin_token` must have the `LARGE_BLOB_WRITE` permission.
+    fn large_blob_write(
+        &self,
+        pin_token: &[u8],
+        serialized: &[u8],
+        max_fragment: usize,
+    ) -> Result<(), PFError> {
+        // Each fragment may be written to flash before the reply.
+        const LARGE_BLOB_WRITE_TIMEOUT_MS: i32 = 10_000;
+
+        for (index, fragment) in serialized.chunks(max_fragment).enumerate() {
+            let offset = index * max_fragment;
+            let mut request = BTreeMap::new();
+            request.insert(
+                Value::Integer(LargeBlobsParam::Set as i128),
+                Value::Bytes(fragment.to_vec()),
+            );
+            request.insert(
+                Value::Integer(LargeBlobsParam::Offset as i128),
+                Value::Integer(offset as i128),
+            );
+            if offset == 0 {
+                request.insert(
+                    Value::Integer(LargeBlobsParam::Length as i128),
+                    Value::Integer(serialized.len() as i128),
+                );
+            }
+            request.insert(
+                Value::Integer(LargeBlobsParam::PinUvAuthParam as i128),
+                Value::Bytes(large_blob_write_auth(pin_token, offset as u32, fragment)),
+            );
+            request.insert(
+                Value::Integer(LargeBlobsParam::PinUvAuthProtocol as i128),
+                Value::Integer(PIN_UV_AUTH_PROTOCOL_ONE as i128),
+            );
+            let mut payload = vec![CtapCommand::LargeBlobs as u8];
+            payload.extend(to_vec(&Value::Map(request)).map_err(|e| PFError::Io(e.to_string()))?);
+            self.send_cbor_with_timeout(CTAPHID_CBOR, &payload, LARGE_BLOB_WRITE_TIMEOUT_MS)?;
+        }
+        log::info!("Wrote {} bytes of large-blob array", serialized.len());
+        Ok(())
+    }
+}
+
+/// `pinUvAuthParam` for one `largeBlobs` write fragment (§6.10.2):
+/// `HMAC(token, 0xFF*32 || 0x0C 0x00 || uint32LE(offset) || SHA-256(fragment))[..16]`.
+pub(crate) fn large_blob_write_auth(pin_token: &[u8], offset: u32, fragment: &[u8]) -> Vec<u8> {
+    let mut message = vec![0xFFu8; 32];
+    message.extend([CtapCommand::LargeBlobs as u8, 0x00]);
+    message.extend(offset.to_le_bytes());
+    message.extend(digest::digest(&digest::SHA256, fragment).as_ref());
+    let hmac_key = hmac::Key::new(hmac::HMAC_SHA256, pin_token);
+    hmac::sign(&hmac_key, &message).as_ref()[..16].to_vec()
 }
 
 #[cfg(test)]
@@ -1612,6 +2167,100 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_pin_retries() {
+        let mut m = BTreeMap::new();
+        m.insert(
+            Value::Integer(ClientPinResponseParam::PinRetries as i128),
+            Value::Integer(5),
+        );
+        m.insert(
+            Value::Integer(ClientPinResponseParam::PowerCycleState as i128),
+            Value::Bool(true),
+        );
+        let parsed = parse_pin_retries(&Value::Map(m)).unwrap();
+        assert_eq!(parsed.retries, 5);
+        assert!(parsed.power_cycle_required);
+
+        // powerCycleState is optional and defaults to false.
+        let mut m = BTreeMap::new();
+        m.insert(
+            Value::Integer(ClientPinResponseParam::PinRetries as i128),
+            Value::Integer(0),
+        );
+        let parsed = parse_pin_retries(&Value::Map(m)).unwrap();
+        assert_eq!(parsed.retries, 0);
+        assert!(!parsed.power_cycle_required);
+
+        assert!(parse_pin_retries(&Value::Map(BTreeMap::new())).is_err());
+    }
+
+    #[test]
+    fn test_parse_uv_retries() {
+        let mut m = BTreeMap::new();
+        m.insert(
+            Value::Integer(ClientPinResponseParam::UvRetries as i128),
+            Value::Integer(3),
+        );
+        assert_eq!(parse_uv_retries(&Value::Map(m)).unwrap(), 3);
+        assert!(parse_uv_retries(&Value::Map(BTreeMap::new())).is_err());
+        assert!(parse_uv_retries(&Value::Integer(3)).is_err());
+    }
+
+    #[test]
+    fn test_parse_creds_metadata() {
+        let mut m = BTreeMap::new();
+        m.insert(
+            Value::Integer(CredentialMgmtResponseParam::ExistingResidentCredentialsCount as i128),
+            Value::Integer(12),
+        );
+        assert!(parse_creds_metadata(&Value::Map(m.clone())).is_err());
+        m.insert(
+            Value::Integer(
+                CredentialMgmtResponseParam::MaxPossibleRemainingResidentCredentialsCount as i128,
+            ),
+            Value::Integer(244),
+        );
+        assert_eq!(
+            parse_creds_metadata(&Value::Map(m)).unwrap(),
+            CredentialsMetadata {
+                existing: 12,
+                remaining: 244,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_cred_protect() {
+        let mut m = BTreeMap::new();
+        assert_eq!(parse_cred_protect(&m), None);
+        m.insert(
+            Value::Integer(CredentialMgmtResponseParam::CredProtect as i128),
+            Value::Integer(3),
+        );
+        assert_eq!(parse_cred_protect(&m), Some(3));
+        m.insert(
+            Value::Integer(CredentialMgmtResponseParam::CredProtect as i128),
+            Value::Integer(-1),
+        );
+        assert_eq!(parse_cred_protect(&m), None);
+    }
+
+    #[test]
+    fn test_large_blob_write_auth() {
+        // The empty array (0x80 plus its trailer), written at offset 0.
+        let fragment = hex::decode("8076be8b528d0075f7aae98d6fa57a6d3c").unwrap();
+        let token = [0x11u8; 32];
+        assert_eq!(
+            hex::encode(large_blob_write_auth(&token, 0, &fragment)),
+            "c8c714c59c9bf4815522ff26f204f9a5"
+        );
+        assert_ne!(
+            large_blob_write_auth(&token, 0, &fragment),
+            large_blob_write_auth(&token, 960, &fragment)
+        );
+    }
+
     #[test]
     fn test_pin_hash_encryption_actually_encrypts() {
         // Verify that our AES-CBC encryption actually modifies the data.
@@ -1641,4 +2290,29 @@ mod tests {
             "Encryption did not modify the block — the old bug is back!"
         );
     }
+
+    #[test]
+    fn test_min_pin_length_params() {
+        let plain = to_vec(&min_pin_length_params(6, &[], false)).unwrap();
+        assert_eq!(plain, vec![0xA1, 0x01, 0x06]);
+
+        let rp_ids = vec!["example.com".to_string()];
+        let Value::Map(full) = min_pin_length_params(8, &rp_ids, true) else {
+            panic!("params are not a map");
+        };
+        let keys: Vec<_> = full.keys().cloned().collect();
+        assert_eq!(
+            keys,
+            vec![
+                Value::Integer(0x01),
+                Value::Integer(0x02),
+                Value::Integer(0x03)
+            ]
+        );
+        assert_eq!(
+            full.get(&Value::Integer(0x02)),
+            Some(&Value::Array(vec![Value::Text("example.com".into())]))
+        );
+        assert_eq!(full.get(&Value::Integer(0x03)), Some(&Value::Bool(true)));
+    }
 }
@@ -0,0 +1,202 @@
+//! PIN encoding rules from CTAP 2.1 §6.5.1.
+//!
+//! The authenticator receives a PIN as UTF-8 bytes, but the spec defines its
+//! length in Unicode code points and requires the string to be in
+//! Normalization Form C first. Without normalization the same PIN typed on two
+//! keyboards (a precomposed "é" versus "e" plus a combining accent) hashes to
+//! different values, and counting bytes rejects short non-ASCII PINs while
+//! letting long ones overflow the 64-byte padded block.
+//!
+//! Every PIN that is hashed or encrypted for the device goes through
+//! [`normalize`], as CTAP 2.1 §6.5.1 has the platform do, so a PIN typed in
+//! decomposed form (as some keyboards and macOS produce) still matches; new
+//! PINs are checked with [`prepare_new`] first.
+//!
+//! Every request that spends a PIN retry goes through [`attempt`], which
+//! refuses to send while another one is in flight and counts wrong PINs for
+//! the session. [`attempt_budget`] tells the PIN prompts how careful to be.
+
+use super::constants::Ctap2Error;
+use crate::error::PFError;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Mutex, TryLockError};
+use unicode_normalization::UnicodeNormalization;
+
+/// Minimum PIN length in code points before the device's own `minPINLength`.
+pub const MIN_CODE_POINTS: usize = 4;
+
+/// Maximum PIN length in UTF-8 bytes: the padded `newPinEnc` block is 64 bytes
+/// and must end with at least one zero byte.
+pub const MAX_BYTES: usize = 63;
+
+/// Wrong PINs a key accepts in a row before it wants to be unplugged
+/// (`CTAP2_ERR_PIN_AUTH_BLOCKED`, CTAP 2.1 §6.5.2.4). Replugging restores
+/// entry but not the retries already spent.
+pub const ATTEMPTS_PER_POWER_UP: u32 = 3;
+
+/// Held while a PIN is being checked by the key.
+static IN_FLIGHT: Mutex<()> = Mutex::new(());
+
+/// Wrong PINs sent since PicoForge started.
+static SESSION_FAILURES: AtomicU32 = AtomicU32::new(0);
+
+/// How much room is left before the PIN blocks, from `getPinRetries`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttemptBudget {
+    /// Three or more attempts left.
+    Ample,
+    /// Two attempts left.
+    Low,
+    /// The next wrong PIN blocks the key until it is reset. The prompts ask
+    /// for an explicit acknowledgement before sending it.
+    Final,
+}
+
+/// Classify the attempts left. `0` is reported as [`AttemptBudget::Final`];
+/// the key refuses the PIN then anyway.
+pub fn attempt_budget(retries: u32) -> AttemptBudget {
+    match retries {
+        0 | 1 => AttemptBudget::Final,
+        2 => AttemptBudget::Low,
+        _ => AttemptBudget::Ample,
+    }
+}
+
+/// Wrong PINs entered since PicoForge started, across all keys.
+pub fn session_failures() -> u32 {
+    SESSION_FAILURES.load(Ordering::Relaxed)
+}
+
+/// Whether `error` is `CTAP2_ERR_PIN_INVALID`, i.e. a retry was spent.
+fn is_wrong_pin(error: &PFError) -> bool {
+    error.ctap() == Some(Ctap2Error::PinInvalid)
+}
+
+/// Send one request that checks a PIN. Fails without sending while another
+/// PIN request is in flight, so two flows holding different PINs cannot
+/// spend two retries between them.
+pub fn attempt<T>(send: impl FnOnce() -> Result<T, PFError>) -> Result<T, PFError> {
+    let _turn = match IN_FLIGHT.try_lock() {
+        Ok(turn) => turn,
+        Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner(),
+        Err(TryLockError::WouldBlock) => {
+            return Err(PFError::Device(
+                "Another PIN check is still in progress; wait for it to finish and try again."
+                    .into(),
+            ));
+        }
+    };
+    let result = send();
+    if let Err(e) = &result
+        && is_wrong_pin(e)
+    {
+        let failures = SESSION_FAILURES.fetch_add(1, Ordering::Relaxed) + 1;
+        log::warn!("Wrong PIN; {} wrong this session", failures);
+    }
+    result
+}
+
+/// The NFC form of `pin`, as sent to the authenticator.
+pub fn normalize(pin: &str) -> String {
+    pin.nfc().collect()
+}
+
+/// Length of `pin` in code points after normalization, as the device counts it.
+pub fn code_points(pin: &str) -> usize {
+    pin.nfc().count()
+}
+
+/// Normalize a new PIN and check it against the CTAP length rules.
+///
+/// Returns the normalized PIN or a message suitable for the PIN dialog. The
+/// device may still reject it if its `minPINLength` is above the default.
+pub fn prepare_new(pin: &str) -> Result<String, String> {
+    let normalized = normalize(pin);
+    if normalized.contains('\0') {
+        return Err("PIN cannot contain a NUL character".into());
+    }
+    let count = code_points(pin);
+    if count < MIN_CODE_POINTS {
+        return Err(format!(
+            "PIN must be at least {} characters",
+            MIN_CODE_POINTS
+        ));
+    }
+    if normalized.len() > MAX_BYTES {
+        return Err(format!(
+            "PIN is too long: {} characters take {} bytes, the limit is {} bytes",
+            count,
+            normalized.len(),
+            MAX_BYTES
+        ));
+    }
+    Ok(normalized)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attempt_budget_thresholds() {
+        assert_eq!(attempt_budget(8), AttemptBudget::Ample);
+        assert_eq!(attempt_budget(3), AttemptBudget::Ample);
+        assert_eq!(attempt_budget(2), AttemptBudget::Low);
+        assert_eq!(attempt_budget(1), AttemptBudget::Final);
+        assert_eq!(attempt_budget(0), AttemptBudget::Final);
+    }
+
+    #[test]
+    fn test_attempt_refuses_concurrent_checks_and_counts_wrong_pins() {
+        let before = session_failures();
+        let nested = attempt(|| {
+            attempt(|| Ok(())).map_err(|e| PFError::Device(format!("inner: {}", e)))?;
+            Ok(())
+        });
+        assert!(
+            nested
+                .unwrap_err()
+                .to_string()
+                .contains("still in progress")
+        );
+
+        let wrong: Result<(), _> = attempt(|| Err(PFError::CtapError(Ctap2Error::PinInvalid)));
+        assert!(wrong.is_err());
+        assert!(attempt(|| Ok(())).is_ok());
+        assert!(session_failures() > before);
+    }
+
+    #[test]
+    fn test_combining_characters_normalize_to_precomposed() {
+        let decomposed = "cafe\u{0301}";
+        let precomposed = "caf\u{00E9}";
+        assert_ne!(decomposed.as_bytes(), precomposed.as_bytes());
+        assert_eq!(normalize(decomposed), precomposed);
+        assert_eq!(code_points(decomposed), 4);
+        assert_eq!(prepare_new(decomposed), Ok(precomposed.to_string()));
+    }
+
+    #[test]
+    fn test_length_counts_code_points_not_bytes() {
+        // Four emoji are four code points but 16 bytes; three umlauts are six
+        // bytes but only three code points.
+        assert_eq!(prepare_new("🔑🔑🔑🔑").as_deref(), Ok("🔑🔑🔑🔑"));
+        assert!(prepare_new("🔑🔑🔑").is_err());
+        assert!(prepare_new("äöü").is_err());
+        assert!(prepare_new("äöüß").is_ok());
+    }
+
+    #[test]
+    fn test_byte_limit() {
+        // 15 emoji are 60 bytes; 16 are 64 and no longer fit the padded block.
+        assert!(prepare_new(&"🔑".repeat(15)).is_ok());
+        assert!(prepare_new(&"🔑".repeat(16)).is_err());
+        assert!(prepare_new(&"a".repeat(63)).is_ok());
+        assert!(prepare_new(&"a".repeat(64)).is_err());
+    }
+
+    #[test]
+    fn test_rejects_nul() {
+        assert!(prepare_new("12\u{0}34").is_err());
+    }
+}
@@ -0,0 +1,453 @@
+//! Built-in CTAP2 reference: commands, CBOR map keys, option names, error
+//! codes and vendor command IDs with one-line descriptions.
+//!
+//! Codes come from the enums in [`super::constants`], so the table cannot drift
+//! from what PicoForge actually sends. Section numbers refer to the CTAP 2.1
+//! Proposed Standard. [`search`] backs the reference panel; [`command`] and
+//! [`error`] annotate wire traces.
+
+use super::constants::{
+    ClientPinParam, ClientPinSubCommand, ConfigSubCommand, CredentialMgmtSubCommand, Ctap2Error,
+    CtapCommand, GetAssertionParam, MakeCredentialParam, VendorCommand, VendorConfigCommand,
+};
+use SpecCategory as C;
+use serde_cbor_2::Value;
+
+/// Which table an entry belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpecCategory {
+    /// CTAP2 command byte.
+    Command,
+    /// `authenticatorMakeCredential` request key.
+    MakeCredentialKey,
+    /// `authenticatorGetAssertion` request key.
+    GetAssertionKey,
+    /// `authenticatorGetInfo` response key.
+    GetInfoKey,
+    /// `authenticatorClientPIN` request key.
+    ClientPinKey,
+    /// `authenticatorClientPIN` sub-command.
+    ClientPinSubCommand,
+    /// `authenticatorCredentialManagement` sub-command.
+    CredentialMgmtSubCommand,
+    /// `authenticatorConfig` sub-command.
+    ConfigSubCommand,
+    /// GetInfo / request option name.
+    Option,
+    /// CTAP2 status code.
+    Error,
+    /// pico-fido vendor CBOR command (`0xC1`).
+    VendorCommand,
+    /// pico-fido 64-bit vendor config command ID.
+    VendorConfig,
+}
+
+impl SpecCategory {
+    /// Label shown next to each entry.
+    pub fn label(self) -> &'static str {
+        match self {
+            SpecCategory::Command => "Command",
+            SpecCategory::MakeCredentialKey => "makeCredential key",
+            SpecCategory::GetAssertionKey => "getAssertion key",
+            SpecCategory::GetInfoKey => "getInfo key",
+            SpecCategory::ClientPinKey => "clientPIN key",
+            SpecCategory::ClientPinSubCommand => "clientPIN subCommand",
+            SpecCategory::CredentialMgmtSubCommand => "credMgmt subCommand",
+            SpecCategory::ConfigSubCommand => "config subCommand",
+            SpecCategory::Option => "Option",
+            SpecCategory::Error => "Error",
+            SpecCategory::VendorCommand => "Vendor command",
+            SpecCategory::VendorConfig => "Vendor config ID",
+        }
+    }
+}
+
+/// One row of the reference.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpecEntry {
+    /// Table the entry belongs to.
+    pub category: SpecCategory,
+    /// Wire value; `None` for option names, which are CBOR text keys.
+    pub code: Option<u64>,
+    /// Name used by the specification.
+    pub name: &'static str,
+    /// What it means.
+    pub description: &'static str,
+    /// CTAP 2.1 section, or the firmware it comes from.
+    pub section: &'static str,
+}
+
+impl SpecEntry {
+    /// The code as it appears in a hex trace (`0x31`, `0x6fcb19b0cbe3acfa`).
+    pub fn code_hex(&self) -> String {
+        match (self.category, self.code) {
+            (_, None) => String::new(),
+            (SpecCategory::VendorConfig, Some(code)) => format!("0x{:016x}", code),
+            (_, Some(code)) => format!("0x{:02X}", code),
+        }
+    }
+}
+
+const fn entry(
+    category: SpecCategory,
+    code: u64,
+    name: &'static str,
+    description: &'static str,
+    section: &'static str,
+) -> SpecEntry {
+    SpecEntry {
+        category,
+        code: Some(code),
+        name,
+        description,
+        section,
+    }
+}
+
+const fn option(name: &'static str, description: &'static str) -> SpecEntry {
+    SpecEntry {
+        category: SpecCategory::Option,
+        code: None,
+        name,
+        description,
+        section: "§6.4",
+    }
+}
+
+/// Every entry, grouped by category.
+#[rustfmt::skip]
+pub static ENTRIES: &[SpecEntry] = &[
+    // ── Commands (§6) ────────────────────────────────────────────────────────
+    entry(C::Command, CtapCommand::MakeCredential as u64, "authenticatorMakeCredential", "Create a new credential", "§6.1"),
+    entry(C::Command, CtapCommand::GetAssertion as u64, "authenticatorGetAssertion", "Sign a challenge with an existing credential", "§6.2"),
+    entry(C::Command, CtapCommand::GetInfo as u64, "authenticatorGetInfo", "Report versions, extensions, options and limits", "§6.4"),
+    entry(C::Command, CtapCommand::ClientPin as u64, "authenticatorClientPIN", "PIN setup, change and PIN/UV auth tokens", "§6.5"),
+    entry(C::Command, CtapCommand::Reset as u64, "authenticatorReset", "Erase all credentials and the PIN; needs a touch shortly after power-up", "§6.6"),
+    entry(C::Command, CtapCommand::GetNextAssertion as u64, "authenticatorGetNextAssertion", "Next assertion when several credentials matched", "§6.3"),
+    entry(C::Command, CtapCommand::BioEnrollment as u64, "authenticatorBioEnrollment", "Enroll, list, rename and remove fingerprints", "§6.7"),
+    entry(C::Command, CtapCommand::CredentialMgmt as u64, "authenticatorCredentialManagement", "Enumerate, delete and update discoverable credentials", "§6.8"),
+    entry(C::Command, CtapCommand::Selection as u64, "authenticatorSelection", "Ask the user to touch this authenticator", "§6.9"),
+    entry(C::Command, CtapCommand::LargeBlobs as u64, "authenticatorLargeBlobs", "Read or write the large-blob array", "§6.10"),
+    entry(C::Command, CtapCommand::Config as u64, "authenticatorConfig", "Enterprise attestation, alwaysUv, minimum PIN length", "§6.11"),
+    // ── makeCredential keys (§6.1) ───────────────────────────────────────────
+    entry(C::MakeCredentialKey, MakeCredentialParam::ClientDataHash as u64, "clientDataHash", "SHA-256 of the client data", "§6.1"),
+    entry(C::MakeCredentialKey, MakeCredentialParam::Rp as u64, "rp", "Relying party {id, name}", "§6.1"),
+    entry(C::MakeCredentialKey, MakeCredentialParam::User as u64, "user", "User {id, name, displayName}", "§6.1"),
+    entry(C::MakeCredentialKey, MakeCredentialParam::PubKeyCredParams as u64, "pubKeyCredParams", "Acceptable algorithms, preferred first", "§6.1"),
+    entry(C::MakeCredentialKey, MakeCredentialParam::ExcludeList as u64, "excludeList", "Credentials that must not already exist", "§6.1"),
+    entry(C::MakeCredentialKey, MakeCredentialParam::Extensions as u64, "extensions", "Extension inputs (credProtect, credBlob, hmac-secret, ...)", "§6.1"),
+    entry(C::MakeCredentialKey, MakeCredentialParam::Options as u64, "options", "rk, up, uv", "§6.1"),
+    entry(C::MakeCredentialKey, MakeCredentialParam::PinUvAuthParam as u64, "pinUvAuthParam", "HMAC of clientDataHash with the PIN/UV token", "§6.1"),
+    entry(C::MakeCredentialKey, MakeCredentialParam::PinUvAuthProtocol as u64, "pinUvAuthProtocol", "PIN/UV auth protocol version", "§6.1"),
+    entry(C::MakeCredentialKey, MakeCredentialParam::EnterpriseAttestation as u64, "enterpriseAttestation", "1 = vendor facilitated, 2 = platform managed", "§6.1"),
+    // ── getAssertion keys (§6.2) ─────────────────────────────────────────────
+    entry(C::GetAssertionKey, GetAssertionParam::RpId as u64, "rpId", "Relying party ID", "§6.2"),
+    entry(C::GetAssertionKey, GetAssertionParam::ClientDataHash as u64, "clientDataHash", "SHA-256 of the client data", "§6.2"),
+    entry(C::GetAssertionKey, GetAssertionParam::AllowList as u64, "allowList", "Only these credentials may answer", "§6.2"),
+    entry(C::GetAssertionKey, GetAssertionParam::Extensions as u64, "extensions", "Extension inputs (getCredBlob, hmac-secret, ...)", "§6.2"),
+    entry(C::GetAssertionKey, GetAssertionParam::Options as u64, "options", "up, uv", "§6.2"),
+    entry(C::GetAssertionKey, GetAssertionParam::PinUvAuthParam as u64, "pinUvAuthParam", "HMAC of clientDataHash with the PIN/UV token", "§6.2"),
+    entry(C::GetAssertionKey, GetAssertionParam::PinUvAuthProtocol as u64, "pinUvAuthProtocol", "PIN/UV auth protocol version", "§6.2"),
+    // ── getInfo keys (§6.4) ──────────────────────────────────────────────────
+    entry(C::GetInfoKey, 0x01, "versions", "Supported protocol versions (FIDO_2_0, FIDO_2_1, U2F_V2)", "§6.4"),
+    entry(C::GetInfoKey, 0x02, "extensions", "Supported extension identifiers", "§6.4"),
+    entry(C::GetInfoKey, 0x03, "aaguid", "Authenticator model identifier", "§6.4"),
+    entry(C::GetInfoKey, 0x04, "options", "Map of option names to booleans", "§6.4"),
+    entry(C::GetInfoKey, 0x05, "maxMsgSize", "Largest message the authenticator accepts", "§6.4"),
+    entry(C::GetInfoKey, 0x06, "pinUvAuthProtocols", "Supported PIN/UV auth protocols", "§6.4"),
+    entry(C::GetInfoKey, 0x07, "maxCredentialCountInList", "Longest allowList/excludeList", "§6.4"),
+    entry(C::GetInfoKey, 0x08, "maxCredentialIdLength", "Longest credential ID in a list", "§6.4"),
+    entry(C::GetInfoKey, 0x09, "transports", "Transports (usb, nfc, ble, internal)", "§6.4"),
+    entry(C::GetInfoKey, 0x0A, "algorithms", "Supported public key algorithms", "§6.4"),
+    entry(C::GetInfoKey, 0x0B, "maxSerializedLargeBlobArray", "Capacity of the large-blob array in bytes", "§6.4"),
+    entry(C::GetInfoKey, 0x0C, "forcePINChange", "The PIN must be changed before use", "§6.4"),
+    entry(C::GetInfoKey, 0x0D, "minPINLength", "Current minimum PIN length", "§6.4"),
+    entry(C::GetInfoKey, 0x0E, "firmwareVersion", "Firmware version as an integer", "§6.4"),
+    entry(C::GetInfoKey, 0x0F, "maxCredBlobLength", "Largest credBlob in bytes", "§6.4"),
+    entry(C::GetInfoKey, 0x10, "maxRPIDsForSetMinPINLength", "How many RP IDs may read minPINLength", "§6.4"),
+    entry(C::GetInfoKey, 0x11, "preferredPlatformUvAttempts", "UV attempts before falling back to PIN", "§6.4"),
+    entry(C::GetInfoKey, 0x12, "uvModality", "Built-in UV method bitmask", "§6.4"),
+    entry(C::GetInfoKey, 0x13, "certifications", "Certification levels (pico-fido: vendor config IDs)", "§6.4"),
+    entry(C::GetInfoKey, 0x14, "remainingDiscoverableCredentials", "Estimated free discoverable credential slots", "§6.4"),
+    entry(C::GetInfoKey, 0x15, "vendorPrototypeConfigCommands", "Vendor config command IDs accepted by authenticatorConfig", "§6.4"),
+    // ── clientPIN (§6.5) ─────────────────────────────────────────────────────
+    entry(C::ClientPinKey, ClientPinParam::PinUvAuthProtocol as u64, "pinUvAuthProtocol", "PIN/UV auth protocol version", "§6.5.5"),
+    entry(C::ClientPinKey, ClientPinParam::SubCommand as u64, "subCommand", "Operation to perform", "§6.5.5"),
+    entry(C::ClientPinKey, ClientPinParam::KeyAgreement as u64, "keyAgreement", "Platform ECDH public key (COSE_Key)", "§6.5.5"),
+    entry(C::ClientPinKey, ClientPinParam::PinUvAuthParam as u64, "pinUvAuthParam", "HMAC proving knowledge of the shared secret", "§6.5.5"),
+    entry(C::ClientPinKey, ClientPinParam::NewPinEnc as u64, "newPinEnc", "New PIN, padded to 64 bytes and encrypted", "§6.5.5"),
+    entry(C::ClientPinKey, ClientPinParam::PinHashEnc as u64, "pinHashEnc", "Encrypted LEFT(SHA-256(PIN), 16)", "§6.5.5"),
+    entry(C::ClientPinKey, ClientPinParam::Permissions as u64, "permissions", "Requested token permissions bitmask", "§6.5.5"),
+    entry(C::ClientPinKey, ClientPinParam::PermissionsRpId as u64, "rpId", "RP ID the token is scoped to", "§6.5.5"),
+    entry(C::ClientPinSubCommand, ClientPinSubCommand::GetPinRetries as u64, "getPINRetries", "Remaining PIN attempts", "§6.5.5.2"),
+    entry(C::ClientPinSubCommand, ClientPinSubCommand::GetKeyAgreement as u64, "getKeyAgreement", "Authenticator ECDH public key", "§6.5.5.4"),
+    entry(C::ClientPinSubCommand, ClientPinSubCommand::SetPin as u64, "setPIN", "Set the first PIN", "§6.5.5.5"),
+    entry(C::ClientPinSubCommand, ClientPinSubCommand::ChangePin as u64, "changePIN", "Replace the PIN", "§6.5.5.6"),
+    entry(C::ClientPinSubCommand, ClientPinSubCommand::GetPinToken as u64, "getPinToken", "Token with default permissions (CTAP 2.0)", "§6.5.5.7"),
+    entry(C::ClientPinSubCommand, ClientPinSubCommand::GetPinUvAuthTokenUsingUvWithPermissions as u64, "getPinUvAuthTokenUsingUvWithPermissions", "Token after built-in UV", "§6.5.5.8"),
+    entry(C::ClientPinSubCommand, ClientPinSubCommand::GetUvRetries as u64, "getUVRetries", "Remaining built-in UV attempts", "§6.5.5.3"),
+    entry(C::ClientPinSubCommand, ClientPinSubCommand::GetPinUvAuthTokenUsingPinWithPermissions as u64, "getPinUvAuthTokenUsingPinWithPermissions", "Token with explicit permissions and RP ID", "§6.5.5.7"),
+    // ── credentialManagement (§6.8) ──────────────────────────────────────────
+    entry(C::CredentialMgmtSubCommand, CredentialMgmtSubCommand::GetCredsMetadata as u64, "getCredsMetadata", "Count of stored and remaining discoverable credentials", "§6.8.2"),
+    entry(C::CredentialMgmtSubCommand, CredentialMgmtSubCommand::EnumerateRpsBegin as u64, "enumerateRPsBegin", "First relying party", "§6.8.3"),
+    entry(C::CredentialMgmtSubCommand, CredentialMgmtSubCommand::EnumerateRpsGetNextRp as u64, "enumerateRPsGetNextRP", "Next relying party", "§6.8.3"),
+    entry(C::CredentialMgmtSubCommand, CredentialMgmtSubCommand::EnumerateCredentialsBegin as u64, "enumerateCredentialsBegin", "First credential of a relying party", "§6.8.4"),
+    entry(C::CredentialMgmtSubCommand, CredentialMgmtSubCommand::EnumerateCredentialsGetNextCredential as u64, "enumerateCredentialsGetNextCredential", "Next credential", "§6.8.4"),
+    entry(C::CredentialMgmtSubCommand, CredentialMgmtSubCommand::DeleteCredential as u64, "deleteCredential", "Delete a discoverable credential", "§6.8.5"),
+    entry(C::CredentialMgmtSubCommand, CredentialMgmtSubCommand::UpdateUserInformation as u64, "updateUserInformation", "Replace a credential's user name and display name", "§6.8.6"),
+    // ── authenticatorConfig (§6.11) ──────────────────────────────────────────
+    entry(C::ConfigSubCommand, ConfigSubCommand::EnableEnterpriseAttestation as u64, "enableEnterpriseAttestation", "Turn on enterprise attestation", "§6.11.1"),
+    entry(C::ConfigSubCommand, ConfigSubCommand::ToggleAlwaysUv as u64, "toggleAlwaysUv", "Require UV for every operation", "§6.11.2"),
+    entry(C::ConfigSubCommand, ConfigSubCommand::SetMinPinLength as u64, "setMinPINLength", "Raise the minimum PIN length, force a PIN change", "§6.11.4"),
+    entry(C::ConfigSubCommand, ConfigSubCommand::VendorPrototype as u64, "vendorPrototype", "Vendor-defined configuration (pico-fido hardware settings)", "§6.11.3"),
+    // ── Options (§6.4) ───────────────────────────────────────────────────────
+    option("plat", "Platform device, not a roaming authenticator"),
+    option("rk", "Can store discoverable credentials"),
+    option("clientPin", "PIN supported; true once a PIN is set"),
+    option("up", "Can test user presence"),
+    option("uv", "Built-in user verification; true once enrolled"),
+    option("pinUvAuthToken", "Supports getPinUvAuthToken...WithPermissions"),
+    option("noMcGaPermissionsWithClientPin", "PIN tokens cannot carry mc/ga permissions"),
+    option("largeBlobs", "Supports authenticatorLargeBlobs"),
+    option("ep", "Enterprise attestation supported; true once enabled"),
+    option("bioEnroll", "Fingerprint enrollment supported; true once enrolled"),
+    option("userVerificationMgmtPreview", "Pre-release bioEnroll"),
+    option("uvBioEnroll", "UV token may carry the bio enrollment permission"),
+    option("authnrCfg", "Supports authenticatorConfig"),
+    option("uvAcfg", "UV token may carry the authenticatorConfig permission"),
+    option("credMgmt", "Supports authenticatorCredentialManagement"),
+    option("credentialMgmtPreview", "Pre-release credMgmt (command 0x41)"),
+    option("setMinPINLength", "Supports setMinPINLength"),
+    option("makeCredUvNotRqd", "Non-discoverable credentials can be made without UV"),
+    option("alwaysUv", "UV is required for every operation"),
+    // ── Status codes (§8.2) ──────────────────────────────────────────────────
+    entry(C::Error, Ctap2Error::Success as u64, "CTAP2_OK", "Success", "§8.2"),
+    entry(C::Error, 0x01, "CTAP1_ERR_INVALID_COMMAND", "Command not supported", "§8.2"),
+    entry(C::Error, 0x02, "CTAP1_ERR_INVALID_PARAMETER", "Invalid parameter in the request", "§8.2"),
+    entry(C::Error, 0x03, "CTAP1_ERR_INVALID_LENGTH", "Invalid message or item length", "§8.2"),
+    entry(C::Error, 0x04, "CTAP1_ERR_INVALID_SEQ", "Invalid CTAPHID continuation sequence", "§8.2"),
+    entry(C::Error, 0x05, "CTAP1_ERR_TIMEOUT", "CTAPHID message timed out", "§8.2"),
+    entry(C::Error, 0x06, "CTAP1_ERR_CHANNEL_BUSY", "Another channel is using the device", "§8.2"),
+    entry(C::Error, 0x0A, "CTAP1_ERR_LOCK_REQUIRED", "Command requires a channel lock", "§8.2"),
+    entry(C::Error, 0x0B, "CTAP1_ERR_INVALID_CHANNEL", "Channel ID is not allocated", "§8.2"),
+    entry(C::Error, Ctap2Error::CborUnexpectedType as u64, "CTAP2_ERR_CBOR_UNEXPECTED_TYPE", "A CBOR value has the wrong type", "§8.2"),
+    entry(C::Error, Ctap2Error::InvalidCbor as u64, "CTAP2_ERR_INVALID_CBOR", "Request is not valid CBOR", "§8.2"),
+    entry(C::Error, Ctap2Error::MissingParameter as u64, "CTAP2_ERR_MISSING_PARAMETER", "A required key is missing", "§8.2"),
+    entry(C::Error, Ctap2Error::LimitExceeded as u64, "CTAP2_ERR_LIMIT_EXCEEDED", "A limit was exceeded (list length, blob size)", "§8.2"),
+    entry(C::Error, Ctap2Error::FpDatabaseFull as u64, "CTAP2_ERR_FP_DATABASE_FULL", "No room for another fingerprint", "§8.2"),
+    entry(C::Error, Ctap2Error::LargeBlobStorageFull as u64, "CTAP2_ERR_LARGE_BLOB_STORAGE_FULL", "Large-blob array is full", "§8.2"),
+    entry(C::Error, Ctap2Error::CredentialExcluded as u64, "CTAP2_ERR_CREDENTIAL_EXCLUDED", "A credential in excludeList already exists", "§8.2"),
+    entry(C::Error, Ctap2Error::Processing as u64, "CTAP2_ERR_PROCESSING", "Still processing", "§8.2"),
+    entry(C::Error, Ctap2Error::InvalidCredential as u64, "CTAP2_ERR_INVALID_CREDENTIAL", "Credential not valid for this authenticator", "§8.2"),
+    entry(C::Error, Ctap2Error::UserActionPending as u64, "CTAP2_ERR_USER_ACTION_PENDING", "Waiting for the user", "§8.2"),
+    entry(C::Error, Ctap2Error::OperationPending as u64, "CTAP2_ERR_OPERATION_PENDING", "Another operation is in progress", "§8.2"),
+    entry(C::Error, Ctap2Error::NoOperations as u64, "CTAP2_ERR_NO_OPERATIONS", "No request is pending", "§8.2"),
+    entry(C::Error, Ctap2Error::UnsupportedAlgorithm as u64, "CTAP2_ERR_UNSUPPORTED_ALGORITHM", "None of the requested algorithms is supported", "§8.2"),
+    entry(C::Error, Ctap2Error::OperationDenied as u64, "CTAP2_ERR_OPERATION_DENIED", "User or policy refused the operation", "§8.2"),
+    entry(C::Error, Ctap2Error::KeyStoreFull as u64, "CTAP2_ERR_KEY_STORE_FULL", "No room for another discoverable credential", "§8.2"),
+    entry(C::Error, Ctap2Error::UnsupportedOption as u64, "CTAP2_ERR_UNSUPPORTED_OPTION", "Option not supported", "§8.2"),
+    entry(C::Error, Ctap2Error::InvalidOption as u64, "CTAP2_ERR_INVALID_OPTION", "Option value not allowed here", "§8.2"),
+    entry(C::Error, Ctap2Error::KeepaliveCancel as u64, "CTAP2_ERR_KEEPALIVE_CANCEL", "Cancelled by CTAPHID_CANCEL", "§8.2"),
+    entry(C::Error, Ctap2Error::NoCredentials as u64, "CTAP2_ERR_NO_CREDENTIALS", "No matching credential", "§8.2"),
+    entry(C::Error, Ctap2Error::UserActionTimeout as u64, "CTAP2_ERR_USER_ACTION_TIMEOUT", "The user did not touch the key in time", "§8.2"),
+    entry(C::Error, Ctap2Error::NotAllowed as u64, "CTAP2_ERR_NOT_ALLOWED", "Not allowed now (e.g. reset later than 10 s after power-up)", "§8.2"),
+    entry(C::Error, Ctap2Error::PinInvalid as u64, "CTAP2_ERR_PIN_INVALID", "Wrong PIN; one attempt used", "§8.2"),
+    entry(C::Error, Ctap2Error::PinBlocked as u64, "CTAP2_ERR_PIN_BLOCKED", "No PIN attempts left; only a reset helps", "§8.2"),
+    entry(C::Error, Ctap2Error::PinAuthInvalid as u64, "CTAP2_ERR_PIN_AUTH_INVALID", "pinUvAuthParam did not verify", "§8.2"),
+    entry(C::Error, Ctap2Error::PinAuthBlocked as u64, "CTAP2_ERR_PIN_AUTH_BLOCKED", "Three wrong PINs in a row; replug the key", "§8.2"),
+    entry(C::Error, Ctap2Error::PinNotSet as u64, "CTAP2_ERR_PIN_NOT_SET", "No PIN has been set", "§8.2"),
+    entry(C::Error, Ctap2Error::PuatRequired as u64, "CTAP2_ERR_PUAT_REQUIRED", "A PIN/UV auth token is required", "§8.2"),
+    entry(C::Error, Ctap2Error::PinPolicyViolation as u64, "CTAP2_ERR_PIN_POLICY_VIOLATION", "PIN does not meet the policy (length, complexity)", "§8.2"),
+    entry(C::Error, Ctap2Error::RequestTooLarge as u64, "CTAP2_ERR_REQUEST_TOO_LARGE", "Request exceeds maxMsgSize", "§8.2"),
+    entry(C::Error, Ctap2Error::ActionTimeout as u64, "CTAP2_ERR_ACTION_TIMEOUT", "Operation timed out", "§8.2"),
+    entry(C::Error, Ctap2Error::UpRequired as u64, "CTAP2_ERR_UP_REQUIRED", "User presence is required", "§8.2"),
+    entry(C::Error, Ctap2Error::UvBlocked as u64, "CTAP2_ERR_UV_BLOCKED", "Built-in UV is blocked", "§8.2"),
+    entry(C::Error, Ctap2Error::IntegrityFailure as u64, "CTAP2_ERR_INTEGRITY_FAILURE", "Integrity check failed (large-blob hash)", "§8.2"),
+    entry(C::Error, Ctap2Error::InvalidSubcommand as u64, "CTAP2_ERR_INVALID_SUBCOMMAND", "Sub-command not supported", "§8.2"),
+    entry(C::Error, Ctap2Error::UvInvalid as u64, "CTAP2_ERR_UV_INVALID", "Built-in UV failed", "§8.2"),
+    entry(C::Error, Ctap2Error::UnauthorizedPermission as u64, "CTAP2_ERR_UNAUTHORIZED_PERMISSION", "Token lacks the required permission", "§8.2"),
+    entry(C::Error, 0x7F, "CTAP1_ERR_OTHER", "Unspecified error", "§8.2"),
+    // ── pico-fido vendor commands ────────────────────────────────────────────
+    entry(C::VendorCommand, VendorCommand::Backup as u64, "Backup", "Export or restore the encrypted key backup", "pico-fido"),
+    entry(C::VendorCommand, VendorCommand::ManageSecurityEnvironment as u64, "ManageSecurityEnvironment", "ECDH key agreement for the secure channel", "pico-fido"),
+    entry(C::VendorCommand, VendorCommand::Unlock as u64, "Unlock", "Unlock a locked device", "pico-fido"),
+    entry(C::VendorCommand, VendorCommand::EnterpriseAttestation as u64, "EnterpriseAttestation", "Generate an enterprise attestation CSR", "pico-fido"),
+    entry(C::VendorCommand, VendorCommand::PhysicalOptions as u64, "PhysicalOptions", "Legacy LED/power options (≤ 7.2)", "pico-fido"),
+    entry(C::VendorCommand, VendorCommand::Memory as u64, "Memory", "Legacy flash statistics (≤ 7.2)", "pico-fido"),
+    entry(C::VendorCommand, VendorCommand::AdminPin as u64, "AdminPin", "Admin PIN operations (≥ 7.6)", "pico-fido"),
+    entry(C::VendorConfig, VendorConfigCommand::AuthEncryptionEnable as u64, "AuthEncryptionEnable", "Enable authenticated encryption", "pico-fido"),
+    entry(C::VendorConfig, VendorConfigCommand::AuthEncryptionDisable as u64, "AuthEncryptionDisable", "Disable authenticated encryption", "pico-fido"),
+    entry(C::VendorConfig, VendorConfigCommand::EnterpriseAttestationUpload as u64, "EnterpriseAttestationUpload", "Upload the enterprise attestation certificate", "pico-fido"),
+    entry(C::VendorConfig, VendorConfigCommand::PinComplexityPolicy as u64, "PinComplexityPolicy", "Set the PIN complexity policy", "pico-fido"),
+    entry(C::VendorConfig, VendorConfigCommand::PhysicalVidPid as u64, "PhysicalVidPid", "Set the USB VID and PID", "pico-fido"),
+    entry(C::VendorConfig, VendorConfigCommand::PhysicalLedBrightness as u64, "PhysicalLedBrightness", "Set the LED brightness", "pico-fido"),
+    entry(C::VendorConfig, VendorConfigCommand::PhysicalLedGpio as u64, "PhysicalLedGpio", "Set the LED GPIO pin", "pico-fido"),
+    entry(C::VendorConfig, VendorConfigCommand::PhysicalOptions as u64, "PhysicalOptions", "Set the options bitmask (dimmable, power-reset, steady)", "pico-fido"),
+];
+
+fn find(category: SpecCategory, code: u64) -> Option<&'static SpecEntry> {
+    ENTRIES
+        .iter()
+        .find(|e| e.category == category && e.code == Some(code))
+}
+
+/// The CTAP2 command for a `CTAPHID_CBOR` request's first byte.
+pub fn command(code: u8) -> Option<&'static SpecEntry> {
+    find(SpecCategory::Command, code as u64)
+}
+
+/// The status code for a `CTAPHID_CBOR` response's first byte.
+pub fn error(code: u8) -> Option<&'static SpecEntry> {
+    find(SpecCategory::Error, code as u64)
+}
+
+/// The CTAP status byte in an error message such as
+/// `Device returned CTAP Error: 0x31` or `Incorrect PIN (CTAP error 0x31)`,
+/// if it is a known code.
+pub fn status_code_in(message: &str) -> Option<u8> {
+    ["Error: 0x", "Status: 0x", "CTAP error 0x"]
+        .iter()
+        .find_map(|marker| {
+            let start = message.find(marker)? + marker.len();
+            let digits = message.get(start..start + 2)?;
+            let code = u8::from_str_radix(digits, 16).ok()?;
+            error(code).map(|_| code)
+        })
+}
+
+/// The CTAP error in `message`, as [`status_code_in`] finds it. For errors
+/// that reach the UI as text; where the [`PFError`](crate::error::PFError) is
+/// at hand, match on its [`ctap`](crate::error::PFError::ctap) instead.
+pub fn ctap_error_in(message: &str) -> Option<Ctap2Error> {
+    status_code_in(message).and_then(Ctap2Error::from_u8)
+}
+
+/// `value` in CBOR diagnostic notation (RFC 8949 §8), e.g.
+/// `{1: h'0102', 2: "name", 3: [true]}`.
+pub fn cbor_diagnostic(value: &Value) -> String {
+    let list = |items: Vec<String>| items.join(", ");
+    match value {
+        Value::Null => "null".into(),
+        Value::Bool(b) => b.to_string(),
+        Value::Integer(n) => n.to_string(),
+        Value::Bytes(bytes) => format!("h'{}'", hex::encode(bytes)),
+        Value::Text(text) => format!("{:?}", text),
+        Value::Array(items) => format!("[{}]", list(items.iter().map(cbor_diagnostic).collect())),
+        Value::Map(map) => format!(
+            "{{{}}}",
+            list(
+                map.iter()
+                    .map(|(k, v)| format!("{}: {}", cbor_diagnostic(k), cbor_diagnostic(v)))
+                    .collect()
+            )
+        ),
+        other => format!("{:?}", other),
+    }
+}
+
+/// Entries matching `query` by code, name, description or category.
+///
+/// Codes match in hex with or without `0x` (`31`, `0x31`), so a byte copied
+/// from a trace finds its meaning. An empty query returns everything.
+pub fn search(query: &str) -> Vec<&'static SpecEntry> {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return ENTRIES.iter().collect();
+    }
+    let hex = query.strip_prefix("0x").unwrap_or(&query);
+    let code = u64::from_str_radix(hex, 16).ok();
+    ENTRIES
+        .iter()
+        .filter(|e| {
+            (code.is_some() && e.code == code)
+                || e.name.to_lowercase().contains(&query)
+                || e.description.to_lowercase().contains(&query)
+                || e.category.label().to_lowercase().contains(&query)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup_by_code() {
+        assert_eq!(command(0x06).unwrap().name, "authenticatorClientPIN");
+        assert_eq!(error(0x31).unwrap().name, "CTAP2_ERR_PIN_INVALID");
+        assert!(error(0x99).is_none());
+    }
+
+    #[test]
+    fn test_cbor_diagnostic_notation() {
+        let mut map = std::collections::BTreeMap::new();
+        map.insert(Value::Integer(1), Value::Bytes(vec![0x01, 0xAB]));
+        map.insert(Value::Integer(2), Value::Text("rp".into()));
+        map.insert(
+            Value::Integer(3),
+            Value::Array(vec![Value::Bool(true), Value::Null]),
+        );
+        assert_eq!(
+            cbor_diagnostic(&Value::Map(map)),
+            "{1: h'01ab', 2: \"rp\", 3: [true, null]}"
+        );
+    }
+
+    #[test]
+    fn test_ctap_error_in_wrapped_messages() {
+        let msg = format!(
+            "Failed to enumerate Relying Parties: {}",
+            Ctap2Error::PinAuthBlocked
+        );
+        assert_eq!(ctap_error_in(&msg), Some(Ctap2Error::PinAuthBlocked));
+        assert_eq!(ctap_error_in("Flash 0x3200 bytes used"), None);
+    }
+
+    #[test]
+    fn test_status_code_in_error_messages() {
+        assert_eq!(
+            status_code_in("Device returned CTAP Error: 0x31"),
+            Some(0x31)
+        );
+        assert_eq!(
+            status_code_in("FIDO Operation Failed with Status: 0x2E"),
+            Some(0x2E)
+        );
+        assert_eq!(
+            status_code_in(&Ctap2Error::NoCredentials.to_string()),
+            Some(0x2E)
+        );
+        assert_eq!(status_code_in("Device returned CTAP Error: 0x99"), None);
+        assert_eq!(status_code_in("No device found"), None);
+    }
+
+    #[test]
+    fn test_search_matches_hex_and_names() {
+        let hits = search("0x31");
+        assert!(hits.iter().any(|e| e.name == "CTAP2_ERR_PIN_INVALID"));
+        assert!(search("31").len() >= hits.len());
+
+        let hits = search("credblob");
+        assert!(hits.iter().any(|e| e.name == "maxCredBlobLength"));
+
+        let hits = search("6fcb19b0cbe3acfa");
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].code_hex(), "0x6fcb19b0cbe3acfa");
+
+        assert_eq!(search("  ").len(), ENTRIES.len());
+    }
+
+    #[test]
+    fn test_codes_unique_per_category() {
+        for (i, a) in ENTRIES.iter().enumerate() {
+            for b in &ENTRIES[i + 1..] {
+                assert!(
+                    a.category != b.category || a.code.is_none() || a.code != b.code,
+                    "duplicate {} {}",
+                    a.category.label(),
+                    a.code_hex()
+                );
+            }
+        }
+    }
+}
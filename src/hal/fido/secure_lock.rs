@@ -0,0 +1,67 @@
+//! Secure lock: keeping the key's device secret encrypted under a key held
+//! by the host.
+//!
+//! With secure lock on, pico-fido stores its device key encrypted and only
+//! decrypts it after a power cycle when the host sends the right host key
+//! (vendor `Unlock`); until then it cannot sign. The host key never crosses
+//! USB in the clear: each command that carries it first runs the MSE key
+//! agreement ([`super::mse`]), and the key travels sealed under the channel
+//! that agreement sets up.
+//!
+//! Enabling and disabling go through `authenticatorConfig` vendor commands
+//! and take the PIN. Where the host key is kept is up to the caller
+//! ([`crate::secure_lock`]).
+
+use super::constants::*;
+use super::mse::SecureChannel;
+use super::ops::FidoOperations;
+use crate::error::PFError;
+use crate::hal::transport::fido::HidTransport;
+use serde_cbor_2::Value;
+
+/// Length of the host key.
+pub const HOST_KEY_LEN: usize = 32;
+
+/// Turn secure lock on, binding the key to `host_key`.
+pub fn enable(pin: &str, host_key: &[u8; HOST_KEY_LEN]) -> Result<(), PFError> {
+    let transport = HidTransport::open()?;
+    let pin_token = transport.get_pin_token_with_permission(
+        pin,
+        PinUvAuthTokenPermissions::AUTHENTICATOR_CONFIG,
+        None,
+    )?;
+    let sealed = SecureChannel::establish(&transport)?.seal(host_key)?;
+    transport.send_vendor_config(
+        &pin_token,
+        VendorConfigCommand::AuthEncryptionEnable,
+        Value::Bytes(sealed),
+    )?;
+    log::info!("Secure lock enabled");
+    Ok(())
+}
+
+/// Turn secure lock off; the device key is stored in the clear again.
+pub fn disable(pin: &str) -> Result<(), PFError> {
+    let transport = HidTransport::open()?;
+    let pin_token = transport.get_pin_token_with_permission(
+        pin,
+        PinUvAuthTokenPermissions::AUTHENTICATOR_CONFIG,
+        None,
+    )?;
+    transport.send_vendor_config(
+        &pin_token,
+        VendorConfigCommand::AuthEncryptionDisable,
+        Value::Integer(0),
+    )?;
+    log::info!("Secure lock disabled");
+    Ok(())
+}
+
+/// Unlock a secure-locked key for this power cycle with `host_key`.
+pub fn unlock(host_key: &[u8; HOST_KEY_LEN]) -> Result<(), PFError> {
+    let transport = HidTransport::open()?;
+    let sealed = SecureChannel::establish(&transport)?.seal(host_key)?;
+    transport.unlock(&sealed)?;
+    log::info!("Secure-locked key unlocked");
+    Ok(())
+}
@@ -0,0 +1,273 @@
+//! Firmware build fingerprinting.
+//!
+//! Compares what a device reports in `authenticatorGetInfo` (AAGUID, CTAP
+//! versions, options, algorithms) against a database of known official
+//! GetInfo shapes in `static/firmware-fingerprints.json`. A device that
+//! claims a pico-fido or RS-Key AAGUID but whose combination matches no known
+//! shape is likely running modified or counterfeit firmware, which matters
+//! for boards bought pre-flashed from third parties.
+//!
+//! The database records no firmware version ranges: there is no published
+//! source mapping releases to GetInfo shapes, so the version a key reports
+//! is not checked. An AAGUID that another firmware also uses (LK-ONE ships
+//! with pico-fido's) cannot be attributed to one family, so a mismatch there
+//! is reported as [`FingerprintVerdict::Ambiguous`], not as suspicious.
+//! Algorithms GetInfo lists that PicoForge does not know are dropped when
+//! parsing and never reach this check.
+
+use crate::hal::types::FidoDeviceInfo;
+use serde::Deserialize;
+use std::sync::OnceLock;
+
+/// One known GetInfo shape as stored in the database.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+struct KnownBuild {
+    name: String,
+    family: String,
+    aaguid: String,
+    /// Other firmware known to report the same AAGUID.
+    #[serde(default)]
+    shared_with: Vec<String>,
+    ctap_versions: Vec<String>,
+    required_options: Vec<String>,
+    required_algorithms: Vec<String>,
+    allowed_algorithms: Vec<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Database {
+    builds: Vec<KnownBuild>,
+}
+
+/// Result of comparing a device against the known builds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FingerprintVerdict {
+    /// Matches a known official build.
+    Known {
+        /// Name of the matching build line.
+        build: String,
+    },
+    /// Claims a known AAGUID, but the reported capabilities match no build.
+    Mismatch {
+        /// Firmware family the AAGUID belongs to.
+        family: String,
+        /// What differs from the closest known build.
+        reasons: Vec<String>,
+    },
+    /// Matches no build, but the AAGUID is shared with firmware the
+    /// database has no shapes for, so the mismatch proves nothing.
+    Ambiguous {
+        /// Firmware family the AAGUID belongs to.
+        family: String,
+        /// The other firmware using the same AAGUID.
+        shared_with: Vec<String>,
+    },
+    /// AAGUID not in the database (other vendor, or a custom build).
+    UnknownAaguid,
+}
+
+impl FingerprintVerdict {
+    /// Whether the user should be warned about this device.
+    pub fn is_suspicious(&self) -> bool {
+        matches!(self, Self::Mismatch { .. })
+    }
+}
+
+fn database() -> &'static [KnownBuild] {
+    static DB: OnceLock<Vec<KnownBuild>> = OnceLock::new();
+    DB.get_or_init(|| {
+        match serde_json::from_str::<Database>(include_str!(
+            "../../../static/firmware-fingerprints.json"
+        )) {
+            Ok(db) => db.builds,
+            Err(e) => {
+                log::error!("Firmware fingerprint database is invalid: {}", e);
+                Vec::new()
+            }
+        }
+    })
+}
+
+/// Compare `info` against the built-in database.
+pub fn check_fingerprint(info: &FidoDeviceInfo) -> FingerprintVerdict {
+    check_against(info, database())
+}
+
+fn check_against(info: &FidoDeviceInfo, builds: &[KnownBuild]) -> FingerprintVerdict {
+    let family: Vec<&KnownBuild> = builds
+        .iter()
+        .filter(|b| b.aaguid.eq_ignore_ascii_case(&info.aaguid))
+        .collect();
+    if family.is_empty() {
+        return FingerprintVerdict::UnknownAaguid;
+    }
+
+    let mut closest: Option<(&KnownBuild, Vec<String>)> = None;
+    for build in &family {
+        let reasons = mismatches(info, build);
+        if reasons.is_empty() {
+            return FingerprintVerdict::Known {
+                build: build.name.clone(),
+            };
+        }
+        // Report against the build with the fewest differences.
+        if closest
+            .as_ref()
+            .is_none_or(|(_, closest)| reasons.len() < closest.len())
+        {
+            closest = Some((build, reasons));
+        }
+    }
+
+    let (build, reasons) = closest.expect("family is not empty");
+    if !build.shared_with.is_empty() {
+        return FingerprintVerdict::Ambiguous {
+            family: build.family.clone(),
+            shared_with: build.shared_with.clone(),
+        };
+    }
+    FingerprintVerdict::Mismatch {
+        family: build.family.clone(),
+        reasons,
+    }
+}
+
+fn mismatches(info: &FidoDeviceInfo, build: &KnownBuild) -> Vec<String> {
+    let mut reasons = Vec::new();
+
+    for v in &build.ctap_versions {
+        if !info.versions.contains(v) {
+            reasons.push(format!("Does not report CTAP version {}", v));
+        }
+    }
+    for opt in &build.required_options {
+        if !info.options.contains_key(opt) {
+            reasons.push(format!("Missing option \"{}\"", opt));
+        }
+    }
+    for alg in &build.required_algorithms {
+        if !info.algorithms.contains(alg) {
+            reasons.push(format!("Missing algorithm {}", alg));
+        }
+    }
+    for alg in &info.algorithms {
+        if !build.allowed_algorithms.contains(alg) {
+            reasons.push(format!("Unexpected algorithm {}", alg));
+        }
+    }
+
+    reasons
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hal::types::{PICOFIDO_AAGUID, RSKEY_AAGUID};
+    use std::collections::HashMap;
+
+    fn pico_fido_7_info() -> FidoDeviceInfo {
+        FidoDeviceInfo {
+            versions: vec!["U2F_V2".into(), "FIDO_2_0".into(), "FIDO_2_1".into()],
+            extensions: vec![],
+            aaguid: PICOFIDO_AAGUID.into(),
+            options: HashMap::from([
+                ("rk".into(), true),
+                ("up".into(), true),
+                ("clientPin".into(), true),
+                ("credMgmt".into(), true),
+            ]),
+            max_msg_size: 1200,
+            pin_protocols: vec![1, 2],
+            remaining_discoverable_credentials: None,
+            min_pin_length: 4,
+            firmware_version: "7.4".into(),
+            vendor_config_commands: vec![],
+            certifications: HashMap::new(),
+            max_credential_count_in_list: None,
+            max_credential_id_length: None,
+            algorithms: vec!["ES256".into(), "EdDSA".into()],
+            max_serialized_large_blob_array: None,
+            force_pin_change: None,
+            max_cred_blob_length: None,
+            max_rpids_for_min_pin_length: None,
+            unknown_fields: vec![],
+        }
+    }
+
+    #[test]
+    fn test_database_parses() {
+        assert!(!database().is_empty());
+    }
+
+    #[test]
+    fn test_known_build() {
+        assert_eq!(
+            check_fingerprint(&pico_fido_7_info()),
+            FingerprintVerdict::Known {
+                build: "pico-fido (CTAP 2.1)".into()
+            }
+        );
+    }
+
+    #[test]
+    fn test_mismatch_on_unexpected_algorithm() {
+        let mut info = pico_fido_7_info();
+        info.aaguid = RSKEY_AAGUID.into();
+        info.algorithms.push("RS256".into());
+
+        let verdict = check_fingerprint(&info);
+        assert!(verdict.is_suspicious());
+        let FingerprintVerdict::Mismatch { reasons, .. } = verdict else {
+            unreachable!()
+        };
+        assert_eq!(reasons, vec!["Unexpected algorithm RS256".to_string()]);
+    }
+
+    #[test]
+    fn test_shared_aaguid_mismatch_is_not_suspicious() {
+        let mut info = pico_fido_7_info();
+        info.algorithms.push("RS256".into());
+
+        let verdict = check_fingerprint(&info);
+        assert_eq!(
+            verdict,
+            FingerprintVerdict::Ambiguous {
+                family: "pico-fido".into(),
+                shared_with: vec!["LK-ONE".into()],
+            }
+        );
+        assert!(!verdict.is_suspicious());
+    }
+
+    #[test]
+    fn test_version_is_not_checked() {
+        let mut info = pico_fido_7_info();
+        info.firmware_version = "99.0".into();
+        assert!(matches!(
+            check_fingerprint(&info),
+            FingerprintVerdict::Known { .. }
+        ));
+    }
+
+    #[test]
+    fn test_unknown_aaguid() {
+        let mut info = pico_fido_7_info();
+        info.aaguid = "00000000000000000000000000000000".into();
+        assert_eq!(check_fingerprint(&info), FingerprintVerdict::UnknownAaguid);
+    }
+
+    #[test]
+    fn test_rskey_pqc_build() {
+        let mut info = pico_fido_7_info();
+        info.aaguid = RSKEY_AAGUID.into();
+        info.firmware_version = "5.7.4".into();
+        info.algorithms.push("ML-DSA-44".into());
+        assert_eq!(
+            check_fingerprint(&info),
+            FingerprintVerdict::Known {
+                build: "RS-Key".into()
+            }
+        );
+    }
+}
@@ -15,6 +15,9 @@
 //! [`crate::hal::types`]. LK-ONE shares pico-fido's AAGUID and is
 //! treated as a pico-fido variant.
 //!
+//! [`fingerprint`] additionally checks the full GetInfo response against
+//! known official builds to flag modified or counterfeit firmware.
+//!
 //! ## Trait methods
 //!
 //! | Method | What it gates |
@@ -23,7 +26,9 @@
 //! | `supports_fido_config_write` | Whether `authenticatorConfig` + `vendorPrototype` writes can be used for config. |
 //! | `supports_rs_key_vendor_command` | Whether RS-Key-specific vendor commands (0x05 etc.) are available. |
 //! | `supports_rescue_channel` | Whether the PC/SC rescue channel is accessible. |
+//! | `tested_range` | Oldest and newest releases this version of PicoForge was tested with; drives [`Compatibility`]. |
 
+pub mod fingerprint;
 pub mod picofido;
 pub mod rskey;
 
@@ -43,6 +48,20 @@ pub enum AnyFirmware {
     RSKey(RSKeyFirmware),
 }
 
+/// How a device's firmware version relates to the releases this version of
+/// PicoForge was tested with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compatibility {
+    /// Within the tested range.
+    Tested,
+    /// Newer than the newest tested release; some features may misbehave.
+    NewerThanTested,
+    /// Older than the oldest supported release; an upgrade is recommended.
+    TooOld,
+    /// The version is missing or could not be parsed.
+    Unknown,
+}
+
 /// Capability gating per firmware variant.
 ///
 /// Each method queries a static or version-derived capability flag.
@@ -67,6 +86,25 @@ pub trait FirmwareTrait {
     fn supports_rs_key_vendor_command(&self) -> bool;
     /// Whether the PC/SC rescue channel can be activated.
     fn supports_rescue_channel(&self) -> bool;
+    /// Oldest and newest `(major, minor)` releases PicoForge was tested with.
+    fn tested_range(&self) -> ((u16, u16), (u16, u16));
+
+    /// Classify the firmware version against [`tested_range`](Self::tested_range).
+    fn compatibility(&self) -> Compatibility {
+        let version = self.version();
+        // Unparseable versions are stored as the 0.0 default.
+        if version.major == 0 && version.minor == 0 {
+            return Compatibility::Unknown;
+        }
+        let ((lo_major, lo_minor), (hi_major, hi_minor)) = self.tested_range();
+        if !version.is_at_least(lo_major, lo_minor) {
+            Compatibility::TooOld
+        } else if version.is_between(lo_major, lo_minor, hi_major, hi_minor) {
+            Compatibility::Tested
+        } else {
+            Compatibility::NewerThanTested
+        }
+    }
 }
 
 impl AnyFirmware {
@@ -176,4 +214,34 @@ impl AnyFirmware {
             Self::RSKey(_) => true,
         }
     }
+
+    /// How the inner firmware's version relates to the tested range.
+    pub fn compatibility(&self) -> Compatibility {
+        match self {
+            Self::PicoFido(fw) => fw.compatibility(),
+            Self::RSKey(fw) => fw.compatibility(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compatibility_pico_fido() {
+        let compat = |v: &str| AnyFirmware::new(FirmwareType::PicoFido, v).compatibility();
+        assert_eq!(compat("7.4"), Compatibility::Tested);
+        assert_eq!(compat("5.0"), Compatibility::Tested);
+        assert_eq!(compat("4.9"), Compatibility::TooOld);
+        assert_eq!(compat("8.0"), Compatibility::NewerThanTested);
+        assert_eq!(compat("garbage"), Compatibility::Unknown);
+    }
+
+    #[test]
+    fn test_compatibility_rskey_uses_sdk_version() {
+        let compat = |v: &str| AnyFirmware::new(FirmwareType::RSKey, v).compatibility();
+        assert_eq!(compat("5.7.4"), Compatibility::Tested);
+        assert_eq!(compat("6.0"), Compatibility::NewerThanTested);
+    }
 }
@@ -64,4 +64,9 @@ impl FirmwareTrait for PicoFidoFirmware {
     fn supports_rescue_channel(&self) -> bool {
         true
     }
+
+    /// 5.x is the oldest line still supported; 7.6 is the newest release tested.
+    fn tested_range(&self) -> ((u16, u16), (u16, u16)) {
+        ((5, 0), (7, 6))
+    }
 }
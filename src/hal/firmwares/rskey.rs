@@ -55,4 +55,9 @@ impl FirmwareTrait for RSKeyFirmware {
     fn supports_rescue_channel(&self) -> bool {
         true
     }
+
+    /// SDK versions reported in GetInfo, not RS-Key release numbers.
+    fn tested_range(&self) -> ((u16, u16), (u16, u16)) {
+        ((5, 0), (5, 9))
+    }
 }
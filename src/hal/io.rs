@@ -3,50 +3,68 @@
 //! Each public function here selects the appropriate protocol path based
 //! on the detected firmware type or an explicit [`DeviceMethod`] parameter.
 //! Some functions (e.g. `read_device_details`) try Rescue (PC/SC) first,
-//! then FIDO, and merge results to produce a complete status snapshot.
+//! then FIDO (or in the order set in `picoforge.toml`), and merge results to
+//! produce a complete status snapshot.
+//!
+//! Everything here blocks. The UI runs it on the HID worker thread with
+//! [`on_hid_thread`] and awaits the [`Pending`] result, or waits for it from
+//! a background task. Flows that need the
+//! key to themselves hold a [`MaintenanceLatch`] from [`begin_maintenance`].
 
 use crate::{
     error::PFError,
-    hal::{fido, rescue, transport::DeviceHandle, types::*},
+    hal::{backend, fido, rescue, transport::manager::DeviceManager, types::*},
+    tunables,
+};
+
+pub use crate::hal::backend::BackendCapabilities;
+
+pub use crate::hal::transport::worker::{
+    MaintenanceLatch, Pending, begin_maintenance, maintenance_reason,
 };
 
+/// Run `job` on the HID worker thread, after the jobs already queued there.
+/// Await the result rather than blocking the caller.
+pub fn on_hid_thread<T, F>(job: F) -> Pending<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    crate::hal::transport::worker::submit(job)
+}
+
+/// What the key reported in its last CTAPHID_KEEPALIVE while a request is
+/// pending, `None` when nothing is waiting on it.
+pub fn keepalive_status() -> Option<KeepaliveStatus> {
+    crate::hal::transport::fido::keepalive_status()
+}
+
 /// Read full device status by merging FIDO and Rescue data where available.
 ///
-/// Tries the FIDO HID transport first, then falls back to the PC/SC
-/// rescue channel. When both succeed, fields from the more detailed
-/// source are used (e.g. serial/flash from Rescue, AAGUID from FIDO).
+/// Each [`DeviceBackend`](backend::DeviceBackend) is asked in turn; a
+/// transport with no key on it just falls through to the next. When both
+/// succeed, fields from the more detailed source are used (e.g. serial/flash
+/// from Rescue, AAGUID from FIDO).
+///
+/// Backends are tried in `transport.order` from `picoforge.toml` (or the
+/// connected key's override); one left out of the list is not read. Writes
+/// and the rescue tools still open the transport they need.
 pub fn read_device_details() -> Result<FullDeviceStatus, PFError> {
     let mut fido_status: Option<FullDeviceStatus> = None;
     let mut rescue_status: Option<FullDeviceStatus> = None;
-    let mut rescue_fw_type: Option<FirmwareType> = None;
-
-    // Discover via Rescue/PC/SC transport (preferred for richer details)
-    match DeviceHandle::try_rescue() {
-        Ok(Some((handle, _identity))) => {
-            rescue_fw_type = Some(handle.firmware_type());
-            match rescue::read_device_details() {
-                Ok(status) => {
-                    log::info!("Rescue device details read successfully");
-                    rescue_status = Some(status);
-                }
-                Err(e) => log::warn!("Rescue read_device_details failed: {}", e),
-            }
-        }
-        Ok(None) => log::info!("No Rescue PC/SC device found"),
-        Err(e) => log::warn!("Rescue PC/SC discovery error: {}", e),
-    }
 
-    // Discover via FIDO/HID transport (fallback for FIDO-only details)
-    match DeviceHandle::try_fido() {
-        Ok(Some((_handle, _identity))) => match fido::read_device_details() {
+    for backend in backend::ordered(&tunables::transport_order()) {
+        match backend.read_status() {
             Ok(status) => {
-                log::info!("FIDO device details read successfully");
-                fido_status = Some(status);
+                log::info!("{} device details read successfully", backend.name());
+                match backend.method() {
+                    DeviceMethod::Fido => fido_status = Some(status),
+                    DeviceMethod::Rescue => rescue_status = Some(status),
+                }
             }
-            Err(e) => log::warn!("FIDO read_device_details failed: {}", e),
-        },
-        Ok(None) => log::info!("No FIDO HID device found"),
-        Err(e) => log::warn!("FIDO HID discovery error: {}", e),
+            Err(PFError::NoDevice) => log::info!("No {} device found", backend.name()),
+            Err(e) => log::warn!("{} read_device_details failed: {}", backend.name(), e),
+        }
     }
 
     match (fido_status, rescue_status) {
@@ -55,8 +73,8 @@ pub fn read_device_details() -> Result<FullDeviceStatus, PFError> {
             Ok(FullDeviceStatus {
                 info: DeviceInfo {
                     serial: rescue.info.serial,
-                    flash_used: rescue.info.flash_used,
-                    flash_total: rescue.info.flash_total,
+                    flash_used: rescue.info.flash_used.or(fido.info.flash_used),
+                    flash_total: rescue.info.flash_total.or(fido.info.flash_total),
                     firmware_version: fido.info.firmware_version,
                 },
                 config: AppConfig {
@@ -75,6 +93,7 @@ pub fn read_device_details() -> Result<FullDeviceStatus, PFError> {
                     led_dimmable: rescue.config.led_dimmable,
                     power_cycle_on_reset: rescue.config.power_cycle_on_reset,
                     led_steady: rescue.config.led_steady,
+                    raw_options: rescue.config.raw_options,
                     enable_secp256k1: rescue.config.enable_secp256k1,
                     led_driver: rescue.config.led_driver.or_else(|| {
                         if fido.config.led_driver.is_some() {
@@ -97,58 +116,88 @@ pub fn read_device_details() -> Result<FullDeviceStatus, PFError> {
                     led_num: rescue.config.led_num,
                 },
                 secure_boot: rescue.secure_boot,
-                secure_lock: rescue.secure_lock,
+                secure_boot_locked: rescue.secure_boot_locked,
+                secure_lock: fido.secure_lock,
                 method: DeviceMethod::Rescue,
                 firmware_type: fido.firmware_type,
             })
         }
         (Some(fido), None) => {
             log::info!("Using FIDO-only device details");
-            Ok(FullDeviceStatus {
-                firmware_type: fido.firmware_type,
-                ..fido
-            })
+            Ok(fido)
         }
         (None, Some(rescue)) => {
             log::info!("Using Rescue-only device details");
-            let ft = rescue_fw_type.filter(|_| rescue.firmware_type == FirmwareType::Unknown);
-            Ok(FullDeviceStatus {
-                firmware_type: ft.unwrap_or(rescue.firmware_type),
-                ..rescue
-            })
+            Ok(rescue)
         }
         (None, None) => {
             log::error!("Failed to read device details via both FIDO and Rescue");
+            // The key is gone or not answering; a cached channel to it is stale.
+            DeviceManager::shared().disconnect();
             Err(PFError::NoDevice)
         }
     }
 }
 
-#[allow(dead_code)]
 /// Enable or lock secure boot on the device (Rescue-only operation).
 pub fn enable_secure_boot(lock: bool) -> Result<String, PFError> {
     rescue::enable_secure_boot(lock)
 }
 
+/// Turn secure lock on, binding the key to `host_key` (FIDO, needs the PIN).
+pub fn enable_secure_lock(
+    pin: String,
+    host_key: [u8; fido::secure_lock::HOST_KEY_LEN],
+) -> Result<String, PFError> {
+    fido::secure_lock::enable(&pin, &host_key)?;
+    Ok("Secure lock enabled.".to_string())
+}
+
+/// Turn secure lock off (FIDO, needs the PIN).
+pub fn disable_secure_lock(pin: String) -> Result<String, PFError> {
+    fido::secure_lock::disable(&pin)?;
+    Ok("Secure lock disabled.".to_string())
+}
+
+/// Unlock a secure-locked key for this power cycle.
+pub fn unlock_device(host_key: [u8; fido::secure_lock::HOST_KEY_LEN]) -> Result<String, PFError> {
+    fido::secure_lock::unlock(&host_key)?;
+    Ok("Key unlocked.".to_string())
+}
+
 #[allow(dead_code)]
 /// Reboot the device (normal or BOOTSEL mode) via the Rescue channel.
 pub fn reboot(to_bootsel: bool) -> Result<String, PFError> {
     rescue::reboot_device(to_bootsel)
 }
 
-/// Write device configuration, selecting FIDO or Rescue path by method.
+/// Write device configuration through the backend for `method`, then read
+/// it back and report which fields took effect.
 ///
-/// The FIDO path requires a PIN; the Rescue path does not.
+/// The FIDO path requires a PIN; the Rescue path does not (see
+/// [`backend_capabilities`]).
 pub fn write_config(
     config: AppConfigInput,
     method: DeviceMethod,
     pin: Option<String>,
-) -> Result<String, PFError> {
-    if method == DeviceMethod::Fido {
-        fido::write_config(config, pin)
-    } else {
-        rescue::write_config(config)
-    }
+) -> Result<ConfigApplyReport, PFError> {
+    backend::write_and_verify(backend::for_method(method).as_ref(), config, pin)
+}
+
+/// What the backend for `method` needs or offers.
+pub fn backend_capabilities(method: DeviceMethod) -> BackendCapabilities {
+    backend::for_method(method).capabilities()
+}
+
+/// Read flash usage as `(used, total)` KiB via the specified transport method,
+/// without re-reading the rest of the device status. `Ok(None)` when the
+/// device does not report it.
+pub fn read_flash_usage(method: DeviceMethod) -> Result<Option<(u32, u32)>, PFError> {
+    let usage = match method {
+        DeviceMethod::Fido => fido::read_memory_stats()?,
+        DeviceMethod::Rescue => Some(rescue::read_flash_info()?),
+    };
+    Ok(usage.map(|(used, total)| (used / 1024, total / 1024)))
 }
 
 /// Read the LED status configuration via the specified transport method.
@@ -219,6 +268,93 @@ pub fn write_management_config(
     }
 }
 
+/// Enumerate the USB descriptors the OS currently reports for the attached key.
+pub fn read_usb_descriptors() -> Option<UsbDescriptorInfo> {
+    crate::hal::transport::fido::HidTransport::usb_descriptors()
+}
+
+/// `vid:pid:serial` of the attached FIDO device, from enumeration only.
+pub fn device_fingerprint() -> Option<String> {
+    crate::hal::transport::fido::HidTransport::fingerprint()
+}
+
+/// Every attached FIDO HID interface, from enumeration only.
+pub fn list_fido_devices() -> Vec<DeviceDescriptor> {
+    crate::hal::transport::fido::HidTransport::enumerate()
+}
+
+/// Path of the FIDO interface operations currently go to.
+pub fn active_fido_device() -> Option<String> {
+    crate::hal::transport::fido::HidTransport::active_path()
+}
+
+/// Manage the FIDO interface at `path` (from [`list_fido_devices`]) from now on.
+pub fn select_fido_device(path: &str) -> Result<DeviceDescriptor, String> {
+    crate::hal::transport::fido::HidTransport::select_path(path).map_err(|e| e.to_string())
+}
+
+/// Ask every attached key for a touch and manage the one touched from now on.
+pub fn select_device_by_touch() -> Result<DeviceDescriptor, String> {
+    crate::hal::transport::fido::HidTransport::select_by_touch().map_err(|e| e.to_string())
+}
+
+/// Make the active key blink (CTAPHID_WINK) so the user can tell which one
+/// PicoForge manages.
+pub fn wink() -> Result<(), String> {
+    crate::hal::transport::fido::HidTransport::open()
+        .and_then(|transport| transport.wink())
+        .map_err(|e| e.to_string())
+}
+
+/// Time CTAPHID_PING round trips and throughput on the active key.
+pub fn ping_self_test() -> Result<PingSelfTest, String> {
+    crate::hal::transport::fido::HidTransport::open()
+        .and_then(|transport| transport.self_test())
+        .map_err(|e| e.to_string())
+}
+
+/// CTAPHID exchanges and failures since the last call, across all devices.
+pub fn take_transport_tally() -> TransportTally {
+    crate::hal::transport::fido::take_tally()
+}
+
+/// Identify boards in BOOTSEL mode (keys rebooted to the loader) from their
+/// boot drives and PICOBOOT interfaces.
+pub fn scan_bootsel() -> Vec<crate::hal::bootsel::BootselBoard> {
+    crate::hal::bootsel::scan()
+}
+
+/// Burn an RP2350 whitelabel into the OTP of the one board in BOOTSEL mode
+/// (PICOBOOT). Irreversible.
+pub fn write_whitelabel(label: &crate::hal::bootsel::Whitelabel) -> Result<String, PFError> {
+    crate::hal::bootsel::write_whitelabel(label)
+}
+
+/// Check that hidapi works and that every FIDO interface can be opened.
+pub fn probe_hid() -> Result<HidProbe, PFError> {
+    crate::hal::transport::fido::HidTransport::probe()
+}
+
+/// Check that the PC/SC service is reachable; returns the reader count.
+pub fn probe_pcsc() -> Result<usize, PFError> {
+    crate::hal::transport::pcsc::PcscTransport::probe()
+}
+
+/// Probe which optional applets (OpenPGP, OATH) respond over PC/SC, on the
+/// card the rescue channel resolves to. None are reported without one.
+pub fn probe_applets() -> AppletPresence {
+    use crate::hal::rescue::constants::{OATH_AID, OPENPGP_AID};
+    use crate::hal::transport::pcsc::PcscTransport;
+
+    let Ok(transport) = PcscTransport::open() else {
+        return AppletPresence::default();
+    };
+    AppletPresence {
+        openpgp: transport.applet_responds(OPENPGP_AID),
+        oath: transport.applet_responds(OATH_AID),
+    }
+}
+
 /// Retrieve the FIDO authenticator metadata (GetInfo) as [`FidoDeviceInfo`].
 pub(crate) fn get_fido_info() -> Result<FidoDeviceInfo, String> {
     fido::get_fido_info()
@@ -232,12 +368,20 @@ pub(crate) fn change_fido_pin(
     fido::change_fido_pin(current_pin, new_pin)
 }
 
-/// Set a new minimum PIN length on the authenticator.
+/// Set a new minimum PIN length on the authenticator, optionally with the
+/// RP IDs allowed to read it and a forced PIN change.
 pub(crate) fn set_min_pin_length(
     current_pin: String,
     min_pin_length: u8,
+    rp_ids: Vec<String>,
+    force_change_pin: bool,
 ) -> Result<String, String> {
-    fido::set_min_pin_length(current_pin, min_pin_length)
+    fido::set_min_pin_length(current_pin, min_pin_length, rp_ids, force_change_pin)
+}
+
+/// Toggle the `alwaysUv` option; returns the new state.
+pub(crate) fn toggle_always_uv(pin: String) -> Result<bool, String> {
+    fido::toggle_always_uv(pin)
 }
 
 /// Enumerate all credentials stored on the authenticator.
@@ -245,9 +389,151 @@ pub fn get_credentials(pin: String) -> Result<Vec<StoredCredential>, String> {
     fido::get_credentials(pin)
 }
 
-/// Delete a credential from the authenticator by credential ID.
-pub fn delete_credential(pin: String, credential_id: String) -> Result<String, String> {
-    fido::delete_credential(pin, credential_id)
+/// Read the stored and remaining discoverable credential counts.
+pub fn get_credentials_metadata(pin: String) -> Result<CredentialsMetadata, String> {
+    fido::get_credentials_metadata(pin)
+}
+
+/// Delete several credentials with one PIN token; returns the failures.
+pub fn delete_credentials_bulk(
+    pin: String,
+    credential_ids: Vec<String>,
+) -> Result<Vec<(String, String)>, String> {
+    fido::delete_credentials_bulk(pin, credential_ids)
+}
+
+/// Read the credential list and serialize it as JSON or CSV.
+pub fn export_credentials(pin: String, format: CredentialExportFormat) -> Result<String, String> {
+    fido::export_credentials(pin, format)
+}
+
+/// Replace the user name and display name of a stored credential.
+pub fn update_credential_user(
+    pin: String,
+    credential_id: String,
+    user_id: String,
+    name: String,
+    display_name: String,
+) -> Result<String, String> {
+    fido::update_credential_user(pin, credential_id, user_id, name, display_name)
+}
+
+/// Create a test credential (diagnostics); waits for a touch.
+pub fn create_test_credential(
+    request: TestCredentialRequest,
+    pin: Option<String>,
+) -> Result<TestCredential, String> {
+    fido::diag::create_test_credential(request, pin)
+}
+
+/// Get an assertion from a test credential, reading back its `credBlob`.
+pub fn get_test_assertion(
+    rp_id: String,
+    credential_id: String,
+    pin: Option<String>,
+) -> Result<TestAssertion, String> {
+    fido::diag::get_test_assertion(rp_id, credential_id, pin)
+}
+
+/// Sign in with one stored passkey and verify the signature with its
+/// stored public key; waits for a touch.
+pub fn test_sign_in(
+    rp_id: String,
+    credential_id: String,
+    public_key: Vec<u8>,
+    pin: Option<String>,
+) -> Result<SignInTest, String> {
+    fido::diag::test_sign_in(rp_id, credential_id, public_key, pin)
+}
+
+/// Get assertions from every discoverable credential of `rp_id` via
+/// GetNextAssertion; waits for one touch.
+pub fn sweep_test_assertions(rp_id: String, pin: Option<String>) -> Result<AssertionSweep, String> {
+    fido::diag::sweep_test_assertions(rp_id, pin)
+}
+
+/// Summarize the large-blob array (no PIN, no touch).
+pub fn read_large_blob_array() -> Result<LargeBlobInfo, String> {
+    fido::large_blob::read_large_blob_array()
+}
+
+/// Read one credential's large blob; waits for a touch.
+pub fn read_large_blob(
+    rp_id: String,
+    credential_id: String,
+    pin: Option<String>,
+) -> Result<LargeBlobInfo, String> {
+    fido::large_blob::read_large_blob(rp_id, credential_id, pin)
+}
+
+/// Store or (with empty `data`) remove one credential's large blob; waits for a touch.
+pub fn write_large_blob(
+    rp_id: String,
+    credential_id: String,
+    data: Vec<u8>,
+    pin: String,
+) -> Result<LargeBlobInfo, String> {
+    fido::large_blob::write_large_blob(rp_id, credential_id, data, pin)
+}
+
+/// Create a discoverable credential with hmac-secret; waits for a touch.
+/// Returns the credential ID, hex-encoded.
+pub fn create_hmac_secret_credential(
+    rp_id: String,
+    user_name: String,
+    pin: String,
+) -> Result<String, String> {
+    fido::hmac_secret::create_hmac_secret_credential(rp_id, user_name, pin)
+}
+
+/// Derive a credential's hmac-secret output for `salt`; waits for a touch.
+pub fn derive_hmac_secret(
+    rp_id: String,
+    credential_id: String,
+    salt: [u8; 32],
+    pin: String,
+) -> Result<[u8; 32], String> {
+    fido::hmac_secret::derive_hmac_secret(rp_id, credential_id, salt, pin)
+}
+
+/// Save the key's encrypted backup to `path`, reporting progress. Returns the
+/// size of the backup blob.
+pub fn backup_device(
+    path: std::path::PathBuf,
+    progress: impl FnMut(fido::backup::BackupProgress),
+) -> Result<usize, String> {
+    fido::backup::backup_device(&path, progress).map_err(|e| e.to_string())
+}
+
+/// Restore an encrypted backup file from `path` to the key, reporting
+/// progress. Returns the size of the backup blob.
+pub fn restore_device(
+    path: std::path::PathBuf,
+    progress: impl FnMut(fido::backup::BackupProgress),
+) -> Result<usize, String> {
+    fido::backup::restore_device(&path, progress).map_err(|e| e.to_string())
+}
+
+/// Fingerprint sensor details (no PIN).
+pub fn read_bio_sensor_info() -> Result<BioSensorInfo, String> {
+    fido::bio::read_sensor_info()
+}
+
+/// List enrolled fingerprints.
+pub fn list_fingerprints(pin: String) -> Result<Vec<BioTemplate>, String> {
+    fido::bio::list_fingerprints(pin)
+}
+
+/// Delete one enrolled fingerprint.
+pub fn remove_fingerprint(pin: String, template_id: String) -> Result<(), String> {
+    fido::bio::remove_fingerprint(pin, template_id)
+}
+
+/// Start a fingerprint enrollment; waits for the first sample.
+pub fn begin_fingerprint_enrollment(
+    pin: String,
+) -> Result<(fido::bio::BioEnrollSession, BioEnrollSample), String> {
+    fido::bio::BioEnrollSession::begin(pin)
 }
 
 /// Perform a factory reset on the authenticator.
@@ -255,6 +541,21 @@ pub fn reset_device() -> Result<String, String> {
     fido::reset_device()
 }
 
+/// Read the remaining PIN attempts (no PIN required).
+pub fn get_pin_retries() -> Result<PinRetries, String> {
+    fido::get_pin_retries()
+}
+
+/// Read the remaining PIN and built-in UV attempts (no PIN required).
+pub fn get_pin_status() -> Result<PinStatus, String> {
+    fido::get_pin_status()
+}
+
+/// Require a touch and the PIN before a USB identity change is written.
+pub fn confirm_identity_change(pin: String) -> Result<String, String> {
+    fido::confirm_identity_change(pin)
+}
+
 /// Enable enterprise attestation on the authenticator.
 pub fn enable_enterprise_attestation(pin: String) -> Result<String, String> {
     fido::enable_enterprise_attestation(pin)
@@ -265,6 +566,13 @@ pub fn get_enterprise_attestation_csr() -> Result<String, String> {
     fido::get_enterprise_attestation_csr()
 }
 
+/// Read back which enterprise attestation certificate the key carries.
+pub fn read_enterprise_attestation_cert(
+    pin: Option<String>,
+) -> Result<Option<AttestationCertInfo>, String> {
+    fido::diag::read_enterprise_attestation_cert(pin)
+}
+
 /// Upload an X.509 certificate for enterprise attestation.
 pub fn upload_enterprise_attestation_cert(
     pin: String,
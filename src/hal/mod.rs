@@ -4,19 +4,28 @@
 //! hal/
 //! ├── mod.rs       — module root
 //! ├── io.rs        — high-level entry points dispatching across protocols
+//! ├── backend.rs   — DeviceBackend trait over the FIDO and Rescue paths
+//! ├── config_plan.rs — configuration writes as ordered per-field operations
 //! ├── types.rs     — shared structs, enums, and constants
+//! ├── bootsel.rs   — RP2040/RP2350 BOOTSEL board info and RP2350 OTP whitelabel
 //! ├── common/      — COSE algorithm/curve enums and firmware-version parsing
 //! │   ├── cose.rs
 //! │   └── version.rs
 //! ├── firmwares/   — per-firmware capability gating (PicoFido, RSKey)
+//! │   ├── fingerprint.rs — GetInfo comparison against known builds
 //! │   ├── picofido.rs
 //! │   └── rskey.rs
-//! ├── transport/   — physical transport abstractions (HID, PC/SC)
+//! ├── transport/   — physical transport abstractions (HID, PC/SC, PICOBOOT)
 //! │   ├── fido.rs  — CTAPHID framing over USB HID
-//! │   └── pcsc.rs  — ISO 7816-4 APDU over PC/SC
+//! │   ├── pcsc.rs  — ISO 7816-4 APDU over PC/SC
+//! │   └── picoboot.rs — RP2040/RP2350 boot ROM over USB bulk (reads, RP2350 OTP)
 //! ├── fido/        — FIDO2 / CTAP2 protocol implementation
+//! │   ├── bio.rs       — authenticatorBioEnrollment (fingerprints)
 //! │   ├── constants.rs — CTAP2 command codes, CBOR keys, vendor commands
-//! │   └── ops.rs       — FidoOperations trait, PIN/credential management
+//! │   ├── large_blob.rs — authenticatorLargeBlobs and per-credential blobs
+//! │   ├── ops.rs       — FidoOperations trait, PIN/credential management
+//! │   ├── pin.rs       — PIN normalization and length rules
+//! │   └── reference.rs — CTAP2 reference table for the spec lookup panel
 //! └── rescue/      — Rescue applet protocol (PC/SC APDU)
 //!     ├── constants.rs — ISO 7816-4 constants, PHY tags, vendor AIDs
 //!     └── ops.rs       — RescueOperations trait
@@ -30,10 +39,16 @@
 //! [`transport`] discovers the device and returns a [`DeviceHandle`](crate::hal::transport::DeviceHandle)
 //! wrapping either a FIDO HID or Rescue PC/SC connection.
 //! [`fido`] and [`rescue`] implement the protocol-level operations.
+//! [`backend`] wraps both behind [`DeviceBackend`](crate::hal::backend::DeviceBackend)
+//! for the operations they share (status, configuration); configuration
+//! writes go through a [`ConfigPlan`](crate::hal::config_plan::ConfigPlan).
 //! [`io`] sits on top and exposes one function per device operation,
 //! selecting the correct protocol path based on the detected firmware.
 
+pub mod backend;
+pub mod bootsel;
 pub mod common;
+pub mod config_plan;
 pub mod fido;
 pub mod firmwares;
 pub mod io;
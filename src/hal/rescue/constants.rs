@@ -369,10 +369,10 @@ bitflags::bitflags! {
         /// the LED is only on/off.
         const LED_DIMMABLE = 0x02;
 
-        /// Disable power-on reset detection.
+        /// Disable power-reset protection.
         ///
-        /// When set, the device doesn't reset on power-on events.
-        /// Useful for devices with unstable power supply.
+        /// When clear, `authenticatorReset` is only accepted shortly after
+        /// the key is plugged in. When set, a reset is accepted at any time.
         const DISABLE_POWER_RESET = 0x04;
 
         /// LED stays steady (no blinking).
@@ -383,6 +383,31 @@ bitflags::bitflags! {
     }
 }
 
+impl RescueOptions {
+    /// Apply the option switches to the word currently stored on the device.
+    ///
+    /// `None` leaves a flag as it is. Bits without a switch (WCID, and
+    /// anything newer firmware adds) are carried over unchanged.
+    pub fn merge(
+        current: u16,
+        led_dimmable: Option<bool>,
+        power_cycle_on_reset: Option<bool>,
+        led_steady: Option<bool>,
+    ) -> u16 {
+        let mut opts = Self::from_bits_retain(current);
+        if let Some(on) = led_dimmable {
+            opts.set(Self::LED_DIMMABLE, on);
+        }
+        if let Some(on) = power_cycle_on_reset {
+            opts.set(Self::DISABLE_POWER_RESET, !on);
+        }
+        if let Some(on) = led_steady {
+            opts.set(Self::LED_STEADY, on);
+        }
+        opts.bits()
+    }
+}
+
 /// Enabled cryptographic curves bitflags.
 ///
 /// These flags define which elliptic curves are available for
@@ -423,6 +448,20 @@ bitflags::bitflags! {
     }
 }
 
+impl RescueCurves {
+    /// Apply the secp256k1 switch to a curves mask.
+    ///
+    /// `None` leaves the mask as it is. Bits this app does not know are
+    /// carried over unchanged.
+    pub fn merge(current: u32, enable_secp256k1: Option<bool>) -> u32 {
+        let mut curves = Self::from_bits_retain(current);
+        if let Some(on) = enable_secp256k1 {
+            curves.set(Self::SECP256K1, on);
+        }
+        curves.bits()
+    }
+}
+
 /// Enabled USB interfaces bitflags.
 ///
 /// These flags define which USB interfaces are active on the device.
@@ -728,3 +767,14 @@ pub const USB_CAP_OATH: u16 = 0x0020;
 
 /// USB capability: FIDO2/CTAP2 interface.
 pub const USB_CAP_FIDO2: u16 = 0x0200;
+
+// --- 6. Standard Applets (presence probing) ---
+
+/// OpenPGP card applet AID (RID `D2 76 00 01 24`, application `01`).
+///
+/// Selected only to check that the applet responds; PicoForge does not
+/// manage OpenPGP keys itself.
+pub const OPENPGP_AID: &[u8] = &[0xD2, 0x76, 0x00, 0x01, 0x24, 0x01];
+
+/// Yubico-compatible OATH applet AID (`A0 00 00 05 27 21 01`).
+pub const OATH_AID: &[u8] = &[0xA0, 0x00, 0x00, 0x05, 0x27, 0x21, 0x01];
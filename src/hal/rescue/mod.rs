@@ -13,9 +13,20 @@ use crate::hal::transport::pcsc::PcscTransport;
 use crate::hal::types::*;
 use ops::RescueOperations;
 
-/// Read full device status via the Rescue applet (PC/SC transport).
+/// Read full device status via the Rescue applet (PC/SC transport). The
+/// firmware type comes from the SELECT answer when the status leaves it open.
 pub fn read_device_details() -> Result<FullDeviceStatus, PFError> {
-    PcscTransport::open()?.read_device_details()
+    let transport = PcscTransport::open()?;
+    let mut status = transport.read_device_details()?;
+    if status.firmware_type == FirmwareType::Unknown {
+        status.firmware_type = transport.firmware_type.clone();
+    }
+    Ok(status)
+}
+
+/// Read flash usage as `(used, total)` bytes via the Rescue applet.
+pub fn read_flash_info() -> Result<(u32, u32), PFError> {
+    PcscTransport::open()?.read_flash_info()
 }
 
 /// Write PHY configuration to the device via the Rescue applet.
@@ -23,6 +34,11 @@ pub fn write_config(config: AppConfigInput) -> Result<String, PFError> {
     PcscTransport::open()?.write_config(config)
 }
 
+/// Read back the stored PHY configuration via the Rescue applet.
+pub fn read_stored_config() -> Result<AppConfig, PFError> {
+    PcscTransport::open()?.read_stored_config()
+}
+
 /// Reboot the device (normal or BOOTSEL mode) via the Rescue applet.
 pub fn reboot_device(to_bootsel: bool) -> Result<String, PFError> {
     PcscTransport::open()?.reboot_device(to_bootsel)
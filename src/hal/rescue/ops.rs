@@ -134,7 +134,7 @@
 //! - `read_device_details()`: Reads full device status via Rescue
 //! - `write_config()`: Writes PHY configuration (VID/PID, LED, curves, etc.)
 //! - `reboot_device()`: Reboots device (normal or BOOTSEL mode)
-//! - `enable_secure_boot()`: Enables secure boot (WIP)
+//! - `enable_secure_boot()`: Enables, and optionally locks, secure boot
 //! - `read_led_config()` / `write_led_status()`: LED color configuration (RS-Key)
 //! - `read_management_config()` / `write_management_config()`: USB interface config (RS-Key)
 //!
@@ -172,11 +172,15 @@ use std::io::Cursor;
 pub trait RescueOperations {
     /// Read full device status (info, config, security flags) via the Rescue applet.
     fn read_device_details(&self) -> Result<FullDeviceStatus, PFError>;
+    /// Read flash usage as `(used, total)` bytes via the Rescue applet.
+    fn read_flash_info(&self) -> Result<(u32, u32), PFError>;
+    /// Read back the stored PHY record alone, for checking a write.
+    fn read_stored_config(&self) -> Result<AppConfig, PFError>;
     /// Write PHY configuration (VID/PID, LED, curves, etc.) via the Rescue applet.
     fn write_config(&self, config: AppConfigInput) -> Result<String, PFError>;
     /// Reboot the device — either normally or into BOOTSEL (firmware-update) mode.
     fn reboot_device(&self, to_bootsel: bool) -> Result<String, PFError>;
-    /// Enable or lock secure boot on the device (WIP / firmware-specific).
+    /// Enable secure boot on the device, and optionally lock it (irreversible).
     fn enable_secure_boot(&self, lock: bool) -> Result<String, PFError>;
     /// Read LED status configuration from the vendor LED applet (RS-Key only).
     fn read_led_config(&self) -> Result<LedStatusConfig, PFError>;
@@ -194,6 +198,158 @@ pub trait RescueOperations {
     fn write_management_config(&self, enabled_mask: u16) -> Result<String, PFError>;
 }
 
+/// Read the raw PHY record (status word stripped), for the fields `WRITE`
+/// merges into rather than replaces.
+fn read_phy_record(transport: &PcscTransport) -> Result<Vec<u8>, PFError> {
+    let mut rx_buf = [0; 256];
+    let response = transport.transmit(
+        &[
+            APDU_CLA_PROPRIETARY,
+            RescueInstruction::Read as u8,
+            ReadParam::PhyConfig as u8,
+            0x01,
+            0x00,
+        ],
+        &mut rx_buf,
+    )?;
+    if !response.ends_with(&[0x90, 0x00]) {
+        return Err(PFError::Device(
+            "Could not read the current configuration; nothing was written.".into(),
+        ));
+    }
+    Ok(response[..response.len() - 2].to_vec())
+}
+
+/// The options word of a PHY record. `None` when the record has no options
+/// tag, i.e. every option bit is clear.
+fn phy_options(data: &[u8]) -> Option<u16> {
+    phy_tlvs(data)
+        .find(|(tag, value)| *tag == PhyTag::Opts as u8 && value.len() >= 2)
+        .map(|(_, value)| u16::from_be_bytes([value[0], value[1]]))
+}
+
+/// The enabled-curves mask of a PHY record. `None` when the record has no
+/// curves tag and the firmware uses its built-in set.
+fn phy_curves(data: &[u8]) -> Option<u32> {
+    phy_tlvs(data)
+        .find(|(tag, value)| *tag == PhyTag::Curves as u8 && value.len() == 4)
+        .map(|(_, value)| u32::from_be_bytes([value[0], value[1], value[2], value[3]]))
+}
+
+/// The touch timeout of a PHY record, in seconds. `None` when the record has
+/// no presence tag and the firmware waits its default 30 s.
+fn phy_presence_timeout(data: &[u8]) -> Option<u8> {
+    phy_tlvs(data)
+        .find(|(tag, value)| *tag == PhyTag::PresenceTimeout as u8 && !value.is_empty())
+        .map(|(_, value)| value[0])
+}
+
+/// Tag/value pairs of a `READ(PhyConfig)` response body, in order. Stops at
+/// the first entry that runs past the end of `data`.
+fn phy_tlvs(data: &[u8]) -> impl Iterator<Item = (u8, &[u8])> {
+    let mut offset = 0;
+    std::iter::from_fn(move || {
+        let tag = *data.get(offset)?;
+        let len = *data.get(offset + 1)? as usize;
+        let value = data.get(offset + 2..offset + 2 + len)?;
+        offset += 2 + len;
+        Some((tag, value))
+    })
+}
+
+/// Decode a `READ(PhyConfig)` response body (status word stripped). Unknown
+/// tags and values of the wrong size are skipped.
+fn parse_phy_config(data: &[u8]) -> AppConfig {
+    let mut config = AppConfig::default();
+    for (tag_byte, field_data) in phy_tlvs(data) {
+        let Some(tag) = PhyTag::from_u8(tag_byte) else {
+            continue;
+        };
+        match tag {
+            PhyTag::VidPid => {
+                if field_data.len() == 4 {
+                    let vid = u16::from_be_bytes([field_data[0], field_data[1]]);
+                    let pid = u16::from_be_bytes([field_data[2], field_data[3]]);
+                    config.vid = format!("{:04X}", vid);
+                    config.pid = format!("{:04X}", pid);
+                }
+            }
+            PhyTag::LedGpio => {
+                if !field_data.is_empty() {
+                    config.led_gpio = Some(field_data[0]);
+                }
+            }
+            PhyTag::LedBrightness => {
+                if !field_data.is_empty() {
+                    config.led_brightness = Some(field_data[0]);
+                }
+            }
+            PhyTag::PresenceTimeout => {
+                if !field_data.is_empty() {
+                    config.touch_timeout = Some(field_data[0]);
+                }
+            }
+            PhyTag::UsbProduct => {
+                let product_str = std::str::from_utf8(field_data)
+                    .unwrap_or("")
+                    .trim_matches(char::from(0));
+                config.product_name = product_str.to_string();
+            }
+            PhyTag::Opts => {
+                if field_data.len() >= 2 {
+                    let options_raw = u16::from_be_bytes([field_data[0], field_data[1]]);
+                    let opts = RescueOptions::from_bits_truncate(options_raw);
+
+                    config.led_dimmable = opts.contains(RescueOptions::LED_DIMMABLE);
+                    config.power_cycle_on_reset =
+                        !opts.contains(RescueOptions::DISABLE_POWER_RESET);
+                    config.led_steady = opts.contains(RescueOptions::LED_STEADY);
+                    config.raw_options = Some(options_raw);
+                }
+            }
+            PhyTag::Curves => {
+                if field_data.len() == 4 {
+                    let raw_curves_value = u32::from_be_bytes([
+                        field_data[0],
+                        field_data[1],
+                        field_data[2],
+                        field_data[3],
+                    ]);
+                    config.raw_curves_mask = Some(raw_curves_value);
+                    let curves = RescueCurves::from_bits_truncate(raw_curves_value);
+                    config.enable_secp256k1 = curves.contains(RescueCurves::SECP256K1);
+                }
+            }
+            PhyTag::LedDriver => {
+                if !field_data.is_empty() {
+                    config.led_driver = Some(field_data[0]);
+                }
+            }
+            PhyTag::LedOrder => {
+                if !field_data.is_empty() {
+                    config.led_order = Some(field_data[0]);
+                }
+            }
+            PhyTag::LedNum => {
+                if !field_data.is_empty() {
+                    config.led_num = Some(field_data[0]);
+                }
+            }
+            PhyTag::EnabledUsbItf => {
+                if !field_data.is_empty() {
+                    config.enabled_usb_itf = Some(field_data[0]);
+                }
+            }
+        }
+    }
+    if config.raw_options.is_none() {
+        // No options tag: the firmware runs with every option bit clear.
+        config.raw_options = Some(0);
+        config.power_cycle_on_reset = true;
+    }
+    config
+}
+
 impl RescueOperations for PcscTransport {
     /// Reads comprehensive device details including identity, flash usage, secure boot status, and PHY configuration.
     ///
@@ -240,33 +396,15 @@ impl RescueOperations for PcscTransport {
         log::info!("Device Version: {}.{}", version_major, version_minor);
         log::info!("Device Serial: {}", serial_str);
 
-        // 2. Read Flash Info
-        let mut rx_buf = [0; 256];
-        let flash_response = self.transmit(
-            &[
-                APDU_CLA_PROPRIETARY,
-                RescueInstruction::Read as u8,
-                ReadParam::FlashInfo as u8,
-                P2_UNUSED,
-                0x00, // Le
-            ],
-            &mut rx_buf,
-        )?;
-
-        if !flash_response.ends_with(&SW_SUCCESS) {
-            return Err(PFError::Device("Failed to read flash".into()));
-        }
-
-        let mut cursor = Cursor::new(&flash_response[..flash_response.len() - 2]);
-        let _free = cursor.read_u32::<BigEndian>().unwrap_or(0);
-        let used = cursor.read_u32::<BigEndian>().unwrap_or(0);
-        let total = cursor.read_u32::<BigEndian>().unwrap_or(0);
-
-        // NOTE: captured but currently unused variables
-        let _nfiles = cursor.read_u32::<BigEndian>().unwrap_or(0);
-        let _chip_size = cursor.read_u32::<BigEndian>().unwrap_or(0);
+        // 2. Read Flash Info. Not fatal: the rest of the status is still
+        // worth showing when only the flash statistics fail.
+        let flash = self
+            .read_flash_info()
+            .inspect_err(|e| log::warn!("Flash info unavailable: {}", e))
+            .ok();
 
         // --- Read Secure Boot Status ---
+        let mut rx_buf = [0; 256];
         let secure_response = self.transmit(
             &[
                 APDU_CLA_PROPRIETARY,
@@ -299,101 +437,7 @@ impl RescueOperations for PcscTransport {
             return Err(PFError::Device("Failed to read config".into()));
         }
 
-        // Parse TLV
-        let mut config = AppConfig::default();
-        let data = &phy_response[..phy_response.len() - 2];
-        let mut offset = 0;
-        while offset < data.len() {
-            if offset + 2 > data.len() {
-                break;
-            }
-            let tag_byte = data[offset];
-            let field_len = data[offset + 1] as usize;
-            offset += 2;
-            if offset + field_len > data.len() {
-                break;
-            }
-            let field_data = &data[offset..offset + field_len];
-
-            if let Some(tag) = PhyTag::from_u8(tag_byte) {
-                match tag {
-                    PhyTag::VidPid => {
-                        if field_data.len() == 4 {
-                            let vid = u16::from_be_bytes([field_data[0], field_data[1]]);
-                            let pid = u16::from_be_bytes([field_data[2], field_data[3]]);
-                            config.vid = format!("{:04X}", vid);
-                            config.pid = format!("{:04X}", pid);
-                        }
-                    }
-                    PhyTag::LedGpio => {
-                        if !field_data.is_empty() {
-                            config.led_gpio = Some(field_data[0]);
-                        }
-                    }
-                    PhyTag::LedBrightness => {
-                        if !field_data.is_empty() {
-                            config.led_brightness = Some(field_data[0]);
-                        }
-                    }
-                    PhyTag::PresenceTimeout => {
-                        if !field_data.is_empty() {
-                            config.touch_timeout = Some(field_data[0]);
-                        }
-                    }
-                    PhyTag::UsbProduct => {
-                        let product_str = std::str::from_utf8(field_data)
-                            .unwrap_or("")
-                            .trim_matches(char::from(0));
-                        config.product_name = product_str.to_string();
-                    }
-                    PhyTag::Opts => {
-                        if field_data.len() >= 2 {
-                            let options_raw = u16::from_be_bytes([field_data[0], field_data[1]]);
-                            let opts = RescueOptions::from_bits_truncate(options_raw);
-
-                            config.led_dimmable = opts.contains(RescueOptions::LED_DIMMABLE);
-                            config.power_cycle_on_reset =
-                                !opts.contains(RescueOptions::DISABLE_POWER_RESET);
-                            config.led_steady = opts.contains(RescueOptions::LED_STEADY);
-                        }
-                    }
-                    PhyTag::Curves => {
-                        if field_data.len() == 4 {
-                            let raw_curves_value = u32::from_be_bytes([
-                                field_data[0],
-                                field_data[1],
-                                field_data[2],
-                                field_data[3],
-                            ]);
-                            config.raw_curves_mask = Some(raw_curves_value);
-                            let curves = RescueCurves::from_bits_truncate(raw_curves_value);
-                            config.enable_secp256k1 = curves.contains(RescueCurves::SECP256K1);
-                        }
-                    }
-                    PhyTag::LedDriver => {
-                        if !field_data.is_empty() {
-                            config.led_driver = Some(field_data[0]);
-                        }
-                    }
-                    PhyTag::LedOrder => {
-                        if !field_data.is_empty() {
-                            config.led_order = Some(field_data[0]);
-                        }
-                    }
-                    PhyTag::LedNum => {
-                        if !field_data.is_empty() {
-                            config.led_num = Some(field_data[0]);
-                        }
-                    }
-                    PhyTag::EnabledUsbItf => {
-                        if !field_data.is_empty() {
-                            config.enabled_usb_itf = Some(field_data[0]);
-                        }
-                    }
-                }
-            }
-            offset += field_len;
-        }
+        let config = parse_phy_config(&phy_response[..phy_response.len() - 2]);
 
         log::info!(
             "Successfully read device details - Serial: {}, Firmware: {}.{}",
@@ -405,18 +449,56 @@ impl RescueOperations for PcscTransport {
         Ok(FullDeviceStatus {
             info: DeviceInfo {
                 serial: serial_str,
-                flash_used: Some(used / 1024),
-                flash_total: Some(total / 1024),
+                flash_used: flash.map(|(used, _)| used / 1024),
+                flash_total: flash.map(|(_, total)| total / 1024),
                 firmware_version: format!("{}.{}", version_major, version_minor),
             },
             config,
             secure_boot: sb_enabled,
-            secure_lock: sb_locked,
+            secure_boot_locked: sb_locked,
+            secure_lock: false,
             method: DeviceMethod::Rescue,
             firmware_type: fw_type.clone(),
         })
     }
 
+    /// Reads flash usage with `READ(FlashInfo)`.
+    ///
+    /// The response holds five big-endian `u32`s: free, used, total, number
+    /// of files and chip size; only used and total are returned.
+    fn read_flash_info(&self) -> Result<(u32, u32), PFError> {
+        let mut rx_buf = [0; 256];
+        let flash_response = self.transmit(
+            &[
+                APDU_CLA_PROPRIETARY,
+                RescueInstruction::Read as u8,
+                ReadParam::FlashInfo as u8,
+                P2_UNUSED,
+                0x00, // Le
+            ],
+            &mut rx_buf,
+        )?;
+
+        if !flash_response.ends_with(&SW_SUCCESS) {
+            return Err(PFError::Device("Failed to read flash".into()));
+        }
+
+        let mut cursor = Cursor::new(&flash_response[..flash_response.len() - 2]);
+        let _free = cursor.read_u32::<BigEndian>().unwrap_or(0);
+        let used = cursor.read_u32::<BigEndian>().unwrap_or(0);
+        let total = cursor.read_u32::<BigEndian>().unwrap_or(0);
+        Ok((used, total))
+    }
+
+    /// Reads the PHY record with `READ(PhyConfig)` and decodes it without
+    /// the identity and flash reads of [`Self::read_device_details`]. Tags
+    /// the record does not carry are left at their defaults.
+    fn read_stored_config(&self) -> Result<AppConfig, PFError> {
+        read_phy_record(self)
+            .map(|data| parse_phy_config(&data))
+            .map_err(|_| PFError::Device("Could not read the configuration back.".into()))
+    }
+
     /// Writes PHY configuration to the device via the Rescue Applet's WRITE command.
     ///
     /// Constructs a TLV (Tag-Length-Value) blob from the provided `AppConfigInput` fields and sends
@@ -424,8 +506,10 @@ impl RescueOperations for PcscTransport {
     /// - `0x00`: VID:PID (4 bytes, big-endian)
     /// - `0x04`: LED GPIO pin
     /// - `0x05`: LED brightness
-    /// - `0x08`: Touch/presence timeout
-    /// - `0x06`: Options bitmask (LED_DIMMABLE, DISABLE_POWER_RESET, LED_STEADY)
+    /// - `0x08`: Touch/presence timeout in seconds (`0` = firmware default),
+    ///   kept from the device when the input leaves it out
+    /// - `0x06`: Options bitmask (LED_DIMMABLE, DISABLE_POWER_RESET, LED_STEADY), merged
+    ///   into the word read from the device so other bits are preserved
     /// - `0x07`: Elliptic curves bitmask (SECP256K1, etc.)
     /// - `0x0C`: LED driver selection
     /// - `0x09`: USB product name (null-terminated)
@@ -443,6 +527,10 @@ impl RescueOperations for PcscTransport {
         log::info!("Writing configuration to device");
         log::debug!("Config input: {:?}", config);
 
+        // The write replaces the whole record, so fields the input leaves out
+        // are carried over from it.
+        let current = read_phy_record(self)?;
+
         // 1. Construct TLV Blob
         let mut tlv = Vec::new();
 
@@ -473,45 +561,48 @@ impl RescueOperations for PcscTransport {
             tlv.push(val);
         }
 
-        // Touch Timeout (Tag 0x08)
-        if let Some(val) = config.touch_timeout {
+        // Touch Timeout (Tag 0x08): an explicit `0` asks for the firmware
+        // default, so only an untouched field keeps the device's value.
+        if let Some(val) = config
+            .touch_timeout
+            .or_else(|| phy_presence_timeout(&current))
+        {
             tlv.push(PhyTag::PresenceTimeout as u8);
             tlv.push(0x01);
             tlv.push(val);
         }
 
-        // Options
-        if let (Some(dim), Some(cycle), Some(steady)) = (
-            config.led_dimmable,
-            config.power_cycle_on_reset,
-            config.led_steady,
-        ) {
-            let mut opts = RescueOptions::empty();
-            if dim {
-                opts.insert(RescueOptions::LED_DIMMABLE);
-            }
-            if !cycle {
-                opts.insert(RescueOptions::DISABLE_POWER_RESET);
-            }
-            if steady {
-                opts.insert(RescueOptions::LED_STEADY);
-            }
-
+        // Options: read-modify-write, so bits without a switch survive. The
+        // tag is re-emitted even when untouched because the write replaces
+        // the whole record.
+        let options_touched = config.led_dimmable.is_some()
+            || config.power_cycle_on_reset.is_some()
+            || config.led_steady.is_some();
+        let current_options = phy_options(&current);
+        if options_touched || current_options.is_some() {
+            let opts = RescueOptions::merge(
+                current_options.unwrap_or(0),
+                config.led_dimmable,
+                config.power_cycle_on_reset,
+                config.led_steady,
+            );
             tlv.push(PhyTag::Opts as u8);
             tlv.push(0x02);
-            tlv.write_u16::<BigEndian>(opts.bits()).unwrap();
+            tlv.write_u16::<BigEndian>(opts).unwrap();
         }
 
-        // Curves
-        if config.enable_secp256k1.is_some() || config.raw_curves_mask.is_some() {
-            let mut mask = config.raw_curves_mask.unwrap_or(0);
-            if let Some(enabled) = config.enable_secp256k1 {
-                if enabled {
-                    mask |= RescueCurves::SECP256K1.bits();
-                } else {
-                    mask &= !RescueCurves::SECP256K1.bits();
-                }
-            }
+        // Curves (Tag 0x0A): a full mask replaces the device's, a lone
+        // secp256k1 switch edits it. Re-emitted when untouched for the same
+        // reason as the options.
+        let current_curves = phy_curves(&current);
+        if config.enable_secp256k1.is_some()
+            || config.raw_curves_mask.is_some()
+            || current_curves.is_some()
+        {
+            let mask = RescueCurves::merge(
+                config.raw_curves_mask.or(current_curves).unwrap_or(0),
+                config.enable_secp256k1,
+            );
             tlv.push(PhyTag::Curves as u8);
             tlv.push(0x04);
             tlv.write_u32::<BigEndian>(mask).unwrap();
@@ -632,11 +723,11 @@ impl RescueOperations for PcscTransport {
         }
     }
 
-    /// Enables or disables secure boot on the device. **UNSTABLE — work in progress.**
+    /// Enables secure boot on the device, optionally locking it.
     ///
     /// Sends a SECURE APDU: `80 1D 00 [LockBool] 00` where:
-    /// - `LockBool = 0x01`: Enable and lock secure boot (irreversible on some firmware)
-    /// - `LockBool = 0x00`: Disable secure boot
+    /// - `LockBool = 0x01`: Enable and lock secure boot (irreversible)
+    /// - `LockBool = 0x00`: Enable secure boot without locking it
     ///
     /// Uses pico-fido instruction `INS_SECURE` (0x1D). RS-Key uses `INS_OTP_LOCK` (0x1B)
     /// for OTP fuse locking, which is a different operation.
@@ -651,9 +742,8 @@ impl RescueOperations for PcscTransport {
     /// - `PFError::Device` if the APDU fails or returns a non-success status
     ///
     /// # Warning
-    /// This function is unstable and may change. Locking secure boot can permanently
-    /// prevent firmware downgrades. Use with caution.
-    #[allow(dead_code)]
+    /// Locking secure boot is permanent: the device then only boots firmware
+    /// signed with its boot key, and the debug interface is locked out.
     fn enable_secure_boot(&self, lock: bool) -> Result<String, PFError> {
         // APDU: 80 1D [KeyIndex] [LockBool] 00
         // KeyIndex = 0 (Default), LockBool = 1 if true
@@ -864,3 +954,61 @@ impl RescueOperations for PcscTransport {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_phy_config_decodes_known_tags() {
+        let data = [
+            0x00, 0x04, 0x1D, 0x50, 0x61, 0x9B, // VID:PID
+            0x05, 0x01, 0x80, // LED brightness
+            0x06, 0x02, 0x00, 0x0A, // options: dimmable, steady
+            0x09, 0x06, b'L', b'K', b'-', b'O', b'N', 0x00, // product, NUL padded
+            0x0A, 0x04, 0x00, 0x00, 0x00, 0x08, // curves: secp256k1
+            0x7E, 0x01, 0xFF, // unknown tag
+        ];
+        let config = parse_phy_config(&data);
+        assert_eq!(config.vid, "1D50");
+        assert_eq!(config.pid, "619B");
+        assert_eq!(config.led_brightness, Some(0x80));
+        assert_eq!(config.product_name, "LK-ON");
+        assert_eq!(config.raw_options, Some(0x0A));
+        assert!(config.led_dimmable && config.led_steady);
+        assert!(config.power_cycle_on_reset);
+        assert_eq!(config.raw_curves_mask, Some(0x08));
+        assert!(config.enable_secp256k1);
+    }
+
+    #[test]
+    fn test_phy_config_without_options_and_truncated_tail() {
+        // The LED GPIO entry claims three bytes but only one follows.
+        let data = [0x08, 0x01, 0x0F, 0x04, 0x03, 0x19];
+        let config = parse_phy_config(&data);
+        assert_eq!(config.touch_timeout, Some(0x0F));
+        assert_eq!(config.led_gpio, None);
+        assert_eq!(config.raw_options, Some(0));
+        assert!(config.power_cycle_on_reset);
+        assert_eq!(phy_tlvs(&data).count(), 1);
+        assert_eq!(phy_options(&data), None);
+    }
+
+    #[test]
+    fn test_phy_record_options_and_curves() {
+        let data = [
+            0x06, 0x02, 0x00, 0x0A, // options
+            0x0A, 0x04, 0x00, 0x00, 0x00, 0x89, // curves: P-256, secp256k1, Ed25519
+        ];
+        assert_eq!(phy_options(&data), Some(0x0A));
+        assert_eq!(phy_curves(&data), Some(0x89));
+        assert_eq!(phy_presence_timeout(&data), None);
+        assert_eq!(phy_presence_timeout(&[0x08, 0x01, 0x0F]), Some(15));
+        // A curves entry of the wrong size is ignored.
+        assert_eq!(phy_curves(&[0x0A, 0x02, 0x00, 0x08]), None);
+        // The secp256k1 switch edits only its own bit.
+        assert_eq!(RescueCurves::merge(0x89, Some(false)), 0x81);
+        assert_eq!(RescueCurves::merge(0x801, Some(true)), 0x809);
+        assert_eq!(RescueCurves::merge(0x89, None), 0x89);
+    }
+}
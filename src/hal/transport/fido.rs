@@ -44,7 +44,10 @@
 //! 3. All subsequent communication uses this CID.
 //!
 //! This allows multiple CTAP2 sessions to coexist on one device (e.g., two
-//! browsers open simultaneously).
+//! browsers open simultaneously). When another session holds the key it
+//! answers `ERR_CHANNEL_BUSY`; [`HidTransport::send_cbor`] waits and retries
+//! that under [`TransportOptions`], and [`HidTransport::send_cbor_read`] also
+//! retries a read the key never answered.
 //!
 //! # Cryptographic operations
 //!
@@ -70,7 +73,9 @@
 //! # File structure
 //!
 //! - [`HidTransport`] — main transport struct; opens HID device, negotiates
-//!   CID, sends/receives CBOR payloads
+//!   CID, sends/receives CBOR payloads. [`HidTransport::open`] hands out the
+//!   connection kept by [`DeviceManager`](super::manager::DeviceManager);
+//!   [`HidTransport::connect`] opens a handle of its own
 //! - [`EnumerateRpResponse`](crate::hal::fido::ops::EnumerateRpResponse),
 //!   [`EnumerateCredentialResponse`](crate::hal::fido::ops::EnumerateCredentialResponse) — response
 //!   types for credential management enumeration
@@ -78,15 +83,36 @@
 //!   full ECDH + AES-CBC flow per CTAP2 §11.5.4
 //! - Vendor methods (`send_vendor_config`, `get_enterprise_attestation_csr`)
 //!   handle pico-fido/RS-Key specific extensions
+//! - [`take_tally`] — exchange and failure counts since the last call, kept
+//!   per device in the inventory to spot flaky hardware
+//! - [`HidTransport::enumerate`] / [`HidTransport::select_path`] — list every
+//!   attached FIDO interface and make one of them the *active* interface that
+//!   `open`, `fingerprint` and `usb_descriptors` use. Without a choice, or once
+//!   that key is gone, the first listed is used. Keyboard, vendor and CCID
+//!   interfaces under the same VID/PID are never listed (`fido_interfaces`).
+//! - [`HidTransport::select_by_touch`] — with several keys attached, asks all
+//!   of them for a touch (authenticatorSelection) and makes the touched one
+//!   the active interface.
 //!
 //! [CTAP2]: https://fidoalliance.org/specs/fido-v2.3-ps-20260226/fido-client-to-authenticator-protocol-v2.3-ps-20260226.html
 //! [pico-fido]: https://github.com/polhenarejos/pico-fido
 //! [RS-Key]: https://github.com/TheMaxMur/RS-Key
 
 use rand::RngExt;
-use std::time::Duration;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::time::{Duration, Instant};
 
+use super::manager::{DeviceManager, SharedTransport};
 use crate::error::PFError;
+use crate::hal::fido::constants::Ctap2Error;
+use crate::hal::fido::reference::{self, SpecEntry};
+use crate::hal::types::{
+    DeviceDescriptor, HidProbe, KeepaliveStatus, PingSelfTest, TransportTally, UsbDescriptorInfo,
+    UsbEndpointInfo, UsbInterfaceDescriptor, UsbInterfaceInfo,
+};
+use crate::logging::{self, WIRE_TARGET};
+use crate::tunables;
 
 /// Size of a single USB HID report in bytes (CTAP2 §11.2 mandates 64-byte reports).
 const HID_REPORT_SIZE: usize = 64;
@@ -97,6 +123,9 @@ const HID_REPORT_SIZE: usize = 64;
 /// as FIDO authenticators by the operating system's HID enumeration.
 const HID_USAGE_PAGE_FIDO: u16 = 0xF1D0;
 
+/// CTAPHID usage on the FIDO usage page (CTAP2 §11.2.8.1).
+const HID_USAGE_CTAPHID: u16 = 0x01;
+
 /// Broadcast Channel ID used for the initial CTAPHID_INIT handshake.
 ///
 /// The host sends an INIT command to this CID to request a unique Channel ID
@@ -109,6 +138,42 @@ const CTAPHID_CID_BROADCAST: u32 = 0xFFFFFFFF;
 /// device responds with the same nonce and a newly allocated Channel ID.
 const CTAPHID_INIT: u8 = 0x86;
 
+/// CTAPHID PING command byte (0x81).
+///
+/// The authenticator echoes the payload back; used to check that a channel
+/// still answers before it is reused, and by [`HidTransport::self_test`].
+const CTAPHID_PING: u8 = 0x81;
+
+/// Largest CTAPHID message: one init packet and 128 continuation packets
+/// (CTAP2 §11.2.4).
+const CTAPHID_MAX_PAYLOAD: usize = (HID_REPORT_SIZE - 7) + 128 * (HID_REPORT_SIZE - 5);
+
+/// Pings of each kind in [`HidTransport::self_test`].
+const SELF_TEST_PINGS: usize = 8;
+
+/// Payload of the latency pings; fits one report each way.
+const SELF_TEST_LATENCY_LEN: usize = 8;
+
+/// Payload of the throughput pings: 18 reports each way, within the message
+/// buffer of every pico key firmware.
+const SELF_TEST_THROUGHPUT_LEN: usize = 1024;
+
+/// CTAPHID WINK command byte (0x88).
+///
+/// Asks the authenticator to show a vendor-defined visual or audible
+/// identification (pico keys blink their LED). Optional; only sent when the
+/// INIT response advertises [`CAPABILITY_WINK`].
+const CTAPHID_WINK: u8 = 0x88;
+
+/// Capability flag in the CTAPHID_INIT response: the key implements WINK.
+const CAPABILITY_WINK: u8 = 0x01;
+
+/// CTAPHID CANCEL command byte (0x11).
+///
+/// Aborts the request pending on the channel; the authenticator answers it
+/// with `CTAP2_ERR_KEEPALIVE_CANCEL`. Carries no payload and gets no reply.
+const CTAPHID_CANCEL: u8 = 0x11;
+
 /// CTAPHID CBOR command byte (0x90).
 ///
 /// Wraps a CTAP2 CBOR-encoded command or response payload. The payload is
@@ -131,17 +196,447 @@ const CTAPHID_KEEPALIVE: u8 = 0xBB;
 /// Default timeout in milliseconds for draining stale HID packets.
 const HID_READ_TIMEOUT_MS: i32 = 10;
 
-/// Timeout in milliseconds for reading the CTAPHID_INIT response during channel negotiation.
-const HID_INIT_READ_TIMEOUT_MS: i32 = 100;
+/// HID reports a CTAPHID message with a `len`-byte payload takes.
+fn ctaphid_packets(len: usize) -> usize {
+    let first = HID_REPORT_SIZE - 7;
+    1 + len.saturating_sub(first).div_ceil(HID_REPORT_SIZE - 5)
+}
+
+/// hidapi path of the interface picked with [`HidTransport::select_path`] or
+/// [`HidTransport::select_by_touch`].
+static ACTIVE_PATH: Mutex<Option<String>> = Mutex::new(None);
+
+/// How long one read waits while polling several keys for a touch, so a
+/// touch on another key or a cancel is noticed quickly.
+const SELECTION_POLL_MS: i32 = 100;
+
+/// The entry of `devices` whose path is `active`, or the first one when there
+/// is no choice or the chosen key is gone.
+fn pick_active<'a, T>(
+    devices: &[&'a T],
+    active: Option<&str>,
+    path: impl Fn(&T) -> String,
+) -> Option<&'a T> {
+    active
+        .and_then(|active| devices.iter().find(|d| path(d) == active))
+        .or(devices.first())
+        .copied()
+}
+
+/// What [`fido_interfaces`] looks at in an enumerated HID entry.
+#[derive(Debug, Clone, PartialEq)]
+struct HidEntry {
+    /// VID, PID and serial, which together name one physical key.
+    device: (u16, u16, String),
+    /// `bInterfaceNumber`, -1 when the platform does not report it.
+    interface_number: i32,
+    usage_page: u16,
+    usage: u16,
+}
+
+impl HidEntry {
+    fn of(info: &hidapi::DeviceInfo) -> Self {
+        Self {
+            device: (
+                info.vendor_id(),
+                info.product_id(),
+                info.serial_number().unwrap_or("").to_string(),
+            ),
+            interface_number: info.interface_number(),
+            usage_page: info.usage_page(),
+            usage: info.usage(),
+        }
+    }
+
+    /// On the FIDO usage page with the CTAPHID usage, or with no usage
+    /// reported (some platforms leave it 0).
+    fn is_fido(&self) -> bool {
+        self.usage_page == HID_USAGE_PAGE_FIDO
+            && (self.usage == HID_USAGE_CTAPHID || self.usage == 0)
+    }
+}
+
+/// Indices of the entries to talk CTAPHID to, in enumeration order.
+///
+/// Firmware that also exposes keyboard or CCID interfaces under the same
+/// VID/PID can show up several times. Only FIDO-page entries count; a key
+/// that has one with the CTAPHID usage drops its entries without a usage,
+/// and each interface number of a key is kept once.
+fn fido_interfaces(entries: &[HidEntry]) -> Vec<usize> {
+    let explicit: Vec<_> = entries
+        .iter()
+        .filter(|e| e.is_fido() && e.usage == HID_USAGE_CTAPHID)
+        .map(|e| &e.device)
+        .collect();
+    let mut kept: Vec<usize> = Vec::new();
+    for (ix, entry) in entries.iter().enumerate() {
+        if !entry.is_fido() || (entry.usage == 0 && explicit.contains(&&entry.device)) {
+            continue;
+        }
+        let duplicate = entry.interface_number >= 0
+            && kept.iter().any(|&k| {
+                entries[k].device == entry.device
+                    && entries[k].interface_number == entry.interface_number
+            });
+        if !duplicate {
+            kept.push(ix);
+        }
+    }
+    kept
+}
+
+fn fido_devices(api: &hidapi::HidApi) -> Vec<&hidapi::DeviceInfo> {
+    let infos: Vec<_> = api.device_list().collect();
+    let entries: Vec<_> = infos.iter().map(|info| HidEntry::of(info)).collect();
+    fido_interfaces(&entries)
+        .into_iter()
+        .map(|ix| infos[ix])
+        .collect()
+}
+
+/// The FIDO interface to talk to: the active one while it is attached,
+/// otherwise the first listed.
+fn active_device(api: &hidapi::HidApi) -> Option<&hidapi::DeviceInfo> {
+    let active = ACTIVE_PATH
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    pick_active(&fido_devices(api), active.as_deref(), |d| {
+        d.path().to_string_lossy().into_owned()
+    })
+}
+
+fn set_active(device: &DeviceDescriptor) {
+    log::info!(
+        "Active FIDO interface: {:04X}:{:04X} {} ({})",
+        device.vid,
+        device.pid,
+        device.product,
+        device.path
+    );
+    *ACTIVE_PATH.lock().unwrap_or_else(|e| e.into_inner()) = Some(device.path.clone());
+    DeviceManager::shared().retain_path(&device.path);
+}
+
+fn descriptor(info: &hidapi::DeviceInfo) -> DeviceDescriptor {
+    DeviceDescriptor {
+        path: info.path().to_string_lossy().into_owned(),
+        vid: info.vendor_id(),
+        pid: info.product_id(),
+        product: info
+            .product_string()
+            .unwrap_or("Unknown FIDO Device")
+            .to_string(),
+        serial: info
+            .serial_number()
+            .filter(|s| !s.is_empty())
+            .map(str::to_string),
+    }
+}
+
+/// Error for a non-zero CTAP status byte: [`PFError::CtapError`] for codes
+/// the spec defines, a message with the raw byte for the rest.
+fn status_error(status: u8) -> PFError {
+    match Ctap2Error::from_u8(status) {
+        Some(code) => PFError::CtapError(code),
+        None => PFError::Device(format!(
+            "FIDO Operation Failed with Status: 0x{:02X}",
+            status
+        )),
+    }
+}
+
+/// Exchanges and failures since [`take_tally`] last ran, across all handles.
+static TALLY: Mutex<Option<TransportTally>> = Mutex::new(None);
+
+/// Tally category of a failed exchange, derived from the messages this module
+/// produces.
+fn error_category(error: &PFError) -> String {
+    if let Some(code) = error.ctap() {
+        return format!("ctap.0x{:02X}", code as u8);
+    }
+    let message = error.to_string();
+    let code = |marker: &str| {
+        message
+            .split_once(marker)
+            .map(|(_, rest)| rest.chars().take(4).collect::<String>())
+    };
+    if message.contains("Timeout") {
+        "timeout".into()
+    } else if message.contains("Sequence mismatch") {
+        "sequence".into()
+    } else if let Some(code) = code("CTAP Error: ") {
+        format!("ctaphid.{}", code)
+    } else if let Some(code) = code("Status: ") {
+        format!("ctap.{}", code)
+    } else {
+        "transport".into()
+    }
+}
+
+/// Count one exchange and, if it failed, its error category.
+fn tally<T>(result: Result<T, PFError>) -> Result<T, PFError> {
+    let mut guard = TALLY.lock().unwrap_or_else(|e| e.into_inner());
+    let tally = guard.get_or_insert_with(TransportTally::default);
+    tally.exchanges += 1;
+    if let Err(e) = &result {
+        *tally.errors.entry(error_category(e)).or_default() += 1;
+    }
+    result
+}
+
+/// Take the exchanges counted since the last call, resetting the tally.
+pub fn take_tally() -> TransportTally {
+    TALLY
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .take()
+        .unwrap_or_default()
+}
+
+/// Last KEEPALIVE status of the exchange waiting on the key, if any.
+static KEEPALIVE: Mutex<Option<KeepaliveStatus>> = Mutex::new(None);
+
+/// What the key said in its last KEEPALIVE, while a response is awaited.
+/// `Some(UpNeeded)` means it is waiting for a touch.
+pub fn keepalive_status() -> Option<KeepaliveStatus> {
+    *KEEPALIVE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn set_keepalive(status: Option<KeepaliveStatus>) {
+    let mut current = KEEPALIVE.lock().unwrap_or_else(|e| e.into_inner());
+    if *current != status {
+        if status == Some(KeepaliveStatus::UpNeeded) {
+            log::info!("Key is waiting for a touch");
+        }
+        *current = status;
+    }
+}
+
+/// Clears the KEEPALIVE status when a wait for a response ends, however it
+/// ends.
+struct AwaitingResponse;
 
-/// Timeout in milliseconds for reading a single HID response packet (excluding keepalives).
-const HID_RESP_READ_TIMEOUT_MS: i32 = 2000;
+impl Drop for AwaitingResponse {
+    fn drop(&mut self) {
+        set_keepalive(None);
+    }
+}
+
+/// Tunable HID timeouts (`[hid]` in `picoforge.toml`, with the connected
+/// key's overrides), as hidapi wants them.
+fn hid_tunables() -> tunables::HidTunables {
+    tunables::hid()
+}
+
+/// Retry policy of [`HidTransport::send_cbor`] for transient CTAPHID
+/// failures: the key answering `ERR_CHANNEL_BUSY` (0x06), or not answering at
+/// all.
+///
+/// A timeout is only retried for a request that changes nothing on the key
+/// ([`HidTransport::send_cbor_read`]), and only when the key sent nothing
+/// back, not even a KEEPALIVE; the channel is renegotiated with CTAPHID_INIT
+/// first. A write that timed out may still have been applied, so it is never
+/// sent twice. Defaults to the `[hid]` retry tunables.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransportOptions {
+    /// Extra tries after the first; `0` never retries.
+    pub retries: u32,
+    /// Wait before the first retry, doubled for each further one.
+    pub backoff: Duration,
+    /// Longest wait between two tries.
+    pub max_backoff: Duration,
+}
+
+impl TransportOptions {
+    /// Wait before retry number `retry`, counting from 0.
+    fn delay(&self, retry: u32) -> Duration {
+        let factor = 1u32.checked_shl(retry).unwrap_or(u32::MAX);
+        self.backoff.saturating_mul(factor).min(self.max_backoff)
+    }
+}
+
+impl From<&tunables::HidTunables> for TransportOptions {
+    fn from(hid: &tunables::HidTunables) -> Self {
+        Self {
+            retries: hid.retries,
+            backoff: Duration::from_millis(hid.retry_backoff_ms as u64),
+            max_backoff: Duration::from_millis(hid.retry_backoff_max_ms as u64),
+        }
+    }
+}
+
+impl Default for TransportOptions {
+    fn default() -> Self {
+        Self::from(&hid_tunables())
+    }
+}
+
+/// Message of a response timeout during which the key sent nothing.
+const NO_ANSWER: &str = "Timeout waiting for device response (no answer)";
+
+/// A failure [`TransportOptions`] retries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Transient {
+    /// CTAPHID `ERR_CHANNEL_BUSY`: another channel holds the key.
+    Busy,
+    /// No packet at all before the deadline.
+    Silent,
+}
+
+/// How `error` is retried, if at all; `read` when the request changes
+/// nothing on the key.
+fn transient(error: &PFError, read: bool) -> Option<Transient> {
+    match error {
+        PFError::Device(msg) if msg == NO_ANSWER => read.then_some(Transient::Silent),
+        _ if error_category(error) == "ctaphid.0x06" => Some(Transient::Busy),
+        _ => None,
+    }
+}
+
+/// How long operations that wait for a touch keep reading through KEEPALIVE.
+pub(crate) fn user_presence_timeout_ms() -> i32 {
+    tunables::user_presence_timeout_ms() as i32
+}
+
+/// Channel ID and capability flags from a CTAPHID_INIT response packet, if it
+/// answers the INIT carrying `nonce`.
+///
+/// Layout after the 7-byte header: nonce (8), CID (4), protocol version,
+/// device version major / minor / build, capabilities.
+fn parse_init_response(packet: &[u8; HID_REPORT_SIZE], nonce: &[u8; 8]) -> Option<(u32, u8)> {
+    if packet[0..4] != CTAPHID_CID_BROADCAST.to_be_bytes()
+        || packet[4] != CTAPHID_INIT
+        || packet[7..15] != nonce[..]
+    {
+        return None;
+    }
+    let cid = u32::from_be_bytes([packet[15], packet[16], packet[17], packet[18]]);
+    Some((cid, packet[23]))
+}
 
-/// Timeout in milliseconds for reading CTAPHID continuation packets.
-const HID_CONT_READ_TIMEOUT_MS: i32 = 500;
+/// Name of a USB interface class (`bInterfaceClass`).
+fn usb_class_name(class: u8) -> String {
+    match class {
+        0x01 => "Audio".to_string(),
+        0x02 => "CDC Control".to_string(),
+        0x03 => "HID".to_string(),
+        0x08 => "Mass Storage".to_string(),
+        0x0A => "CDC Data".to_string(),
+        0x0B => "Smart Card (CCID)".to_string(),
+        0xEF => "Miscellaneous".to_string(),
+        0xFE => "Application-specific".to_string(),
+        0xFF => "Vendor-specific".to_string(),
+        other => format!("Class 0x{:02X}", other),
+    }
+}
 
-/// Maximum total time in milliseconds allowed for a complete CBOR command/response exchange.
-const HID_TOTAL_TIMEOUT_MS: i32 = 5000;
+/// `bcdUSB` and the interfaces of the USB device behind a HID interface,
+/// read from the descriptors the OS cached at enumeration. With several
+/// devices of the same VID/PID attached, each is opened to compare serials;
+/// `None` if none matches or USB is not reachable (e.g. Bluetooth).
+fn usb_configuration(
+    vid: u16,
+    pid: u16,
+    serial: Option<&str>,
+) -> Option<(u16, Vec<UsbInterfaceDescriptor>)> {
+    let devices = rusb::devices()
+        .map_err(|e| log::debug!("Could not list USB devices: {}", e))
+        .ok()?;
+    let candidates: Vec<_> = devices
+        .iter()
+        .filter_map(|device| {
+            let descriptor = device.device_descriptor().ok()?;
+            (descriptor.vendor_id() == vid && descriptor.product_id() == pid)
+                .then_some((device, descriptor))
+        })
+        .collect();
+    let (device, descriptor) = if candidates.len() == 1 {
+        candidates.into_iter().next()?
+    } else {
+        candidates.into_iter().find(|(device, descriptor)| {
+            device
+                .open()
+                .and_then(|handle| handle.read_serial_number_string_ascii(descriptor))
+                .is_ok_and(|found| Some(found.as_str()) == serial)
+        })?
+    };
+
+    let version = descriptor.usb_version();
+    let bcd =
+        (version.major() as u16) << 8 | (version.minor() as u16) << 4 | version.sub_minor() as u16;
+    let config = device.active_config_descriptor().ok()?;
+    let interfaces = config
+        .interfaces()
+        .filter_map(|interface| interface.descriptors().next())
+        .map(|alt| UsbInterfaceDescriptor {
+            number: alt.interface_number(),
+            class: alt.class_code(),
+            subclass: alt.sub_class_code(),
+            protocol: alt.protocol_code(),
+            class_name: usb_class_name(alt.class_code()),
+            endpoints: alt
+                .endpoint_descriptors()
+                .map(|ep| UsbEndpointInfo {
+                    address: ep.address(),
+                    transfer_type: format!("{:?}", ep.transfer_type()),
+                    max_packet_size: ep.max_packet_size(),
+                    interval: ep.interval(),
+                })
+                .collect(),
+        })
+        .collect();
+    Some((bcd, interfaces))
+}
+
+/// Describe a HID interface by its top-level usage page / usage pair.
+fn hid_interface_class(usage_page: u16, usage: u16) -> String {
+    match (usage_page, usage) {
+        (HID_USAGE_PAGE_FIDO, _) => "FIDO (CTAPHID)".to_string(),
+        (0x0001, 0x0006) => "Keyboard".to_string(),
+        (0x0001, 0x0002) => "Mouse".to_string(),
+        (0x000C, _) => "Consumer Control".to_string(),
+        (0xFF00..=0xFFFF, _) => format!("Vendor-defined (0x{:04X})", usage_page),
+        _ => format!(
+            "HID (usage page 0x{:04X}, usage 0x{:04X})",
+            usage_page, usage
+        ),
+    }
+}
+
+/// Name of the CTAP2 command or status byte that starts a `CTAPHID_CBOR`
+/// payload, appended to wire trace lines so they read without the spec.
+fn trace_annotation(
+    cmd: u8,
+    payload: &[u8],
+    lookup: fn(u8) -> Option<&'static SpecEntry>,
+) -> String {
+    match payload.first() {
+        Some(&code) if cmd == CTAPHID_CBOR => lookup(code)
+            .map(|entry| format!(" ({})", entry.name))
+            .unwrap_or_default(),
+        _ => String::new(),
+    }
+}
+
+/// Print a CTAPHID request a dry run keeps from the key, with a CBOR body
+/// in diagnostic notation.
+fn print_unsent(cid: u32, cmd: u8, payload: &[u8]) {
+    let mut request = format!(
+        "CTAPHID > cid={:08X} cmd={:02X} {}{}",
+        cid,
+        cmd,
+        hex::encode_upper(payload),
+        trace_annotation(cmd, payload, reference::command)
+    );
+    if cmd == CTAPHID_CBOR
+        && let Some(body) = payload.get(1..).filter(|body| !body.is_empty())
+        && let Ok(value) = serde_cbor_2::from_slice(body)
+    {
+        request.push_str(&format!("\n  {}", reference::cbor_diagnostic(&value)));
+    }
+    logging::print_unsent(&request);
+}
 
 /// USB HID transport for CTAP2/FIDO2 communication.
 ///
@@ -150,24 +645,44 @@ const HID_TOTAL_TIMEOUT_MS: i32 = 5000;
 /// handling, and all higher-level CTAP2 operations (PIN, credential management,
 /// vendor commands).
 ///
-/// Created via [`HidTransport::open`], which scans for a device with the FIDO
+/// Created via [`HidTransport::connect`], which scans for a device with the FIDO
 /// HID Usage Page (0xF1D0) and performs the INIT handshake to obtain a Channel ID.
+/// Operations use the shared connection from [`HidTransport::open`] instead.
 #[derive(Debug)]
 pub struct HidTransport {
     device: hidapi::HidDevice,
-    cid: u32,
+    /// Channel ID; replaced when a retry renegotiates the channel.
+    cid: AtomicU32,
+    /// Set when an exchange failed below the CTAP level, so the channel may
+    /// hold a stale response or be gone.
+    faulted: AtomicBool,
+    /// Capability flags from the CTAPHID_INIT response.
+    capabilities: u8,
+    /// Retry policy of [`send_cbor`](Self::send_cbor).
+    options: TransportOptions,
     pub vid: u16,
     pub pid: u16,
     pub product_name: String,
+    /// hidapi path of the opened interface.
+    pub path: String,
 }
 
 impl HidTransport {
-    /// Open the first available FIDO HID device and negotiate a Channel ID.
+    /// The shared connection to the active FIDO key, held until the returned
+    /// guard is dropped.
+    ///
+    /// Reuses the Channel ID of an earlier call while the key still answers;
+    /// otherwise [`connect`](Self::connect)s. See [`DeviceManager`].
+    pub fn open() -> Result<SharedTransport, PFError> {
+        DeviceManager::shared().transport()
+    }
+
+    /// Open the active FIDO HID device and negotiate a new Channel ID.
     ///
     /// Scans for a device with HID Usage Page `0xF1D0`, opens it, and performs
     /// the CTAPHID_INIT handshake. Returns an error if no device is found or
     /// the INIT handshake times out.
-    pub fn open() -> Result<Self, PFError> {
+    pub fn connect() -> Result<Self, PFError> {
         log::info!("Attempting to open HID transport for FIDO device...");
         let api = hidapi::HidApi::new().map_err(|e| {
             log::error!("Failed to initialize HidApi: {}", e);
@@ -175,14 +690,15 @@ impl HidTransport {
         })?;
 
         // Find device with FIDO Usage Page (0xF1D0)
-        let info = api
-            .device_list()
-            .find(|d| d.usage_page() == HID_USAGE_PAGE_FIDO)
-            .ok_or_else(|| {
-                log::warn!("No FIDO device found with Usage Page 0xF1D0.");
-                PFError::NoDevice
-            })?;
+        let info = active_device(&api).ok_or_else(|| {
+            log::warn!("No FIDO device found with Usage Page 0xF1D0.");
+            PFError::NoDevice
+        })?;
+        Self::open_info(&api, info)
+    }
 
+    /// Open one enumerated FIDO interface and negotiate a Channel ID.
+    fn open_info(api: &hidapi::HidApi, info: &hidapi::DeviceInfo) -> Result<Self, PFError> {
         log::debug!(
             "Found FIDO device: VendorID=0x{:04X}, ProductID=0x{:04X}",
             info.vendor_id(),
@@ -196,24 +712,47 @@ impl HidTransport {
             .unwrap_or("Unknown FIDO Device")
             .to_string();
 
-        let device = info.open_device(&api).map_err(|e| {
+        let device = info.open_device(api).map_err(|e| {
             log::error!("Failed to open HID device: {}", e);
             PFError::Device(format!("Failed to open HID device: {}", e))
         })?;
 
         // Negotiate Channel ID (CID)
-        let cid = Self::init_channel(&device).map_err(|e| {
-            log::error!("Failed to negotiate Channel ID: {}", e);
-            PFError::Device(format!("Failed to negotiate Channel ID: {}", e))
-        })?;
+        let attempts = hid_tunables().init_attempts.max(1);
+        let mut attempt = 1;
+        let (cid, capabilities) = loop {
+            match Self::init_channel(&device) {
+                Ok(channel) => break channel,
+                Err(e) if attempt < attempts => {
+                    log::warn!(
+                        "Channel negotiation attempt {}/{} failed: {}",
+                        attempt,
+                        attempts,
+                        e
+                    );
+                    attempt += 1;
+                }
+                Err(e) => {
+                    log::error!("Failed to negotiate Channel ID: {}", e);
+                    return Err(PFError::Device(format!(
+                        "Failed to negotiate Channel ID: {}",
+                        e
+                    )));
+                }
+            }
+        };
 
         log::info!("HID Transport established successfully. CID: 0x{:08X}", cid);
         Ok(Self {
             device,
-            cid,
+            cid: AtomicU32::new(cid),
+            faulted: AtomicBool::new(false),
+            capabilities,
+            options: TransportOptions::default(),
             vid,
             pid,
             product_name,
+            path: info.path().to_string_lossy().into_owned(),
         })
     }
 
@@ -226,9 +765,7 @@ impl HidTransport {
     /// A change in the returned value signals a plug / unplug / swap.
     pub fn fingerprint() -> Option<String> {
         let api = hidapi::HidApi::new().ok()?;
-        let info = api
-            .device_list()
-            .find(|d| d.usage_page() == HID_USAGE_PAGE_FIDO)?;
+        let info = active_device(&api)?;
         Some(format!(
             "{:04x}:{:04x}:{}",
             info.vendor_id(),
@@ -237,12 +774,219 @@ impl HidTransport {
         ))
     }
 
+    /// Startup probe: initialise hidapi and try to open every FIDO interface.
+    ///
+    /// Opening without `CTAPHID_INIT` is harmless, and it is the step that
+    /// fails when the OS denies access (missing udev rule, Windows without
+    /// elevation). Fails only when hidapi itself cannot be initialised.
+    pub fn probe() -> Result<HidProbe, PFError> {
+        let api = hidapi::HidApi::new()
+            .map_err(|e| PFError::Device(format!("Failed to initialize HidApi: {}", e)))?;
+
+        let mut probe = HidProbe::default();
+        for info in fido_devices(&api) {
+            probe.fido_interfaces += 1;
+            if let Err(e) = info.open_device(&api) {
+                log::warn!(
+                    "FIDO interface {:?} is listed but cannot be opened: {}",
+                    info.path(),
+                    e
+                );
+                probe.inaccessible.push(format!(
+                    "{:04X}:{:04X} ({}): {}",
+                    info.vendor_id(),
+                    info.product_id(),
+                    info.path().to_string_lossy(),
+                    e
+                ));
+            }
+        }
+
+        Ok(probe)
+    }
+
+    /// Every attached FIDO interface, from enumeration only.
+    pub fn enumerate() -> Vec<DeviceDescriptor> {
+        let Ok(api) = hidapi::HidApi::new() else {
+            return Vec::new();
+        };
+        fido_devices(&api).into_iter().map(descriptor).collect()
+    }
+
+    /// Make the FIDO interface at `path` the one [`open`](Self::open) uses
+    /// from now on. Fails when it is no longer attached.
+    pub fn select_path(path: &str) -> Result<DeviceDescriptor, PFError> {
+        let device = Self::enumerate()
+            .into_iter()
+            .find(|d| d.path == path)
+            .ok_or(PFError::NoDevice)?;
+        set_active(&device);
+        Ok(device)
+    }
+
+    /// Path of the interface [`open`](HidTransport::open) currently uses.
+    pub fn active_path() -> Option<String> {
+        let api = hidapi::HidApi::new().ok()?;
+        active_device(&api).map(|info| info.path().to_string_lossy().into_owned())
+    }
+
+    /// Ask every attached FIDO key for a touch and make the first one touched
+    /// the active interface (CTAP 2.1 §6.9).
+    ///
+    /// Each key is polled on its own thread; once one answers, the others get
+    /// CTAPHID_CANCEL. A single attached key is chosen without a touch. Keys
+    /// that reject authenticatorSelection (CTAP 2.0) drop out of the race; it
+    /// fails when none is touched within the user-presence timeout.
+    pub fn select_by_touch() -> Result<DeviceDescriptor, PFError> {
+        let api = hidapi::HidApi::new()
+            .map_err(|e| PFError::Device(format!("Failed to initialize HidApi: {}", e)))?;
+        let infos = fido_devices(&api);
+        let chosen = match infos.as_slice() {
+            [] => return Err(PFError::NoDevice),
+            [only] => descriptor(only),
+            _ => {
+                let transports: Vec<_> = infos
+                    .iter()
+                    .filter_map(|info| {
+                        Self::open_info(&api, info)
+                            .inspect_err(|e| {
+                                log::warn!("Skipping {:?} for selection: {}", info.path(), e)
+                            })
+                            .ok()
+                            .map(|transport| (descriptor(info), transport))
+                    })
+                    .collect();
+                log::info!(
+                    "Asking {} FIDO keys for a touch (authenticatorSelection)",
+                    transports.len()
+                );
+                let done = AtomicBool::new(false);
+                let winner = Mutex::new(None);
+                std::thread::scope(|scope| {
+                    for (candidate, transport) in transports {
+                        let (done, winner) = (&done, &winner);
+                        scope.spawn(move || match transport.poll_selection(done) {
+                            Ok(true) => {
+                                done.store(true, Ordering::SeqCst);
+                                let mut winner = winner.lock().unwrap_or_else(|e| e.into_inner());
+                                winner.get_or_insert(candidate);
+                            }
+                            Ok(false) => {}
+                            Err(e) => log::info!("{} left the selection: {}", candidate.path, e),
+                        });
+                    }
+                });
+                winner
+                    .into_inner()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .ok_or_else(|| {
+                        PFError::Device(
+                            "No key was touched (CTAP2_ERR_USER_ACTION_TIMEOUT 0x2F)".into(),
+                        )
+                    })?
+            }
+        };
+        set_active(&chosen);
+        Ok(chosen)
+    }
+
+    /// Send authenticatorSelection and wait for its answer in short reads,
+    /// giving up with CTAPHID_CANCEL once `stop` is set. `Ok(true)` when this
+    /// key was touched, `Ok(false)` when it was cancelled.
+    fn poll_selection(&self, stop: &AtomicBool) -> Result<bool, PFError> {
+        self.write_cbor_request(CTAPHID_CBOR, &[0x0B])?;
+        let deadline =
+            std::time::Instant::now() + Duration::from_millis(user_presence_timeout_ms() as u64);
+        let mut packet = [0u8; HID_REPORT_SIZE];
+        loop {
+            if stop.load(Ordering::SeqCst) {
+                self.write_cbor_request(CTAPHID_CANCEL, &[])?;
+                return Ok(false);
+            }
+            if std::time::Instant::now() > deadline {
+                self.write_cbor_request(CTAPHID_CANCEL, &[])?;
+                return Err(PFError::CtapError(Ctap2Error::UserActionTimeout));
+            }
+            let read = self
+                .device
+                .read_timeout(&mut packet[..], SELECTION_POLL_MS)
+                .map_err(|e| PFError::Io(format!("Failed to read HID packet: {}", e)))?;
+            if read == 0 || packet[0..4] != self.cid().to_be_bytes() {
+                continue;
+            }
+            match packet[4] {
+                CTAPHID_KEEPALIVE => continue,
+                CTAPHID_ERROR => {
+                    return Err(PFError::Device(format!(
+                        "Device returned CTAP Error: 0x{:02X}",
+                        packet[5]
+                    )));
+                }
+                CTAPHID_CBOR if packet[7] == 0x00 => return Ok(true),
+                CTAPHID_CBOR => return Err(status_error(packet[7])),
+                other => {
+                    return Err(PFError::Device(format!(
+                        "Unexpected command response: 0x{:02X} (Expected 0x{:02X})",
+                        other, CTAPHID_CBOR
+                    )));
+                }
+            }
+        }
+    }
+
+    /// Read the USB descriptor strings and interfaces of the attached key.
+    ///
+    /// Like [`fingerprint`](HidTransport::fingerprint) this only enumerates, so
+    /// it never contends with an open handle. Every HID interface sharing the
+    /// FIDO interface's VID/PID/serial is reported, the one PicoForge talks
+    /// to marked `in_use`. hidapi exposes neither `bcdUSB` nor endpoint
+    /// descriptors; those, and the non-HID interfaces, come from libusb.
+    pub fn usb_descriptors() -> Option<UsbDescriptorInfo> {
+        let api = hidapi::HidApi::new().ok()?;
+        let fido = active_device(&api)?;
+
+        let interfaces = api
+            .device_list()
+            .filter(|d| {
+                d.vendor_id() == fido.vendor_id()
+                    && d.product_id() == fido.product_id()
+                    && d.serial_number() == fido.serial_number()
+            })
+            .map(|d| UsbInterfaceInfo {
+                in_use: d.path() == fido.path() && d.usage_page() == fido.usage_page(),
+                interface_number: d.interface_number(),
+                usage_page: d.usage_page(),
+                usage: d.usage(),
+                class: hid_interface_class(d.usage_page(), d.usage()),
+                path: d.path().to_string_lossy().into_owned(),
+            })
+            .collect();
+        let (usb_version, usb_interfaces) =
+            usb_configuration(fido.vendor_id(), fido.product_id(), fido.serial_number())
+                .map_or((None, Vec::new()), |(bcd, interfaces)| {
+                    (Some(bcd), interfaces)
+                });
+
+        Some(UsbDescriptorInfo {
+            vid: fido.vendor_id(),
+            pid: fido.product_id(),
+            release_number: fido.release_number(),
+            manufacturer: fido.manufacturer_string().map(str::to_string),
+            product: fido.product_string().map(str::to_string),
+            serial: fido.serial_number().map(str::to_string),
+            bus_type: format!("{:?}", fido.bus_type()),
+            interfaces,
+            usb_version,
+            usb_interfaces,
+        })
+    }
+
     /// Negotiate a CTAPHID Channel ID via CTAPHID_INIT.
     ///
     /// Sends an INIT command to the broadcast CID (`0xFFFFFFFF`) with a random
     /// 8-byte nonce, then reads the response to extract the allocated CID.
     /// Drains any stale packets before the handshake to avoid confusion.
-    fn init_channel(device: &hidapi::HidDevice) -> Result<u32, PFError> {
+    fn init_channel(device: &hidapi::HidDevice) -> Result<(u32, u8), PFError> {
         log::debug!("Initializing CTAPHID channel...");
 
         let mut stale_packet_buffer = [0u8; HID_REPORT_SIZE];
@@ -258,6 +1002,11 @@ impl HidTransport {
 
         let mut nonce = [0u8; 8];
         rand::rng().fill(&mut nonce);
+        if logging::dry_run() {
+            // Go on with the broadcast channel so the request itself shows.
+            print_unsent(CTAPHID_CID_BROADCAST, CTAPHID_INIT, &nonce);
+            return Ok((CTAPHID_CID_BROADCAST, 0));
+        }
 
         // Construct Init Packet: [CID(4) | CMD(1) | LEN(2) | NONCE(8)]
         let mut report = [0u8; HID_REPORT_SIZE + 1]; // +1 for Report ID (always 0)
@@ -278,23 +1027,20 @@ impl HidTransport {
         while start.elapsed() < Duration::from_secs(1) {
             let mut init_buf = [0u8; HID_REPORT_SIZE];
             if device
-                .read_timeout(&mut init_buf[..], HID_INIT_READ_TIMEOUT_MS)
+                .read_timeout(
+                    &mut init_buf[..],
+                    hid_tunables().init_read_timeout_ms as i32,
+                )
                 .is_ok()
             {
                 // Check if response matches our broadcast and nonce
-                if init_buf[0..4] == CTAPHID_CID_BROADCAST.to_be_bytes()
-                    && init_buf[4] == CTAPHID_INIT
-                    && init_buf[7..15] == nonce
-                {
-                    // New CID is at bytes 16..20
-                    let new_cid = u32::from_be_bytes([
-                        init_buf[15],
-                        init_buf[16],
-                        init_buf[17],
-                        init_buf[18],
-                    ]);
-                    log::debug!("Channel negotiation successful. New CID: 0x{:08X}", new_cid);
-                    return Ok(new_cid);
+                if let Some((new_cid, capabilities)) = parse_init_response(&init_buf, &nonce) {
+                    log::debug!(
+                        "Channel negotiation successful. New CID: 0x{:08X}, capabilities: 0x{:02X}",
+                        new_cid,
+                        capabilities
+                    );
+                    return Ok((new_cid, capabilities));
                 } else {
                     log::trace!(
                         "Received ignoreable HID packet during CID negotiation: {:02X?}",
@@ -311,9 +1057,64 @@ impl HidTransport {
 
     /// Send a CTAP2 CBOR command and wait for the response using the default timeout.
     ///
-    /// Convenience wrapper around [`send_cbor_with_timeout`](HidTransport::send_cbor_with_timeout).
+    /// Wraps [`send_cbor_with_timeout`](HidTransport::send_cbor_with_timeout)
+    /// and retries `ERR_CHANNEL_BUSY` with exponential backoff, as set by
+    /// [`TransportOptions`].
     pub fn send_cbor(&self, cmd: u8, payload: &[u8]) -> Result<Vec<u8>, PFError> {
-        self.send_cbor_with_timeout(cmd, payload, HID_TOTAL_TIMEOUT_MS)
+        self.send_cbor_retrying(cmd, payload, false)
+    }
+
+    /// [`send_cbor`](HidTransport::send_cbor) for a request that changes
+    /// nothing on the key (GetInfo, getPinRetries, starting an enumeration,
+    /// vendor reads), which is also sent again when the key never answered.
+    pub fn send_cbor_read(&self, cmd: u8, payload: &[u8]) -> Result<Vec<u8>, PFError> {
+        self.send_cbor_retrying(cmd, payload, true)
+    }
+
+    fn send_cbor_retrying(&self, cmd: u8, payload: &[u8], read: bool) -> Result<Vec<u8>, PFError> {
+        let timeout_ms = hid_tunables().total_timeout_ms as i32;
+        let mut retry = 0;
+        loop {
+            let result = self.send_cbor_with_timeout(cmd, payload, timeout_ms);
+            let failure = match &result {
+                Err(e) if retry < self.options.retries => transient(e, read).map(|f| (f, e)),
+                _ => None,
+            };
+            let Some((failure, error)) = failure else {
+                return result;
+            };
+            let delay = self.options.delay(retry);
+            retry += 1;
+            log::warn!(
+                "{} (try {} of {}); retrying in {} ms",
+                error,
+                retry,
+                self.options.retries + 1,
+                delay.as_millis()
+            );
+            std::thread::sleep(delay);
+            if failure == Transient::Silent
+                && let Err(e) = self.reinit()
+            {
+                log::warn!("Could not renegotiate the channel: {}", e);
+                return result;
+            }
+        }
+    }
+
+    /// The current Channel ID.
+    fn cid(&self) -> u32 {
+        self.cid.load(Ordering::Relaxed)
+    }
+
+    /// Negotiate a new Channel ID on the open device, dropping whatever the
+    /// old channel left unread.
+    fn reinit(&self) -> Result<(), PFError> {
+        let (cid, _) = Self::init_channel(&self.device)?;
+        log::info!("Renegotiated CTAPHID channel. CID: 0x{:08X}", cid);
+        self.cid.store(cid, Ordering::Relaxed);
+        self.faulted.store(false, Ordering::Relaxed);
+        Ok(())
     }
 
     /// Send a CTAP2 CBOR command and wait for the response with a custom timeout.
@@ -327,8 +1128,10 @@ impl HidTransport {
         payload: &[u8],
         timeout_ms: i32,
     ) -> Result<Vec<u8>, PFError> {
-        self.write_cbor_request(cmd, payload)?;
-        self.read_cbor_response(cmd, timeout_ms)
+        self.record_exchange(
+            self.write_cbor_request(cmd, payload)
+                .and_then(|()| self.read_cbor_response(cmd, timeout_ms)),
+        )
     }
 
     /// Send a CTAP2 CBOR command and return the raw HID response without status-byte parsing.
@@ -336,22 +1139,129 @@ impl HidTransport {
     /// Unlike [`send_cbor`](HidTransport::send_cbor), this does not check the CTAP status byte
     /// or strip it from the response. Useful for vendor commands that return non-standard payloads.
     pub fn send_raw(&self, cmd: u8, payload: &[u8]) -> Result<Vec<u8>, PFError> {
-        self.write_cbor_request(cmd, payload)?;
-        self.read_hid_response(cmd, HID_TOTAL_TIMEOUT_MS)
+        self.record_exchange(
+            self.write_cbor_request(cmd, payload)
+                .and_then(|()| self.read_hid_response(cmd, hid_tunables().total_timeout_ms as i32)),
+        )
     }
 
     /// Send the CTAP authenticatorReset command (0x07).
     ///
     /// Resets the authenticator to its factory state: all credentials, PINs,
-    /// and configuration are erased. Uses a 30-second timeout to allow for
-    /// any required user interaction (e.g., touch confirmation).
+    /// and configuration are erased. Waits the user-presence timeout
+    /// (30 seconds unless tuned) for any required touch confirmation.
     pub fn reset(&self) -> Result<(), PFError> {
         log::info!("Sending CTAP authenticatorReset (0x07)...");
-        self.write_cbor_request(CTAPHID_CBOR, &[0x07])?;
-        self.read_cbor_response(CTAPHID_CBOR, 30_000)?;
+        self.record_exchange(
+            self.write_cbor_request(CTAPHID_CBOR, &[0x07])
+                .and_then(|()| self.read_cbor_response(CTAPHID_CBOR, user_presence_timeout_ms())),
+        )?;
+        Ok(())
+    }
+
+    /// Send the CTAP authenticatorSelection command (0x0B).
+    ///
+    /// Blinks the authenticator and blocks until the user touches it. Uses the
+    /// same user-presence window as [`reset`](HidTransport::reset); a missed touch
+    /// surfaces as `CTAP2_ERR_USER_ACTION_TIMEOUT` (0x2F).
+    pub fn selection(&self) -> Result<(), PFError> {
+        log::info!("Sending CTAP authenticatorSelection (0x0B)...");
+        self.record_exchange(
+            self.write_cbor_request(CTAPHID_CBOR, &[0x0B])
+                .and_then(|()| self.read_cbor_response(CTAPHID_CBOR, user_presence_timeout_ms())),
+        )?;
         Ok(())
     }
 
+    /// Send CTAPHID_PING with `len` random bytes, check that they come back
+    /// unchanged and return the round-trip time.
+    ///
+    /// Not counted in the transport tally. Success clears the fault left by
+    /// an earlier failed exchange, as the channel is in sync again.
+    pub fn ping(&self, len: usize) -> Result<Duration, PFError> {
+        if len > CTAPHID_MAX_PAYLOAD {
+            return Err(PFError::Device(format!(
+                "CTAPHID_PING payload of {} bytes exceeds the {}-byte message limit",
+                len, CTAPHID_MAX_PAYLOAD
+            )));
+        }
+        let mut payload = vec![0u8; len];
+        rand::rng().fill(&mut payload[..]);
+
+        let started = Instant::now();
+        self.write_cbor_request(CTAPHID_PING, &payload)?;
+        let echo =
+            self.read_hid_response(CTAPHID_PING, hid_tunables().response_read_timeout_ms as i32)?;
+        let elapsed = started.elapsed();
+        if echo != payload {
+            return Err(PFError::Device("CTAPHID_PING echo did not match".into()));
+        }
+        self.faulted.store(false, Ordering::Relaxed);
+        Ok(elapsed)
+    }
+
+    /// Measure the link with CTAPHID_PING: round-trip latency of single-report
+    /// pings, then report and byte rates of multi-report ones. Helps tell a
+    /// flaky cable or hub from a firmware problem.
+    pub fn self_test(&self) -> Result<PingSelfTest, PFError> {
+        log::info!("Running CTAPHID_PING self-test on {}", self.product_name);
+        let latencies = (0..SELF_TEST_PINGS)
+            .map(|_| self.ping(SELF_TEST_LATENCY_LEN))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let started = Instant::now();
+        for _ in 0..SELF_TEST_PINGS {
+            self.ping(SELF_TEST_THROUGHPUT_LEN)?;
+        }
+        let throughput_elapsed = started.elapsed();
+
+        // Each ping travels both ways.
+        let round_trips = 2 * SELF_TEST_PINGS as u64;
+        let report = PingSelfTest {
+            latencies,
+            throughput_payload: SELF_TEST_THROUGHPUT_LEN,
+            throughput_bytes: round_trips * SELF_TEST_THROUGHPUT_LEN as u64,
+            throughput_packets: round_trips * ctaphid_packets(SELF_TEST_THROUGHPUT_LEN) as u64,
+            throughput_elapsed,
+        };
+        log::info!("CTAPHID_PING self-test: {}", report.summary());
+        Ok(report)
+    }
+
+    /// Send CTAPHID_WINK so the key identifies itself (pico keys blink
+    /// their LED). Fails without sending anything when the key did not
+    /// advertise WINK in its INIT response.
+    pub fn wink(&self) -> Result<(), PFError> {
+        if self.capabilities & CAPABILITY_WINK == 0 {
+            return Err(PFError::Device(
+                "This key does not support CTAPHID_WINK".into(),
+            ));
+        }
+        log::info!("Sending CTAPHID_WINK to {}", self.product_name);
+        let result = self.write_cbor_request(CTAPHID_WINK, &[]).and_then(|()| {
+            self.read_hid_response(CTAPHID_WINK, hid_tunables().response_read_timeout_ms as i32)
+        });
+        self.record_exchange(result).map(|_| ())
+    }
+
+    /// Whether an exchange failed below the CTAP level since the last
+    /// successful [`ping`](Self::ping).
+    pub fn faulted(&self) -> bool {
+        self.faulted.load(Ordering::Relaxed)
+    }
+
+    /// [`tally`] one exchange, marking the channel faulted unless it failed
+    /// with a CTAP status (the key answered properly).
+    fn record_exchange<T>(&self, result: Result<T, PFError>) -> Result<T, PFError> {
+        if let Err(e) = &result
+            && !error_category(e).starts_with("ctap.")
+            && !matches!(e, PFError::Device(msg) if msg == logging::NOT_SENT)
+        {
+            self.faulted.store(true, Ordering::Relaxed);
+        }
+        tally(result)
+    }
+
     /// Fragment and write a CTAPHID request to the device.
     ///
     /// Encodes the command byte and payload into a CTAPHID init packet followed
@@ -363,6 +1273,20 @@ impl HidTransport {
             cmd,
             payload.len()
         );
+        if logging::dry_run() {
+            print_unsent(self.cid(), cmd, payload);
+            return Err(PFError::Device(logging::NOT_SENT.into()));
+        }
+        if logging::trace_wire() {
+            log::info!(
+                target: WIRE_TARGET,
+                "CTAPHID > cid={:08X} cmd={:02X} {}{}",
+                self.cid(),
+                cmd,
+                logging::wire_hex(payload),
+                trace_annotation(cmd, payload, reference::command)
+            );
+        }
 
         let total_len = payload.len();
         let mut sent = 0;
@@ -370,7 +1294,7 @@ impl HidTransport {
 
         // 1. Init Packet
         let mut report = [0u8; HID_REPORT_SIZE + 1];
-        report[1..5].copy_from_slice(&self.cid.to_be_bytes());
+        report[1..5].copy_from_slice(&self.cid().to_be_bytes());
         report[5] = cmd;
         report[6] = (total_len >> 8) as u8;
         report[7] = (total_len & 0xFF) as u8;
@@ -393,7 +1317,7 @@ impl HidTransport {
         // 2. Continuation Packets
         while sent < total_len {
             let mut report = [0u8; HID_REPORT_SIZE + 1];
-            report[1..5].copy_from_slice(&self.cid.to_be_bytes());
+            report[1..5].copy_from_slice(&self.cid().to_be_bytes());
             report[5] = 0x7F & sequence; // SEQ
             sequence += 1;
 
@@ -442,10 +1366,7 @@ impl HidTransport {
                 "FIDO Operation returned failure status: 0x{:02X}",
                 ctap_status_byte
             );
-            return Err(PFError::Device(format!(
-                "FIDO Operation Failed with Status: 0x{:02X}",
-                ctap_status_byte
-            )));
+            return Err(status_error(ctap_status_byte));
         }
 
         log::debug!(
@@ -477,18 +1398,24 @@ impl HidTransport {
         let timeout_duration = std::time::Duration::from_millis(timeout_ms as u64);
 
         // 1. Read First Packet (Keepalive Loop)
+        let awaiting = AwaitingResponse;
+        let mut kept_alive = false;
         loop {
             if deadline_start.elapsed() > timeout_duration {
+                if !kept_alive {
+                    log::error!("{}", NO_ANSWER);
+                    return Err(PFError::Device(NO_ANSWER.into()));
+                }
                 log::error!("Timeout waiting for device response (Keepalive limit exceeded)");
                 return Err(PFError::Device(
                     "Timeout waiting for device response (Keepalive limit exceeded)".into(),
                 ));
             }
 
-            if let Err(e) = self
-                .device
-                .read_timeout(&mut packet_buf[..], HID_RESP_READ_TIMEOUT_MS)
-            {
+            if let Err(e) = self.device.read_timeout(
+                &mut packet_buf[..],
+                hid_tunables().response_read_timeout_ms as i32,
+            ) {
                 log::error!("Timeout reading response packet: {}", e);
                 return Err(PFError::Io(format!(
                     "Timeout reading response packet: {}",
@@ -498,7 +1425,7 @@ impl HidTransport {
 
             // Check CID mismatch
             if u32::from_be_bytes([packet_buf[0], packet_buf[1], packet_buf[2], packet_buf[3]])
-                != self.cid
+                != self.cid()
             {
                 log::warn!("Received packet from different CID, ignoring...");
                 continue;
@@ -511,12 +1438,15 @@ impl HidTransport {
                     "Device sent KEEPALIVE (Status: 0x{:02X}), waiting...",
                     keepalive_status
                 );
+                set_keepalive(Some(keepalive_status.into()));
+                kept_alive = true;
                 continue;
             }
 
             // If we are here, it's a real response
             break;
         }
+        drop(awaiting);
 
         if packet_buf[4] == CTAPHID_ERROR {
             log::error!("Device returned CTAP Error code: 0x{:02X}", packet_buf[5]);
@@ -548,10 +1478,10 @@ impl HidTransport {
 
         // 2. Read Continuation Packets
         while read_len < expected_len {
-            if let Err(e) = self
-                .device
-                .read_timeout(&mut packet_buf[..], HID_CONT_READ_TIMEOUT_MS)
-            {
+            if let Err(e) = self.device.read_timeout(
+                &mut packet_buf[..],
+                hid_tunables().continuation_read_timeout_ms as i32,
+            ) {
                 log::error!("Timeout reading continuation packet: {}", e);
                 return Err(PFError::Io(format!(
                     "Timeout reading continuation packet: {}",
@@ -560,7 +1490,7 @@ impl HidTransport {
             }
 
             if u32::from_be_bytes([packet_buf[0], packet_buf[1], packet_buf[2], packet_buf[3]])
-                != self.cid
+                != self.cid()
             {
                 continue; // Ignore packets from other channels
             }
@@ -581,6 +1511,214 @@ impl HidTransport {
             read_len += in_pkt;
         }
 
+        if logging::trace_wire() {
+            log::info!(
+                target: WIRE_TARGET,
+                "CTAPHID < cid={:08X} cmd={:02X} {}{}",
+                self.cid(),
+                cmd,
+                logging::wire_hex(&response_data),
+                trace_annotation(cmd, &response_data, reference::error)
+            );
+        }
+
         Ok(response_data)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_category_follows_failure_messages() {
+        let cases = [
+            (
+                PFError::Device("Timeout waiting for device response".into()),
+                "timeout",
+            ),
+            (PFError::Device("Sequence mismatch".into()), "sequence"),
+            (
+                PFError::Device("Device returned CTAP Error: 0x06".into()),
+                "ctaphid.0x06",
+            ),
+            (PFError::CtapError(Ctap2Error::PinInvalid), "ctap.0x31"),
+            (status_error(0x2E), "ctap.0x2E"),
+            (status_error(0x13), "ctap.0x13"),
+            (
+                PFError::Io("Failed to write initial HID packet: gone".into()),
+                "transport",
+            ),
+        ];
+        for (error, category) in cases {
+            assert_eq!(error_category(&error), category);
+        }
+    }
+
+    #[test]
+    fn test_retries_back_off_exponentially_up_to_the_cap() {
+        let options = TransportOptions {
+            retries: 5,
+            backoff: Duration::from_millis(50),
+            max_backoff: Duration::from_millis(300),
+        };
+        let delays: Vec<_> = (0..5)
+            .map(|retry| options.delay(retry).as_millis())
+            .collect();
+        assert_eq!(delays, [50, 100, 200, 300, 300]);
+        assert_eq!(options.delay(40), Duration::from_millis(300));
+        assert_eq!(
+            TransportOptions::from(&tunables::HidTunables::default()).retries,
+            2
+        );
+    }
+
+    #[test]
+    fn test_only_busy_and_silent_reads_are_retried() {
+        for read in [false, true] {
+            assert_eq!(
+                transient(
+                    &PFError::Device("Device returned CTAP Error: 0x06".into()),
+                    read
+                ),
+                Some(Transient::Busy)
+            );
+        }
+        assert_eq!(
+            transient(&PFError::Device(NO_ANSWER.into()), true),
+            Some(Transient::Silent)
+        );
+        assert_eq!(transient(&PFError::Device(NO_ANSWER.into()), false), None);
+        let not_retried = [
+            PFError::Device(
+                "Timeout waiting for device response (Keepalive limit exceeded)".into(),
+            ),
+            PFError::Device("Device returned CTAP Error: 0x01".into()),
+            PFError::CtapError(Ctap2Error::PinInvalid),
+            PFError::Io("Failed to write initial HID packet: gone".into()),
+        ];
+        for error in not_retried {
+            assert_eq!(transient(&error, true), None, "{}", error);
+        }
+    }
+
+    #[test]
+    fn test_fido_interfaces_skip_other_interfaces_of_the_key() {
+        let entry = |serial: &str, interface_number, usage_page, usage| HidEntry {
+            device: (0x2E8A, 0x10FE, serial.to_string()),
+            interface_number,
+            usage_page,
+            usage,
+        };
+        let entries = [
+            // Keyboard and vendor interfaces of one key, then its FIDO
+            // interface listed twice (once per top-level collection).
+            entry("A", 1, 0x0001, 0x0006),
+            entry("A", 2, 0xFF00, 0x0001),
+            entry("A", 0, HID_USAGE_PAGE_FIDO, HID_USAGE_CTAPHID),
+            entry("A", 0, HID_USAGE_PAGE_FIDO, HID_USAGE_CTAPHID),
+            // A FIDO-page entry without a usage next to the explicit one.
+            entry("A", 3, HID_USAGE_PAGE_FIDO, 0),
+            // A second key on a platform that reports no usage at all.
+            entry("B", 0, HID_USAGE_PAGE_FIDO, 0),
+            // FIDO page, wrong usage.
+            entry("C", 0, HID_USAGE_PAGE_FIDO, 0x02),
+        ];
+        assert_eq!(fido_interfaces(&entries), [2, 5]);
+
+        // Unknown interface numbers are never merged.
+        let unnumbered = [
+            entry("A", -1, HID_USAGE_PAGE_FIDO, HID_USAGE_CTAPHID),
+            entry("A", -1, HID_USAGE_PAGE_FIDO, HID_USAGE_CTAPHID),
+        ];
+        assert_eq!(fido_interfaces(&unnumbered), [0, 1]);
+    }
+
+    #[test]
+    fn test_keepalive_status_clears_when_the_wait_ends() {
+        let awaiting = AwaitingResponse;
+        set_keepalive(Some(0x02.into()));
+        assert_eq!(keepalive_status(), Some(KeepaliveStatus::UpNeeded));
+        set_keepalive(Some(0x01.into()));
+        assert_eq!(keepalive_status(), Some(KeepaliveStatus::Processing));
+        drop(awaiting);
+        assert_eq!(keepalive_status(), None);
+        assert_eq!(KeepaliveStatus::from(0x7F), KeepaliveStatus::Other(0x7F));
+    }
+
+    #[test]
+    fn test_ctaphid_packets_counts_init_and_continuations() {
+        assert_eq!(ctaphid_packets(0), 1);
+        assert_eq!(ctaphid_packets(57), 1);
+        assert_eq!(ctaphid_packets(58), 2);
+        assert_eq!(ctaphid_packets(57 + 59), 2);
+        assert_eq!(ctaphid_packets(SELF_TEST_THROUGHPUT_LEN), 18);
+        assert_eq!(ctaphid_packets(CTAPHID_MAX_PAYLOAD), 129);
+    }
+
+    #[test]
+    fn test_ping_self_test_rates_and_summary() {
+        let report = PingSelfTest {
+            latencies: [2, 4, 3].map(Duration::from_millis).to_vec(),
+            throughput_payload: 1024,
+            throughput_bytes: 16 * 1024,
+            throughput_packets: 16 * 18,
+            throughput_elapsed: Duration::from_millis(500),
+        };
+        assert_eq!(
+            report.latency_range(),
+            Some((
+                Duration::from_millis(2),
+                Duration::from_millis(3),
+                Duration::from_millis(4)
+            ))
+        );
+        assert_eq!(report.packets_per_second(), 576.0);
+        assert_eq!(report.bytes_per_second(), 32768.0);
+        assert_eq!(
+            report.summary(),
+            "Round trip 2.0 / 3.0 / 4.0 ms (min / avg / max over 3 pings). \
+             576 packets/s, 32.0 KiB/s with 1024-byte payloads."
+        );
+    }
+
+    #[test]
+    fn test_init_response_carries_cid_and_capabilities() {
+        let nonce = [1, 2, 3, 4, 5, 6, 7, 8];
+        let mut packet = [0u8; HID_REPORT_SIZE];
+        packet[0..4].copy_from_slice(&CTAPHID_CID_BROADCAST.to_be_bytes());
+        packet[4] = CTAPHID_INIT;
+        packet[6] = 17;
+        packet[7..15].copy_from_slice(&nonce);
+        packet[15..19].copy_from_slice(&0x0102_0304u32.to_be_bytes());
+        packet[19] = 2; // CTAPHID protocol version
+        packet[23] = CAPABILITY_WINK | 0x04;
+
+        let (cid, capabilities) = parse_init_response(&packet, &nonce).unwrap();
+        assert_eq!(cid, 0x0102_0304);
+        assert_ne!(capabilities & CAPABILITY_WINK, 0);
+
+        // An answer to someone else's INIT.
+        assert_eq!(parse_init_response(&packet, &[0; 8]), None);
+    }
+
+    #[test]
+    fn test_active_device_falls_back_to_first_listed() {
+        let (a, b) = ("/dev/hidraw3".to_string(), "/dev/hidraw7".to_string());
+        let devices = [&a, &b];
+        let path = |d: &String| d.clone();
+
+        assert_eq!(pick_active(&devices, None, path), Some(&a));
+        assert_eq!(pick_active(&devices, Some("/dev/hidraw7"), path), Some(&b));
+        // The chosen key was unplugged.
+        assert_eq!(pick_active(&devices, Some("/dev/hidraw9"), path), Some(&a));
+        assert_eq!(pick_active::<String>(&[], Some("/dev/hidraw7"), path), None);
+    }
+
+    #[test]
+    fn test_usb_class_names() {
+        assert_eq!(usb_class_name(0x03), "HID");
+        assert_eq!(usb_class_name(0x0B), "Smart Card (CCID)");
+        assert_eq!(usb_class_name(0x42), "Class 0x42");
+    }
+}
@@ -0,0 +1,155 @@
+//! Shared FIDO HID connection.
+//!
+//! Opening a key takes a HidApi enumeration, an `open` and a CTAPHID_INIT
+//! round trip. [`DeviceManager`] keeps the last opened [`HidTransport`] and
+//! hands it out again from [`HidTransport::open`], so back-to-back operations
+//! reuse its Channel ID instead of negotiating a new one each time.
+//!
+//! - A connection idle for longer than `hid.health_check_idle_ms`, or whose
+//!   last exchange failed below the CTAP level, gets a CTAPHID_PING before it
+//!   is reused. When that fails (key unplugged, rebooted after a reset or
+//!   config write, channel dropped) it is replaced by a fresh one.
+//! - Choosing another key ([`HidTransport::select_path`]) drops the
+//!   connection to the previous one.
+//! - A status read that finds no key ([`crate::hal::io::read_device_details`])
+//!   drops the connection, so the next key plugged in starts on a fresh one.
+//! - Users are serialized by a mutex, so two operations never interleave
+//!   packets on one channel. Sessions that wait on the user between calls
+//!   (fingerprint enrollment) take their own handle with
+//!   [`HidTransport::connect`] rather than hold the shared one.
+
+use std::fmt;
+use std::ops::Deref;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use super::fido::HidTransport;
+use crate::error::PFError;
+use crate::tunables;
+
+/// Random bytes echoed by the health-check ping.
+const HEALTH_CHECK_LEN: usize = 8;
+
+/// The cached connection and when it was last handed back.
+struct Connection {
+    transport: HidTransport,
+    released_at: Instant,
+}
+
+/// Owner of the shared FIDO HID connection. See the module docs.
+pub struct DeviceManager {
+    connection: Mutex<Option<Connection>>,
+}
+
+static MANAGER: DeviceManager = DeviceManager {
+    connection: Mutex::new(None),
+};
+
+/// Whether a cached connection has to answer a ping before it is reused.
+fn needs_health_check(idle: Duration, threshold: Duration, faulted: bool) -> bool {
+    faulted || idle >= threshold
+}
+
+impl DeviceManager {
+    /// The process-wide manager.
+    pub fn shared() -> &'static DeviceManager {
+        &MANAGER
+    }
+
+    fn lock(&'static self) -> MutexGuard<'static, Option<Connection>> {
+        self.connection.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Exclusive use of the connection to the active key, reconnecting when
+    /// there is none or the cached one fails its health check.
+    pub fn transport(&'static self) -> Result<SharedTransport, PFError> {
+        let threshold = Duration::from_millis(tunables::get().hid.health_check_idle_ms as u64);
+        let mut guard = self.lock();
+        if let Some(connection) = guard.as_ref()
+            && needs_health_check(
+                connection.released_at.elapsed(),
+                threshold,
+                connection.transport.faulted(),
+            )
+            && let Err(e) = connection.transport.ping(HEALTH_CHECK_LEN)
+        {
+            log::info!(
+                "Cached FIDO connection did not answer PING, reconnecting: {}",
+                e
+            );
+            *guard = None;
+        }
+        if guard.is_none() {
+            *guard = Some(Connection {
+                transport: HidTransport::connect()?,
+                released_at: Instant::now(),
+            });
+        } else {
+            log::debug!("Reusing the FIDO HID connection");
+        }
+        Ok(SharedTransport { guard })
+    }
+
+    /// Drop the cached connection unless it is to the interface at `path`.
+    /// Waits for a current user to finish.
+    pub fn retain_path(&'static self, path: &str) {
+        let mut guard = self.lock();
+        if guard.as_ref().is_some_and(|c| c.transport.path != path) {
+            log::debug!("Closing the FIDO HID connection to the previous key");
+            *guard = None;
+        }
+    }
+
+    /// Drop the cached connection; the next operation opens a new one.
+    /// Waits for a current user to finish.
+    pub fn disconnect(&'static self) {
+        *self.lock() = None;
+    }
+}
+
+/// Exclusive use of the shared connection, released when dropped.
+/// Derefs to [`HidTransport`].
+pub struct SharedTransport {
+    guard: MutexGuard<'static, Option<Connection>>,
+}
+
+impl Deref for SharedTransport {
+    type Target = HidTransport;
+
+    fn deref(&self) -> &HidTransport {
+        match self.guard.as_ref() {
+            Some(connection) => &connection.transport,
+            None => unreachable!("SharedTransport is only built around a connection"),
+        }
+    }
+}
+
+impl Drop for SharedTransport {
+    fn drop(&mut self) {
+        if let Some(connection) = self.guard.as_mut() {
+            connection.released_at = Instant::now();
+        }
+    }
+}
+
+impl fmt::Debug for SharedTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_health_check_after_idle_or_failure() {
+        let threshold = Duration::from_millis(2000);
+        let (busy, idle) = (Duration::from_millis(50), Duration::from_secs(5));
+        assert!(!needs_health_check(busy, threshold, false));
+        assert!(needs_health_check(busy, threshold, true));
+        assert!(needs_health_check(idle, threshold, false));
+        // A zero threshold checks every time.
+        assert!(needs_health_check(Duration::ZERO, Duration::ZERO, false));
+    }
+}
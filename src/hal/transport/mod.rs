@@ -9,10 +9,18 @@
 //!   a PC/SC smart-card reader. Used when the device is in rescue/bootloader mode
 //!   or when FIDO commands are blocked (e.g. firmware version ≥ 7.4 on pico-fido).
 //!
+//! A third, [`picoboot`], talks to the RP2040/RP2350 boot ROM while a key
+//! sits in BOOTSEL mode: it reads board details and burns the RP2350 OTP
+//! whitelabel.
+//!
 //! The [`DeviceHandle::discover`] method tries PC/SC first and falls back to
 //! FIDO HID. The PC/SC rescue channel provides richer device details (serial,
 //! flash stats, secure boot) and does not require PIN authentication for
 //! configuration writes.
+//!
+//! FIDO operations share one HID connection kept by
+//! [`manager::DeviceManager`] rather than opening the key each time. The UI
+//! hands blocking reads to the [`worker`] thread and awaits the result.
 
 use std::fmt;
 
@@ -22,13 +30,20 @@ use crate::hal::types::FirmwareType;
 pub mod fido;
 use fido::HidTransport;
 
+pub mod manager;
+use manager::SharedTransport;
+
 pub mod pcsc;
 use pcsc::PcscTransport;
 
+pub mod picoboot;
+
+pub mod worker;
+
 /// A connected device handle over either the FIDO or rescue transport.
 pub enum DeviceHandle {
-    /// Connected via CTAPHID (USB HID).
-    Fido(HidTransport),
+    /// Connected via CTAPHID (USB HID), holding the shared connection.
+    Fido(SharedTransport),
     /// Connected via PC/SC (ISO 7816-4 APDU, rescue/bootloader mode).
     Rescue(PcscTransport),
 }
@@ -61,6 +76,7 @@ pub struct DeviceIdentity {
 
 impl DeviceHandle {
     /// Return the firmware type for a rescue handle, or `Unknown` for FIDO.
+    #[allow(dead_code)]
     pub fn firmware_type(&self) -> FirmwareType {
         match self {
             Self::Fido(_) => FirmwareType::Unknown,
@@ -70,7 +86,7 @@ impl DeviceHandle {
 
     /// Extract the inner FIDO transport, consuming the handle.
     #[allow(dead_code)]
-    pub fn into_fido(self) -> Option<HidTransport> {
+    pub fn into_fido(self) -> Option<SharedTransport> {
         match self {
             Self::Fido(t) => Some(t),
             _ => None,
@@ -6,8 +6,18 @@
 
 use crate::error::PFError;
 use crate::hal::{rescue::constants::*, types::FirmwareType};
+use crate::logging::{self, WIRE_TARGET};
 use pcsc::{Context, Protocols, Scope, ShareMode};
 
+/// In a dry run, print `apdu` instead of sending it and fail.
+fn hold_back(apdu: &[u8]) -> Result<(), PFError> {
+    if logging::dry_run() {
+        logging::print_unsent(&format!("APDU > {}", hex::encode_upper(apdu)));
+        return Err(PFError::Device(logging::NOT_SENT.into()));
+    }
+    Ok(())
+}
+
 /// PC/SC transport wrapping a connected ISO 7816-4 smart card.
 pub struct PcscTransport {
     /// The connected PC/SC card handle.
@@ -60,6 +70,7 @@ impl PcscTransport {
         ];
         apdu.extend_from_slice(aid);
 
+        hold_back(&apdu)?;
         let mut rx_buf = [0; 256];
         let rx = card.transmit(&apdu, &mut rx_buf)?;
 
@@ -90,7 +101,56 @@ impl PcscTransport {
         })
     }
 
+    /// Startup probe: establish a PC/SC context and count the readers.
+    ///
+    /// An empty reader list is not an error; it only means no key is plugged
+    /// in. Fails when the PC/SC service itself is unavailable.
+    pub fn probe() -> Result<usize, PFError> {
+        let ctx = Context::establish(Scope::User)?;
+
+        let mut readers_buf = [0; 2048];
+        match ctx.list_readers(&mut readers_buf) {
+            Ok(readers) => Ok(readers.count()),
+            Err(pcsc::Error::NoReadersAvailable) => Ok(0),
+            Err(e) => Err(PFError::Pcsc(e)),
+        }
+    }
+
+    /// Whether this card answers SELECT for `aid` with 9000.
+    ///
+    /// Used to detect optional applets (OpenPGP, OATH) on the key the rescue
+    /// channel opened, so another card in another reader is never probed.
+    /// Leaves `aid` selected. Any transport error counts as "not present".
+    pub fn applet_responds(&self, aid: &[u8]) -> bool {
+        let mut apdu = vec![
+            APDU_CLA_ISO,
+            APDU_INS_SELECT,
+            APDU_P1_SELECT_BY_DF_NAME,
+            APDU_P2_RETURN_FCI,
+            aid.len() as u8,
+        ];
+        apdu.extend_from_slice(aid);
+
+        if hold_back(&apdu).is_err() {
+            return false;
+        }
+        let mut rx_buf = [0; 256];
+        self.card
+            .transmit(&apdu, &mut rx_buf)
+            .map(|rx| rx.ends_with(&[0x90, 0x00]))
+            .unwrap_or(false)
+    }
+
     pub fn transmit<'a>(&self, apdu: &[u8], rx_buf: &'a mut [u8]) -> Result<&'a [u8], PFError> {
-        self.card.transmit(apdu, rx_buf).map_err(PFError::Pcsc)
+        hold_back(apdu)?;
+        let trace = logging::trace_wire();
+        if trace {
+            log::info!(target: WIRE_TARGET, "APDU > {}", logging::wire_hex(apdu));
+        }
+        let response = self.card.transmit(apdu, rx_buf).map_err(PFError::Pcsc)?;
+        if trace {
+            log::info!(target: WIRE_TARGET, "APDU < {}", logging::wire_hex(response));
+        }
+        Ok(response)
     }
 }
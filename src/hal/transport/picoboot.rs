@@ -0,0 +1,443 @@
+//! PICOBOOT: the vendor USB interface of the RP2040/RP2350 boot ROM.
+//!
+//! A board in BOOTSEL mode exposes it next to the UF2 mass-storage drive.
+//! Every command is a 32-byte struct on the bulk OUT endpoint, followed by an
+//! optional data phase and a zero-length acknowledgement in the opposite
+//! direction (RP2040 datasheet §2.8.5, RP2350 datasheet §5.6):
+//!
+//! ```text
+//! dMagic(4) | dToken(4) | bCmdId(1) | bCmdSize(1) | reserved(2) | dTransferLength(4) | args(16)
+//! ```
+//!
+//! hidapi and PC/SC cannot reach bulk endpoints, so this transport goes
+//! through libusb. It reads the boot ROM version (which gives the chip
+//! revision) and flash contents, and reads and writes RP2350 OTP rows. Flash
+//! is never written.
+//!
+//! On Linux the interface needs the usual picotool udev rule; on Windows an
+//! RP2040 needs the WinUSB driver bound to it (RP2350 asks for it itself).
+
+use std::time::Duration;
+
+use crate::error::PFError;
+use crate::logging::{self, WIRE_TARGET};
+
+/// Raspberry Pi vendor ID.
+const VID_RASPBERRY_PI: u16 = 0x2E8A;
+/// RP2040 boot ROM product ID.
+const PID_RP2040_BOOT: u16 = 0x0003;
+/// RP2350 boot ROM product ID.
+const PID_RP2350_BOOT: u16 = 0x000F;
+
+/// Value of `dMagic` in every command.
+const PICOBOOT_MAGIC: u32 = 0x431F_D10B;
+
+/// Switch the flash to serial command mode so `READ` can reach it. RP2040
+/// needs this after reset; RP2350 accepts it as a no-op.
+const CMD_ENTER_CMD_XIP: u8 = 0x07;
+/// Read `dSize` bytes from `dAddr`.
+const CMD_READ: u8 = 0x84;
+/// Read OTP rows (RP2350 only).
+const CMD_OTP_READ: u8 = 0x8C;
+/// Write OTP rows (RP2350 only).
+const CMD_OTP_WRITE: u8 = 0x0D;
+
+/// Bytes per OTP row in a transfer: 16 data bits when the boot ROM applies
+/// ECC, the raw 24 bits padded to a word otherwise.
+const OTP_ECC_ROW_LEN: usize = 2;
+const OTP_RAW_ROW_LEN: usize = 4;
+
+/// Vendor control request that aborts a stuck command and clears a halt.
+const REQUEST_IF_RESET: u8 = 0x41;
+
+/// Boot ROM header: `'M' 'u' chip version` at this address.
+const ROM_MAGIC_ADDR: u32 = 0x0000_0010;
+/// Start of the XIP flash window.
+const FLASH_BASE: u32 = 0x1000_0000;
+/// The XIP window maps at most 16 MiB of flash per chip select.
+const FLASH_WINDOW: u32 = 16 * 1024 * 1024;
+/// Smallest flash size probed for.
+const FLASH_MIN: u32 = 256 * 1024;
+/// Bytes compared when looking for the flash wrapping around.
+const FLASH_PROBE_LEN: u32 = 256;
+
+/// How long one bulk transfer may take.
+const TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Chip family reported by the boot ROM header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RomChip {
+    Rp2040,
+    Rp2350,
+}
+
+/// What PICOBOOT reports about one board.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PicobootInfo {
+    /// USB bus and address, for log messages.
+    pub location: String,
+    /// Chip family from the boot ROM header.
+    pub chip: RomChip,
+    /// Silicon revision, e.g. `B2` or `A4`.
+    pub revision: String,
+    /// Flash size in bytes; `None` while the flash is blank, because a blank
+    /// chip cannot be told from its own wrap-around.
+    pub flash_size: Option<u32>,
+}
+
+/// An open PICOBOOT interface.
+pub struct PicobootTransport {
+    handle: rusb::DeviceHandle<rusb::GlobalContext>,
+    interface: u8,
+    ep_in: u8,
+    ep_out: u8,
+    token: u32,
+    location: String,
+    chip: RomChip,
+}
+
+fn usb_error(e: rusb::Error) -> PFError {
+    PFError::Io(format!("PICOBOOT: {}", e))
+}
+
+impl PicobootTransport {
+    /// Open every attached board that is in BOOTSEL mode. Boards whose
+    /// interface cannot be opened (permissions, missing driver) are logged
+    /// and skipped.
+    pub fn open_all() -> Vec<Self> {
+        let devices = match rusb::devices() {
+            Ok(devices) => devices,
+            Err(e) => {
+                log::debug!("Could not list USB devices: {}", e);
+                return Vec::new();
+            }
+        };
+        devices
+            .iter()
+            .filter(|device| {
+                device.device_descriptor().is_ok_and(|d| {
+                    d.vendor_id() == VID_RASPBERRY_PI
+                        && matches!(d.product_id(), PID_RP2040_BOOT | PID_RP2350_BOOT)
+                })
+            })
+            .filter_map(|device| {
+                let location = format!("bus {} address {}", device.bus_number(), device.address());
+                let chip = match device.device_descriptor().ok()?.product_id() {
+                    PID_RP2040_BOOT => RomChip::Rp2040,
+                    _ => RomChip::Rp2350,
+                };
+                Self::open(&device, location.clone(), chip)
+                    .inspect_err(|e| log::warn!("Could not open PICOBOOT at {}: {}", location, e))
+                    .ok()
+            })
+            .collect()
+    }
+
+    /// Open the one attached RP2350 in BOOTSEL mode. Refuses when there is
+    /// none or several, so a write cannot land on the wrong board.
+    pub fn open_rp2350() -> Result<Self, PFError> {
+        let mut boards: Vec<Self> = Self::open_all()
+            .into_iter()
+            .filter(|t| t.chip == RomChip::Rp2350)
+            .collect();
+        match boards.len() {
+            0 => Err(PFError::Device(
+                "No RP2350 in BOOTSEL mode answered over PICOBOOT".into(),
+            )),
+            1 => Ok(boards.remove(0)),
+            n => Err(PFError::Device(format!(
+                "{} RP2350 boards are in BOOTSEL mode; attach only the one to write",
+                n
+            ))),
+        }
+    }
+
+    /// USB bus and address of the board, for log messages.
+    pub fn location(&self) -> &str {
+        &self.location
+    }
+
+    fn open(
+        device: &rusb::Device<rusb::GlobalContext>,
+        location: String,
+        chip: RomChip,
+    ) -> Result<Self, PFError> {
+        let config = device.active_config_descriptor().map_err(usb_error)?;
+        let (interface, ep_in, ep_out) = config
+            .interfaces()
+            .flat_map(|interface| interface.descriptors())
+            .find_map(|alt| {
+                if alt.class_code() != 0xFF {
+                    return None;
+                }
+                let bulk = |direction| {
+                    alt.endpoint_descriptors()
+                        .find(|ep| {
+                            ep.transfer_type() == rusb::TransferType::Bulk
+                                && ep.direction() == direction
+                        })
+                        .map(|ep| ep.address())
+                };
+                Some((
+                    alt.interface_number(),
+                    bulk(rusb::Direction::In)?,
+                    bulk(rusb::Direction::Out)?,
+                ))
+            })
+            .ok_or_else(|| PFError::Device("No PICOBOOT interface".into()))?;
+
+        let handle = device.open().map_err(usb_error)?;
+        // Not supported on macOS and Windows, where nothing else claims it.
+        let _ = handle.set_auto_detach_kernel_driver(true);
+        handle.claim_interface(interface).map_err(usb_error)?;
+        let transport = Self {
+            handle,
+            interface,
+            ep_in,
+            ep_out,
+            token: 1,
+            location,
+            chip,
+        };
+        transport.reset()?;
+        Ok(transport)
+    }
+
+    /// Clear whatever a previous host left half done.
+    fn reset(&self) -> Result<(), PFError> {
+        let request_type = rusb::request_type(
+            rusb::Direction::Out,
+            rusb::RequestType::Vendor,
+            rusb::Recipient::Interface,
+        );
+        self.handle
+            .write_control(
+                request_type,
+                REQUEST_IF_RESET,
+                0,
+                self.interface as u16,
+                &[],
+                TIMEOUT,
+            )
+            .map(|_| ())
+            .map_err(usb_error)
+    }
+
+    /// Send the 32-byte command struct.
+    fn send_command(&mut self, id: u8, args: &[u8], transfer_len: usize) -> Result<(), PFError> {
+        let mut packet = [0u8; 32];
+        packet[0..4].copy_from_slice(&PICOBOOT_MAGIC.to_le_bytes());
+        packet[4..8].copy_from_slice(&self.token.to_le_bytes());
+        packet[8] = id;
+        packet[9] = args.len() as u8;
+        packet[12..16].copy_from_slice(&(transfer_len as u32).to_le_bytes());
+        packet[16..16 + args.len()].copy_from_slice(args);
+        self.token = self.token.wrapping_add(1);
+        if logging::trace_wire() {
+            log::info!(target: WIRE_TARGET, "PICOBOOT > {}", logging::wire_hex(&packet));
+        }
+
+        self.handle
+            .write_bulk(self.ep_out, &packet, TIMEOUT)
+            .map(|_| ())
+            .map_err(usb_error)
+    }
+
+    /// Run one command with `data_out` as its data phase.
+    fn command_out(&mut self, id: u8, args: &[u8], data_out: &[u8]) -> Result<(), PFError> {
+        self.send_command(id, args, data_out.len())?;
+        if logging::trace_wire() {
+            log::info!(target: WIRE_TARGET, "PICOBOOT > {}", logging::wire_hex(data_out));
+        }
+        self.handle
+            .write_bulk(self.ep_out, data_out, TIMEOUT)
+            .map_err(usb_error)?;
+        // A refused write stalls here instead of acknowledging.
+        self.handle
+            .read_bulk(self.ep_in, &mut [], TIMEOUT)
+            .map_err(usb_error)?;
+        Ok(())
+    }
+
+    /// Run one command. `data_in` is filled from the device when it is
+    /// non-empty; otherwise the command has no data phase.
+    fn command(&mut self, id: u8, args: &[u8], data_in: &mut [u8]) -> Result<(), PFError> {
+        self.send_command(id, args, data_in.len())?;
+        if data_in.is_empty() {
+            // Zero-length status from the device.
+            self.handle
+                .read_bulk(self.ep_in, &mut [], TIMEOUT)
+                .map_err(usb_error)?;
+            return Ok(());
+        }
+
+        let mut read = 0;
+        while read < data_in.len() {
+            let n = self
+                .handle
+                .read_bulk(self.ep_in, &mut data_in[read..], TIMEOUT)
+                .map_err(usb_error)?;
+            if n == 0 {
+                return Err(PFError::Io("PICOBOOT: short read".into()));
+            }
+            read += n;
+        }
+        if logging::trace_wire() {
+            log::info!(target: WIRE_TARGET, "PICOBOOT < {}", logging::wire_hex(data_in));
+        }
+        // Acknowledge with a zero-length packet the other way.
+        self.handle
+            .write_bulk(self.ep_out, &[], TIMEOUT)
+            .map_err(usb_error)?;
+        Ok(())
+    }
+
+    /// Read `len` bytes of memory at `addr`.
+    fn read(&mut self, addr: u32, len: u32) -> Result<Vec<u8>, PFError> {
+        let mut args = [0u8; 8];
+        args[0..4].copy_from_slice(&addr.to_le_bytes());
+        args[4..8].copy_from_slice(&len.to_le_bytes());
+        let mut data = vec![0u8; len as usize];
+        self.command(CMD_READ, &args, &mut data)?;
+        Ok(data)
+    }
+
+    /// Chip family and revision from the boot ROM header.
+    fn rom_chip(&mut self) -> Result<(RomChip, String), PFError> {
+        let header = self.read(ROM_MAGIC_ADDR, 4)?;
+        parse_rom_header(&header)
+            .ok_or_else(|| PFError::Device(format!("Unknown boot ROM header {:02X?}", header)))
+    }
+
+    /// Flash size, found where the flash starts repeating itself: addresses
+    /// past the end of the chip wrap around to its start.
+    fn flash_size(&mut self) -> Result<Option<u32>, PFError> {
+        self.command(CMD_ENTER_CMD_XIP, &[], &mut [])?;
+        let first = self.read(FLASH_BASE, FLASH_PROBE_LEN)?;
+        if first.iter().all(|&b| b == first[0]) {
+            return Ok(None);
+        }
+        let mut size = FLASH_MIN;
+        while size < FLASH_WINDOW {
+            if self.read(FLASH_BASE + size, FLASH_PROBE_LEN)? == first {
+                return Ok(Some(size));
+            }
+            size *= 2;
+        }
+        Ok(Some(FLASH_WINDOW))
+    }
+
+    fn otp_args(&self, row: u16, count: usize, ecc: bool) -> Result<[u8; 5], PFError> {
+        if self.chip != RomChip::Rp2350 {
+            return Err(PFError::Device("Only the RP2350 has OTP".into()));
+        }
+        let count = u16::try_from(count)
+            .map_err(|_| PFError::Device("Too many OTP rows in one transfer".into()))?;
+        let mut args = [0u8; 5];
+        args[0..2].copy_from_slice(&row.to_le_bytes());
+        args[2..4].copy_from_slice(&count.to_le_bytes());
+        args[4] = ecc as u8;
+        Ok(args)
+    }
+
+    /// Read `count` OTP rows from `row` with ECC correction.
+    pub fn otp_read_ecc(&mut self, row: u16, count: usize) -> Result<Vec<u16>, PFError> {
+        let args = self.otp_args(row, count, true)?;
+        let mut data = vec![0u8; count * OTP_ECC_ROW_LEN];
+        self.command(CMD_OTP_READ, &args, &mut data)?;
+        Ok(data
+            .chunks_exact(OTP_ECC_ROW_LEN)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .collect())
+    }
+
+    /// Read `count` raw 24-bit OTP rows from `row`.
+    pub fn otp_read_raw(&mut self, row: u16, count: usize) -> Result<Vec<u32>, PFError> {
+        let args = self.otp_args(row, count, false)?;
+        let mut data = vec![0u8; count * OTP_RAW_ROW_LEN];
+        self.command(CMD_OTP_READ, &args, &mut data)?;
+        Ok(data
+            .chunks_exact(OTP_RAW_ROW_LEN)
+            .map(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+            .collect())
+    }
+
+    /// Burn `values` into consecutive OTP rows from `row`, with ECC computed
+    /// by the boot ROM. Irreversible.
+    pub fn otp_write_ecc(&mut self, row: u16, values: &[u16]) -> Result<(), PFError> {
+        let args = self.otp_args(row, values.len(), true)?;
+        let data: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+        self.command_out(CMD_OTP_WRITE, &args, &data)
+    }
+
+    /// Burn raw 24-bit `values` into consecutive OTP rows from `row`. Only
+    /// bits that are set are programmed. Irreversible.
+    pub fn otp_write_raw(&mut self, row: u16, values: &[u32]) -> Result<(), PFError> {
+        let args = self.otp_args(row, values.len(), false)?;
+        let data: Vec<u8> = values
+            .iter()
+            .flat_map(|v| (v & 0x00FF_FFFF).to_le_bytes())
+            .collect();
+        self.command_out(CMD_OTP_WRITE, &args, &data)
+    }
+
+    /// Read everything [`PicobootInfo`] holds.
+    pub fn info(&mut self) -> Result<PicobootInfo, PFError> {
+        let (chip, revision) = self.rom_chip()?;
+        Ok(PicobootInfo {
+            location: self.location.clone(),
+            chip,
+            revision,
+            flash_size: self.flash_size()?,
+        })
+    }
+}
+
+impl Drop for PicobootTransport {
+    fn drop(&mut self) {
+        let _ = self.handle.release_interface(self.interface);
+    }
+}
+
+/// Decode the boot ROM header `'M' 'u' chip version`. RP2040 numbers its
+/// ROMs 1–3 for silicon B0–B2; RP2350 uses the revision number itself.
+fn parse_rom_header(header: &[u8]) -> Option<(RomChip, String)> {
+    match *header {
+        [b'M', b'u', 1, version @ 1..] => Some((RomChip::Rp2040, format!("B{}", version - 1))),
+        [b'M', b'u', 2, version] => Some((RomChip::Rp2350, format!("A{}", version))),
+        _ => None,
+    }
+}
+
+/// Read every attached BOOTSEL board over PICOBOOT. Boards that cannot be
+/// opened or do not answer are left out.
+pub fn scan() -> Vec<PicobootInfo> {
+    PicobootTransport::open_all()
+        .into_iter()
+        .filter_map(|mut transport| {
+            transport
+                .info()
+                .inspect_err(|e| log::warn!("PICOBOOT at {}: {}", transport.location, e))
+                .ok()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rom_header_revisions() {
+        assert_eq!(
+            parse_rom_header(&[b'M', b'u', 1, 3]),
+            Some((RomChip::Rp2040, "B2".into()))
+        );
+        assert_eq!(
+            parse_rom_header(&[b'M', b'u', 2, 4]),
+            Some((RomChip::Rp2350, "A4".into()))
+        );
+        assert_eq!(parse_rom_header(&[b'M', b'u', 1, 0]), None);
+        assert_eq!(parse_rom_header(&[0xFF; 4]), None);
+    }
+}
@@ -0,0 +1,304 @@
+//! Background thread for device I/O.
+//!
+//! A CTAPHID exchange blocks for up to `hid.total_timeout_ms`, and anything
+//! waiting for a touch blocks for the user-presence timeout. [`submit`] runs
+//! such a job on the `picoforge-hid` thread and returns a [`Pending`] result
+//! right away, which a view `.await`s inside `cx.spawn` (or a background
+//! thread [`wait`](Pending::wait)s for), so the GPUI thread never blocks on
+//! the key.
+//!
+//! Jobs run one at a time in submission order, as they would contend for the
+//! one shared connection ([`super::manager`]) anyway. A job submitted from
+//! the worker itself runs in place rather than waiting behind its caller.
+//! A job that panics resolves its [`Pending`] with an error and leaves the
+//! thread running. The submitter's log action follows the job onto the thread.
+//!
+//! A flow that must have the key to itself (factory reset, secure-boot
+//! provisioning) takes a [`MaintenanceLatch`] with [`begin_maintenance`] and
+//! talks to the key directly. While it is held, queued jobs wait and the
+//! background pollers check [`maintenance_reason`] and skip their turn.
+//! Dropping the latch — the flow finished, failed or was cancelled — resumes
+//! them; a latch left past its time limit lapses on its own.
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, OnceLock};
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
+
+use crate::error::PFError;
+use crate::logging;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Name of the worker thread, also how a job knows it is already on it.
+const THREAD_NAME: &str = "picoforge-hid";
+
+static QUEUE: OnceLock<Sender<Job>> = OnceLock::new();
+
+/// How often the worker looks at the latch while jobs are held back.
+const MAINTENANCE_POLL: Duration = Duration::from_millis(100);
+
+/// Sender to the worker thread, started on first use.
+fn queue() -> &'static Sender<Job> {
+    QUEUE.get_or_init(|| {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let spawned = std::thread::Builder::new()
+            .name(THREAD_NAME.into())
+            .spawn(move || run(receiver));
+        if let Err(e) = spawned {
+            log::error!("Could not start the HID worker thread: {}", e);
+        }
+        sender
+    })
+}
+
+/// Worker loop: run jobs in order, holding them back during maintenance.
+fn run(receiver: Receiver<Job>) {
+    let mut held: VecDeque<Job> = VecDeque::new();
+    loop {
+        while maintenance_reason().is_none() {
+            match held.pop_front() {
+                Some(job) => job(),
+                None => break,
+            }
+        }
+        let next = if held.is_empty() {
+            receiver.recv().map_err(|_| RecvTimeoutError::Disconnected)
+        } else {
+            receiver.recv_timeout(MAINTENANCE_POLL)
+        };
+        match next {
+            Ok(job) if held.is_empty() && maintenance_reason().is_none() => job(),
+            Ok(job) => held.push_back(job),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+}
+
+/// The flow holding the key, and until when.
+struct Latch {
+    id: u64,
+    reason: String,
+    deadline: Instant,
+}
+
+impl Latch {
+    fn lapsed(&self, now: Instant) -> bool {
+        now >= self.deadline
+    }
+}
+
+static MAINTENANCE: Mutex<Option<Latch>> = Mutex::new(None);
+static NEXT_LATCH: AtomicU64 = AtomicU64::new(1);
+
+fn maintenance() -> MutexGuard<'static, Option<Latch>> {
+    MAINTENANCE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Hold the key for a sensitive flow described by `reason` (shown to the
+/// user) for at most `limit`. Replaces a latch already held.
+pub fn begin_maintenance(reason: impl Into<String>, limit: Duration) -> MaintenanceLatch {
+    let id = NEXT_LATCH.fetch_add(1, Ordering::Relaxed);
+    let reason = reason.into();
+    log::info!(
+        "Maintenance mode: {}, background device access paused",
+        reason
+    );
+    *maintenance() = Some(Latch {
+        id,
+        reason,
+        deadline: Instant::now() + limit,
+    });
+    MaintenanceLatch { id }
+}
+
+/// What the key is held for, or `None` when background traffic may run.
+/// Clears a latch that outlived its time limit.
+pub fn maintenance_reason() -> Option<String> {
+    let mut latch = maintenance();
+    if latch.as_ref().is_some_and(|l| l.lapsed(Instant::now()))
+        && let Some(l) = latch.take()
+    {
+        log::warn!(
+            "Maintenance mode timed out ({}), resuming device access",
+            l.reason
+        );
+    }
+    latch.as_ref().map(|l| l.reason.clone())
+}
+
+/// Maintenance mode from [`begin_maintenance`], ended when dropped.
+#[derive(Debug)]
+pub struct MaintenanceLatch {
+    id: u64,
+}
+
+impl Drop for MaintenanceLatch {
+    fn drop(&mut self) {
+        let mut latch = maintenance();
+        if latch.as_ref().is_some_and(|l| l.id == self.id) {
+            *latch = None;
+            log::info!("Maintenance mode ended, resuming device access");
+        }
+    }
+}
+
+/// Result slot shared between the worker and a [`Pending`].
+struct Slot<T> {
+    result: Option<Result<T, PFError>>,
+    waker: Option<Waker>,
+}
+
+type Shared<T> = Arc<(Mutex<Slot<T>>, Condvar)>;
+
+fn fill<T>(shared: &Shared<T>, result: Result<T, PFError>) {
+    let (slot, ready) = &**shared;
+    let mut slot = slot.lock().unwrap_or_else(|e| e.into_inner());
+    slot.result = Some(result);
+    if let Some(waker) = slot.waker.take() {
+        waker.wake();
+    }
+    ready.notify_all();
+}
+
+/// The result of a job handed to [`submit`], once it has run.
+///
+/// Resolves to an error only when the job panicked or the worker thread is
+/// gone; the job's own errors are part of `T`.
+pub struct Pending<T> {
+    shared: Shared<T>,
+}
+
+impl<T> Pending<T> {
+    /// Block the calling thread until the job has run. Not for the GPUI
+    /// thread; await the `Pending` there instead.
+    pub fn wait(self) -> Result<T, PFError> {
+        let (slot, ready) = &*self.shared;
+        let mut slot = slot.lock().unwrap_or_else(|e| e.into_inner());
+        loop {
+            if let Some(result) = slot.result.take() {
+                return result;
+            }
+            slot = ready.wait(slot).unwrap_or_else(|e| e.into_inner());
+        }
+    }
+}
+
+impl<T> Future for Pending<T> {
+    type Output = Result<T, PFError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut slot = self.shared.0.lock().unwrap_or_else(|e| e.into_inner());
+        match slot.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                slot.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Run `job` on the worker thread after the jobs already queued.
+pub fn submit<T, F>(job: F) -> Pending<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let shared: Shared<T> = Arc::new((
+        Mutex::new(Slot {
+            result: None,
+            waker: None,
+        }),
+        Condvar::new(),
+    ));
+    let for_worker = shared.clone();
+    let action = logging::current_action();
+    let job: Job = Box::new(move || {
+        let scoped = || match &action {
+            Some(action) => action.scope(job),
+            None => job(),
+        };
+        let result = panic::catch_unwind(AssertUnwindSafe(scoped)).map_err(|_| {
+            log::error!("A device job panicked on the HID worker thread");
+            PFError::Device("Device operation panicked".into())
+        });
+        fill(&for_worker, result);
+    });
+    if std::thread::current().name() == Some(THREAD_NAME) {
+        job();
+    } else if queue().send(job).is_err() {
+        fill(
+            &shared,
+            Err(PFError::Device(
+                "The HID worker thread is not running".into(),
+            )),
+        );
+    }
+    Pending { shared }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jobs_run_in_submission_order() {
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let pending: Vec<_> = (0..5)
+            .map(|i| {
+                let order = order.clone();
+                submit(move || order.lock().unwrap().push(i))
+            })
+            .collect();
+        for job in pending {
+            job.wait().unwrap();
+        }
+        assert_eq!(*order.lock().unwrap(), [0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_job_submitted_from_the_worker_runs_in_place() {
+        let nested = submit(|| submit(|| 3).wait().unwrap());
+        assert_eq!(nested.wait().unwrap(), 3);
+    }
+
+    #[test]
+    fn test_panicking_job_reports_an_error() {
+        let pending = submit(|| -> u8 { panic!("boom") });
+        assert!(pending.wait().is_err());
+        // The worker survives it.
+        assert_eq!(submit(|| 7).wait().unwrap(), 7);
+    }
+
+    #[test]
+    fn test_jobs_wait_out_maintenance() {
+        let latch = begin_maintenance("test", Duration::from_secs(30));
+        let ran = Arc::new(Mutex::new(false));
+        let flag = ran.clone();
+        let pending = submit(move || *flag.lock().unwrap() = true);
+        std::thread::sleep(Duration::from_millis(300));
+        assert!(!*ran.lock().unwrap());
+        drop(latch);
+        pending.wait().unwrap();
+        assert!(*ran.lock().unwrap());
+    }
+
+    #[test]
+    fn test_latch_lapses_at_its_deadline() {
+        let start = Instant::now();
+        let latch = Latch {
+            id: 0,
+            reason: "test".into(),
+            deadline: start + Duration::from_secs(60),
+        };
+        assert!(!latch.lapsed(start));
+        assert!(latch.lapsed(start + Duration::from_secs(60)));
+    }
+}
@@ -9,6 +9,7 @@
 
 use serde::{Deserialize, Serialize};
 use std::fmt;
+use std::time::Duration;
 
 // ── Application-level types ─────────────────────────────────────────────────
 
@@ -49,9 +50,16 @@ pub struct AppConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub led_driver: Option<u8>,
     pub led_dimmable: bool,
+    /// Power-reset protection: `authenticatorReset` is only accepted within
+    /// a few seconds of the key being plugged in. Stored inverted on the
+    /// device as the `DISABLE_POWER_RESET` option bit.
     pub power_cycle_on_reset: bool,
     /// When set, the LED stays on (not pulsed) for touch/processing states.
     pub led_steady: bool,
+    /// Options word as read from the device. `None` when it could not be read,
+    /// in which case the option flags above are defaults, not device state.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub raw_options: Option<u16>,
     pub enable_secp256k1: bool,
     /// Bitmask of raw (unwrapped) curve identifiers supported by the firmware.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -68,7 +76,7 @@ pub struct AppConfig {
 }
 
 /// Partial config update; `None` fields are left unchanged on the device.
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct AppConfigInput {
     pub vid: Option<String>,
@@ -88,6 +96,52 @@ pub struct AppConfigInput {
     pub led_num: Option<u8>,
 }
 
+/// One field of a configuration write and what the device reported for it
+/// afterwards.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigFieldReadBack {
+    /// Label of the field, as shown in the config screen.
+    pub field: String,
+    /// Value that was written.
+    pub written: String,
+    /// Value read back. `None` when the device does not report the field.
+    pub read_back: Option<String>,
+}
+
+/// Outcome of a configuration write, checked field by field against the
+/// configuration record read back from the device.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigApplyReport {
+    /// Every field that was written.
+    pub applied: Vec<ConfigFieldReadBack>,
+    /// Fields read back with the value that was written.
+    pub verified: Vec<String>,
+    /// Fields that were not checked: the device does not report them, or
+    /// the write path could not carry them and they were not written.
+    pub skipped: Vec<String>,
+    /// What the backend said about the write, e.g. that the key needs to be
+    /// re-plugged.
+    pub note: String,
+    /// Whether a field was written that only takes effect after a replug.
+    pub replug_required: bool,
+}
+
+impl ConfigApplyReport {
+    /// Fields read back with a value other than the one written.
+    pub fn mismatched(&self) -> impl Iterator<Item = &ConfigFieldReadBack> {
+        self.applied
+            .iter()
+            .filter(|f| f.read_back.is_some() && !self.verified.contains(&f.field))
+    }
+
+    /// Whether no field was read back with the wrong value.
+    pub fn is_consistent(&self) -> bool {
+        self.mismatched().next().is_none()
+    }
+}
+
 /// Aggregated snapshot of device info, config, and security state.
 #[derive(Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
@@ -96,9 +150,15 @@ pub struct FullDeviceStatus {
     pub info: DeviceInfo,
     /// Full device configuration (USB descriptors, LED, touch, crypto).
     pub config: AppConfig,
-    /// Whether secure boot is enabled on the device.
+    /// Whether secure boot is enabled on the device. Only readable through
+    /// the Rescue applet.
     pub secure_boot: bool,
-    /// Whether the device's secure configuration is locked (read-only until reset).
+    /// Whether secure boot is locked in OTP, which also locks out the debug
+    /// interface. Irreversible; only readable through the Rescue applet.
+    pub secure_boot_locked: bool,
+    /// Whether secure lock is on: the device key is stored encrypted under a
+    /// host key and needs an unlock after each power cycle. Read from the
+    /// GetInfo certifications over FIDO.
     pub secure_lock: bool,
     /// Protocol channel used for the last successful communication.
     pub method: DeviceMethod,
@@ -197,6 +257,38 @@ pub struct FidoDeviceInfo {
     pub max_serialized_large_blob_array: Option<i128>,
     pub force_pin_change: Option<bool>,
     pub max_cred_blob_length: Option<i128>,
+    /// `maxRPIDsForSetMinPINLength`: how many RP IDs setMinPINLength accepts.
+    pub max_rpids_for_min_pin_length: Option<i128>,
+    /// GetInfo members this version does not know (newer CTAP or vendor
+    /// firmware), kept as raw CBOR, by key.
+    pub unknown_fields: Vec<UnknownGetInfoField>,
+}
+
+/// A GetInfo member PicoForge cannot interpret, preserved as its raw CBOR
+/// encoding so new firmware data shows up without an app release.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct UnknownGetInfoField {
+    /// Integer key in the GetInfo map.
+    pub key: i128,
+    /// CBOR encoding of the value, hex-encoded when serialized.
+    #[serde(serialize_with = "serialize_hex")]
+    pub cbor: Vec<u8>,
+}
+
+impl UnknownGetInfoField {
+    /// The key as shown to users, `0x1D` style.
+    pub fn label(&self) -> String {
+        if self.key >= 0 {
+            format!("0x{:02X}", self.key)
+        } else {
+            self.key.to_string()
+        }
+    }
+}
+
+fn serialize_hex<S: serde::Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&hex::encode_upper(bytes))
 }
 
 /// A single FIDO2 credential stored on the device.
@@ -209,6 +301,531 @@ pub struct StoredCredential {
     pub user_display_name: String,
     pub user_id: String,
     pub credential_id: String,
+    /// COSE algorithm of the credential's public key (`-7` ES256, `-47` ES256K, ...).
+    pub algorithm: Option<i32>,
+    /// COSE key type of the public key (`kty`: 1 OKP, 2 EC2, ...).
+    pub key_type: Option<i32>,
+    /// COSE curve of the public key (`crv`: 1 P-256, 6 Ed25519, ...), for
+    /// curve-based keys.
+    pub curve: Option<i32>,
+    /// The CBOR-encoded COSE public key, for verifying test sign-ins. Empty
+    /// when the authenticator did not report one; never exported.
+    #[serde(skip)]
+    pub public_key: Vec<u8>,
+    /// `credProtect` policy; `None` when the authenticator did not report one.
+    pub cred_protect: Option<CredProtectPolicy>,
+}
+
+/// `credProtect` level a credential was created with (CTAP 2.1 §12.1).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CredProtectPolicy {
+    /// Usable without user verification.
+    UserVerificationOptional = 1,
+    /// Usable without user verification only when its ID is in the allow list.
+    UserVerificationOptionalWithCredentialIdList = 2,
+    /// Always requires user verification.
+    UserVerificationRequired = 3,
+}
+
+impl CredProtectPolicy {
+    /// Map the CBOR level to a policy; unknown levels give `None`.
+    pub fn from_u8(level: u8) -> Option<Self> {
+        match level {
+            1 => Some(Self::UserVerificationOptional),
+            2 => Some(Self::UserVerificationOptionalWithCredentialIdList),
+            3 => Some(Self::UserVerificationRequired),
+            _ => None,
+        }
+    }
+
+    /// Short badge text.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::UserVerificationOptional => "UV optional",
+            Self::UserVerificationOptionalWithCredentialIdList => "UV optional with ID list",
+            Self::UserVerificationRequired => "UV required",
+        }
+    }
+}
+
+impl StoredCredential {
+    /// Algorithm and curve for display, e.g. "ES256 / P-256". Unknown values
+    /// are shown as their COSE number; an RSA key shows its key type instead
+    /// of a curve. Empty when the key reported neither.
+    pub fn key_description(&self) -> String {
+        use crate::hal::common::cose::{CoseAlgorithm, CoseCurve, CoseKeyType};
+
+        let algorithm = self
+            .algorithm
+            .map(|alg| match CoseAlgorithm::from_i128(alg as i128) {
+                Some(alg) => alg.to_string(),
+                None => format!("alg {}", alg),
+            });
+        let curve = match (self.curve, self.key_type) {
+            (Some(crv), _) => Some(match CoseCurve::from_i128(crv as i128) {
+                Some(crv) => crv.to_string(),
+                None => format!("crv {}", crv),
+            }),
+            (None, Some(kty)) => CoseKeyType::from_i128(kty as i128).map(|kty| kty.to_string()),
+            (None, None) => None,
+        };
+        [algorithm, curve]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(" / ")
+    }
+}
+
+/// File format for exporting the stored credential list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CredentialExportFormat {
+    /// One JSON document with an `exportedAt` timestamp.
+    Json,
+    /// One row per credential with a header line.
+    Csv,
+}
+
+impl CredentialExportFormat {
+    /// File extension, without the dot.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Csv => "csv",
+        }
+    }
+}
+
+/// Options for a test credential created from the diagnostics tools.
+#[derive(Debug, Clone, Default)]
+pub struct TestCredentialRequest {
+    /// Relying party ID the credential is created under.
+    pub rp_id: String,
+    /// User name stored with the credential.
+    pub user_name: String,
+    /// Store the credential on the device (discoverable credential).
+    pub resident_key: bool,
+    /// Bytes to store with the `credBlob` extension.
+    pub cred_blob: Option<Vec<u8>>,
+    /// `enterpriseAttestation` to request (1 vendor-facilitated, 2
+    /// platform-managed); `None` leaves it out.
+    pub enterprise_attestation: Option<u8>,
+}
+
+/// Summary of an enterprise attestation certificate.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct AttestationCertInfo {
+    /// Certificate serial number, hex.
+    pub serial: String,
+    /// Subject as `CN=…, O=…`.
+    pub subject: String,
+    /// End of validity, `YYYY-MM-DD HH:MM:SS UTC`.
+    pub not_after: String,
+}
+
+/// Outcome of `authenticatorMakeCredential` for a test credential.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestCredential {
+    pub rp_id: String,
+    /// Credential ID, hex encoded.
+    pub credential_id: String,
+    /// Attestation statement format (`packed`, `none`, ...).
+    pub attestation_format: String,
+    pub sign_count: u32,
+    pub user_verified: bool,
+    /// `credBlob` extension output: whether the blob was stored. `None` when
+    /// no blob was sent or the authenticator ignored the extension.
+    pub cred_blob_stored: Option<bool>,
+    /// Local verification of the attestation statement. `None` unless the
+    /// format is `packed`.
+    pub attestation: Option<AttestationVerification>,
+}
+
+/// Result of verifying a `packed` attestation statement the way a relying
+/// party would (WebAuthn §8.2).
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttestationVerification {
+    /// Every check passed.
+    pub valid: bool,
+    /// Signed with the credential's own key rather than an `x5c` certificate.
+    pub self_attestation: bool,
+    /// AAGUID from the attested credential data, hex.
+    pub aaguid: String,
+    /// The checks that ran, in order.
+    pub checks: Vec<VerificationCheck>,
+}
+
+/// One check of a local signature verification (an attestation statement
+/// or a [`SignInTest`]).
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerificationCheck {
+    pub name: String,
+    pub passed: bool,
+    /// What was compared, or why it failed.
+    pub detail: String,
+}
+
+/// Outcome of a test sign-in with one stored passkey: `authenticatorGetAssertion`
+/// with only that credential in the allow list, verified with its stored
+/// public key.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignInTest {
+    /// Every check passed.
+    pub valid: bool,
+    pub sign_count: u32,
+    pub user_verified: bool,
+    /// The checks that ran, in order.
+    pub checks: Vec<VerificationCheck>,
+}
+
+/// Outcome of `authenticatorGetAssertion` against a test credential.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestAssertion {
+    /// Credential ID, hex encoded.
+    pub credential_id: String,
+    pub sign_count: u32,
+    pub user_present: bool,
+    pub user_verified: bool,
+    /// `user.name` (or `displayName`) for discoverable credentials.
+    pub user_name: Option<String>,
+    /// Blob returned for `getCredBlob`. Empty when the credential has none;
+    /// `None` when the authenticator did not answer the extension.
+    pub cred_blob: Option<Vec<u8>>,
+}
+
+/// Assertions for every discoverable credential of one RP, collected with
+/// `authenticatorGetNextAssertion`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssertionSweep {
+    /// `numberOfCredentials` from the first response (1 when omitted).
+    pub reported: u32,
+    /// One assertion per credential, in the order the key returned them.
+    pub assertions: Vec<TestAssertion>,
+    /// Why the sweep stopped early, if a GetNextAssertion failed.
+    pub error: Option<String>,
+}
+
+/// The authenticator's large-blob array and, when requested, one
+/// credential's entry in it.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LargeBlobInfo {
+    /// Entries in the array, across all credentials.
+    pub entries: usize,
+    /// Serialized array size in bytes, including the trailer.
+    pub used_bytes: usize,
+    /// `maxSerializedLargeBlobArray` from GetInfo.
+    pub capacity: Option<usize>,
+    /// `false` when the stored trailer did not match and the array was read
+    /// as empty, as the spec requires.
+    pub trailer_valid: bool,
+    /// Decrypted, decompressed blob of the credential that was asked for.
+    /// `None` when no entry opens with its `largeBlobKey`.
+    pub blob: Option<Vec<u8>>,
+}
+
+/// An enrolled fingerprint template.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BioTemplate {
+    /// Template ID, hex encoded.
+    pub template_id: String,
+    /// Friendly name; empty when none was set.
+    pub name: String,
+}
+
+/// Fingerprint sensor details from `getFingerprintSensorInfo`.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BioSensorInfo {
+    /// `1` for a touch sensor, `2` for a swipe sensor.
+    pub fingerprint_kind: Option<u8>,
+    /// Good samples an enrollment needs.
+    pub max_samples: Option<u32>,
+    /// Longest friendly name, in bytes.
+    pub max_name_length: Option<usize>,
+}
+
+/// Outcome of one enrollment sample.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BioEnrollSample {
+    /// Raw `lastEnrollSampleStatus`; see `BioSampleStatus`.
+    pub status: u8,
+    /// Good samples still needed; `0` once the template is complete.
+    pub remaining: u32,
+}
+
+/// Discoverable credential counts from `getCredsMetadata`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialsMetadata {
+    /// Discoverable credentials currently stored.
+    pub existing: u32,
+    /// How many more the key estimates it can store.
+    pub remaining: u32,
+}
+
+/// Remaining PIN attempts as reported by `getPinRetries`.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PinRetries {
+    /// Attempts left before the PIN is permanently blocked.
+    pub retries: u32,
+    /// `true` when the key must be unplugged before it accepts another PIN.
+    pub power_cycle_required: bool,
+}
+
+/// PIN and built-in user verification attempts left.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PinStatus {
+    /// `getPinRetries`.
+    pub pin: PinRetries,
+    /// `getUVRetries`; `None` when the key has no built-in UV (fingerprint).
+    pub uv_retries: Option<u32>,
+}
+
+/// Why the authenticator currently refuses PIN entry.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PinLockout {
+    /// `CTAP2_ERR_PIN_AUTH_BLOCKED` (0x34): too many wrong PINs since power-up.
+    /// Replugging the key restores entry; the retry counter is kept.
+    PowerCycleRequired,
+    /// `CTAP2_ERR_PIN_BLOCKED` (0x32): no retries left. Only a reset helps.
+    Blocked,
+}
+
+// ── USB descriptor types ────────────────────────────────────────────────────
+
+/// One HID interface of the connected key, as enumerated by the OS.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct UsbInterfaceInfo {
+    /// `bInterfaceNumber` (-1 when the platform does not report it).
+    pub interface_number: i32,
+    /// HID usage page from the report descriptor.
+    pub usage_page: u16,
+    /// HID usage from the report descriptor.
+    pub usage: u16,
+    /// Human-readable interface class derived from the usage page.
+    pub class: String,
+    /// OS device path of the interface.
+    pub path: String,
+    /// The FIDO interface PicoForge opens for CTAPHID.
+    pub in_use: bool,
+}
+
+/// One endpoint of a USB interface.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct UsbEndpointInfo {
+    /// `bEndpointAddress`; bit 7 set for IN endpoints.
+    pub address: u8,
+    /// `Control`, `Isochronous`, `Bulk` or `Interrupt`.
+    pub transfer_type: String,
+    /// `wMaxPacketSize`.
+    pub max_packet_size: u16,
+    /// `bInterval`.
+    pub interval: u8,
+}
+
+/// One interface of the key's active USB configuration.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct UsbInterfaceDescriptor {
+    /// `bInterfaceNumber`.
+    pub number: u8,
+    /// `bInterfaceClass`.
+    pub class: u8,
+    /// `bInterfaceSubClass`.
+    pub subclass: u8,
+    /// `bInterfaceProtocol`.
+    pub protocol: u8,
+    /// Human-readable name of the class, e.g. `HID` or `Smart Card (CCID)`.
+    pub class_name: String,
+    /// Endpoints of the default alternate setting.
+    pub endpoints: Vec<UsbEndpointInfo>,
+}
+
+/// USB descriptor strings and interfaces the OS currently sees for the key.
+///
+/// Gathered by HID enumeration and the cached USB descriptors, so it reflects
+/// what the host picked up after the last replug rather than what is stored
+/// in the device's phy config.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct UsbDescriptorInfo {
+    pub vid: u16,
+    pub pid: u16,
+    /// `bcdDevice` release number.
+    pub release_number: u16,
+    /// `iManufacturer` string.
+    pub manufacturer: Option<String>,
+    /// `iProduct` string as reported to the OS.
+    pub product: Option<String>,
+    /// `iSerial` string.
+    pub serial: Option<String>,
+    /// Bus the device is attached through (USB, Bluetooth, ...).
+    pub bus_type: String,
+    /// Every HID interface sharing the key's VID/PID/serial.
+    pub interfaces: Vec<UsbInterfaceInfo>,
+    /// `bcdUSB`. `None` when the USB device could not be matched, e.g. on a
+    /// Bluetooth link.
+    pub usb_version: Option<u16>,
+    /// Every interface of the active configuration, HID or not. Empty when
+    /// the USB device could not be matched.
+    pub usb_interfaces: Vec<UsbInterfaceDescriptor>,
+}
+
+// ── Capability types ────────────────────────────────────────────────────────
+
+/// Optional smart-card applets that answered a SELECT over PC/SC.
+#[derive(Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct AppletPresence {
+    pub openpgp: bool,
+    pub oath: bool,
+}
+
+// ── Startup probe types ─────────────────────────────────────────────────────
+
+/// Result of probing the HID stack at startup.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HidProbe {
+    /// Number of FIDO HID interfaces (usage page 0xF1D0) the OS lists.
+    pub fido_interfaces: usize,
+    /// One message per FIDO interface that is listed but cannot be opened.
+    pub inaccessible: Vec<String>,
+}
+
+/// A FIDO HID interface that could be managed, as enumerated by hidapi.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceDescriptor {
+    /// Platform hidapi path; identifies the interface while it stays plugged in.
+    pub path: String,
+    pub vid: u16,
+    pub pid: u16,
+    pub product: String,
+    pub serial: Option<String>,
+}
+
+// ── Transport health types ──────────────────────────────────────────────────
+
+/// CTAPHID exchanges and their failures since the tally was last taken.
+///
+/// Error categories are `transport` (HID read/write failures and malformed
+/// frames), `timeout`, `sequence` (continuation packet out of order),
+/// `ctaphid.0xNN` (CTAPHID_ERROR codes) and `ctap.0xNN` (CTAP2 status bytes).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TransportTally {
+    /// Requests written to the device.
+    pub exchanges: u64,
+    /// Failures per category.
+    pub errors: std::collections::BTreeMap<String, u64>,
+}
+
+impl TransportTally {
+    /// Whether nothing was sent since the last tally.
+    pub fn is_empty(&self) -> bool {
+        self.exchanges == 0 && self.errors.is_empty()
+    }
+}
+
+/// Outcome of a CTAPHID_PING self-test: small pings for latency, then
+/// large ones for throughput.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PingSelfTest {
+    /// Round-trip time of each small ping, in order.
+    pub latencies: Vec<Duration>,
+    /// Payload size of each throughput ping, in bytes.
+    pub throughput_payload: usize,
+    /// Payload bytes sent and echoed back during the throughput pings.
+    pub throughput_bytes: u64,
+    /// HID reports written and read during the throughput pings.
+    pub throughput_packets: u64,
+    /// Wall time of the throughput pings.
+    pub throughput_elapsed: Duration,
+}
+
+impl PingSelfTest {
+    /// Fastest, mean and slowest latency round trip.
+    pub fn latency_range(&self) -> Option<(Duration, Duration, Duration)> {
+        let min = *self.latencies.iter().min()?;
+        let max = *self.latencies.iter().max()?;
+        let mean = self.latencies.iter().sum::<Duration>() / self.latencies.len() as u32;
+        Some((min, mean, max))
+    }
+
+    /// HID reports moved per second while measuring throughput.
+    pub fn packets_per_second(&self) -> f64 {
+        let secs = self.throughput_elapsed.as_secs_f64();
+        if secs == 0.0 {
+            return 0.0;
+        }
+        self.throughput_packets as f64 / secs
+    }
+
+    /// Payload bytes moved per second while measuring throughput.
+    pub fn bytes_per_second(&self) -> f64 {
+        let secs = self.throughput_elapsed.as_secs_f64();
+        if secs == 0.0 {
+            return 0.0;
+        }
+        self.throughput_bytes as f64 / secs
+    }
+
+    /// One line for the UI and the log.
+    pub fn summary(&self) -> String {
+        let latency = match self.latency_range() {
+            Some((min, mean, max)) => format!(
+                "Round trip {:.1} / {:.1} / {:.1} ms (min / avg / max over {} pings)",
+                min.as_secs_f64() * 1000.0,
+                mean.as_secs_f64() * 1000.0,
+                max.as_secs_f64() * 1000.0,
+                self.latencies.len()
+            ),
+            None => "No latency pings".to_string(),
+        };
+        format!(
+            "{}. {:.0} packets/s, {:.1} KiB/s with {}-byte payloads.",
+            latency,
+            self.packets_per_second(),
+            self.bytes_per_second() / 1024.0,
+            self.throughput_payload
+        )
+    }
+}
+
+/// What the key reports in CTAPHID_KEEPALIVE while a request is pending
+/// (CTAP2 §11.2.9.2.1).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeepaliveStatus {
+    /// `STATUS_PROCESSING` (0x01): still working.
+    Processing,
+    /// `STATUS_UPNEEDED` (0x02): waiting for a touch.
+    UpNeeded,
+    /// A status byte the spec does not define.
+    Other(u8),
+}
+
+impl From<u8> for KeepaliveStatus {
+    fn from(status: u8) -> Self {
+        match status {
+            0x01 => Self::Processing,
+            0x02 => Self::UpNeeded,
+            other => Self::Other(other),
+        }
+    }
 }
 
 // ── Constants ───────────────────────────────────────────────────────────────
@@ -0,0 +1,354 @@
+//! Relying-party impact of a configuration change.
+//!
+//! Answers "will my existing passkeys keep working?" before a write, with a
+//! fixed set of rules:
+//!
+//! - A new USB VID/PID or product name is invisible to WebAuthn. Browsers
+//!   talk CTAP over any FIDO HID interface, so passkeys keep working. Only
+//!   OS-level rules that match on the USB identity (udev, MDM allow-lists)
+//!   need updating.
+//! - A new AAGUID changes what attestation reports. Relying parties that
+//!   check attestation against the FIDO MDS or an allow-list may reject new
+//!   registrations, and some re-check it on sign-in.
+//! - Some VID/PIDs belong to hardware an OS driver claims by ID (USB serial
+//!   adapters, smart-card readers, the RP2040 bootrom). A key using one may
+//!   vanish behind that driver until it is fixed from rescue mode, so those
+//!   identities are flagged as breaking.
+//! - Disabling a curve stops the key from signing with it. Credentials whose
+//!   public key uses that curve can no longer sign in.
+//!
+//! Credentials are only known after the Passkeys screen has been unlocked;
+//! without them a curve rule reports that it could not tell.
+
+use crate::hal::types::{RescueCurves, StoredCredential};
+
+/// How bad an impact is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Nothing the user needs to do for passkeys to keep working.
+    Info,
+    /// Some relying parties or setups may be affected.
+    Warning,
+    /// Existing credentials stop working.
+    Breaking,
+}
+
+impl Severity {
+    /// Short label used in the confirmation text.
+    pub fn label(self) -> &'static str {
+        match self {
+            Severity::Info => "No effect",
+            Severity::Warning => "Check",
+            Severity::Breaking => "Breaks",
+        }
+    }
+}
+
+/// One finding of [`analyze`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Impact {
+    /// How bad it is.
+    pub severity: Severity,
+    /// One-line summary.
+    pub title: String,
+    /// Who is affected and why.
+    pub detail: String,
+}
+
+/// The parts of a pending write that matter to relying parties.
+#[derive(Debug, Clone, Default)]
+pub struct ConfigDiff {
+    /// `(old, new)` VID:PID when it changes.
+    pub vid_pid: Option<(String, String)>,
+    /// The USB product name changes.
+    pub product_name_changed: bool,
+    /// `(old, new)` AAGUID when it changes.
+    pub aaguid: Option<(String, String)>,
+    /// Curves enabled now that the write turns off.
+    pub disabled_curves: u32,
+}
+
+impl ConfigDiff {
+    /// Curves turned off between two `ENABLED_CURVES` masks.
+    pub fn curves_disabled_between(before: u32, after: u32) -> u32 {
+        before & !after
+    }
+}
+
+/// A USB identity that an operating system driver claims by VID/PID.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DriverConflict {
+    /// USB vendor ID.
+    pub vid: u16,
+    /// USB product ID, or `None` when the driver claims the whole vendor.
+    pub pid: Option<u16>,
+    /// What the OS takes the key for.
+    pub reason: &'static str,
+}
+
+/// VID/PIDs known to be captured by an OS driver.
+const DRIVER_CONFLICTS: &[DriverConflict] = &[
+    DriverConflict {
+        vid: 0x0403,
+        pid: Some(0x6001),
+        reason: "FTDI FT232 serial adapter; ftdi_sio and the FTDI VCP driver claim it",
+    },
+    DriverConflict {
+        vid: 0x067B,
+        pid: Some(0x2303),
+        reason: "Prolific PL2303 serial adapter; the pl2303 driver claims it",
+    },
+    DriverConflict {
+        vid: 0x10C4,
+        pid: Some(0xEA60),
+        reason: "Silicon Labs CP210x serial adapter; the cp210x driver claims it",
+    },
+    DriverConflict {
+        vid: 0x046D,
+        pid: Some(0xC52B),
+        reason: "Logitech Unifying receiver; hid-logitech-dj takes over its HID interfaces",
+    },
+    DriverConflict {
+        vid: 0x076B,
+        pid: Some(0x3021),
+        reason: "OMNIKEY CardMan 3121 reader; vendor CCID drivers bind to it on Windows",
+    },
+    DriverConflict {
+        vid: 0x08E6,
+        pid: Some(0x3437),
+        reason: "Gemalto PC Twin reader; vendor CCID drivers bind to it on Windows",
+    },
+    DriverConflict {
+        vid: 0x2E8A,
+        pid: Some(0x0003),
+        reason: "RP2040 bootrom (BOOTSEL); picotool and udev rules treat the key as the \
+                 bootloader",
+    },
+    DriverConflict {
+        vid: 0x1D6B,
+        pid: None,
+        reason: "Linux Foundation IDs for root hubs and gadget drivers",
+    },
+    DriverConflict {
+        vid: 0x0000,
+        pid: None,
+        reason: "Vendor ID 0000 is reserved; Windows reports the key as an unknown device",
+    },
+];
+
+/// The known driver conflict for a `VID`, `PID` pair of hex strings, if any.
+/// Unparsable input never conflicts.
+pub fn driver_conflict(vid: &str, pid: &str) -> Option<&'static DriverConflict> {
+    let vid = u16::from_str_radix(vid.trim(), 16).ok()?;
+    let pid = u16::from_str_radix(pid.trim(), 16).ok()?;
+    DRIVER_CONFLICTS
+        .iter()
+        .find(|c| c.vid == vid && c.pid.is_none_or(|p| p == pid))
+}
+
+/// COSE algorithms that sign with a curve, with the curve's display name.
+const CURVE_ALGORITHMS: &[(u32, &str, &[i32])] = &[
+    (RescueCurves::SECP256R1.bits(), "P-256", &[-7, -9]),
+    (RescueCurves::SECP384R1.bits(), "P-384", &[-35, -51]),
+    (RescueCurves::SECP521R1.bits(), "P-521", &[-36, -52]),
+    (RescueCurves::SECP256K1.bits(), "secp256k1", &[-47]),
+    (RescueCurves::ED25519.bits(), "Ed25519", &[-8, -19]),
+    (RescueCurves::ED448.bits(), "Ed448", &[-53]),
+];
+
+/// Run every rule against `diff`. `credentials` is the list from the
+/// Passkeys screen, if it has been read. Results are sorted worst first.
+pub fn analyze(diff: &ConfigDiff, credentials: Option<&[StoredCredential]>) -> Vec<Impact> {
+    let mut impacts = Vec::new();
+
+    if let Some((old, new)) = &diff.vid_pid {
+        impacts.push(Impact {
+            severity: Severity::Info,
+            title: format!("USB identity {} → {}", old, new),
+            detail: "Browsers find the key by its FIDO interface, not its VID/PID, so \
+                     existing passkeys keep working. Update udev rules or device \
+                     allow-lists that match on the old identity."
+                .into(),
+        });
+        if let Some((vid, pid)) = new.split_once(':')
+            && let Some(conflict) = driver_conflict(vid, pid)
+        {
+            impacts.push(Impact {
+                severity: Severity::Breaking,
+                title: format!("{} is claimed by an OS driver", new),
+                detail: format!(
+                    "{}. The key may stop showing up as a security key until its identity \
+                     is restored from rescue mode.",
+                    conflict.reason
+                ),
+            });
+        }
+    }
+    if diff.product_name_changed {
+        impacts.push(Impact {
+            severity: Severity::Info,
+            title: "USB product name".into(),
+            detail: "Only shown by the operating system; relying parties never see it.".into(),
+        });
+    }
+    if let Some((old, new)) = &diff.aaguid {
+        impacts.push(Impact {
+            severity: Severity::Warning,
+            title: format!("AAGUID {} → {}", old, new),
+            detail: "Relying parties that check attestation (enterprise sign-in, FIDO MDS \
+                     allow-lists) may reject new registrations or flag the key on sign-in. \
+                     Sites that ignore attestation are unaffected."
+                .into(),
+        });
+    }
+
+    for (curve, name, algorithms) in CURVE_ALGORITHMS {
+        if diff.disabled_curves & curve == 0 {
+            continue;
+        }
+        let impact = match credentials {
+            None => Impact {
+                severity: Severity::Warning,
+                title: format!("Disabling {}", name),
+                detail: format!(
+                    "Passkeys using {} will stop working. Unlock the Passkeys screen first \
+                     to see which ones are affected.",
+                    name
+                ),
+            },
+            Some(credentials) => {
+                let affected: Vec<String> = credentials
+                    .iter()
+                    .filter(|c| c.algorithm.is_some_and(|alg| algorithms.contains(&alg)))
+                    .map(|c| format!("{} ({})", c.rp_id, c.user_name))
+                    .collect();
+                if affected.is_empty() {
+                    Impact {
+                        severity: Severity::Info,
+                        title: format!("Disabling {}", name),
+                        detail: format!("No stored passkey uses {}.", name),
+                    }
+                } else {
+                    Impact {
+                        severity: Severity::Breaking,
+                        title: format!(
+                            "Disabling {} breaks {} passkey{}",
+                            name,
+                            affected.len(),
+                            if affected.len() == 1 { "" } else { "s" }
+                        ),
+                        detail: affected.join(", "),
+                    }
+                }
+            }
+        };
+        impacts.push(impact);
+    }
+
+    impacts.sort_by(|a, b| b.severity.cmp(&a.severity));
+    impacts
+}
+
+/// Render impacts as the plain-text body of a confirmation dialog.
+pub fn summary(impacts: &[Impact]) -> String {
+    let mut lines: Vec<String> = impacts
+        .iter()
+        .map(|i| format!("[{}] {}: {}", i.severity.label(), i.title, i.detail))
+        .collect();
+    let worst = impacts.iter().map(|i| i.severity).max();
+    lines.push(String::new());
+    lines.push(
+        match worst {
+            Some(Severity::Breaking) => "Some existing passkeys will stop working.",
+            Some(Severity::Warning) => "Existing passkeys may be affected.",
+            _ => "Existing passkeys will keep working.",
+        }
+        .into(),
+    );
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn credential(rp_id: &str, algorithm: i32) -> StoredCredential {
+        StoredCredential {
+            rp_id: rp_id.into(),
+            rp_name: rp_id.into(),
+            user_name: "alice".into(),
+            user_display_name: String::new(),
+            user_id: String::new(),
+            credential_id: "00".into(),
+            algorithm: Some(algorithm),
+            key_type: None,
+            curve: None,
+            cred_protect: None,
+            public_key: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_vid_pid_change_is_harmless() {
+        let diff = ConfigDiff {
+            vid_pid: Some(("2E8A:10FE".into(), "1D50:619B".into())),
+            ..Default::default()
+        };
+        let impacts = analyze(&diff, None);
+        assert_eq!(impacts.len(), 1);
+        assert_eq!(impacts[0].severity, Severity::Info);
+        assert!(summary(&impacts).ends_with("will keep working."));
+    }
+
+    #[test]
+    fn test_driver_conflict_lookup() {
+        assert!(driver_conflict("0403", "6001").is_some());
+        assert!(driver_conflict("1d6b", "0104").is_some());
+        assert!(driver_conflict("2E8A", "10FE").is_none());
+        assert!(driver_conflict("zz", "6001").is_none());
+    }
+
+    #[test]
+    fn test_conflicting_vid_pid_is_breaking() {
+        let diff = ConfigDiff {
+            vid_pid: Some(("2E8A:10FE".into(), "10C4:EA60".into())),
+            ..Default::default()
+        };
+        let impacts = analyze(&diff, None);
+        assert_eq!(impacts.len(), 2);
+        assert_eq!(impacts[0].severity, Severity::Breaking);
+        assert!(impacts[0].detail.contains("cp210x"));
+    }
+
+    #[test]
+    fn test_disabling_secp256k1_lists_es256k_credentials() {
+        let diff = ConfigDiff {
+            disabled_curves: ConfigDiff::curves_disabled_between(0x09, 0x01),
+            ..Default::default()
+        };
+        let credentials = [
+            credential("wallet.example", -47),
+            credential("example.com", -7),
+        ];
+
+        let impacts = analyze(&diff, Some(&credentials));
+        assert_eq!(impacts.len(), 1);
+        assert_eq!(impacts[0].severity, Severity::Breaking);
+        assert_eq!(impacts[0].detail, "wallet.example (alice)");
+
+        let unknown = analyze(&diff, None);
+        assert_eq!(unknown[0].severity, Severity::Warning);
+    }
+
+    #[test]
+    fn test_impacts_sorted_worst_first() {
+        let diff = ConfigDiff {
+            product_name_changed: true,
+            aaguid: Some(("a".into(), "b".into())),
+            ..Default::default()
+        };
+        let impacts = analyze(&diff, Some(&[]));
+        assert_eq!(impacts[0].severity, Severity::Warning);
+        assert_eq!(impacts[1].severity, Severity::Info);
+    }
+}
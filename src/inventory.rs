@@ -0,0 +1,820 @@
+//! Device inventory: the USB identity each key was last configured with.
+//!
+//! Stored as JSON in the platform data directory (`inventory.json`), keyed by
+//! serial number. A record is written whenever PicoForge successfully writes a
+//! VID/PID or product name, so it reflects what the user chose rather than
+//! whatever the device happens to report.
+//!
+//! Firmware updates can reset the USB identity to the build's defaults, after
+//! which password managers and udev rules keyed on the old VID/PID stop
+//! recognising the key. [`identity_drift`] compares a freshly read device
+//! against its record so the UI can offer to re-apply the previous identity.
+//!
+//! The same file holds the user's notes and tags on passkeys, keyed by
+//! credential ID, and device nicknames keyed by serial. Both are local
+//! bookkeeping only and never written to a key.
+//!
+//! Each device also gets a short timeline of what PicoForge saw happen to it:
+//! configuration writes, PIN changes, firmware changes noticed on connect and
+//! the passkey count whenever the Passkeys screen is unlocked.
+//!
+//! Alongside the timeline, daily counts of CTAPHID exchanges and their
+//! failures (see [`TransportTally`]) are kept for [`HEALTH_DAYS`] days, so a
+//! key or cable that keeps dropping packets stands out:
+//! [`elevated_error_hint`] reports when hardware-level failures exceed
+//! [`ELEVATED_ERROR_PERCENT`] of recent exchanges.
+//!
+//! The enterprise attestation certificate last read back from each key is
+//! kept too, so the certificates handed out across a fleet can be audited
+//! from one place.
+//!
+//! Keys with quirky firmware can have [`DeviceOverrides`] (transport order,
+//! timeouts, skipped vendor reads) stored by serial; the device model applies
+//! them whenever that key is connected.
+
+use crate::hal::types::{AttestationCertInfo, FullDeviceStatus, TransportTally};
+use crate::settings;
+use crate::tunables::DeviceOverrides;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Last configured identity of one device.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceRecord {
+    /// USB vendor ID (hex, as entered).
+    pub vid: String,
+    /// USB product ID (hex, as entered).
+    pub pid: String,
+    /// USB product name, if it was part of the configuration.
+    pub product_name: Option<String>,
+    /// Firmware version at the time of the write.
+    pub firmware_version: String,
+    /// When the identity was written, in UNIX seconds.
+    pub configured_at: u64,
+}
+
+/// Local note and tags attached to one passkey.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct CredentialNote {
+    /// Free-form note, e.g. "work account".
+    pub text: String,
+    /// Short labels, e.g. "recovery key".
+    pub tags: Vec<String>,
+    /// When the note was last edited, in UNIX seconds.
+    pub updated_at: u64,
+}
+
+impl CredentialNote {
+    /// Whether the note or one of the tags contains `query` (already lowercase).
+    pub fn matches(&self, query: &str) -> bool {
+        self.text.to_lowercase().contains(query)
+            || self.tags.iter().any(|t| t.to_lowercase().contains(query))
+    }
+}
+
+/// Most timeline entries kept per device; older ones are dropped.
+pub const MAX_TIMELINE_ENTRIES: usize = 50;
+
+/// Something that happened to a device.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum TimelineEvent {
+    /// First time PicoForge saw the device.
+    FirstSeen {
+        /// Firmware version at the time.
+        firmware: String,
+    },
+    /// The device came back with a different firmware version.
+    FirmwareChanged {
+        /// Version last seen.
+        from: String,
+        /// Version now.
+        to: String,
+    },
+    /// A configuration was written.
+    ConfigApplied,
+    /// The PIN was set or changed.
+    PinChanged,
+    /// Number of passkeys read from the device, recorded when it changed.
+    Credentials {
+        /// Discoverable credentials stored.
+        count: usize,
+    },
+}
+
+/// A [`TimelineEvent`] and when it happened.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TimelineEntry {
+    /// When, in UNIX seconds.
+    pub at: u64,
+    /// What happened.
+    #[serde(flatten)]
+    pub event: TimelineEvent,
+}
+
+impl TimelineEntry {
+    /// One-line description. `previous_count` is the passkey count of the
+    /// entry before, used to show the trend.
+    pub fn describe(&self, previous_count: Option<usize>) -> String {
+        match &self.event {
+            TimelineEvent::FirstSeen { firmware } => {
+                format!("First connected with firmware {}", firmware)
+            }
+            TimelineEvent::FirmwareChanged { from, to } => {
+                format!("Firmware changed from {} to {}", from, to)
+            }
+            TimelineEvent::ConfigApplied => "Configuration applied".to_string(),
+            TimelineEvent::PinChanged => "PIN changed".to_string(),
+            TimelineEvent::Credentials { count } => match previous_count {
+                Some(prev) if prev != *count => format!(
+                    "{} passkeys stored ({:+})",
+                    count,
+                    *count as i64 - prev as i64
+                ),
+                _ => format!("{} passkeys stored", count),
+            },
+        }
+    }
+}
+
+/// Days of transport error counts kept per device.
+pub const HEALTH_DAYS: u64 = 30;
+
+/// Fewest exchanges in the window before an error rate is judged.
+pub const MIN_HEALTH_EXCHANGES: u64 = 100;
+
+/// Share of exchanges (in percent) failing at the transport level above which
+/// a key is flagged.
+pub const ELEVATED_ERROR_PERCENT: u64 = 2;
+
+/// Error categories that point at the key, cable or port rather than at the
+/// request. CTAP status codes (wrong PIN, no credentials, ...) are excluded.
+fn is_hardware_error(category: &str) -> bool {
+    matches!(category, "transport" | "timeout" | "sequence") || category.starts_with("ctaphid.")
+}
+
+/// Transport counts of one device for one UTC day.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct HealthDay {
+    /// Days since the UNIX epoch.
+    pub day: u64,
+    /// Requests written to the device.
+    pub exchanges: u64,
+    /// Failures per category, as in [`TransportTally::errors`].
+    pub errors: BTreeMap<String, u64>,
+}
+
+/// Enterprise attestation certificate read back from one device.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AttestationCertRecord {
+    /// The certificate, or `None` when the device returned none.
+    pub certificate: Option<AttestationCertInfo>,
+    /// When it was read, in UNIX seconds.
+    pub read_at: u64,
+}
+
+/// All known devices, keyed by serial.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct Inventory {
+    /// Records by device serial.
+    pub devices: BTreeMap<String, DeviceRecord>,
+    /// Passkey notes by credential ID (hex).
+    #[serde(rename = "credentialNotes")]
+    pub credential_notes: BTreeMap<String, CredentialNote>,
+    /// Friendly device names by serial, e.g. "Blue key on keyring".
+    pub nicknames: BTreeMap<String, String>,
+    /// Timeline by serial, oldest entry first.
+    pub timelines: BTreeMap<String, Vec<TimelineEntry>>,
+    /// Daily transport counts by serial, oldest day first.
+    #[serde(rename = "deviceHealth")]
+    pub device_health: BTreeMap<String, Vec<HealthDay>>,
+    /// Enterprise attestation certificates by serial, as last read back.
+    #[serde(rename = "attestationCerts")]
+    pub attestation_certs: BTreeMap<String, AttestationCertRecord>,
+    /// Connection overrides by serial.
+    #[serde(rename = "deviceOverrides")]
+    pub device_overrides: BTreeMap<String, DeviceOverrides>,
+}
+
+/// Difference between a device's record and what it reports now.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IdentityDrift {
+    /// Serial of the affected device.
+    pub serial: String,
+    /// The identity the user configured.
+    pub expected: DeviceRecord,
+    /// VID/PID the device reports now.
+    pub found_vid_pid: String,
+    /// Product name the device reports now.
+    pub found_product_name: String,
+    /// Firmware version now, when it differs from the one in the record.
+    pub firmware_changed_to: Option<String>,
+}
+
+fn inventory_path() -> Option<PathBuf> {
+    settings::project_dirs().map(|dirs| dirs.data_local_dir().join("inventory.json"))
+}
+
+/// Load the inventory. A missing or unreadable file yields an empty one.
+pub fn load() -> Inventory {
+    let Some(path) = inventory_path() else {
+        return Inventory::default();
+    };
+    match fs::read_to_string(&path) {
+        Ok(text) => serde_json::from_str(&text).unwrap_or_else(|e| {
+            log::warn!("Ignoring unreadable inventory {:?}: {}", path, e);
+            Inventory::default()
+        }),
+        Err(_) => Inventory::default(),
+    }
+}
+
+/// Write the inventory to disk, creating the data directory if needed.
+pub fn save(inventory: &Inventory) -> Result<(), String> {
+    let path = inventory_path().ok_or("Could not determine the data directory")?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Could not create {}: {}", parent.display(), e))?;
+    }
+    let text = serde_json::to_string_pretty(inventory).map_err(|e| e.to_string())?;
+    fs::write(&path, text).map_err(|e| format!("Could not write {}: {}", path.display(), e))
+}
+
+/// Record the identity just written to `serial` and persist it.
+///
+/// `vid`/`pid`/`product_name` are the values written; `None` keeps the value
+/// already on record (or the one the device reported before the write).
+pub fn record_identity(
+    before: &FullDeviceStatus,
+    vid: Option<&str>,
+    pid: Option<&str>,
+    product_name: Option<&str>,
+) -> Result<(), String> {
+    let mut inventory = load();
+    let previous = inventory.devices.get(&before.info.serial);
+
+    let record = DeviceRecord {
+        vid: vid
+            .map(str::to_string)
+            .or_else(|| previous.map(|r| r.vid.clone()))
+            .unwrap_or_else(|| before.config.vid.clone()),
+        pid: pid
+            .map(str::to_string)
+            .or_else(|| previous.map(|r| r.pid.clone()))
+            .unwrap_or_else(|| before.config.pid.clone()),
+        product_name: product_name
+            .map(str::to_string)
+            .or_else(|| previous.and_then(|r| r.product_name.clone())),
+        firmware_version: before.info.firmware_version.clone(),
+        configured_at: now(),
+    };
+
+    inventory.devices.insert(before.info.serial.clone(), record);
+    save(&inventory)
+}
+
+/// Split a comma-separated tag list, dropping blanks and duplicates.
+pub fn parse_tags(input: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in input.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+        if !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            tags.push(tag.to_string());
+        }
+    }
+    tags
+}
+
+/// Store the note and tags for `credential_id` and persist them. An empty
+/// note with no tags removes the entry.
+pub fn set_credential_note(credential_id: &str, text: &str, tags: &str) -> Result<(), String> {
+    let mut inventory = load();
+    let note = CredentialNote {
+        text: text.trim().to_string(),
+        tags: parse_tags(tags),
+        updated_at: now(),
+    };
+    if note.text.is_empty() && note.tags.is_empty() {
+        inventory.credential_notes.remove(credential_id);
+    } else {
+        inventory
+            .credential_notes
+            .insert(credential_id.to_string(), note);
+    }
+    save(&inventory)
+}
+
+/// Longest nickname accepted, in characters.
+pub const MAX_NICKNAME_CHARS: usize = 48;
+
+/// Set the nickname of the device with `serial` and persist it. A blank name
+/// removes it.
+pub fn set_nickname(serial: &str, name: &str) -> Result<(), String> {
+    let name = name.trim();
+    if name.chars().count() > MAX_NICKNAME_CHARS {
+        return Err(format!(
+            "Nickname must be at most {} characters",
+            MAX_NICKNAME_CHARS
+        ));
+    }
+    let mut inventory = load();
+    if name.is_empty() {
+        inventory.nicknames.remove(serial);
+    } else {
+        inventory
+            .nicknames
+            .insert(serial.to_string(), name.to_string());
+    }
+    save(&inventory)
+}
+
+/// Store the connection overrides of `serial` and persist them. Empty
+/// overrides remove the entry.
+pub fn set_device_overrides(serial: &str, overrides: DeviceOverrides) -> Result<(), String> {
+    overrides.validate()?;
+    let mut inventory = load();
+    if overrides.is_empty() {
+        inventory.device_overrides.remove(serial);
+    } else {
+        inventory
+            .device_overrides
+            .insert(serial.to_string(), overrides);
+    }
+    save(&inventory)
+}
+
+/// Append `event` to the timeline of `serial`, dropping the oldest entries
+/// beyond [`MAX_TIMELINE_ENTRIES`].
+pub fn push_event(inventory: &mut Inventory, serial: &str, event: TimelineEvent, at: u64) {
+    let timeline = inventory.timelines.entry(serial.to_string()).or_default();
+    timeline.push(TimelineEntry { at, event });
+    let excess = timeline.len().saturating_sub(MAX_TIMELINE_ENTRIES);
+    timeline.drain(..excess);
+}
+
+/// Record `event` for `serial` and persist it.
+pub fn record_event(serial: &str, event: TimelineEvent) -> Result<(), String> {
+    let mut inventory = load();
+    push_event(&mut inventory, serial, event, now());
+    save(&inventory)
+}
+
+/// What changed since the last time `serial` was seen with `firmware`: a
+/// [`TimelineEvent::FirstSeen`] for a new device, a
+/// [`TimelineEvent::FirmwareChanged`] after an update, otherwise `None`.
+pub fn firmware_event(
+    inventory: &Inventory,
+    serial: &str,
+    firmware: &str,
+) -> Option<TimelineEvent> {
+    let last = inventory.timelines.get(serial).and_then(|timeline| {
+        timeline.iter().rev().find_map(|entry| match &entry.event {
+            TimelineEvent::FirstSeen { firmware } => Some(firmware),
+            TimelineEvent::FirmwareChanged { to, .. } => Some(to),
+            _ => None,
+        })
+    });
+    match last {
+        None => Some(TimelineEvent::FirstSeen {
+            firmware: firmware.to_string(),
+        }),
+        Some(last) if last != firmware => Some(TimelineEvent::FirmwareChanged {
+            from: last.clone(),
+            to: firmware.to_string(),
+        }),
+        Some(_) => None,
+    }
+}
+
+/// Record the firmware `serial` was connected with, if it is new or changed.
+pub fn observe_firmware(serial: &str, firmware: &str) -> Result<(), String> {
+    let mut inventory = load();
+    let Some(event) = firmware_event(&inventory, serial, firmware) else {
+        return Ok(());
+    };
+    push_event(&mut inventory, serial, event, now());
+    save(&inventory)
+}
+
+/// Record the passkey count of `serial` unless it equals the last one recorded.
+pub fn observe_credential_count(serial: &str, count: usize) -> Result<(), String> {
+    let mut inventory = load();
+    let last = inventory.timelines.get(serial).and_then(|timeline| {
+        timeline.iter().rev().find_map(|entry| match entry.event {
+            TimelineEvent::Credentials { count } => Some(count),
+            _ => None,
+        })
+    });
+    if last == Some(count) {
+        return Ok(());
+    }
+    push_event(
+        &mut inventory,
+        serial,
+        TimelineEvent::Credentials { count },
+        now(),
+    );
+    save(&inventory)
+}
+
+/// The most recent `limit` entries of `serial`, newest first, with their
+/// descriptions.
+pub fn recent_events(inventory: &Inventory, serial: &str, limit: usize) -> Vec<(u64, String)> {
+    let Some(timeline) = inventory.timelines.get(serial) else {
+        return Vec::new();
+    };
+    let mut previous_count = None;
+    let mut described: Vec<(u64, String)> = timeline
+        .iter()
+        .map(|entry| {
+            let text = entry.describe(previous_count);
+            if let TimelineEvent::Credentials { count } = entry.event {
+                previous_count = Some(count);
+            }
+            (entry.at, text)
+        })
+        .collect();
+    described.reverse();
+    described.truncate(limit);
+    described
+}
+
+/// Record the enterprise attestation certificate just read from `serial`
+/// (`None` when it returned none) and persist it.
+pub fn record_attestation_cert(
+    serial: &str,
+    certificate: Option<&AttestationCertInfo>,
+) -> Result<(), String> {
+    let mut inventory = load();
+    inventory.attestation_certs.insert(
+        serial.to_string(),
+        AttestationCertRecord {
+            certificate: certificate.cloned(),
+            read_at: now(),
+        },
+    );
+    save(&inventory)
+}
+
+/// Add `tally` to the day containing `at` for `serial`, dropping days older
+/// than [`HEALTH_DAYS`].
+pub fn add_health(inventory: &mut Inventory, serial: &str, tally: &TransportTally, at: u64) {
+    let today = at / 86_400;
+    let days = inventory
+        .device_health
+        .entry(serial.to_string())
+        .or_default();
+    if days.last().is_none_or(|d| d.day != today) {
+        days.push(HealthDay {
+            day: today,
+            ..Default::default()
+        });
+    }
+    if let Some(day) = days.last_mut() {
+        day.exchanges += tally.exchanges;
+        for (category, count) in &tally.errors {
+            *day.errors.entry(category.clone()).or_default() += count;
+        }
+    }
+    days.retain(|d| d.day + HEALTH_DAYS > today);
+}
+
+/// Add `tally` to the health counts of `serial` and persist them. An empty
+/// tally is not written.
+pub fn record_health(serial: &str, tally: &TransportTally) -> Result<(), String> {
+    if tally.is_empty() {
+        return Ok(());
+    }
+    let mut inventory = load();
+    add_health(&mut inventory, serial, tally, now());
+    save(&inventory)
+}
+
+/// A warning for `serial` when hardware-level failures over the last
+/// [`HEALTH_DAYS`] days (as of `at`) exceed [`ELEVATED_ERROR_PERCENT`] of at
+/// least [`MIN_HEALTH_EXCHANGES`] exchanges.
+pub fn elevated_error_hint(inventory: &Inventory, serial: &str, at: u64) -> Option<String> {
+    let today = at / 86_400;
+    let mut exchanges = 0;
+    let mut by_category: BTreeMap<&str, u64> = BTreeMap::new();
+    for day in inventory.device_health.get(serial)? {
+        if day.day + HEALTH_DAYS <= today {
+            continue;
+        }
+        exchanges += day.exchanges;
+        for (category, count) in &day.errors {
+            if is_hardware_error(category) {
+                *by_category.entry(category).or_default() += count;
+            }
+        }
+    }
+    let errors: u64 = by_category.values().sum();
+    if exchanges < MIN_HEALTH_EXCHANGES || errors * 100 <= exchanges * ELEVATED_ERROR_PERCENT {
+        return None;
+    }
+    let breakdown = by_category
+        .iter()
+        .map(|(category, count)| format!("{} {}", count, category))
+        .collect::<Vec<_>>()
+        .join(", ");
+    Some(format!(
+        "This key has elevated error rates: {} of {} exchanges failed in the last {} days ({}). \
+         Try another cable or USB port; if it persists, the key may be failing.",
+        errors, exchanges, HEALTH_DAYS, breakdown
+    ))
+}
+
+/// [`elevated_error_hint`] for `serial` as of now, from the stored inventory.
+pub fn health_hint(serial: &str) -> Option<String> {
+    elevated_error_hint(&load(), serial, now())
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Compare `status` against its inventory record. `None` when the device is
+/// unknown or still has the recorded identity.
+pub fn identity_drift(inventory: &Inventory, status: &FullDeviceStatus) -> Option<IdentityDrift> {
+    let record = inventory.devices.get(&status.info.serial)?;
+    let config = &status.config;
+
+    let vid_pid_matches = record.vid.eq_ignore_ascii_case(&config.vid)
+        && record.pid.eq_ignore_ascii_case(&config.pid);
+    // An empty product name means the channel could not read it back.
+    let name_matches = config.product_name.is_empty()
+        || record
+            .product_name
+            .as_ref()
+            .is_none_or(|name| *name == config.product_name);
+    if vid_pid_matches && name_matches {
+        return None;
+    }
+
+    Some(IdentityDrift {
+        serial: status.info.serial.clone(),
+        expected: record.clone(),
+        found_vid_pid: format!("{}:{}", config.vid, config.pid).to_uppercase(),
+        found_product_name: config.product_name.clone(),
+        firmware_changed_to: (record.firmware_version != status.info.firmware_version)
+            .then(|| status.info.firmware_version.clone()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hal::types::{AppConfig, DeviceInfo, DeviceMethod, FirmwareType};
+
+    fn status(vid: &str, pid: &str, version: &str) -> FullDeviceStatus {
+        FullDeviceStatus {
+            info: DeviceInfo {
+                serial: "ABC123".into(),
+                flash_used: None,
+                flash_total: None,
+                firmware_version: version.into(),
+            },
+            config: AppConfig {
+                vid: vid.into(),
+                pid: pid.into(),
+                product_name: "My Key".into(),
+                ..Default::default()
+            },
+            secure_boot: false,
+            secure_boot_locked: false,
+            secure_lock: false,
+            method: DeviceMethod::Fido,
+            firmware_type: FirmwareType::PicoFido,
+        }
+    }
+
+    fn inventory_with(vid: &str, pid: &str, version: &str) -> Inventory {
+        let mut inventory = Inventory::default();
+        inventory.devices.insert(
+            "ABC123".into(),
+            DeviceRecord {
+                vid: vid.into(),
+                pid: pid.into(),
+                product_name: Some("My Key".into()),
+                firmware_version: version.into(),
+                configured_at: 0,
+            },
+        );
+        inventory
+    }
+
+    #[test]
+    fn test_no_drift_when_identity_matches() {
+        let inventory = inventory_with("1d50", "619b", "7.2");
+        assert_eq!(
+            identity_drift(&inventory, &status("1D50", "619B", "7.2")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_no_drift_for_unknown_device() {
+        assert_eq!(
+            identity_drift(&Inventory::default(), &status("FEFF", "FCFD", "7.4")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_drift_after_firmware_update() {
+        let inventory = inventory_with("1D50", "619B", "7.2");
+        let drift = identity_drift(&inventory, &status("FEFF", "FCFD", "7.4")).unwrap();
+
+        assert_eq!(drift.found_vid_pid, "FEFF:FCFD");
+        assert_eq!(drift.firmware_changed_to.as_deref(), Some("7.4"));
+        assert_eq!(drift.expected.vid, "1D50");
+    }
+
+    #[test]
+    fn test_parse_tags() {
+        assert_eq!(
+            parse_tags(" work, recovery key ,,Work, "),
+            vec!["work".to_string(), "recovery key".to_string()]
+        );
+        assert!(parse_tags(" , ").is_empty());
+    }
+
+    #[test]
+    fn test_note_matches_text_and_tags() {
+        let note = CredentialNote {
+            text: "Shared with the Ops team".into(),
+            tags: vec!["Recovery Key".into()],
+            updated_at: 0,
+        };
+        assert!(note.matches("ops"));
+        assert!(note.matches("recovery"));
+        assert!(!note.matches("personal"));
+    }
+
+    #[test]
+    fn test_inventory_from_older_version_still_loads() {
+        let inventory: Inventory = serde_json::from_str(r#"{"devices":{}}"#).unwrap();
+        assert!(inventory.credential_notes.is_empty());
+        assert!(inventory.nicknames.is_empty());
+        assert!(inventory.timelines.is_empty());
+        assert!(inventory.device_overrides.is_empty());
+    }
+
+    #[test]
+    fn test_device_overrides_json() {
+        use crate::tunables::{Transport, VendorRead};
+
+        let overrides = DeviceOverrides {
+            transport_order: Some(vec![Transport::Fido]),
+            user_presence_timeout_ms: Some(60_000),
+            disabled_vendor_reads: vec![VendorRead::Memory],
+            ..Default::default()
+        };
+        let json = serde_json::to_value(&overrides).unwrap();
+        assert_eq!(json["transportOrder"], serde_json::json!(["fido"]));
+        assert_eq!(json["disabledVendorReads"], serde_json::json!(["memory"]));
+
+        let back: DeviceOverrides =
+            serde_json::from_str(r#"{"userPresenceTimeoutMs":60000,"transportOrder":["fido"],"disabledVendorReads":["memory"]}"#)
+                .unwrap();
+        assert_eq!(back, overrides);
+    }
+
+    #[test]
+    fn test_firmware_event() {
+        let mut inventory = Inventory::default();
+        let first = firmware_event(&inventory, "ABC123", "7.2").unwrap();
+        assert_eq!(
+            first,
+            TimelineEvent::FirstSeen {
+                firmware: "7.2".into()
+            }
+        );
+        push_event(&mut inventory, "ABC123", first, 1);
+        assert_eq!(firmware_event(&inventory, "ABC123", "7.2"), None);
+        assert_eq!(
+            firmware_event(&inventory, "ABC123", "7.4"),
+            Some(TimelineEvent::FirmwareChanged {
+                from: "7.2".into(),
+                to: "7.4".into()
+            })
+        );
+    }
+
+    #[test]
+    fn test_timeline_is_capped() {
+        let mut inventory = Inventory::default();
+        for at in 0..(MAX_TIMELINE_ENTRIES as u64 + 5) {
+            push_event(&mut inventory, "ABC123", TimelineEvent::PinChanged, at);
+        }
+        let timeline = &inventory.timelines["ABC123"];
+        assert_eq!(timeline.len(), MAX_TIMELINE_ENTRIES);
+        assert_eq!(timeline[0].at, 5);
+    }
+
+    #[test]
+    fn test_recent_events_show_credential_trend() {
+        let mut inventory = Inventory::default();
+        push_event(
+            &mut inventory,
+            "ABC123",
+            TimelineEvent::Credentials { count: 3 },
+            1,
+        );
+        push_event(&mut inventory, "ABC123", TimelineEvent::ConfigApplied, 2);
+        push_event(
+            &mut inventory,
+            "ABC123",
+            TimelineEvent::Credentials { count: 5 },
+            3,
+        );
+        let recent = recent_events(&inventory, "ABC123", 2);
+        assert_eq!(
+            recent,
+            vec![
+                (3, "5 passkeys stored (+2)".to_string()),
+                (2, "Configuration applied".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_timeline_entry_json() {
+        let entry = TimelineEntry {
+            at: 7,
+            event: TimelineEvent::Credentials { count: 2 },
+        };
+        let json = serde_json::to_string(&entry).unwrap();
+        assert_eq!(json, r#"{"at":7,"kind":"credentials","count":2}"#);
+        assert_eq!(serde_json::from_str::<TimelineEntry>(&json).unwrap(), entry);
+    }
+
+    fn tally(exchanges: u64, errors: &[(&str, u64)]) -> TransportTally {
+        TransportTally {
+            exchanges,
+            errors: errors.iter().map(|(c, n)| (c.to_string(), *n)).collect(),
+        }
+    }
+
+    #[test]
+    fn test_add_health_buckets_by_day() {
+        let mut inventory = Inventory::default();
+        let day = 86_400;
+        add_health(&mut inventory, "ABC123", &tally(10, &[("timeout", 1)]), 5);
+        add_health(&mut inventory, "ABC123", &tally(5, &[("timeout", 2)]), 6);
+        add_health(&mut inventory, "ABC123", &tally(3, &[]), day + 1);
+        let days = &inventory.device_health["ABC123"];
+        assert_eq!(days.len(), 2);
+        assert_eq!(days[0].exchanges, 15);
+        assert_eq!(days[0].errors["timeout"], 3);
+        assert_eq!(days[1].day, 1);
+
+        add_health(&mut inventory, "ABC123", &tally(1, &[]), HEALTH_DAYS * day);
+        let days = &inventory.device_health["ABC123"];
+        assert_eq!(
+            days.iter().map(|d| d.day).collect::<Vec<_>>(),
+            vec![1, HEALTH_DAYS]
+        );
+    }
+
+    #[test]
+    fn test_elevated_error_hint() {
+        let mut inventory = Inventory::default();
+        // Too few exchanges to judge.
+        add_health(&mut inventory, "ABC123", &tally(50, &[("sequence", 10)]), 0);
+        assert_eq!(elevated_error_hint(&inventory, "ABC123", 0), None);
+
+        // CTAP status codes (wrong PIN and the like) do not count.
+        add_health(
+            &mut inventory,
+            "ABC123",
+            &tally(150, &[("ctap.0x31", 40)]),
+            0,
+        );
+        let hint = elevated_error_hint(&inventory, "ABC123", 0).unwrap();
+        assert!(hint.contains("10 of 200 exchanges"));
+        assert!(hint.contains("10 sequence"));
+
+        // Outside the window the counts no longer apply.
+        assert_eq!(
+            elevated_error_hint(&inventory, "ABC123", HEALTH_DAYS * 86_400),
+            None
+        );
+
+        let mut healthy = Inventory::default();
+        add_health(&mut healthy, "ABC123", &tally(500, &[("timeout", 10)]), 0);
+        assert_eq!(elevated_error_hint(&healthy, "ABC123", 0), None);
+        assert_eq!(elevated_error_hint(&healthy, "OTHER", 0), None);
+    }
+}
@@ -0,0 +1,275 @@
+//! Pre-filled GitHub issue drafts for bug reports.
+//!
+//! The draft collects what maintainers always ask for first: app version,
+//! OS, the connected key's firmware and a guess at its board, the error the
+//! user saw and the tail of `picoforge.log`. Everything is passed through
+//! [`sanitize`] before it leaves the machine, and nothing is sent by
+//! PicoForge itself: the draft is opened in the browser as a new-issue URL
+//! and the user reviews it before submitting.
+
+use crate::logging::{self, LogStatus};
+use std::fs;
+use std::sync::Mutex;
+
+const NEW_ISSUE_URL: &str = "https://github.com/librekeys/picoforge/issues/new";
+
+/// Browsers and GitHub reject much longer URLs; the log excerpt is trimmed
+/// until the draft fits.
+const MAX_URL_LEN: usize = 7500;
+
+const LOG_EXCERPT_LINES: usize = 40;
+
+/// What the draft says about the connected key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceSummary {
+    /// Firmware family, e.g. "Pico FIDO".
+    pub firmware_type: String,
+    /// Version the key reports.
+    pub firmware_version: String,
+    /// Best guess at the board or product, from the USB identity and the
+    /// firmware fingerprint.
+    pub board: String,
+    /// Channel PicoForge used, e.g. "FIDO".
+    pub method: String,
+    /// Serial number; never included, only used to redact it from the text.
+    pub serial: String,
+}
+
+static DEVICE: Mutex<Option<DeviceSummary>> = Mutex::new(None);
+
+/// Record the connected key (or `None` when it is gone), so drafts opened
+/// from anywhere in the UI can describe it.
+pub fn set_device(device: Option<DeviceSummary>) {
+    if let Ok(mut slot) = DEVICE.lock() {
+        *slot = device;
+    }
+}
+
+fn device() -> Option<DeviceSummary> {
+    DEVICE.lock().ok().and_then(|slot| slot.clone())
+}
+
+/// A bug report ready to be opened in the browser.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IssueDraft {
+    /// Issue title.
+    pub title: String,
+    /// Sanitized error message, if the draft was opened from an error.
+    pub error: Option<String>,
+    /// Connected key, if any.
+    pub device: Option<DeviceSummary>,
+    /// Sanitized tail of the log, oldest line first.
+    pub log_lines: Vec<String>,
+}
+
+impl IssueDraft {
+    /// Collect a draft for `error` (or a general report when `None`).
+    pub fn collect(error: Option<&str>) -> Self {
+        let device = device();
+        let serial = device.as_ref().map(|d| d.serial.as_str());
+        let error = error.map(|e| sanitize(e, serial));
+        let title = match error.as_deref().and_then(|e| e.lines().next()) {
+            Some(line) => format!("Error: {}", truncate(line, 80)),
+            None => String::new(),
+        };
+        let log_lines = log_excerpt()
+            .into_iter()
+            .map(|line| sanitize(&line, serial))
+            .collect();
+
+        Self {
+            title,
+            error,
+            device,
+            log_lines,
+        }
+    }
+
+    /// Markdown issue body.
+    pub fn body(&self) -> String {
+        let mut body = String::from(
+            "### What happened\n\n<!-- What did you do, and what did you expect instead? -->\n\n",
+        );
+
+        if let Some(error) = &self.error {
+            body.push_str(&format!("### Error\n\n```\n{}\n```\n\n", error));
+        }
+
+        body.push_str("### Environment\n\n");
+        body.push_str(&format!(
+            "- PicoForge: {}\n- OS: {} ({})\n",
+            env!("CARGO_PKG_VERSION"),
+            std::env::consts::OS,
+            std::env::consts::ARCH
+        ));
+        match &self.device {
+            Some(device) => body.push_str(&format!(
+                "- Firmware: {} {}\n- Board (guess): {}\n- Connected via: {}\n",
+                device.firmware_type, device.firmware_version, device.board, device.method
+            )),
+            None => body.push_str("- Device: none connected\n"),
+        }
+
+        if !self.log_lines.is_empty() {
+            body.push_str(&format!(
+                "\n### Log excerpt\n\n<details><summary>Last {} lines of picoforge.log</summary>\n\n```\n{}\n```\n\n</details>\n",
+                self.log_lines.len(),
+                self.log_lines.join("\n")
+            ));
+        }
+        body
+    }
+
+    /// New-issue URL with title and body filled in. Drops the oldest log
+    /// lines until it is short enough for a browser to open.
+    pub fn url(&self) -> String {
+        let mut draft = self.clone();
+        loop {
+            let url = format!(
+                "{}?title={}&body={}",
+                NEW_ISSUE_URL,
+                percent_encode(&draft.title),
+                percent_encode(&draft.body())
+            );
+            if url.len() <= MAX_URL_LEN {
+                return url;
+            }
+            if draft.log_lines.is_empty() {
+                // Only a very long error is left; keep its start.
+                let error = draft.error.take().unwrap_or_default();
+                draft.error = Some(truncate(&error, error.len() / 2));
+                continue;
+            }
+            draft.log_lines.remove(0);
+        }
+    }
+}
+
+/// Last lines of `picoforge.log`, or nothing when logging is console-only.
+fn log_excerpt() -> Vec<String> {
+    let Some(LogStatus::File(dir)) = logging::log_status() else {
+        return Vec::new();
+    };
+    let Ok(text) = fs::read_to_string(dir.join("picoforge.log")) else {
+        return Vec::new();
+    };
+    let lines: Vec<&str> = text.lines().collect();
+    lines[lines.len().saturating_sub(LOG_EXCERPT_LINES)..]
+        .iter()
+        .map(|l| l.to_string())
+        .collect()
+}
+
+/// Remove what should not end up in a public issue: the key's serial number,
+/// the home directory, e-mail addresses (user names of passkeys) and long
+/// hex strings (credential IDs, keys, wire traces).
+pub fn sanitize(text: &str, serial: Option<&str>) -> String {
+    let mut text = text.to_string();
+    if let Some(serial) = serial.filter(|s| !s.is_empty()) {
+        text = text.replace(serial, "<serial>");
+    }
+    if let Some(home) = directories::BaseDirs::new().map(|d| d.home_dir().to_path_buf())
+        && let Some(home) = home.to_str().filter(|h| h.len() > 1)
+    {
+        text = text.replace(home, "~");
+    }
+
+    text.split_inclusive(|c: char| c.is_whitespace() || "\"'(),;[]{}".contains(c))
+        .map(|chunk| {
+            let token =
+                chunk.trim_end_matches(|c: char| c.is_whitespace() || "\"'(),;[]{}".contains(c));
+            let rest = &chunk[token.len()..];
+            let hex_len = token.chars().filter(|c| c.is_ascii_hexdigit()).count();
+            if token.contains('@') && token.contains('.') && !token.starts_with('@') {
+                format!("<email>{}", rest)
+            } else if hex_len >= 16 && hex_len == token.len() {
+                format!("<hex:{}>{}", hex_len / 2, rest)
+            } else {
+                chunk.to_string()
+            }
+        })
+        .collect()
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let mut out: String = text.chars().take(max_chars).collect();
+    out.push('…');
+    out
+}
+
+/// Percent-encode a query value (RFC 3986 unreserved characters pass through).
+fn percent_encode(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() * 3);
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                out.push(byte as char)
+            }
+            _ => out.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn draft(log_lines: usize) -> IssueDraft {
+        IssueDraft {
+            title: "Error: boom".into(),
+            error: Some("boom".into()),
+            device: Some(DeviceSummary {
+                firmware_type: "Pico FIDO".into(),
+                firmware_version: "7.4".into(),
+                board: "Pico Keys Fido (2E8A:10FE)".into(),
+                method: "FIDO".into(),
+                serial: "E6614C311B7A5B2A".into(),
+            }),
+            log_lines: (0..log_lines)
+                .map(|i| format!("[2025-01-01 00:00:00 INFO picoforge] line {}", i))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_sanitize_redacts_serial_hex_and_email() {
+        let text =
+            "Device E6614C311B7A5B2A: cred a1b2c3d4e5f60718293a4b5c6d7e8f90 for alice@example.com";
+        let clean = sanitize(text, Some("E6614C311B7A5B2A"));
+        assert_eq!(clean, "Device <serial>: cred <hex:16> for <email>");
+    }
+
+    #[test]
+    fn test_sanitize_keeps_short_codes() {
+        let text = "CTAP error 0x31 (PIN_INVALID), VID:PID 2E8A:10FE";
+        assert_eq!(sanitize(text, None), text);
+    }
+
+    #[test]
+    fn test_body_lists_environment() {
+        let body = draft(2).body();
+        assert!(body.contains("- Firmware: Pico FIDO 7.4"));
+        assert!(body.contains("Board (guess): Pico Keys Fido"));
+        assert!(body.contains("```\nboom\n```"));
+        assert!(body.contains("line 1"));
+        assert!(!body.contains("E6614C311B7A5B2A"));
+    }
+
+    #[test]
+    fn test_url_is_trimmed_to_fit() {
+        let url = draft(500).url();
+        assert!(url.len() <= MAX_URL_LEN);
+        assert!(url.starts_with(NEW_ISSUE_URL));
+        assert!(url.contains("line%20499"));
+        assert!(!url.contains("line%200%0A"));
+    }
+
+    #[test]
+    fn test_percent_encode() {
+        assert_eq!(percent_encode("a b&c=d/é"), "a%20b%26c%3Dd%2F%C3%A9");
+    }
+}
@@ -0,0 +1,330 @@
+//! Storage janitor: keeps what PicoForge writes within `[storage]` quotas.
+//!
+//! PicoForge writes into three places: the log directory (`picoforge.log`,
+//! its rotations and `audit.log`), the platform cache directory (release
+//! notes) and the platform data directory (inventory, metrics, the device
+//! registry). [`sweep`] measures all three and prunes the first two:
+//!
+//! - prunable files older than `storage.retention_days` are deleted;
+//! - if the total is still over `storage.quota_mb`, the oldest prunable files
+//!   go next until it fits.
+//!
+//! The live `picoforge.log` and `audit.log` and everything in the data
+//! directory are never deleted: they are either open or the user's records.
+//! When they alone push usage past `storage.warn_percent` of the quota,
+//! [`StorageReport::warning`] says so and the app shows it. Only PicoForge's
+//! own log files are looked at in the log directory, which the user may have
+//! pointed somewhere shared.
+
+use crate::logging::{self, LogStatus};
+use crate::settings;
+use crate::tunables;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Files in the log directory that are written to while PicoForge runs.
+const LIVE_LOGS: &[&str] = &["picoforge.log", "audit.log"];
+
+const MIB: u64 = 1024 * 1024;
+
+/// One file under a measured directory.
+#[derive(Debug, Clone, PartialEq)]
+struct FileEntry {
+    path: PathBuf,
+    bytes: u64,
+    modified: SystemTime,
+    /// May be deleted by the retention policy or to meet the quota.
+    prunable: bool,
+}
+
+/// Usage of one of the directories PicoForge writes to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AreaUsage {
+    /// `Logs`, `Cache` or `App data`.
+    pub name: &'static str,
+    /// Directory that was measured.
+    pub dir: PathBuf,
+    /// Bytes left after pruning.
+    pub bytes: u64,
+}
+
+/// Outcome of one [`sweep`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct StorageReport {
+    /// Per-directory usage, logs first.
+    pub areas: Vec<AreaUsage>,
+    /// Files deleted by this sweep.
+    pub pruned_files: usize,
+    /// Bytes those files freed.
+    pub pruned_bytes: u64,
+    /// `storage.quota_mb` in bytes.
+    pub quota_bytes: u64,
+    /// Usage at which [`warning`](Self::warning) speaks up.
+    pub warn_bytes: u64,
+}
+
+impl StorageReport {
+    /// Bytes in use after pruning.
+    pub fn total_bytes(&self) -> u64 {
+        self.areas.iter().map(|a| a.bytes).sum()
+    }
+
+    /// What to tell the user when usage is near or over the quota.
+    pub fn warning(&self) -> Option<String> {
+        let total = self.total_bytes();
+        if total < self.warn_bytes {
+            return None;
+        }
+        let largest = self
+            .areas
+            .iter()
+            .max_by_key(|a| a.bytes)
+            .map(|a| format!(" Most of it is {} ({}).", a.name, a.dir.display()))
+            .unwrap_or_default();
+        Some(format!(
+            "PicoForge is using {} MiB of its {} MiB storage quota.{}",
+            total.div_ceil(MIB),
+            self.quota_bytes / MIB,
+            largest
+        ))
+    }
+}
+
+/// Which of `files` to delete: the prunable ones older than `retention`,
+/// then the oldest remaining prunable ones until the total is at most
+/// `quota`. Indices into `files`.
+fn plan_prune(files: &[FileEntry], now: SystemTime, retention: Duration, quota: u64) -> Vec<usize> {
+    let expired = |f: &FileEntry| {
+        now.duration_since(f.modified)
+            .is_ok_and(|age| age > retention)
+    };
+    let mut doomed: Vec<usize> = (0..files.len())
+        .filter(|&i| files[i].prunable && expired(&files[i]))
+        .collect();
+    let mut total: u64 = files
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| !doomed.contains(i))
+        .map(|(_, f)| f.bytes)
+        .sum();
+
+    let mut oldest_first: Vec<usize> = (0..files.len())
+        .filter(|&i| files[i].prunable && !doomed.contains(&i))
+        .collect();
+    oldest_first.sort_by_key(|&i| files[i].modified);
+    for i in oldest_first {
+        if total <= quota {
+            break;
+        }
+        total -= files[i].bytes;
+        doomed.push(i);
+    }
+    doomed
+}
+
+/// Every regular file under `dir`, skipping the directories in `skip`.
+fn walk(dir: &Path, skip: &[PathBuf], prunable: bool) -> Vec<FileEntry> {
+    let mut files = Vec::new();
+    let Ok(entries) = fs::read_dir(dir) else {
+        return files;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(meta) = entry.metadata() else {
+            continue;
+        };
+        if meta.is_dir() {
+            if !skip.contains(&path) {
+                files.extend(walk(&path, skip, prunable));
+            }
+        } else if meta.is_file() {
+            files.push(FileEntry {
+                prunable,
+                bytes: meta.len(),
+                modified: meta.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                path,
+            });
+        }
+    }
+    files
+}
+
+/// PicoForge's own logs in `dir`, not recursing: a custom log directory may
+/// be shared with other files, which are left alone.
+fn log_files(dir: &Path) -> Vec<FileEntry> {
+    let is_ours = |path: &Path| {
+        path.file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| LIVE_LOGS.iter().any(|live| name.starts_with(live)))
+    };
+    let is_live = |path: &Path| {
+        path.file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| LIVE_LOGS.contains(&name))
+    };
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            let meta = entry.metadata().ok().filter(|m| m.is_file())?;
+            is_ours(&path).then(|| FileEntry {
+                prunable: !is_live(&path),
+                bytes: meta.len(),
+                modified: meta.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                path,
+            })
+        })
+        .collect()
+}
+
+/// The log directory in use this session.
+fn log_dir() -> PathBuf {
+    match logging::log_status() {
+        Some(LogStatus::File(dir)) => dir.clone(),
+        Some(LogStatus::ConsoleOnly { dir, .. }) => dir.clone(),
+        None => logging::default_log_dir(),
+    }
+}
+
+/// Measure, prune and report. Blocks on the file system; run it off the UI
+/// thread.
+pub fn sweep() -> StorageReport {
+    let storage = &tunables::get().storage;
+    let quota_bytes = u64::from(storage.quota_mb) * MIB;
+    let warn_bytes = quota_bytes * u64::from(storage.warn_percent) / 100;
+    let retention = Duration::from_secs(u64::from(storage.retention_days) * 24 * 60 * 60);
+
+    let logs = log_dir();
+    let dirs = settings::project_dirs();
+    let cache = dirs.as_ref().map(|d| d.cache_dir().to_path_buf());
+    let data = dirs.as_ref().map(|d| d.data_local_dir().to_path_buf());
+    // The default log directory sits inside the data directory.
+    let nested: Vec<PathBuf> = [Some(logs.clone()), cache.clone()]
+        .into_iter()
+        .flatten()
+        .collect();
+
+    let mut areas: Vec<(&'static str, PathBuf, Vec<FileEntry>)> =
+        vec![("Logs", logs.clone(), log_files(&logs))];
+    if let Some(cache) = cache {
+        let files = walk(&cache, &[], true);
+        areas.push(("Cache", cache, files));
+    }
+    if let Some(data) = data {
+        let files = walk(&data, &nested, false);
+        areas.push(("App data", data, files));
+    }
+
+    let all: Vec<FileEntry> = areas.iter().flat_map(|(_, _, f)| f.clone()).collect();
+    let doomed = plan_prune(&all, SystemTime::now(), retention, quota_bytes);
+
+    let mut pruned_files = 0;
+    let mut pruned_bytes = 0;
+    let mut failed = Vec::new();
+    for &i in &doomed {
+        match fs::remove_file(&all[i].path) {
+            Ok(()) => {
+                pruned_files += 1;
+                pruned_bytes += all[i].bytes;
+            }
+            Err(e) => {
+                log::warn!("Could not prune {}: {}", all[i].path.display(), e);
+                failed.push(i);
+            }
+        }
+    }
+    if pruned_files > 0 {
+        log::info!(
+            "Storage janitor removed {} files ({} KiB)",
+            pruned_files,
+            pruned_bytes / 1024
+        );
+    }
+
+    let removed = |path: &Path| {
+        doomed
+            .iter()
+            .any(|&i| !failed.contains(&i) && all[i].path == path)
+    };
+    StorageReport {
+        areas: areas
+            .into_iter()
+            .map(|(name, dir, files)| AreaUsage {
+                name,
+                dir,
+                bytes: files
+                    .iter()
+                    .filter(|f| !removed(&f.path))
+                    .map(|f| f.bytes)
+                    .sum(),
+            })
+            .collect(),
+        pruned_files,
+        pruned_bytes,
+        quota_bytes,
+        warn_bytes,
+    }
+}
+
+/// Time between sweeps while the app runs.
+pub fn interval() -> Duration {
+    Duration::from_secs(u64::from(tunables::get().storage.check_interval_minutes) * 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+    fn file(name: &str, bytes: u64, age_days: u32, prunable: bool) -> FileEntry {
+        FileEntry {
+            path: PathBuf::from(name),
+            bytes,
+            modified: SystemTime::UNIX_EPOCH + DAY * 100 - DAY * age_days,
+            prunable,
+        }
+    }
+
+    #[test]
+    fn test_prunes_expired_files_then_oldest_until_under_quota() {
+        let now = SystemTime::UNIX_EPOCH + DAY * 100;
+        let files = [
+            file("picoforge.log", 40, 0, false),
+            file("picoforge.log.1", 30, 45, true),
+            file("release-notes/a.json", 10, 5, true),
+            file("release-notes/b.json", 10, 2, true),
+            file("inventory.json", 20, 90, false),
+        ];
+
+        // Within quota: only the expired rotation goes.
+        assert_eq!(plan_prune(&files, now, DAY * 30, 1000), [1]);
+        // 80 bytes left after that; a 70-byte quota also takes the oldest
+        // cached file, and never the live log or the inventory.
+        assert_eq!(plan_prune(&files, now, DAY * 30, 70), [1, 2]);
+        assert_eq!(plan_prune(&files, now, DAY * 30, 0), [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_warning_only_near_the_quota() {
+        let report = |bytes| StorageReport {
+            areas: vec![AreaUsage {
+                name: "Logs",
+                dir: PathBuf::from("/tmp/logs"),
+                bytes,
+            }],
+            pruned_files: 0,
+            pruned_bytes: 0,
+            quota_bytes: 100 * MIB,
+            warn_bytes: 80 * MIB,
+        };
+        assert_eq!(report(79 * MIB).warning(), None);
+        let warning = report(95 * MIB).warning().unwrap();
+        assert!(warning.contains("95 MiB of its 100 MiB"));
+        assert!(warning.contains("Logs"));
+    }
+}
@@ -1,16 +1,38 @@
 //! Logging initialisation with log4rs.
 //!
-//! Sets up a rolling-file appender (10 MB per file, delete-oldest policy)
+//! Sets up a rolling-file appender (10 MB per file unless tuned in `picoforge.toml`,
+//! delete-oldest policy)
 //! and a console appender. The `picoforge` logger defaults to `Trace` in
 //! debug builds and `Info` in release builds; verbose third-party
 //! loggers (`gpui`, `gpui_component`, `blade_graphics`) are capped at
 //! `Error` to reduce noise.
+//!
+//! Security-relevant changes (e.g. USB identity rewrites) are logged under
+//! [`AUDIT_TARGET`], which additionally goes to a separate, append-only
+//! `audit.log` next to the main log so the trail survives log rotation.
+//!
+//! Raw protocol traffic (CTAPHID payloads and APDUs) is logged in hex under
+//! [`WIRE_TARGET`] when the app is started with `--trace-wire`; see
+//! [`set_trace_wire`].
+//!
+//! Every user-initiated operation (an "Apply Changes" click, a passkey
+//! deletion, ...) gets an [`ActionId`]. Log lines written inside
+//! [`ActionId::scope`] carry it as an `[act …]` prefix in every appender,
+//! the audit trail included, so one operation can be followed end-to-end with
+//! a plain `grep` across the log and a diagnostic bundle.
+//!
+//! When the NDJSON event stream is open ([`crate::events`]), every app log
+//! record is also forwarded to it as a `log` event.
+//!
+//! Initialisation never panics. If the log directory (the platform default or
+//! the one chosen in settings) cannot be written, logging falls back to the
+//! console and [`log_status`] reports why.
 
-use directories::ProjectDirs;
 use log::LevelFilter;
 use log4rs::{
     append::{
         console::{ConsoleAppender, Target},
+        file::FileAppender,
         rolling_file::{
             RollingFileAppender,
             policy::compound::{
@@ -18,68 +40,315 @@ use log4rs::{
             },
         },
     },
-    config::{Appender, Logger, Root},
-    encode::pattern::PatternEncoder,
+    config::{Appender, Logger, Root, runtime::ConfigErrors},
+    encode::{self, Encode, pattern::PatternEncoder},
 };
+use std::cell::RefCell;
+use std::fmt;
 use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, OnceLock};
 
-/// Initializes log4rs with custom configuration for stdout and file logging.
-pub fn logger_init() {
-    let qual = "in";
-    let org = "suyogtandel";
-    let app = "picoforge";
-
-    // Determine the log file path using ProjectDirs for cross-platform compatibility
-    let log_file_path = {
-        let log_dir = if let Some(proj_dirs) = ProjectDirs::from(qual, org, app) {
-            proj_dirs.data_local_dir().join("logs")
-        } else {
-            eprintln!("Could not determine project directories. Falling back to local directory.");
-            std::path::PathBuf::from("logs")
-        };
-
-        if let Err(e) = fs::create_dir_all(&log_dir) {
-            eprintln!("Failed to create log directory at {:?}: {}", log_dir, e);
+use crate::{events, settings, tunables};
+
+/// Log target for the audit trail. Use as `log::info!(target: AUDIT_TARGET, ...)`.
+pub const AUDIT_TARGET: &str = "picoforge::audit";
+
+/// Log target for raw protocol traffic. Only written while [`trace_wire`] is on.
+pub const WIRE_TARGET: &str = "picoforge::wire";
+
+static TRACE_WIRE: AtomicBool = AtomicBool::new(false);
+
+/// Enable or disable hex tracing of every CTAPHID message and APDU.
+///
+/// Traces include PIN/UV auth parameters and key-agreement material (never
+/// the PIN itself), so trace logs should not be shared publicly.
+pub fn set_trace_wire(enabled: bool) {
+    TRACE_WIRE.store(enabled, Ordering::Relaxed);
+    if enabled {
+        log::warn!(
+            "Wire tracing enabled: raw protocol payloads are written to the log. \
+             Do not share these logs publicly."
+        );
+    }
+}
+
+/// Whether wire tracing is enabled.
+pub fn trace_wire() -> bool {
+    TRACE_WIRE.load(Ordering::Relaxed)
+}
+
+static DRY_RUN: AtomicBool = AtomicBool::new(false);
+
+/// Error message of a request a dry run printed instead of sending.
+pub const NOT_SENT: &str = "Not sent (dry run)";
+
+/// Print requests instead of sending them (`--dry-run` of the headless
+/// commands). The transports hand each request to [`print_unsent`] and fail
+/// it with [`NOT_SENT`], so a command stops at the first request whose
+/// answer it needs.
+pub fn set_dry_run(enabled: bool) {
+    DRY_RUN.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether requests are printed instead of sent.
+pub fn dry_run() -> bool {
+    DRY_RUN.load(Ordering::Relaxed)
+}
+
+/// Print a request a dry run kept from the key. Goes to stderr, so JSON
+/// output on stdout stays parseable.
+pub fn print_unsent(request: &str) {
+    eprintln!("dry run: {}", request);
+}
+
+/// Hex for a wire-trace line, cut to `capture.wire_trace_max_bytes`.
+pub fn wire_hex(bytes: &[u8]) -> String {
+    let max = tunables::get().capture.wire_trace_max_bytes;
+    if max == 0 || bytes.len() <= max {
+        return hex::encode_upper(bytes);
+    }
+    format!(
+        "{}…(+{} bytes)",
+        hex::encode_upper(&bytes[..max]),
+        bytes.len() - max
+    )
+}
+
+static NEXT_ACTION: AtomicU32 = AtomicU32::new(1);
+
+thread_local! {
+    static CURRENT_ACTION: RefCell<Option<ActionId>> = const { RefCell::new(None) };
+}
+
+/// Correlation ID of one user-initiated operation.
+///
+/// Cheap to clone; move a clone into each background closure and wrap the
+/// work in [`scope`](Self::scope), since the ID is tracked per thread.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActionId(Arc<str>);
+
+impl ActionId {
+    /// Start a new action and log its name, e.g. `"config.apply"`.
+    ///
+    /// IDs are the process ID plus a counter, unique within one log file and
+    /// distinguishable across the GUI and CLI writing to the same directory.
+    pub fn begin(name: &str) -> Self {
+        let n = NEXT_ACTION.fetch_add(1, Ordering::Relaxed);
+        let id = Self(format!("{:x}-{:04}", std::process::id(), n).into());
+        id.scope(|| log::info!("Action started: {}", name));
+        id
+    }
+
+    /// Run `f` with this ID attached to every log line it writes on the
+    /// current thread. Scopes nest; the previous ID is restored afterwards.
+    pub fn scope<T>(&self, f: impl FnOnce() -> T) -> T {
+        let previous = CURRENT_ACTION.with(|c| c.replace(Some(self.clone())));
+        let _restore = RestoreAction(previous);
+        f()
+    }
+
+    /// The ID as shown in log lines.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for ActionId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Puts the outer action back when a scope ends, even by panic.
+struct RestoreAction(Option<ActionId>);
+
+impl Drop for RestoreAction {
+    fn drop(&mut self) {
+        CURRENT_ACTION.with(|c| *c.borrow_mut() = self.0.take());
+    }
+}
+
+/// The action the current thread is working for, if any.
+pub fn current_action() -> Option<ActionId> {
+    CURRENT_ACTION.with(|c| c.borrow().clone())
+}
+
+/// [`PatternEncoder`] that prefixes the message with the current [`ActionId`].
+///
+/// Appenders encode on the logging thread, so the thread-local is still the
+/// caller's when this runs.
+#[derive(Debug)]
+struct ActionEncoder(PatternEncoder);
+
+impl ActionEncoder {
+    fn new(pattern: &str) -> Box<Self> {
+        Box::new(Self(PatternEncoder::new(pattern)))
+    }
+}
+
+impl Encode for ActionEncoder {
+    fn encode(&self, w: &mut dyn encode::Write, record: &log::Record) -> anyhow::Result<()> {
+        match current_action() {
+            Some(id) => self.0.encode(
+                w,
+                &record
+                    .to_builder()
+                    .args(format_args!("[act {}] {}", id, record.args()))
+                    .build(),
+            ),
+            None => self.0.encode(w, record),
+        }
+    }
+}
+
+/// Forwards app log records to the event stream while it is open.
+#[derive(Debug)]
+struct EventAppender;
+
+impl log4rs::append::Append for EventAppender {
+    fn append(&self, record: &log::Record) -> anyhow::Result<()> {
+        if events::enabled() {
+            events::emit(events::Event::Log {
+                level: record.level().to_string(),
+                target: record.target().to_string(),
+                message: record.args().to_string(),
+            });
         }
+        Ok(())
+    }
 
-        log_dir.join("picoforge.log")
+    fn flush(&self) {}
+}
+
+/// Where log output ended up after [`logger_init`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum LogStatus {
+    /// Logging to files in this directory (and the console).
+    File(PathBuf),
+    /// File logging could not be set up; only the console receives output.
+    ConsoleOnly {
+        /// Directory that was attempted.
+        dir: PathBuf,
+        /// Why the file appenders could not be created.
+        reason: String,
+    },
+}
+
+static LOG_STATUS: OnceLock<LogStatus> = OnceLock::new();
+
+/// The logging outcome recorded by [`logger_init`], if it has run.
+pub fn log_status() -> Option<&'static LogStatus> {
+    LOG_STATUS.get()
+}
+
+/// Default log directory inside the platform data directory.
+pub fn default_log_dir() -> PathBuf {
+    if let Some(proj_dirs) = settings::project_dirs() {
+        proj_dirs.data_local_dir().join("logs")
+    } else {
+        eprintln!("Could not determine project directories. Falling back to local directory.");
+        PathBuf::from("logs")
+    }
+}
+
+/// Initializes log4rs with custom configuration for stdout and file logging.
+///
+/// Never panics: if the log directory is unusable, falls back to console-only
+/// logging and records the reason in [`log_status`] so the UI can show it.
+pub fn logger_init(custom_dir: Option<PathBuf>) {
+    let log_dir = custom_dir.unwrap_or_else(default_log_dir);
+
+    let (config, status) = match file_config(&log_dir) {
+        Ok(config) => (Ok(config), LogStatus::File(log_dir)),
+        Err(reason) => {
+            eprintln!(
+                "File logging unavailable ({}). Logging to console only.",
+                reason
+            );
+            (
+                console_config().map_err(|e| e.to_string()),
+                LogStatus::ConsoleOnly {
+                    dir: log_dir,
+                    reason,
+                },
+            )
+        }
     };
 
+    match config {
+        Ok(config) => {
+            if let Err(e) = log4rs::init_config(config) {
+                eprintln!("Failed to initialise logging: {}", e);
+            }
+        }
+        Err(e) => eprintln!("Failed to build logging config: {}", e),
+    }
+
+    if let LogStatus::ConsoleOnly { dir, reason } = &status {
+        log::warn!("File logging disabled, could not use {:?}: {}", dir, reason);
+    }
+    let _ = LOG_STATUS.set(status);
+}
+
+fn app_level() -> LevelFilter {
+    if cfg!(debug_assertions) {
+        LevelFilter::Trace
+    } else {
+        LevelFilter::Info
+    }
+}
+
+fn console_appender() -> ConsoleAppender {
+    ConsoleAppender::builder()
+        .target(Target::Stdout)
+        .encoder(ActionEncoder::new(
+            "[{d(%Y-%m-%d %H:%M:%S %Z)} {h({l})} {t}] {m}{n}",
+        ))
+        .build()
+}
+
+/// Full configuration: console, rolling `picoforge.log` and `audit.log` in `log_dir`.
+fn file_config(log_dir: &Path) -> Result<log4rs::Config, String> {
+    fs::create_dir_all(log_dir)
+        .map_err(|e| format!("Failed to create log directory at {:?}: {}", log_dir, e))?;
+
     // TODO: Add session based log files or rolling log files with archiving of old files, to prevent a single log file from growing too large.
-    let size_trigger = SizeTrigger::new(10 * 1024 * 1024); // 10 MB limit
+    let max_mb = u64::from(tunables::get().capture.log_file_max_mb);
+    let size_trigger = SizeTrigger::new(max_mb * 1024 * 1024);
     let roller = DeleteRoller::new();
     let policy = CompoundPolicy::new(Box::new(size_trigger), Box::new(roller));
 
     // File Appender
     let logfile = RollingFileAppender::builder()
-        .encoder(Box::new(PatternEncoder::new(
+        .encoder(ActionEncoder::new(
             "[{d(%Y-%m-%d %H:%M:%S %Z)} {l} {t}] {m}{n}",
-        )))
-        .build(log_file_path, Box::new(policy))
-        .unwrap();
-
-    // Console Appender
-    let stdout = ConsoleAppender::builder()
-        .target(Target::Stdout)
-        .encoder(Box::new(PatternEncoder::new(
-            "[{d(%Y-%m-%d %H:%M:%S %Z)} {h({l})} {t}] {m}{n}",
-        )))
-        .build();
+        ))
+        .build(log_dir.join("picoforge.log"), Box::new(policy))
+        .map_err(|e| format!("Cannot open picoforge.log: {}", e))?;
 
-    let app_level = if cfg!(debug_assertions) {
-        LevelFilter::Trace
-    } else {
-        LevelFilter::Info
-    };
+    // Audit Appender (never rotated)
+    let auditfile = FileAppender::builder()
+        .encoder(ActionEncoder::new("[{d(%Y-%m-%d %H:%M:%S %Z)}] {m}{n}"))
+        .build(log_dir.join("audit.log"))
+        .map_err(|e| format!("Cannot open audit.log: {}", e))?;
 
-    let config = log4rs::Config::builder()
-        .appender(Appender::builder().build("stdout", Box::new(stdout)))
+    log4rs::Config::builder()
+        .appender(Appender::builder().build("stdout", Box::new(console_appender())))
         .appender(Appender::builder().build("logfile", Box::new(logfile)))
+        .appender(Appender::builder().build("auditfile", Box::new(auditfile)))
+        .appender(Appender::builder().build("events", Box::new(EventAppender)))
         .logger(
             Logger::builder()
-                .appenders(["stdout", "logfile"])
+                .appenders(["stdout", "logfile", "events"])
                 .additive(false)
-                .build("picoforge", app_level),
+                .build("picoforge", app_level()),
+        )
+        .logger(
+            Logger::builder()
+                .appender("auditfile")
+                .build(AUDIT_TARGET, LevelFilter::Info),
         )
         .logger(Logger::builder().build("gpui", LevelFilter::Error))
         .logger(Logger::builder().build("gpui_component", LevelFilter::Error))
@@ -89,7 +358,50 @@ pub fn logger_init() {
                 .appenders(vec!["logfile", "stdout"])
                 .build(LevelFilter::Error),
         )
-        .unwrap();
+        .map_err(|e| e.to_string())
+}
+
+/// Fallback configuration with the console appender only.
+fn console_config() -> Result<log4rs::Config, ConfigErrors> {
+    log4rs::Config::builder()
+        .appender(Appender::builder().build("stdout", Box::new(console_appender())))
+        .appender(Appender::builder().build("events", Box::new(EventAppender)))
+        .logger(
+            Logger::builder()
+                .appenders(["stdout", "events"])
+                .additive(false)
+                .build("picoforge", app_level()),
+        )
+        .logger(Logger::builder().build("gpui", LevelFilter::Error))
+        .logger(Logger::builder().build("gpui_component", LevelFilter::Error))
+        .logger(Logger::builder().build("blade_graphics", LevelFilter::Error))
+        .build(Root::builder().appender("stdout").build(LevelFilter::Error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_action_scopes_nest_and_restore() {
+        assert_eq!(current_action(), None);
+        let outer = ActionId::begin("outer");
+        let inner = ActionId::begin("inner");
+        assert_ne!(outer, inner);
+
+        outer.scope(|| {
+            assert_eq!(current_action().as_ref(), Some(&outer));
+            inner.scope(|| assert_eq!(current_action().as_ref(), Some(&inner)));
+            assert_eq!(current_action().as_ref(), Some(&outer));
+        });
+        assert_eq!(current_action(), None);
+    }
 
-    log4rs::init_config(config).unwrap();
+    #[test]
+    fn test_action_scope_restored_after_panic() {
+        let id = ActionId::begin("panics");
+        let result = std::panic::catch_unwind(|| id.scope(|| panic!("boom")));
+        assert!(result.is_err());
+        assert_eq!(current_action(), None);
+    }
 }
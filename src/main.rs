@@ -48,6 +48,7 @@
 //! - **Configure hardware settings**: USB VID/PID, LED GPIO pins, brightness, touch timeout
 //! - **Manage FIDO2 credentials**: List, delete, and factory-reset passkeys
 //! - **PIN management**: Set, change, and configure minimum PIN length
+//! - **Fingerprints**: Enroll and remove fingerprints on keys with a sensor
 //! - **Security features**: Enable/disable secure boot, enterprise attestation
 //! - **LED customization**: Configure LED colors and behavior for different device states (RS-Key)
 //! - **USB interface management**: Enable/disable FIDO2, OpenPGP, PIV, OATH, OTP applets (RS-Key)
@@ -143,28 +144,57 @@
 //! ├── shell.nix                           # Nix development shell
 //! ├── src/                                # Source code
 //! │   ├── main.rs                         # ← THIS FILE: Application entry point
+//! │   ├── advisories.rs                   # Opt-in security advisories feed (cached)
+//! │   ├── backup.rs                       # Encrypted app data export/import
+//! │   ├── capabilities.rs                 # Capability snapshots and their diff
+//! │   ├── cli.rs                          # Command-line parsing and headless commands
+//! │   ├── config_defaults.rs              # Firmware default configuration and deviations
+//! │   ├── cues.rs                         # Optional sound cues (touch, success, failure)
 //! │   ├── error.rs                        # Application-wide error types (PFError)
+//! │   ├── events.rs                       # Opt-in NDJSON event stream
+//! │   ├── impact.rs                       # Passkey impact of configuration changes
+//! │   ├── inventory.rs                    # Last configured USB identity per device
+//! │   ├── issue_report.rs                 # Pre-filled, sanitized GitHub issue drafts
+//! │   ├── janitor.rs                      # Storage quotas, retention and pruning
 //! │   ├── logging.rs                      # log4rs configuration
+//! │   ├── metrics.rs                      # Opt-in, local-only usage counters
+//! │   ├── provisioning.rs                 # Provisioning session and signed report
+//! │   ├── registry.rs                     # Which process owns which device
+//! │   ├── release_notes.rs                # Firmware release notes from GitHub (cached)
+//! │   ├── secure_lock.rs                  # Host keys for secure-locked keys
+//! │   ├── settings.rs                     # Persistent application settings
+//! │   ├── startup.rs                      # Startup self-check pipeline
+//! │   ├── synced_passkeys.rs              # Password manager passkey exports vs. the key
+//! │   ├── tunables.rs                     # Advanced knobs from picoforge.toml
+//! │   ├── vault.rs                        # Notes vault sealed with an hmac-secret key
 //! │   ├── hal/                            # Hardware abstraction layer
 //! │   │   ├── mod.rs                      # Module root
 //! │   │   ├── io.rs                       # High-level dispatch across protocols
 //! │   │   ├── types.rs                    # Shared data structures
+//! │   │   ├── bootsel.rs                  # BOOTSEL board info and RP2350 whitelabel
 //! │   │   ├── common/                     # COSE enums, version parsing
 //! │   │   │   ├── mod.rs
 //! │   │   │   ├── cose.rs
 //! │   │   │   └── version.rs
 //! │   │   ├── firmwares/                  # Per-firmware capability gating
 //! │   │   │   ├── mod.rs
+//! │   │   │   ├── fingerprint.rs          # GetInfo comparison against known builds
 //! │   │   │   ├── picofido.rs
 //! │   │   │   └── rskey.rs
 //! │   │   ├── transport/                  # Physical transport abstractions
 //! │   │   │   ├── mod.rs
 //! │   │   │   ├── fido.rs                 # CTAPHID over USB HID
-//! │   │   │   └── pcsc.rs                 # ISO 7816-4 over PC/SC
+//! │   │   │   ├── pcsc.rs                 # ISO 7816-4 over PC/SC
+//! │   │   │   └── picoboot.rs             # Boot ROM reads, RP2350 OTP writes
 //! │   │   ├── fido/                       # FIDO2/CTAP2 protocol
 //! │   │   │   ├── mod.rs
+//! │   │   │   ├── bio.rs                  # Fingerprint enrollment
 //! │   │   │   ├── constants.rs
-//! │   │   │   └── ops.rs                  # PIN, credential mgmt, vendor cmds
+//! │   │   │   ├── hmac_secret.rs          # hmac-secret credentials, key derivation
+//! │   │   │   ├── large_blob.rs           # Per-credential large blobs
+//! │   │   │   ├── ops.rs                  # PIN, credential mgmt, vendor cmds
+//! │   │   │   ├── pin.rs                  # PIN normalization (NFC, code points)
+//! │   │   │   └── reference.rs            # Searchable CTAP2 reference table
 //! │   │   └── rescue/                     # Rescue applet (PC/SC APDU)
 //! │   │       ├── mod.rs
 //! │   │       ├── constants.rs            # ISO 7816-4, PHY tags, vendor AIDs
@@ -174,11 +204,21 @@
 //! │       ├── app.rs                      # ApplicationRoot, AppModels, layout, Render
 //! │       ├── assets.rs                   # rust-embed asset loader
 //! │       ├── colors.rs                   # Theme color constants
+//! │       ├── quick_actions.rs            # Action registry, title bar pins
+//! │       ├── scale.rs                    # UI scale (rem size), zoom shortcuts
 //! │       ├── models/                     # Shared reactive state (DeviceRepo)
 //! │       │   ├── mod.rs
 //! │       │   └── device.rs
 //! │       ├── screens/                    # Page views (sidebar sections)
 //! │       │   ├── mod.rs
+//! │       │   ├── applets/                # OpenPGP / OATH (shown when the applet responds)
+//! │       │   │   ├── mod.rs
+//! │       │   │   ├── view.rs
+//! │       │   │   └── view_model.rs
+//! │       │   ├── biometrics/             # Fingerprint enrollment (bioEnroll keys)
+//! │       │   │   ├── mod.rs
+//! │       │   │   ├── view.rs
+//! │       │   │   └── view_model.rs
 //! │       │   ├── home/
 //! │       │   │   ├── mod.rs
 //! │       │   │   ├── view.rs
@@ -195,6 +235,10 @@
 //! │       │   │   ├── mod.rs
 //! │       │   │   ├── view.rs
 //! │       │   │   └── view_model.rs
+//! │       │   ├── vault/                  # Notes vault (hmac-secret keys)
+//! │       │   │   ├── mod.rs
+//! │       │   │   ├── view.rs
+//! │       │   │   └── view_model.rs
 //! │       │   └── about/
 //! │       │       ├── mod.rs
 //! │       │       ├── view.rs
@@ -203,12 +247,22 @@
 //! │           ├── mod.rs
 //! │           ├── button.rs
 //! │           ├── card.rs
+//! │           ├── coach_mark.rs           # Spotlight overlay for the onboarding tour
 //! │           ├── dialog.rs
+//! │           ├── firmware_update.rs      # Release notes since the installed firmware
+//! │           ├── help.rs                 # Help drawer (bundled Markdown guides)
+//! │           ├── issue_report.rs         # "Report this issue" links
 //! │           ├── page_view.rs
+//! │           ├── pin_recovery.rs         # Guided PIN lockout recovery
+//! │           ├── qr.rs                   # QR code rendering and SVG export
 //! │           ├── sidebar.rs
+//! │           ├── spec_reference.rs       # Searchable CTAP2 reference drawer
+//! │           ├── startup_status.rs       # Startup self-check results dialog
 //! │           └── tag.rs
 //! ├── static/
 //! │   ├── appIcons/                       # Application icons (SVG, PNG, ICO, ICNS)
+//! │   ├── firmware-fingerprints.json      # Known official firmware builds (GetInfo fingerprints)
+//! │   ├── help/                           # Troubleshooting guides (Markdown, compiled in)
 //! │   └── icons/                          # UI icons (SVG, loaded via rust-embed)
 //! ├── themes/
 //! │   └── picoforge-zinc.json             # Application theme (Zinc dark palette)
@@ -375,14 +429,15 @@
 //! DeviceRepo::refresh()          [sole polling method]
 //!       │
 //!       ├── begin_load()
-//!       ├── io::read_device_details()
-//!       │     ├──► rescue::read_device_details()
+//!       ├── io::on_hid_thread(DeviceReading::read)     [HID worker thread, awaited]
+//!       ├── io::read_device_details()               [backends in transport.order]
+//!       │     ├──► RescueBackend → rescue::read_device_details()
 //!       │     │         ├── connect_and_select()          [PC/SC]
 //!       │     │         ├── READ(FlashInfo)
 //!       │     │         ├── READ(SecureBootStatus)
 //!       │     │         └── READ(PhyConfig)               [TLV parsing]
 //!       │     │
-//!       │     └──► fido::read_device_details()            [fallback]
+//!       │     └──► FidoBackend → fido::read_device_details()  [fallback]
 //!       │               ├── HidTransport::open()
 //!       │               ├── GetInfo (CTAP2 0x04)
 //!       │               └── Vendor commands (0xC1/0xC2)
@@ -390,6 +445,7 @@
 //!       ├── io::get_fido_info()
 //!       ├── io::read_led_config()                       [RS-Key only]
 //!       ├── io::read_management_config()                [RS-Key only]
+//!       ├── apply_reading()                             [GPUI thread]
 //!       ├── end_load()
 //!       ├── cx.emit(DeviceEvent::Updated)
 //!       │
@@ -416,9 +472,11 @@
 //!       │
 //!       ├── spawn background task
 //!       │     │
-//!       │     ├── DeviceRepo::write_config_blocking()
+//!       │     ├── DeviceRepo::write_config_blocking()   [waits on the HID worker]
 //!       │     │     │
 //!       │     │     ├──► io::write_config(config, method, pin)
+//!       │     │     │      │   backend::for_method(method)
+//!       │     │     │      │   ConfigPlan for the backend's write path
 //!       │     │     │      ├── rescue::write_config()  or
 //!       │     │     │      └── fido::write_config()
 //!       │     │     │
@@ -430,12 +488,12 @@
 //!       └── UI update (on main thread):
 //!             │
 //!             ├── On success: repo.apply_fresh_state(fs)
-//!             │     ├── Updates status, led, management fields
+//!             │     ├── Updates status, led, management, FIDO info fields
 //!             │     ├── Checks device_changed (serial comparison)
 //!             │     └── cx.emit(DeviceEvent::Updated)
 //!             │
 //!             └── On error: show dialog with error message
-//!                     (special-case "0x3E" for FIDO mode)
+//!                     (special-case InvalidSubcommand for FIDO mode)
 //! ```
 //!
 //! ### Credential Management Flow
@@ -637,6 +695,21 @@
 //! - **Release builds**: `Info` level to file only
 //! - **Log location**: Platform-specific data directory (`ProjectDirs`)
 //! - **Rotation**: 10MB size trigger, delete old logs
+//! - **Wire tracing**: `picoforge --trace-wire` logs every CTAPHID message and
+//!   APDU in hex under the `picoforge::wire` target
+//!
+//! ### Command line
+//!
+//! `src/cli.rs` parses arguments before anything else runs. Without a command
+//! the GUI starts; `status`, `completions`, `--help` and `--version` run
+//! headless and exit. `status --output json` prints stable camelCase field
+//! names for use from provisioning scripts. `--safe-mode` starts the GUI
+//! without reading or watching any key until the user connects from the
+//! banner (see `src/startup.rs`).
+//! `--events <file>` appends device events, progress and log records to the
+//! file as NDJSON for other tools to follow (see `src/events.rs`).
+//! `--dry-run` makes a headless command print the CTAPHID requests and APDUs
+//! it would send to stderr instead of sending them.
 //!
 //! ### Formatting
 //!
@@ -892,17 +965,68 @@ use gpui_component::Root;
 use gpui_component::{Theme, ThemeMode, ThemeSet};
 use ui::app::ApplicationRoot;
 
+pub mod advisories;
+pub mod backup;
+pub mod capabilities;
+pub mod cli;
+pub mod config_defaults;
+pub mod cues;
 pub mod error;
+pub mod events;
 mod hal;
+pub mod impact;
+pub mod inventory;
+pub mod issue_report;
+pub mod janitor;
 pub mod logging;
+pub mod metrics;
+pub mod provisioning;
+pub mod registry;
+pub mod release_notes;
+pub mod secure_lock;
+pub mod settings;
+pub mod startup;
+pub mod synced_passkeys;
+pub mod tunables;
 mod ui;
+pub mod vault;
 
 fn main() {
-    logging::logger_init();
+    let args = match cli::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => std::process::exit(cli::usage_error(&e)),
+    };
+    if args.command != cli::Command::Gui {
+        // Headless commands skip logger setup so nothing but the requested
+        // output reaches stdout.
+        logging::set_dry_run(args.dry_run);
+        std::process::exit(cli::run(&args.command));
+    }
+
+    let (settings, settings_error) = settings::load();
+    let tunables_error = tunables::init();
+    logging::logger_init(settings.log_dir);
+    logging::set_trace_wire(args.trace_wire);
+    if let Some(path) = &args.events {
+        match events::open(std::path::Path::new(path)) {
+            Ok(()) => log::info!("Streaming events to {}", path),
+            Err(e) => log::error!("{}", e),
+        }
+    }
+    startup::set_safe_mode(args.safe_mode || settings.safe_mode);
+    metrics::set_enabled(settings.metrics_enabled);
+    cues::set_enabled(settings.sound_cues);
+    startup::run(settings_error, tunables_error);
     let app = Application::new().with_assets(ui::assets::Assets);
 
     app.run(move |cx| {
         gpui_component::init(cx);
+        ui::quick_actions::init(cx);
+        cx.on_app_quit(|_| async {
+            startup::end_session();
+            registry::release();
+        })
+        .detach();
         Theme::change(ThemeMode::Dark, None, cx);
 
         // Register sidebar toggle keybinding
@@ -922,6 +1046,7 @@ fn main() {
                 }
             }
         }
+        ui::scale::init(cx);
 
         cx.activate(true);
 
@@ -0,0 +1,163 @@
+//! Opt-in, local-only usage metrics.
+//!
+//! When enabled in settings, PicoForge counts which screens and operations are
+//! used and which classes of error occur. Only names and counts are kept: no
+//! serials, paths, PINs or error messages. Nothing is ever sent over the
+//! network; the counts live in `metrics.json` in the platform data directory,
+//! are shown on the About screen, and leave the machine only when the user
+//! exports them and chooses to share the file.
+//!
+//! Names are dotted lowercase keys such as `screen.passkeys` or
+//! `config.write`; error classes add the [`PFError`](crate::error::PFError)
+//! kind, e.g. `config.write.device`.
+
+use crate::settings;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Counters collected since `since`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Metrics {
+    /// When counting started (or was last reset), in UNIX seconds.
+    pub since: u64,
+    /// PicoForge version that last wrote the file.
+    pub app_version: String,
+    /// Uses per feature.
+    pub features: BTreeMap<String, u64>,
+    /// Occurrences per error class.
+    pub errors: BTreeMap<String, u64>,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        Self {
+            since: now(),
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Count one use of `feature`.
+    pub fn count_feature(&mut self, feature: &str) {
+        *self.features.entry(feature.to_string()).or_default() += 1;
+    }
+
+    /// Count one occurrence of error class `class`.
+    pub fn count_error(&mut self, class: &str) {
+        *self.errors.entry(class.to_string()).or_default() += 1;
+    }
+}
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static STORE: Mutex<Option<Metrics>> = Mutex::new(None);
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn metrics_path() -> Option<PathBuf> {
+    settings::project_dirs().map(|dirs| dirs.data_local_dir().join("metrics.json"))
+}
+
+fn load() -> Metrics {
+    metrics_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_else(Metrics::new)
+}
+
+fn save(metrics: &Metrics) -> Result<(), String> {
+    let path = metrics_path().ok_or("Could not determine the data directory")?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Could not create {}: {}", parent.display(), e))?;
+    }
+    let text = serde_json::to_string_pretty(metrics).map_err(|e| e.to_string())?;
+    fs::write(&path, text).map_err(|e| format!("Could not write {}: {}", path.display(), e))
+}
+
+/// Turn counting on or off for this session (from settings at startup, and
+/// when the user flips the switch).
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether counting is on.
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+fn update(f: impl FnOnce(&mut Metrics)) {
+    if !enabled() {
+        return;
+    }
+    let Ok(mut store) = STORE.lock() else {
+        return;
+    };
+    let metrics = store.get_or_insert_with(load);
+    f(metrics);
+    metrics.app_version = env!("CARGO_PKG_VERSION").to_string();
+    if let Err(e) = save(metrics) {
+        log::debug!("Could not save usage metrics: {}", e);
+    }
+}
+
+/// Count one use of `feature`. No-op unless metrics are enabled.
+pub fn record_feature(feature: &str) {
+    update(|m| m.count_feature(feature));
+}
+
+/// Count one occurrence of error class `class`. No-op unless metrics are enabled.
+pub fn record_error(class: &str) {
+    update(|m| m.count_error(class));
+}
+
+/// Current counters, read from disk if nothing was recorded this session.
+pub fn snapshot() -> Metrics {
+    match STORE.lock() {
+        Ok(mut store) => store.get_or_insert_with(load).clone(),
+        Err(_) => load(),
+    }
+}
+
+/// Discard all counters and start counting from now.
+pub fn reset() -> Result<(), String> {
+    let fresh = Metrics::new();
+    save(&fresh)?;
+    if let Ok(mut store) = STORE.lock() {
+        *store = Some(fresh);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counts_accumulate() {
+        let mut metrics = Metrics::new();
+        metrics.count_feature("screen.passkeys");
+        metrics.count_feature("screen.passkeys");
+        metrics.count_error("config.write.device");
+
+        assert_eq!(metrics.features["screen.passkeys"], 2);
+        assert_eq!(metrics.errors["config.write.device"], 1);
+    }
+
+    #[test]
+    fn test_missing_fields_default() {
+        let metrics: Metrics = serde_json::from_str(r#"{"features":{"a":1}}"#).unwrap();
+        assert_eq!(metrics.features["a"], 1);
+        assert!(metrics.errors.is_empty());
+    }
+}
@@ -0,0 +1,369 @@
+//! Provisioning session and signed report artifact.
+//!
+//! While PicoForge is open, every successful configuration write is recorded in
+//! a [`ProvisioningSession`]. At the end of a bulk provisioning run the session
+//! is exported as a JSON report listing each device, its firmware, the profile
+//! that was applied and when, so an organisation can show how each key was set
+//! up.
+//!
+//! The report is authenticated with HMAC-SHA256 under a per-installation app
+//! key (`report.key` in the data directory, created on first use). This proves
+//! the report came from this installation and was not edited afterwards; it is
+//! not a public-key signature, so verifying requires the same key file.
+//!
+//! Firmware cannot be read back from the device, so the per-device
+//! `firmwareIdSha256` is a digest of the identity the device reports (type,
+//! version, AAGUID), not of the flash contents. The image that was flashed can
+//! be hashed separately with [`FirmwareImage::from_file`].
+
+use crate::hal::types::{AppConfig, AppConfigInput, FullDeviceStatus};
+use crate::settings;
+use ring::rand::{SecureRandom, SystemRandom};
+use ring::{digest, hmac};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Version of the report layout, bumped on incompatible changes.
+const REPORT_FORMAT: u32 = 1;
+
+/// Name of the signature algorithm recorded in the report.
+const SIGNATURE_ALG: &str = "HMAC-SHA256";
+
+/// Length of the app key in bytes.
+pub(crate) const KEY_LEN: usize = 32;
+
+/// Current time as UNIX seconds.
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(digest::digest(&digest::SHA256, data))
+}
+
+/// Firmware image that was flashed during the session.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FirmwareImage {
+    /// File name of the image (no directory).
+    pub file_name: String,
+    /// SHA-256 of the file contents, hex encoded.
+    pub sha256: String,
+}
+
+impl FirmwareImage {
+    /// Hash a firmware image (e.g. a `.uf2`) from disk.
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let data =
+            fs::read(path).map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+        Ok(Self {
+            file_name: path
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            sha256: sha256_hex(&data),
+        })
+    }
+}
+
+/// One successfully configured device.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProvisionedDevice {
+    /// Device serial number.
+    pub serial: String,
+    /// Nickname from the inventory, if the device has one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nickname: Option<String>,
+    /// Detected firmware variant.
+    pub firmware_type: String,
+    /// Firmware version as reported by the device.
+    pub firmware_version: String,
+    /// AAGUID, when the device was reached over FIDO.
+    pub aaguid: Option<String>,
+    /// SHA-256 over the reported firmware identity.
+    pub firmware_id_sha256: String,
+    /// Profile that was written; `null` fields were left unchanged.
+    pub applied_profile: AppConfigInput,
+    /// Configuration read back after the write, if the read succeeded.
+    pub resulting_config: Option<AppConfig>,
+    /// When the write completed, in UNIX seconds.
+    pub configured_at: u64,
+}
+
+/// Devices configured since the session started.
+#[derive(Debug, Clone)]
+pub struct ProvisioningSession {
+    started_at: u64,
+    firmware_image: Option<FirmwareImage>,
+    devices: Vec<ProvisionedDevice>,
+}
+
+impl Default for ProvisioningSession {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProvisioningSession {
+    /// Start an empty session now.
+    pub fn new() -> Self {
+        Self {
+            started_at: now(),
+            firmware_image: None,
+            devices: Vec::new(),
+        }
+    }
+
+    /// Devices recorded so far, in the order they were configured.
+    pub fn devices(&self) -> &[ProvisionedDevice] {
+        &self.devices
+    }
+
+    /// Firmware image attached to the session, if any.
+    pub fn firmware_image(&self) -> Option<&FirmwareImage> {
+        self.firmware_image.as_ref()
+    }
+
+    /// Attach the firmware image that was flashed onto the devices.
+    pub fn set_firmware_image(&mut self, image: Option<FirmwareImage>) {
+        self.firmware_image = image;
+    }
+
+    /// Record a successful write.
+    ///
+    /// `before` is the status read before the write (for identity), `after`
+    /// the status read back afterwards, if that succeeded.
+    pub fn record(
+        &mut self,
+        before: &FullDeviceStatus,
+        nickname: Option<String>,
+        aaguid: Option<String>,
+        profile: AppConfigInput,
+        after: Option<&FullDeviceStatus>,
+    ) {
+        let firmware_type = before.firmware_type.to_string();
+        let firmware_version = before.info.firmware_version.clone();
+        let identity = format!(
+            "{}|{}|{}",
+            firmware_type,
+            firmware_version,
+            aaguid.as_deref().unwrap_or("")
+        );
+
+        self.devices.push(ProvisionedDevice {
+            serial: before.info.serial.clone(),
+            nickname,
+            firmware_type,
+            firmware_version,
+            aaguid,
+            firmware_id_sha256: sha256_hex(identity.as_bytes()),
+            applied_profile: profile,
+            resulting_config: after.map(|s| s.config.clone()),
+            configured_at: now(),
+        });
+    }
+
+    /// Freeze the session into an unsigned report.
+    pub fn report(&self) -> ProvisioningReport {
+        ProvisioningReport {
+            format: REPORT_FORMAT,
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            started_at: self.started_at,
+            finished_at: now(),
+            firmware_image: self.firmware_image.clone(),
+            devices: self.devices.clone(),
+        }
+    }
+}
+
+/// Report contents covered by the signature.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProvisioningReport {
+    /// Report layout version.
+    pub format: u32,
+    /// PicoForge version that produced the report.
+    pub app_version: String,
+    /// Session start, in UNIX seconds.
+    pub started_at: u64,
+    /// Time the report was generated, in UNIX seconds.
+    pub finished_at: u64,
+    /// Firmware image flashed during the session, if one was attached.
+    pub firmware_image: Option<FirmwareImage>,
+    /// Every device configured during the session.
+    pub devices: Vec<ProvisionedDevice>,
+}
+
+/// HMAC over the serialized [`ProvisioningReport`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportSignature {
+    /// Always `HMAC-SHA256`.
+    pub alg: String,
+    /// First 8 bytes of SHA-256 over the app key, hex encoded.
+    pub key_id: String,
+    /// MAC value, hex encoded.
+    pub value: String,
+}
+
+/// The exported artifact: report plus signature.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SignedReport {
+    /// Signed contents.
+    pub report: ProvisioningReport,
+    /// Signature over `report`.
+    pub signature: ReportSignature,
+}
+
+fn key_id(key: &[u8]) -> String {
+    sha256_hex(key)[..16].to_string()
+}
+
+fn report_bytes(report: &ProvisioningReport) -> Result<Vec<u8>, String> {
+    serde_json::to_vec(report).map_err(|e| format!("Could not serialize report: {}", e))
+}
+
+impl SignedReport {
+    /// Sign `report` with `key`.
+    pub fn sign(report: ProvisioningReport, key: &[u8]) -> Result<Self, String> {
+        let mac_key = hmac::Key::new(hmac::HMAC_SHA256, key);
+        let tag = hmac::sign(&mac_key, &report_bytes(&report)?);
+        Ok(Self {
+            report,
+            signature: ReportSignature {
+                alg: SIGNATURE_ALG.to_string(),
+                key_id: key_id(key),
+                value: hex::encode(tag.as_ref()),
+            },
+        })
+    }
+
+    /// Check the signature against `key`.
+    pub fn verify(&self, key: &[u8]) -> Result<(), String> {
+        if self.signature.alg != SIGNATURE_ALG {
+            return Err(format!(
+                "Unsupported signature algorithm {}",
+                self.signature.alg
+            ));
+        }
+        if self.signature.key_id != key_id(key) {
+            return Err("Report was signed with a different app key".into());
+        }
+        let tag = hex::decode(&self.signature.value)
+            .map_err(|_| "Signature is not valid hex".to_string())?;
+        let mac_key = hmac::Key::new(hmac::HMAC_SHA256, key);
+        hmac::verify(&mac_key, &report_bytes(&self.report)?, &tag)
+            .map_err(|_| "Signature does not match the report contents".to_string())
+    }
+
+    /// Pretty-printed JSON for writing to disk.
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(self).map_err(|e| format!("Could not serialize report: {}", e))
+    }
+}
+
+// ── App key ─────────────────────────────────────────────────────────────────
+
+/// Location of the app key file.
+pub(crate) fn key_path() -> Option<PathBuf> {
+    settings::project_dirs().map(|dirs| dirs.data_local_dir().join("report.key"))
+}
+
+/// Load the app key, generating and storing a new one on first use.
+pub fn load_or_create_key() -> Result<Vec<u8>, String> {
+    let path = key_path().ok_or("Could not determine the data directory")?;
+
+    match fs::read(&path) {
+        Ok(key) if key.len() == KEY_LEN => return Ok(key),
+        Ok(_) => {
+            return Err(format!(
+                "{} is corrupt; move it aside to generate a new key",
+                path.display()
+            ));
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(format!("Could not read {}: {}", path.display(), e)),
+    }
+
+    let mut key = vec![0u8; KEY_LEN];
+    SystemRandom::new()
+        .fill(&mut key)
+        .map_err(|_| "Could not generate a report key".to_string())?;
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Could not create {}: {}", parent.display(), e))?;
+    }
+    write_private(&path, &key).map_err(|e| format!("Could not write {}: {}", path.display(), e))?;
+    log::info!("Created provisioning report key {:?}", path);
+
+    Ok(key)
+}
+
+/// Create `path` with `data`, readable only by the current user where the
+/// platform allows. Fails if the file exists.
+#[cfg(unix)]
+pub(crate) fn write_private(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+    use std::os::unix::fs::OpenOptionsExt;
+
+    fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(path)?
+        .write_all(data)
+}
+
+#[cfg(not(unix))]
+pub(crate) fn write_private(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    fs::write(path, data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_report() -> ProvisioningReport {
+        ProvisioningReport {
+            format: REPORT_FORMAT,
+            app_version: "0.0.0".into(),
+            started_at: 1_700_000_000,
+            finished_at: 1_700_000_600,
+            firmware_image: Some(FirmwareImage {
+                file_name: "pico_fido.uf2".into(),
+                sha256: "00".repeat(32),
+            }),
+            devices: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_signed_report_round_trip() {
+        let key = [7u8; KEY_LEN];
+        let signed = SignedReport::sign(sample_report(), &key).unwrap();
+        assert_eq!(signed.signature.alg, SIGNATURE_ALG);
+
+        let parsed: SignedReport = serde_json::from_str(&signed.to_json().unwrap()).unwrap();
+        assert_eq!(parsed.verify(&key), Ok(()));
+    }
+
+    #[test]
+    fn test_signed_report_rejects_tampering_and_wrong_key() {
+        let key = [7u8; KEY_LEN];
+        let signed = SignedReport::sign(sample_report(), &key).unwrap();
+
+        let mut tampered = signed.clone();
+        tampered.report.finished_at += 1;
+        assert!(tampered.verify(&key).is_err());
+
+        assert!(signed.verify(&[8u8; KEY_LEN]).is_err());
+    }
+}
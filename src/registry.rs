@@ -0,0 +1,422 @@
+//! Device ownership registry shared by every PicoForge process.
+//!
+//! The desktop app and headless commands talk to the same key, and CTAPHID
+//! channels or PC/SC transactions from two processes interleave badly. Each
+//! process records which device it is using in `devices.json` under the
+//! runtime directory (the data directory where the platform has none),
+//! keyed by the `vid:pid:serial` fingerprint from the HID enumeration.
+//!
+//! Ownership is cooperative. A claim stays valid while its owner refreshes
+//! the heartbeat; a crashed process's claim goes stale after
+//! [`STALE_AFTER_SECS`] and is ignored. A headless command that finds the
+//! device owned by the desktop app asks it to step aside with
+//! [`claim_for_cli`]; the app sees the request on its next [`gui_tick`],
+//! drops its claim, pauses polling and refuses device operations (see
+//! [`yielded_to`]), and takes the device back once the command releases it.
+//!
+//! Updates are serialised with a `devices.lock` file created exclusively.
+//! A lock older than [`LOCK_STALE_MS`] is assumed to belong to a process
+//! that died mid-update and is removed.
+
+use crate::settings;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// A claim whose heartbeat is older than this is treated as abandoned.
+pub const STALE_AFTER_SECS: u64 = 10;
+/// How often an owner refreshes its heartbeat.
+const HEARTBEAT_SECS: u64 = 3;
+/// How long a headless command waits for the desktop app to step aside.
+const TAKEOVER_WAIT_MS: u64 = 4000;
+/// A lock file older than this is removed.
+const LOCK_STALE_MS: u64 = 2000;
+const LOCK_RETRY_MS: u64 = 20;
+
+/// The process the desktop app last stepped aside for, per [`gui_tick`].
+static YIELDED_TO: Mutex<Option<Claim>> = Mutex::new(None);
+
+/// Kind of process holding a claim.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// The desktop app.
+    Gui,
+    /// A headless command.
+    Cli,
+}
+
+impl Role {
+    /// How the role is named in messages.
+    pub fn describe(self) -> &'static str {
+        match self {
+            Role::Gui => "the PicoForge desktop app",
+            Role::Cli => "a picoforge command",
+        }
+    }
+}
+
+/// One process's claim on a device.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Claim {
+    /// Fingerprint of the device (`vid:pid:serial`).
+    pub device: String,
+    /// Process ID of the owner.
+    pub pid: u32,
+    /// Kind of owner.
+    pub role: Role,
+    /// When the claim was made, in UNIX seconds.
+    pub since: u64,
+    /// Last time the owner confirmed it is alive, in UNIX seconds.
+    pub heartbeat: u64,
+    /// PID of a process that asked the owner to release the device.
+    #[serde(default)]
+    pub takeover_requested_by: Option<u32>,
+}
+
+impl Claim {
+    fn is_fresh(&self, now: u64) -> bool {
+        now.saturating_sub(self.heartbeat) < STALE_AFTER_SECS
+    }
+}
+
+/// Contents of `devices.json`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct Registry {
+    /// Live claims, at most one per device.
+    pub claims: Vec<Claim>,
+}
+
+impl Registry {
+    /// Drop stale claims. Returns whether anything was removed.
+    fn prune(&mut self, now: u64) -> bool {
+        let before = self.claims.len();
+        self.claims.retain(|c| c.is_fresh(now));
+        self.claims.len() != before
+    }
+
+    /// The live claim on `device` held by a process other than `pid`.
+    pub fn owner(&self, device: &str, pid: u32, now: u64) -> Option<&Claim> {
+        self.claims
+            .iter()
+            .find(|c| c.device == device && c.pid != pid && c.is_fresh(now))
+    }
+
+    /// Claim `device` for `pid`, or return the process that holds it.
+    /// A process holds at most one device; claiming another moves its claim.
+    pub fn claim(&mut self, device: &str, pid: u32, role: Role, now: u64) -> Result<(), Claim> {
+        if let Some(owner) = self.owner(device, pid, now) {
+            return Err(owner.clone());
+        }
+        match self
+            .claims
+            .iter_mut()
+            .find(|c| c.pid == pid && c.device == device)
+        {
+            Some(own) => own.heartbeat = now,
+            None => {
+                self.claims.retain(|c| c.pid != pid);
+                self.claims.push(Claim {
+                    device: device.to_string(),
+                    pid,
+                    role,
+                    since: now,
+                    heartbeat: now,
+                    takeover_requested_by: None,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Ask the owner of `device` to release it. Returns the owner's claim.
+    pub fn request_takeover(&mut self, device: &str, pid: u32, now: u64) -> Option<Claim> {
+        let owner = self
+            .claims
+            .iter_mut()
+            .find(|c| c.device == device && c.pid != pid && c.is_fresh(now))?;
+        owner.takeover_requested_by = Some(pid);
+        Some(owner.clone())
+    }
+
+    /// Remove every claim held by `pid`.
+    pub fn release(&mut self, pid: u32) -> bool {
+        let before = self.claims.len();
+        self.claims.retain(|c| c.pid != pid);
+        self.claims.len() != before
+    }
+
+    /// One polling step of a long-running owner such as the desktop app.
+    ///
+    /// Honours a pending takeover request by dropping the claim, stays out
+    /// of the way while another process holds `device`, and otherwise
+    /// claims it or refreshes the heartbeat. Returns the process the caller
+    /// is yielding to, if any, and whether the registry changed.
+    fn tick(
+        &mut self,
+        device: Option<&str>,
+        pid: u32,
+        role: Role,
+        now: u64,
+    ) -> (Option<Claim>, bool) {
+        let mut changed = self.prune(now);
+
+        let requester = self
+            .claims
+            .iter()
+            .find(|c| c.pid == pid)
+            .and_then(|c| c.takeover_requested_by);
+        if let Some(requester) = requester {
+            self.release(pid);
+            let placeholder = Claim {
+                device: device.unwrap_or_default().to_string(),
+                pid: requester,
+                role: Role::Cli,
+                since: now,
+                heartbeat: now,
+                takeover_requested_by: None,
+            };
+            // The requester claims right after; report it as the owner.
+            let owner = device
+                .and_then(|d| self.owner(d, pid, now).cloned())
+                .unwrap_or(placeholder);
+            return (Some(owner), true);
+        }
+
+        let Some(device) = device else {
+            return (None, self.release(pid) || changed);
+        };
+        if let Some(owner) = self.owner(device, pid, now) {
+            let owner = owner.clone();
+            return (Some(owner), self.release(pid) || changed);
+        }
+
+        let due = self
+            .claims
+            .iter()
+            .find(|c| c.pid == pid && c.device == device)
+            .is_none_or(|c| now.saturating_sub(c.heartbeat) >= HEARTBEAT_SECS);
+        if due {
+            let _ = self.claim(device, pid, role, now);
+            changed = true;
+        }
+        (None, changed)
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn registry_dir() -> Option<PathBuf> {
+    settings::project_dirs().map(|dirs| {
+        dirs.runtime_dir()
+            .unwrap_or_else(|| dirs.data_local_dir())
+            .to_path_buf()
+    })
+}
+
+/// Removes the lock file when dropped.
+struct LockGuard(PathBuf);
+
+impl Drop for LockGuard {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+fn lock(dir: &std::path::Path) -> Result<LockGuard, String> {
+    let path = dir.join("devices.lock");
+    let deadline = SystemTime::now() + Duration::from_millis(LOCK_STALE_MS * 2);
+    loop {
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(_) => return Ok(LockGuard(path)),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                let stale = fs::metadata(&path)
+                    .and_then(|m| m.modified())
+                    .ok()
+                    .and_then(|t| t.elapsed().ok())
+                    .is_some_and(|age| age >= Duration::from_millis(LOCK_STALE_MS));
+                if stale {
+                    log::warn!("Removing stale device registry lock {}", path.display());
+                    let _ = fs::remove_file(&path);
+                } else if SystemTime::now() >= deadline {
+                    return Err("Timed out waiting for the device registry lock".into());
+                } else {
+                    thread::sleep(Duration::from_millis(LOCK_RETRY_MS));
+                }
+            }
+            Err(e) => return Err(format!("Could not lock {}: {}", path.display(), e)),
+        }
+    }
+}
+
+/// Load the registry under the lock, apply `f`, and write it back when `f`
+/// reports a change.
+fn update<T>(f: impl FnOnce(&mut Registry, u64) -> (T, bool)) -> Result<T, String> {
+    let dir = registry_dir().ok_or("Could not determine the runtime directory")?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Could not create {}: {}", dir.display(), e))?;
+    let _guard = lock(&dir)?;
+
+    let path = dir.join("devices.json");
+    let mut registry: Registry = fs::read_to_string(&path)
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default();
+    let (result, changed) = f(&mut registry, now_secs());
+    if changed {
+        let text = serde_json::to_string_pretty(&registry).map_err(|e| e.to_string())?;
+        let tmp = dir.join("devices.json.tmp");
+        fs::write(&tmp, text).map_err(|e| format!("Could not write {}: {}", tmp.display(), e))?;
+        fs::rename(&tmp, &path)
+            .map_err(|e| format!("Could not write {}: {}", path.display(), e))?;
+    }
+    Ok(result)
+}
+
+/// Desktop app polling step: heartbeat the claim on `device` (the current
+/// fingerprint, `None` when no key is attached) and honour takeover
+/// requests. Returns the process the app must leave the device to.
+///
+/// Registry errors are logged and treated as "not yielding", so a read-only
+/// runtime directory never locks the app out of its own device.
+pub fn gui_tick(device: Option<&str>) -> Option<Claim> {
+    let pid = std::process::id();
+    let yielded = match update(|registry, now| registry.tick(device, pid, Role::Gui, now)) {
+        Ok(yielded) => yielded,
+        Err(e) => {
+            log::warn!("Device registry unavailable: {}", e);
+            None
+        }
+    };
+    *YIELDED_TO.lock().unwrap_or_else(|e| e.into_inner()) = yielded.clone();
+    yielded
+}
+
+/// The process the desktop app is leaving the device to, as of the last
+/// [`gui_tick`]. Device operations check it before touching the key.
+pub fn yielded_to() -> Option<Claim> {
+    YIELDED_TO.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Claim `device` for a headless command. If the desktop app owns it, ask
+/// the app to step aside and wait up to a few seconds, printing progress to
+/// stderr. Fails with a user-facing message when the device stays busy.
+pub fn claim_for_cli(device: &str) -> Result<(), String> {
+    let pid = std::process::id();
+    let first = update(|registry, now| {
+        let result = registry.claim(device, pid, Role::Cli, now);
+        let changed = result.is_ok();
+        (result, changed)
+    });
+    let owner = match first {
+        Ok(Ok(())) => return Ok(()),
+        Ok(Err(owner)) => owner,
+        Err(e) => {
+            log::warn!("Device registry unavailable: {}", e);
+            return Ok(());
+        }
+    };
+    if owner.role != Role::Gui {
+        return Err(format!(
+            "The key is in use by {} (pid {}). Wait for it to finish and try again.",
+            owner.role.describe(),
+            owner.pid
+        ));
+    }
+
+    eprintln!(
+        "picoforge: the key is in use by {} (pid {}); asking it to pause...",
+        owner.role.describe(),
+        owner.pid
+    );
+    update(|registry, now| ((), registry.request_takeover(device, pid, now).is_some()))?;
+
+    let deadline = SystemTime::now() + Duration::from_millis(TAKEOVER_WAIT_MS);
+    while SystemTime::now() < deadline {
+        thread::sleep(Duration::from_millis(200));
+        let claimed = update(|registry, now| {
+            let ok = registry.claim(device, pid, Role::Cli, now).is_ok();
+            (ok, ok)
+        })?;
+        if claimed {
+            eprintln!("picoforge: the desktop app paused; continuing.");
+            return Ok(());
+        }
+    }
+    update(|registry, _| {
+        let mut changed = false;
+        for claim in registry.claims.iter_mut() {
+            if claim.takeover_requested_by == Some(pid) {
+                claim.takeover_requested_by = None;
+                changed = true;
+            }
+        }
+        ((), changed)
+    })?;
+    Err(format!(
+        "The PicoForge desktop app (pid {}) did not release the key. \
+         Finish what it is doing, or close it, and try again.",
+        owner.pid
+    ))
+}
+
+/// Drop every claim held by this process.
+pub fn release() {
+    let pid = std::process::id();
+    if let Err(e) = update(|registry, _| ((), registry.release(pid))) {
+        log::warn!("Could not release device registry claim: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = "2e8a:10fe:ABCDEF";
+
+    #[test]
+    fn test_claim_conflicts_with_fresh_owner_only() {
+        let mut registry = Registry::default();
+        assert!(registry.claim(KEY, 1, Role::Gui, 100).is_ok());
+        let owner = registry.claim(KEY, 2, Role::Cli, 105).unwrap_err();
+        assert_eq!(owner.pid, 1);
+
+        // The GUI stopped heartbeating: its claim is abandoned.
+        assert!(
+            registry
+                .claim(KEY, 2, Role::Cli, 100 + STALE_AFTER_SECS)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_takeover_handshake() {
+        let mut registry = Registry::default();
+        assert_eq!(registry.tick(Some(KEY), 1, Role::Gui, 100), (None, true));
+        // Heartbeat is not rewritten on every tick.
+        assert_eq!(registry.tick(Some(KEY), 1, Role::Gui, 101), (None, false));
+
+        assert_eq!(registry.request_takeover(KEY, 2, 101).unwrap().pid, 1);
+        let (yielded, changed) = registry.tick(Some(KEY), 1, Role::Gui, 102);
+        assert_eq!(yielded.map(|c| c.pid), Some(2));
+        assert!(changed);
+
+        assert!(registry.claim(KEY, 2, Role::Cli, 102).is_ok());
+        let (yielded, _) = registry.tick(Some(KEY), 1, Role::Gui, 103);
+        assert_eq!(yielded.map(|c| c.role), Some(Role::Cli));
+
+        assert!(registry.release(2));
+        assert_eq!(registry.tick(Some(KEY), 1, Role::Gui, 104), (None, true));
+        assert_eq!(registry.claims.len(), 1);
+    }
+}
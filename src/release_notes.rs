@@ -0,0 +1,280 @@
+//! Firmware release notes from GitHub, cached for offline use.
+//!
+//! Only pico-fido is covered: its release tags (`v7.6`) match the version the
+//! key reports. RS-Key reports its SDK version and LK-ONE is released
+//! elsewhere, so neither can be matched against a release list.
+//!
+//! Nothing is fetched unless the user opens the release notes. Each
+//! successful fetch is written to the platform cache directory; when GitHub
+//! cannot be reached the last cached list is shown instead, marked with the
+//! time it was fetched.
+
+use crate::hal::common::version::FirmwareVersion;
+use crate::hal::types::FirmwareType;
+use crate::settings;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// GitHub repository of pico-fido releases.
+const PICO_FIDO_REPO: &str = "polhenarejos/pico-fido";
+
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// One published release.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Release {
+    /// Git tag, e.g. `v7.6`.
+    pub tag_name: String,
+    /// Release title; often just the tag.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Release notes in Markdown.
+    #[serde(default)]
+    pub body: Option<String>,
+    /// Release page on GitHub.
+    pub html_url: String,
+    /// Publication time, ISO 8601.
+    #[serde(default)]
+    pub published_at: Option<String>,
+    /// Unpublished draft.
+    #[serde(default)]
+    pub draft: bool,
+    /// Marked as a pre-release on GitHub.
+    #[serde(default)]
+    pub prerelease: bool,
+}
+
+impl Release {
+    /// Version from the tag, if it is one.
+    pub fn version(&self) -> Option<FirmwareVersion> {
+        FirmwareVersion::parse(self.tag_name.trim_start_matches(['v', 'V']))
+    }
+}
+
+/// Cached release list.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct CachedReleases {
+    /// When the list was fetched, in UNIX seconds.
+    fetched_at: u64,
+    releases: Vec<Release>,
+}
+
+/// Releases newer than the installed firmware.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReleaseNotes {
+    /// Newer stable releases, newest first. Empty when up to date.
+    pub newer: Vec<Release>,
+    /// Newest stable release overall.
+    pub latest: Option<Release>,
+    /// When the list was fetched, in UNIX seconds.
+    pub fetched_at: u64,
+    /// `Some(reason)` when GitHub could not be reached and the cache was used.
+    pub offline: Option<String>,
+}
+
+/// The release repository for a firmware, if its versions match release tags.
+pub fn repository(firmware: &FirmwareType) -> Option<&'static str> {
+    match firmware {
+        FirmwareType::PicoFido => Some(PICO_FIDO_REPO),
+        _ => None,
+    }
+}
+
+fn key(version: &FirmwareVersion) -> (u16, u16, u16) {
+    (version.major, version.minor, version.patch)
+}
+
+/// Stable releases newer than `installed`, newest first. Drafts, pre-releases
+/// and tags that are not versions are skipped.
+pub fn newer_than(releases: &[Release], installed: &FirmwareVersion) -> Vec<Release> {
+    let mut newer: Vec<(FirmwareVersion, Release)> = releases
+        .iter()
+        .filter(|r| !r.draft && !r.prerelease)
+        .filter_map(|r| r.version().map(|v| (v, r.clone())))
+        .filter(|(v, _)| key(v) > key(installed))
+        .collect();
+    newer.sort_by(|(a, _), (b, _)| key(b).cmp(&key(a)));
+    newer.into_iter().map(|(_, r)| r).collect()
+}
+
+/// Newest stable release with a version tag.
+fn latest(releases: &[Release]) -> Option<Release> {
+    releases
+        .iter()
+        .filter(|r| !r.draft && !r.prerelease)
+        .filter_map(|r| r.version().map(|v| (v, r)))
+        .max_by(|(a, _), (b, _)| key(a).cmp(&key(b)))
+        .map(|(_, r)| r.clone())
+}
+
+fn cache_path(repo: &str) -> Option<PathBuf> {
+    let dirs = settings::project_dirs()?;
+    Some(
+        dirs.cache_dir()
+            .join("release-notes")
+            .join(format!("{}.json", repo.replace('/', "-"))),
+    )
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn fetch(repo: &str) -> Result<Vec<Release>, String> {
+    let url = format!("https://api.github.com/repos/{}/releases?per_page=50", repo);
+    let body = ureq::get(&url)
+        .timeout(FETCH_TIMEOUT)
+        .set("Accept", "application/vnd.github+json")
+        .set(
+            "User-Agent",
+            concat!("PicoForge/", env!("CARGO_PKG_VERSION")),
+        )
+        .call()
+        .map_err(|e| e.to_string())?
+        .into_string()
+        .map_err(|e| e.to_string())?;
+    serde_json::from_str(&body).map_err(|e| format!("Unexpected response from GitHub: {}", e))
+}
+
+fn read_cache(repo: &str) -> Option<CachedReleases> {
+    let text = fs::read_to_string(cache_path(repo)?).ok()?;
+    serde_json::from_str(&text).ok()
+}
+
+fn write_cache(repo: &str, cached: &CachedReleases) {
+    let Some(path) = cache_path(repo) else {
+        return;
+    };
+    let result = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| {
+            let json = serde_json::to_string(cached).map_err(std::io::Error::other)?;
+            fs::write(&path, json)
+        });
+    if let Err(e) = result {
+        log::warn!("Could not cache release notes at {:?}: {}", path, e);
+    }
+}
+
+/// How long ago `fetched_at` (UNIX seconds) was, e.g. for the offline note.
+pub fn age(fetched_at: u64) -> String {
+    let hours = now().saturating_sub(fetched_at) / 3600;
+    match hours {
+        0 => "less than an hour ago".to_string(),
+        1..=47 => format!("{} hour{} ago", hours, if hours == 1 { "" } else { "s" }),
+        _ => format!("{} days ago", hours / 24),
+    }
+}
+
+/// Release notes between `installed` and the latest release. Blocking; fetches
+/// from GitHub and falls back to the cache.
+pub fn load(firmware: &FirmwareType, installed: &str) -> Result<ReleaseNotes, String> {
+    let repo = repository(firmware)
+        .ok_or_else(|| format!("Release notes are not available for {} firmware", firmware))?;
+    let installed = FirmwareVersion::parse(installed)
+        .ok_or_else(|| format!("Cannot compare firmware version \"{}\"", installed))?;
+
+    let (cached, offline) = match fetch(repo) {
+        Ok(releases) => {
+            log::info!("Fetched {} releases of {}", releases.len(), repo);
+            let cached = CachedReleases {
+                fetched_at: now(),
+                releases,
+            };
+            write_cache(repo, &cached);
+            (cached, None)
+        }
+        Err(e) => {
+            log::warn!("Could not fetch releases of {}: {}", repo, e);
+            let cached = read_cache(repo).ok_or_else(|| {
+                format!(
+                    "Could not reach GitHub ({}) and no release notes are cached yet",
+                    e
+                )
+            })?;
+            (cached, Some(e))
+        }
+    };
+
+    Ok(ReleaseNotes {
+        newer: newer_than(&cached.releases, &installed),
+        latest: latest(&cached.releases),
+        fetched_at: cached.fetched_at,
+        offline,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn release(tag: &str) -> Release {
+        Release {
+            tag_name: tag.to_string(),
+            name: None,
+            body: Some(format!("Notes for {}", tag)),
+            html_url: format!("https://github.com/{}/releases/tag/{}", PICO_FIDO_REPO, tag),
+            published_at: None,
+            draft: false,
+            prerelease: false,
+        }
+    }
+
+    #[test]
+    fn test_parses_github_payload() {
+        let json = r#"[{"tag_name":"v7.6","name":"Pico Fido v7.6","body":"- Fixes","html_url":"https://example.invalid","published_at":"2025-01-01T00:00:00Z","draft":false,"prerelease":false,"assets":[]}]"#;
+        let releases: Vec<Release> = serde_json::from_str(json).unwrap();
+        assert_eq!(releases[0].tag_name, "v7.6");
+        assert_eq!(
+            releases[0].version().map(|v| (v.major, v.minor)),
+            Some((7, 6))
+        );
+    }
+
+    #[test]
+    fn test_newer_than_is_sorted_and_exclusive() {
+        let releases = vec![
+            release("v7.2"),
+            release("v7.6"),
+            release("v7.4"),
+            release("v6.4"),
+        ];
+        let installed = FirmwareVersion::parse("7.2").unwrap();
+        let tags: Vec<_> = newer_than(&releases, &installed)
+            .into_iter()
+            .map(|r| r.tag_name)
+            .collect();
+        assert_eq!(tags, vec!["v7.6", "v7.4"]);
+    }
+
+    #[test]
+    fn test_skips_prereleases_and_odd_tags() {
+        let mut beta = release("v8.0");
+        beta.prerelease = true;
+        let releases = vec![beta, release("nightly"), release("v7.4")];
+        let installed = FirmwareVersion::parse("7.0").unwrap();
+        assert_eq!(newer_than(&releases, &installed).len(), 1);
+        assert_eq!(latest(&releases).map(|r| r.tag_name), Some("v7.4".into()));
+    }
+
+    #[test]
+    fn test_up_to_date() {
+        let releases = vec![release("v7.4")];
+        let installed = FirmwareVersion::parse("7.4").unwrap();
+        assert!(newer_than(&releases, &installed).is_empty());
+    }
+
+    #[test]
+    fn test_only_pico_fido_has_a_repository() {
+        assert_eq!(repository(&FirmwareType::PicoFido), Some(PICO_FIDO_REPO));
+        assert_eq!(repository(&FirmwareType::RSKey), None);
+        assert_eq!(repository(&FirmwareType::Unknown), None);
+    }
+}
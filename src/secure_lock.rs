@@ -0,0 +1,154 @@
+//! Host keys for secure-locked security keys.
+//!
+//! Secure lock (see [`crate::hal::fido::secure_lock`]) keeps a key's device
+//! secret encrypted under a 32-byte host key, which the key asks for again
+//! after every power cycle. PicoForge creates one host key per security key
+//! when secure lock is first enabled and keeps it in the platform data
+//! directory as `secure-lock/<serial>.key`, readable only by the current
+//! user. The file is the only copy on this machine: a secure-locked key whose
+//! host key is lost stays locked until it is reset. The app-data archive
+//! ([`crate::backup`]) carries every host key, so export one before moving
+//! to another machine.
+
+use crate::hal::fido::secure_lock::HOST_KEY_LEN;
+use crate::provisioning::write_private;
+use crate::settings;
+use ring::rand::{SecureRandom, SystemRandom};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// A host key.
+pub type HostKey = [u8; HOST_KEY_LEN];
+
+/// File name of the host key for `serial`, or `None` when the serial does
+/// not identify a key.
+fn file_name(serial: &str) -> Option<String> {
+    let serial: String = serial
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+        .collect();
+    if serial.is_empty() || serial.eq_ignore_ascii_case("unknown") {
+        return None;
+    }
+    Some(format!("{}.key", serial))
+}
+
+fn key_dir() -> Result<PathBuf, String> {
+    let dirs = settings::project_dirs().ok_or("Could not determine the data directory")?;
+    Ok(dirs.data_local_dir().join("secure-lock"))
+}
+
+fn key_path(serial: &str) -> Result<PathBuf, String> {
+    let name = file_name(serial)
+        .ok_or("This key does not report a serial number, so its host key cannot be stored")?;
+    Ok(key_dir()?.join(name))
+}
+
+/// The stored host key for `serial`, if there is one.
+pub fn load_key(serial: &str) -> Result<Option<HostKey>, String> {
+    let path = key_path(serial)?;
+    match fs::read(&path) {
+        Ok(bytes) => HostKey::try_from(bytes.as_slice()).map(Some).map_err(|_| {
+            format!(
+                "{} is corrupt; the key cannot be unlocked with it",
+                path.display()
+            )
+        }),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("Could not read {}: {}", path.display(), e)),
+    }
+}
+
+/// Every stored host key, by serial. Files that are not host keys are
+/// skipped.
+pub fn all_keys() -> Result<BTreeMap<String, HostKey>, String> {
+    let dir = key_dir()?;
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+        Err(e) => return Err(format!("Could not read {}: {}", dir.display(), e)),
+    };
+
+    let mut keys = BTreeMap::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Some(serial) = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_suffix(".key"))
+        else {
+            continue;
+        };
+        if file_name(serial).as_deref() != path.file_name().and_then(|name| name.to_str()) {
+            continue;
+        }
+        let bytes =
+            fs::read(&path).map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+        match HostKey::try_from(bytes.as_slice()) {
+            Ok(key) => {
+                keys.insert(serial.to_string(), key);
+            }
+            Err(_) => log::warn!("Skipping corrupt host key {:?}", path),
+        }
+    }
+    Ok(keys)
+}
+
+/// Store `key` as the host key of `serial`. A different key already stored
+/// for it is kept as `<serial>.key.bak`, since it may be the one the
+/// security key still expects.
+pub fn store_key(serial: &str, key: &HostKey) -> Result<(), String> {
+    let path = key_path(serial)?;
+    match load_key(serial)? {
+        Some(existing) if existing == *key => return Ok(()),
+        Some(_) => {
+            let backup = path.with_extension("key.bak");
+            fs::rename(&path, &backup)
+                .map_err(|e| format!("Could not back up {}: {}", path.display(), e))?;
+        }
+        None => {}
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Could not create {}: {}", parent.display(), e))?;
+    }
+    write_private(&path, key).map_err(|e| format!("Could not write {}: {}", path.display(), e))
+}
+
+/// The host key for `serial`, generating and storing a new one if there is
+/// none yet.
+pub fn load_or_create_key(serial: &str) -> Result<HostKey, String> {
+    if let Some(key) = load_key(serial)? {
+        return Ok(key);
+    }
+
+    let mut key = [0u8; HOST_KEY_LEN];
+    SystemRandom::new()
+        .fill(&mut key)
+        .map_err(|_| "Could not generate a host key".to_string())?;
+
+    store_key(serial, &key)?;
+    log::info!("Created secure lock host key for {}", serial);
+
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_name_keeps_serial_safe() {
+        assert_eq!(file_name("E6614C31").as_deref(), Some("E6614C31.key"));
+        assert_eq!(file_name("../ab/c d").as_deref(), Some("abcd.key"));
+    }
+
+    #[test]
+    fn test_file_name_rejects_missing_serial() {
+        assert!(file_name("").is_none());
+        assert!(file_name("Unknown").is_none());
+        assert!(file_name("/..").is_none());
+    }
+}
@@ -0,0 +1,142 @@
+//! Persistent application settings.
+//!
+//! Stored as JSON in the platform config directory
+//! (e.g. `~/.config/picoforge/settings.json` on Linux). A missing or
+//! unreadable file yields [`Settings::default`]; loading never fails hard,
+//! because settings are read before logging is up.
+
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+/// User-configurable application settings.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Settings {
+    /// Custom directory for `picoforge.log` and `audit.log`.
+    /// `None` uses the platform data directory.
+    pub log_dir: Option<PathBuf>,
+    /// Count feature use and error classes locally (see [`crate::metrics`]).
+    pub metrics_enabled: bool,
+    /// The onboarding tour was finished or skipped; it no longer opens on
+    /// launch but can be replayed from Help.
+    pub onboarding_done: bool,
+    /// Ids of the [`crate::ui::quick_actions`] shown in the title bar.
+    pub pinned_actions: Vec<String>,
+    /// UI scale in percent (see [`UI_SCALES`]). `None` is 100%.
+    pub ui_scale: Option<u16>,
+    /// Play [`crate::cues`] when the key wants a touch and when an operation
+    /// finishes.
+    pub sound_cues: bool,
+    /// Check the connected firmware against the published security
+    /// advisories (see [`crate::advisories`]).
+    pub advisory_checks: bool,
+    /// Start every launch in safe mode, as `--safe-mode` does (see
+    /// [`crate::startup::set_safe_mode`]).
+    pub safe_mode: bool,
+    /// Ask for a touch and the PIN before a VID/PID change is written
+    /// (Configuration → Identity).
+    pub identity_protection: bool,
+}
+
+/// UI scale steps offered in About → Appearance and by the zoom shortcuts,
+/// in percent.
+pub const UI_SCALES: &[u16] = &[90, 100, 110, 125, 150];
+
+impl Settings {
+    /// The UI scale in percent, kept within [`UI_SCALES`]' range in case the
+    /// file was edited by hand.
+    pub fn ui_scale_percent(&self) -> u16 {
+        let (min, max) = (UI_SCALES[0], UI_SCALES[UI_SCALES.len() - 1]);
+        self.ui_scale.unwrap_or(100).clamp(min, max)
+    }
+}
+
+/// The next scale step above (`larger`) or below `current`, staying at the
+/// ends of [`UI_SCALES`].
+pub fn step_ui_scale(current: u16, larger: bool) -> u16 {
+    let next = if larger {
+        UI_SCALES.iter().find(|&&step| step > current)
+    } else {
+        UI_SCALES.iter().rev().find(|&&step| step < current)
+    };
+    next.copied().unwrap_or(current)
+}
+
+/// Platform project directories for PicoForge.
+pub fn project_dirs() -> Option<ProjectDirs> {
+    ProjectDirs::from("in", "suyogtandel", "picoforge")
+}
+
+/// Directory holding `settings.json`.
+pub fn config_dir() -> Option<PathBuf> {
+    project_dirs().map(|dirs| dirs.config_dir().to_path_buf())
+}
+
+fn settings_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("settings.json"))
+}
+
+/// Load settings from disk, returning the defaults and a reason if that fails.
+///
+/// A missing file is not an error.
+pub fn load() -> (Settings, Option<String>) {
+    let Some(path) = settings_path() else {
+        return (
+            Settings::default(),
+            Some("Could not determine the settings directory".into()),
+        );
+    };
+
+    match fs::read_to_string(&path) {
+        Ok(text) => match serde_json::from_str(&text) {
+            Ok(settings) => (settings, None),
+            Err(e) => (
+                Settings::default(),
+                Some(format!("Could not parse {}: {}", path.display(), e)),
+            ),
+        },
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => (Settings::default(), None),
+        Err(e) => (
+            Settings::default(),
+            Some(format!("Could not read {}: {}", path.display(), e)),
+        ),
+    }
+}
+
+/// Write settings to disk, creating the config directory if needed.
+pub fn save(settings: &Settings) -> Result<(), String> {
+    let path = settings_path().ok_or("Could not determine the settings directory")?;
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Could not create {}: {}", parent.display(), e))?;
+    }
+
+    let text = serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?;
+    fs::write(&path, text).map_err(|e| format!("Could not write {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ui_scale_steps_and_clamps() {
+        assert_eq!(Settings::default().ui_scale_percent(), 100);
+        let hand_edited = Settings {
+            ui_scale: Some(400),
+            ..Settings::default()
+        };
+        assert_eq!(hand_edited.ui_scale_percent(), 150);
+
+        assert_eq!(step_ui_scale(100, true), 110);
+        assert_eq!(step_ui_scale(100, false), 90);
+        assert_eq!(step_ui_scale(150, true), 150);
+        assert_eq!(step_ui_scale(90, false), 90);
+        // Off-step values move to the neighbouring step.
+        assert_eq!(step_ui_scale(115, true), 125);
+        assert_eq!(step_ui_scale(115, false), 110);
+    }
+}
@@ -0,0 +1,240 @@
+//! Startup self-check pipeline.
+//!
+//! Runs once in `main` before the window opens and records the outcome of each
+//! step: settings, advanced tunables, log files, hidapi, PC/SC, device permissions, and whether
+//! the previous session exited cleanly. Previously a missing `pcscd` or an
+//! unreadable hidraw node only showed up as features quietly not working; the
+//! UI now shows [`results`] in a startup status dialog when any step fails.
+//!
+//! Checks are cheap (enumeration only, no device I/O beyond opening the HID
+//! interface), so running them synchronously does not delay startup.
+//!
+//! In safe mode ([`set_safe_mode`], from `--safe-mode` or the About screen
+//! setting) the HID and PC/SC checks are skipped, and the UI does not read
+//! or watch for keys until the user connects. A key that floods keepalives
+//! or a permission problem that crashes the read then cannot lock the app
+//! up at launch.
+
+use crate::hal::io;
+use crate::logging::{self, LogStatus};
+use crate::settings;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Outcome of a single startup check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    /// The check passed.
+    Ok,
+    /// Something is degraded but the app is usable.
+    Warning,
+    /// A subsystem is unavailable; related features will not work.
+    Failed,
+}
+
+/// One step of the startup pipeline.
+#[derive(Debug, Clone)]
+pub struct StartupCheck {
+    /// Short name shown in the status dialog.
+    pub name: &'static str,
+    /// Outcome of the check.
+    pub status: CheckStatus,
+    /// What was found, or what went wrong and how to fix it.
+    pub detail: String,
+}
+
+impl StartupCheck {
+    fn new(name: &'static str, status: CheckStatus, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status,
+            detail: detail.into(),
+        }
+    }
+}
+
+static RESULTS: OnceLock<Vec<StartupCheck>> = OnceLock::new();
+
+static SAFE_MODE: AtomicBool = AtomicBool::new(false);
+
+/// Start in safe mode: no device I/O until the user connects. Call before
+/// [`run`].
+pub fn set_safe_mode(enabled: bool) {
+    SAFE_MODE.store(enabled, Ordering::Relaxed);
+    if enabled {
+        log::warn!("Safe mode: devices are not read until the user connects");
+    }
+}
+
+/// Whether the app was started in safe mode.
+pub fn safe_mode() -> bool {
+    SAFE_MODE.load(Ordering::Relaxed)
+}
+
+/// Results of [`run`], or an empty slice if it has not run yet.
+pub fn results() -> &'static [StartupCheck] {
+    RESULTS.get().map(Vec::as_slice).unwrap_or(&[])
+}
+
+/// Whether any check ended in a warning or failure.
+pub fn has_problems() -> bool {
+    results().iter().any(|c| c.status != CheckStatus::Ok)
+}
+
+/// Run every check and store the results. Call once, after logging is set up.
+pub fn run(settings_error: Option<String>, tunables_error: Option<String>) {
+    let mut checks = vec![
+        check_settings(settings_error),
+        check_tunables(tunables_error),
+        check_logging(),
+        check_previous_session(),
+    ];
+    if safe_mode() {
+        checks.push(StartupCheck::new(
+            "Devices",
+            CheckStatus::Ok,
+            "Not checked in safe mode",
+        ));
+    } else {
+        checks.push(check_hid());
+        checks.push(check_pcsc());
+    }
+
+    for check in &checks {
+        match check.status {
+            CheckStatus::Ok => log::info!("Startup check {}: {}", check.name, check.detail),
+            CheckStatus::Warning => log::warn!("Startup check {}: {}", check.name, check.detail),
+            CheckStatus::Failed => log::error!("Startup check {}: {}", check.name, check.detail),
+        }
+    }
+
+    let _ = RESULTS.set(checks);
+}
+
+fn check_settings(error: Option<String>) -> StartupCheck {
+    match error {
+        None => StartupCheck::new("Settings", CheckStatus::Ok, "Loaded"),
+        Some(e) => StartupCheck::new(
+            "Settings",
+            CheckStatus::Warning,
+            format!("{}. Using defaults.", e),
+        ),
+    }
+}
+
+fn check_tunables(error: Option<String>) -> StartupCheck {
+    match error {
+        None => StartupCheck::new("Advanced tunables", CheckStatus::Ok, "Loaded"),
+        Some(e) => StartupCheck::new(
+            "Advanced tunables",
+            CheckStatus::Warning,
+            format!("{}. Using defaults; fix the file under About.", e),
+        ),
+    }
+}
+
+fn check_logging() -> StartupCheck {
+    match logging::log_status() {
+        Some(LogStatus::File(dir)) => StartupCheck::new(
+            "Log files",
+            CheckStatus::Ok,
+            format!("Writing to {}", dir.display()),
+        ),
+        Some(LogStatus::ConsoleOnly { reason, .. }) => StartupCheck::new(
+            "Log files",
+            CheckStatus::Warning,
+            format!(
+                "{}. Logging to the console only; choose another log directory under About.",
+                reason
+            ),
+        ),
+        None => StartupCheck::new("Log files", CheckStatus::Warning, "Logging not initialised"),
+    }
+}
+
+fn check_hid() -> StartupCheck {
+    match io::probe_hid() {
+        Err(e) => StartupCheck::new(
+            "USB HID",
+            CheckStatus::Failed,
+            format!("{}. FIDO features are unavailable.", e),
+        ),
+        Ok(probe) if !probe.inaccessible.is_empty() => StartupCheck::new(
+            "Device permissions",
+            CheckStatus::Failed,
+            format!(
+                "A FIDO key is connected but cannot be opened: {}. See Help → Permissions.",
+                probe.inaccessible.join("; ")
+            ),
+        ),
+        Ok(probe) => StartupCheck::new(
+            "USB HID",
+            CheckStatus::Ok,
+            format!("{} FIDO interface(s) accessible", probe.fido_interfaces),
+        ),
+    }
+}
+
+fn check_pcsc() -> StartupCheck {
+    match io::probe_pcsc() {
+        Ok(readers) => StartupCheck::new(
+            "PC/SC",
+            CheckStatus::Ok,
+            format!("Service available, {} reader(s)", readers),
+        ),
+        Err(e) => StartupCheck::new(
+            "PC/SC",
+            CheckStatus::Warning,
+            format!(
+                "{}. Rescue-mode configuration is unavailable; is pcscd running?",
+                e
+            ),
+        ),
+    }
+}
+
+// ── Session marker ──────────────────────────────────────────────────────────
+//
+// A marker file is created on launch and removed on a clean quit. Finding it
+// at the next launch means the previous session crashed or was killed.
+
+fn session_marker_path() -> Option<PathBuf> {
+    settings::project_dirs().map(|dirs| dirs.data_local_dir().join("session.lock"))
+}
+
+fn check_previous_session() -> StartupCheck {
+    let Some(path) = session_marker_path() else {
+        return StartupCheck::new(
+            "Previous session",
+            CheckStatus::Ok,
+            "Session tracking unavailable",
+        );
+    };
+
+    let crashed = path.exists();
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Err(e) = fs::write(&path, std::process::id().to_string()) {
+        log::warn!("Could not write session marker {:?}: {}", path, e);
+    }
+
+    if crashed {
+        StartupCheck::new(
+            "Previous session",
+            CheckStatus::Warning,
+            "PicoForge did not shut down cleanly last time. If a write was in progress, re-check the device configuration. The log file has details.",
+        )
+    } else {
+        StartupCheck::new("Previous session", CheckStatus::Ok, "Closed cleanly")
+    }
+}
+
+/// Remove the session marker. Call when the application quits normally.
+pub fn end_session() {
+    if let Some(path) = session_marker_path() {
+        let _ = fs::remove_file(path);
+    }
+}
@@ -0,0 +1,389 @@
+//! Synced passkeys from a password manager export, compared with the key.
+//!
+//! A passkey kept in a password manager syncs across devices; one on a
+//! security key does not, and is lost with the key. Neither is a backup of
+//! the other unless both exist for the same site. [`compare`] lines up the
+//! relying parties of an export against the credentials read from the key
+//! and sorts them into sites with both, sites with only a synced passkey
+//! (no hardware-bound fallback) and sites with only the key (no way in if
+//! it is lost).
+//!
+//! Accepted exports:
+//!
+//! - Bitwarden JSON, which lists passkeys under `login.fido2Credentials`
+//!   with their RP ID.
+//! - CSV from Bitwarden, 1Password or a hand-made sheet. Columns are found
+//!   by header name. A passkey column (`passkey`, `fido2`, ...) limits the
+//!   rows to those with a value in it; without one, every row is taken as a
+//!   passkey, so a CSV export should be filtered to passkey logins first.
+//!
+//! Only relying parties and user names are read; passwords and notes in the
+//! file are ignored and nothing is stored.
+
+use crate::hal::types::StoredCredential;
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::path::Path;
+
+/// Header names of the column holding the relying party, best first.
+const RP_COLUMNS: &[&str] = &["rp_id", "rpid", "relying party", "relying_party"];
+/// Header names of URL columns, used when there is no RP column.
+const URL_COLUMNS: &[&str] = &["login_uri", "url", "urls", "website", "uri"];
+/// Header names of the user name column.
+const USER_COLUMNS: &[&str] = &["login_username", "username", "user name", "user"];
+/// Header names of a column marking which rows have a passkey.
+const PASSKEY_COLUMNS: &[&str] = &[
+    "passkey",
+    "passkeys",
+    "fido2",
+    "login_fido2credentials",
+    "has_passkey",
+];
+
+/// Where an export came from, as far as its layout tells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    BitwardenJson,
+    BitwardenCsv,
+    OnePasswordCsv,
+    Csv,
+}
+
+impl ExportFormat {
+    pub fn label(self) -> &'static str {
+        match self {
+            ExportFormat::BitwardenJson => "Bitwarden JSON",
+            ExportFormat::BitwardenCsv => "Bitwarden CSV",
+            ExportFormat::OnePasswordCsv => "1Password CSV",
+            ExportFormat::Csv => "CSV",
+        }
+    }
+}
+
+/// One passkey listed in an export.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncedPasskey {
+    /// Normalized relying party (host name).
+    pub rp_id: String,
+    pub user_name: String,
+}
+
+/// The passkeys of one export.
+#[derive(Debug, Clone)]
+pub struct SyncedExport {
+    pub format: ExportFormat,
+    pub passkeys: Vec<SyncedPasskey>,
+}
+
+/// Relying parties sorted by where their passkeys live.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Coverage {
+    /// A synced passkey and one on the key.
+    pub both: Vec<String>,
+    /// Only a synced passkey: no hardware-bound fallback.
+    pub synced_only: Vec<String>,
+    /// Only the key: nothing left if it is lost.
+    pub hardware_only: Vec<String>,
+}
+
+/// Read and parse the export at `path`.
+pub fn load(path: &Path) -> Result<SyncedExport, String> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+    parse(&contents)
+}
+
+/// Parse an export, JSON or CSV.
+pub fn parse(contents: &str) -> Result<SyncedExport, String> {
+    let contents = contents.trim_start_matches('\u{feff}');
+    let export = if contents.trim_start().starts_with('{') {
+        parse_bitwarden_json(contents)?
+    } else {
+        parse_csv_export(contents)?
+    };
+    if export.passkeys.is_empty() {
+        return Err(format!(
+            "The {} export lists no passkeys",
+            export.format.label()
+        ));
+    }
+    Ok(export)
+}
+
+fn parse_bitwarden_json(contents: &str) -> Result<SyncedExport, String> {
+    let root: Value =
+        serde_json::from_str(contents).map_err(|e| format!("Not a valid JSON export: {}", e))?;
+    if root.get("encrypted").and_then(Value::as_bool) == Some(true) {
+        return Err("Encrypted exports cannot be read; export unencrypted JSON".into());
+    }
+    let items = root
+        .get("items")
+        .and_then(Value::as_array)
+        .ok_or("Not a Bitwarden export: no items")?;
+
+    let passkeys = items
+        .iter()
+        .filter_map(|item| item.get("login"))
+        .flat_map(|login| {
+            let fallback_user = login
+                .get("username")
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            login
+                .get("fido2Credentials")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(move |credential| {
+                    let rp_id = credential
+                        .get("rpId")
+                        .and_then(Value::as_str)
+                        .and_then(normalize_rp)?;
+                    let user_name = credential
+                        .get("userName")
+                        .and_then(Value::as_str)
+                        .unwrap_or(fallback_user)
+                        .to_string();
+                    Some(SyncedPasskey { rp_id, user_name })
+                })
+        })
+        .collect();
+    Ok(SyncedExport {
+        format: ExportFormat::BitwardenJson,
+        passkeys,
+    })
+}
+
+fn parse_csv_export(contents: &str) -> Result<SyncedExport, String> {
+    let mut rows = parse_csv(contents).into_iter();
+    let header: Vec<String> = rows
+        .next()
+        .ok_or("The export is empty")?
+        .iter()
+        .map(|h| h.trim().to_ascii_lowercase())
+        .collect();
+    let column = |names: &[&str]| {
+        names
+            .iter()
+            .find_map(|name| header.iter().position(|h| h == name))
+    };
+
+    let rp = column(RP_COLUMNS);
+    let url = column(URL_COLUMNS);
+    if rp.is_none() && url.is_none() {
+        return Err("No relying party or URL column in the CSV header".into());
+    }
+    let user = column(USER_COLUMNS);
+    let marker = column(PASSKEY_COLUMNS);
+    let format = if header.iter().any(|h| h == "login_uri") {
+        ExportFormat::BitwardenCsv
+    } else if header.iter().any(|h| h == "title") && url.is_some() {
+        ExportFormat::OnePasswordCsv
+    } else {
+        ExportFormat::Csv
+    };
+
+    let field = |row: &[String], ix: Option<usize>| {
+        ix.and_then(|ix| row.get(ix))
+            .map(|v| v.trim().to_string())
+            .unwrap_or_default()
+    };
+    let passkeys = rows
+        .filter(|row| marker.is_none_or(|_| is_marked(&field(row, marker))))
+        .filter_map(|row| {
+            let rp_id = rp
+                .and_then(|ix| row.get(ix))
+                .map(String::as_str)
+                .and_then(normalize_rp)
+                .or_else(|| {
+                    url.and_then(|ix| row.get(ix))
+                        .and_then(|v| v.split([',', '\n']).find_map(normalize_rp))
+                })?;
+            Some(SyncedPasskey {
+                rp_id,
+                user_name: field(&row, user),
+            })
+        })
+        .collect();
+    Ok(SyncedExport { format, passkeys })
+}
+
+/// Whether a passkey column value says the row has one.
+fn is_marked(value: &str) -> bool {
+    !matches!(
+        value.to_ascii_lowercase().as_str(),
+        "" | "0" | "no" | "false" | "[]"
+    )
+}
+
+/// Rows of RFC 4180 CSV: quoted fields may hold commas, doubled quotes and
+/// line breaks. Blank lines are skipped.
+fn parse_csv(contents: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = contents.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            ('"', true) => quoted = false,
+            ('"', false) if field.is_empty() => quoted = true,
+            (',', false) => row.push(std::mem::take(&mut field)),
+            ('\r', false) => {}
+            ('\n', false) => {
+                row.push(std::mem::take(&mut field));
+                if row.iter().any(|f| !f.is_empty()) {
+                    rows.push(std::mem::take(&mut row));
+                }
+                row.clear();
+            }
+            (c, _) => field.push(c),
+        }
+    }
+    row.push(field);
+    if row.iter().any(|f| !f.is_empty()) {
+        rows.push(row);
+    }
+    rows
+}
+
+/// Host name of an RP ID or URL: lowercase, without scheme, credentials,
+/// port, path or trailing dot. `None` when nothing like a host is left.
+fn normalize_rp(value: &str) -> Option<String> {
+    let value = value.trim();
+    let rest = value.split_once("://").map_or(value, |(_, rest)| rest);
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host = authority.rsplit('@').next()?;
+    let host = host.split(':').next()?.trim_end_matches('.');
+    let host = host.to_ascii_lowercase();
+    let plausible = host.contains('.') || host == "localhost";
+    (plausible && !host.contains(char::is_whitespace)).then_some(host)
+}
+
+/// Whether two RP IDs belong to the same site: equal, or one a subdomain of
+/// the other (a passkey for `example.com` signs in on `login.example.com`).
+fn same_site(a: &str, b: &str) -> bool {
+    a == b || a.ends_with(&format!(".{}", b)) || b.ends_with(&format!(".{}", a))
+}
+
+/// Sort the relying parties of `synced` and `hardware` by where their
+/// passkeys live. Each list is sorted and has no duplicates.
+pub fn compare(synced: &[SyncedPasskey], hardware: &[StoredCredential]) -> Coverage {
+    let synced: BTreeSet<&str> = synced.iter().map(|p| p.rp_id.as_str()).collect();
+    let hardware: BTreeSet<String> = hardware
+        .iter()
+        .filter_map(|c| normalize_rp(&c.rp_id))
+        .collect();
+
+    let mut coverage = Coverage::default();
+    for rp in &synced {
+        if hardware.iter().any(|h| same_site(rp, h)) {
+            coverage.both.push(rp.to_string());
+        } else {
+            coverage.synced_only.push(rp.to_string());
+        }
+    }
+    coverage.hardware_only = hardware
+        .into_iter()
+        .filter(|h| !synced.iter().any(|rp| same_site(rp, h)))
+        .collect();
+    coverage
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn credential(rp_id: &str) -> StoredCredential {
+        StoredCredential {
+            rp_id: rp_id.into(),
+            rp_name: String::new(),
+            user_name: String::new(),
+            user_display_name: String::new(),
+            user_id: String::new(),
+            credential_id: String::new(),
+            algorithm: None,
+            key_type: None,
+            curve: None,
+            public_key: Vec::new(),
+            cred_protect: None,
+        }
+    }
+
+    #[test]
+    fn test_bitwarden_json_reads_fido2_credentials() {
+        let json = r#"{"encrypted": false, "items": [
+            {"login": {"username": "alice", "fido2Credentials": [{"rpId": "GitHub.com"}]}},
+            {"login": {"username": "bob", "fido2Credentials": []}},
+            {"type": 2}
+        ]}"#;
+        let export = parse(json).unwrap();
+        assert_eq!(export.format, ExportFormat::BitwardenJson);
+        assert_eq!(
+            export.passkeys,
+            vec![SyncedPasskey {
+                rp_id: "github.com".into(),
+                user_name: "alice".into(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_csv_uses_passkey_column_and_quoted_fields() {
+        let csv = "Title,Url,Username,Passkey\r\n\
+                   \"Mail, work\",https://mail.example.com/inbox,\"a\"\"b\",yes\r\n\
+                   Forum,https://forum.example.org,c,\r\n";
+        let export = parse(csv).unwrap();
+        assert_eq!(export.format, ExportFormat::OnePasswordCsv);
+        assert_eq!(
+            export.passkeys,
+            vec![SyncedPasskey {
+                rp_id: "mail.example.com".into(),
+                user_name: "a\"b".into(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_csv_without_rp_column_is_refused() {
+        assert!(parse("name,notes\nx,y\n").is_err());
+        assert!(parse("url,username\n,alice\n").is_err());
+    }
+
+    #[test]
+    fn test_normalize_rp_strips_url_parts() {
+        assert_eq!(
+            normalize_rp("https://user@Login.Example.com:8443/a?b#c").as_deref(),
+            Some("login.example.com")
+        );
+        assert_eq!(normalize_rp("example.com.").as_deref(), Some("example.com"));
+        assert_eq!(normalize_rp("not a site"), None);
+    }
+
+    #[test]
+    fn test_compare_matches_subdomains() {
+        let synced = vec![
+            SyncedPasskey {
+                rp_id: "example.com".into(),
+                user_name: String::new(),
+            },
+            SyncedPasskey {
+                rp_id: "synced.org".into(),
+                user_name: String::new(),
+            },
+        ];
+        let hardware = vec![credential("login.example.com"), credential("github.com")];
+        assert_eq!(
+            compare(&synced, &hardware),
+            Coverage {
+                both: vec!["example.com".into()],
+                synced_only: vec!["synced.org".into()],
+                hardware_only: vec!["github.com".into()],
+            }
+        );
+    }
+}
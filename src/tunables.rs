@@ -0,0 +1,568 @@
+//! Advanced tunables from `picoforge.toml`.
+//!
+//! Timeouts, retry counts and similar knobs that are right for the keys we
+//! test with but may be wrong for a slow USB hub, a flaky smart-card reader
+//! or a key that takes long to answer. They live in `picoforge.toml` next to
+//! `settings.json` and are read once at startup, before logging; the raw
+//! editor on the About screen writes the file and the values apply after a
+//! restart.
+//!
+//! Every key is optional and falls back to its default. Unknown keys are an
+//! error, so a typo does not silently do nothing; a file that fails to parse
+//! is ignored as a whole and reported by the startup check.
+//!
+//! A key can carry its own [`DeviceOverrides`] in the inventory. They are
+//! layered over the file for as long as that key is connected; code reading
+//! an overridable value goes through [`hid`], [`user_presence_timeout_ms`],
+//! [`transport_order`] or [`vendor_read_enabled`] rather than [`get`].
+
+use crate::settings;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{OnceLock, RwLock};
+
+/// Documented default file, shown in the editor when none exists yet.
+pub const TEMPLATE: &str = r#"# PicoForge advanced tunables. Every key is optional; delete a line to use
+# its default. Changes apply after restarting PicoForge.
+
+[hid]
+# Whole CTAPHID request/response exchange, in milliseconds.
+total_timeout_ms = 5000
+# Waiting for the first packet of a response.
+response_read_timeout_ms = 2000
+# Waiting for each continuation packet.
+continuation_read_timeout_ms = 500
+# Waiting for the CTAPHID_INIT answer while opening the key.
+init_read_timeout_ms = 100
+# How often to try CTAPHID_INIT before giving up.
+init_attempts = 1
+# Idle time after which the reused connection is checked with CTAPHID_PING
+# before the next operation; 0 checks every time.
+health_check_idle_ms = 2000
+# Extra tries for a request the key answered busy, or for a read it did not
+# answer at all; 0 never retries. The channel is renegotiated after a timeout.
+retries = 2
+# Wait before the first retry, doubled for each further one.
+retry_backoff_ms = 50
+# Longest wait between two tries.
+retry_backoff_max_ms = 1000
+
+[keepalive]
+# How long to keep waiting through KEEPALIVE packets for a touch.
+user_presence_timeout_ms = 30000
+
+[capture]
+# Size at which picoforge.log is rotated, in MiB.
+log_file_max_mb = 10
+# Bytes of each payload shown in --trace-wire lines; 0 shows all.
+wire_trace_max_bytes = 0
+
+[transport]
+# Transports to read the key over, in order. One left out is skipped when
+# reading; writes and the rescue tools still open the transport they need.
+order = ["rescue", "fido"]
+
+[storage]
+# Space PicoForge may use for logs, caches and app data, in MiB.
+quota_mb = 200
+# Warn in the app once this share of the quota is used, in percent.
+warn_percent = 80
+# Rotated logs and cached files older than this are deleted, in days.
+retention_days = 30
+# How often the sizes are checked while PicoForge runs, in minutes.
+check_interval_minutes = 60
+"#;
+
+/// All tunables. Sections mirror the TOML tables.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Tunables {
+    /// `[hid]`: CTAPHID timeouts and retries.
+    pub hid: HidTunables,
+    /// `[keepalive]`: waiting for a touch.
+    pub keepalive: KeepaliveTunables,
+    /// `[capture]`: log and trace sizes.
+    pub capture: CaptureTunables,
+    /// `[transport]`: which transports are probed.
+    pub transport: TransportTunables,
+    /// `[storage]`: disk quota and retention for what PicoForge writes.
+    pub storage: StorageTunables,
+}
+
+/// CTAPHID read timeouts and retries.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct HidTunables {
+    /// Whole request/response exchange.
+    pub total_timeout_ms: u32,
+    /// First packet of a response.
+    pub response_read_timeout_ms: u32,
+    /// Each continuation packet.
+    pub continuation_read_timeout_ms: u32,
+    /// The CTAPHID_INIT answer while opening the key.
+    pub init_read_timeout_ms: u32,
+    /// CTAPHID_INIT tries before opening fails.
+    pub init_attempts: u32,
+    /// Idle time before the reused connection is pinged; `0` pings every time.
+    pub health_check_idle_ms: u32,
+    /// Extra tries for a request answered busy, or a read that timed out.
+    pub retries: u32,
+    /// Wait before the first retry, doubled per further retry.
+    pub retry_backoff_ms: u32,
+    /// Cap on the wait between retries.
+    pub retry_backoff_max_ms: u32,
+}
+
+impl Default for HidTunables {
+    fn default() -> Self {
+        Self {
+            total_timeout_ms: 5000,
+            response_read_timeout_ms: 2000,
+            continuation_read_timeout_ms: 500,
+            init_read_timeout_ms: 100,
+            init_attempts: 1,
+            health_check_idle_ms: 2000,
+            retries: 2,
+            retry_backoff_ms: 50,
+            retry_backoff_max_ms: 1000,
+        }
+    }
+}
+
+/// Waiting for the user while the key sends KEEPALIVE.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct KeepaliveTunables {
+    /// How long operations that need a touch keep waiting.
+    pub user_presence_timeout_ms: u32,
+}
+
+impl Default for KeepaliveTunables {
+    fn default() -> Self {
+        Self {
+            user_presence_timeout_ms: 30_000,
+        }
+    }
+}
+
+/// Log rotation and wire-trace sizes.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct CaptureTunables {
+    /// Rotation size of `picoforge.log`, in MiB.
+    pub log_file_max_mb: u32,
+    /// Payload bytes per wire-trace line; `0` is unlimited.
+    pub wire_trace_max_bytes: usize,
+}
+
+impl Default for CaptureTunables {
+    fn default() -> Self {
+        Self {
+            log_file_max_mb: 10,
+            wire_trace_max_bytes: 0,
+        }
+    }
+}
+
+/// A device transport that can be probed.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Transport {
+    /// Rescue applet over PC/SC.
+    Rescue,
+    /// CTAPHID over USB HID.
+    Fido,
+}
+
+/// Which transports are probed, and in what order.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct TransportTunables {
+    /// Transports to probe, first to last.
+    pub order: Vec<Transport>,
+}
+
+impl Default for TransportTunables {
+    fn default() -> Self {
+        Self {
+            order: vec![Transport::Rescue, Transport::Fido],
+        }
+    }
+}
+
+impl TransportTunables {
+    /// Whether `transport` is probed at all.
+    pub fn enabled(&self, transport: Transport) -> bool {
+        self.order.contains(&transport)
+    }
+}
+
+/// Disk quota and retention enforced by [`crate::janitor`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageTunables {
+    /// Space for logs, caches and app data, in MiB.
+    pub quota_mb: u32,
+    /// Share of the quota at which the app warns, in percent.
+    pub warn_percent: u8,
+    /// Age after which prunable files are deleted, in days.
+    pub retention_days: u32,
+    /// Minutes between checks.
+    pub check_interval_minutes: u32,
+}
+
+impl Default for StorageTunables {
+    fn default() -> Self {
+        Self {
+            quota_mb: 200,
+            warn_percent: 80,
+            retention_days: 30,
+            check_interval_minutes: 60,
+        }
+    }
+}
+
+/// Vendor commands PicoForge sends on its own while reading a key. A
+/// firmware build that misbehaves on one can have it switched off in its
+/// [`DeviceOverrides`], after which the key is read as if it lacked it.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+pub enum VendorRead {
+    /// Legacy PhysicalOptions read (pico-fido ≤ 7.2), also the probe for the
+    /// legacy configuration commands.
+    PhysicalOptions,
+    /// Legacy flash statistics (pico-fido ≤ 7.2).
+    Memory,
+    /// RS-Key CONFIG_READ of the PHY record.
+    RsKeyConfig,
+}
+
+impl VendorRead {
+    pub const ALL: [VendorRead; 3] = [
+        VendorRead::PhysicalOptions,
+        VendorRead::Memory,
+        VendorRead::RsKeyConfig,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            VendorRead::PhysicalOptions => "Legacy physical options",
+            VendorRead::Memory => "Legacy flash statistics",
+            VendorRead::RsKeyConfig => "RS-Key configuration read",
+        }
+    }
+}
+
+/// Connection settings for one key, stored in the inventory by serial.
+///
+/// `None` keeps the value from the file. There is no PIN protocol setting:
+/// PicoForge always uses PIN/UV auth protocol 1, which every CTAP 2 key
+/// implements.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct DeviceOverrides {
+    /// Transports to probe, first to last.
+    pub transport_order: Option<Vec<Transport>>,
+    /// Whole CTAPHID request/response exchange.
+    pub total_timeout_ms: Option<u32>,
+    /// First packet of a response.
+    pub response_read_timeout_ms: Option<u32>,
+    /// How long operations that need a touch keep waiting.
+    pub user_presence_timeout_ms: Option<u32>,
+    /// Vendor reads skipped for this key.
+    pub disabled_vendor_reads: Vec<VendorRead>,
+}
+
+impl DeviceOverrides {
+    /// Whether nothing is overridden.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Apply the same limits as the file.
+    pub fn validate(&self) -> Result<(), String> {
+        let timeouts = [
+            ("Exchange timeout", self.total_timeout_ms),
+            ("Response timeout", self.response_read_timeout_ms),
+            ("Touch timeout", self.user_presence_timeout_ms),
+        ];
+        for (name, value) in timeouts {
+            if let Some(value) = value {
+                check_timeout(name, value)?;
+            }
+        }
+        if let Some(order) = &self.transport_order {
+            check_order("Transport order", order)?;
+        }
+        Ok(())
+    }
+}
+
+/// Longest timeout accepted, so a stray zero too many cannot hang the app.
+const MAX_TIMEOUT_MS: u32 = 600_000;
+
+fn check_timeout(name: &str, value: u32) -> Result<(), String> {
+    if value == 0 || value > MAX_TIMEOUT_MS {
+        return Err(format!(
+            "{} must be between 1 and {} ms, got {}",
+            name, MAX_TIMEOUT_MS, value
+        ));
+    }
+    Ok(())
+}
+
+fn check_order(name: &str, order: &[Transport]) -> Result<(), String> {
+    if order.is_empty() {
+        return Err(format!("{} must name at least one transport", name));
+    }
+    for (i, transport) in order.iter().enumerate() {
+        if order[..i].contains(transport) {
+            return Err(format!("{} lists {:?} twice", name, transport));
+        }
+    }
+    Ok(())
+}
+
+/// Parse and check the file contents.
+pub fn parse(text: &str) -> Result<Tunables, String> {
+    let tunables: Tunables = toml::from_str(text).map_err(|e| e.to_string())?;
+
+    let timeouts = [
+        ("hid.total_timeout_ms", tunables.hid.total_timeout_ms),
+        (
+            "hid.response_read_timeout_ms",
+            tunables.hid.response_read_timeout_ms,
+        ),
+        (
+            "hid.continuation_read_timeout_ms",
+            tunables.hid.continuation_read_timeout_ms,
+        ),
+        (
+            "hid.init_read_timeout_ms",
+            tunables.hid.init_read_timeout_ms,
+        ),
+        ("hid.retry_backoff_ms", tunables.hid.retry_backoff_ms),
+        (
+            "hid.retry_backoff_max_ms",
+            tunables.hid.retry_backoff_max_ms,
+        ),
+        (
+            "keepalive.user_presence_timeout_ms",
+            tunables.keepalive.user_presence_timeout_ms,
+        ),
+    ];
+    for (name, value) in timeouts {
+        check_timeout(name, value)?;
+    }
+    if !(1..=10).contains(&tunables.hid.init_attempts) {
+        return Err("hid.init_attempts must be between 1 and 10".into());
+    }
+    if tunables.hid.retries > 10 {
+        return Err("hid.retries must be at most 10".into());
+    }
+    if tunables.hid.retry_backoff_max_ms < tunables.hid.retry_backoff_ms {
+        return Err("hid.retry_backoff_max_ms must not be below hid.retry_backoff_ms".into());
+    }
+    if tunables.hid.health_check_idle_ms > MAX_TIMEOUT_MS {
+        return Err(format!(
+            "hid.health_check_idle_ms must be at most {} ms",
+            MAX_TIMEOUT_MS
+        ));
+    }
+    if tunables.capture.log_file_max_mb == 0 {
+        return Err("capture.log_file_max_mb must be at least 1".into());
+    }
+    if tunables.storage.quota_mb == 0 {
+        return Err("storage.quota_mb must be at least 1".into());
+    }
+    if !(1..=100).contains(&tunables.storage.warn_percent) {
+        return Err("storage.warn_percent must be between 1 and 100".into());
+    }
+    if tunables.storage.retention_days == 0 {
+        return Err("storage.retention_days must be at least 1".into());
+    }
+    if tunables.storage.check_interval_minutes == 0 {
+        return Err("storage.check_interval_minutes must be at least 1".into());
+    }
+    check_order("transport.order", &tunables.transport.order)?;
+    Ok(tunables)
+}
+
+static TUNABLES: OnceLock<Tunables> = OnceLock::new();
+
+/// Overrides of the connected key; `None` while no key with any is connected.
+static DEVICE_OVERRIDES: RwLock<Option<DeviceOverrides>> = RwLock::new(None);
+
+/// The tunables in effect. Defaults until [`init`] has run.
+pub fn get() -> &'static Tunables {
+    TUNABLES.get_or_init(Tunables::default)
+}
+
+/// Layer `overrides` over the file from now on (`None` drops them). Returns
+/// whether anything changed.
+pub fn set_device_overrides(overrides: Option<DeviceOverrides>) -> bool {
+    let overrides = overrides.filter(|o| !o.is_empty());
+    let mut current = DEVICE_OVERRIDES.write().unwrap_or_else(|e| e.into_inner());
+    if *current == overrides {
+        return false;
+    }
+    *current = overrides;
+    true
+}
+
+fn with_overrides<T>(f: impl FnOnce(Option<&DeviceOverrides>) -> T) -> T {
+    let current = DEVICE_OVERRIDES.read().unwrap_or_else(|e| e.into_inner());
+    f(current.as_ref())
+}
+
+/// `[hid]` with the connected key's overrides applied.
+pub fn hid() -> HidTunables {
+    let mut hid = get().hid.clone();
+    with_overrides(|o| {
+        if let Some(o) = o {
+            hid.total_timeout_ms = o.total_timeout_ms.unwrap_or(hid.total_timeout_ms);
+            hid.response_read_timeout_ms = o
+                .response_read_timeout_ms
+                .unwrap_or(hid.response_read_timeout_ms);
+        }
+    });
+    hid
+}
+
+/// `keepalive.user_presence_timeout_ms`, or the connected key's override.
+pub fn user_presence_timeout_ms() -> u32 {
+    with_overrides(|o| o.and_then(|o| o.user_presence_timeout_ms))
+        .unwrap_or(get().keepalive.user_presence_timeout_ms)
+}
+
+/// `transport.order`, or the connected key's override.
+pub fn transport_order() -> Vec<Transport> {
+    with_overrides(|o| o.and_then(|o| o.transport_order.clone()))
+        .unwrap_or_else(|| get().transport.order.clone())
+}
+
+/// Whether `read` may be sent to the connected key.
+pub fn vendor_read_enabled(read: VendorRead) -> bool {
+    with_overrides(|o| o.is_none_or(|o| !o.disabled_vendor_reads.contains(&read)))
+}
+
+/// Path of `picoforge.toml`.
+pub fn path() -> Option<PathBuf> {
+    settings::config_dir().map(|dir| dir.join("picoforge.toml"))
+}
+
+/// Load the file and make it current. Call once at startup, before logging.
+///
+/// Returns why the file was ignored, if it was. A missing file is not an error.
+pub fn init() -> Option<String> {
+    let (tunables, error) = match path() {
+        None => (
+            Tunables::default(),
+            Some("Could not determine the config directory".to_string()),
+        ),
+        Some(path) => match fs::read_to_string(&path) {
+            Ok(text) => match parse(&text) {
+                Ok(tunables) => (tunables, None),
+                Err(e) => (
+                    Tunables::default(),
+                    Some(format!("Could not use {}: {}", path.display(), e)),
+                ),
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => (Tunables::default(), None),
+            Err(e) => (
+                Tunables::default(),
+                Some(format!("Could not read {}: {}", path.display(), e)),
+            ),
+        },
+    };
+    let _ = TUNABLES.set(tunables);
+    error
+}
+
+/// Text for the editor: the file as it is on disk, or [`TEMPLATE`].
+pub fn read_text() -> String {
+    path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .unwrap_or_else(|| TEMPLATE.to_string())
+}
+
+/// Check `text` and write it to `picoforge.toml`. Nothing is written if it
+/// does not parse.
+pub fn save_text(text: &str) -> Result<(), String> {
+    parse(text)?;
+    let path = path().ok_or("Could not determine the config directory")?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Could not create {}: {}", parent.display(), e))?;
+    }
+    fs::write(&path, text).map_err(|e| format!("Could not write {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_template_matches_defaults() {
+        assert_eq!(parse(TEMPLATE).unwrap(), Tunables::default());
+    }
+
+    #[test]
+    fn test_missing_keys_keep_defaults() {
+        let tunables = parse("[hid]\ntotal_timeout_ms = 9000\n").unwrap();
+        assert_eq!(tunables.hid.total_timeout_ms, 9000);
+        assert_eq!(tunables.hid.response_read_timeout_ms, 2000);
+        assert_eq!(tunables.transport, TransportTunables::default());
+        assert_eq!(parse("").unwrap(), Tunables::default());
+    }
+
+    #[test]
+    fn test_unknown_keys_and_bad_values_are_rejected() {
+        assert!(parse("[hid]\ntotal_timeout = 9000\n").is_err());
+        assert!(parse("[hid]\ntotal_timeout_ms = 0\n").is_err());
+        assert!(parse("[hid]\ninit_attempts = 0\n").is_err());
+        assert!(parse("[hid]\nhealth_check_idle_ms = 0\n").is_ok());
+        assert!(parse("[hid]\nhealth_check_idle_ms = 700000\n").is_err());
+        assert!(parse("[hid]\nretries = 0\n").is_ok());
+        assert!(parse("[hid]\nretries = 11\n").is_err());
+        assert!(parse("[hid]\nretry_backoff_ms = 2000\n").is_err());
+        assert!(parse("[storage]\nquota_mb = 0\n").is_err());
+        assert!(parse("[storage]\nwarn_percent = 120\n").is_err());
+        assert!(parse("[transport]\norder = []\n").is_err());
+        assert!(parse("[transport]\norder = [\"fido\", \"fido\"]\n").is_err());
+        assert!(parse("[transport]\norder = [\"usb\"]\n").is_err());
+    }
+
+    #[test]
+    fn test_device_overrides_validation() {
+        assert!(DeviceOverrides::default().is_empty());
+        assert!(DeviceOverrides::default().validate().is_ok());
+
+        let slow = DeviceOverrides {
+            total_timeout_ms: Some(20_000),
+            transport_order: Some(vec![Transport::Fido]),
+            ..Default::default()
+        };
+        assert!(slow.validate().is_ok());
+        assert!(!slow.is_empty());
+
+        let zero = DeviceOverrides {
+            user_presence_timeout_ms: Some(0),
+            ..Default::default()
+        };
+        assert!(zero.validate().is_err());
+        let empty_order = DeviceOverrides {
+            transport_order: Some(Vec::new()),
+            ..Default::default()
+        };
+        assert!(empty_order.validate().is_err());
+    }
+
+    #[test]
+    fn test_transport_order() {
+        let tunables = parse("[transport]\norder = [\"fido\"]\n").unwrap();
+        assert_eq!(tunables.transport.order, vec![Transport::Fido]);
+        assert!(!tunables.transport.enabled(Transport::Rescue));
+    }
+}
@@ -5,21 +5,78 @@
 //! `active_destination`, and renders the sidebar toggle button as the last child
 //! of `main-area` (so it paints on top of the content column). Sidebar collapse/width
 //! state and toggle hover state are owned by [`AppSidebar`].
+//!
+//! On first launch (and from Help via [`StartTour`]) it lays the onboarding
+//! [`CoachMarks`] over the window.
+//!
+//! It also handles the [`quick_actions`] registry, whose pinned entries are
+//! shown as buttons in the title bar.
 
+use crate::issue_report;
+use crate::janitor;
+use crate::metrics;
+use crate::settings;
+use crate::startup;
+use crate::ui::components::coach_mark::{CoachMarkEvent, CoachMarks, CoachStep};
 use crate::ui::components::sidebar::{AppSidebar, SidebarEvent};
-use crate::ui::models::device::{DeviceEvent, DeviceRepo};
+use crate::ui::components::startup_status;
+use crate::ui::models::device::{DeviceCapabilities, DeviceEvent, DeviceRepo};
+use crate::ui::quick_actions::{
+    self, LockPasskeys, OpenConfiguration, OpenHome, OpenPasskeys, OpenSecurity, PinnedActions,
+    RefreshDevice,
+};
 use crate::ui::screens::{
-    about::AboutViewModel, config::ConfigViewModel, home::HomeViewModel, passkeys::PasskeysEvent,
-    passkeys::PasskeysViewModel, security::SecurityViewModel,
+    about::AboutViewModel,
+    applets::{AppletKind, AppletViewModel},
+    biometrics::BiometricsViewModel,
+    config::ConfigViewModel,
+    home::HomeViewModel,
+    passkeys::PasskeysEvent,
+    passkeys::PasskeysViewModel,
+    security::SecurityViewModel,
+    vault::VaultViewModel,
 };
 use gpui::prelude::*;
 use gpui::*;
 use gpui_component::Root;
 use gpui_component::{
-    ActiveTheme, Icon, TitleBar, WindowExt, h_flex, scroll::ScrollableElement, v_flex,
+    ActiveTheme, Icon, Sizable, TitleBar, WindowExt,
+    button::{Button, ButtonVariants},
+    h_flex,
+    scroll::ScrollableElement,
+    v_flex,
 };
 
-gpui::actions!(picoforge, [ToggleSidebar]);
+gpui::actions!(picoforge, [ToggleSidebar, StartTour]);
+
+/// Steps of the onboarding tour; targets are anchored in the sidebar.
+const ONBOARDING_TOUR: &[CoachStep] = &[
+    CoachStep {
+        target: "sidebar.menu",
+        title: "Screens",
+        body: "Each screen manages one part of the key. Passkeys, Configuration, Security and the applets only appear when the connected key supports them.",
+    },
+    CoachStep {
+        target: "sidebar.status",
+        title: "Device status",
+        body: "Online means PicoForge reaches the key over both FIDO and its rescue interface. Online - FIDO means only FIDO answered, so some settings are read-only.",
+    },
+    CoachStep {
+        target: "sidebar.refresh",
+        title: "Refresh",
+        body: "Keys are picked up when plugged in. Refresh reads the key again, e.g. after another app was using it.",
+    },
+    CoachStep {
+        target: "sidebar.menu",
+        title: "PIN-protected operations",
+        body: "Listing or deleting passkeys, changing the PIN and writing configuration ask for the key's PIN. Passkeys keeps it in memory until you lock storage or unplug the key.",
+    },
+    CoachStep {
+        target: "",
+        title: "That's it",
+        body: "Troubleshooting guides live under Help in the sidebar, where you can also replay this tour.",
+    },
+];
 
 /// Shared reactive models accessible to every screen view-model.
 pub struct AppModels {
@@ -33,7 +90,11 @@ pub struct ViewModelStore {
     pub about: Option<Entity<AboutViewModel>>,
     pub security: Option<Entity<SecurityViewModel>>,
     pub passkeys: Option<Entity<PasskeysViewModel>>,
+    pub biometrics: Option<Entity<BiometricsViewModel>>,
     pub config: Option<Entity<ConfigViewModel>>,
+    pub vault: Option<Entity<VaultViewModel>>,
+    pub openpgp: Option<Entity<AppletViewModel>>,
+    pub oath: Option<Entity<AppletViewModel>>,
 }
 
 impl ViewModelStore {
@@ -44,7 +105,11 @@ impl ViewModelStore {
             about: None,
             security: None,
             passkeys: None,
+            biometrics: None,
             config: None,
+            vault: None,
+            openpgp: None,
+            oath: None,
         }
     }
 }
@@ -54,11 +119,34 @@ impl ViewModelStore {
 pub enum Destination {
     Home,
     Passkeys,
+    Biometrics,
     Configuration,
     Security,
+    Vault,
+    OpenPgp,
+    Oath,
     About,
 }
 
+impl Destination {
+    /// Whether this screen makes sense for the connected device. Home and
+    /// About are always available; Home doubles as the "No device" landing.
+    pub fn is_available(self, caps: &DeviceCapabilities) -> bool {
+        match self {
+            Destination::Home | Destination::About => true,
+            Destination::Configuration => caps.connected && caps.vendor_management,
+            // Reset and authenticatorConfig work on any FIDO2 key; the
+            // screen hides its pico-fido-only cards itself.
+            Destination::Security => caps.connected,
+            Destination::Passkeys => caps.connected && caps.credential_management,
+            Destination::Biometrics => caps.connected && caps.bio_enrollment,
+            Destination::Vault => caps.connected && caps.hmac_secret,
+            Destination::OpenPgp => caps.connected && caps.openpgp,
+            Destination::Oath => caps.connected && caps.oath,
+        }
+    }
+}
+
 /// Top-level GPUI component — owns models, navigation, and wires sidebar + content routing.
 pub struct ApplicationRoot {
     pub models: AppModels,
@@ -66,10 +154,19 @@ pub struct ApplicationRoot {
     pub views_store: ViewModelStore,
     pub sidebar: Entity<AppSidebar>,
     pub focus_handle: FocusHandle,
+    /// Set when a startup self-check failed; the status dialog is shown once.
+    show_startup_status: bool,
+    /// Onboarding tour overlay, while it is showing.
+    tour: Option<Entity<CoachMarks>>,
+    /// Set when the last storage sweep found usage near the quota.
+    storage_warning: Option<String>,
+    /// Periodic [`janitor::sweep`]; cancelled with the root.
+    _storage_janitor: Task<()>,
 }
 
 impl ApplicationRoot {
-    /// Creates the root, initialises `DeviceRepo`, sidebar, and triggers an immediate device poll.
+    /// Creates the root, initialises `DeviceRepo`, sidebar, and triggers an immediate device poll
+    /// (none in safe mode).
     pub fn new(cx: &mut Context<Self>) -> Self {
         let device = cx.new(|_| DeviceRepo::new());
         let sidebar = cx.new(|_| AppSidebar::new(Destination::Home, device.clone()));
@@ -83,6 +180,13 @@ impl ApplicationRoot {
              cx: &mut Context<Self>| {
                 if this.models.device.read(cx).device_changed {
                     this.views_store.passkeys = None;
+                    this.views_store.biometrics = None;
+                    this.views_store.vault = None;
+                }
+                issue_report::set_device(this.models.device.read(cx).issue_summary());
+                let caps = this.models.device.read(cx).capabilities();
+                if !this.active_destination.is_available(&caps) {
+                    this.navigate(Destination::Home, cx);
                 }
                 cx.notify();
             },
@@ -97,23 +201,47 @@ impl ApplicationRoot {
              event: &SidebarEvent,
              cx: &mut Context<Self>| {
                 match event {
-                    SidebarEvent::Navigate(dest) => {
-                        this.active_destination = *dest;
-                        this.sidebar.update(cx, |s, cx| {
-                            s.set_active_destination(*dest);
-                            cx.notify();
-                        });
-                        cx.notify();
-                    }
+                    SidebarEvent::Navigate(dest) => this.navigate(*dest, cx),
                     SidebarEvent::RefreshDevice => {
                         this.models.device.update(cx, |repo, cx| repo.refresh(cx));
                     }
+                    SidebarEvent::SelectDevice(path) => {
+                        this.models
+                            .device
+                            .update(cx, |repo, cx| repo.select_device(path, cx));
+                    }
                 }
             },
         )
         .detach();
 
-        let this = Self {
+        cx.observe_global::<PinnedActions>(|_, cx| cx.notify())
+            .detach();
+
+        let storage_janitor = cx.spawn(async move |this, cx| {
+            loop {
+                let report = cx
+                    .background_executor()
+                    .spawn(async { janitor::sweep() })
+                    .await;
+                let warning = report.warning();
+                if let Some(warning) = &warning {
+                    log::warn!("{}", warning);
+                }
+                let updated = this.update(cx, |this, cx| {
+                    if this.storage_warning != warning {
+                        this.storage_warning = warning;
+                        cx.notify();
+                    }
+                });
+                if updated.is_err() {
+                    break;
+                }
+                cx.background_executor().timer(janitor::interval()).await;
+            }
+        });
+
+        let mut this = Self {
             models: AppModels {
                 device: device.clone(),
             },
@@ -121,15 +249,101 @@ impl ApplicationRoot {
             views_store: ViewModelStore::new(),
             sidebar,
             focus_handle: cx.focus_handle(),
+            show_startup_status: startup::has_problems(),
+            tour: None,
+            storage_warning: None,
+            _storage_janitor: storage_janitor,
         };
+        if !settings::load().0.onboarding_done {
+            this.start_tour(cx);
+        }
 
-        device.update(cx, |repo, cx| {
-            repo.refresh(cx);
-            repo.start_hotplug_watch(cx);
-        });
+        device.update(cx, |repo, cx| repo.start(cx));
         this
     }
 
+    /// Switch the content area to `dest` and highlight it in the sidebar.
+    fn navigate(&mut self, dest: Destination, cx: &mut Context<Self>) {
+        if dest != self.active_destination {
+            metrics::record_feature(&format!("screen.{:?}", dest).to_lowercase());
+        }
+        self.active_destination = dest;
+        self.sidebar.update(cx, |s, cx| {
+            s.set_active_destination(dest);
+            cx.notify();
+        });
+        cx.notify();
+    }
+
+    /// Navigate to `dest` from a quick action, or say why it is not possible.
+    fn open_if_available(
+        &mut self,
+        dest: Destination,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let caps = self.models.device.read(cx).capabilities();
+        if dest.is_available(&caps) {
+            self.navigate(dest, cx);
+        } else {
+            window.push_notification(
+                format!("{:?} is not available for the connected key.", dest),
+                cx,
+            );
+        }
+    }
+
+    /// Forget the Passkeys PIN, if that screen was opened.
+    fn lock_passkeys(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        match self.views_store.passkeys.clone() {
+            Some(passkeys) => {
+                passkeys.update(cx, |vm, cx| vm.lock_storage(cx));
+                window.push_notification("Passkey storage locked.", cx);
+            }
+            None => window.push_notification("Passkey storage is not unlocked.", cx),
+        }
+    }
+
+    /// Buttons for the quick actions pinned in the settings.
+    fn render_pinned_actions(cx: &App) -> impl IntoElement {
+        h_flex()
+            .gap_1()
+            .pr_2()
+            .on_mouse_down(MouseButton::Left, |_, _, cx| cx.stop_propagation())
+            .children(quick_actions::pinned(cx).into_iter().map(|action| {
+                Button::new(action.id)
+                    .ghost()
+                    .xsmall()
+                    .icon(Icon::default().path(action.icon))
+                    .tooltip(action.label)
+                    .on_click(move |_, window, cx| window.dispatch_action(action.action(), cx))
+            }))
+    }
+
+    /// Show the onboarding tour from its first step.
+    fn start_tour(&mut self, cx: &mut Context<Self>) {
+        let tour = cx.new(|cx| CoachMarks::new(ONBOARDING_TOUR, cx));
+        cx.subscribe(&tour, |this, _, event: &CoachMarkEvent, cx| {
+            let CoachMarkEvent::Finished { completed } = event;
+            log::info!(
+                "Onboarding tour {}",
+                if *completed { "completed" } else { "skipped" }
+            );
+            this.tour = None;
+            let (mut next, _) = settings::load();
+            if !next.onboarding_done {
+                next.onboarding_done = true;
+                if let Err(e) = settings::save(&next) {
+                    log::warn!("Could not save settings: {}", e);
+                }
+            }
+            cx.notify();
+        })
+        .detach();
+        self.tour = Some(tour);
+        cx.notify();
+    }
+
     pub fn focus_handle(&self) -> FocusHandle {
         self.focus_handle.clone()
     }
@@ -137,6 +351,12 @@ impl ApplicationRoot {
 
 impl Render for ApplicationRoot {
     fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        if std::mem::take(&mut self.show_startup_status) {
+            window.defer(cx, |window, cx| {
+                startup_status::open_startup_status(startup::results(), window, cx);
+            });
+        }
+
         let dialog_layer = Root::render_dialog_layer(window, cx);
         let sheet_layer = Root::render_sheet_layer(window, cx);
 
@@ -146,23 +366,121 @@ impl Render for ApplicationRoot {
                 .justify_between()
                 .bg(cx.theme().title_bar)
                 .items_center()
-                .cursor(gpui::CursorStyle::OpenHand),
+                .cursor(gpui::CursorStyle::OpenHand)
+                .child(div())
+                .child(Self::render_pinned_actions(cx)),
         );
 
+        // Started with --safe-mode or the safe mode setting: no key has been
+        // read yet.
+        let safe_mode_banner = self.models.device.read(cx).safe_mode.then(|| {
+            h_flex()
+                .w_full()
+                .px_4()
+                .py_2()
+                .gap_2()
+                .bg(cx.theme().warning.opacity(0.15))
+                .text_sm()
+                .text_color(cx.theme().warning)
+                .child(Icon::default().path("icons/info.svg"))
+                .child(div().flex_1().child(
+                    "Safe mode: PicoForge has not read or watched any key since it started.",
+                ))
+                .child(
+                    Button::new("leave-safe-mode")
+                        .small()
+                        .label("Connect")
+                        .on_click(cx.listener(|this, _, _, cx| {
+                            this.models.device.update(cx, |repo, cx| repo.refresh(cx));
+                        })),
+                )
+        });
+
+        // Another PicoForge process (usually a CLI command) is using the key.
+        let yield_banner = self.models.device.read(cx).yielded_to.as_ref().map(|owner| {
+            h_flex()
+                .w_full()
+                .px_4()
+                .py_2()
+                .gap_2()
+                .bg(cx.theme().warning.opacity(0.15))
+                .text_sm()
+                .text_color(cx.theme().warning)
+                .child(Icon::default().path("icons/info.svg"))
+                .child(format!(
+                    "The key is in use by {} (pid {}). Device polling is paused and resumes when it finishes.",
+                    owner.role.describe(),
+                    owner.pid
+                ))
+        });
+
+        // The storage janitor found PicoForge's files near their quota.
+        let storage_banner = self.storage_warning.as_ref().map(|warning| {
+            h_flex()
+                .w_full()
+                .px_4()
+                .py_2()
+                .gap_2()
+                .bg(cx.theme().warning.opacity(0.15))
+                .text_sm()
+                .text_color(cx.theme().warning)
+                .child(Icon::default().path("icons/info.svg"))
+                .child(format!(
+                    "{} Lower the log size or raise [storage] quota_mb in picoforge.toml.",
+                    warning
+                ))
+        });
+
+        // A pending request is waiting for user presence.
+        let touch_banner = self.models.device.read(cx).touch_needed.then(|| {
+            h_flex()
+                .w_full()
+                .px_4()
+                .py_2()
+                .gap_2()
+                .bg(cx.theme().info.opacity(0.15))
+                .text_sm()
+                .text_color(cx.theme().info)
+                .child(Icon::default().path("icons/fingerprint.svg"))
+                .child("Touch your key now to continue.")
+        });
+
+        // A flow in this process (e.g. a reset) holds the key.
+        let maintenance_banner = self
+            .models
+            .device
+            .read(cx)
+            .maintenance
+            .as_ref()
+            .map(|reason| {
+                h_flex()
+                    .w_full()
+                    .px_4()
+                    .py_2()
+                    .gap_2()
+                    .bg(cx.theme().warning.opacity(0.15))
+                    .text_sm()
+                    .text_color(cx.theme().warning)
+                    .child(Icon::default().path("icons/info.svg"))
+                    .child(format!(
+                        "{}. Device polling is paused and resumes when it finishes.",
+                        reason
+                    ))
+            });
+
         let content_area = v_flex()
             .track_focus(&self.focus_handle)
             .key_context("ApplicationRoot")
-            .on_action(cx.listener(|this, _: &ToggleSidebar, _, cx| {
-                this.sidebar.update(cx, |s, cx| {
-                    s.collapsed = !s.collapsed;
-                    cx.notify();
-                });
-            }))
             .min_h(px(0.))
             .min_w(px(0.))
             .overflow_y_scrollbar()
             .flex_grow()
             .bg(cx.theme().background)
+            .children(safe_mode_banner)
+            .children(yield_banner)
+            .children(maintenance_banner)
+            .children(touch_banner)
+            .children(storage_banner)
             .child(match self.active_destination {
                 Destination::Home => {
                     let view = self.views_store.home.get_or_insert_with(|| {
@@ -187,6 +505,12 @@ impl Render for ApplicationRoot {
                     });
                     view.clone().into_any_element()
                 }
+                Destination::Biometrics => {
+                    let view = self.views_store.biometrics.get_or_insert_with(|| {
+                        cx.new(|cx| BiometricsViewModel::new(window, cx, &self.models))
+                    });
+                    view.clone().into_any_element()
+                }
                 Destination::Configuration => {
                     let view = self.views_store.config.get_or_insert_with(|| {
                         cx.new(|cx| ConfigViewModel::new(window, cx, &self.models))
@@ -199,6 +523,28 @@ impl Render for ApplicationRoot {
                     });
                     view.clone().into_any_element()
                 }
+                Destination::Vault => {
+                    let view = self.views_store.vault.get_or_insert_with(|| {
+                        cx.new(|cx| VaultViewModel::new(window, cx, &self.models))
+                    });
+                    view.clone().into_any_element()
+                }
+                Destination::OpenPgp => {
+                    let view = self.views_store.openpgp.get_or_insert_with(|| {
+                        cx.new(|cx| {
+                            AppletViewModel::new(AppletKind::OpenPgp, window, cx, &self.models)
+                        })
+                    });
+                    view.clone().into_any_element()
+                }
+                Destination::Oath => {
+                    let view = self.views_store.oath.get_or_insert_with(|| {
+                        cx.new(|cx| {
+                            AppletViewModel::new(AppletKind::Oath, window, cx, &self.models)
+                        })
+                    });
+                    view.clone().into_any_element()
+                }
                 Destination::About => {
                     let view = self.views_store.about.get_or_insert_with(|| {
                         cx.new(|cx| AboutViewModel::new(window, cx, &self.models))
@@ -297,9 +643,35 @@ impl Render for ApplicationRoot {
 
         div()
             .id("application-root")
+            .on_action(cx.listener(|this, _: &StartTour, _, cx| this.start_tour(cx)))
+            .on_action(cx.listener(|this, _: &ToggleSidebar, _, cx| {
+                this.sidebar.update(cx, |s, cx| {
+                    s.collapsed = !s.collapsed;
+                    cx.notify();
+                });
+            }))
+            .on_action(cx.listener(|this, _: &RefreshDevice, _, cx| {
+                this.models.device.update(cx, |repo, cx| repo.refresh(cx));
+            }))
+            .on_action(
+                cx.listener(|this, _: &LockPasskeys, window, cx| this.lock_passkeys(window, cx)),
+            )
+            .on_action(cx.listener(|this, _: &OpenHome, window, cx| {
+                this.open_if_available(Destination::Home, window, cx)
+            }))
+            .on_action(cx.listener(|this, _: &OpenPasskeys, window, cx| {
+                this.open_if_available(Destination::Passkeys, window, cx)
+            }))
+            .on_action(cx.listener(|this, _: &OpenConfiguration, window, cx| {
+                this.open_if_available(Destination::Configuration, window, cx)
+            }))
+            .on_action(cx.listener(|this, _: &OpenSecurity, window, cx| {
+                this.open_if_available(Destination::Security, window, cx)
+            }))
             .size_full()
             .overflow_hidden()
             .child(body)
+            .children(self.tour.clone())
             .children(dialog_layer)
             .children(sheet_layer)
     }
@@ -0,0 +1,260 @@
+//! Spotlight overlay that walks through parts of the UI one step at a time.
+//!
+//! Elements to point at get an [`anchor`] child, which records their window
+//! bounds under a name while painting. [`CoachMarks`] renders on top of
+//! everything: it dims the window except for the current step's anchor, draws
+//! a ring around it and places a card with the step's text next to it. A step
+//! whose anchor is not on screen (e.g. a screen the connected key does not
+//! support) is shown as a centred card without a highlight.
+//!
+//! Bounds are read one frame late: each render of the overlay takes what the
+//! anchors recorded during the previous paint, and an anchor that moved asks
+//! for another frame so the highlight catches up.
+
+use gpui::prelude::FluentBuilder;
+use gpui::*;
+use gpui_component::{
+    ActiveTheme, Sizable,
+    button::{Button, ButtonVariants},
+    h_flex, v_flex,
+};
+use std::collections::HashMap;
+
+/// Width of the step card.
+const CARD_WIDTH: Pixels = px(320.);
+/// Gap between the highlighted element and the ring / card.
+const SPOTLIGHT_PADDING: Pixels = px(6.);
+
+/// One stop of a tour.
+#[derive(Clone, Copy, Debug)]
+pub struct CoachStep {
+    /// Name given to [`anchor`]; unknown names show a centred card.
+    pub target: &'static str,
+    pub title: &'static str,
+    pub body: &'static str,
+}
+
+/// Anchor bounds recorded during paint, by name.
+#[derive(Default)]
+struct CoachTargets {
+    /// Only collected while a tour is showing.
+    active: bool,
+    /// Recorded during the frame being drawn.
+    current: HashMap<&'static str, Bounds<Pixels>>,
+    /// Complete set from the previous frame, read by the overlay.
+    previous: HashMap<&'static str, Bounds<Pixels>>,
+}
+
+impl Global for CoachTargets {}
+
+/// Invisible child that records the bounds of its parent as `id`, so a tour
+/// step can point at it. The parent must be `relative()`. Records nothing
+/// while no tour is showing.
+pub fn anchor(id: &'static str) -> impl IntoElement {
+    canvas(
+        move |bounds, window, cx| {
+            let targets = cx.default_global::<CoachTargets>();
+            if !targets.active {
+                return;
+            }
+            if targets.previous.get(id) != Some(&bounds) {
+                window.refresh();
+            }
+            targets.current.insert(id, bounds);
+        },
+        |_, _, _, _| {},
+    )
+    .absolute()
+    .top_0()
+    .left_0()
+    .size_full()
+}
+
+/// Emitted when the user leaves the tour.
+pub enum CoachMarkEvent {
+    /// `completed` is false when the tour was skipped before the last step.
+    Finished { completed: bool },
+}
+
+/// Full-window overlay showing one [`CoachStep`] at a time.
+pub struct CoachMarks {
+    steps: &'static [CoachStep],
+    index: usize,
+}
+
+impl EventEmitter<CoachMarkEvent> for CoachMarks {}
+
+impl CoachMarks {
+    pub fn new(steps: &'static [CoachStep], cx: &mut Context<Self>) -> Self {
+        let targets = cx.default_global::<CoachTargets>();
+        targets.active = true;
+        targets.current.clear();
+        targets.previous.clear();
+        Self { steps, index: 0 }
+    }
+
+    fn finish(&mut self, completed: bool, cx: &mut Context<Self>) {
+        let targets = cx.default_global::<CoachTargets>();
+        targets.active = false;
+        targets.current.clear();
+        targets.previous.clear();
+        cx.emit(CoachMarkEvent::Finished { completed });
+    }
+
+    fn next(&mut self, cx: &mut Context<Self>) {
+        if self.index + 1 < self.steps.len() {
+            self.index += 1;
+            cx.notify();
+        } else {
+            self.finish(true, cx);
+        }
+    }
+
+    fn back(&mut self, cx: &mut Context<Self>) {
+        self.index = self.index.saturating_sub(1);
+        cx.notify();
+    }
+
+    /// Where the card goes: right of the target when it fits, otherwise
+    /// below it, kept inside the window.
+    fn card_origin(target: Bounds<Pixels>, viewport: Size<Pixels>) -> Point<Pixels> {
+        let margin = px(16.);
+        let right = target.right() + margin;
+        if right + CARD_WIDTH + margin <= viewport.width {
+            let y = target.top().min(viewport.height - px(220.)).max(margin);
+            point(right, y)
+        } else {
+            let x = target
+                .left()
+                .min(viewport.width - CARD_WIDTH - margin)
+                .max(margin);
+            point(x, target.bottom() + margin)
+        }
+    }
+}
+
+impl Render for CoachMarks {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let targets = cx.default_global::<CoachTargets>();
+        targets.previous = std::mem::take(&mut targets.current);
+        let step = self.steps[self.index.min(self.steps.len() - 1)];
+        let target = targets.previous.get(step.target).copied();
+        if targets.previous.is_empty() {
+            // First frame of the tour: anchors have not painted yet.
+            window.request_animation_frame();
+        }
+
+        let theme = cx.theme();
+        let viewport = window.viewport_size();
+        let scrim = gpui::black().opacity(0.6);
+        let is_last = self.index + 1 == self.steps.len();
+
+        let card = v_flex()
+            .id("coach-card")
+            .occlude()
+            .w(CARD_WIDTH)
+            .gap_2()
+            .p_4()
+            .rounded_lg()
+            .border_1()
+            .border_color(theme.border)
+            .bg(theme.popover)
+            .shadow_lg()
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(theme.muted_foreground)
+                    .child(format!("{} of {}", self.index + 1, self.steps.len())),
+            )
+            .child(div().font_semibold().child(step.title))
+            .child(
+                div()
+                    .text_sm()
+                    .text_color(theme.muted_foreground)
+                    .child(step.body),
+            )
+            .child(
+                h_flex()
+                    .pt_2()
+                    .justify_between()
+                    .child(
+                        Button::new("coach-skip")
+                            .small()
+                            .ghost()
+                            .label("Skip tour")
+                            .on_click(cx.listener(|this, _, _, cx| this.finish(false, cx))),
+                    )
+                    .child(
+                        h_flex()
+                            .gap_2()
+                            .when(self.index > 0, |this| {
+                                this.child(
+                                    Button::new("coach-back")
+                                        .small()
+                                        .label("Back")
+                                        .on_click(cx.listener(|this, _, _, cx| this.back(cx))),
+                                )
+                            })
+                            .child(
+                                Button::new("coach-next")
+                                    .small()
+                                    .primary()
+                                    .label(if is_last { "Done" } else { "Next" })
+                                    .on_click(cx.listener(|this, _, _, cx| this.next(cx))),
+                            ),
+                    ),
+            );
+
+        let overlay = div()
+            .id("coach-marks")
+            .absolute()
+            .top_0()
+            .left_0()
+            .size_full()
+            .occlude();
+
+        let Some(target) = target else {
+            return overlay
+                .bg(scrim)
+                .flex()
+                .items_center()
+                .justify_center()
+                .child(card);
+        };
+
+        // Dim everything but the target with four bands around it.
+        let hole = target.dilate(SPOTLIGHT_PADDING);
+        let band = || div().absolute().bg(scrim);
+        let origin = Self::card_origin(hole, viewport);
+
+        overlay
+            .child(band().top_0().left_0().w_full().h(hole.top()))
+            .child(band().top(hole.bottom()).left_0().w_full().bottom_0())
+            .child(
+                band()
+                    .top(hole.top())
+                    .left_0()
+                    .w(hole.left())
+                    .h(hole.size.height),
+            )
+            .child(
+                band()
+                    .top(hole.top())
+                    .left(hole.right())
+                    .right_0()
+                    .h(hole.size.height),
+            )
+            .child(
+                div()
+                    .absolute()
+                    .top(hole.top())
+                    .left(hole.left())
+                    .w(hole.size.width)
+                    .h(hole.size.height)
+                    .rounded_md()
+                    .border_2()
+                    .border_color(theme.primary),
+            )
+            .child(div().absolute().top(origin.y).left(origin.x).child(card))
+    }
+}
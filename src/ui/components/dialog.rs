@@ -1,9 +1,15 @@
 //! Modal dialog components for PIN prompts, confirmations, and status display.
 
+use crate::cues::{self, Cue};
+use crate::ui::components::help::{self, HelpTopic};
+use crate::ui::components::{issue_report, pin_recovery, spec_reference};
+use crate::ui::models::device::{DeviceRepo, PinStatus, pin, pin_lockout_from_error, reference};
+use gpui::prelude::FluentBuilder;
 use gpui::*;
 use gpui_component::{
     ActiveTheme, Disableable, Sizable, WindowExt,
     button::{Button, ButtonVariant, ButtonVariants},
+    checkbox::Checkbox,
     h_flex,
     input::{Input, InputEvent, InputState},
     v_flex,
@@ -15,6 +21,152 @@ type ChangePinCallback =
     std::rc::Rc<dyn Fn(String, String, WeakEntity<ChangePinContent>, &mut App)>;
 type SetPinCallback = std::rc::Rc<dyn Fn(String, WeakEntity<SetPinContent>, &mut App)>;
 
+/// Read the PIN (and built-in UV) attempts left off the UI thread and hand
+/// them to `apply`.
+fn refresh_pin_status<T: 'static>(
+    cx: &mut Context<T>,
+    apply: impl FnOnce(&mut T, PinStatus) + 'static,
+) -> Task<()> {
+    cx.spawn(async move |this, cx| {
+        let result = cx
+            .background_executor()
+            .spawn(async move { DeviceRepo::get_pin_status_blocking() })
+            .await;
+        let _ = this.update(cx, |this, cx| {
+            match result {
+                Ok(status) => apply(this, status),
+                Err(e) => log::debug!("Could not read PIN retries for the prompt: {}", e),
+            }
+            cx.notify();
+        });
+    })
+}
+
+fn retries_badge(text: String, retries: u32, cx: &App) -> impl IntoElement {
+    let color = match retries {
+        0..=2 => cx.theme().danger,
+        3..=4 => cx.theme().warning,
+        _ => cx.theme().muted_foreground,
+    };
+    div()
+        .flex_shrink_0()
+        .px_2()
+        .py_0p5()
+        .rounded_md()
+        .border_1()
+        .border_color(color)
+        .text_xs()
+        .text_color(color)
+        .child(text)
+}
+
+/// Badges with the PIN attempts left and, on keys with a fingerprint sensor,
+/// the fingerprint attempts left before the PIN is required.
+fn render_pin_status(status: &PinStatus, cx: &App) -> impl IntoElement {
+    let plural = |n: u32| if n == 1 { "" } else { "s" };
+    let pin = &status.pin;
+    let pin_text = if pin.power_cycle_required {
+        "Unplug and re-plug the key before the next attempt".to_string()
+    } else {
+        format!("{} attempt{} left", pin.retries, plural(pin.retries))
+    };
+    v_flex()
+        .flex_shrink_0()
+        .items_end()
+        .gap_1()
+        .child(retries_badge(pin_text, pin.retries, cx))
+        .children(status.uv_retries.map(|uv| {
+            retries_badge(
+                format!("{} fingerprint attempt{} left", uv, plural(uv)),
+                uv,
+                cx,
+            )
+        }))
+}
+
+/// Whether a PIN may be sent with `status` attempts left. The final attempt
+/// is held back until the user has ticked the acknowledgement box.
+fn attempt_allowed(status: Option<&PinStatus>, acknowledged: bool) -> bool {
+    acknowledged
+        || !status.is_some_and(|s| pin::attempt_budget(s.pin.retries) == pin::AttemptBudget::Final)
+}
+
+/// Inline warning shown when one or two PIN attempts remain, with the
+/// power-cycle guidance. On the final attempt it carries the "I understand"
+/// checkbox; `on_toggle` receives the new state.
+fn render_attempt_budget(
+    status: Option<&PinStatus>,
+    acknowledged: bool,
+    on_toggle: impl Fn(&bool, &mut Window, &mut App) + 'static,
+    cx: &App,
+) -> Option<AnyElement> {
+    let status = status?;
+    let budget = pin::attempt_budget(status.pin.retries);
+    if budget == pin::AttemptBudget::Ample || status.pin.retries == 0 {
+        return None;
+    }
+    let headline = match budget {
+        pin::AttemptBudget::Final => {
+            "Last attempt: one more wrong PIN blocks this key until it is reset, erasing every passkey on it."
+        }
+        _ => "Only two PIN attempts are left before this key is blocked.",
+    };
+    let failures = pin::session_failures();
+    let guidance = format!(
+        "Unplugging the key only clears the lock after {} wrong PINs in a row; it never gives attempts back.{}",
+        pin::ATTEMPTS_PER_POWER_UP,
+        if failures > 0 {
+            format!(
+                " {} wrong PIN{} entered since PicoForge started.",
+                failures,
+                if failures == 1 { " was" } else { "s were" }
+            )
+        } else {
+            String::new()
+        }
+    );
+    let danger = cx.theme().danger;
+    Some(
+        v_flex()
+            .gap_2()
+            .px_3()
+            .py_2()
+            .rounded_md()
+            .border_1()
+            .border_color(danger)
+            .child(
+                h_flex()
+                    .gap_2()
+                    .items_start()
+                    .text_sm()
+                    .text_color(danger)
+                    .font_weight(FontWeight::SEMIBOLD)
+                    .child(
+                        div().flex_shrink_0().child(
+                            gpui_component::Icon::new(gpui_component::IconName::TriangleAlert)
+                                .with_size(gpui_component::Size::Medium),
+                        ),
+                    )
+                    .child(div().flex_1().child(headline)),
+            )
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(cx.theme().muted_foreground)
+                    .child(guidance),
+            )
+            .when(budget == pin::AttemptBudget::Final, |this| {
+                this.child(
+                    Checkbox::new("final-attempt-ack")
+                        .label("I understand that a wrong PIN now blocks the key")
+                        .checked(acknowledged)
+                        .on_click(on_toggle),
+                )
+            })
+            .into_any_element(),
+    )
+}
+
 #[derive(Clone)]
 enum DialogPhase {
     Input,
@@ -35,7 +187,13 @@ pub struct PinPromptContent {
     confirm_label: SharedString,
     pin_input: Entity<InputState>,
     on_confirm: PinPromptCallback,
+    /// Attempts left, read with getPinRetries (and getUVRetries) when the
+    /// prompt opens and after every failed attempt.
+    retries: Option<PinStatus>,
+    /// The "I understand" box for the final attempt is ticked.
+    final_acknowledged: bool,
     _subscription: Subscription,
+    _retries_task: Option<Task<()>>,
 }
 
 impl PinPromptContent {
@@ -53,17 +211,54 @@ impl PinPromptContent {
     /// Transition the dialog to a success state.
     pub fn set_success(&mut self, msg: String, cx: &mut Context<Self>) {
         self.phase = DialogPhase::Success(msg);
+        cues::play(Cue::Success);
         cx.notify();
     }
 
     /// Transition the dialog to an error state with the given message.
     pub fn set_error(&mut self, msg: String, cx: &mut Context<Self>) {
         self.phase = DialogPhase::Error(msg);
+        cues::play(Cue::Failure);
+        self.final_acknowledged = false;
+        self.refresh_retries(cx);
         cx.notify();
     }
 
+    fn refresh_retries(&mut self, cx: &mut Context<Self>) {
+        self._retries_task = Some(refresh_pin_status(cx, |this: &mut Self, status| {
+            this.retries = Some(status)
+        }));
+    }
+
+    fn render_retries(&self, cx: &Context<Self>) -> Option<impl IntoElement> {
+        self.retries
+            .as_ref()
+            .map(|status| render_pin_status(status, cx))
+    }
+
+    fn attempt_allowed(&self) -> bool {
+        attempt_allowed(self.retries.as_ref(), self.final_acknowledged)
+    }
+
+    fn render_attempt_budget(&self, cx: &Context<Self>) -> Option<AnyElement> {
+        let handle = cx.entity().downgrade();
+        render_attempt_budget(
+            self.retries.as_ref(),
+            self.final_acknowledged,
+            move |checked, _, cx| {
+                let _ = handle.update(cx, |this, cx| {
+                    this.final_acknowledged = *checked;
+                    cx.notify();
+                });
+            },
+            cx,
+        )
+    }
+
     fn trigger_confirm(&mut self, cx: &mut Context<Self>) {
-        if matches!(self.phase, DialogPhase::Loading | DialogPhase::Success(_)) {
+        if matches!(self.phase, DialogPhase::Loading | DialogPhase::Success(_))
+            || !self.attempt_allowed()
+        {
             return;
         }
         let pin = self.pin_input.read(cx).text().to_string();
@@ -76,10 +271,11 @@ impl PinPromptContent {
 }
 
 impl Render for PinPromptContent {
-    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         let phase = self.phase.clone();
+        let entering = matches!(phase, DialogPhase::Input | DialogPhase::Error(_));
 
-        match &phase {
+        let body = match &phase {
             DialogPhase::Success(msg) => v_flex()
                 .gap_4()
                 .child(
@@ -135,8 +331,16 @@ impl Render for PinPromptContent {
                 let confirm_label = self.confirm_label.clone();
                 let on_confirm = self.on_confirm.clone();
                 let handle = cx.entity().downgrade();
+                let allowed = self.attempt_allowed();
 
-                let mut container = v_flex().gap_4().child(self.description.clone());
+                let mut container = v_flex().gap_4().child(
+                    h_flex()
+                        .justify_between()
+                        .items_start()
+                        .gap_2()
+                        .child(self.description.clone())
+                        .children(self.render_retries(cx)),
+                );
 
                 if let Some(warning) = &self.warning {
                     container = container.child(
@@ -168,6 +372,8 @@ impl Render for PinPromptContent {
                     );
                 }
 
+                container = container.children(self.render_attempt_budget(cx));
+
                 container
                     .child(
                         div()
@@ -179,7 +385,7 @@ impl Render for PinPromptContent {
                             .text_sm()
                             .child(render_error_message(err_msg.clone())),
                     )
-                    .child(Input::new(&pin_input))
+                    .child(Input::new(&pin_input).mask_toggle())
                     .child(
                         h_flex()
                             .justify_end()
@@ -193,6 +399,7 @@ impl Render for PinPromptContent {
                                 Button::new("confirm")
                                     .primary()
                                     .label(confirm_label)
+                                    .disabled(!allowed)
                                     .on_click(move |_, _, cx| {
                                         let pin = pin_input.read(cx).text().to_string();
                                         if !pin.is_empty() {
@@ -212,8 +419,16 @@ impl Render for PinPromptContent {
                 let confirm_label = self.confirm_label.clone();
                 let on_confirm = self.on_confirm.clone();
                 let handle = cx.entity().downgrade();
+                let allowed = self.attempt_allowed();
 
-                let mut container = v_flex().gap_4().child(self.description.clone());
+                let mut container = v_flex().gap_4().child(
+                    h_flex()
+                        .justify_between()
+                        .items_start()
+                        .gap_2()
+                        .child(self.description.clone())
+                        .children(self.render_retries(cx)),
+                );
 
                 if let Some(warning) = &self.warning {
                     container = container.child(
@@ -245,8 +460,10 @@ impl Render for PinPromptContent {
                     );
                 }
 
+                container = container.children(self.render_attempt_budget(cx));
+
                 container
-                    .child(Input::new(&pin_input))
+                    .child(Input::new(&pin_input).mask_toggle())
                     .child(
                         h_flex()
                             .justify_end()
@@ -260,6 +477,7 @@ impl Render for PinPromptContent {
                                 Button::new("confirm")
                                     .primary()
                                     .label(confirm_label)
+                                    .disabled(!allowed)
                                     .on_click(move |_, _, cx| {
                                         let pin = pin_input.read(cx).text().to_string();
                                         if !pin.is_empty() {
@@ -273,10 +491,50 @@ impl Render for PinPromptContent {
                     )
                     .into_any_element()
             }
-        }
+        };
+
+        pin_dialog_frame(body, entering, window, cx)
     }
 }
 
+/// Wraps a PIN dialog body with the caps-lock warning, re-rendered whenever
+/// a modifier key changes. `entering` is true while a PIN field is editable;
+/// Escape cancels only then, so a write in progress cannot be orphaned.
+fn pin_dialog_frame<T: 'static>(
+    body: AnyElement,
+    entering: bool,
+    window: &Window,
+    cx: &mut Context<T>,
+) -> impl IntoElement {
+    let caps_lock = entering && window.capslock().on;
+    v_flex()
+        .gap_3()
+        .on_modifiers_changed(cx.listener(|_, _: &ModifiersChangedEvent, _, cx| cx.notify()))
+        .on_key_down(cx.listener(move |_, event: &KeyDownEvent, window, cx| {
+            if event.keystroke.key == "escape" {
+                cx.stop_propagation();
+                if entering {
+                    window.close_dialog(cx);
+                }
+            }
+        }))
+        .child(body)
+        .when(caps_lock, |this| {
+            this.child(
+                h_flex()
+                    .gap_2()
+                    .items_center()
+                    .text_sm()
+                    .text_color(cx.theme().warning)
+                    .child(
+                        gpui_component::Icon::new(gpui_component::IconName::TriangleAlert)
+                            .with_size(gpui_component::Size::Small),
+                    )
+                    .child("Caps Lock is on"),
+            )
+        })
+}
+
 /// Open a PIN prompt dialog and return the submitted PIN.
 pub fn open_pin_prompt(
     title: &str,
@@ -308,7 +566,7 @@ pub fn open_pin_prompt(
             }
         });
 
-        PinPromptContent {
+        let mut content = PinPromptContent {
             phase: DialogPhase::Input,
             title: title_str,
             description,
@@ -316,8 +574,13 @@ pub fn open_pin_prompt(
             confirm_label,
             pin_input: pin_for_sub,
             on_confirm: std::rc::Rc::new(on_confirm),
+            retries: None,
+            final_acknowledged: false,
             _subscription: sub,
-        }
+            _retries_task: None,
+        };
+        content.refresh_retries(cx);
+        content
     });
 
     window.open_dialog(cx, move |dialog, _, _| {
@@ -348,12 +611,14 @@ impl ConfirmContent {
     /// Transition the dialog to a success state.
     pub fn set_success(&mut self, msg: String, cx: &mut Context<Self>) {
         self.phase = DialogPhase::Success(msg);
+        cues::play(Cue::Success);
         cx.notify();
     }
 
     /// Transition the dialog to an error state with the given message.
     pub fn set_error(&mut self, msg: String, cx: &mut Context<Self>) {
         self.phase = DialogPhase::Error(msg);
+        cues::play(Cue::Failure);
         cx.notify();
     }
 }
@@ -523,10 +788,53 @@ pub struct ChangePinContent {
     new_pin: Entity<InputState>,
     confirm_pin: Entity<InputState>,
     on_confirm: ChangePinCallback,
+    /// Attempts left for the current PIN, refreshed after every failure.
+    retries: Option<PinStatus>,
+    /// The "I understand" box for the final attempt is ticked.
+    final_acknowledged: bool,
     _subscriptions: Vec<Subscription>,
+    _retries_task: Option<Task<()>>,
 }
 
 impl ChangePinContent {
+    fn refresh_retries(&mut self, cx: &mut Context<Self>) {
+        self._retries_task = Some(refresh_pin_status(cx, |this: &mut Self, status| {
+            this.retries = Some(status)
+        }));
+    }
+
+    fn render_intro(&self, cx: &Context<Self>) -> impl IntoElement {
+        h_flex()
+            .justify_between()
+            .items_start()
+            .gap_2()
+            .child("Enter your current PIN and choose a new one.")
+            .children(
+                self.retries
+                    .as_ref()
+                    .map(|status| render_pin_status(status, cx)),
+            )
+    }
+
+    fn attempt_allowed(&self) -> bool {
+        attempt_allowed(self.retries.as_ref(), self.final_acknowledged)
+    }
+
+    fn render_attempt_budget(&self, cx: &Context<Self>) -> Option<AnyElement> {
+        let handle = cx.entity().downgrade();
+        render_attempt_budget(
+            self.retries.as_ref(),
+            self.final_acknowledged,
+            move |checked, _, cx| {
+                let _ = handle.update(cx, |this, cx| {
+                    this.final_acknowledged = *checked;
+                    cx.notify();
+                });
+            },
+            cx,
+        )
+    }
+
     fn set_loading(&mut self, cx: &mut Context<Self>) {
         self.phase = DialogPhase::Loading;
         cx.notify();
@@ -535,17 +843,23 @@ impl ChangePinContent {
     /// Transition the dialog to a success state.
     pub fn set_success(&mut self, msg: String, cx: &mut Context<Self>) {
         self.phase = DialogPhase::Success(msg);
+        cues::play(Cue::Success);
         cx.notify();
     }
 
     /// Transition the dialog to an error state with the given message.
     pub fn set_error(&mut self, msg: String, cx: &mut Context<Self>) {
         self.phase = DialogPhase::Error(msg);
+        cues::play(Cue::Failure);
+        self.final_acknowledged = false;
+        self.refresh_retries(cx);
         cx.notify();
     }
 
     fn trigger_confirm(&mut self, cx: &mut Context<Self>) {
-        if matches!(self.phase, DialogPhase::Loading | DialogPhase::Success(_)) {
+        if matches!(self.phase, DialogPhase::Loading | DialogPhase::Success(_))
+            || !self.attempt_allowed()
+        {
             return;
         }
 
@@ -562,8 +876,8 @@ impl ChangePinContent {
             return;
         }
 
-        if new_pin_text.len() < 4 {
-            self.set_error("PIN must be at least 4 characters".to_string(), cx);
+        if let Err(e) = pin::prepare_new(&new_pin_text) {
+            self.set_error(e, cx);
             return;
         }
 
@@ -574,10 +888,11 @@ impl ChangePinContent {
 }
 
 impl Render for ChangePinContent {
-    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         let phase = self.phase.clone();
+        let entering = matches!(phase, DialogPhase::Input | DialogPhase::Error(_));
 
-        match &phase {
+        let body = match &phase {
             DialogPhase::Success(msg) => v_flex()
                 .gap_4()
                 .child(
@@ -637,10 +952,12 @@ impl Render for ChangePinContent {
                 let confirm_pin_entity = self.confirm_pin.clone();
                 let on_confirm = self.on_confirm.clone();
                 let handle = cx.entity().downgrade();
+                let allowed = self.attempt_allowed();
 
                 v_flex()
                     .gap_4()
-                    .child("Enter your current PIN and choose a new one.")
+                    .child(self.render_intro(cx))
+                    .children(self.render_attempt_budget(cx))
                     .child(
                         div()
                             .px_3()
@@ -655,11 +972,11 @@ impl Render for ChangePinContent {
                         v_flex()
                             .gap_4()
                             .child("Current PIN")
-                            .child(Input::new(&current_pin_entity))
+                            .child(Input::new(&current_pin_entity).mask_toggle())
                             .child("New PIN")
-                            .child(Input::new(&new_pin_entity))
+                            .child(Input::new(&new_pin_entity).mask_toggle())
                             .child("Confirm New PIN")
-                            .child(Input::new(&confirm_pin_entity)),
+                            .child(Input::new(&confirm_pin_entity).mask_toggle()),
                     )
                     .child(
                         h_flex()
@@ -670,45 +987,55 @@ impl Render for ChangePinContent {
                                     .label("Cancel")
                                     .on_click(|_, window, cx| window.close_dialog(cx)),
                             )
-                            .child(Button::new("confirm").primary().label("Confirm").on_click(
-                                move |_, _, cx| {
-                                    let current_pin_text =
-                                        current_pin_entity.read(cx).text().to_string();
-                                    let new_pin_text = new_pin_entity.read(cx).text().to_string();
-                                    let confirm_pin_text =
-                                        confirm_pin_entity.read(cx).text().to_string();
+                            .child(
+                                Button::new("confirm")
+                                    .primary()
+                                    .label("Confirm")
+                                    .disabled(!allowed)
+                                    .on_click(move |_, _, cx| {
+                                        let current_pin_text =
+                                            current_pin_entity.read(cx).text().to_string();
+                                        let new_pin_text =
+                                            new_pin_entity.read(cx).text().to_string();
+                                        let confirm_pin_text =
+                                            confirm_pin_entity.read(cx).text().to_string();
+
+                                        if current_pin_text.is_empty() {
+                                            return;
+                                        }
 
-                                    if current_pin_text.is_empty() {
-                                        return;
-                                    }
+                                        if new_pin_text != confirm_pin_text {
+                                            if let Some(h) = handle.upgrade() {
+                                                h.update(cx, |this, cx| {
+                                                    this.set_error(
+                                                        "PINs do not match".to_string(),
+                                                        cx,
+                                                    );
+                                                });
+                                            }
+                                            return;
+                                        }
 
-                                    if new_pin_text != confirm_pin_text {
-                                        if let Some(h) = handle.upgrade() {
-                                            h.update(cx, |this, cx| {
-                                                this.set_error("PINs do not match".to_string(), cx);
-                                            });
+                                        if let Err(e) = pin::prepare_new(&new_pin_text) {
+                                            if let Some(h) = handle.upgrade() {
+                                                h.update(cx, |this, cx| {
+                                                    this.set_error(e, cx);
+                                                });
+                                            }
+                                            return;
                                         }
-                                        return;
-                                    }
 
-                                    if new_pin_text.len() < 4 {
                                         if let Some(h) = handle.upgrade() {
-                                            h.update(cx, |this, cx| {
-                                                this.set_error(
-                                                    "PIN must be at least 4 characters".to_string(),
-                                                    cx,
-                                                );
-                                            });
+                                            h.update(cx, |this, cx| this.set_loading(cx));
                                         }
-                                        return;
-                                    }
-
-                                    if let Some(h) = handle.upgrade() {
-                                        h.update(cx, |this, cx| this.set_loading(cx));
-                                    }
-                                    on_confirm(current_pin_text, new_pin_text, handle.clone(), cx);
-                                },
-                            )),
+                                        on_confirm(
+                                            current_pin_text,
+                                            new_pin_text,
+                                            handle.clone(),
+                                            cx,
+                                        );
+                                    }),
+                            ),
                     )
                     .into_any_element()
             }
@@ -719,19 +1046,21 @@ impl Render for ChangePinContent {
                 let confirm_pin_entity = self.confirm_pin.clone();
                 let on_confirm = self.on_confirm.clone();
                 let handle = cx.entity().downgrade();
+                let allowed = self.attempt_allowed();
 
                 v_flex()
                     .gap_4()
-                    .child("Enter your current PIN and choose a new one.")
+                    .child(self.render_intro(cx))
+                    .children(self.render_attempt_budget(cx))
                     .child(
                         v_flex()
                             .gap_4()
                             .child("Current PIN")
-                            .child(Input::new(&current_pin_entity))
+                            .child(Input::new(&current_pin_entity).mask_toggle())
                             .child("New PIN")
-                            .child(Input::new(&new_pin_entity))
+                            .child(Input::new(&new_pin_entity).mask_toggle())
                             .child("Confirm New PIN")
-                            .child(Input::new(&confirm_pin_entity)),
+                            .child(Input::new(&confirm_pin_entity).mask_toggle()),
                     )
                     .child(
                         h_flex()
@@ -742,49 +1071,61 @@ impl Render for ChangePinContent {
                                     .label("Cancel")
                                     .on_click(|_, window, cx| window.close_dialog(cx)),
                             )
-                            .child(Button::new("confirm").primary().label("Confirm").on_click(
-                                move |_, _, cx| {
-                                    let current_pin_text =
-                                        current_pin_entity.read(cx).text().to_string();
-                                    let new_pin_text = new_pin_entity.read(cx).text().to_string();
-                                    let confirm_pin_text =
-                                        confirm_pin_entity.read(cx).text().to_string();
+                            .child(
+                                Button::new("confirm")
+                                    .primary()
+                                    .label("Confirm")
+                                    .disabled(!allowed)
+                                    .on_click(move |_, _, cx| {
+                                        let current_pin_text =
+                                            current_pin_entity.read(cx).text().to_string();
+                                        let new_pin_text =
+                                            new_pin_entity.read(cx).text().to_string();
+                                        let confirm_pin_text =
+                                            confirm_pin_entity.read(cx).text().to_string();
+
+                                        if current_pin_text.is_empty() {
+                                            return;
+                                        }
 
-                                    if current_pin_text.is_empty() {
-                                        return;
-                                    }
+                                        if new_pin_text != confirm_pin_text {
+                                            if let Some(h) = handle.upgrade() {
+                                                h.update(cx, |this, cx| {
+                                                    this.set_error(
+                                                        "PINs do not match".to_string(),
+                                                        cx,
+                                                    );
+                                                });
+                                            }
+                                            return;
+                                        }
 
-                                    if new_pin_text != confirm_pin_text {
-                                        if let Some(h) = handle.upgrade() {
-                                            h.update(cx, |this, cx| {
-                                                this.set_error("PINs do not match".to_string(), cx);
-                                            });
+                                        if let Err(e) = pin::prepare_new(&new_pin_text) {
+                                            if let Some(h) = handle.upgrade() {
+                                                h.update(cx, |this, cx| {
+                                                    this.set_error(e, cx);
+                                                });
+                                            }
+                                            return;
                                         }
-                                        return;
-                                    }
 
-                                    if new_pin_text.len() < 4 {
                                         if let Some(h) = handle.upgrade() {
-                                            h.update(cx, |this, cx| {
-                                                this.set_error(
-                                                    "PIN must be at least 4 characters".to_string(),
-                                                    cx,
-                                                );
-                                            });
+                                            h.update(cx, |this, cx| this.set_loading(cx));
                                         }
-                                        return;
-                                    }
-
-                                    if let Some(h) = handle.upgrade() {
-                                        h.update(cx, |this, cx| this.set_loading(cx));
-                                    }
-                                    on_confirm(current_pin_text, new_pin_text, handle.clone(), cx);
-                                },
-                            )),
+                                        on_confirm(
+                                            current_pin_text,
+                                            new_pin_text,
+                                            handle.clone(),
+                                            cx,
+                                        );
+                                    }),
+                            ),
                     )
                     .into_any_element()
             }
-        }
+        };
+
+        pin_dialog_frame(body, entering, window, cx)
     }
 }
 
@@ -822,14 +1163,19 @@ pub fn open_change_pin(
             },
         );
 
-        ChangePinContent {
+        let mut content = ChangePinContent {
             phase: DialogPhase::Input,
             current_pin,
             new_pin,
             confirm_pin: confirm_for_sub,
             on_confirm: std::rc::Rc::new(on_confirm),
+            retries: None,
+            final_acknowledged: false,
             _subscriptions: vec![sub],
-        }
+            _retries_task: None,
+        };
+        content.refresh_retries(cx);
+        content
     });
 
     window.open_dialog(cx, move |dialog, _, _| {
@@ -859,12 +1205,14 @@ impl SetPinContent {
     /// Transition the dialog to a success state.
     pub fn set_success(&mut self, msg: String, cx: &mut Context<Self>) {
         self.phase = DialogPhase::Success(msg);
+        cues::play(Cue::Success);
         cx.notify();
     }
 
     /// Transition the dialog to an error state with the given message.
     pub fn set_error(&mut self, msg: String, cx: &mut Context<Self>) {
         self.phase = DialogPhase::Error(msg);
+        cues::play(Cue::Failure);
         cx.notify();
     }
 
@@ -881,8 +1229,8 @@ impl SetPinContent {
             return;
         }
 
-        if new_pin_text.len() < 4 {
-            self.set_error("PIN must be at least 4 characters".to_string(), cx);
+        if let Err(e) = pin::prepare_new(&new_pin_text) {
+            self.set_error(e, cx);
             return;
         }
 
@@ -893,10 +1241,11 @@ impl SetPinContent {
 }
 
 impl Render for SetPinContent {
-    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         let phase = self.phase.clone();
+        let entering = matches!(phase, DialogPhase::Input | DialogPhase::Error(_));
 
-        match &phase {
+        let body = match &phase {
             DialogPhase::Success(msg) => v_flex()
                 .gap_4()
                 .child(
@@ -971,9 +1320,9 @@ impl Render for SetPinContent {
                         v_flex()
                             .gap_4()
                             .child("New PIN")
-                            .child(Input::new(&new_pin_entity))
+                            .child(Input::new(&new_pin_entity).mask_toggle())
                             .child("Confirm New PIN")
-                            .child(Input::new(&confirm_pin_entity)),
+                            .child(Input::new(&confirm_pin_entity).mask_toggle()),
                     )
                     .child(
                         h_flex()
@@ -999,13 +1348,10 @@ impl Render for SetPinContent {
                                         return;
                                     }
 
-                                    if new_pin_text.len() < 4 {
+                                    if let Err(e) = pin::prepare_new(&new_pin_text) {
                                         if let Some(h) = handle.upgrade() {
                                             h.update(cx, |this, cx| {
-                                                this.set_error(
-                                                    "PIN must be at least 4 characters".to_string(),
-                                                    cx,
-                                                );
+                                                this.set_error(e, cx);
                                             });
                                         }
                                         return;
@@ -1034,9 +1380,9 @@ impl Render for SetPinContent {
                         v_flex()
                             .gap_4()
                             .child("New PIN")
-                            .child(Input::new(&new_pin_entity))
+                            .child(Input::new(&new_pin_entity).mask_toggle())
                             .child("Confirm New PIN")
-                            .child(Input::new(&confirm_pin_entity)),
+                            .child(Input::new(&confirm_pin_entity).mask_toggle()),
                     )
                     .child(
                         h_flex()
@@ -1062,13 +1408,10 @@ impl Render for SetPinContent {
                                         return;
                                     }
 
-                                    if new_pin_text.len() < 4 {
+                                    if let Err(e) = pin::prepare_new(&new_pin_text) {
                                         if let Some(h) = handle.upgrade() {
                                             h.update(cx, |this, cx| {
-                                                this.set_error(
-                                                    "PIN must be at least 4 characters".to_string(),
-                                                    cx,
-                                                );
+                                                this.set_error(e, cx);
                                             });
                                         }
                                         return;
@@ -1083,7 +1426,9 @@ impl Render for SetPinContent {
                     )
                     .into_any_element()
             }
-        }
+        };
+
+        pin_dialog_frame(body, entering, window, cx)
     }
 }
 
@@ -1149,11 +1494,13 @@ impl StatusContent {
 
     pub fn set_success(&mut self, msg: String, cx: &mut Context<Self>) {
         self.phase = DialogPhase::Success(msg);
+        cues::play(Cue::Success);
         cx.notify();
     }
 
     pub fn set_error(&mut self, msg: String, cx: &mut Context<Self>) {
         self.phase = DialogPhase::Error(msg);
+        cues::play(Cue::Failure);
         cx.notify();
     }
 }
@@ -1280,28 +1627,40 @@ pub fn open_status_dialog(
 }
 
 fn render_error_message(msg: String) -> impl IntoElement {
-    let troubleshooting_phrase = "troubleshooting guide";
-    let url = "https://github.com/librekeys/picoforge/wiki/Troubleshooting#1-my-key-is-not-detected-by-picoforge-or-picoforge-displays-a-device-status-of-online---fido-and-there-are-some-settings-that-i-cannot-configure";
-
-    if msg.contains(troubleshooting_phrase) {
+    // Errors reach the dialogs as text; recover the CTAP error once here.
+    let ctap = reference::ctap_error_in(&msg);
+    let topic = help::help_topic_for_error(&msg, ctap);
+
+    if let Some(lockout) = ctap.and_then(pin_lockout_from_error) {
+        v_flex().gap_2().child(div().child(msg)).child(
+            h_flex()
+                .gap_1()
+                .items_center()
+                .text_color(rgb(0x3b82f6))
+                .cursor_pointer()
+                .on_mouse_down(MouseButton::Left, move |_, window, cx| {
+                    pin_recovery::open_pin_recovery(lockout, window, cx);
+                })
+                .child(gpui_component::Icon::new(
+                    gpui_component::IconName::ArrowRight,
+                ))
+                .child("Start PIN recovery"),
+        )
+    } else if topic == Some(HelpTopic::FidoOnlyMode) {
         v_flex()
+            .gap_1()
             .child("The device firmware does not support being configured in fido only communication mode.")
-            .child(
-                h_flex()
-                    .gap_1()
-                    .child("Have a look at the")
-                    .child(
-                        div()
-                            .text_color(rgb(0x3b82f6))
-                            .cursor_pointer()
-                            .on_mouse_down(MouseButton::Left, move |_, _, cx| {
-                                cx.open_url(url);
-                            })
-                            .child(troubleshooting_phrase.to_string()),
-                    )
-                    .child("to fix this"),
-            )
+            .child(help::help_link(
+                HelpTopic::FidoOnlyMode,
+                "Open the troubleshooting guide to fix this",
+            ))
     } else {
-        div().child(msg)
+        let status = reference::status_code_in(&msg);
+        v_flex()
+            .gap_2()
+            .child(div().child(msg.clone()))
+            .children(status.map(spec_reference::spec_link))
+            .children(topic.map(|topic| help::help_link(topic, format!("Help: {}", topic.title()))))
+            .child(issue_report::report_issue_link(msg))
     }
 }
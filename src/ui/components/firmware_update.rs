@@ -0,0 +1,264 @@
+//! Firmware update dialog: release notes between the installed firmware and
+//! the latest release, from [`crate::release_notes`].
+//!
+//! PicoForge does not flash firmware; the dialog helps decide whether an
+//! update is worth doing and links to the release page for the download.
+
+use crate::release_notes::{self, Release, ReleaseNotes};
+use crate::ui::models::device::FirmwareType;
+use gpui::*;
+use gpui_component::{
+    ActiveTheme, Icon, IconName, Sizable, WindowExt,
+    button::{Button, ButtonVariants},
+    h_flex,
+    text::TextView,
+    v_flex,
+};
+
+/// Open the release notes for the connected key's firmware.
+pub fn open_firmware_update(
+    firmware: FirmwareType,
+    installed: String,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let content = cx.new(|cx| {
+        let mut content = FirmwareUpdateContent {
+            firmware,
+            installed,
+            notes: None,
+            loading: false,
+            _task: None,
+        };
+        content.load(cx);
+        content
+    });
+
+    window.open_dialog(cx, move |dialog, _, _| {
+        dialog.title("Firmware Updates").child(content.clone())
+    });
+}
+
+pub struct FirmwareUpdateContent {
+    firmware: FirmwareType,
+    installed: String,
+    notes: Option<Result<ReleaseNotes, String>>,
+    loading: bool,
+    _task: Option<Task<()>>,
+}
+
+impl FirmwareUpdateContent {
+    fn load(&mut self, cx: &mut Context<Self>) {
+        if self.loading {
+            return;
+        }
+        self.loading = true;
+        cx.notify();
+
+        let firmware = self.firmware.clone();
+        let installed = self.installed.clone();
+        self._task = Some(cx.spawn(async move |this, cx| {
+            let result = cx
+                .background_executor()
+                .spawn(async move { release_notes::load(&firmware, &installed) })
+                .await;
+
+            let _ = this.update(cx, |this, cx| {
+                this.loading = false;
+                if let Err(e) = &result {
+                    log::warn!("Release notes unavailable: {}", e);
+                }
+                this.notes = Some(result);
+                cx.notify();
+            });
+        }));
+    }
+
+    fn render_summary(&self, notes: &ReleaseNotes, cx: &Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+        let latest = notes
+            .latest
+            .as_ref()
+            .map(|r| r.tag_name.clone())
+            .unwrap_or_else(|| "unknown".to_string());
+
+        let (icon, color, heading) = if notes.newer.is_empty() {
+            (
+                IconName::CircleCheck,
+                theme.green,
+                "This key runs the latest release.".to_string(),
+            )
+        } else {
+            (
+                IconName::Info,
+                theme.primary,
+                format!(
+                    "{} newer release{} available.",
+                    notes.newer.len(),
+                    if notes.newer.len() == 1 {
+                        " is"
+                    } else {
+                        "s are"
+                    }
+                ),
+            )
+        };
+
+        v_flex()
+            .gap_1()
+            .child(
+                h_flex()
+                    .gap_2()
+                    .items_center()
+                    .child(
+                        Icon::new(icon)
+                            .text_color(color)
+                            .with_size(gpui_component::Size::Medium),
+                    )
+                    .child(div().font_semibold().child(heading)),
+            )
+            .child(
+                div()
+                    .text_sm()
+                    .text_color(theme.muted_foreground)
+                    .child(format!("Installed v{} · Latest {}", self.installed, latest)),
+            )
+    }
+
+    fn render_release(ix: usize, release: &Release, cx: &Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+        let title = release
+            .name
+            .clone()
+            .filter(|n| !n.trim().is_empty())
+            .unwrap_or_else(|| release.tag_name.clone());
+        let date = release
+            .published_at
+            .as_deref()
+            .and_then(|d| d.get(..10))
+            .unwrap_or_default()
+            .to_string();
+        let body = release
+            .body
+            .clone()
+            .filter(|b| !b.trim().is_empty())
+            .unwrap_or_else(|| "_No release notes were published for this release._".to_string());
+        let url = release.html_url.clone();
+
+        v_flex()
+            .gap_2()
+            .p_3()
+            .border_1()
+            .border_color(theme.border)
+            .rounded_lg()
+            .child(
+                h_flex()
+                    .justify_between()
+                    .items_center()
+                    .child(
+                        h_flex()
+                            .gap_2()
+                            .items_baseline()
+                            .child(div().font_semibold().child(title))
+                            .child(
+                                div()
+                                    .text_xs()
+                                    .text_color(theme.muted_foreground)
+                                    .child(date),
+                            ),
+                    )
+                    .child(
+                        Button::new(("release-page", ix))
+                            .ghost()
+                            .small()
+                            .icon(Icon::default().path("icons/external-link.svg"))
+                            .label("Release Page")
+                            .on_click(move |_, _, cx| cx.open_url(&url)),
+                    ),
+            )
+            .child(TextView::markdown(
+                SharedString::from(format!("release-notes-{}", release.tag_name)),
+                body,
+            ))
+    }
+}
+
+impl Render for FirmwareUpdateContent {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+
+        let body =
+            match &self.notes {
+                None => div()
+                    .text_sm()
+                    .text_color(theme.muted_foreground)
+                    .child("Fetching release notes...")
+                    .into_any_element(),
+                Some(Err(e)) => div()
+                    .text_sm()
+                    .text_color(theme.danger)
+                    .child(e.clone())
+                    .into_any_element(),
+                Some(Ok(notes)) => {
+                    let offline = notes.offline.as_ref().map(|reason| {
+                        div().text_sm().text_color(theme.warning).child(format!(
+                            "Offline ({}). Showing release notes saved {}.",
+                            reason,
+                            release_notes::age(notes.fetched_at)
+                        ))
+                    });
+                    v_flex()
+                        .gap_3()
+                        .child(self.render_summary(notes, cx))
+                        .children(offline)
+                        .child(
+                            div()
+                                .id("release-notes-list")
+                                .max_h(px(420.))
+                                .overflow_y_scroll()
+                                .child(
+                                    v_flex()
+                                        .gap_3()
+                                        .children(notes.newer.iter().enumerate().map(
+                                            |(ix, release)| Self::render_release(ix, release, cx),
+                                        )),
+                                ),
+                        )
+                        .into_any_element()
+                }
+            };
+
+        v_flex()
+            .gap_4()
+            .child(body)
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(cx.theme().muted_foreground)
+                    .child(
+                        "PicoForge does not flash firmware. Download the UF2 from the release \
+                         page and copy it to the key in BOOTSEL mode.",
+                    ),
+            )
+            .child(
+                h_flex()
+                    .justify_end()
+                    .gap_2()
+                    .child(
+                        Button::new("release-notes-refresh")
+                            .icon(Icon::default().path("icons/refresh-cw.svg"))
+                            .label("Refresh")
+                            .loading(self.loading)
+                            .on_click(cx.listener(|this, _, _, cx| this.load(cx))),
+                    )
+                    .child(
+                        Button::new("release-notes-close")
+                            .primary()
+                            .label("Close")
+                            .on_click(|_, window, cx| {
+                                window.close_dialog(cx);
+                            }),
+                    ),
+            )
+    }
+}
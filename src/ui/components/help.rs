@@ -0,0 +1,175 @@
+//! In-app help drawer with bundled troubleshooting guides.
+//!
+//! The guides live as Markdown under `static/help/` and are compiled into the
+//! binary, so they are available offline and always match the running version.
+//! Error dialogs link straight to the relevant topic via [`help_topic_for_error`].
+//! The drawer also replays the onboarding tour ([`StartTour`]).
+
+use crate::ui::app::StartTour;
+use crate::ui::models::device::Ctap2Error;
+use gpui::*;
+use gpui_component::{
+    ActiveTheme, Icon, Placement, Sizable, WindowExt,
+    button::{Button, ButtonVariants},
+    h_flex,
+    text::TextView,
+    v_flex,
+};
+
+/// A bundled troubleshooting guide.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HelpTopic {
+    DeviceOffline,
+    FidoOnlyMode,
+    PinBlocked,
+    Permissions,
+    Replug,
+}
+
+impl HelpTopic {
+    /// All topics, in the order they appear in the drawer.
+    pub const ALL: [HelpTopic; 5] = [
+        HelpTopic::DeviceOffline,
+        HelpTopic::FidoOnlyMode,
+        HelpTopic::PinBlocked,
+        HelpTopic::Permissions,
+        HelpTopic::Replug,
+    ];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            HelpTopic::DeviceOffline => "Device offline",
+            HelpTopic::FidoOnlyMode => "FIDO-only mode",
+            HelpTopic::PinBlocked => "PIN blocked",
+            HelpTopic::Permissions => "Permissions",
+            HelpTopic::Replug => "Unplug and re-plug",
+        }
+    }
+
+    fn markdown(self) -> &'static str {
+        match self {
+            HelpTopic::DeviceOffline => include_str!("../../../static/help/device-offline.md"),
+            HelpTopic::FidoOnlyMode => include_str!("../../../static/help/fido-only-mode.md"),
+            HelpTopic::PinBlocked => include_str!("../../../static/help/pin-blocked.md"),
+            HelpTopic::Permissions => include_str!("../../../static/help/permissions.md"),
+            HelpTopic::Replug => include_str!("../../../static/help/replug.md"),
+        }
+    }
+}
+
+/// Pick the guide that best explains an error, given its message and the CTAP
+/// error it carries, if any.
+pub fn help_topic_for_error(msg: &str, ctap: Option<Ctap2Error>) -> Option<HelpTopic> {
+    let lower = msg.to_lowercase();
+
+    if lower.contains("troubleshooting guide") || lower.contains("fido only") {
+        Some(HelpTopic::FidoOnlyMode)
+    } else if ctap == Some(Ctap2Error::PinBlocked) || lower.contains("pin blocked") {
+        Some(HelpTopic::PinBlocked)
+    } else if ctap == Some(Ctap2Error::PinAuthBlocked)
+        || lower.contains("unplug")
+        || lower.contains("re-plug")
+    {
+        Some(HelpTopic::Replug)
+    } else if lower.contains("permission denied") || lower.contains("access denied") {
+        Some(HelpTopic::Permissions)
+    } else if lower.contains("no device") || lower.contains("not found") {
+        Some(HelpTopic::DeviceOffline)
+    } else {
+        None
+    }
+}
+
+/// Open the help drawer, optionally jumping straight to `topic`.
+pub fn open_help(topic: Option<HelpTopic>, window: &mut Window, cx: &mut App) {
+    let content = cx.new(|_| HelpContent {
+        selected: topic.unwrap_or(HelpTopic::DeviceOffline),
+    });
+
+    window.open_sheet_at(Placement::Right, cx, move |sheet, _, _| {
+        sheet
+            .title("Help")
+            .size(px(520.))
+            .resizable(false)
+            .child(content.clone())
+    });
+}
+
+/// Drawer body: topic switcher plus the rendered guide.
+pub struct HelpContent {
+    selected: HelpTopic,
+}
+
+impl Render for HelpContent {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+        let selected = self.selected;
+
+        let topics = h_flex()
+            .flex_wrap()
+            .gap_1()
+            .pb_3()
+            .border_b_1()
+            .border_color(theme.border)
+            .children(HelpTopic::ALL.iter().enumerate().map(|(ix, topic)| {
+                let topic = *topic;
+                let button = Button::new(("help-topic", ix))
+                    .label(topic.title())
+                    .on_click(cx.listener(move |this, _, _, cx| {
+                        this.selected = topic;
+                        cx.notify();
+                    }));
+                if topic == selected {
+                    button.primary()
+                } else {
+                    button.ghost()
+                }
+            }));
+
+        let tour = h_flex()
+            .justify_between()
+            .items_center()
+            .text_sm()
+            .text_color(theme.muted_foreground)
+            .child("New to PicoForge?")
+            .child(
+                Button::new("help-start-tour")
+                    .small()
+                    .label("Take the UI tour")
+                    .on_click(|_, window, cx| {
+                        window.close_sheet(cx);
+                        window.dispatch_action(Box::new(StartTour), cx);
+                    }),
+            );
+
+        v_flex()
+            .size_full()
+            .gap_3()
+            .child(tour)
+            .child(topics)
+            .child(
+                div()
+                    .id("help-body")
+                    .flex_1()
+                    .overflow_y_scroll()
+                    .child(TextView::markdown(
+                        SharedString::from(format!("help-{:?}", selected)),
+                        selected.markdown(),
+                    )),
+            )
+    }
+}
+
+/// Inline "open help" link shown under an error message.
+pub fn help_link(topic: HelpTopic, label: impl Into<SharedString>) -> impl IntoElement {
+    h_flex()
+        .gap_1()
+        .items_center()
+        .text_color(rgb(0x3b82f6))
+        .cursor_pointer()
+        .on_mouse_down(MouseButton::Left, move |_, window, cx| {
+            open_help(Some(topic), window, cx);
+        })
+        .child(Icon::default().path("icons/book-open.svg"))
+        .child(label.into())
+}
@@ -0,0 +1,35 @@
+//! "Report this issue" entry points that open a pre-filled GitHub issue
+//! draft from [`crate::issue_report`].
+
+use crate::issue_report::IssueDraft;
+use gpui::*;
+use gpui_component::{Icon, h_flex};
+
+/// Collect a draft for `error` (or a general report) off the UI thread and
+/// open it in the browser.
+pub fn open_issue_draft(error: Option<String>, cx: &mut App) {
+    cx.spawn(async move |cx| {
+        let url = cx
+            .background_executor()
+            .spawn(async move { IssueDraft::collect(error.as_deref()).url() })
+            .await;
+        log::info!("Opening issue draft ({} bytes)", url.len());
+        let _ = cx.update(|cx| cx.open_url(&url));
+    })
+    .detach();
+}
+
+/// Inline "report this issue" link shown under an error message.
+pub fn report_issue_link(error: impl Into<String>) -> impl IntoElement {
+    let error = error.into();
+    h_flex()
+        .gap_1()
+        .items_center()
+        .text_color(rgb(0x3b82f6))
+        .cursor_pointer()
+        .on_mouse_down(MouseButton::Left, move |_, _, cx| {
+            open_issue_draft(Some(error.clone()), cx);
+        })
+        .child(Icon::default().path("icons/github.svg"))
+        .child("Report this issue")
+}
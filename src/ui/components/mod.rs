@@ -2,7 +2,17 @@
 
 pub mod button;
 pub mod card;
+pub mod coach_mark;
 pub mod dialog;
+pub mod firmware_update;
+pub mod help;
+pub mod issue_report;
 pub mod page_view;
+pub mod pin_recovery;
+pub mod qr;
 pub mod sidebar;
+pub mod spec_reference;
+pub mod startup_status;
+pub mod table;
 pub mod tag;
+pub mod view_state;
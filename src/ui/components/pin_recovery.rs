@@ -0,0 +1,251 @@
+//! Guided recovery dialog for PIN lockouts (CTAP 0x32 / 0x34).
+//!
+//! Instead of leaving the user with a bare status code, the dialog reads the
+//! remaining retries, explains whether the lockout clears with a replug or
+//! needs a factory reset, and lets the user re-check after replugging.
+
+use crate::ui::components::help::{self, HelpTopic};
+use crate::ui::models::device::{DeviceRepo, PinLockout, PinRetries, pin_lockout_from_retries};
+use gpui::*;
+use gpui_component::{
+    ActiveTheme, Icon, IconName, Sizable, WindowExt,
+    button::{Button, ButtonVariants},
+    h_flex, v_flex,
+};
+
+/// Open the PIN recovery dialog for the lockout reported by a failed operation.
+pub fn open_pin_recovery(lockout: PinLockout, window: &mut Window, cx: &mut App) {
+    let content = cx.new(|cx| {
+        let mut content = PinRecoveryContent {
+            lockout: Some(lockout),
+            retries: None,
+            checking: false,
+            check_error: None,
+            _task: None,
+        };
+        content.check(cx);
+        content
+    });
+
+    window.open_dialog(cx, move |dialog, _, _| {
+        dialog
+            .title("PIN Recovery")
+            .child(content.clone())
+            .overlay_closable(false)
+    });
+}
+
+pub struct PinRecoveryContent {
+    /// Current lockout; `None` once a re-check shows PIN entry is available.
+    lockout: Option<PinLockout>,
+    retries: Option<PinRetries>,
+    checking: bool,
+    check_error: Option<String>,
+    _task: Option<Task<()>>,
+}
+
+impl PinRecoveryContent {
+    /// Re-read the retry counter and update the lockout state from it.
+    fn check(&mut self, cx: &mut Context<Self>) {
+        if self.checking {
+            return;
+        }
+        self.checking = true;
+        cx.notify();
+
+        self._task = Some(cx.spawn(async move |this, cx| {
+            let result = cx
+                .background_executor()
+                .spawn(async move { DeviceRepo::get_pin_retries_blocking() })
+                .await;
+
+            let _ = this.update(cx, |this, cx| {
+                this.checking = false;
+                match result {
+                    Ok(retries) => {
+                        log::info!(
+                            "PIN retries: {} (power cycle required: {})",
+                            retries.retries,
+                            retries.power_cycle_required
+                        );
+                        this.lockout = pin_lockout_from_retries(&retries);
+                        this.retries = Some(retries);
+                        this.check_error = None;
+                    }
+                    Err(e) => {
+                        log::warn!("Could not read PIN retries: {}", e);
+                        this.check_error = Some(e);
+                    }
+                }
+                cx.notify();
+            });
+        }));
+    }
+
+    fn render_retries(&self, cx: &Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+
+        let (value, color) = match (&self.retries, &self.check_error) {
+            (Some(r), _) if r.retries == 0 => (
+                "0 — the PIN is permanently blocked".to_string(),
+                theme.danger,
+            ),
+            (Some(r), _) => (
+                format!(
+                    "{} attempt{} left before a permanent block",
+                    r.retries,
+                    if r.retries == 1 { "" } else { "s" }
+                ),
+                theme.foreground,
+            ),
+            (None, Some(_)) => (
+                "Unknown — the key is not reachable".to_string(),
+                theme.muted_foreground,
+            ),
+            (None, None) => ("Reading...".to_string(), theme.muted_foreground),
+        };
+
+        h_flex()
+            .justify_between()
+            .items_center()
+            .px_3()
+            .py_2()
+            .rounded_md()
+            .bg(theme.muted.opacity(0.3))
+            .border_1()
+            .border_color(theme.border)
+            .child(
+                div()
+                    .text_sm()
+                    .text_color(theme.muted_foreground)
+                    .child("Remaining PIN retries"),
+            )
+            .child(div().text_sm().font_medium().text_color(color).child(value))
+    }
+
+    fn steps(&self) -> (&'static str, &'static str, Vec<&'static str>) {
+        match self.lockout {
+            Some(PinLockout::PowerCycleRequired) => (
+                "PIN entry is paused",
+                "Three wrong PINs were entered since the key was plugged in. This lock clears when the key restarts; the remaining retries are kept.",
+                vec![
+                    "Unplug the key and wait about two seconds.",
+                    "Plug it back in.",
+                    "Press \"Check again\" below.",
+                    "Retry the operation with the correct PIN.",
+                ],
+            ),
+            Some(PinLockout::Blocked) => (
+                "The PIN is blocked",
+                "Every PIN retry has been used. The PIN can no longer be entered and replugging does not help.",
+                vec![
+                    "Open Security in the sidebar.",
+                    "In the Reset card, choose \"Reset Device\" and confirm.",
+                    "Unplug and re-plug the key within 10 seconds, then touch it.",
+                    "Set a new PIN and re-register the key with your accounts.",
+                ],
+            ),
+            None => (
+                "PIN entry is available",
+                "The key accepts PIN entry again. Retry the operation, carefully.",
+                vec![],
+            ),
+        }
+    }
+}
+
+impl Render for PinRecoveryContent {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+        let (heading, explanation, steps) = self.steps();
+
+        let (icon, icon_color) = match self.lockout {
+            Some(PinLockout::Blocked) => (IconName::CircleX, theme.danger),
+            Some(PinLockout::PowerCycleRequired) => (IconName::TriangleAlert, theme.warning),
+            None => (IconName::CircleCheck, theme.green),
+        };
+
+        let steps_list =
+            v_flex()
+                .gap_1()
+                .children(steps.into_iter().enumerate().map(|(ix, step)| {
+                    h_flex()
+                        .gap_2()
+                        .items_start()
+                        .text_sm()
+                        .child(
+                            div()
+                                .text_color(theme.muted_foreground)
+                                .child(format!("{}.", ix + 1)),
+                        )
+                        .child(step)
+                }));
+
+        let data_loss_warning = (self.lockout == Some(PinLockout::Blocked)).then(|| {
+            div()
+                .px_3()
+                .py_2()
+                .rounded_md()
+                .bg(rgb(0x18181b))
+                .text_color(rgb(0xef4444))
+                .text_sm()
+                .child("A factory reset permanently deletes every passkey stored on the key.")
+        });
+
+        let check_error = self.check_error.clone().map(|e| {
+            div()
+                .text_sm()
+                .text_color(theme.muted_foreground)
+                .child(format!("Last check failed: {}", e))
+        });
+
+        v_flex()
+            .gap_4()
+            .child(
+                h_flex()
+                    .gap_2()
+                    .items_center()
+                    .child(
+                        Icon::new(icon)
+                            .text_color(icon_color)
+                            .with_size(gpui_component::Size::Large),
+                    )
+                    .child(div().font_semibold().child(heading)),
+            )
+            .child(
+                div()
+                    .text_sm()
+                    .text_color(theme.muted_foreground)
+                    .child(explanation),
+            )
+            .child(self.render_retries(cx))
+            .child(steps_list)
+            .children(data_loss_warning)
+            .children(check_error)
+            .child(help::help_link(
+                HelpTopic::PinBlocked,
+                "Read the full PIN troubleshooting guide",
+            ))
+            .child(
+                h_flex()
+                    .justify_end()
+                    .gap_2()
+                    .child(
+                        Button::new("pin-recovery-check")
+                            .label("Check again")
+                            .loading(self.checking)
+                            .on_click(cx.listener(|this, _, _, cx| {
+                                this.check(cx);
+                            })),
+                    )
+                    .child(
+                        Button::new("pin-recovery-close")
+                            .primary()
+                            .label("Close")
+                            .on_click(|_, window, cx| {
+                                window.close_dialog(cx);
+                            }),
+                    ),
+            )
+    }
+}
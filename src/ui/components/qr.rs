@@ -0,0 +1,97 @@
+//! Locally generated QR codes, rendered as GPUI elements or exported as SVG.
+
+use gpui::prelude::FluentBuilder;
+use gpui::*;
+use qrcode::{Color, EcLevel, QrCode};
+
+/// Light modules around the code, as required by the QR spec.
+const QUIET_ZONE: usize = 4;
+
+/// Encoded QR module grid.
+#[derive(Clone)]
+pub struct QrMatrix {
+    width: usize,
+    dark: Vec<bool>,
+}
+
+impl QrMatrix {
+    /// Encode `data` at error-correction level M.
+    pub fn encode(data: &str) -> Result<Self, String> {
+        let code = QrCode::with_error_correction_level(data.as_bytes(), EcLevel::M)
+            .map_err(|e| format!("Could not encode QR code: {}", e))?;
+        Ok(Self {
+            width: code.width(),
+            dark: code
+                .to_colors()
+                .into_iter()
+                .map(|c| c == Color::Dark)
+                .collect(),
+        })
+    }
+
+    fn is_dark(&self, x: usize, y: usize) -> bool {
+        self.dark[y * self.width + x]
+    }
+
+    /// Standalone SVG document, one unit per module, including the quiet zone.
+    pub fn to_svg(&self) -> String {
+        let size = self.width + 2 * QUIET_ZONE;
+        let mut path = String::new();
+        for y in 0..self.width {
+            for x in 0..self.width {
+                if self.is_dark(x, y) {
+                    path.push_str(&format!("M{},{}h1v1h-1z", x + QUIET_ZONE, y + QUIET_ZONE));
+                }
+            }
+        }
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {size} {size}\" shape-rendering=\"crispEdges\">\
+             <rect width=\"{size}\" height=\"{size}\" fill=\"#fff\"/>\
+             <path d=\"{path}\" fill=\"#000\"/></svg>"
+        )
+    }
+}
+
+/// Renders a [`QrMatrix`] as a grid of black and white squares.
+#[derive(IntoElement)]
+pub struct QrCodeView {
+    matrix: QrMatrix,
+    module: Pixels,
+}
+
+impl QrCodeView {
+    pub fn new(matrix: QrMatrix) -> Self {
+        Self {
+            matrix,
+            module: px(4.),
+        }
+    }
+
+    /// Size of one module in pixels.
+    pub fn module_size(mut self, module: Pixels) -> Self {
+        self.module = module;
+        self
+    }
+}
+
+impl RenderOnce for QrCodeView {
+    fn render(self, _window: &mut Window, _cx: &mut App) -> impl IntoElement {
+        let module = self.module;
+        let width = self.matrix.width;
+
+        div()
+            .flex()
+            .flex_col()
+            .flex_none()
+            .bg(rgb(0xffffff))
+            .p(module * QUIET_ZONE as f32)
+            .children((0..width).map(|y| {
+                let matrix = &self.matrix;
+                div().flex().flex_row().children((0..width).map(move |x| {
+                    div()
+                        .size(module)
+                        .when(matrix.is_dark(x, y), |this| this.bg(rgb(0x000000)))
+                }))
+            }))
+    }
+}
@@ -2,7 +2,9 @@
 
 use crate::ui::app::Destination;
 use crate::ui::components::button::PFIconButton;
-use crate::ui::models::device::{DeviceMethod, DeviceRepo};
+use crate::ui::components::coach_mark;
+use crate::ui::components::help;
+use crate::ui::models::device::{DeviceDescriptor, DeviceMethod, DeviceRepo};
 use gpui::*;
 use gpui_component::{
     ActiveTheme, Icon, IconName, Side,
@@ -18,6 +20,8 @@ pub enum SidebarEvent {
     Navigate(Destination),
     /// Re-poll device hardware.
     RefreshDevice,
+    /// Manage the FIDO interface at this hidapi path.
+    SelectDevice(String),
 }
 
 impl EventEmitter<SidebarEvent> for AppSidebar {}
@@ -32,6 +36,8 @@ pub struct AppSidebar {
     current_width: Pixels,
     active_destination: Destination,
     device: Entity<DeviceRepo>,
+    /// Whether the device picker list is unfolded.
+    picker_open: bool,
 }
 
 impl AppSidebar {
@@ -42,6 +48,7 @@ impl AppSidebar {
             current_width: px(255.),
             active_destination,
             device,
+            picker_open: false,
         }
     }
 
@@ -80,6 +87,127 @@ impl AppSidebar {
             }))
     }
 
+    /// Dropdown listing the attached FIDO keys, shown when there is more than
+    /// one. Picking an entry makes it the device every screen manages.
+    fn device_picker(&self, cx: &mut Context<Self>) -> Option<AnyElement> {
+        let state = self.device.read(cx);
+        if state.fido_devices.len() < 2 {
+            return None;
+        }
+        let label = |device: &DeviceDescriptor| {
+            format!("{} ({:04X}:{:04X})", device.product, device.vid, device.pid)
+        };
+        let active = state.active_device_path.clone();
+        let current = state
+            .fido_devices
+            .iter()
+            .find(|d| Some(&d.path) == active.as_ref())
+            .map(label)
+            .unwrap_or_else(|| "Choose a device".to_string());
+        let theme = cx.theme();
+        let (fg, muted, hover) = (
+            theme.sidebar_foreground,
+            theme.muted_foreground,
+            theme.sidebar_accent,
+        );
+
+        let toggle = h_flex()
+            .id("device-picker")
+            .w_full()
+            .items_center()
+            .justify_between()
+            .gap_2()
+            .px_2()
+            .py_1()
+            .rounded_md()
+            .border_1()
+            .border_color(theme.sidebar_border)
+            .cursor_pointer()
+            .hover(move |s| s.bg(hover))
+            .child(
+                div()
+                    .text_size(px(12.))
+                    .text_color(fg)
+                    .overflow_hidden()
+                    .text_ellipsis()
+                    .whitespace_nowrap()
+                    .child(current),
+            )
+            .child(
+                Icon::new(if self.picker_open {
+                    IconName::ChevronUp
+                } else {
+                    IconName::ChevronDown
+                })
+                .size_4()
+                .text_color(muted),
+            )
+            .on_click(cx.listener(|this, _, _, cx| {
+                this.picker_open = !this.picker_open;
+                cx.notify();
+            }));
+
+        let entries = self.picker_open.then(|| {
+            v_flex()
+                .gap_0p5()
+                .children(state.fido_devices.iter().enumerate().map(|(ix, device)| {
+                    let path = device.path.clone();
+                    let selected = Some(&device.path) == active.as_ref();
+                    h_flex()
+                        .id(("device-picker-entry", ix))
+                        .w_full()
+                        .items_center()
+                        .gap_2()
+                        .px_2()
+                        .py_1()
+                        .rounded_md()
+                        .cursor_pointer()
+                        .hover(move |s| s.bg(hover))
+                        .child(div().w(px(16.)).flex_shrink_0().children(
+                            selected.then(|| Icon::new(IconName::Check).size_4().text_color(fg)),
+                        ))
+                        .child(
+                            v_flex()
+                                .overflow_hidden()
+                                .child(
+                                    div()
+                                        .text_size(px(12.))
+                                        .text_color(fg)
+                                        .text_ellipsis()
+                                        .whitespace_nowrap()
+                                        .child(label(device)),
+                                )
+                                .child(
+                                    div()
+                                        .text_size(px(10.))
+                                        .text_color(muted)
+                                        .text_ellipsis()
+                                        .whitespace_nowrap()
+                                        .child(
+                                            device
+                                                .serial
+                                                .clone()
+                                                .unwrap_or_else(|| device.path.clone()),
+                                        ),
+                                ),
+                        )
+                        .on_click(cx.listener(move |this, _, _, cx| {
+                            this.picker_open = false;
+                            cx.emit(SidebarEvent::SelectDevice(path.clone()));
+                            cx.notify();
+                        }))
+                }))
+        });
+
+        Some(
+            v_flex()
+                .gap_1()
+                .child(toggle)
+                .children(entries)
+                .into_any_element(),
+        )
+    }
+
     fn menu_item_icon_name(
         &self,
         cx: &mut Context<Self>,
@@ -110,9 +238,12 @@ impl Render for AppSidebar {
             self.current_width = target_width;
         }
 
+        let device_picker = self.device_picker(cx);
         let state = self.device.read(cx);
         let status_owned = state.status.clone();
         let error_owned = state.error.clone();
+        let capabilities = state.capabilities();
+        let nickname = state.nickname().map(str::to_string);
 
         let sidebar_bg = cx.theme().sidebar;
         let sidebar_fg = cx.theme().sidebar_foreground;
@@ -166,6 +297,46 @@ impl Render for AppSidebar {
         };
 
         // ── Navigation items (gpui-component Sidebar) ────────────────
+        // Device-specific screens are only listed when the connected key
+        // supports them; with no device only Home (connect instructions),
+        // About and Help remain.
+        let mut menu = SidebarMenu::new().child(self.menu_item(
+            cx,
+            "Home",
+            "icons/house.svg",
+            Destination::Home,
+        ));
+        for (label, icon_path, dest) in [
+            ("Passkeys", "icons/key-round.svg", Destination::Passkeys),
+            (
+                "Biometrics",
+                "icons/fingerprint.svg",
+                Destination::Biometrics,
+            ),
+            (
+                "Configuration",
+                "icons/settings.svg",
+                Destination::Configuration,
+            ),
+            ("Security", "icons/shield-check.svg", Destination::Security),
+            ("Notes Vault", "icons/scroll-text.svg", Destination::Vault),
+            ("OpenPGP", "icons/lock.svg", Destination::OpenPgp),
+            ("OATH", "icons/asterisk.svg", Destination::Oath),
+        ] {
+            if dest.is_available(&capabilities) {
+                menu = menu.child(self.menu_item(cx, label, icon_path, dest));
+            }
+        }
+        let menu = menu
+            .child(self.menu_item_icon_name(cx, "About", IconName::Info, Destination::About))
+            .child(
+                SidebarMenuItem::new("Help")
+                    .icon(Icon::default().path("icons/book-open.svg"))
+                    .on_click(cx.listener(|_, _, window, cx| {
+                        help::open_help(None, window, cx);
+                    })),
+            );
+
         let nav_sidebar = Sidebar::new(Side::Left)
             .collapsed(sidebar_width < px(120.))
             .collapsible(false)
@@ -174,36 +345,7 @@ impl Render for AppSidebar {
             .flex_grow()
             .bg(sidebar_bg)
             .border_color(gpui::transparent_white())
-            .child(
-                SidebarGroup::new("Menu").child(
-                    SidebarMenu::new()
-                        .child(self.menu_item(cx, "Home", "icons/house.svg", Destination::Home))
-                        .child(self.menu_item(
-                            cx,
-                            "Passkeys",
-                            "icons/key-round.svg",
-                            Destination::Passkeys,
-                        ))
-                        .child(self.menu_item(
-                            cx,
-                            "Configuration",
-                            "icons/settings.svg",
-                            Destination::Configuration,
-                        ))
-                        .child(self.menu_item(
-                            cx,
-                            "Security",
-                            "icons/shield-check.svg",
-                            Destination::Security,
-                        ))
-                        .child(self.menu_item_icon_name(
-                            cx,
-                            "About",
-                            IconName::Info,
-                            Destination::About,
-                        )),
-                ),
-            );
+            .child(SidebarGroup::new("Menu").child(menu));
 
         // ── Footer (device status + refresh) ─────────────────────────
         let footer = v_flex()
@@ -220,34 +362,48 @@ impl Render for AppSidebar {
                     .justify_center()
                     .gap_2()
                     .child(
-                        Button::new("refresh-btn-collapsed")
-                            .ghost()
-                            .child(Icon::default().path("icons/refresh-cw.svg"))
-                            .on_click(cx.listener(|_, _, _, cx| {
-                                cx.emit(SidebarEvent::RefreshDevice);
-                            }))
-                            .w_full(),
+                        div()
+                            .relative()
+                            .w_full()
+                            .child(
+                                Button::new("refresh-btn-collapsed")
+                                    .ghost()
+                                    .child(Icon::default().path("icons/refresh-cw.svg"))
+                                    .on_click(cx.listener(|_, _, _, cx| {
+                                        cx.emit(SidebarEvent::RefreshDevice);
+                                    }))
+                                    .w_full(),
+                            )
+                            .child(coach_mark::anchor("sidebar.refresh")),
                     )
-                    .child(div().w(px(8.)).h(px(8.)).rounded_full().bg(
-                        if let Some(s) = &status_owned {
-                            if s.method == DeviceMethod::Fido {
+                    .child(
+                        div()
+                            .relative()
+                            .w(px(8.))
+                            .h(px(8.))
+                            .rounded_full()
+                            .child(coach_mark::anchor("sidebar.status"))
+                            .bg(if let Some(s) = &status_owned {
+                                if s.method == DeviceMethod::Fido {
+                                    rgb(0xf59e0b)
+                                } else {
+                                    rgb(0x22c55e)
+                                }
+                            } else if error_owned.is_some() {
                                 rgb(0xf59e0b)
                             } else {
-                                rgb(0x22c55e)
-                            }
-                        } else if error_owned.is_some() {
-                            rgb(0xf59e0b)
-                        } else {
-                            rgb(0xef4444)
-                        },
-                    ))
+                                rgb(0xef4444)
+                            }),
+                    )
             } else {
                 v_flex()
                     .gap_3()
                     .child(
                         h_flex()
+                            .relative()
                             .items_center()
                             .justify_between()
+                            .child(coach_mark::anchor("sidebar.status"))
                             .child(
                                 div()
                                     .text_size(px(12.))
@@ -284,11 +440,31 @@ impl Render for AppSidebar {
                                     )
                             }),
                     )
+                    .children(nickname.map(|nickname| {
+                        div()
+                            .text_size(px(12.))
+                            .text_color(sidebar_fg)
+                            .overflow_hidden()
+                            .text_ellipsis()
+                            .whitespace_nowrap()
+                            .child(nickname)
+                    }))
+                    .children(device_picker)
                     .child(
-                        PFIconButton::new(Icon::default().path("icons/refresh-cw.svg"), "Refresh")
-                            .on_click(cx.listener(|_, _, _, cx| {
-                                cx.emit(SidebarEvent::RefreshDevice);
-                            })),
+                        div()
+                            .relative()
+                            .child(
+                                PFIconButton::new(
+                                    Icon::default().path("icons/refresh-cw.svg"),
+                                    "Refresh",
+                                )
+                                .on_click(cx.listener(
+                                    |_, _, _, cx| {
+                                        cx.emit(SidebarEvent::RefreshDevice);
+                                    },
+                                )),
+                            )
+                            .child(coach_mark::anchor("sidebar.refresh")),
                     )
             });
 
@@ -300,7 +476,14 @@ impl Render for AppSidebar {
             .border_color(border_color)
             .w(sidebar_width)
             .child(header)
-            .child(nav_sidebar)
+            .child(
+                v_flex()
+                    .relative()
+                    .w_full()
+                    .flex_grow()
+                    .child(nav_sidebar)
+                    .child(coach_mark::anchor("sidebar.menu")),
+            )
             .child(footer);
 
         div()
@@ -0,0 +1,131 @@
+//! Searchable CTAP2 reference drawer.
+//!
+//! Lists the built-in [`reference`] table (commands, CBOR keys, option names,
+//! status codes and vendor command IDs) so a wire trace or an error code can
+//! be read without the spec open. Error dialogs open it on the status byte
+//! they show; the Logging card on the About screen opens it empty.
+
+use crate::ui::models::device::reference::{self, SpecEntry};
+use gpui::*;
+use gpui_component::{
+    ActiveTheme, Icon, Placement, WindowExt, h_flex,
+    input::{Input, InputEvent, InputState},
+    v_flex,
+};
+
+/// Open the reference drawer, optionally pre-filled with `query`.
+pub fn open_spec_reference(query: Option<String>, window: &mut Window, cx: &mut App) {
+    let content = cx.new(|cx| SpecReferenceContent::new(query, window, cx));
+
+    window.open_sheet_at(Placement::Right, cx, move |sheet, _, _| {
+        sheet
+            .title("CTAP Reference")
+            .size(px(560.))
+            .resizable(false)
+            .child(content.clone())
+    });
+}
+
+/// Drawer body: search box plus the matching entries.
+pub struct SpecReferenceContent {
+    search: Entity<InputState>,
+    _subscription: Subscription,
+}
+
+impl SpecReferenceContent {
+    fn new(query: Option<String>, window: &mut Window, cx: &mut Context<Self>) -> Self {
+        let search = cx.new(|cx| {
+            InputState::new(window, cx)
+                .placeholder("Search by code, name or description (e.g. 0x31, credMgmt)")
+                .default_value(query.unwrap_or_default())
+        });
+        let _subscription = cx.subscribe(&search, |_, _, _: &InputEvent, cx| cx.notify());
+        Self {
+            search,
+            _subscription,
+        }
+    }
+
+    fn render_entry(entry: &SpecEntry, cx: &App) -> impl IntoElement {
+        let theme = cx.theme();
+        v_flex()
+            .gap_0p5()
+            .py_2()
+            .border_b_1()
+            .border_color(theme.border)
+            .child(
+                h_flex()
+                    .gap_2()
+                    .items_baseline()
+                    .child(
+                        div()
+                            .text_sm()
+                            .text_color(theme.muted_foreground)
+                            .child(entry.code_hex()),
+                    )
+                    .child(div().text_sm().font_medium().child(entry.name)),
+            )
+            .child(div().text_sm().child(entry.description))
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(theme.muted_foreground)
+                    .child(format!("{} · {}", entry.category.label(), entry.section)),
+            )
+    }
+}
+
+impl Render for SpecReferenceContent {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let query = self.search.read(cx).text().to_string();
+        let entries = reference::search(&query);
+        let count = if entries.is_empty() {
+            "No matching entries".to_string()
+        } else {
+            format!("{} entries", entries.len())
+        };
+
+        v_flex()
+            .size_full()
+            .gap_3()
+            .child(Input::new(&self.search))
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(cx.theme().muted_foreground)
+                    .child(count),
+            )
+            .child(
+                div()
+                    .id("spec-reference-list")
+                    .flex_1()
+                    .overflow_y_scroll()
+                    .child(
+                        v_flex().children(
+                            entries
+                                .into_iter()
+                                .map(|entry| Self::render_entry(entry, cx)),
+                        ),
+                    ),
+            )
+    }
+}
+
+/// Inline "look up" link shown under an error that carries a CTAP status byte.
+pub fn spec_link(code: u8) -> impl IntoElement {
+    let query = format!("0x{:02X}", code);
+    let label = match reference::error(code) {
+        Some(entry) => format!("{} ({}): {}", query, entry.name, entry.description),
+        None => format!("Look up {}", query),
+    };
+    h_flex()
+        .gap_1()
+        .items_center()
+        .text_color(rgb(0x3b82f6))
+        .cursor_pointer()
+        .on_mouse_down(MouseButton::Left, move |_, window, cx| {
+            open_spec_reference(Some(query.clone()), window, cx);
+        })
+        .child(Icon::default().path("icons/book-open.svg"))
+        .child(label)
+}
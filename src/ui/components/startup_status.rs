@@ -0,0 +1,76 @@
+//! Startup status dialog listing the self-check results from [`crate::startup`].
+
+use crate::startup::{CheckStatus, StartupCheck};
+use crate::ui::components::help::{self, HelpTopic};
+use gpui::*;
+use gpui_component::{
+    ActiveTheme, Icon, IconName, Sizable, WindowExt,
+    button::{Button, ButtonVariants},
+    h_flex, v_flex,
+};
+
+/// Show the startup check results. Intended for when at least one check failed.
+pub fn open_startup_status(checks: &'static [StartupCheck], window: &mut Window, cx: &mut App) {
+    window.open_dialog(cx, move |dialog, _, cx| {
+        let theme = cx.theme();
+
+        let rows = checks.iter().map(|check| {
+            let (icon, color) = match check.status {
+                CheckStatus::Ok => (IconName::CircleCheck, theme.green),
+                CheckStatus::Warning => (IconName::TriangleAlert, theme.warning),
+                CheckStatus::Failed => (IconName::CircleX, theme.danger),
+            };
+
+            h_flex()
+                .gap_3()
+                .items_start()
+                .py_2()
+                .border_b_1()
+                .border_color(theme.border)
+                .child(Icon::new(icon).text_color(color).with_size(gpui_component::Size::Medium))
+                .child(
+                    v_flex()
+                        .gap_0p5()
+                        .child(div().text_sm().font_medium().child(check.name))
+                        .child(
+                            div()
+                                .text_sm()
+                                .text_color(theme.muted_foreground)
+                                .child(check.detail.clone()),
+                        ),
+                )
+        });
+
+        let needs_permissions_help = checks
+            .iter()
+            .any(|c| c.status != CheckStatus::Ok && c.name == "Device permissions");
+
+        dialog
+            .title("Startup Check")
+            .child(
+                v_flex()
+                    .gap_3()
+                    .child(
+                        div()
+                            .text_sm()
+                            .text_color(theme.muted_foreground)
+                            .child("Some parts of PicoForge could not be initialised. Related features may be unavailable until this is fixed."),
+                    )
+                    .child(v_flex().children(rows))
+                    .children(needs_permissions_help.then(|| {
+                        help::help_link(HelpTopic::Permissions, "Help: Permissions")
+                    }))
+                    .child(
+                        h_flex().justify_end().child(
+                            Button::new("startup-continue")
+                                .primary()
+                                .label("Continue")
+                                .on_click(|_, window, cx| {
+                                    window.close_dialog(cx);
+                                }),
+                        ),
+                    ),
+            )
+            .close_button(false)
+    });
+}
@@ -0,0 +1,289 @@
+//! Virtualized table for long lists, such as the passkeys on a key.
+//!
+//! Only the rows in view are built each frame ([`uniform_list`]), so a key
+//! with hundreds of resident credentials scrolls as smoothly as one with
+//! three. The header sits outside the scrolled area and stays put. With the
+//! table focused, ↑/↓, Page Up/Down and Home/End move the active row and
+//! Enter activates it, as a click does.
+//!
+//! The owning view keeps an `Entity<TableState>` and passes it in on every
+//! render, with a closure that builds the cells of a range of rows. Every
+//! row has the same height.
+
+use gpui::prelude::FluentBuilder;
+use gpui::*;
+use gpui_component::{ActiveTheme, h_flex, v_flex};
+use std::ops::Range;
+use std::rc::Rc;
+
+/// Width of a column.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColumnWidth {
+    Fixed(Pixels),
+    /// Shares the width left by the fixed columns with the other fill columns.
+    Fill,
+}
+
+/// One column: header text and width.
+#[derive(Clone, Debug)]
+pub struct TableColumn {
+    pub title: SharedString,
+    pub width: ColumnWidth,
+}
+
+impl TableColumn {
+    pub fn fill(title: impl Into<SharedString>) -> Self {
+        Self {
+            title: title.into(),
+            width: ColumnWidth::Fill,
+        }
+    }
+
+    pub fn fixed(title: impl Into<SharedString>, width: Pixels) -> Self {
+        Self {
+            title: title.into(),
+            width: ColumnWidth::Fixed(width),
+        }
+    }
+}
+
+/// Focus, scroll position and active row of one table; lives as long as
+/// the view that owns it.
+pub struct TableState {
+    focus_handle: FocusHandle,
+    scroll_handle: UniformListScrollHandle,
+    active_row: Option<usize>,
+}
+
+impl TableState {
+    pub fn new(cx: &mut Context<Self>) -> Self {
+        Self {
+            focus_handle: cx.focus_handle(),
+            scroll_handle: UniformListScrollHandle::new(),
+            active_row: None,
+        }
+    }
+
+    /// Make `row` active and scroll it into view.
+    fn set_active_row(&mut self, row: Option<usize>, cx: &mut Context<Self>) {
+        self.active_row = row;
+        if let Some(row) = row {
+            self.scroll_handle.scroll_to_item(row, ScrollStrategy::Top);
+        }
+        cx.notify();
+    }
+
+    /// Move the active row for `key`. Returns whether the key was a
+    /// navigation key.
+    fn navigate(
+        &mut self,
+        key: &str,
+        row_count: usize,
+        page: usize,
+        cx: &mut Context<Self>,
+    ) -> bool {
+        if row_count == 0 {
+            return false;
+        }
+        let last = row_count - 1;
+        let current = self.active_row.map(|row| row.min(last));
+        let next = match (key, current) {
+            ("down", None) | ("home", _) | ("pagedown", None) => 0,
+            ("up", None) | ("end", _) | ("pageup", None) => last,
+            ("down", Some(row)) => (row + 1).min(last),
+            ("up", Some(row)) => row.saturating_sub(1),
+            ("pagedown", Some(row)) => (row + page).min(last),
+            ("pageup", Some(row)) => row.saturating_sub(page),
+            _ => return false,
+        };
+        self.set_active_row(Some(next), cx);
+        true
+    }
+}
+
+type RenderRows = dyn Fn(Range<usize>, &mut Window, &mut App) -> Vec<Vec<AnyElement>>;
+type OnActivate = dyn Fn(usize, &mut Window, &mut App);
+
+/// A table over `row_count` rows of `row_height`, at most `max_height` tall.
+#[derive(IntoElement)]
+pub struct VirtualTable {
+    id: ElementId,
+    state: Entity<TableState>,
+    columns: Vec<TableColumn>,
+    row_count: usize,
+    row_height: Pixels,
+    max_height: Pixels,
+    render_rows: Rc<RenderRows>,
+    on_activate: Option<Rc<OnActivate>>,
+}
+
+impl VirtualTable {
+    /// `render_rows` returns, for each row in the range, one element per
+    /// column.
+    pub fn new(
+        id: impl Into<ElementId>,
+        state: &Entity<TableState>,
+        columns: Vec<TableColumn>,
+        row_count: usize,
+        render_rows: impl Fn(Range<usize>, &mut Window, &mut App) -> Vec<Vec<AnyElement>> + 'static,
+    ) -> Self {
+        Self {
+            id: id.into(),
+            state: state.clone(),
+            columns,
+            row_count,
+            row_height: px(44.),
+            max_height: px(480.),
+            render_rows: Rc::new(render_rows),
+            on_activate: None,
+        }
+    }
+
+    pub fn row_height(mut self, height: Pixels) -> Self {
+        self.row_height = height;
+        self
+    }
+
+    pub fn max_height(mut self, height: Pixels) -> Self {
+        self.max_height = height;
+        self
+    }
+
+    /// Called with the row index on click and on Enter.
+    pub fn on_activate(mut self, f: impl Fn(usize, &mut Window, &mut App) + 'static) -> Self {
+        self.on_activate = Some(Rc::new(f));
+        self
+    }
+}
+
+/// Size `cell` to its column.
+fn sized(cell: Div, width: ColumnWidth) -> Div {
+    match width {
+        ColumnWidth::Fixed(width) => cell.w(width).flex_none(),
+        ColumnWidth::Fill => cell.flex_1().min_w_0(),
+    }
+}
+
+impl RenderOnce for VirtualTable {
+    fn render(self, _window: &mut Window, cx: &mut App) -> impl IntoElement {
+        let theme = cx.theme();
+        let (border, muted_fg, header_bg, active_bg, hover_bg) = (
+            theme.border,
+            theme.muted_foreground,
+            theme.secondary,
+            theme.accent,
+            theme.muted,
+        );
+        let (focus_handle, scroll_handle, active_row) = {
+            let state = self.state.read(cx);
+            (
+                state.focus_handle.clone(),
+                state.scroll_handle.clone(),
+                state.active_row,
+            )
+        };
+        let row_count = self.row_count;
+        let row_height = self.row_height;
+        let (row_px, max_px) = (f32::from(row_height), f32::from(self.max_height));
+        let height = px((row_px * row_count as f32).min(max_px));
+        let page = ((max_px / row_px).floor() as usize).max(1);
+
+        let header = h_flex()
+            .px_3()
+            .py_2()
+            .gap_3()
+            .bg(header_bg)
+            .border_b_1()
+            .border_color(border)
+            .text_xs()
+            .font_weight(FontWeight::MEDIUM)
+            .text_color(muted_fg)
+            .children(self.columns.iter().map(|column| {
+                sized(div(), column.width)
+                    .overflow_hidden()
+                    .whitespace_nowrap()
+                    .text_ellipsis()
+                    .child(column.title.clone())
+            }));
+
+        let widths: Vec<ColumnWidth> = self.columns.iter().map(|c| c.width).collect();
+        let render_rows = self.render_rows.clone();
+        let on_activate = self.on_activate.clone();
+        let state = self.state.clone();
+        let list = uniform_list(
+            self.id.clone(),
+            row_count,
+            move |range: Range<usize>, window, cx| {
+                let start = range.start;
+                render_rows(range, window, cx)
+                    .into_iter()
+                    .enumerate()
+                    .map(|(offset, cells)| {
+                        let ix = start + offset;
+                        let state = state.clone();
+                        let on_activate = on_activate.clone();
+                        h_flex()
+                            .id(ix)
+                            .h(row_height)
+                            .px_3()
+                            .gap_3()
+                            .items_center()
+                            .border_b_1()
+                            .border_color(border)
+                            .cursor_pointer()
+                            .when(active_row == Some(ix), |row| row.bg(active_bg))
+                            .hover(|row| row.bg(hover_bg))
+                            .on_click(move |_, window, cx| {
+                                state.update(cx, |state, cx| {
+                                    state.focus_handle.focus(window);
+                                    state.active_row = Some(ix);
+                                    cx.notify();
+                                });
+                                if let Some(on_activate) = &on_activate {
+                                    on_activate(ix, window, cx);
+                                }
+                            })
+                            .children(cells.into_iter().zip(widths.iter()).map(|(cell, width)| {
+                                sized(div(), *width)
+                                    .overflow_hidden()
+                                    .whitespace_nowrap()
+                                    .text_ellipsis()
+                                    .child(cell)
+                            }))
+                    })
+                    .collect::<Vec<_>>()
+            },
+        )
+        .track_scroll(scroll_handle)
+        .h(height);
+
+        let state = self.state.clone();
+        let on_activate = self.on_activate.clone();
+        v_flex()
+            .w_full()
+            .border_1()
+            .border_color(border)
+            .rounded_lg()
+            .overflow_hidden()
+            .text_sm()
+            .track_focus(&focus_handle)
+            .on_key_down(move |event: &KeyDownEvent, window, cx| {
+                let key = event.keystroke.key.as_str();
+                if key == "enter" {
+                    let active = state.read(cx).active_row.filter(|row| *row < row_count);
+                    if let (Some(row), Some(on_activate)) = (active, &on_activate) {
+                        on_activate(row, window, cx);
+                        cx.stop_propagation();
+                    }
+                    return;
+                }
+                let handled =
+                    state.update(cx, |state, cx| state.navigate(key, row_count, page, cx));
+                if handled {
+                    cx.stop_propagation();
+                }
+            })
+            .child(header)
+            .child(list)
+    }
+}
@@ -0,0 +1,212 @@
+//! Placeholders a screen shows in place of its content: [`Skeleton`] while
+//! the device is being read, [`EmptyState`] when there is nothing to show and
+//! [`ErrorState`] when reading failed.
+//!
+//! [`for_phase`] picks one from the device repo's [`DevicePhase`], so every
+//! screen that needs a key reacts to the reader the same way.
+
+use crate::ui::models::device::{DevicePhase, DeviceRepo};
+use gpui::prelude::FluentBuilder;
+use gpui::*;
+use gpui_component::{
+    ActiveTheme, Icon, IconName, StyledExt,
+    button::{Button, ButtonVariants},
+    v_flex,
+};
+use std::time::Duration;
+
+type RetryCallback = Box<dyn Fn(&ClickEvent, &mut Window, &mut App)>;
+
+/// Pulsing grey blocks shaped like the cards that are about to appear.
+#[derive(IntoElement)]
+pub struct Skeleton {
+    cards: usize,
+}
+
+impl Skeleton {
+    /// A skeleton of `cards` card outlines.
+    pub fn new(cards: usize) -> Self {
+        Self { cards }
+    }
+}
+
+impl RenderOnce for Skeleton {
+    fn render(self, _window: &mut Window, cx: &mut App) -> impl IntoElement {
+        let theme = cx.theme();
+        let bar = |width: f32| div().h_3().w(relative(width)).rounded_md().bg(theme.muted);
+
+        v_flex().gap_6().children((0..self.cards).map(|ix| {
+            v_flex()
+                .gap_4()
+                .p_6()
+                .border_1()
+                .border_color(theme.border)
+                .rounded_xl()
+                .child(bar(0.3).h_4())
+                .child(bar(0.8))
+                .child(bar(0.6))
+                .with_animation(
+                    ElementId::NamedInteger("skeleton".into(), ix as u64),
+                    Animation::new(Duration::from_millis(1600))
+                        .repeat()
+                        .with_easing(pulsating_between(0.4, 1.0)),
+                    |card, delta| card.opacity(delta),
+                )
+        }))
+    }
+}
+
+/// An icon, a headline and a hint, centred in a bordered panel. Extra
+/// children (steps, links) go below the hint.
+#[derive(IntoElement)]
+pub struct EmptyState {
+    icon: Icon,
+    title: SharedString,
+    description: Option<SharedString>,
+    children: Vec<AnyElement>,
+}
+
+impl EmptyState {
+    pub fn new(icon: impl Into<Icon>, title: impl Into<SharedString>) -> Self {
+        Self {
+            icon: icon.into(),
+            title: title.into(),
+            description: None,
+            children: Vec::new(),
+        }
+    }
+
+    pub fn description(mut self, description: impl Into<SharedString>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+}
+
+impl ParentElement for EmptyState {
+    fn extend(&mut self, elements: impl IntoIterator<Item = AnyElement>) {
+        self.children.extend(elements);
+    }
+}
+
+impl RenderOnce for EmptyState {
+    fn render(self, _window: &mut Window, cx: &mut App) -> impl IntoElement {
+        let theme = cx.theme();
+        v_flex()
+            .items_center()
+            .gap_4()
+            .py_12()
+            .px_6()
+            .border_1()
+            .border_color(theme.border)
+            .rounded_xl()
+            .child(self.icon.size_10().text_color(theme.muted_foreground))
+            .child(div().text_xl().font_bold().child(self.title))
+            .when_some(self.description, |this, description| {
+                this.child(
+                    div()
+                        .max_w(px(520.))
+                        .text_sm()
+                        .text_center()
+                        .text_color(theme.muted_foreground)
+                        .child(description),
+                )
+            })
+            .children(self.children)
+    }
+}
+
+/// A failed operation: what failed, the error as reported, and a retry
+/// button when the caller can try again.
+#[derive(IntoElement)]
+pub struct ErrorState {
+    title: SharedString,
+    message: SharedString,
+    on_retry: Option<RetryCallback>,
+}
+
+impl ErrorState {
+    pub fn new(title: impl Into<SharedString>, message: impl Into<SharedString>) -> Self {
+        Self {
+            title: title.into(),
+            message: message.into(),
+            on_retry: None,
+        }
+    }
+
+    pub fn on_retry(
+        mut self,
+        handler: impl Fn(&ClickEvent, &mut Window, &mut App) + 'static,
+    ) -> Self {
+        self.on_retry = Some(Box::new(handler));
+        self
+    }
+}
+
+impl RenderOnce for ErrorState {
+    fn render(self, _window: &mut Window, cx: &mut App) -> impl IntoElement {
+        let theme = cx.theme();
+        v_flex()
+            .items_center()
+            .gap_4()
+            .py_12()
+            .px_6()
+            .border_1()
+            .border_color(theme.danger)
+            .rounded_xl()
+            .child(
+                Icon::new(IconName::CircleX)
+                    .size_10()
+                    .text_color(theme.danger),
+            )
+            .child(div().text_xl().font_bold().child(self.title))
+            .child(
+                div()
+                    .max_w(px(520.))
+                    .text_sm()
+                    .text_center()
+                    .text_color(theme.muted_foreground)
+                    .child(self.message),
+            )
+            .when_some(self.on_retry, |this, on_retry| {
+                this.child(
+                    Button::new("view-state-retry")
+                        .outline()
+                        .label("Try again")
+                        .on_click(on_retry),
+                )
+            })
+    }
+}
+
+/// The placeholder for `phase`, or `None` when the screen should render its
+/// content. `absent` is the screen's own "connect a key" panel; a failed read
+/// offers a retry that refreshes `device`.
+pub fn for_phase(
+    phase: &DevicePhase,
+    absent: impl IntoElement,
+    device: &Entity<DeviceRepo>,
+) -> Option<AnyElement> {
+    match phase {
+        DevicePhase::Ready => None,
+        DevicePhase::Loading => Some(Skeleton::new(2).into_any_element()),
+        DevicePhase::Absent => Some(absent.into_any_element()),
+        DevicePhase::Paused(reason) => Some(
+            EmptyState::new(IconName::Loader, "Device access paused")
+                .description(format!(
+                    "{}. The key is read again once it is released.",
+                    reason
+                ))
+                .into_any_element(),
+        ),
+        DevicePhase::Failed(error) => {
+            let device = device.downgrade();
+            Some(
+                ErrorState::new("Could not read the device", error.clone())
+                    .on_retry(move |_, _, cx| {
+                        let _ = device.update(cx, |repo, cx| repo.refresh(cx));
+                    })
+                    .into_any_element(),
+            )
+        }
+    }
+}
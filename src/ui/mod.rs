@@ -86,6 +86,7 @@
 //! ├── assets.rs          # AssetLoaderImpl via rust-embed (loads SVGs from static/)
 //! ├── colors.rs          # Zinc palette constants (u32 RGB). WIP — HSLA migration planned.
 //! │                       # Reference: https://ui.shadcn.com/colors
+//! ├── quick_actions.rs   # Registry of app-wide actions; which are pinned to the title bar
 //! ├── models/
 //! │   ├── mod.rs         # pub mod device
 //! │   └── device.rs      # DeviceRepo — reactive state for device status, FIDO info,
@@ -102,7 +103,7 @@
 //! │   │                   # Renders nav items; emits Nav / RefreshDevice events
 //! │   └── tag.rs         # Tag/badge widgets
 //! ├── screens/
-//! │   ├── mod.rs         # pub mod home, config, passkeys, security, about
+//! │   ├── mod.rs         # pub mod home, config, passkeys, biometrics, security, vault, about
 //! │   ├── home/
 //! │   │   ├── mod.rs     # HomeView re-export
 //! │   │   ├── view_model.rs  # HomeViewModel — device summary state
@@ -115,10 +116,18 @@
 //! │   │   ├── mod.rs     # PasskeysView re-export
 //! │   │   ├── view_model.rs  # PasskeysViewModel — credential list, unlock state
 //! │   │   └── view.rs    # PasskeysView — passkey table, credential operations
+//! │   ├── biometrics/
+//! │   │   ├── mod.rs     # BiometricsViewModel re-export
+//! │   │   ├── view_model.rs  # BiometricsViewModel — unlock, enrollment loop, removal
+//! │   │   └── view.rs    # Fingerprint list, enrollment progress
 //! │   ├── security/
 //! │   │   ├── mod.rs     # SecurityView re-export
 //! │   │   ├── view_model.rs  # SecurityViewModel — reset, attestation, FIDO2 config
 //! │   │   └── view.rs    # SecurityView — security settings UI
+//! │   ├── vault/
+//! │   │   ├── mod.rs     # VaultViewModel re-export
+//! │   │   ├── view_model.rs  # VaultViewModel — create, unlock, edit and lock notes
+//! │   │   └── view.rs    # Note list and editor
 //! │   └── about/
 //! │       ├── mod.rs     # AboutView re-export
 //! │       ├── view_model.rs  # AboutViewModel — version, firmware details
@@ -134,8 +143,8 @@
 //!    to display. Each screen's view-model is lazily created via `get_or_insert_with`
 //!    on `ViewModelStore`. Passkeys survives navigation (only invalidated on device change).
 //! 3. [`DeviceRepo::refresh()`](crate::ui::models::device::DeviceRepo::refresh) (called at startup and on sidebar refresh) performs the
-//!    full HAL poll cycle — reads device details, FIDO info, LED/management config — and
-//!    emits [`DeviceEvent::Updated`](crate::ui::models::device::DeviceEvent::Updated). All subscribers re-read from `DeviceRepo`.
+//!    full HAL poll cycle on the HID worker thread — reads device details, FIDO info,
+//!    LED/management config — and on completion emits [`DeviceEvent::Updated`](crate::ui::models::device::DeviceEvent::Updated). All subscribers re-read from `DeviceRepo`.
 //! 4. For writes, ViewModels call `DeviceRepo::*_blocking()` static methods from background
 //!    tasks, then push fresh state via `repo.apply_fresh_state()`, which emits the event.
 //! 5. Screen view-models read `DeviceRepo` in their `Render` or event handlers
@@ -153,4 +162,6 @@ pub mod assets;
 pub mod colors;
 pub mod components;
 pub mod models;
+pub mod quick_actions;
+pub mod scale;
 pub mod screens;
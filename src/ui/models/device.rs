@@ -8,29 +8,76 @@
 //! # Architecture
 //!
 //! - **Blocking static methods** (`*_blocking`) wrap HAL I/O calls for use
-//!   inside background tasks spawned by ViewModels.
+//!   inside background tasks spawned by ViewModels. They run the call on the
+//!   HID worker thread and wait for it.
 //! - **`refresh()`** performs a full polling cycle and emits
 //!   [`DeviceEvent::Updated`].
 //! - **`apply_fresh_state()`** lets ViewModels push post-write HAL results
 //!   back into the repo so subscribers get the event.
+//! - **`arm_replug_check()`** remembers identity values that only take effect
+//!   after a replug; the next refresh after the device comes back compares
+//!   them against what it reads and emits [`DeviceEvent::ReplugVerified`].
+//!   A check still waiting after [`REPLUG_CHECK_TIMEOUT`] is dropped with a
+//!   failed result.
 
+use crate::advisories::{self, Advisory};
+use crate::cues::{self, Cue};
+use crate::events::{self, Event};
+use crate::hal::fido::advertises_vendor_config;
+use crate::hal::fido::constants::VendorConfigCommand;
 use crate::hal::firmwares::AnyFirmware;
 use crate::hal::io;
 use crate::hal::types;
+use crate::inventory::{self, AttestationCertRecord, IdentityDrift, TimelineEvent};
+use crate::issue_report;
+use crate::metrics;
+use crate::registry::{self, Claim};
+use crate::secure_lock;
+use crate::settings;
+use crate::startup;
+use crate::tunables::{self, DeviceOverrides};
 use gpui::*;
-use std::time::Duration;
+use std::collections::{BTreeMap, HashSet};
+use std::time::{Duration, Instant};
 
 /// How often the hot-plug watcher samples device presence. Only a *change*
 /// triggers a refresh, so this is a detection-latency knob, not a poll cost.
 const HOTPLUG_POLL_MS: u64 = 1000;
 
+/// How often the hot-plug watcher looks for boards in BOOTSEL mode while no
+/// key is connected. The boot drive mounts a few seconds after the reboot.
+const BOOTSEL_SCAN_MS: u64 = 3000;
+
+/// How long an armed [`ReplugCheck`] waits for the device to come back.
+const REPLUG_CHECK_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// How often the touch watcher looks at the key's KEEPALIVE status.
+const TOUCH_POLL_MS: u64 = 150;
+
+/// Timeline entries shown for the connected device.
+const RECENT_EVENTS: usize = 6;
+
+pub use crate::hal::bootsel::{BootselBoard, BootselChip, WHITELABEL_BASE_ROW, Whitelabel};
+pub use crate::hal::fido::backup::{BackupProgress, BackupStage};
+pub use crate::hal::fido::bio::BioEnrollSession;
+pub use crate::hal::fido::constants::{BioSampleStatus, Ctap2Error};
+pub use crate::hal::fido::{
+    is_pico_device, parse_credential_id, pin_lockout_from_error, pin_lockout_from_retries,
+};
+pub use crate::hal::fido::{pin, reference};
+pub use crate::hal::firmwares::Compatibility;
+pub use crate::hal::firmwares::fingerprint::{FingerprintVerdict, check_fingerprint};
+pub use crate::hal::io::MaintenanceLatch;
 pub use crate::hal::rescue::constants::{
     LedColor, LedStatus, USB_CAP_FIDO2, USB_CAP_OATH, USB_CAP_OPENPGP, USB_CAP_OTP, USB_CAP_PIV,
     USB_CAP_U2F,
 };
 pub use types::{
-    AppConfigInput, DeviceMethod, FidoDeviceInfo, FirmwareType, FullDeviceStatus, LedStatusConfig,
-    StoredCredential,
+    AppConfigInput, AppletPresence, AssertionSweep, AttestationCertInfo, BioEnrollSample,
+    BioSensorInfo, BioTemplate, CredProtectPolicy, CredentialExportFormat, CredentialsMetadata,
+    DeviceDescriptor, DeviceMethod, FidoDeviceInfo, FirmwareType, FullDeviceStatus, LargeBlobInfo,
+    LedStatusConfig, PinLockout, PinRetries, PinStatus, PingSelfTest, SignInTest, StoredCredential,
+    TestAssertion, TestCredential, TestCredentialRequest, UsbDescriptorInfo,
 };
 
 // ── Events ──────────────────────────────────────────────────────────────────
@@ -39,6 +86,15 @@ pub use types::{
 pub enum DeviceEvent {
     /// Device details were refreshed.
     Updated,
+    /// The device came back after a replug and the armed identity check ran.
+    /// `Ok` carries a success message, `Err` lists the mismatches.
+    ReplugVerified(Result<String, String>),
+    /// A device was connected whose USB identity differs from the one last
+    /// configured in PicoForge (typically reset by a firmware update). The
+    /// drift stays pending until taken with [`DeviceRepo::take_identity_drift`].
+    IdentityDrift,
+    /// The key was factory reset; every credential and the PIN are gone.
+    Reset,
 }
 
 impl EventEmitter<DeviceEvent> for DeviceRepo {}
@@ -51,6 +107,162 @@ pub struct FreshDeviceState {
     pub status: types::FullDeviceStatus,
     pub led_status: Option<types::LedStatusConfig>,
     pub management_apps: Option<types::ManagementAppConfig>,
+    pub fido_info: Option<types::FidoDeviceInfo>,
+    pub usb_descriptors: Option<types::UsbDescriptorInfo>,
+    pub applets: AppletPresence,
+}
+
+/// What a `*_blocking` call returns when it did not run: the key was held
+/// in maintenance or by another process, or the HID worker failed.
+trait HidOutcome {
+    fn not_run(error: crate::error::PFError) -> Self;
+}
+
+impl<T> HidOutcome for Result<T, crate::error::PFError> {
+    fn not_run(error: crate::error::PFError) -> Self {
+        Err(error)
+    }
+}
+
+impl<T> HidOutcome for Result<T, String> {
+    fn not_run(error: crate::error::PFError) -> Self {
+        Err(error.to_string())
+    }
+}
+
+impl<T> HidOutcome for Option<T> {
+    fn not_run(_: crate::error::PFError) -> Self {
+        None
+    }
+}
+
+// ── Post-replug verification ────────────────────────────────────────────────
+
+/// Values written to the device that only become visible after a replug.
+/// `None` fields were not changed and are not checked.
+#[derive(Clone, Debug)]
+pub struct ReplugCheck {
+    /// Serial of the device the values were written to.
+    pub serial: String,
+    pub vid: Option<String>,
+    pub pid: Option<String>,
+    pub product_name: Option<String>,
+}
+
+impl ReplugCheck {
+    /// Compare the expected values against the phy config and the USB
+    /// descriptors the OS reports after the replug.
+    fn verify(
+        &self,
+        status: &types::FullDeviceStatus,
+        usb: Option<&types::UsbDescriptorInfo>,
+    ) -> Result<String, String> {
+        let mut mismatches = Vec::new();
+        let config = &status.config;
+
+        for (label, expected, stored, seen) in [
+            (
+                "VID",
+                &self.vid,
+                &config.vid,
+                usb.map(|u| format!("{:04X}", u.vid)),
+            ),
+            (
+                "PID",
+                &self.pid,
+                &config.pid,
+                usb.map(|u| format!("{:04X}", u.pid)),
+            ),
+            (
+                "Product name",
+                &self.product_name,
+                &config.product_name,
+                usb.and_then(|u| u.product.clone()),
+            ),
+        ] {
+            let Some(expected) = expected else { continue };
+            if !stored.eq_ignore_ascii_case(expected) {
+                mismatches.push(format!(
+                    "{} stored on the device is {}, expected {}",
+                    label, stored, expected
+                ));
+            }
+            if let Some(seen) = seen
+                && !seen.eq_ignore_ascii_case(expected)
+            {
+                mismatches.push(format!(
+                    "{} reported to the OS is {}, expected {}",
+                    label, seen, expected
+                ));
+            }
+        }
+
+        if mismatches.is_empty() {
+            Ok("Device reconnected and the new USB identity is active.".into())
+        } else {
+            Err(format!(
+                "Device reconnected but the configuration does not match: {}.",
+                mismatches.join("; ")
+            ))
+        }
+    }
+}
+
+// ── Capabilities ────────────────────────────────────────────────────────────
+
+/// What the connected device offers. Drives which screens the sidebar shows.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DeviceCapabilities {
+    /// A device answered the last poll.
+    pub connected: bool,
+    /// The key accepts pico-fido/RS-Key vendor commands (configuration,
+    /// secure boot/lock, backup, LED). `false` for generic FIDO keys.
+    pub vendor_management: bool,
+    /// GetInfo advertises `credMgmt` (or the 2.1-pre `credentialMgmtPreview`).
+    pub credential_management: bool,
+    /// The OpenPGP applet answered SELECT.
+    pub openpgp: bool,
+    /// The OATH applet answered SELECT.
+    pub oath: bool,
+    /// GetInfo advertises `bioEnroll` (or the 2.1-pre `userVerificationMgmtPreview`).
+    pub bio_enrollment: bool,
+    /// GetInfo lists the `hmac-secret` extension and a PIN is set.
+    pub hmac_secret: bool,
+    /// Hardware settings a configuration write can change.
+    pub vendor_config: VendorConfigSupport,
+}
+
+/// Hardware settings whose vendor config command the firmware advertises in
+/// GetInfo (`vendorPrototypeConfigCommands`). The Configuration screen
+/// disables the controls of the others, which a write would refuse. All
+/// `true` unless the key is pico-fido over FIDO with a list in GetInfo.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct VendorConfigSupport {
+    pub vid_pid: bool,
+    pub led_gpio: bool,
+    pub led_brightness: bool,
+    /// LED dimmable, LED steady and power cycle on reset.
+    pub physical_options: bool,
+}
+
+// ── Device phase ────────────────────────────────────────────────────────────
+
+/// Where the device reader stands, as far as a screen that needs a key is
+/// concerned. Screens render their content only in [`DevicePhase::Ready`]
+/// and a placeholder from [`crate::ui::components::view_state`] otherwise.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DevicePhase {
+    /// No reading has finished yet, or one is running with no key shown.
+    Loading,
+    /// The last poll found no key.
+    Absent,
+    /// A key is attached but reading it failed; carries the error.
+    Failed(String),
+    /// Another PicoForge process or a maintenance flow holds the key;
+    /// carries who or what.
+    Paused(String),
+    /// A key was read.
+    Ready,
 }
 
 // ── DeviceRepo ──────────────────────────────────────────────────────────────
@@ -60,11 +272,167 @@ pub struct DeviceRepo {
     pub fido_info: Option<types::FidoDeviceInfo>,
     pub led_status: Option<types::LedStatusConfig>,
     pub management_apps: Option<types::ManagementAppConfig>,
+    /// USB descriptors as the OS sees them (refreshed with every poll).
+    pub usb_descriptors: Option<types::UsbDescriptorInfo>,
+    /// Optional applets that responded during the last poll.
+    pub applets: types::AppletPresence,
+    /// Boards in BOOTSEL mode, rescanned by the hot-plug watcher while no
+    /// key is connected.
+    pub bootsel_boards: Vec<BootselBoard>,
+    /// FIDO interfaces attached at the last poll; with more than one the user
+    /// picks a key in the sidebar ([`Self::select_device`]) or by touch
+    /// ([`Self::select_device_by_touch_blocking`]).
+    pub fido_devices: Vec<DeviceDescriptor>,
+    /// Path of the interface operations go to.
+    pub active_device_path: Option<String>,
     pub error: Option<String>,
+    /// The last poll failed on a key that is attached (`error` is not just
+    /// "No device found").
+    read_failed: bool,
     pub loading: bool,
     pub device_changed: bool,
+    /// Identity check armed after a write that needs a replug.
+    replug_check: Option<ReplugCheck>,
+    /// Set once the device was seen absent while `replug_check` is armed.
+    replug_seen_absent: bool,
+    /// When the armed `replug_check` gives up.
+    replug_deadline: Option<Instant>,
     /// Handle to the hot-plug watcher task; dropped (cancelled) with the repo.
     hotplug_watch: Option<Task<()>>,
+    /// Handle to the touch watcher task; dropped (cancelled) with the repo.
+    touch_watch: Option<Task<()>>,
+    /// The key answered a pending request with KEEPALIVE `UPNEEDED`: it is
+    /// waiting for a touch.
+    pub touch_needed: bool,
+    /// Refresh waiting on the HID worker thread.
+    refresh_task: Option<Task<()>>,
+    /// Serials already checked against the inventory this session, so an
+    /// identity drift is only raised once per device.
+    drift_checked: HashSet<String>,
+    /// Drift found for the connected device that no screen has taken yet, so
+    /// it is still offered when Configuration is first opened later.
+    pending_drift: Option<IdentityDrift>,
+    /// Credentials read on the Passkeys screen for the current device, kept so
+    /// other screens can tell which passkeys a change affects.
+    pub credentials: Option<Vec<StoredCredential>>,
+    /// Another PicoForge process asked for the device (see
+    /// [`crate::registry`]). Polling, refreshes and device operations pause
+    /// until it is done.
+    pub yielded_to: Option<Claim>,
+    /// A flow in this process holds the key (see [`Self::begin_maintenance`]).
+    /// Polling and refreshes pause until it lets go.
+    pub maintenance: Option<String>,
+    /// Device nicknames from the inventory, by serial.
+    nicknames: BTreeMap<String, String>,
+    /// Recent timeline of the connected device from the inventory, newest
+    /// first, as `(UNIX seconds, description)`.
+    pub timeline: Vec<(u64, String)>,
+    /// Warning when the connected key's recent transport error rate is high
+    /// (see [`inventory::elevated_error_hint`]).
+    pub health_hint: Option<String>,
+    /// Published security advisories that apply to the connected firmware
+    /// (see [`crate::advisories`]); empty unless the user opted in.
+    pub advisories: Vec<Advisory>,
+    /// When the connected firmware was last matched against the advisories.
+    advisories_checked: Option<Instant>,
+    advisory_task: Option<Task<()>>,
+    /// Started in safe mode and not connected yet: nothing reads or watches
+    /// the key until the first refresh the user asks for.
+    pub safe_mode: bool,
+}
+
+/// Everything a refresh reads from the hardware, gathered on the HID worker
+/// thread and applied to the repo on the GPUI thread.
+struct DeviceReading {
+    fido_devices: Vec<DeviceDescriptor>,
+    active_device_path: Option<String>,
+    /// The connected key, or why none could be read.
+    result: Result<ConnectedReading, crate::error::PFError>,
+}
+
+struct ConnectedReading {
+    status: FullDeviceStatus,
+    fido_info: Result<types::FidoDeviceInfo, String>,
+    usb_descriptors: Option<types::UsbDescriptorInfo>,
+    applets: types::AppletPresence,
+    led_status: Option<types::LedStatusConfig>,
+    management_apps: Option<types::ManagementAppConfig>,
+}
+
+impl DeviceReading {
+    /// Read the device, first making the interface at `select` the active
+    /// one if given. Blocking.
+    fn read(select: Option<String>) -> Self {
+        // A key unplugged in the meantime just leaves the previous choice.
+        if let Some(path) = select
+            && let Err(e) = io::select_fido_device(&path)
+        {
+            log::warn!("Could not select {}: {}", path, e);
+        }
+        let fido_devices = io::list_fido_devices();
+        let active_device_path = io::active_fido_device();
+
+        let mut details = io::read_device_details();
+        // Overrides are keyed by serial, so they are only known once the key
+        // has answered. When they change, read again so the status already
+        // reflects them (transport order, skipped vendor reads).
+        match &details {
+            Ok(status) => {
+                let overrides = inventory::load()
+                    .device_overrides
+                    .remove(&status.info.serial);
+                if tunables::set_device_overrides(overrides) {
+                    log::info!(
+                        "Connection overrides changed for {}; reading it again",
+                        status.info.serial
+                    );
+                    details = io::read_device_details();
+                }
+            }
+            Err(crate::error::PFError::NoDevice) => {
+                tunables::set_device_overrides(None);
+            }
+            Err(_) => {}
+        }
+
+        let result = match details {
+            Ok(status) => {
+                let (led_status, management_apps) =
+                    if status.firmware_type == types::FirmwareType::RSKey {
+                        (
+                            io::read_led_config(status.method.clone()).ok(),
+                            io::read_management_config(status.method.clone()).ok(),
+                        )
+                    } else {
+                        (None, None)
+                    };
+                Ok(ConnectedReading {
+                    fido_info: io::get_fido_info(),
+                    usb_descriptors: io::read_usb_descriptors(),
+                    applets: io::probe_applets(),
+                    led_status,
+                    management_apps,
+                    status,
+                })
+            }
+            Err(e) => Err(e),
+        };
+
+        Self {
+            fido_devices,
+            active_device_path,
+            result,
+        }
+    }
+
+    /// A reading that never ran because the worker thread failed.
+    fn failed(error: crate::error::PFError) -> Self {
+        Self {
+            fido_devices: Vec::new(),
+            active_device_path: None,
+            result: Err(error),
+        }
+    }
 }
 
 impl DeviceRepo {
@@ -75,14 +443,65 @@ impl DeviceRepo {
             fido_info: None,
             led_status: None,
             management_apps: None,
+            usb_descriptors: None,
+            applets: types::AppletPresence::default(),
+            bootsel_boards: Vec::new(),
+            fido_devices: Vec::new(),
+            active_device_path: None,
             error: None,
+            read_failed: false,
             loading: false,
             device_changed: false,
+            replug_check: None,
+            replug_seen_absent: false,
+            replug_deadline: None,
             hotplug_watch: None,
+            touch_watch: None,
+            touch_needed: false,
+            refresh_task: None,
+            drift_checked: HashSet::new(),
+            pending_drift: None,
+            credentials: None,
+            yielded_to: None,
+            maintenance: None,
+            nicknames: inventory::load().nicknames,
+            timeline: Vec::new(),
+            health_hint: None,
+            advisories: Vec::new(),
+            advisories_checked: None,
+            advisory_task: None,
+            safe_mode: startup::safe_mode(),
         }
     }
 
     // ── HAL static methods (blocking — call from background executor) ──────
+    //
+    // Each one runs on the HID worker thread and waits for it, so operations
+    // from every screen reach the key one at a time, in the order they were
+    // started. While a flow holds the maintenance latch, or another process
+    // holds the key (see `yielded_to`), they fail at once instead of queueing;
+    // the maintenance flow (reset, restore) passes its latch and talks to the
+    // key directly, as do the presence probes.
+
+    /// Run a device operation on the HID worker thread and wait for it.
+    /// Refused while a flow holds the key in maintenance or another process
+    /// holds it.
+    fn on_hid<R: HidOutcome + Send + 'static>(job: impl FnOnce() -> R + Send + 'static) -> R {
+        if let Some(owner) = registry::yielded_to() {
+            return R::not_run(crate::error::PFError::Device(format!(
+                "The key is in use by {} (pid {}). Try again once it has finished.",
+                owner.role.describe(),
+                owner.pid
+            )));
+        }
+        if let Some(reason) = Self::maintenance_reason() {
+            return R::not_run(crate::error::PFError::Device(format!(
+                "The key is busy: {}. Try again once it has finished.",
+                reason
+            )));
+        }
+        io::on_hid_thread(job).wait().unwrap_or_else(R::not_run)
+    }
 
     pub fn firmware_supports_legacy_fido_config(
         fw_type: &types::FirmwareType,
@@ -92,28 +511,39 @@ impl DeviceRepo {
     }
 
     pub fn read_device_state_blocking() -> Result<FreshDeviceState, crate::error::PFError> {
-        let status = io::read_device_details()?;
-        let (led_status, management_apps) = if status.firmware_type == types::FirmwareType::RSKey {
-            (
-                io::read_led_config(status.method.clone()).ok(),
-                io::read_management_config(status.method.clone()).ok(),
-            )
-        } else {
-            (None, None)
-        };
-        Ok(FreshDeviceState {
-            status,
-            led_status,
-            management_apps,
+        Self::on_hid(|| {
+            let status = io::read_device_details()?;
+            let (led_status, management_apps) =
+                if status.firmware_type == types::FirmwareType::RSKey {
+                    (
+                        io::read_led_config(status.method.clone()).ok(),
+                        io::read_management_config(status.method.clone()).ok(),
+                    )
+                } else {
+                    (None, None)
+                };
+            Ok(FreshDeviceState {
+                status,
+                led_status,
+                management_apps,
+                fido_info: io::get_fido_info().ok(),
+                usb_descriptors: io::read_usb_descriptors(),
+                applets: io::probe_applets(),
+            })
         })
     }
 
+    /// Whether a configuration write over `method` needs the FIDO PIN.
+    pub fn config_needs_pin(method: &types::DeviceMethod) -> bool {
+        io::backend_capabilities(method.clone()).config_needs_pin
+    }
+
     pub fn write_config_blocking(
         config: types::AppConfigInput,
         method: types::DeviceMethod,
         pin: Option<String>,
-    ) -> Result<String, crate::error::PFError> {
-        io::write_config(config, method, pin)
+    ) -> Result<types::ConfigApplyReport, crate::error::PFError> {
+        Self::on_hid(move || io::write_config(config, method, pin))
     }
 
     pub fn write_led_config_blocking(
@@ -121,7 +551,7 @@ impl DeviceRepo {
         config: LedStatusConfig,
         pin: Option<String>,
     ) -> Result<String, crate::error::PFError> {
-        io::write_led_config(method, config, pin)
+        Self::on_hid(move || io::write_led_config(method, config, pin))
     }
 
     pub fn write_management_config_blocking(
@@ -129,56 +559,317 @@ impl DeviceRepo {
         enabled_mask: u16,
         pin: Option<String>,
     ) -> Result<String, crate::error::PFError> {
-        io::write_management_config(method, enabled_mask, pin)
+        Self::on_hid(move || io::write_management_config(method, enabled_mask, pin))
+    }
+
+    pub fn read_usb_descriptors_blocking() -> Option<types::UsbDescriptorInfo> {
+        Self::on_hid(io::read_usb_descriptors)
+    }
+
+    pub fn read_flash_usage_blocking(method: DeviceMethod) -> Result<Option<(u32, u32)>, String> {
+        Self::on_hid(move || io::read_flash_usage(method).map_err(|e| e.to_string()))
     }
 
     pub fn get_fido_info_blocking() -> Result<types::FidoDeviceInfo, String> {
-        io::get_fido_info()
+        Self::on_hid(io::get_fido_info)
     }
 
     pub fn get_credentials_blocking(pin: String) -> Result<Vec<types::StoredCredential>, String> {
-        io::get_credentials(pin)
+        Self::on_hid(move || io::get_credentials(pin))
     }
 
-    pub fn delete_credential_blocking(
+    pub fn export_credentials_blocking(
         pin: String,
+        format: types::CredentialExportFormat,
+    ) -> Result<String, String> {
+        Self::on_hid(move || io::export_credentials(pin, format))
+    }
+
+    pub fn get_credentials_metadata_blocking(
+        pin: String,
+    ) -> Result<types::CredentialsMetadata, String> {
+        Self::on_hid(move || io::get_credentials_metadata(pin))
+    }
+
+    pub fn create_test_credential_blocking(
+        request: types::TestCredentialRequest,
+        pin: Option<String>,
+    ) -> Result<types::TestCredential, String> {
+        Self::on_hid(move || io::create_test_credential(request, pin))
+    }
+
+    pub fn get_test_assertion_blocking(
+        rp_id: String,
         credential_id: String,
+        pin: Option<String>,
+    ) -> Result<types::TestAssertion, String> {
+        Self::on_hid(move || io::get_test_assertion(rp_id, credential_id, pin))
+    }
+
+    pub fn test_sign_in_blocking(
+        rp_id: String,
+        credential_id: String,
+        public_key: Vec<u8>,
+        pin: Option<String>,
+    ) -> Result<types::SignInTest, String> {
+        Self::on_hid(move || io::test_sign_in(rp_id, credential_id, public_key, pin))
+    }
+
+    pub fn sweep_test_assertions_blocking(
+        rp_id: String,
+        pin: Option<String>,
+    ) -> Result<types::AssertionSweep, String> {
+        Self::on_hid(move || io::sweep_test_assertions(rp_id, pin))
+    }
+
+    pub fn read_large_blob_array_blocking() -> Result<types::LargeBlobInfo, String> {
+        Self::on_hid(io::read_large_blob_array)
+    }
+
+    pub fn read_large_blob_blocking(
+        rp_id: String,
+        credential_id: String,
+        pin: Option<String>,
+    ) -> Result<types::LargeBlobInfo, String> {
+        Self::on_hid(move || io::read_large_blob(rp_id, credential_id, pin))
+    }
+
+    pub fn write_large_blob_blocking(
+        rp_id: String,
+        credential_id: String,
+        data: Vec<u8>,
+        pin: String,
+    ) -> Result<types::LargeBlobInfo, String> {
+        Self::on_hid(move || io::write_large_blob(rp_id, credential_id, data, pin))
+    }
+
+    pub fn create_hmac_secret_credential_blocking(
+        rp_id: String,
+        user_name: String,
+        pin: String,
+    ) -> Result<String, String> {
+        Self::on_hid(move || io::create_hmac_secret_credential(rp_id, user_name, pin))
+    }
+
+    pub fn derive_hmac_secret_blocking(
+        rp_id: String,
+        credential_id: String,
+        salt: [u8; 32],
+        pin: String,
+    ) -> Result<[u8; 32], String> {
+        Self::on_hid(move || io::derive_hmac_secret(rp_id, credential_id, salt, pin))
+    }
+
+    pub fn read_bio_sensor_info_blocking() -> Result<types::BioSensorInfo, String> {
+        Self::on_hid(io::read_bio_sensor_info)
+    }
+
+    pub fn list_fingerprints_blocking(pin: String) -> Result<Vec<types::BioTemplate>, String> {
+        Self::on_hid(move || io::list_fingerprints(pin))
+    }
+
+    pub fn remove_fingerprint_blocking(pin: String, template_id: String) -> Result<(), String> {
+        Self::on_hid(move || io::remove_fingerprint(pin, template_id))
+    }
+
+    pub fn begin_fingerprint_enrollment_blocking(
+        pin: String,
+    ) -> Result<(BioEnrollSession, types::BioEnrollSample), String> {
+        Self::on_hid(move || io::begin_fingerprint_enrollment(pin))
+    }
+
+    /// Capture the next enrollment sample; waits for a finger. A failed
+    /// capture cancels the enrollment on the key.
+    pub fn capture_fingerprint_sample_blocking(
+        mut session: BioEnrollSession,
+    ) -> Result<(BioEnrollSession, types::BioEnrollSample), String> {
+        Self::on_hid(move || match session.capture_next() {
+            Ok(sample) => Ok((session, sample)),
+            Err(e) => {
+                let _ = session.cancel();
+                Err(e)
+            }
+        })
+    }
+
+    pub fn cancel_fingerprint_enrollment_blocking(session: BioEnrollSession) -> Result<(), String> {
+        Self::on_hid(move || session.cancel())
+    }
+
+    pub fn finish_fingerprint_enrollment_blocking(
+        session: BioEnrollSession,
+        name: String,
+    ) -> Result<types::BioTemplate, String> {
+        Self::on_hid(move || session.finish(name))
+    }
+
+    pub fn delete_credentials_bulk_blocking(
+        pin: String,
+        credential_ids: Vec<String>,
+    ) -> Result<Vec<(String, String)>, String> {
+        Self::on_hid(move || io::delete_credentials_bulk(pin, credential_ids))
+    }
+
+    pub fn update_credential_user_blocking(
+        pin: String,
+        credential_id: String,
+        user_id: String,
+        name: String,
+        display_name: String,
     ) -> Result<String, String> {
-        io::delete_credential(pin, credential_id)
+        Self::on_hid(move || {
+            io::update_credential_user(pin, credential_id, user_id, name, display_name)
+        })
     }
 
     pub fn change_fido_pin_blocking(
         current: Option<String>,
         new: String,
     ) -> Result<String, String> {
-        io::change_fido_pin(current, new)
+        Self::on_hid(move || io::change_fido_pin(current, new))
     }
 
     pub fn set_min_pin_length_blocking(pin: String, min_len: u8) -> Result<String, String> {
-        io::set_min_pin_length(pin, min_len)
+        Self::on_hid(move || io::set_min_pin_length(pin, min_len, Vec::new(), false))
+    }
+
+    pub fn set_min_pin_length_rp_ids_blocking(
+        pin: String,
+        min_len: u8,
+        rp_ids: Vec<String>,
+        force_change_pin: bool,
+    ) -> Result<String, String> {
+        Self::on_hid(move || io::set_min_pin_length(pin, min_len, rp_ids, force_change_pin))
+    }
+
+    pub fn toggle_always_uv_blocking(pin: String) -> Result<bool, String> {
+        Self::on_hid(move || io::toggle_always_uv(pin))
     }
 
     pub fn get_enterprise_attestation_csr_blocking() -> Result<String, String> {
-        io::get_enterprise_attestation_csr()
+        Self::on_hid(io::get_enterprise_attestation_csr)
+    }
+
+    pub fn backup_device_blocking(
+        path: std::path::PathBuf,
+        mut progress: impl FnMut(BackupProgress) + Send + 'static,
+    ) -> Result<usize, String> {
+        Self::on_hid(move || {
+            io::backup_device(path, |p| {
+                emit_backup_progress("backup", p);
+                progress(p)
+            })
+        })
+    }
+
+    /// Restore a backup onto the key. Only the flow holding `latch` may, and
+    /// it talks to the key directly rather than through the worker.
+    pub fn restore_device_blocking(
+        _latch: &MaintenanceLatch,
+        path: std::path::PathBuf,
+        mut progress: impl FnMut(BackupProgress),
+    ) -> Result<usize, String> {
+        io::restore_device(path, |p| {
+            emit_backup_progress("restore", p);
+            progress(p)
+        })
+    }
+
+    /// Enable secure boot through the Rescue applet, locking it when `lock`.
+    pub fn enable_secure_boot_blocking(lock: bool) -> Result<String, String> {
+        Self::on_hid(move || io::enable_secure_boot(lock).map_err(|e| e.to_string()))
+    }
+
+    /// Burn `label` into the OTP of the RP2350 in BOOTSEL mode. Not a HID
+    /// job: the board has no FIDO interface while in the boot loader.
+    pub fn write_whitelabel_blocking(label: Whitelabel) -> Result<String, String> {
+        io::write_whitelabel(&label).map_err(|e| e.to_string())
+    }
+
+    /// Turn secure lock on for the key with `serial`, creating its host key
+    /// on first use.
+    pub fn enable_secure_lock_blocking(pin: String, serial: String) -> Result<String, String> {
+        let host_key = secure_lock::load_or_create_key(&serial)?;
+        Self::on_hid(move || io::enable_secure_lock(pin, host_key).map_err(|e| e.to_string()))
+    }
+
+    pub fn disable_secure_lock_blocking(pin: String) -> Result<String, String> {
+        Self::on_hid(move || io::disable_secure_lock(pin).map_err(|e| e.to_string()))
+    }
+
+    /// Unlock the secure-locked key with `serial` with its stored host key.
+    pub fn unlock_device_blocking(serial: String) -> Result<String, String> {
+        let host_key = secure_lock::load_key(&serial)?
+            .ok_or("No host key for this key is stored on this computer")?;
+        Self::on_hid(move || io::unlock_device(host_key).map_err(|e| e.to_string()))
+    }
+
+    pub fn read_enterprise_attestation_cert_blocking(
+        pin: Option<String>,
+    ) -> Result<Option<AttestationCertInfo>, String> {
+        Self::on_hid(move || io::read_enterprise_attestation_cert(pin))
     }
 
     pub fn upload_enterprise_attestation_cert_blocking(
         pin: String,
         cert_path: String,
     ) -> Result<String, String> {
-        io::upload_enterprise_attestation_cert(pin, cert_path)
+        Self::on_hid(move || io::upload_enterprise_attestation_cert(pin, cert_path))
     }
 
     pub fn enable_enterprise_attestation_blocking(pin: String) -> Result<String, String> {
-        io::enable_enterprise_attestation(pin)
+        Self::on_hid(move || io::enable_enterprise_attestation(pin))
+    }
+
+    pub fn confirm_identity_change_blocking(pin: String) -> Result<String, String> {
+        Self::on_hid(move || io::confirm_identity_change(pin))
     }
 
-    pub fn reset_device_blocking() -> Result<String, String> {
+    pub fn get_pin_retries_blocking() -> Result<PinRetries, String> {
+        Self::on_hid(io::get_pin_retries)
+    }
+
+    pub fn get_pin_status_blocking() -> Result<PinStatus, String> {
+        Self::on_hid(io::get_pin_status)
+    }
+
+    /// Factory-reset the key. Only the flow holding `latch` may, and it talks
+    /// to the key directly rather than through the worker.
+    pub fn reset_device_blocking(_latch: &MaintenanceLatch) -> Result<String, String> {
         io::reset_device()
     }
 
     pub fn read_device_serial_blocking() -> Option<String> {
-        io::read_device_details().ok().map(|s| s.info.serial)
+        Self::on_hid(|| io::read_device_details().ok().map(|s| s.info.serial))
+    }
+
+    /// Wait for a touch on one of the attached keys and make it the active
+    /// device. Blocks until a key is touched or the user-presence timeout.
+    pub fn select_device_by_touch_blocking() -> Result<DeviceDescriptor, String> {
+        Self::on_hid(io::select_device_by_touch)
+    }
+
+    /// Pause background device traffic (refreshes, hot-plug re-reads, queued
+    /// HID jobs) and refuse other `*_blocking` operations while a sensitive
+    /// flow talks to the key, for at most `limit`. Traffic resumes when the
+    /// returned latch is dropped.
+    pub fn begin_maintenance(reason: &str, limit: Duration) -> MaintenanceLatch {
+        io::begin_maintenance(reason, limit)
+    }
+
+    /// What the key is held for, if a flow holds it.
+    pub fn maintenance_reason() -> Option<String> {
+        io::maintenance_reason()
+    }
+
+    /// Blink the managed key's LED (CTAPHID_WINK).
+    pub fn wink_blocking() -> Result<(), String> {
+        Self::on_hid(io::wink)
+    }
+
+    /// Measure the HID link to the managed key with CTAPHID_PING.
+    pub fn ping_self_test_blocking() -> Result<PingSelfTest, String> {
+        Self::on_hid(io::ping_self_test)
     }
 
     pub fn check_hid_available_blocking() -> bool {
@@ -206,21 +897,92 @@ impl DeviceRepo {
         self.status = Some(state.status);
         self.led_status = state.led_status;
         self.management_apps = state.management_apps;
-        self.fido_info = Self::get_fido_info_blocking().ok();
+        self.fido_info = state.fido_info;
+        self.usb_descriptors = state.usb_descriptors;
+        self.applets = state.applets;
         cx.emit(DeviceEvent::Updated);
         cx.notify();
     }
 
-    /// Re-read FIDO info from the device and emit [`DeviceEvent::Updated`].
+    /// Arm a [`ReplugCheck`]. The hot-plug watcher notices the device leaving
+    /// and returning; the refresh that reads it back runs the comparison and
+    /// emits [`DeviceEvent::ReplugVerified`]. Re-arming replaces any pending check.
+    pub fn arm_replug_check(&mut self, check: ReplugCheck) {
+        log::info!("Armed post-replug verification: {:?}", check);
+        self.replug_check = Some(check);
+        self.replug_seen_absent = false;
+        self.replug_deadline = Some(Instant::now() + REPLUG_CHECK_TIMEOUT);
+    }
+
+    /// Re-read FIDO info on the HID worker thread and emit
+    /// [`DeviceEvent::Updated`] once it is in.
     /// ViewModels should call this instead of manually setting `repo.fido_info`.
     pub fn update_fido_info(&mut self, cx: &mut Context<Self>) {
-        self.fido_info = Self::get_fido_info_blocking().ok();
+        let weak = cx.entity().downgrade();
+        cx.spawn(async move |_, cx| {
+            let fido_info = io::on_hid_thread(io::get_fido_info)
+                .await
+                .ok()
+                .and_then(Result::ok);
+            let _ = weak.update(cx, |repo, cx| {
+                repo.fido_info = fido_info;
+                cx.emit(DeviceEvent::Updated);
+                cx.notify();
+            });
+        })
+        .detach();
+    }
+
+    // ── Partial refresh ────────────────────────────────────────────────────
+    //
+    // Setters for one slice of the state, read back by a screen on its own
+    // (see the Home cards). Each emits [`DeviceEvent::Updated`].
+
+    /// Store FIDO info read in the background.
+    pub fn set_fido_info(&mut self, info: types::FidoDeviceInfo, cx: &mut Context<Self>) {
+        self.fido_info = Some(info);
+        cx.emit(DeviceEvent::Updated);
+        cx.notify();
+    }
+
+    /// Store USB descriptors read in the background.
+    pub fn set_usb_descriptors(&mut self, usb: types::UsbDescriptorInfo, cx: &mut Context<Self>) {
+        self.usb_descriptors = Some(usb);
+        cx.emit(DeviceEvent::Updated);
+        cx.notify();
+    }
+
+    /// Store flash usage (`(used, total)` KiB) read in the background.
+    pub fn set_flash_usage(&mut self, usage: Option<(u32, u32)>, cx: &mut Context<Self>) {
+        let Some(status) = self.status.as_mut() else {
+            return;
+        };
+        status.info.flash_used = usage.map(|(used, _)| used);
+        status.info.flash_total = usage.map(|(_, total)| total);
+        cx.emit(DeviceEvent::Updated);
+        cx.notify();
+    }
+
+    /// Re-read the connected device's timeline from the inventory.
+    pub fn refresh_timeline(&mut self, cx: &mut Context<Self>) {
+        self.reload_timeline();
         cx.emit(DeviceEvent::Updated);
         cx.notify();
     }
 
     // ── Polling cycle ──────────────────────────────────────────────────────
 
+    /// Read the key and start watching for changes, unless the app started
+    /// in safe mode; then nothing happens until the user refreshes.
+    pub fn start(&mut self, cx: &mut Context<Self>) {
+        if self.safe_mode {
+            return;
+        }
+        self.refresh(cx);
+        self.start_hotplug_watch(cx);
+        self.start_touch_watch(cx);
+    }
+
     /// Start the hot-plug watcher: a background timer that samples the device
     /// fingerprint and, whenever it changes (plug / unplug / swap), triggers a
     /// [`refresh`](Self::refresh) so every screen reflects the current key with
@@ -238,18 +1000,47 @@ impl DeviceRepo {
                 .background_executor()
                 .spawn(async { Self::device_fingerprint_blocking() })
                 .await;
+            let mut last_bootsel_scan: Option<Instant> = None;
             loop {
                 cx.background_executor()
                     .timer(Duration::from_millis(HOTPLUG_POLL_MS))
                     .await;
-                let current = cx
+                let (current, yielded, maintenance) = cx
                     .background_executor()
-                    .spawn(async { Self::device_fingerprint_blocking() })
+                    .spawn(async {
+                        let current = Self::device_fingerprint_blocking();
+                        let yielded = registry::gui_tick(current.as_deref());
+                        (current, yielded, Self::maintenance_reason())
+                    })
                     .await;
-                if current == last {
+                let paused = yielded.is_some() || maintenance.is_some();
+                let Ok(resumed) = weak.update(cx, |repo, cx| {
+                    repo.expire_replug_check(cx);
+                    let handed_back = repo.set_yielded(yielded, cx);
+                    repo.set_maintenance(maintenance, cx) || handed_back
+                }) else {
+                    break;
+                };
+                // Drive probing and PICOBOOT reads block, so they stay off the
+                // GPUI thread and out of the HID worker's queue.
+                let scan_bootsel = !paused
+                    && last_bootsel_scan
+                        .is_none_or(|t| t.elapsed() >= Duration::from_millis(BOOTSEL_SCAN_MS))
+                    && weak
+                        .update(cx, |repo, _| repo.status.is_none() && !repo.loading)
+                        .unwrap_or(false);
+                if scan_bootsel {
+                    last_bootsel_scan = Some(Instant::now());
+                    let boards = cx
+                        .background_executor()
+                        .spawn(async { io::scan_bootsel() })
+                        .await;
+                    let _ = weak.update(cx, |repo, cx| repo.set_bootsel_boards(boards, cx));
+                }
+                if paused || (current == last && !resumed) {
                     continue;
                 }
-                // Re-read on the main thread. Skip while a refresh/write is in
+                // Start a re-read. Skip while a refresh/write is in
                 // flight and retry next tick (don't commit `last`, or we'd drop
                 // the change). Break when the repo — and thus the app — is gone.
                 let refreshed = weak.update(cx, |repo, cx| {
@@ -269,25 +1060,152 @@ impl DeviceRepo {
         }));
     }
 
+    /// Store the boards a BOOTSEL scan found, unless a key connected meanwhile.
+    fn set_bootsel_boards(&mut self, boards: Vec<BootselBoard>, cx: &mut Context<Self>) {
+        if self.status.is_some() || self.bootsel_boards == boards {
+            return;
+        }
+        self.bootsel_boards = boards;
+        cx.emit(DeviceEvent::Updated);
+        cx.notify();
+    }
+
+    /// Start the touch watcher: follows the KEEPALIVE status of whatever
+    /// request is pending on the key and sets [`touch_needed`](Self::touch_needed)
+    /// while it waits for a touch, so a "Touch your key" prompt can show
+    /// instead of the app looking frozen. Idempotent.
+    pub fn start_touch_watch(&mut self, cx: &mut Context<Self>) {
+        if self.touch_watch.is_some() {
+            return;
+        }
+        let weak = cx.entity().downgrade();
+        self.touch_watch = Some(cx.spawn(async move |_, cx| {
+            loop {
+                cx.background_executor()
+                    .timer(Duration::from_millis(TOUCH_POLL_MS))
+                    .await;
+                let needed = io::keepalive_status() == Some(types::KeepaliveStatus::UpNeeded);
+                let updated = weak.update(cx, |repo, cx| {
+                    if repo.touch_needed != needed {
+                        repo.touch_needed = needed;
+                        if needed {
+                            cues::play(Cue::TouchNeeded);
+                        }
+                        cx.emit(DeviceEvent::Updated);
+                        cx.notify();
+                    }
+                });
+                if updated.is_err() {
+                    break;
+                }
+            }
+        }));
+    }
+
+    /// Record whether another process owns the device. Returns `true` when
+    /// it just handed the device back, so the caller re-reads it.
+    fn set_yielded(&mut self, yielded: Option<Claim>, cx: &mut Context<Self>) -> bool {
+        let was_yielded = self.yielded_to.is_some();
+        match (&yielded, was_yielded) {
+            (Some(owner), false) => log::info!(
+                "Pausing device access: {} (pid {}) is using the key",
+                owner.role.describe(),
+                owner.pid
+            ),
+            (None, true) => log::info!("Resuming device access"),
+            _ => {}
+        }
+        let changed = self.yielded_to.as_ref().map(|c| c.pid) != yielded.as_ref().map(|c| c.pid);
+        self.yielded_to = yielded;
+        if changed {
+            cx.emit(DeviceEvent::Updated);
+            cx.notify();
+        }
+        was_yielded && self.yielded_to.is_none()
+    }
+
+    /// Record whether a flow in this process holds the key. Returns `true`
+    /// when it just let go, so the caller re-reads the device.
+    fn set_maintenance(&mut self, maintenance: Option<String>, cx: &mut Context<Self>) -> bool {
+        if self.maintenance == maintenance {
+            return false;
+        }
+        let ended = maintenance.is_none();
+        self.maintenance = maintenance;
+        cx.emit(DeviceEvent::Updated);
+        cx.notify();
+        ended
+    }
+
+    /// Manage the FIDO interface at `path` from now on and read it.
+    /// Does nothing while another process or a maintenance flow owns the
+    /// device.
+    pub fn select_device(&mut self, path: &str, cx: &mut Context<Self>) {
+        self.load(Some(path.to_string()), cx);
+    }
+
     /// Initiate a device-details refresh (async, emits [`DeviceEvent::Updated`] on completion).
+    /// The key is read on the HID worker thread. Does nothing while another
+    /// process or a maintenance flow owns the device.
     pub fn refresh(&mut self, cx: &mut Context<Self>) {
-        if self.loading {
+        self.load(None, cx);
+    }
+
+    /// Read the device on the HID worker thread, first making the interface
+    /// at `select` the active one if given, then apply what was read.
+    fn load(&mut self, select: Option<String>, cx: &mut Context<Self>) {
+        if self.loading || self.yielded_to.is_some() || Self::maintenance_reason().is_some() {
             return;
         }
+        if std::mem::take(&mut self.safe_mode) {
+            log::info!("Leaving safe mode");
+            self.start_hotplug_watch(cx);
+            self.start_touch_watch(cx);
+        }
 
         self.begin_load();
+        cx.notify();
 
         let old_serial = self.status.as_ref().map(|s| s.info.serial.clone());
+        let health_changed = Self::record_transport_health(old_serial.as_deref());
 
-        match io::read_device_details() {
-            Ok(status) => {
+        let weak = cx.entity().downgrade();
+        self.refresh_task = Some(cx.spawn(async move |_, cx| {
+            let reading = io::on_hid_thread(move || DeviceReading::read(select))
+                .await
+                .unwrap_or_else(DeviceReading::failed);
+            let _ = weak.update(cx, |repo, cx| {
+                repo.apply_reading(reading, old_serial, health_changed, cx);
+            });
+        }));
+    }
+
+    fn apply_reading(
+        &mut self,
+        reading: DeviceReading,
+        old_serial: Option<String>,
+        health_changed: bool,
+        cx: &mut Context<Self>,
+    ) {
+        self.fido_devices = reading.fido_devices;
+        self.active_device_path = reading.active_device_path;
+
+        match reading.result {
+            Ok(connected) => {
+                let status = connected.status;
                 self.device_changed = old_serial
                     .as_ref()
                     .map(|s| *s != status.info.serial)
                     .unwrap_or(true);
+                if self.device_changed {
+                    events::emit(Event::DeviceConnected {
+                        serial: status.info.serial.clone(),
+                        firmware: status.info.firmware_version.clone(),
+                    });
+                }
                 self.status = Some(status.clone());
 
-                match io::get_fido_info() {
+                match connected.fido_info {
                     Ok(fido) => self.fido_info = Some(fido),
                     Err(e) => {
                         log::error!("FIDO Info fetch failed: {}", e);
@@ -295,31 +1213,429 @@ impl DeviceRepo {
                     }
                 }
 
-                if status.firmware_type == types::FirmwareType::RSKey {
-                    self.led_status = io::read_led_config(status.method.clone()).ok();
-                    self.management_apps = io::read_management_config(status.method.clone()).ok();
-                } else {
-                    self.led_status = None;
-                    self.management_apps = None;
+                self.usb_descriptors = connected.usb_descriptors;
+                self.applets = connected.applets;
+                self.bootsel_boards.clear();
+
+                if self.device_changed {
+                    self.credentials = None;
+                    if let Err(e) = inventory::observe_firmware(
+                        &status.info.serial,
+                        &status.info.firmware_version,
+                    ) {
+                        log::warn!("Could not update the device timeline: {}", e);
+                    }
+                    self.reload_timeline();
+                    match self.compatibility() {
+                        Some(Compatibility::Tested) | None => {}
+                        Some(compat) => log::warn!(
+                            "{} firmware {} is outside the tested range: {:?}",
+                            status.firmware_type,
+                            status.info.firmware_version,
+                            compat
+                        ),
+                    }
+                }
+
+                if self.device_changed || health_changed {
+                    self.health_hint = inventory::health_hint(&status.info.serial);
+                    if let Some(hint) = &self.health_hint {
+                        log::warn!("{}", hint);
+                    }
                 }
+
+                self.check_advisories(&status, cx);
+
+                self.led_status = connected.led_status;
+                self.management_apps = connected.management_apps;
             }
             Err(e) => {
+                if !matches!(e, crate::error::PFError::NoDevice) {
+                    metrics::record_error(&format!("device.read.{}", e.kind().to_lowercase()));
+                }
+                if let Some(serial) = old_serial {
+                    events::emit(Event::DeviceDisconnected { serial });
+                }
                 self.set_error(format!("{}", e));
+                self.read_failed = !matches!(e, crate::error::PFError::NoDevice);
+                self.credentials = None;
                 self.device_changed = false;
+                if self.replug_check.is_some() {
+                    self.replug_seen_absent = true;
+                }
+                // Unplugged before anyone took the drift: check it again when
+                // the key comes back instead of dropping it.
+                if let Some(drift) = self.pending_drift.take() {
+                    self.drift_checked.remove(&drift.serial);
+                }
             }
         }
 
         self.end_load();
         cx.emit(DeviceEvent::Updated);
+        if let Some(result) = self.take_replug_result() {
+            Self::emit_replug_result(result, cx);
+        }
+        if let Some(drift) = self.detect_identity_drift() {
+            self.pending_drift = Some(drift);
+            cx.emit(DeviceEvent::IdentityDrift);
+        }
         cx.notify();
     }
 
+    /// Match the connected firmware against the security advisories in the
+    /// background when the user opted in: on every new device, and again on
+    /// a later refresh once [`advisories::CHECK_INTERVAL`] has passed.
+    fn check_advisories(&mut self, status: &FullDeviceStatus, cx: &mut Context<Self>) {
+        if !settings::load().0.advisory_checks {
+            self.advisories.clear();
+            self.advisories_checked = None;
+            return;
+        }
+        let recent = self
+            .advisories_checked
+            .is_some_and(|at| at.elapsed() < advisories::CHECK_INTERVAL);
+        if recent && !self.device_changed {
+            return;
+        }
+        self.advisories_checked = Some(Instant::now());
+
+        let firmware = status.firmware_type.clone();
+        let version = status.info.firmware_version.clone();
+        let serial = status.info.serial.clone();
+        let weak = cx.entity().downgrade();
+        self.advisory_task = Some(cx.spawn(async move |_, cx| {
+            let found = cx
+                .background_executor()
+                .spawn({
+                    let version = version.clone();
+                    async move { advisories::check(&firmware, &version) }
+                })
+                .await;
+            let _ = weak.update(cx, |repo, cx| {
+                // The key may have been swapped while the feed was fetched.
+                if repo.status.as_ref().map(|s| s.info.serial.as_str()) != Some(serial.as_str()) {
+                    return;
+                }
+                for advisory in &found {
+                    log::warn!(
+                        "Firmware {} is affected by security advisory {}: {}",
+                        version,
+                        advisory.id,
+                        advisory.title
+                    );
+                }
+                repo.advisories = found;
+                cx.notify();
+            });
+        }));
+    }
+
+    /// Compare a newly connected device against its inventory record, once per
+    /// serial per session. Skipped while a replug check is armed, since that
+    /// device is mid-way through an intentional identity change.
+    fn detect_identity_drift(&mut self) -> Option<IdentityDrift> {
+        let status = self.status.as_ref()?;
+        if self.replug_check.is_some() || !self.drift_checked.insert(status.info.serial.clone()) {
+            return None;
+        }
+        let drift = inventory::identity_drift(&inventory::load(), status)?;
+        log::warn!(
+            "Device {} reports USB identity {}, expected {}:{} from the inventory",
+            drift.serial,
+            drift.found_vid_pid,
+            drift.expected.vid,
+            drift.expected.pid
+        );
+        Some(drift)
+    }
+
+    /// Hand the pending identity drift to the screen that offers to restore
+    /// it. Returns `None` once taken, so the offer is only made once.
+    pub fn take_identity_drift(&mut self) -> Option<IdentityDrift> {
+        self.pending_drift.take()
+    }
+
+    /// Run the armed [`ReplugCheck`] once the same device is back after having
+    /// been absent. Returns `None` while still waiting.
+    fn take_replug_result(&mut self) -> Option<Result<String, String>> {
+        let status = self.status.as_ref()?;
+        let check = self.replug_check.as_ref()?;
+        if !self.replug_seen_absent || status.info.serial != check.serial {
+            return None;
+        }
+        let result = check.verify(status, self.usb_descriptors.as_ref());
+        match &result {
+            Ok(msg) => log::info!("Post-replug verification passed: {}", msg),
+            Err(msg) => log::warn!("Post-replug verification failed: {}", msg),
+        }
+        self.replug_check = None;
+        self.replug_seen_absent = false;
+        self.replug_deadline = None;
+        Some(result)
+    }
+
+    /// Drop an armed [`ReplugCheck`] whose device has not come back in time,
+    /// reporting it as failed.
+    fn expire_replug_check(&mut self, cx: &mut Context<Self>) {
+        if self
+            .replug_deadline
+            .is_none_or(|deadline| Instant::now() < deadline)
+        {
+            return;
+        }
+        self.replug_check = None;
+        self.replug_seen_absent = false;
+        self.replug_deadline = None;
+        let message = format!(
+            "The device did not come back within {} seconds, so the new USB identity \
+             was not verified. Replug it and check the Configuration screen.",
+            REPLUG_CHECK_TIMEOUT.as_secs()
+        );
+        log::warn!("Post-replug verification timed out");
+        Self::emit_replug_result(Err(message), cx);
+    }
+
+    fn emit_replug_result(result: Result<String, String>, cx: &mut Context<Self>) {
+        events::emit(Event::ReplugVerified {
+            ok: result.is_ok(),
+            message: match &result {
+                Ok(message) | Err(message) => message.clone(),
+            },
+        });
+        cx.emit(DeviceEvent::ReplugVerified(result));
+    }
+
+    // ── Capabilities ───────────────────────────────────────────────────────
+
+    /// What screens that need a key should show.
+    pub fn phase(&self) -> DevicePhase {
+        if self.status.is_some() {
+            return DevicePhase::Ready;
+        }
+        if let Some(owner) = &self.yielded_to {
+            return DevicePhase::Paused(format!("In use by {}", owner.role.describe()));
+        }
+        if let Some(reason) = &self.maintenance {
+            return DevicePhase::Paused(reason.clone());
+        }
+        match &self.error {
+            _ if self.loading => DevicePhase::Loading,
+            Some(error) if self.read_failed => DevicePhase::Failed(error.clone()),
+            Some(_) => DevicePhase::Absent,
+            None => DevicePhase::Loading,
+        }
+    }
+
+    /// Capabilities of the currently connected device (all `false` when none).
+    pub fn capabilities(&self) -> DeviceCapabilities {
+        let option = |name: &str| {
+            self.fido_info
+                .as_ref()
+                .and_then(|f| f.options.get(name).copied())
+                .unwrap_or(false)
+        };
+
+        let vendor_management = match (&self.status, &self.fido_info) {
+            (Some(status), _) if status.method == types::DeviceMethod::Rescue => true,
+            (Some(status), Some(info)) => is_pico_device(&status.firmware_type, info),
+            (Some(status), None) => status.firmware_type != types::FirmwareType::Unknown,
+            (None, _) => false,
+        };
+
+        let advertises = |command| match (&self.status, &self.fido_info) {
+            (Some(status), Some(info)) if status.method == types::DeviceMethod::Fido => {
+                advertises_vendor_config(&status.firmware_type, info, command)
+            }
+            _ => true,
+        };
+
+        DeviceCapabilities {
+            connected: self.status.is_some(),
+            vendor_management,
+            credential_management: option("credMgmt") || option("credentialMgmtPreview"),
+            openpgp: self.applets.openpgp,
+            oath: self.applets.oath,
+            bio_enrollment: self.fido_info.as_ref().is_some_and(|f| {
+                f.options.contains_key("bioEnroll")
+                    || f.options.contains_key("userVerificationMgmtPreview")
+            }),
+            hmac_secret: option("clientPin")
+                && self
+                    .fido_info
+                    .as_ref()
+                    .is_some_and(|f| f.extensions.iter().any(|e| e == "hmac-secret")),
+            vendor_config: VendorConfigSupport {
+                vid_pid: advertises(VendorConfigCommand::PhysicalVidPid),
+                led_gpio: advertises(VendorConfigCommand::PhysicalLedGpio),
+                led_brightness: advertises(VendorConfigCommand::PhysicalLedBrightness),
+                physical_options: advertises(VendorConfigCommand::PhysicalOptions),
+            },
+        }
+    }
+
+    /// The connected device as described in bug report drafts.
+    pub fn issue_summary(&self) -> Option<issue_report::DeviceSummary> {
+        let status = self.status.as_ref()?;
+        let mut board = format!(
+            "{} ({}:{})",
+            status.config.product_name, status.config.vid, status.config.pid
+        );
+        match self.fido_info.as_ref().map(check_fingerprint) {
+            Some(FingerprintVerdict::Known { build }) => {
+                board.push_str(&format!(", matches {}", build))
+            }
+            Some(FingerprintVerdict::Mismatch { family, .. }) => {
+                board.push_str(&format!(", claims {} but matches no known build", family))
+            }
+            _ => {}
+        }
+        Some(issue_report::DeviceSummary {
+            firmware_type: status.firmware_type.to_string(),
+            firmware_version: status.info.firmware_version.clone(),
+            board,
+            method: format!("{:?}", status.method),
+            serial: status.info.serial.clone(),
+        })
+    }
+
+    /// Nickname of the connected device, if the user gave it one.
+    pub fn nickname(&self) -> Option<&str> {
+        let serial = &self.status.as_ref()?.info.serial;
+        self.nicknames.get(serial).map(String::as_str)
+    }
+
+    /// Rename the connected device. A blank name removes the nickname.
+    pub fn set_nickname(&mut self, name: &str, cx: &mut Context<Self>) -> Result<(), String> {
+        let serial = self
+            .status
+            .as_ref()
+            .map(|s| s.info.serial.clone())
+            .ok_or("No device connected")?;
+        inventory::set_nickname(&serial, name)?;
+        self.nicknames = inventory::load().nicknames;
+        cx.notify();
+        Ok(())
+    }
+
+    /// Connection overrides stored for the connected device.
+    pub fn device_overrides(&self) -> DeviceOverrides {
+        let Some(status) = self.status.as_ref() else {
+            return DeviceOverrides::default();
+        };
+        inventory::load()
+            .device_overrides
+            .remove(&status.info.serial)
+            .unwrap_or_default()
+    }
+
+    /// Store connection overrides for the connected device and read it again
+    /// with them. Empty overrides remove the entry.
+    pub fn set_device_overrides(
+        &mut self,
+        overrides: DeviceOverrides,
+        cx: &mut Context<Self>,
+    ) -> Result<(), String> {
+        let serial = self
+            .status
+            .as_ref()
+            .map(|s| s.info.serial.clone())
+            .ok_or("No device connected")?;
+        inventory::set_device_overrides(&serial, overrides)?;
+        self.refresh(cx);
+        Ok(())
+    }
+
+    /// Add `event` to the connected device's timeline.
+    pub fn record_event(&mut self, event: TimelineEvent, cx: &mut Context<Self>) {
+        let Some(serial) = self.status.as_ref().map(|s| s.info.serial.clone()) else {
+            return;
+        };
+        if let Err(e) = inventory::record_event(&serial, event) {
+            log::warn!("Could not update the device timeline: {}", e);
+        }
+        self.reload_timeline();
+        cx.notify();
+    }
+
+    /// Record how many passkeys the connected device holds; only a change
+    /// adds a timeline entry.
+    pub fn observe_credential_count(&mut self, count: usize, cx: &mut Context<Self>) {
+        let Some(serial) = self.status.as_ref().map(|s| s.info.serial.clone()) else {
+            return;
+        };
+        if let Err(e) = inventory::observe_credential_count(&serial, count) {
+            log::warn!("Could not update the device timeline: {}", e);
+        }
+        self.reload_timeline();
+        cx.notify();
+    }
+
+    /// Enterprise attestation certificate last read back from the connected
+    /// device, if it was ever read.
+    pub fn attestation_cert_record(&self) -> Option<AttestationCertRecord> {
+        let serial = &self.status.as_ref()?.info.serial;
+        inventory::load().attestation_certs.remove(serial)
+    }
+
+    /// Remember the enterprise attestation certificate just read from the
+    /// connected device.
+    pub fn record_attestation_cert(
+        &mut self,
+        certificate: Option<&AttestationCertInfo>,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(serial) = self.status.as_ref().map(|s| s.info.serial.clone()) else {
+            return;
+        };
+        if let Err(e) = inventory::record_attestation_cert(&serial, certificate) {
+            log::warn!("Could not store the attestation certificate: {}", e);
+        }
+        cx.notify();
+    }
+
+    /// Store the CTAPHID exchanges made since the last refresh under `serial`,
+    /// the device connected during them. Returns whether anything was stored.
+    fn record_transport_health(serial: Option<&str>) -> bool {
+        let tally = io::take_transport_tally();
+        let Some(serial) = serial.filter(|_| !tally.is_empty()) else {
+            return false;
+        };
+        if let Err(e) = inventory::record_health(serial, &tally) {
+            log::warn!("Could not update the device health counts: {}", e);
+        }
+        true
+    }
+
+    fn reload_timeline(&mut self) {
+        self.timeline = match &self.status {
+            Some(status) => {
+                inventory::recent_events(&inventory::load(), &status.info.serial, RECENT_EVENTS)
+            }
+            None => Vec::new(),
+        };
+    }
+
+    /// How the connected firmware's version relates to the tested range.
+    /// `None` without a device, or for firmware PicoForge does not recognise.
+    pub fn compatibility(&self) -> Option<Compatibility> {
+        let status = self.status.as_ref()?;
+        if status.firmware_type == types::FirmwareType::Unknown {
+            return None;
+        }
+        Some(
+            AnyFirmware::new(status.firmware_type.clone(), &status.info.firmware_version)
+                .compatibility(),
+        )
+    }
+
     // ── State lifecycle helpers ────────────────────────────────────────────
 
     /// Mark the repo as loading.
     pub fn begin_load(&mut self) {
         self.loading = true;
         self.error = None;
+        self.read_failed = false;
     }
 
     /// Mark the repo as finished loading.
@@ -333,7 +1649,72 @@ impl DeviceRepo {
         self.fido_info = None;
         self.led_status = None;
         self.management_apps = None;
+        self.usb_descriptors = None;
+        self.applets = types::AppletPresence::default();
+        self.timeline.clear();
+        self.health_hint = None;
+        self.advisories.clear();
         self.loading = false;
         self.error = Some(error);
     }
 }
+
+/// Report backup or restore progress on the event stream.
+fn emit_backup_progress(operation: &str, progress: BackupProgress) {
+    events::emit(Event::Progress {
+        operation: operation.to_string(),
+        stage: format!("{:?}", progress.stage),
+        done: progress.done,
+        total: progress.total,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(vid: &str, pid: &str, product_name: &str) -> FullDeviceStatus {
+        FullDeviceStatus {
+            info: types::DeviceInfo {
+                serial: "ABC123".into(),
+                flash_used: None,
+                flash_total: None,
+                firmware_version: "7.4".into(),
+            },
+            config: types::AppConfig {
+                vid: vid.into(),
+                pid: pid.into(),
+                product_name: product_name.into(),
+                ..Default::default()
+            },
+            secure_boot: false,
+            secure_boot_locked: false,
+            secure_lock: false,
+            method: DeviceMethod::Fido,
+            firmware_type: types::FirmwareType::PicoFido,
+        }
+    }
+
+    fn check() -> ReplugCheck {
+        ReplugCheck {
+            serial: "ABC123".into(),
+            vid: Some("1D50".into()),
+            pid: Some("619B".into()),
+            product_name: None,
+        }
+    }
+
+    #[test]
+    fn test_replug_check_passes_on_the_new_identity() {
+        let status = status("1d50", "619b", "Old name");
+        assert!(check().verify(&status, None).is_ok());
+    }
+
+    #[test]
+    fn test_replug_check_lists_mismatches() {
+        let status = status("1D50", "0001", "Pico Key");
+        let err = check().verify(&status, None).unwrap_err();
+        assert!(err.contains("PID stored on the device is 0001, expected 619B"));
+        assert!(!err.contains("VID"));
+    }
+}
@@ -0,0 +1,133 @@
+//! Registry of app-wide actions that can be run from outside their screen.
+//!
+//! Each [`QuickAction`] pairs a stable id (what gets persisted) with a label,
+//! an icon and the GPUI action it dispatches. [`ApplicationRoot`] handles the
+//! actions, so they work from any screen. The user picks which ones are
+//! pinned to the title bar in About → Title Bar; the choice is saved in
+//! [`Settings::pinned_actions`] and mirrored in the [`PinnedActions`] global
+//! so the title bar updates as soon as it changes.
+//!
+//! [`ApplicationRoot`]: crate::ui::app::ApplicationRoot
+//! [`Settings::pinned_actions`]: crate::settings::Settings::pinned_actions
+
+use crate::settings;
+use crate::ui::app::{StartTour, ToggleSidebar};
+use gpui::{Action, App, Global};
+
+gpui::actions!(
+    picoforge,
+    [
+        RefreshDevice,
+        LockPasskeys,
+        OpenHome,
+        OpenPasskeys,
+        OpenConfiguration,
+        OpenSecurity
+    ]
+);
+
+/// One entry of the registry.
+#[derive(Clone, Copy)]
+pub struct QuickAction {
+    /// Stable id, saved in the settings file.
+    pub id: &'static str,
+    pub label: &'static str,
+    pub icon: &'static str,
+    build: fn() -> Box<dyn Action>,
+}
+
+impl QuickAction {
+    /// A fresh instance of the action, for `Window::dispatch_action`.
+    pub fn action(&self) -> Box<dyn Action> {
+        (self.build)()
+    }
+}
+
+/// Every action that can be pinned, in the order they are offered and shown.
+pub const QUICK_ACTIONS: &[QuickAction] = &[
+    QuickAction {
+        id: "device.refresh",
+        label: "Refresh device",
+        icon: "icons/refresh-cw.svg",
+        build: || Box::new(RefreshDevice),
+    },
+    QuickAction {
+        id: "passkeys.lock",
+        label: "Lock passkey storage",
+        icon: "icons/lock.svg",
+        build: || Box::new(LockPasskeys),
+    },
+    QuickAction {
+        id: "open.home",
+        label: "Go to Home",
+        icon: "icons/house.svg",
+        build: || Box::new(OpenHome),
+    },
+    QuickAction {
+        id: "open.passkeys",
+        label: "Go to Passkeys",
+        icon: "icons/key-round.svg",
+        build: || Box::new(OpenPasskeys),
+    },
+    QuickAction {
+        id: "open.configuration",
+        label: "Go to Configuration",
+        icon: "icons/settings.svg",
+        build: || Box::new(OpenConfiguration),
+    },
+    QuickAction {
+        id: "open.security",
+        label: "Go to Security",
+        icon: "icons/shield-check.svg",
+        build: || Box::new(OpenSecurity),
+    },
+    QuickAction {
+        id: "sidebar.toggle",
+        label: "Toggle sidebar",
+        icon: "icons/panel-left.svg",
+        build: || Box::new(ToggleSidebar),
+    },
+    QuickAction {
+        id: "tour.start",
+        label: "Take the UI tour",
+        icon: "icons/info.svg",
+        build: || Box::new(StartTour),
+    },
+];
+
+/// Ids pinned to the title bar, as saved.
+#[derive(Default)]
+pub struct PinnedActions(pub Vec<String>);
+
+impl Global for PinnedActions {}
+
+/// Load the pinned ids from the settings into the global.
+pub fn init(cx: &mut App) {
+    cx.set_global(PinnedActions(settings::load().0.pinned_actions));
+}
+
+/// Pinned entries, skipping ids this version does not know.
+pub fn pinned(cx: &App) -> Vec<&'static QuickAction> {
+    let ids = &cx.global::<PinnedActions>().0;
+    QUICK_ACTIONS
+        .iter()
+        .filter(|action| ids.iter().any(|id| id == action.id))
+        .collect()
+}
+
+/// Pin or unpin `id` and save the choice.
+pub fn set_pinned(id: &str, pinned: bool, cx: &mut App) -> Result<(), String> {
+    let (mut next, _) = settings::load();
+    next.pinned_actions.retain(|pinned_id| pinned_id != id);
+    if pinned {
+        next.pinned_actions.push(id.to_string());
+    }
+    settings::save(&next)?;
+    log::info!(
+        "{} {} the title bar",
+        id,
+        if pinned { "pinned to" } else { "removed from" }
+    );
+    cx.set_global(PinnedActions(next.pinned_actions));
+    Ok(())
+}
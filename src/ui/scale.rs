@@ -0,0 +1,67 @@
+//! UI scale for HiDPI displays and low vision.
+//!
+//! gpui-component sizes text and spacing in rems and sets the window's rem
+//! size from the theme's font size on every frame, so scaling that font size
+//! scales every view. The percentage is saved in [`Settings::ui_scale`] and
+//! changed in About → Appearance or with the zoom shortcuts
+//! (`ctrl-=`, `ctrl--`, `ctrl-0`).
+//!
+//! [`Settings::ui_scale`]: crate::settings::Settings::ui_scale
+
+use crate::settings::{self, step_ui_scale};
+use gpui::{App, Global, KeyBinding, px};
+use gpui_component::Theme;
+
+gpui::actions!(picoforge, [ZoomIn, ZoomOut, ResetZoom]);
+
+/// Theme font size (rem) at 100%.
+const BASE_REM: f32 = 16.0;
+
+/// The UI scale in effect, in percent.
+pub struct UiScale(pub u16);
+
+impl Global for UiScale {}
+
+/// Apply the saved scale and register the zoom shortcuts. Call after the
+/// theme is loaded.
+pub fn init(cx: &mut App) {
+    apply(settings::load().0.ui_scale_percent(), cx);
+
+    cx.bind_keys([
+        KeyBinding::new("ctrl-=", ZoomIn, None),
+        KeyBinding::new("ctrl-+", ZoomIn, None),
+        KeyBinding::new("ctrl--", ZoomOut, None),
+        KeyBinding::new("ctrl-0", ResetZoom, None),
+    ]);
+    cx.on_action(|_: &ZoomIn, cx| step(true, cx));
+    cx.on_action(|_: &ZoomOut, cx| step(false, cx));
+    cx.on_action(|_: &ResetZoom, cx| {
+        if let Err(e) = set(100, cx) {
+            log::error!("Failed to save the UI scale: {}", e);
+        }
+    });
+}
+
+fn step(larger: bool, cx: &mut App) {
+    let current = cx.global::<UiScale>().0;
+    if let Err(e) = set(step_ui_scale(current, larger), cx) {
+        log::error!("Failed to save the UI scale: {}", e);
+    }
+}
+
+/// Scale the UI to `percent` and save it.
+pub fn set(percent: u16, cx: &mut App) -> Result<(), String> {
+    let (mut next, _) = settings::load();
+    next.ui_scale = (percent != 100).then_some(percent);
+    let percent = next.ui_scale_percent();
+    settings::save(&next)?;
+    log::info!("UI scale set to {}%", percent);
+    apply(percent, cx);
+    Ok(())
+}
+
+fn apply(percent: u16, cx: &mut App) {
+    cx.set_global(UiScale(percent));
+    Theme::global_mut(cx).font_size = px(BASE_REM * f32::from(percent) / 100.0);
+    cx.refresh_windows();
+}
@@ -1,10 +1,32 @@
-use crate::ui::components::{card::Card, page_view::PageView, tag::Tag};
+use crate::settings::UI_SCALES;
+use crate::tunables;
+use crate::ui::components::{
+    card::Card, issue_report, page_view::PageView, spec_reference, tag::Tag,
+};
+use crate::ui::quick_actions::QUICK_ACTIONS;
+use crate::ui::scale::UiScale;
 use crate::ui::screens::about::view_model::AboutViewModel;
 use gpui::*;
-use gpui_component::{ActiveTheme, Icon, StyledExt, button::Button, h_flex, v_flex};
+use gpui_component::{
+    ActiveTheme, Icon, StyledExt,
+    button::{Button, ButtonVariants},
+    h_flex,
+    input::Input,
+    switch::Switch,
+    v_flex,
+};
 
 impl Render for AboutViewModel {
     fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let appearance_card = self.render_appearance_card(cx);
+        let logging_card = self.render_logging_card(cx);
+        let self_test_card = self.render_self_test_card(cx);
+        let safe_mode_card = self.render_safe_mode_card(cx);
+        let data_card = self.render_app_data_card(cx);
+        let metrics_card = self.render_metrics_card(cx);
+        let advisories_card = self.render_advisories_card(cx);
+        let title_bar_card = self.render_title_bar_card(cx);
+        let tunables_card = self.render_tunables_card(cx);
         let theme = cx.theme();
         PageView::build(
             "About",
@@ -14,9 +36,10 @@ impl Render for AboutViewModel {
                 .flex()
                 .justify_center()
                 .child(
-                    div()
+                    v_flex()
                         .w_full()
                         .max_w(px(1200.0))
+                        .gap_4()
                         .child(
                             Card::new().child(
                                 v_flex()
@@ -125,12 +148,568 @@ impl Render for AboutViewModel {
                                                             "https://github.com/librekeys/picoforge/wiki",
                                                         )
                                                     }),
+                                            )
+                                            .child(
+                                                Button::new("report_issue_btn")
+                                                    .outline()
+                                                    .bg(rgb(0x222225))
+                                                    .child(
+                                                        h_flex()
+                                                            .gap_2()
+                                                            .child(
+                                                                Icon::default()
+                                                                    .path("icons/triangle-alert.svg")
+                                                                    .size_4(),
+                                                            )
+                                                            .child("Report an Issue"),
+                                                    )
+                                                    .on_click(|_, _, cx| {
+                                                        issue_report::open_issue_draft(None, cx)
+                                                    }),
                                             ),
                                     ),
                             ),
-                        ),
+                        )
+                        .child(appearance_card)
+                        .child(logging_card)
+                        .child(self_test_card)
+                        .child(safe_mode_card)
+                        .child(data_card)
+                        .child(title_bar_card)
+                        .child(metrics_card)
+                        .child(advisories_card)
+                        .child(tunables_card),
                 ),
             theme,
         )
     }
 }
+
+impl AboutViewModel {
+    fn render_logging_card(&mut self, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+        let (status_text, healthy) = self.log_status_text();
+
+        let save_result = self.save_result.clone().map(|result| {
+            let (msg, color) = match result {
+                Ok(msg) => (msg, theme.muted_foreground),
+                Err(msg) => (msg, theme.danger),
+            };
+            div().text_sm().text_color(color).child(msg)
+        });
+
+        Card::new()
+            .title("Logging")
+            .description("Where PicoForge writes its log and audit files.")
+            .icon(Icon::default().path("icons/scroll-text.svg"))
+            .child(
+                v_flex()
+                    .gap_3()
+                    .child(
+                        div()
+                            .text_sm()
+                            .text_color(if healthy {
+                                theme.muted_foreground
+                            } else {
+                                theme.warning
+                            })
+                            .child(status_text),
+                    )
+                    .child(
+                        v_flex()
+                            .gap_1()
+                            .child(div().text_sm().font_medium().child("Log Directory"))
+                            .child(Input::new(&self.log_dir_input)),
+                    )
+                    .children(save_result)
+                    .child(
+                        h_flex()
+                            .gap_2()
+                            .child(
+                                Button::new("open-log-folder")
+                                    .ghost()
+                                    .label("Open Log Folder")
+                                    .on_click(cx.listener(|this, _, _, cx| {
+                                        this.open_log_folder(cx);
+                                    })),
+                            )
+                            .child(
+                                Button::new("open-config-folder")
+                                    .ghost()
+                                    .label("Open Config Folder")
+                                    .on_click(cx.listener(|this, _, _, cx| {
+                                        this.open_config_folder(cx);
+                                    })),
+                            )
+                            .child(
+                                Button::new("open-spec-reference")
+                                    .ghost()
+                                    .label("CTAP Reference")
+                                    .on_click(|_, window, cx| {
+                                        spec_reference::open_spec_reference(None, window, cx);
+                                    }),
+                            )
+                            .child(div().flex_1())
+                            .child(
+                                Button::new("log-dir-default")
+                                    .label("Use Default")
+                                    .on_click(cx.listener(|this, _, window, cx| {
+                                        this.reset_log_dir(window, cx);
+                                    })),
+                            )
+                            .child(
+                                Button::new("log-dir-save")
+                                    .primary()
+                                    .label("Save")
+                                    .on_click(cx.listener(|this, _, _, cx| {
+                                        this.save_log_dir(cx);
+                                    })),
+                            ),
+                    ),
+            )
+    }
+
+    fn render_self_test_card(&mut self, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+        let busy = self.self_test_busy;
+
+        let result = self.self_test_result.clone().map(|result| {
+            let (msg, color) = match result {
+                Ok(msg) => (msg, theme.foreground),
+                Err(msg) => (msg, theme.danger),
+            };
+            div().text_sm().text_color(color).child(msg)
+        });
+
+        Card::new()
+            .title("Link Self-Test")
+            .description("Echo random data off the key with CTAPHID_PING to check the cable and hub.")
+            .icon(Icon::default().path("icons/network.svg"))
+            .child(
+                v_flex()
+                    .gap_3()
+                    .child(
+                        div()
+                            .text_sm()
+                            .text_color(theme.muted_foreground)
+                            .child("Slow or uneven round trips, or failures here while the key otherwise enumerates, usually point at the connection rather than the firmware."),
+                    )
+                    .children(result)
+                    .child(
+                        h_flex().justify_end().child(
+                            Button::new("run-self-test")
+                                .primary()
+                                .label("Run Self-Test")
+                                .loading(busy)
+                                .disabled(busy)
+                                .on_click(cx.listener(|this, _, _, cx| {
+                                    this.run_self_test(cx);
+                                })),
+                        ),
+                    ),
+            )
+    }
+
+    fn render_safe_mode_card(&mut self, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+        let enabled = self.settings.safe_mode;
+        let error = self
+            .safe_mode_error
+            .clone()
+            .map(|e| div().text_sm().text_color(theme.danger).child(e));
+
+        Card::new()
+            .title("Safe Mode")
+            .description("Start without reading any key, for when a misbehaving key or a permission problem makes PicoForge hang at launch.")
+            .icon(Icon::default().path("icons/info.svg"))
+            .child(
+                v_flex()
+                    .gap_3()
+                    .child(
+                        h_flex()
+                            .items_center()
+                            .justify_between()
+                            .child(
+                                v_flex().gap_0p5().child("Start in safe mode").child(
+                                    div()
+                                        .text_sm()
+                                        .text_color(theme.muted_foreground)
+                                        .child("Takes effect at the next launch. Keys are read once you click Connect in the banner or refresh; --safe-mode does the same for one launch."),
+                                ),
+                            )
+                            .child(
+                                Switch::new("safe-mode")
+                                    .checked(enabled)
+                                    .on_click(cx.listener(|this, checked, _, cx| {
+                                        this.set_safe_mode(*checked, cx);
+                                    })),
+                            ),
+                    )
+                    .children(error),
+            )
+    }
+
+    fn render_app_data_card(&mut self, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+        let busy = self.data_busy;
+
+        let data_result = self.data_result.clone().map(|result| {
+            let (msg, color) = match result {
+                Ok(msg) => (msg, theme.muted_foreground),
+                Err(msg) => (msg, theme.danger),
+            };
+            div().text_sm().text_color(color).child(msg)
+        });
+
+        Card::new()
+            .title("Application Data")
+            .description("Move settings, the device inventory, the provisioning report key, secure lock host keys and the audit log to another machine.")
+            .icon(Icon::default().path("icons/hard-drive.svg"))
+            .child(
+                v_flex()
+                    .gap_3()
+                    .child(
+                        div()
+                            .text_sm()
+                            .text_color(theme.muted_foreground)
+                            .child("The archive is encrypted with the passphrase below. It cannot be recovered without it."),
+                    )
+                    .child(
+                        v_flex()
+                            .gap_1()
+                            .child(div().text_sm().font_medium().child("Passphrase"))
+                            .child(Input::new(&self.passphrase_input)),
+                    )
+                    .children(data_result)
+                    .child(
+                        h_flex()
+                            .gap_2()
+                            .justify_end()
+                            .child(
+                                Button::new("app-data-import")
+                                    .label("Import...")
+                                    .disabled(busy)
+                                    .on_click(cx.listener(|this, _, window, cx| {
+                                        this.import_app_data(window, cx);
+                                    })),
+                            )
+                            .child(
+                                Button::new("app-data-export")
+                                    .primary()
+                                    .label("Export...")
+                                    .loading(busy)
+                                    .disabled(busy)
+                                    .on_click(cx.listener(|this, _, _, cx| {
+                                        this.export_app_data(cx);
+                                    })),
+                            ),
+                    ),
+            )
+    }
+
+    fn render_metrics_card(&mut self, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+        let enabled = self.settings.metrics_enabled;
+
+        let metrics_result = self.metrics_result.clone().map(|result| {
+            let (msg, color) = match result {
+                Ok(msg) => (msg, theme.muted_foreground),
+                Err(msg) => (msg, theme.danger),
+            };
+            div().text_sm().text_color(color).child(msg)
+        });
+
+        let row = |name: &String, count: &u64| {
+            h_flex()
+                .justify_between()
+                .text_sm()
+                .child(
+                    div()
+                        .font_family("Mono")
+                        .text_color(theme.muted_foreground)
+                        .child(name.clone()),
+                )
+                .child(div().text_color(theme.foreground).child(count.to_string()))
+        };
+        let features = self.metrics.features.iter().map(row);
+        let errors = self.metrics.errors.iter().map(row);
+        let empty = self.metrics.features.is_empty() && self.metrics.errors.is_empty();
+
+        Card::new()
+            .title("Usage Metrics")
+            .description("Count which features are used and which errors occur. Stored on this computer only; nothing is sent anywhere.")
+            .icon(Icon::default().path("icons/scroll-text.svg"))
+            .child(
+                v_flex()
+                    .gap_3()
+                    .child(
+                        h_flex()
+                            .items_center()
+                            .justify_between()
+                            .child(
+                                v_flex().gap_0p5().child("Collect usage metrics").child(
+                                    div()
+                                        .text_sm()
+                                        .text_color(theme.muted_foreground)
+                                        .child("Only screen names, operation names and error types are counted, never serials or messages."),
+                                ),
+                            )
+                            .child(
+                                Switch::new("metrics-enabled")
+                                    .checked(enabled)
+                                    .on_click(cx.listener(|this, checked, _, cx| {
+                                        this.set_metrics_enabled(*checked, cx);
+                                    })),
+                            ),
+                    )
+                    .child(div().h_px().bg(theme.border))
+                    .child(if empty {
+                        div()
+                            .text_sm()
+                            .text_color(theme.muted_foreground)
+                            .child("Nothing recorded yet.")
+                            .into_any_element()
+                    } else {
+                        v_flex()
+                            .gap_1()
+                            .child(div().text_sm().font_medium().child("Features"))
+                            .children(features)
+                            .child(div().text_sm().font_medium().pt_2().child("Errors"))
+                            .children(errors)
+                            .into_any_element()
+                    })
+                    .children(metrics_result)
+                    .child(
+                        h_flex()
+                            .gap_2()
+                            .justify_end()
+                            .child(
+                                Button::new("metrics-refresh")
+                                    .ghost()
+                                    .label("Refresh")
+                                    .on_click(cx.listener(|this, _, _, cx| {
+                                        this.refresh_metrics(cx);
+                                    })),
+                            )
+                            .child(
+                                Button::new("metrics-reset")
+                                    .label("Reset")
+                                    .disabled(empty)
+                                    .on_click(cx.listener(|this, _, _, cx| {
+                                        this.reset_metrics(cx);
+                                    })),
+                            )
+                            .child(
+                                Button::new("metrics-export")
+                                    .primary()
+                                    .label("Export...")
+                                    .disabled(empty)
+                                    .on_click(cx.listener(|this, _, _, cx| {
+                                        this.export_metrics(cx);
+                                    })),
+                            ),
+                    ),
+            )
+    }
+
+    fn render_advisories_card(&mut self, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+        let enabled = self.settings.advisory_checks;
+        let error = self
+            .advisory_error
+            .clone()
+            .map(|e| div().text_sm().text_color(theme.danger).child(e));
+
+        Card::new()
+            .title("Security Advisories")
+            .description("Warn on the Home screen when the connected firmware is affected by a published security advisory.")
+            .icon(Icon::default().path("icons/shield-check.svg"))
+            .child(
+                v_flex()
+                    .gap_3()
+                    .child(
+                        h_flex()
+                            .items_center()
+                            .justify_between()
+                            .child(
+                                v_flex().gap_0p5().child("Check for advisories").child(
+                                    div()
+                                        .text_sm()
+                                        .text_color(theme.muted_foreground)
+                                        .child("Downloads the advisories list from the librekeys organisation at most once a day and compares it on this computer; nothing about your key is sent."),
+                                ),
+                            )
+                            .child(
+                                Switch::new("advisory-checks")
+                                    .checked(enabled)
+                                    .on_click(cx.listener(|this, checked, _, cx| {
+                                        this.set_advisory_checks(*checked, cx);
+                                    })),
+                            ),
+                    )
+                    .children(error),
+            )
+    }
+
+    fn render_appearance_card(&mut self, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+        let current = cx.global::<UiScale>().0;
+        let sound_cues = self.settings.sound_cues;
+        let scale_error = self
+            .scale_error
+            .clone()
+            .map(|e| div().text_sm().text_color(theme.danger).child(e));
+        let steps = UI_SCALES.iter().map(|&percent| {
+            let button = Button::new(SharedString::from(format!("ui-scale-{}", percent)))
+                .label(format!("{}%", percent))
+                .on_click(cx.listener(move |this, _, _, cx| {
+                    this.set_ui_scale(percent, cx);
+                }));
+            if percent == current {
+                button.primary()
+            } else {
+                button.ghost()
+            }
+        });
+
+        Card::new()
+            .title("Appearance")
+            .description(
+                "Scale text and spacing in every screen and choose whether PicoForge plays sounds. Ctrl+= and Ctrl+- zoom, Ctrl+0 resets.",
+            )
+            .icon(Icon::default().path("icons/palette.svg"))
+            .child(
+                v_flex()
+                    .gap_3()
+                    .child(
+                        h_flex()
+                            .items_center()
+                            .justify_between()
+                            .child(div().text_sm().child("UI scale"))
+                            .child(h_flex().gap_1().children(steps)),
+                    )
+                    .child(
+                        h_flex()
+                            .items_center()
+                            .justify_between()
+                            .child(
+                                v_flex().gap_0p5().child(div().text_sm().child("Sound cues")).child(
+                                    div()
+                                        .text_sm()
+                                        .text_color(theme.muted_foreground)
+                                        .child("Play a sound when the key wants a touch and when an operation succeeds or fails."),
+                                ),
+                            )
+                            .child(
+                                Switch::new("sound-cues")
+                                    .checked(sound_cues)
+                                    .on_click(cx.listener(|this, checked, _, cx| {
+                                        this.set_sound_cues(*checked, cx);
+                                    })),
+                            ),
+                    )
+                    .children(scale_error),
+            )
+    }
+
+    fn render_title_bar_card(&mut self, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+        let pin_error = self
+            .pin_error
+            .clone()
+            .map(|e| div().text_sm().text_color(theme.danger).child(e));
+        let rows = QUICK_ACTIONS.iter().map(|action| {
+            let pinned = self
+                .settings
+                .pinned_actions
+                .iter()
+                .any(|id| id == action.id);
+            h_flex()
+                .items_center()
+                .justify_between()
+                .child(
+                    h_flex()
+                        .gap_2()
+                        .items_center()
+                        .text_sm()
+                        .child(Icon::default().path(action.icon).size_4())
+                        .child(action.label),
+                )
+                .child(
+                    Switch::new(SharedString::from(format!("pin-{}", action.id)))
+                        .checked(pinned)
+                        .on_click(cx.listener(move |this, checked, _, cx| {
+                            this.set_action_pinned(action.id, *checked, cx);
+                        })),
+                )
+        });
+
+        Card::new()
+            .title("Title Bar")
+            .description(
+                "Actions pinned here get a button in the title bar and work from any screen.",
+            )
+            .icon(Icon::default().path("icons/star.svg"))
+            .child(v_flex().gap_3().children(rows).children(pin_error))
+    }
+
+    fn render_tunables_card(&mut self, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+        let path = tunables::path()
+            .map(|path| path.display().to_string())
+            .unwrap_or_else(|| "picoforge.toml".into());
+
+        let result = self.tunables_result.clone().map(|result| {
+            let (msg, color) = match result {
+                Ok(msg) => (msg, theme.muted_foreground),
+                Err(msg) => (msg, theme.danger),
+            };
+            div().text_sm().text_color(color).child(msg)
+        });
+
+        Card::new()
+            .title("Advanced Tunables")
+            .description(
+                "HID timeouts, retries, touch patience, capture sizes and transport order. \
+                 Only change these if your hardware needs it.",
+            )
+            .icon(Icon::default().path("icons/settings.svg"))
+            .child(
+                v_flex()
+                    .gap_3()
+                    .child(
+                        div()
+                            .text_sm()
+                            .text_color(theme.muted_foreground)
+                            .child(path),
+                    )
+                    .child(
+                        Input::new(&self.tunables_input)
+                            .h(px(320.0))
+                            .font_family("monospace")
+                            .text_xs(),
+                    )
+                    .children(result)
+                    .child(
+                        h_flex()
+                            .gap_2()
+                            .child(div().flex_1())
+                            .child(
+                                Button::new("tunables-default")
+                                    .label("Reset to Defaults")
+                                    .on_click(cx.listener(|this, _, window, cx| {
+                                        this.reset_tunables(window, cx);
+                                    })),
+                            )
+                            .child(
+                                Button::new("tunables-save")
+                                    .primary()
+                                    .label("Save")
+                                    .on_click(cx.listener(|this, _, _, cx| {
+                                        this.save_tunables(cx);
+                                    })),
+                            ),
+                    ),
+            )
+    }
+}
@@ -1,13 +1,523 @@
 //! View model for the about screen — version info and firmware compatibility.
+//!
+//! Also hosts the application settings that are not tied to a device, such as
+//! the UI scale, the log directory, the CTAPHID_PING link self-test, usage
+//! metrics, security advisory checks and title bar pins, the encrypted app
+//! data export/import and the raw `picoforge.toml` editor.
 
+use crate::backup;
+use crate::cues::{self, Cue};
+use crate::logging::{self, LogStatus};
+use crate::metrics::{self, Metrics};
+use crate::settings::{self, Settings};
+use crate::tunables;
 use crate::ui::app::AppModels;
+use crate::ui::models::device::DeviceRepo;
+use crate::ui::quick_actions;
+use crate::ui::scale;
+use directories::UserDirs;
 use gpui::*;
+use gpui_component::input::InputState;
+use std::path::PathBuf;
 
 /// Application metadata and firmware compatibility information.
-pub struct AboutViewModel;
+pub struct AboutViewModel {
+    pub(super) settings: Settings,
+    pub(super) log_dir_input: Entity<InputState>,
+    /// Result of the last settings save (`Ok` message or error).
+    pub(super) save_result: Option<Result<String, String>>,
+    pub(super) passphrase_input: Entity<InputState>,
+    /// Result of the last app data export or import.
+    pub(super) data_result: Option<Result<String, String>>,
+    pub(super) data_busy: bool,
+    /// Counters shown in the metrics card; refreshed on demand.
+    pub(super) metrics: Metrics,
+    /// Summary of the last link self-test, or why it failed.
+    pub(super) self_test_result: Option<Result<String, String>>,
+    pub(super) self_test_busy: bool,
+    /// Result of the last metrics export or reset.
+    pub(super) metrics_result: Option<Result<String, String>>,
+    /// Why the last title bar pin change could not be saved.
+    pub(super) pin_error: Option<String>,
+    /// Why the last Appearance change (UI scale, sound cues) could not be saved.
+    pub(super) scale_error: Option<String>,
+    /// Why the last advisory check switch could not be saved.
+    pub(super) advisory_error: Option<String>,
+    /// Why the last safe mode switch could not be saved.
+    pub(super) safe_mode_error: Option<String>,
+    /// Raw `picoforge.toml` text.
+    pub(super) tunables_input: Entity<InputState>,
+    /// Result of the last `picoforge.toml` save.
+    pub(super) tunables_result: Option<Result<String, String>>,
+    _task: Option<Task<()>>,
+    _self_test_task: Option<Task<()>>,
+}
 
 impl AboutViewModel {
-    pub fn new(_window: &mut Window, _cx: &mut Context<Self>, _models: &AppModels) -> Self {
-        Self
+    pub fn new(window: &mut Window, cx: &mut Context<Self>, _models: &AppModels) -> Self {
+        let (settings, _) = settings::load();
+        let current = settings
+            .log_dir
+            .as_ref()
+            .map(|dir| dir.display().to_string())
+            .unwrap_or_default();
+        let default_dir = logging::default_log_dir().display().to_string();
+
+        let log_dir_input = cx.new(|cx| {
+            InputState::new(window, cx)
+                .placeholder(default_dir)
+                .default_value(current)
+        });
+
+        let passphrase_input = cx.new(|cx| {
+            InputState::new(window, cx)
+                .placeholder("Archive passphrase")
+                .masked(true)
+        });
+
+        let tunables_input = cx.new(|cx| {
+            InputState::new(window, cx)
+                .multi_line(true)
+                .default_value(tunables::read_text())
+        });
+
+        Self {
+            settings,
+            log_dir_input,
+            save_result: None,
+            passphrase_input,
+            data_result: None,
+            data_busy: false,
+            metrics: metrics::snapshot(),
+            self_test_result: None,
+            self_test_busy: false,
+            metrics_result: None,
+            pin_error: None,
+            scale_error: None,
+            advisory_error: None,
+            safe_mode_error: None,
+            tunables_input,
+            tunables_result: None,
+            _task: None,
+            _self_test_task: None,
+        }
+    }
+
+    /// Check the editor text and write it to `picoforge.toml`.
+    pub(super) fn save_tunables(&mut self, cx: &mut Context<Self>) {
+        let text = self.tunables_input.read(cx).value().to_string();
+        self.tunables_result = Some(match tunables::save_text(&text) {
+            Ok(()) => {
+                log::info!("Saved advanced tunables");
+                Ok("Saved. Applies after restarting PicoForge.".into())
+            }
+            Err(e) => {
+                log::error!("Failed to save advanced tunables: {}", e);
+                Err(e)
+            }
+        });
+        cx.notify();
+    }
+
+    /// Put the documented defaults back into the editor. Nothing is saved.
+    pub(super) fn reset_tunables(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.tunables_input.update(cx, |input, cx| {
+            input.set_value(tunables::TEMPLATE, window, cx)
+        });
+        self.tunables_result = None;
+        cx.notify();
+    }
+
+    /// Where logs are being written in this session, for display.
+    pub(super) fn log_status_text(&self) -> (String, bool) {
+        match logging::log_status() {
+            Some(LogStatus::File(dir)) => (format!("Writing logs to {}", dir.display()), true),
+            Some(LogStatus::ConsoleOnly { dir, reason }) => (
+                format!(
+                    "File logging is disabled: {} ({}). Logs only go to the console.",
+                    reason,
+                    dir.display()
+                ),
+                false,
+            ),
+            None => ("Logging is not initialised".into(), false),
+        }
+    }
+
+    /// Persist the log directory from the input. Empty means the default.
+    pub(super) fn save_log_dir(&mut self, cx: &mut Context<Self>) {
+        let value = self.log_dir_input.read(cx).value().trim().to_string();
+        let log_dir = (!value.is_empty()).then(|| PathBuf::from(value));
+
+        let (mut next, _) = settings::load();
+        next.log_dir = log_dir;
+
+        self.save_result = Some(match settings::save(&next) {
+            Ok(()) => {
+                log::info!("Log directory set to {:?}", next.log_dir);
+                self.settings = next;
+                Ok("Saved. The new log directory is used after restarting PicoForge.".into())
+            }
+            Err(e) => {
+                log::error!("Failed to save settings: {}", e);
+                Err(e)
+            }
+        });
+        cx.notify();
+    }
+
+    /// Clear the custom log directory and go back to the platform default.
+    pub(super) fn reset_log_dir(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.log_dir_input
+            .update(cx, |input, cx| input.set_value("", window, cx));
+        self.save_log_dir(cx);
+    }
+
+    /// Open `dir` in the system file manager, creating it first so a fresh
+    /// install has something to show.
+    fn open_folder(&mut self, dir: Option<PathBuf>, cx: &mut Context<Self>) {
+        let Some(dir) = dir else {
+            self.save_result = Some(Err("Could not determine the directory".into()));
+            cx.notify();
+            return;
+        };
+        if let Err(e) = std::fs::create_dir_all(&dir) {
+            self.save_result = Some(Err(format!("Could not create {}: {}", dir.display(), e)));
+            cx.notify();
+            return;
+        }
+        log::info!("Opening {:?} in the file manager", dir);
+        cx.open_with_system(&dir);
+    }
+
+    /// Open the directory logs are written to in this session.
+    pub(super) fn open_log_folder(&mut self, cx: &mut Context<Self>) {
+        let dir = match logging::log_status() {
+            Some(LogStatus::File(dir)) => dir.clone(),
+            Some(LogStatus::ConsoleOnly { dir, .. }) => dir.clone(),
+            None => logging::default_log_dir(),
+        };
+        self.open_folder(Some(dir), cx);
+    }
+
+    /// Open the directory holding `settings.json`.
+    pub(super) fn open_config_folder(&mut self, cx: &mut Context<Self>) {
+        self.open_folder(settings::config_dir(), cx);
+    }
+
+    /// Pin a quick action to the title bar, or remove it.
+    /// Ping the managed key to measure latency and throughput of the link.
+    pub(super) fn run_self_test(&mut self, cx: &mut Context<Self>) {
+        self.self_test_busy = true;
+        self.self_test_result = None;
+        cx.notify();
+
+        let entity = cx.entity().downgrade();
+        self._self_test_task = Some(cx.spawn(async move |_, cx| {
+            let result = cx
+                .background_executor()
+                .spawn(async { DeviceRepo::ping_self_test_blocking() })
+                .await
+                .map(|report| report.summary());
+            if let Err(e) = &result {
+                log::error!("Link self-test failed: {}", e);
+            }
+            let _ = entity.update(cx, |this, cx| {
+                this.self_test_busy = false;
+                this.self_test_result = Some(result);
+                cx.notify();
+            });
+        }));
+    }
+
+    pub(super) fn set_action_pinned(&mut self, id: &str, pinned: bool, cx: &mut Context<Self>) {
+        match quick_actions::set_pinned(id, pinned, cx) {
+            Ok(()) => {
+                self.settings.pinned_actions =
+                    cx.global::<quick_actions::PinnedActions>().0.clone();
+                self.pin_error = None;
+            }
+            Err(e) => self.pin_error = Some(e),
+        }
+        cx.notify();
+    }
+
+    /// Scale the whole UI to `percent` and save it.
+    pub(super) fn set_ui_scale(&mut self, percent: u16, cx: &mut Context<Self>) {
+        match scale::set(percent, cx) {
+            Ok(()) => {
+                self.settings.ui_scale = settings::load().0.ui_scale;
+                self.scale_error = None;
+            }
+            Err(e) => self.scale_error = Some(e),
+        }
+        cx.notify();
+    }
+
+    /// Turn sound cues on or off and persist the choice. A success cue
+    /// confirms the switch works.
+    pub(super) fn set_sound_cues(&mut self, enabled: bool, cx: &mut Context<Self>) {
+        let (mut next, _) = settings::load();
+        next.sound_cues = enabled;
+        match settings::save(&next) {
+            Ok(()) => {
+                cues::set_enabled(enabled);
+                cues::play(Cue::Success);
+                self.settings = next;
+                self.scale_error = None;
+            }
+            Err(e) => self.scale_error = Some(e),
+        }
+        cx.notify();
+    }
+
+    /// Opt in to or out of local usage metrics and persist the choice.
+    pub(super) fn set_metrics_enabled(&mut self, enabled: bool, cx: &mut Context<Self>) {
+        let (mut next, _) = settings::load();
+        next.metrics_enabled = enabled;
+        match settings::save(&next) {
+            Ok(()) => {
+                log::info!(
+                    "Usage metrics {}",
+                    if enabled { "enabled" } else { "disabled" }
+                );
+                metrics::set_enabled(enabled);
+                self.settings = next;
+                self.metrics_result = None;
+            }
+            Err(e) => self.metrics_result = Some(Err(e)),
+        }
+        cx.notify();
+    }
+
+    /// Opt in to or out of security advisory checks and persist the choice.
+    /// The connected key is checked on its next refresh.
+    pub(super) fn set_advisory_checks(&mut self, enabled: bool, cx: &mut Context<Self>) {
+        let (mut next, _) = settings::load();
+        next.advisory_checks = enabled;
+        match settings::save(&next) {
+            Ok(()) => {
+                log::info!(
+                    "Security advisory checks {}",
+                    if enabled { "enabled" } else { "disabled" }
+                );
+                self.settings = next;
+                self.advisory_error = None;
+            }
+            Err(e) => self.advisory_error = Some(e),
+        }
+        cx.notify();
+    }
+
+    /// Start every launch in safe mode, or stop doing so, and persist the
+    /// choice. The running session is not affected.
+    pub(super) fn set_safe_mode(&mut self, enabled: bool, cx: &mut Context<Self>) {
+        let (mut next, _) = settings::load();
+        next.safe_mode = enabled;
+        match settings::save(&next) {
+            Ok(()) => {
+                log::info!(
+                    "Safe mode at launch {}",
+                    if enabled { "enabled" } else { "disabled" }
+                );
+                self.settings = next;
+                self.safe_mode_error = None;
+            }
+            Err(e) => self.safe_mode_error = Some(e),
+        }
+        cx.notify();
+    }
+
+    /// Re-read the counters for display.
+    pub(super) fn refresh_metrics(&mut self, cx: &mut Context<Self>) {
+        self.metrics = metrics::snapshot();
+        cx.notify();
+    }
+
+    /// Discard all counters.
+    pub(super) fn reset_metrics(&mut self, cx: &mut Context<Self>) {
+        self.metrics_result = Some(metrics::reset().map(|_| "Counters cleared.".to_string()));
+        self.metrics = metrics::snapshot();
+        cx.notify();
+    }
+
+    /// Save the counters as JSON wherever the user chooses.
+    pub(super) fn export_metrics(&mut self, cx: &mut Context<Self>) {
+        let json = match serde_json::to_string_pretty(&metrics::snapshot()) {
+            Ok(json) => json,
+            Err(e) => {
+                self.metrics_result = Some(Err(e.to_string()));
+                cx.notify();
+                return;
+            }
+        };
+
+        let default_dir = UserDirs::new()
+            .and_then(|d| {
+                d.document_dir()
+                    .or_else(|| d.download_dir())
+                    .map(|p| p.to_path_buf())
+            })
+            .unwrap_or_else(|| PathBuf::from(std::env::var("HOME").unwrap_or_else(|_| ".".into())));
+        let receiver = cx.prompt_for_new_path(&default_dir, Some("picoforge-metrics.json"));
+
+        let entity = cx.entity().downgrade();
+        self._task = Some(cx.spawn(async move |_, cx| {
+            let result = match receiver.await {
+                Ok(Ok(Some(path))) => std::fs::write(&path, json)
+                    .map(|_| format!("Metrics saved to {}", path.display()))
+                    .map_err(|e| format!("Could not write {}: {}", path.display(), e)),
+                Ok(Err(e)) => Err(format!("Save dialog error: {}", e)),
+                _ => return,
+            };
+            let _ = entity.update(cx, |this, cx| {
+                this.metrics_result = Some(result);
+                cx.notify();
+            });
+        }));
+    }
+
+    fn finish_data_task(&mut self, result: Result<String, String>, cx: &mut Context<Self>) {
+        if let Err(e) = &result {
+            log::error!("App data transfer failed: {}", e);
+        }
+        self.data_busy = false;
+        self.data_result = Some(result);
+        cx.notify();
+    }
+
+    /// Encrypt the app data with the entered passphrase and save the archive.
+    pub(super) fn export_app_data(&mut self, cx: &mut Context<Self>) {
+        let passphrase = self.passphrase_input.read(cx).value().to_string();
+        if passphrase.chars().count() < backup::MIN_PASSPHRASE_LEN {
+            self.data_result = Some(Err(format!(
+                "Passphrase must be at least {} characters",
+                backup::MIN_PASSPHRASE_LEN
+            )));
+            cx.notify();
+            return;
+        }
+
+        let default_dir = UserDirs::new()
+            .and_then(|d| {
+                d.document_dir()
+                    .or_else(|| d.download_dir())
+                    .map(|p| p.to_path_buf())
+            })
+            .unwrap_or_else(|| PathBuf::from(std::env::var("HOME").unwrap_or_else(|_| ".".into())));
+        let file_name = format!("picoforge-app-data.{}", backup::ARCHIVE_EXTENSION);
+        let receiver = cx.prompt_for_new_path(&default_dir, Some(&file_name));
+
+        self.data_busy = true;
+        self.data_result = None;
+        cx.notify();
+
+        let entity = cx.entity().downgrade();
+        self._task = Some(cx.spawn(async move |_, cx| {
+            let path = match receiver.await {
+                Ok(Ok(Some(path))) => path,
+                Ok(Err(e)) => {
+                    let _ = entity.update(cx, |this, cx| {
+                        this.finish_data_task(Err(format!("Save dialog error: {}", e)), cx);
+                    });
+                    return;
+                }
+                _ => {
+                    let _ = entity.update(cx, |this, cx| {
+                        this.data_busy = false;
+                        cx.notify();
+                    });
+                    return;
+                }
+            };
+
+            let result = cx
+                .background_executor()
+                .spawn(async move {
+                    let archive =
+                        backup::collect().and_then(|data| backup::encrypt(&data, &passphrase))?;
+                    std::fs::write(&path, archive)
+                        .map_err(|e| format!("Could not write {}: {}", path.display(), e))?;
+                    log::info!("Exported application data to {:?}", path);
+                    Ok(format!("App data exported to {}", path.display()))
+                })
+                .await;
+
+            let _ = entity.update(cx, |this, cx| this.finish_data_task(result, cx));
+        }));
+    }
+
+    /// Pick an archive, decrypt it with the entered passphrase and restore it.
+    pub(super) fn import_app_data(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let passphrase = self.passphrase_input.read(cx).value().to_string();
+        if passphrase.is_empty() {
+            self.data_result = Some(Err("Enter the archive passphrase first".into()));
+            cx.notify();
+            return;
+        }
+
+        let receiver = cx.prompt_for_paths(gpui::PathPromptOptions {
+            files: true,
+            directories: false,
+            multiple: false,
+            prompt: Some("Select PicoForge App Data Archive".into()),
+        });
+
+        self.data_busy = true;
+        self.data_result = None;
+        cx.notify();
+
+        let window_handle = window.window_handle();
+        let entity = cx.entity().downgrade();
+        self._task = Some(cx.spawn(async move |_, cx| {
+            let Ok(Ok(Some(paths))) = receiver.await else {
+                let _ = entity.update(cx, |this, cx| {
+                    this.data_busy = false;
+                    cx.notify();
+                });
+                return;
+            };
+            let Some(path) = paths.into_iter().next() else {
+                return;
+            };
+
+            let result = cx
+                .background_executor()
+                .spawn(async move {
+                    let archive = std::fs::read_to_string(&path)
+                        .map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+                    let data = backup::decrypt(&archive, &passphrase)?;
+                    let restored = backup::restore(&data)?;
+                    Ok::<_, String>((data, restored))
+                })
+                .await;
+
+            let _ = cx.update_window(window_handle, |_, window, cx| {
+                let _ = entity.update(cx, |this, cx| match result {
+                    Ok((data, restored)) => {
+                        if let Some(settings) = data.settings {
+                            let log_dir = settings
+                                .log_dir
+                                .as_ref()
+                                .map(|dir| dir.display().to_string())
+                                .unwrap_or_default();
+                            this.log_dir_input
+                                .update(cx, |input, cx| input.set_value(log_dir, window, cx));
+                            this.settings = settings;
+                        }
+                        let msg = if restored.is_empty() {
+                            "The archive was empty; nothing was imported.".to_string()
+                        } else {
+                            format!(
+                                "Imported: {}. Restart PicoForge to apply imported settings.",
+                                restored.join(", ")
+                            )
+                        };
+                        this.finish_data_task(Ok(msg), cx);
+                    }
+                    Err(e) => this.finish_data_task(Err(e), cx),
+                });
+            });
+        }));
     }
 }
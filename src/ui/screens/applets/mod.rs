@@ -0,0 +1,5 @@
+//! Applet screens — OpenPGP and OATH presence and pointers to their tools.
+
+pub mod view;
+pub mod view_model;
+pub use view_model::{AppletKind, AppletViewModel};
@@ -0,0 +1,91 @@
+use crate::ui::components::{card::Card, page_view::PageView, tag::Tag};
+use crate::ui::screens::applets::view_model::{AppletKind, AppletViewModel};
+use gpui::*;
+use gpui_component::{ActiveTheme, Icon, h_flex, v_flex};
+
+impl AppletViewModel {
+    fn tools(&self) -> (&'static str, &'static [(&'static str, &'static str)]) {
+        match self.kind {
+            AppletKind::OpenPgp => (
+                "PicoForge does not manage OpenPGP keys. Use GnuPG or another OpenPGP card tool:",
+                &[
+                    ("Show card status", "gpg --card-status"),
+                    ("Edit card settings", "gpg --card-edit"),
+                    (
+                        "Move a key to the card",
+                        "gpg --edit-key <KEYID>, then keytocard",
+                    ),
+                ],
+            ),
+            AppletKind::Oath => (
+                "PicoForge does not manage OATH accounts. Use a Yubico-compatible OATH client:",
+                &[
+                    ("List accounts", "ykman oath accounts list"),
+                    ("Add an account", "ykman oath accounts add <NAME> <SECRET>"),
+                    ("Generate a code", "ykman oath accounts code <NAME>"),
+                ],
+            ),
+        }
+    }
+}
+
+impl Render for AppletViewModel {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let responds = self.responds(cx);
+        let theme = cx.theme();
+        let (intro, tools) = self.tools();
+
+        let commands = v_flex().gap_2().children(tools.iter().map(|(label, cmd)| {
+            h_flex()
+                .justify_between()
+                .items_center()
+                .gap_4()
+                .child(div().text_sm().child(*label))
+                .child(
+                    div()
+                        .text_xs()
+                        .font_family("monospace")
+                        .bg(theme.muted)
+                        .px_2()
+                        .py_1()
+                        .rounded_md()
+                        .child(*cmd),
+                )
+        }));
+
+        PageView::build(
+            self.kind.title(),
+            format!("{} applet on the connected device.", self.kind.title()),
+            Card::new()
+                .title("Applet")
+                .description("Detected by selecting the applet over PC/SC.")
+                .icon(Icon::default().path("icons/microchip.svg"))
+                .child(
+                    v_flex()
+                        .gap_4()
+                        .child(
+                            h_flex()
+                                .gap_2()
+                                .items_center()
+                                .child(div().text_sm().child("Status"))
+                                .child(
+                                    Tag::new(if responds {
+                                        "Responding"
+                                    } else {
+                                        "Not responding"
+                                    })
+                                    .active(responds),
+                                ),
+                        )
+                        .child(
+                            div()
+                                .text_sm()
+                                .text_color(theme.muted_foreground)
+                                .child(intro),
+                        )
+                        .child(commands),
+                ),
+            theme,
+        )
+    }
+}
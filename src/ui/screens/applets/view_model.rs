@@ -0,0 +1,50 @@
+//! View model for the applet screens — which applet, and whether it still responds.
+
+use crate::ui::app::AppModels;
+use crate::ui::models::device::{DeviceEvent, DeviceRepo};
+use gpui::*;
+
+/// Optional smart-card applet shown in the sidebar when it responds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AppletKind {
+    OpenPgp,
+    Oath,
+}
+
+impl AppletKind {
+    pub fn title(self) -> &'static str {
+        match self {
+            AppletKind::OpenPgp => "OpenPGP",
+            AppletKind::Oath => "OATH",
+        }
+    }
+}
+
+/// Read-only view of an applet the device exposes.
+pub struct AppletViewModel {
+    pub(super) kind: AppletKind,
+    pub(super) device: Entity<DeviceRepo>,
+}
+
+impl AppletViewModel {
+    pub fn new(
+        kind: AppletKind,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+        models: &AppModels,
+    ) -> Self {
+        let device = models.device.clone();
+        cx.subscribe(&device, |_, _, _: &DeviceEvent, cx| cx.notify())
+            .detach();
+        Self { kind, device }
+    }
+
+    /// Whether the applet answered during the last device poll.
+    pub(super) fn responds(&self, cx: &App) -> bool {
+        let caps = self.device.read(cx).capabilities();
+        match self.kind {
+            AppletKind::OpenPgp => caps.openpgp,
+            AppletKind::Oath => caps.oath,
+        }
+    }
+}
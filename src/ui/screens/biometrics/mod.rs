@@ -0,0 +1,5 @@
+//! Biometrics screen — fingerprint enrollment, listing, and removal.
+
+pub mod view;
+pub mod view_model;
+pub use view_model::BiometricsViewModel;
@@ -0,0 +1,280 @@
+use crate::ui::components::{button::PFIconButton, card::Card, page_view::PageView};
+use crate::ui::screens::biometrics::view_model::BiometricsViewModel;
+use gpui::prelude::FluentBuilder;
+use gpui::*;
+use gpui_component::button::{Button, ButtonVariants};
+use gpui_component::{
+    ActiveTheme, Disableable, Icon, Sizable, StyledExt, badge::Badge, h_flex, input::Input, v_flex,
+};
+
+impl Render for BiometricsViewModel {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let content = v_flex()
+            .gap_6()
+            .w_full()
+            .child(self.render_sensor(cx))
+            .child(if self.unlocked {
+                self.render_unlocked_state(cx).into_any_element()
+            } else {
+                self.render_locked_state(cx).into_any_element()
+            });
+
+        PageView::build(
+            "Biometrics",
+            "Enroll and remove fingerprints on your key.",
+            content,
+            cx.theme(),
+        )
+    }
+}
+
+impl BiometricsViewModel {
+    fn render_sensor(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+        let text = match &self.sensor {
+            None => "Reading sensor information...".to_string(),
+            Some(info) => {
+                let kind = match info.fingerprint_kind {
+                    Some(1) => "Touch sensor",
+                    Some(2) => "Swipe sensor",
+                    _ => "Fingerprint sensor",
+                };
+                match info.max_samples {
+                    Some(samples) => format!("{}, {} good samples per enrollment", kind, samples),
+                    None => kind.to_string(),
+                }
+            }
+        };
+
+        h_flex()
+            .gap_2()
+            .items_center()
+            .text_sm()
+            .text_color(theme.muted_foreground)
+            .child(Icon::default().path("icons/fingerprint.svg").size_4())
+            .child(text)
+    }
+
+    fn render_locked_state(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let listener = cx.listener(|this, _, window, cx| {
+            this.open_unlock_dialog(window, cx);
+        });
+        let theme = cx.theme();
+
+        Card::new()
+            .title("Fingerprints")
+            .icon(Icon::default().path("icons/fingerprint.svg"))
+            .description("Fingerprints enrolled on this key")
+            .child(
+                v_flex()
+                    .items_center()
+                    .justify_center()
+                    .gap_3()
+                    .py_3()
+                    .child(
+                        div().rounded_full().bg(theme.muted).p_4().child(
+                            Icon::default()
+                                .path("icons/shield.svg")
+                                .size_12()
+                                .text_color(theme.muted_foreground),
+                        ),
+                    )
+                    .child(
+                        div()
+                            .text_lg()
+                            .font_semibold()
+                            .child("Authentication Required"),
+                    )
+                    .child(
+                        div()
+                            .text_color(theme.muted_foreground)
+                            .text_sm()
+                            .child("Enter your PIN to view, enroll and remove fingerprints."),
+                    )
+                    .child(
+                        PFIconButton::new(Icon::default().path("icons/lock-open.svg"), "Unlock")
+                            .on_click(listener)
+                            .with_colors(rgb(0xe4e4e7), rgb(0xd0d0d3), rgb(0xe4e4e7))
+                            .with_text_color(rgb(0x18181b)),
+                    ),
+            )
+    }
+
+    fn render_unlocked_state(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+        let enrolling = self.enrolling;
+        let count = self.templates.len();
+
+        let rows: Vec<AnyElement> = self
+            .templates
+            .iter()
+            .enumerate()
+            .map(|(ix, template)| {
+                let template_for_remove = template.clone();
+                h_flex()
+                    .justify_between()
+                    .items_center()
+                    .p_3()
+                    .border_1()
+                    .border_color(theme.border)
+                    .rounded_lg()
+                    .child(
+                        v_flex()
+                            .child(div().font_medium().child(if template.name.is_empty() {
+                                "Unnamed fingerprint".to_string()
+                            } else {
+                                template.name.clone()
+                            }))
+                            .child(
+                                div()
+                                    .text_xs()
+                                    .font_family("monospace")
+                                    .text_color(theme.muted_foreground)
+                                    .child(template.template_id.clone()),
+                            ),
+                    )
+                    .child(
+                        Button::new(("bio-remove", ix))
+                            .ghost()
+                            .small()
+                            .icon(Icon::default().path("icons/trash-2.svg"))
+                            .disabled(enrolling || self.loading)
+                            .on_click(cx.listener(move |this, _, window, cx| {
+                                this.open_remove_dialog(&template_for_remove, window, cx);
+                            })),
+                    )
+                    .into_any_element()
+            })
+            .collect();
+
+        let progress = self.last_sample.as_ref().map(|sample| {
+            let total = self.sensor.as_ref().and_then(|s| s.max_samples);
+            let counter = match total {
+                Some(total) if total >= sample.remaining => {
+                    format!("Sample {} of {}", total - sample.remaining, total)
+                }
+                _ => format!("{} samples remaining", sample.remaining),
+            };
+            v_flex()
+                .gap_1()
+                .p_3()
+                .bg(theme.muted)
+                .rounded_md()
+                .child(div().text_sm().font_medium().child(counter))
+                .child(div().text_sm().text_color(theme.muted_foreground).child(
+                    if sample.remaining == 0 {
+                        "Done. Saving..."
+                    } else {
+                        Self::sample_hint(sample)
+                    },
+                ))
+        });
+
+        let result = self.result.clone().map(|result| {
+            let (msg, color) = match result {
+                Ok(msg) => (msg, theme.muted_foreground),
+                Err(msg) => (msg, theme.danger),
+            };
+            div().text_sm().text_color(color).child(msg)
+        });
+
+        Card::new()
+            .title("Fingerprints")
+            .icon(Icon::default().path("icons/fingerprint.svg"))
+            .description("Fingerprints enrolled on this key")
+            .child(
+                v_flex()
+                    .gap_4()
+                    .child(
+                        h_flex()
+                            .justify_between()
+                            .items_center()
+                            .child(
+                                h_flex()
+                                    .gap_4()
+                                    .items_center()
+                                    .child(
+                                        Badge::new()
+                                            .child(
+                                                h_flex()
+                                                    .gap_1()
+                                                    .items_center()
+                                                    .child(
+                                                        Icon::default()
+                                                            .path("icons/lock-open.svg")
+                                                            .size_3p5(),
+                                                    )
+                                                    .child("Unlocked"),
+                                            )
+                                            .color(gpui::green()),
+                                    )
+                                    .child(div().w_px().h_4().bg(theme.border))
+                                    .child(
+                                        div()
+                                            .text_sm()
+                                            .text_color(theme.muted_foreground)
+                                            .child(format!("{} fingerprints enrolled", count)),
+                                    ),
+                            )
+                            .child(
+                                Button::new("bio-lock")
+                                    .ghost()
+                                    .label("Lock")
+                                    .disabled(enrolling)
+                                    .on_click(cx.listener(|this, _, _, cx| this.lock(cx))),
+                            ),
+                    )
+                    .when(rows.is_empty(), |this| {
+                        this.child(
+                            div()
+                                .text_sm()
+                                .text_color(theme.muted_foreground)
+                                .child("No fingerprints enrolled yet."),
+                        )
+                    })
+                    .children(rows)
+                    .when_some(self.error.clone(), |this, error| {
+                        this.child(div().text_sm().text_color(theme.danger).child(error))
+                    })
+                    .child(div().h_px().bg(theme.border))
+                    .child(div().font_medium().child("Add Fingerprint"))
+                    .child(
+                        div().text_sm().text_color(theme.muted_foreground).child(
+                            "Touch the sensor when asked and lift your finger between samples.",
+                        ),
+                    )
+                    .child(Input::new(&self.name_input).disabled(enrolling))
+                    .children(progress)
+                    .children(result)
+                    .child(
+                        h_flex()
+                            .gap_2()
+                            .justify_end()
+                            .when(enrolling, |this| {
+                                this.child(
+                                    Button::new("bio-cancel")
+                                        .label(if self.cancel_requested {
+                                            "Cancelling..."
+                                        } else {
+                                            "Cancel"
+                                        })
+                                        .disabled(self.cancel_requested)
+                                        .on_click(cx.listener(|this, _, _, cx| {
+                                            this.cancel_enrollment(cx);
+                                        })),
+                                )
+                            })
+                            .child(
+                                Button::new("bio-enroll")
+                                    .primary()
+                                    .label("Enroll Fingerprint")
+                                    .loading(enrolling)
+                                    .disabled(enrolling || self.loading)
+                                    .on_click(cx.listener(|this, _, _, cx| {
+                                        this.start_enrollment(cx);
+                                    })),
+                            ),
+                    ),
+            )
+    }
+}
@@ -0,0 +1,376 @@
+//! View model for the biometrics screen — fingerprint enrollment and removal.
+
+use crate::logging::ActionId;
+use crate::ui::app::AppModels;
+use crate::ui::components::dialog;
+use crate::ui::components::dialog::{ConfirmContent, PinPromptContent};
+use crate::ui::models::device::{
+    BioEnrollSample, BioSampleStatus, BioSensorInfo, BioTemplate, DeviceRepo,
+};
+use gpui::*;
+use gpui_component::input::InputState;
+
+/// Enrolled fingerprints and the enrollment in progress.
+pub struct BiometricsViewModel {
+    pub(super) unlocked: bool,
+    cached_pin: Option<String>,
+    pub(super) templates: Vec<BioTemplate>,
+    /// Read on open; needs no PIN.
+    pub(super) sensor: Option<BioSensorInfo>,
+    pub(super) loading: bool,
+    pub(super) error: Option<String>,
+    /// Name for the next enrolled fingerprint.
+    pub(super) name_input: Entity<InputState>,
+    pub(super) enrolling: bool,
+    /// Set by Cancel; honoured once the sample in flight returns.
+    pub(super) cancel_requested: bool,
+    pub(super) last_sample: Option<BioEnrollSample>,
+    /// Result of the last enrollment, removal or cancel.
+    pub(super) result: Option<Result<String, String>>,
+    _task: Option<Task<()>>,
+    _enroll_task: Option<Task<()>>,
+}
+
+impl BiometricsViewModel {
+    /// Dropped and recreated by the app root when another key is connected;
+    /// an enrollment in progress is abandoned and the key times it out.
+    pub fn new(window: &mut Window, cx: &mut Context<Self>, _models: &AppModels) -> Self {
+        let name_input =
+            cx.new(|cx| InputState::new(window, cx).placeholder("Name, e.g. Right index"));
+
+        let mut this = Self {
+            unlocked: false,
+            cached_pin: None,
+            templates: Vec::new(),
+            sensor: None,
+            loading: false,
+            error: None,
+            name_input,
+            enrolling: false,
+            cancel_requested: false,
+            last_sample: None,
+            result: None,
+            _task: None,
+            _enroll_task: None,
+        };
+        this.read_sensor_info(cx);
+        this
+    }
+
+    fn read_sensor_info(&mut self, cx: &mut Context<Self>) {
+        let weak_self = cx.entity().downgrade();
+        self._task = Some(cx.spawn(async move |_, cx| {
+            let result = cx
+                .background_executor()
+                .spawn(async move { DeviceRepo::read_bio_sensor_info_blocking() })
+                .await;
+
+            let _ = weak_self.update(cx, |this, cx| {
+                match result {
+                    Ok(info) => this.sensor = Some(info),
+                    Err(e) => log::warn!("Could not read fingerprint sensor info: {}", e),
+                }
+                cx.notify();
+            });
+        }));
+    }
+
+    pub(super) fn open_unlock_dialog(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let view_handle = cx.entity().downgrade();
+
+        dialog::open_pin_prompt(
+            "Unlock Biometrics",
+            "Enter your device PIN to manage fingerprints",
+            None,
+            "Unlock",
+            window,
+            cx,
+            move |pin, dialog_handle, cx| {
+                let _ = view_handle.update(cx, |this, cx| {
+                    this.unlock(pin, dialog_handle, cx);
+                });
+            },
+        );
+    }
+
+    fn unlock(
+        &mut self,
+        pin: String,
+        dialog_handle: WeakEntity<PinPromptContent>,
+        cx: &mut Context<Self>,
+    ) {
+        if self.loading {
+            return;
+        }
+        self.loading = true;
+        cx.notify();
+
+        let weak_self = cx.entity().downgrade();
+        self._task = Some(cx.spawn(async move |_, cx| {
+            let pin_for_bg = pin.clone();
+            let result = cx
+                .background_executor()
+                .spawn(async move { DeviceRepo::list_fingerprints_blocking(pin_for_bg) })
+                .await;
+
+            let _ = weak_self.update(cx, |this, cx| {
+                this.loading = false;
+                match result {
+                    Ok(templates) => {
+                        log::info!("{} fingerprints enrolled", templates.len());
+                        this.unlocked = true;
+                        this.cached_pin = Some(pin);
+                        this.templates = templates;
+                        let _ = dialog_handle.update(cx, |d, cx| {
+                            d.set_success("Biometrics unlocked.".to_string(), cx);
+                        });
+                    }
+                    Err(e) => {
+                        log::error!("Failed to list fingerprints: {}", e);
+                        let _ = dialog_handle.update(cx, |d, cx| {
+                            d.set_error(format!("Failed to unlock: {}", e), cx);
+                        });
+                    }
+                }
+                cx.notify();
+            });
+        }));
+    }
+
+    pub(super) fn lock(&mut self, cx: &mut Context<Self>) {
+        self.unlocked = false;
+        self.cached_pin = None;
+        self.templates.clear();
+        self.result = None;
+        self.cancel_requested = self.enrolling;
+        cx.notify();
+    }
+
+    fn refresh_templates(&mut self, cx: &mut Context<Self>) {
+        let Some(pin) = self.cached_pin.clone() else {
+            return;
+        };
+        let weak_self = cx.entity().downgrade();
+        self._task = Some(cx.spawn(async move |_, cx| {
+            let result = cx
+                .background_executor()
+                .spawn(async move { DeviceRepo::list_fingerprints_blocking(pin) })
+                .await;
+
+            let _ = weak_self.update(cx, |this, cx| {
+                match result {
+                    Ok(templates) => this.templates = templates,
+                    Err(e) => this.error = Some(e),
+                }
+                cx.notify();
+            });
+        }));
+    }
+
+    /// Enroll a new fingerprint. Samples are captured one after another until
+    /// the key needs no more, with the status of each shown as it comes in.
+    pub(super) fn start_enrollment(&mut self, cx: &mut Context<Self>) {
+        let Some(pin) = self.cached_pin.clone() else {
+            return;
+        };
+        if self.enrolling {
+            return;
+        }
+        let name = self.name_input.read(cx).value().to_string();
+        self.enrolling = true;
+        self.cancel_requested = false;
+        self.last_sample = None;
+        self.result = None;
+        self.error = None;
+        cx.notify();
+
+        let action = ActionId::begin("bio.enroll");
+        action.scope(|| log::info!("Starting fingerprint enrollment..."));
+        let weak_self = cx.entity().downgrade();
+
+        self._enroll_task = Some(cx.spawn(async move |_, cx| {
+            let act = action.clone();
+            let begun = cx
+                .background_executor()
+                .spawn(async move {
+                    act.scope(|| DeviceRepo::begin_fingerprint_enrollment_blocking(pin))
+                })
+                .await;
+            let (mut session, mut sample) = match begun {
+                Ok(begun) => begun,
+                Err(e) => {
+                    let _ = weak_self.update(cx, |this, cx| this.finish_enrollment(Err(e), cx));
+                    return;
+                }
+            };
+
+            loop {
+                let cancel = weak_self
+                    .update(cx, |this, cx| {
+                        this.last_sample = Some(sample.clone());
+                        cx.notify();
+                        this.cancel_requested
+                    })
+                    .unwrap_or(true);
+
+                if cancel {
+                    let act = action.clone();
+                    let result = cx
+                        .background_executor()
+                        .spawn(async move {
+                            act.scope(|| {
+                                DeviceRepo::cancel_fingerprint_enrollment_blocking(session)
+                            })
+                        })
+                        .await
+                        .map(|_| "Enrollment cancelled.".to_string());
+                    let _ = weak_self.update(cx, |this, cx| this.finish_enrollment(result, cx));
+                    return;
+                }
+
+                if sample.remaining == 0 {
+                    let act = action.clone();
+                    let name = name.clone();
+                    let result = cx
+                        .background_executor()
+                        .spawn(async move {
+                            act.scope(|| {
+                                DeviceRepo::finish_fingerprint_enrollment_blocking(session, name)
+                            })
+                        })
+                        .await
+                        .map(|template| match template.name.as_str() {
+                            "" => "Fingerprint enrolled.".to_string(),
+                            name => format!("Fingerprint \"{}\" enrolled.", name),
+                        });
+                    let _ = weak_self.update(cx, |this, cx| {
+                        this.finish_enrollment(result, cx);
+                        this.refresh_templates(cx);
+                    });
+                    return;
+                }
+
+                let act = action.clone();
+                let next = cx
+                    .background_executor()
+                    .spawn(async move {
+                        act.scope(|| DeviceRepo::capture_fingerprint_sample_blocking(session))
+                    })
+                    .await;
+                match next {
+                    Ok(next) => (session, sample) = next,
+                    Err(e) => {
+                        let _ = weak_self.update(cx, |this, cx| this.finish_enrollment(Err(e), cx));
+                        return;
+                    }
+                }
+            }
+        }));
+    }
+
+    pub(super) fn cancel_enrollment(&mut self, cx: &mut Context<Self>) {
+        if self.enrolling {
+            self.cancel_requested = true;
+            cx.notify();
+        }
+    }
+
+    fn finish_enrollment(&mut self, result: Result<String, String>, cx: &mut Context<Self>) {
+        match &result {
+            Ok(msg) => log::info!("{}", msg),
+            Err(e) => log::error!("Fingerprint enrollment failed: {}", e),
+        }
+        self.enrolling = false;
+        self.cancel_requested = false;
+        self.last_sample = None;
+        self.result = Some(result);
+        cx.notify();
+    }
+
+    /// What to tell the user about the sample that just came in.
+    pub(super) fn sample_hint(sample: &BioEnrollSample) -> &'static str {
+        match BioSampleStatus::from_u8(sample.status) {
+            Some(status) => status.hint(),
+            None => "Unexpected sample status. Touch the sensor again.",
+        }
+    }
+
+    pub(super) fn open_remove_dialog(
+        &mut self,
+        template: &BioTemplate,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(pin) = self.cached_pin.clone() else {
+            return;
+        };
+        let template_id = template.template_id.clone();
+        let label = if template.name.is_empty() {
+            format!("fingerprint {}", template.template_id)
+        } else {
+            format!("\"{}\"", template.name)
+        };
+        let view_handle = cx.entity().downgrade();
+
+        dialog::open_confirm(
+            "Remove Fingerprint",
+            format!("Remove {} from this key?", label),
+            "Remove",
+            gpui_component::button::ButtonVariant::Danger,
+            window,
+            cx,
+            move |dialog_handle, _, cx| {
+                let _ = view_handle.update(cx, |this, cx| {
+                    this.remove(pin.clone(), template_id.clone(), dialog_handle, cx);
+                });
+            },
+        );
+    }
+
+    fn remove(
+        &mut self,
+        pin: String,
+        template_id: String,
+        dialog_handle: WeakEntity<ConfirmContent>,
+        cx: &mut Context<Self>,
+    ) {
+        if self.loading {
+            return;
+        }
+        self.loading = true;
+        cx.notify();
+
+        let action = ActionId::begin("bio.remove");
+        action.scope(|| log::info!("Removing fingerprint..."));
+        let weak_self = cx.entity().downgrade();
+
+        self._task = Some(cx.spawn(async move |_, cx| {
+            let act = action.clone();
+            let result = cx
+                .background_executor()
+                .spawn(async move {
+                    act.scope(|| DeviceRepo::remove_fingerprint_blocking(pin, template_id))
+                })
+                .await;
+
+            let _ = weak_self.update(cx, |this, cx| {
+                this.loading = false;
+                action.scope(|| match result {
+                    Ok(()) => {
+                        let _ = dialog_handle.update(cx, |d, cx| {
+                            d.set_success("Fingerprint removed.".to_string(), cx);
+                        });
+                        this.refresh_templates(cx);
+                    }
+                    Err(e) => {
+                        log::error!("Error removing fingerprint: {}", e);
+                        let _ = dialog_handle.update(cx, |d, cx| {
+                            d.set_error(format!("Error removing: {}", e), cx);
+                        });
+                    }
+                });
+                cx.notify();
+            });
+        }));
+    }
+}
@@ -1,28 +1,123 @@
-use crate::ui::components::{card::Card, page_view::PageView};
+use crate::config_defaults::{self, ConfigSection};
+use crate::impact;
+use crate::ui::components::{
+    card::Card,
+    page_view::PageView,
+    tag::Tag,
+    view_state::{self, EmptyState},
+};
 use crate::ui::models::device::{
     DeviceMethod, FirmwareType, LedColor, LedStatus, USB_CAP_FIDO2, USB_CAP_OATH, USB_CAP_OPENPGP,
-    USB_CAP_OTP, USB_CAP_PIV, USB_CAP_U2F,
+    USB_CAP_OTP, USB_CAP_PIV, USB_CAP_U2F, VendorConfigSupport,
 };
 use crate::ui::screens::config::view_model::ConfigViewModel;
+use gpui::prelude::FluentBuilder;
 use gpui::*;
 use gpui_component::{button::*, input::*, select::*, slider::*, switch::*, *};
 
+/// `control`, with a tooltip naming `command` when the firmware does not
+/// advertise it (the caller disables the control).
+fn gated(
+    id: &'static str,
+    control: impl IntoElement,
+    supported: bool,
+    command: &'static str,
+) -> impl IntoElement {
+    div().id(id).child(control).when(!supported, |this| {
+        this.tooltip(move |window, cx| {
+            Tooltip::new(format!(
+                "This firmware does not advertise the {} vendor command",
+                command
+            ))
+            .build(window, cx)
+        })
+    })
+}
+
 impl ConfigViewModel {
-    fn render_identity_card(
+    /// Header badge and reset button for a card, plus the list of its
+    /// settings that differ from the firmware defaults.
+    fn render_section_defaults(
         &self,
-        theme: &Theme,
+        section: ConfigSection,
+        cx: &mut Context<Self>,
+    ) -> (AnyElement, Option<AnyElement>) {
+        let deviations: Vec<_> = self
+            .device
+            .read(cx)
+            .status
+            .as_ref()
+            .map(|s| config_defaults::deviations(&s.config))
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|d| d.section == section)
+            .collect();
+        if deviations.is_empty() {
+            return (Tag::new("Defaults").active(true).into_any_element(), None);
+        }
+
+        let theme = cx.theme();
+        let header = h_flex()
+            .gap_2()
+            .items_center()
+            .child(Tag::new(format!("{} changed", deviations.len())))
+            .child(
+                Button::new(SharedString::from(format!("reset-{:?}", section)))
+                    .ghost()
+                    .xsmall()
+                    .label("Reset section to defaults")
+                    .disabled(self.loading)
+                    .tooltip("Fill in the firmware defaults; Apply Changes writes them")
+                    .on_click(cx.listener(move |this, _, window, cx| {
+                        this.reset_section(section, window, cx)
+                    })),
+            )
+            .into_any_element();
+        let notice = v_flex()
+            .gap_1()
+            .text_sm()
+            .children(deviations.into_iter().map(|d| {
+                div().text_color(theme.warning).child(format!(
+                    "{}: {} (default: {})",
+                    d.field, d.current, d.default
+                ))
+            }))
+            .into_any_element();
+        (header, Some(notice))
+    }
+
+    fn render_identity_card(
+        &mut self,
+        cx: &mut Context<Self>,
         is_fido: bool,
         hardware_config_disabled: bool,
+        support: VendorConfigSupport,
     ) -> impl IntoElement {
+        let protection_listener = cx.listener(|this, checked, window, cx| {
+            this.set_identity_protection(*checked, window, cx);
+        });
+
+        let (defaults_header, defaults_notice) =
+            self.render_section_defaults(ConfigSection::Identity, cx);
+        let conflict = impact::driver_conflict(
+            &self.vid_input.read(cx).text().to_string(),
+            &self.pid_input.read(cx).text().to_string(),
+        );
+        let vid_pid_disabled = hardware_config_disabled || !support.vid_pid;
+        let theme = cx.theme();
+
         let content = v_flex()
             .gap_4()
             .child(
-                v_flex().gap_2().child("Vendor Preset").child(
+                v_flex().gap_2().child("Vendor Preset").child(gated(
+                    "vendor-preset-gate",
                     Select::new(&self.vendor_select)
                         .bg(rgb(0x222225))
                         .w_full()
-                        .disabled(hardware_config_disabled),
-                ),
+                        .disabled(vid_pid_disabled),
+                    support.vid_pid,
+                    "PhysicalVidPid",
+                )),
             )
             .child(
                 div()
@@ -30,35 +125,79 @@ impl ConfigViewModel {
                     .grid_cols(2)
                     .gap_4()
                     .child(
-                        v_flex().gap_2().child("Vendor ID (HEX)").child(
+                        v_flex().gap_2().child("Vendor ID (HEX)").child(gated(
+                            "vid-gate",
                             Input::new(&self.vid_input)
                                 .font_family("Mono")
                                 .bg(rgb(0x222225))
-                                .disabled(hardware_config_disabled || !self.is_custom_vendor),
-                        ),
+                                .disabled(vid_pid_disabled || !self.is_custom_vendor),
+                            support.vid_pid,
+                            "PhysicalVidPid",
+                        )),
                     )
                     .child(
-                        v_flex().gap_2().child("Product ID (HEX)").child(
+                        v_flex().gap_2().child("Product ID (HEX)").child(gated(
+                            "pid-gate",
                             Input::new(&self.pid_input)
                                 .font_family("Mono")
                                 .bg(rgb(0x222225))
-                                .disabled(hardware_config_disabled || !self.is_custom_vendor),
-                        ),
+                                .disabled(vid_pid_disabled || !self.is_custom_vendor),
+                            support.vid_pid,
+                            "PhysicalVidPid",
+                        )),
                     ),
             )
+            .children(conflict.map(|conflict| {
+                div().text_sm().text_color(theme.warning).child(format!(
+                    "Known driver conflict: {}. Applying it can leave the key unusable \
+                         until it is fixed from rescue mode.",
+                    conflict.reason
+                ))
+            }))
             .child(div().h_px().bg(theme.border))
             .child(
-                v_flex().gap_2().child("Product Name").child(
-                    Input::new(&self.product_name_input)
-                        .bg(rgb(0x222225))
-                        .disabled(is_fido),
-                ),
+                v_flex()
+                    .gap_2()
+                    .child("Product Name")
+                    .child(
+                        Input::new(&self.product_name_input)
+                            .bg(rgb(0x222225))
+                            .disabled(is_fido),
+                    )
+                    .when(is_fido, |this| {
+                        this.child(div().text_sm().text_color(theme.muted_foreground).child(
+                            "Not supported over FIDO on this firmware. \
+                             Connect in rescue mode to change it.",
+                        ))
+                    }),
+            )
+            .child(div().h_px().bg(theme.border))
+            .child(
+                h_flex()
+                    .items_center()
+                    .justify_between()
+                    .child(
+                        v_flex().gap_0p5().child("Confirm Identity Changes").child(
+                            div()
+                                .text_sm()
+                                .text_color(theme.muted_foreground)
+                                .child("Require a touch and PIN (a confirmation over Rescue) before VID/PID writes"),
+                        ),
+                    )
+                    .child(
+                        Switch::new("identity-protection")
+                            .checked(self.identity_protection)
+                            .disabled(vid_pid_disabled)
+                            .on_click(protection_listener),
+                    ),
             );
 
         Card::new()
             .title("Identity")
             .description("USB Identification settings")
             .icon(Icon::default().path("icons/tag.svg"))
+            .header_right(defaults_header)
+            .children(defaults_notice)
             .child(content)
     }
 
@@ -67,6 +206,7 @@ impl ConfigViewModel {
         cx: &mut Context<Self>,
         is_fido: bool,
         hardware_config_disabled: bool,
+        support: VendorConfigSupport,
     ) -> impl IntoElement {
         let dim_listener = cx.listener(|this, checked, _, cx| {
             this.led_dimmable = *checked;
@@ -78,9 +218,13 @@ impl ConfigViewModel {
             cx.notify();
         });
 
+        let (defaults_header, defaults_notice) =
+            self.render_section_defaults(ConfigSection::Led, cx);
         let theme = cx.theme();
 
         let brightness = self.led_brightness_slider.read(cx).value().start() as i32;
+        let options_disabled =
+            hardware_config_disabled || !self.options_known || !support.physical_options;
 
         let content = v_flex()
             .gap_4()
@@ -89,19 +233,35 @@ impl ConfigViewModel {
                     .gap_4()
                     .flex_wrap()
                     .child(
-                        v_flex().gap_2().flex_1().child("LED GPIO Pin").child(
+                        v_flex().gap_2().flex_1().child("LED GPIO Pin").child(gated(
+                            "led-gpio-gate",
                             Input::new(&self.led_gpio_input)
                                 .bg(rgb(0x222225))
-                                .disabled(hardware_config_disabled),
-                        ),
+                                .disabled(hardware_config_disabled || !support.led_gpio),
+                            support.led_gpio,
+                            "PhysicalLedGpio",
+                        )),
                     )
                     .child(
-                        v_flex().gap_2().flex_1().child("LED Driver").child(
-                            Select::new(&self.led_driver_select)
-                                .w_full()
-                                .bg(rgb(0x222225))
-                                .disabled(is_fido),
-                        ),
+                        v_flex()
+                            .gap_2()
+                            .flex_1()
+                            .child("LED Driver")
+                            .child(
+                                Select::new(&self.led_driver_select)
+                                    .w_full()
+                                    .bg(rgb(0x222225))
+                                    .placeholder("Firmware default")
+                                    .disabled(is_fido),
+                            )
+                            .when(is_fido, |this| {
+                                this.child(
+                                    div().text_sm().text_color(theme.muted_foreground).child(
+                                        "Not supported over FIDO on this firmware. \
+                                         Connect in rescue mode to change it.",
+                                    ),
+                                )
+                            }),
                     ),
             )
             .child(div().h_px().bg(theme.border))
@@ -111,9 +271,14 @@ impl ConfigViewModel {
                         .items_center()
                         .gap_4()
                         .child(
-                            Slider::new(&self.led_brightness_slider)
-                                .flex_1()
-                                .disabled(hardware_config_disabled),
+                            gated(
+                                "led-brightness-gate",
+                                Slider::new(&self.led_brightness_slider)
+                                    .disabled(hardware_config_disabled || !support.led_brightness),
+                                support.led_brightness,
+                                "PhysicalLedBrightness",
+                            )
+                            .flex_1(),
                         )
                         .child(
                             div()
@@ -135,12 +300,15 @@ impl ConfigViewModel {
                                 .child("Allow brightness adjustment"),
                         ),
                     )
-                    .child(
+                    .child(gated(
+                        "led-dimmable-gate",
                         Switch::new("led-dimmable")
                             .checked(self.led_dimmable)
-                            .disabled(hardware_config_disabled)
+                            .disabled(options_disabled)
                             .on_click(dim_listener),
-                    ),
+                        support.physical_options,
+                        "PhysicalOptions",
+                    )),
             )
             .child(
                 h_flex()
@@ -154,34 +322,63 @@ impl ConfigViewModel {
                                 .child("Keep LED on constantly"),
                         ),
                     )
-                    .child(
+                    .child(gated(
+                        "led-steady-gate",
                         Switch::new("led-steady")
                             .checked(self.led_steady)
-                            .disabled(hardware_config_disabled)
+                            .disabled(options_disabled)
                             .on_click(steady_listener),
-                    ),
+                        support.physical_options,
+                        "PhysicalOptions",
+                    )),
             );
 
         Card::new()
             .title("LED Settings")
             .description("Adjust visual feedback behavior")
             .icon(Icon::default().path("icons/microchip.svg"))
+            .header_right(defaults_header)
+            .children(defaults_notice)
             .child(content)
     }
 
-    fn render_touch_card(&self, _theme: &Theme, is_fido: bool) -> impl IntoElement {
+    fn render_touch_card(&self, cx: &mut Context<Self>, is_fido: bool) -> impl IntoElement {
+        let (defaults_header, defaults_notice) =
+            self.render_section_defaults(ConfigSection::Touch, cx);
+        let theme = cx.theme();
         let content = v_flex().gap_4().child(
-            v_flex().gap_2().child("Touch Timeout (seconds)").child(
-                Input::new(&self.touch_timeout_input)
-                    .bg(rgb(0x222225))
-                    .disabled(is_fido),
-            ),
+            v_flex()
+                .gap_2()
+                .child("Touch Timeout (seconds)")
+                .child(
+                    Input::new(&self.touch_timeout_input)
+                        .bg(rgb(0x222225))
+                        .disabled(is_fido),
+                )
+                .child(
+                    div()
+                        .text_sm()
+                        .text_color(theme.muted_foreground)
+                        .child(if is_fido {
+                            "Not supported over FIDO on this firmware. \
+                             Connect in rescue mode to change it."
+                                .to_string()
+                        } else {
+                            format!(
+                                "0 to {} seconds. Clear the field or enter 0 for the \
+                                 30 s firmware default.",
+                                config_defaults::MAX_TOUCH_TIMEOUT
+                            )
+                        }),
+                ),
         );
 
         Card::new()
             .title("Touch & Timing")
             .description("Configure interaction timeouts")
             .icon(Icon::default().path("icons/settings.svg"))
+            .header_right(defaults_header)
+            .children(defaults_notice)
             .child(content)
     }
 
@@ -189,41 +386,139 @@ impl ConfigViewModel {
         &mut self,
         cx: &mut Context<Self>,
         hardware_config_disabled: bool,
+        support: VendorConfigSupport,
     ) -> impl IntoElement {
         let power_cycle_listener = cx.listener(|this, checked, _, cx| {
             this.power_cycle = *checked;
             cx.notify();
         });
 
+        let device_power_cycle = self
+            .device
+            .read(cx)
+            .status
+            .as_ref()
+            .map(|s| s.config.power_cycle_on_reset);
+        let (defaults_header, defaults_notice) =
+            self.render_section_defaults(ConfigSection::Options, cx);
         let theme = cx.theme();
 
-        let content = v_flex().gap_4().child(
+        let mut content = v_flex().gap_4().child(
             h_flex()
                 .items_center()
                 .justify_between()
-                .child(
-                    v_flex().gap_0p5().child("Power Cycle on Reset").child(
-                        div()
-                            .text_sm()
-                            .text_color(theme.muted_foreground)
-                            .child("Restart device on reset"),
+                .gap_4()
+                .child(v_flex().gap_0p5().child("Power Cycle on Reset").child(
+                    div().text_sm().text_color(theme.muted_foreground).child(
+                        "Only accept a FIDO reset within a few seconds of plugging \
+                                 the key in",
                     ),
-                )
-                .child(
+                ))
+                .child(gated(
+                    "power-cycle-gate",
                     Switch::new("power-cycle")
                         .checked(self.power_cycle)
-                        .disabled(hardware_config_disabled)
+                        .disabled(
+                            hardware_config_disabled
+                                || !self.options_known
+                                || !support.physical_options,
+                        )
                         .on_click(power_cycle_listener),
-                ),
+                    support.physical_options,
+                    "PhysicalOptions",
+                )),
         );
 
+        if !self.options_known {
+            content = content.child(div().text_sm().text_color(theme.muted_foreground).child(
+                "The current options could not be read from the device, so they \
+                         are shown with default values and will not be written.",
+            ));
+        } else if device_power_cycle == Some(true) && !self.power_cycle {
+            content = content.child(div().text_sm().text_color(theme.danger).child(
+                "Turning this off lets anything that can talk to the key wipe \
+                         every credential at any time, needing only a touch. Keep it on \
+                         unless a tool you rely on cannot ask for a replug before resetting.",
+            ));
+        }
+
         Card::new()
             .title("Device Options")
             .description("Toggle advanced features")
             .icon(Icon::default().path("icons/settings.svg"))
+            .header_right(defaults_header)
+            .children(defaults_notice)
             .child(content)
     }
 
+    fn render_curves_card(&mut self, cx: &mut Context<Self>, is_fido: bool) -> impl IntoElement {
+        type Toggle = fn(&mut ConfigViewModel) -> &mut bool;
+        let curves: [(&'static str, &'static str, Toggle); 11] = [
+            ("curve-p256", "NIST P-256", |this| &mut this.curve_p256),
+            ("curve-p384", "NIST P-384", |this| &mut this.curve_p384),
+            ("curve-p521", "NIST P-521", |this| &mut this.curve_p521),
+            ("curve-secp256k1", "secp256k1", |this| {
+                &mut this.curve_secp256k1
+            }),
+            ("curve-bp256", "Brainpool P-256", |this| {
+                &mut this.curve_bp256
+            }),
+            ("curve-bp384", "Brainpool P-384", |this| {
+                &mut this.curve_bp384
+            }),
+            ("curve-bp512", "Brainpool P-512", |this| {
+                &mut this.curve_bp512
+            }),
+            ("curve-ed25519", "Ed25519", |this| &mut this.curve_ed25519),
+            ("curve-ed448", "Ed448", |this| &mut this.curve_ed448),
+            ("curve-x25519", "X25519", |this| &mut this.curve_x25519),
+            ("curve-x448", "X448", |this| &mut this.curve_x448),
+        ];
+
+        let switches: Vec<AnyElement> = curves
+            .into_iter()
+            .map(|(id, label, toggle)| {
+                let checked = *toggle(self);
+                h_flex()
+                    .w(px(200.))
+                    .child(
+                        Switch::new(id)
+                            .label(label)
+                            .checked(checked)
+                            .disabled(is_fido)
+                            .on_click(cx.listener(move |this, checked, _, cx| {
+                                *toggle(this) = *checked;
+                                cx.notify();
+                            })),
+                    )
+                    .into_any_element()
+            })
+            .collect();
+        let theme = cx.theme();
+
+        Card::new()
+            .title("Enabled Curves")
+            .description("Elliptic curves the firmware may use for new keys")
+            .icon(Icon::default().path("icons/key-round.svg"))
+            .child(
+                v_flex()
+                    .gap_4()
+                    .child(h_flex().flex_wrap().gap_4().children(switches))
+                    .child(
+                        div()
+                            .text_sm()
+                            .text_color(theme.muted_foreground)
+                            .child(if is_fido {
+                                "Not supported over FIDO on this firmware. \
+                             Connect in rescue mode to change them."
+                            } else {
+                                "secp256k1 is off by default; turn it on for wallets and \
+                             other tools that sign with it."
+                            }),
+                    ),
+            )
+    }
+
     fn render_rskey_led_card(&mut self, cx: &mut Context<Self>, is_fido: bool) -> impl IntoElement {
         let theme = cx.theme();
         let mut rows = v_flex().gap_4();
@@ -520,37 +815,119 @@ impl ConfigViewModel {
             .icon(Icon::default().path("icons/cpu.svg"))
             .child(rows)
     }
+
+    fn render_provisioning_card(&mut self, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+        let devices = self.session.devices();
+
+        let summary = match devices.len() {
+            0 => "No devices configured in this session yet.".to_string(),
+            1 => "1 device configured in this session.".to_string(),
+            n => format!("{} devices configured in this session.", n),
+        };
+        let firmware = match self.session.firmware_image() {
+            Some(image) => format!(
+                "Firmware image: {} ({}…)",
+                image.file_name,
+                &image.sha256[..16]
+            ),
+            None => "No firmware image attached.".to_string(),
+        };
+
+        let rows = devices.iter().map(|device| {
+            h_flex()
+                .justify_between()
+                .text_sm()
+                .child(div().font_medium().child(match &device.nickname {
+                    Some(nickname) => format!("{} ({})", nickname, device.serial),
+                    None => device.serial.clone(),
+                }))
+                .child(div().text_color(theme.muted_foreground).child(format!(
+                    "{} {}",
+                    device.firmware_type, device.firmware_version
+                )))
+        });
+
+        let session_result = self.session_result.clone().map(|result| {
+            let (msg, color) = match result {
+                Ok(msg) => (msg, theme.muted_foreground),
+                Err(msg) => (msg, theme.danger),
+            };
+            div().text_sm().text_color(color).child(msg)
+        });
+
+        Card::new()
+            .title("Provisioning Report")
+            .description("Export a signed record of every device configured in this session")
+            .icon(Icon::default().path("icons/file.svg"))
+            .child(
+                v_flex()
+                    .gap_3()
+                    .child(div().text_sm().child(summary))
+                    .child(
+                        div()
+                            .text_sm()
+                            .text_color(theme.muted_foreground)
+                            .child(firmware),
+                    )
+                    .child(v_flex().gap_1().children(rows))
+                    .children(session_result)
+                    .child(
+                        h_flex()
+                            .gap_2()
+                            .justify_end()
+                            .child(
+                                Button::new("session-reset")
+                                    .label("New Session")
+                                    .disabled(self.loading)
+                                    .on_click(cx.listener(|this, _, _, cx| {
+                                        this.reset_session(cx);
+                                    })),
+                            )
+                            .child(
+                                Button::new("session-firmware")
+                                    .label("Attach Firmware Image")
+                                    .disabled(self.loading)
+                                    .on_click(cx.listener(|this, _, _, cx| {
+                                        this.select_firmware_image(cx);
+                                    })),
+                            )
+                            .child(
+                                Button::new("session-export")
+                                    .primary()
+                                    .label("Export Report")
+                                    .disabled(self.loading || devices.is_empty())
+                                    .on_click(cx.listener(|this, _, _, cx| {
+                                        this.export_report(cx);
+                                    })),
+                            ),
+                    ),
+            )
+    }
 }
 
 impl Render for ConfigViewModel {
     fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
-        let has_device = self.device.read(cx).status.is_some();
+        let phase = self.device.read(cx).phase();
+        let no_device = EmptyState::new(
+            Icon::default().path("icons/hard-drive.svg"),
+            "No Device Connected",
+        )
+        .description("Connect your pico-key to change its configuration.");
 
-        if !has_device {
-            let theme = cx.theme();
+        if let Some(placeholder) = view_state::for_phase(&phase, no_device, &self.device) {
             return PageView::build(
                 "Configuration",
                 "Customize device settings and behavior.",
-                div()
-                    .flex()
-                    .items_center()
-                    .justify_center()
-                    .h_64()
-                    .border_1()
-                    .border_color(theme.border)
-                    .rounded_xl()
-                    .child(
-                        div()
-                            .text_color(theme.muted_foreground)
-                            .child("No Device Connected"),
-                    ),
-                theme,
+                placeholder,
+                cx.theme(),
             )
             .into_any_element();
         }
 
         let device = self.device.read(cx);
         let status = device.status.clone();
+        let support = device.capabilities().vendor_config;
         let is_fido = status.as_ref().map(|s| s.method.clone()) == Some(DeviceMethod::Fido);
         let is_rskey = status.as_ref().map(|s| &s.firmware_type) == Some(&FirmwareType::RSKey);
 
@@ -566,17 +943,17 @@ impl Render for ConfigViewModel {
         let is_fido_no_rskey = is_fido && !is_rskey;
 
         let led_card = self
-            .render_led_card(cx, is_fido_no_rskey, hardware_config_disabled)
+            .render_led_card(cx, is_fido_no_rskey, hardware_config_disabled, support)
             .into_any_element();
         let options_card = self
-            .render_options_card(cx, hardware_config_disabled)
+            .render_options_card(cx, hardware_config_disabled, support)
             .into_any_element();
 
         let identity_card = self
-            .render_identity_card(cx.theme(), is_fido_no_rskey, hardware_config_disabled)
+            .render_identity_card(cx, is_fido_no_rskey, hardware_config_disabled, support)
             .into_any_element();
         let touch_card = self
-            .render_touch_card(cx.theme(), is_fido_no_rskey)
+            .render_touch_card(cx, is_fido_no_rskey)
             .into_any_element();
 
         let mut inner = v_flex()
@@ -586,6 +963,10 @@ impl Render for ConfigViewModel {
             .child(touch_card)
             .child(options_card);
 
+        if !is_rskey {
+            inner = inner.child(self.render_curves_card(cx, is_fido).into_any_element());
+        }
+
         if is_rskey {
             // No curves card: RS-Key's firmware ignores the phy ENABLED_CURVES
             // tag (curve support is compile-time), so exposing it would only mislead.
@@ -596,24 +977,40 @@ impl Render for ConfigViewModel {
         }
 
         inner = inner.child(
-            h_flex().justify_end().pt_4().child(
-                Button::new("apply-changes")
-                    .icon(Icon::default().path("icons/save.svg"))
-                    .child("Apply Changes")
-                    .disabled(self.loading || hardware_config_disabled)
-                    .custom(
-                        ButtonCustomVariant::new(cx)
-                            .color(rgb(0xe3e3e6).into())
-                            .hover(rgb(0xcfcfd1).into())
-                            .active(rgb(0xe3e3e6).into())
-                            .foreground(rgb(0x4b4b4e).into()),
-                    )
-                    .on_click(cx.listener(|this, _, window, cx| {
-                        this.apply_changes(window, cx);
-                    })),
-            ),
+            h_flex()
+                .justify_end()
+                .gap_2()
+                .pt_4()
+                .child(
+                    Button::new("reset-all-defaults")
+                        .ghost()
+                        .label("Reset configuration to firmware defaults")
+                        .disabled(self.loading || hardware_config_disabled)
+                        .tooltip("LED pin, driver and brightness are kept as they are")
+                        .on_click(cx.listener(|this, _, window, cx| {
+                            this.reset_all_to_defaults(window, cx);
+                        })),
+                )
+                .child(
+                    Button::new("apply-changes")
+                        .icon(Icon::default().path("icons/save.svg"))
+                        .child("Apply Changes")
+                        .disabled(self.loading || hardware_config_disabled)
+                        .custom(
+                            ButtonCustomVariant::new(cx)
+                                .color(rgb(0xe3e3e6).into())
+                                .hover(rgb(0xcfcfd1).into())
+                                .active(rgb(0xe3e3e6).into())
+                                .foreground(rgb(0x4b4b4e).into()),
+                        )
+                        .on_click(cx.listener(|this, _, window, cx| {
+                            this.apply_changes(window, cx);
+                        })),
+                ),
         );
 
+        inner = inner.child(self.render_provisioning_card(cx));
+
         let theme = cx.theme();
         PageView::build(
             "Configuration",
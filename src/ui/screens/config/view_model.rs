@@ -1,15 +1,25 @@
 //! View model for the configuration screen — form state and save logic.
 
-use crate::hal::types::{AppConfig, RescueCurves};
+use crate::config_defaults::{self, ConfigSection};
+use crate::hal::types::{AppConfig, ConfigApplyReport, RescueCurves};
+use crate::impact::{self, ConfigDiff, Severity};
+use crate::inventory::{self, IdentityDrift, TimelineEvent};
+use crate::logging::{AUDIT_TARGET, ActionId};
+use crate::metrics;
+use crate::provisioning::{self, FirmwareImage, ProvisioningSession, SignedReport};
+use crate::settings;
 use crate::ui::app::AppModels;
 use crate::ui::components::dialog::PinPromptContent;
 use crate::ui::components::{dialog, dialog::StatusContent};
 use crate::ui::models::device::{
-    AppConfigInput, DeviceEvent, DeviceMethod, DeviceRepo, FullDeviceStatus, LedStatusConfig,
+    AppConfigInput, Ctap2Error, DeviceEvent, DeviceMethod, DeviceRepo, FullDeviceStatus,
+    LedStatusConfig, ReplugCheck,
 };
 
+use directories::UserDirs;
 use gpui::*;
-use gpui_component::input::InputState;
+use gpui_component::WindowExt;
+use gpui_component::input::{InputEvent, InputState};
 use gpui_component::select::{SelectItem, SelectState};
 use gpui_component::slider::SliderState;
 
@@ -161,6 +171,14 @@ impl LedDriverType {
             Self::Esp32Neopixel,
         ]
     }
+
+    /// Row of the device's TAG_LED_DRIVER value in [`Self::all`]. `None` when
+    /// the phy has no driver set (firmware default) or one we don't list, so
+    /// the select shows its placeholder instead of a driver the key isn't using.
+    pub fn index_of(value: Option<u8>) -> Option<usize> {
+        let value = value?;
+        Self::all().iter().position(|d| d.value() == value)
+    }
 }
 
 #[derive(Clone, PartialEq)]
@@ -218,8 +236,15 @@ pub struct ConfigViewModel {
     pub(super) led_steady: bool,
     pub(super) touch_timeout_input: Entity<InputState>,
     pub(super) power_cycle: bool,
+    /// Whether the options word was read back from the device. Until it is,
+    /// the option switches show defaults and are never written.
+    pub(super) options_known: bool,
     pub(super) loading: bool,
     pub(super) is_custom_vendor: bool,
+    /// When set, VID/PID changes need a device touch plus the PIN before they
+    /// are written (guards against silent identity spoofing). Persisted in
+    /// [`settings::Settings`].
+    pub(super) identity_protection: bool,
 
     // RS-Key specific state
     pub(super) led_status_steady: bool,
@@ -229,6 +254,12 @@ pub struct ConfigViewModel {
     pub(super) usb_apps_enabled: u16,
     pub(super) enabled_usb_itf: Option<u8>,
 
+    /// Devices configured since PicoForge started (or the session was reset),
+    /// exported as the provisioning report.
+    pub(super) session: ProvisioningSession,
+    /// Result of the last report export or firmware image selection.
+    pub(super) session_result: Option<Result<String, String>>,
+
     // Curve toggles — initialized from raw_curves_mask, rebuilt into mask on save.
     pub(super) curve_p256: bool,
     pub(super) curve_p384: bool,
@@ -248,10 +279,26 @@ pub struct ConfigViewModel {
 impl ConfigViewModel {
     pub fn new(window: &mut Window, cx: &mut Context<Self>, models: &AppModels) -> Self {
         let device = models.device.clone();
-        cx.subscribe_in(&device, window, |this, _, _: &DeviceEvent, window, cx| {
-            this.sync_from_device(window, cx);
-        })
+        cx.subscribe_in(
+            &device,
+            window,
+            |this, _, event: &DeviceEvent, window, cx| match event {
+                DeviceEvent::Updated => this.sync_from_device(window, cx),
+                DeviceEvent::ReplugVerified(Ok(msg)) => {
+                    window.push_notification(msg.clone(), cx);
+                }
+                DeviceEvent::ReplugVerified(Err(msg)) => {
+                    window.push_notification(format!("Verification failed: {}", msg), cx);
+                }
+                DeviceEvent::IdentityDrift => this.offer_pending_identity_restore(window, cx),
+                DeviceEvent::Reset => {}
+            },
+        )
         .detach();
+        // A drift found before this screen was first opened is still pending.
+        cx.defer_in(window, |this, window, cx| {
+            this.offer_pending_identity_restore(window, cx)
+        });
 
         let device_read = device.read(cx);
         let config = device_read.status.as_ref().map(|s| &s.config);
@@ -283,12 +330,13 @@ impl ConfigViewModel {
         let led_dimmable = config.map(|c| c.led_dimmable).unwrap_or(true);
         let led_steady = config.map(|c| c.led_steady).unwrap_or(false);
         let power_cycle = config.map(|c| c.power_cycle_on_reset).unwrap_or(false);
+        let options_known = config.is_some_and(|c| c.raw_options.is_some());
         let enabled_usb_itf = config.and_then(|c| c.enabled_usb_itf);
         let curves = config
             .and_then(|c| c.raw_curves_mask)
             .map(RescueCurves::from_bits_truncate)
             .unwrap_or(RescueCurves::empty());
-        let current_driver_val = config.and_then(|c| c.led_driver).unwrap_or(0);
+        let initial_driver_idx = LedDriverType::index_of(config.and_then(|c| c.led_driver));
 
         let mut led_status_steady = false;
         let mut led_status_colors = [0; 4];
@@ -348,6 +396,11 @@ impl ConfigViewModel {
         let pid_input = cx.new(|cx| InputState::new(window, cx).default_value(current_pid.clone()));
         let product_name_input =
             cx.new(|cx| InputState::new(window, cx).default_value(current_product_name.clone()));
+        // Re-render on edits so the driver conflict warning follows the inputs.
+        cx.subscribe(&vid_input, |_, _, _: &InputEvent, cx| cx.notify())
+            .detach();
+        cx.subscribe(&pid_input, |_, _, _: &InputEvent, cx| cx.notify())
+            .detach();
 
         let led_gpio_input = cx.new(|cx| {
             InputState::new(window, cx)
@@ -355,15 +408,10 @@ impl ConfigViewModel {
                 .default_value(current_led_gpio.clone())
         });
 
-        let initial_driver_idx = LedDriverType::all()
-            .iter()
-            .position(|d| d.value() == current_driver_val)
-            .unwrap_or(0);
-
         let led_driver_select = cx.new(|cx| {
             SelectState::new(
                 drivers,
-                Some(gpui_component::IndexPath::default().row(initial_driver_idx)),
+                initial_driver_idx.map(|ix| gpui_component::IndexPath::default().row(ix)),
                 window,
                 cx,
             )
@@ -418,6 +466,7 @@ impl ConfigViewModel {
             led_steady,
             touch_timeout_input,
             power_cycle,
+            options_known,
             curve_p256: curves.contains(RescueCurves::SECP256R1),
             curve_p384: curves.contains(RescueCurves::SECP384R1),
             curve_p521: curves.contains(RescueCurves::SECP521R1),
@@ -431,21 +480,41 @@ impl ConfigViewModel {
             curve_x448: curves.contains(RescueCurves::CURVE448),
             loading: false,
             is_custom_vendor,
+            identity_protection: settings::load().0.identity_protection,
             led_status_steady,
             led_status_colors,
             led_status_brightness,
             usb_apps_supported,
             usb_apps_enabled,
             enabled_usb_itf,
+            session: ProvisioningSession::new(),
+            session_result: None,
             _task: None,
         }
     }
 
+    /// Turn identity protection on or off and persist the choice.
+    pub(super) fn set_identity_protection(
+        &mut self,
+        enabled: bool,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let (mut next, _) = settings::load();
+        next.identity_protection = enabled;
+        match settings::save(&next) {
+            Ok(()) => self.identity_protection = enabled,
+            Err(e) => window.push_notification(format!("Could not save the setting: {}", e), cx),
+        }
+        cx.notify();
+    }
+
     pub(super) fn write_config_to_device(
         &mut self,
         changes: AppConfigInput,
         method: DeviceMethod,
         pin: Option<String>,
+        confirm_identity: bool,
         dialog_handle: StatusDialogHandle,
         cx: &mut Context<Self>,
     ) {
@@ -455,6 +524,40 @@ impl ConfigViewModel {
             .status
             .as_ref()
             .map(|s| s.info.serial.clone());
+        let previous_identity = self
+            .device
+            .read(cx)
+            .status
+            .as_ref()
+            .map(|s| format!("{}:{}", s.config.vid, s.config.pid).to_uppercase());
+        let new_identity = changes
+            .vid
+            .as_ref()
+            .zip(changes.pid.as_ref())
+            .map(|(vid, pid)| format!("{}:{}", vid, pid).to_uppercase());
+        let replug_check = self
+            .device
+            .read(cx)
+            .status
+            .as_ref()
+            .and_then(|s| Self::replug_check_for(s, &changes));
+        let status_before = self.device.read(cx).status.clone();
+        // Touch + PIN go over CTAPHID, so they only confirm a key opened over
+        // FIDO; a Rescue write was confirmed in a dialog before it got here.
+        let touch_confirm = confirm_identity && method == DeviceMethod::Fido;
+        let confirmed_by = match (confirm_identity, touch_confirm) {
+            (false, _) => "none",
+            (true, true) => "touch+PIN",
+            (true, false) => "dialog",
+        };
+        let aaguid = self
+            .device
+            .read(cx)
+            .fido_info
+            .as_ref()
+            .map(|info| info.aaguid.clone());
+        let profile = changes.clone();
+        let action = ActionId::begin("config.apply");
 
         self.loading = true;
         cx.notify();
@@ -464,10 +567,11 @@ impl ConfigViewModel {
 
         self._task = Some(cx.spawn(async move |_, cx| {
             let serial_check = expected_serial.clone();
+            let act = action.clone();
             let device_still_matches = cx
                 .background_executor()
                 .spawn(async move {
-                    let current = DeviceRepo::read_device_serial_blocking();
+                    let current = act.scope(DeviceRepo::read_device_serial_blocking);
                     match (serial_check, current) {
                         (Some(expected), Some(current)) => expected == current,
                         (None, _) => true,
@@ -478,6 +582,7 @@ impl ConfigViewModel {
 
             if !device_still_matches {
                 let _ = weak_self.update(cx, |this, cx| {
+                    action.scope(|| log::warn!("Device changed before config write"));
                     this.loading = false;
                     this.device.update(cx, |repo, repo_cx| {
                         repo.refresh(repo_cx);
@@ -498,6 +603,46 @@ impl ConfigViewModel {
 
             let dialog = dialog_handle;
 
+            if touch_confirm {
+                cx.update(|cx| {
+                    if let StatusDialogHandle::Pin(dh) = &dialog {
+                        let _ = dh.update(cx, |d, cx| {
+                            d.set_loading_msg(
+                                "Touch your device to confirm the USB identity change...",
+                                cx,
+                            );
+                        });
+                    }
+                })
+                .ok();
+
+                let confirm_pin = pin.clone().unwrap_or_default();
+                let act = action.clone();
+                let confirmed = cx
+                    .background_executor()
+                    .spawn(async move {
+                        act.scope(|| DeviceRepo::confirm_identity_change_blocking(confirm_pin))
+                    })
+                    .await;
+
+                if let Err(e) = confirmed {
+                    action.scope(|| log::warn!("USB identity change was not confirmed: {}", e));
+                    let _ = weak_self.update(cx, |this, cx| {
+                        this.loading = false;
+                        match &dialog {
+                            StatusDialogHandle::Pin(dh) => {
+                                let _ = dh.update(cx, |d, cx| d.set_error(e, cx));
+                            }
+                            StatusDialogHandle::Status(dh) => {
+                                let _ = dh.update(cx, |d, cx| d.set_error(e, cx));
+                            }
+                        }
+                        cx.notify();
+                    });
+                    return;
+                }
+            }
+
             // Tell the user to press the button — RS-Key firmware requires
             // user presence for config writes on both FIDO and Rescue paths.
             cx.update(|cx| {
@@ -520,29 +665,45 @@ impl ConfigViewModel {
                 }
             }).ok();
 
+            let act = action.clone();
             let result = cx
                 .background_executor()
                 .spawn(async move {
-                    DeviceRepo::write_config_blocking(changes, method_clone, pin)
+                    act.scope(|| DeviceRepo::write_config_blocking(changes, method_clone, pin))
                 })
                 .await;
 
             let dialog_handle = dialog;
 
             let fresh_state = if result.is_ok() {
+                let act = action.clone();
                 cx.background_executor()
-                    .spawn(async move { DeviceRepo::read_device_state_blocking().ok() })
+                    .spawn(
+                        async move { act.scope(|| DeviceRepo::read_device_state_blocking().ok()) },
+                    )
                     .await
             } else {
                 None
             };
 
-            let _ = weak_self.update(cx, |this, cx| {
+            let _ = weak_self.update(cx, |this, cx| action.scope(|| {
                 this.loading = false;
 
                 match result {
-                    Ok(msg) => {
-                        log::info!("Success: {}", msg);
+                    Ok(report) => {
+                        log::info!("Success: {}", report.note);
+                        metrics::record_feature("config.write");
+
+                        if previous_identity.is_some() && new_identity != previous_identity {
+                            log::info!(
+                                target: AUDIT_TARGET,
+                                "USB identity changed on device {}: {} -> {} (confirmed by: {})",
+                                expected_serial.as_deref().unwrap_or("unknown"),
+                                previous_identity.as_deref().unwrap_or("unknown"),
+                                new_identity.as_deref().unwrap_or("unknown"),
+                                confirmed_by
+                            );
+                        }
 
                         if let Some(fs) = &fresh_state {
                             let serial_matches = expected_serial.as_deref()
@@ -558,6 +719,7 @@ impl ConfigViewModel {
                                 this.led_dimmable = config.led_dimmable;
                                 this.led_steady = config.led_steady;
                                 this.power_cycle = config.power_cycle_on_reset;
+                                this.options_known = config.raw_options.is_some();
                                 Self::sync_curve_toggles(this, Some(config));
 
                                 this.device.update(cx, |repo, repo_cx| {
@@ -568,35 +730,84 @@ impl ConfigViewModel {
                             }
                         }
 
+                        if let Some(before) = &status_before {
+                            let after = fresh_state
+                                .as_ref()
+                                .map(|fs| &fs.status)
+                                .filter(|s| s.info.serial == before.info.serial);
+                            if profile.vid.is_some()
+                                || profile.pid.is_some()
+                                || profile.product_name.is_some()
+                            {
+                                if let Err(e) = inventory::record_identity(
+                                    before,
+                                    profile.vid.as_deref(),
+                                    profile.pid.as_deref(),
+                                    profile.product_name.as_deref(),
+                                ) {
+                                    log::warn!("Could not update device inventory: {}", e);
+                                }
+                            }
+                            if after.is_some() {
+                                this.device.update(cx, |repo, repo_cx| {
+                                    repo.record_event(TimelineEvent::ConfigApplied, repo_cx);
+                                });
+                            }
+                            let nickname = this.device.read(cx).nickname().map(str::to_string);
+                            this.session.record(before, nickname, aaguid, profile, after);
+                        }
+
+                        let mut summary = Self::apply_report_summary(&report);
+                        if let Some(check) = replug_check {
+                            this.device.update(cx, |repo, _| repo.arm_replug_check(check));
+                            summary.push_str(
+                                "\n\nUnplug and re-plug the device; the new identity will be \
+                                 verified automatically.",
+                            );
+                        } else if report.replug_required {
+                            summary.push_str("\n\nUnplug and re-plug the device to apply the changes.");
+                        }
+
+                        // A field that reads back wrong did not take effect,
+                        // whatever the write's status word said.
+                        let consistent = report.is_consistent();
                         match &dialog_handle {
                             StatusDialogHandle::Pin(dh) => {
                                 let _ = dh.update(cx, |d, cx| {
-                                    d.set_success(
-                                        "Configuration applied successfully.".to_string(),
-                                        cx,
-                                    );
+                                    if consistent {
+                                        d.set_success(summary, cx);
+                                    } else {
+                                        d.set_error(summary, cx);
+                                    }
                                 });
                             }
                             StatusDialogHandle::Status(dh) => {
                                 let _ = dh.update(cx, |d, cx| {
-                                    d.set_success(
-                                        "Configuration applied successfully.".to_string(),
-                                        cx,
-                                    );
+                                    if consistent {
+                                        d.set_success(summary, cx);
+                                    } else {
+                                        d.set_error(summary, cx);
+                                    }
                                 });
                             }
                         }
                     }
                     Err(e) => {
                         log::error!("Error saving config: {}", e);
+                        metrics::record_error(&format!("config.write.{}", e.kind().to_lowercase()));
 
                         let mut err_msg = format!("Failed to apply configuration: {}", e);
 
-                        if method == DeviceMethod::Fido && err_msg.contains("0x3E") {
+                        if method == DeviceMethod::Fido
+                            && e.ctap() == Some(Ctap2Error::InvalidSubcommand)
+                        {
                             err_msg = "The device firmware does not support being configured in fido only communication mode. \nHave a look at the troubleshooting guide to fix this".to_string();
-                        } else if method == DeviceMethod::Fido && err_msg.contains("0x27") {
+                        } else if method == DeviceMethod::Fido
+                            && e.ctap() == Some(Ctap2Error::OperationDenied)
+                        {
                             err_msg = "Configuration denied (Status: 0x27). This usually means the operation timed out waiting for you to touch the device's button, or the PIN token was rejected.".to_string();
                         }
+                        err_msg.push_str(&format!("\n\nLog reference: {}", action));
 
                         match &dialog_handle {
                             StatusDialogHandle::Pin(dh) => {
@@ -614,21 +825,77 @@ impl ConfigViewModel {
                 }
 
                 cx.notify();
-            });
+            }));
         }));
     }
 
+    /// Identity protection for a key opened over Rescue: an explicit
+    /// confirmation naming the new VID/PID before the write is sent.
+    fn open_rescue_identity_confirm(
+        &mut self,
+        changes: AppConfigInput,
+        method: DeviceMethod,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let new_identity = format!(
+            "{}:{}",
+            changes.vid.as_deref().unwrap_or_default(),
+            changes.pid.as_deref().unwrap_or_default()
+        )
+        .to_uppercase();
+        let message = format!(
+            "The key will enumerate as {} after the next replug. This key is connected \
+             over the rescue interface, so the change cannot be confirmed with a touch \
+             and PIN; confirm it here instead.",
+            new_identity
+        );
+        let view_handle = cx.entity().downgrade();
+
+        dialog::open_confirm(
+            "Confirm USB Identity Change",
+            message,
+            "Change Identity",
+            gpui_component::button::ButtonVariant::Danger,
+            window,
+            cx,
+            move |_dialog_handle, window, cx| {
+                window.close_dialog(cx);
+                let changes = changes.clone();
+                let method = method.clone();
+                let _ = view_handle.update(cx, |this, cx| {
+                    let handle = dialog::open_status_dialog("Applying Configuration", window, cx);
+                    this.write_config_to_device(
+                        changes,
+                        method,
+                        None,
+                        true,
+                        StatusDialogHandle::Status(handle),
+                        cx,
+                    );
+                });
+            },
+        );
+    }
+
     fn open_pin_dialog(
         &mut self,
         changes: AppConfigInput,
+        method: DeviceMethod,
+        confirm_identity: bool,
         window: &mut Window,
         cx: &mut Context<Self>,
     ) {
         let view_handle = cx.entity().downgrade();
+        let description = if confirm_identity {
+            "Changing the USB identity requires your device PIN and a touch on the device."
+        } else {
+            "Enter your device PIN to apply changes."
+        };
 
         dialog::open_pin_prompt(
             "Authentication Required",
-            "Enter your device PIN to apply changes.",
+            description,
             None,
             "Confirm",
             window,
@@ -637,8 +904,9 @@ impl ConfigViewModel {
                 let _ = view_handle.update(cx, |this, cx| {
                     this.write_config_to_device(
                         changes.clone(),
-                        DeviceMethod::Fido,
+                        method.clone(),
                         Some(pin),
+                        confirm_identity,
                         StatusDialogHandle::Pin(dialog_handle),
                         cx,
                     );
@@ -648,6 +916,15 @@ impl ConfigViewModel {
     }
 
     pub(super) fn apply_changes(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let touch_timeout_str = self.touch_timeout_input.read(cx).text().to_string();
+        let touch_timeout = match config_defaults::parse_touch_timeout(&touch_timeout_str) {
+            Ok(touch_timeout) => touch_timeout,
+            Err(e) => {
+                window.push_notification(e, cx);
+                return;
+            }
+        };
+
         let device = self.device.read(cx);
         let Some(status) = &device.status else { return };
 
@@ -670,17 +947,20 @@ impl ConfigViewModel {
         let mut has_changes = false;
 
         let vid = self.vid_input.read(cx).text().to_string();
-        if vid != current_vid {
-            has_changes = true;
-        }
-
         let pid = self.pid_input.read(cx).text().to_string();
-        if pid != current_pid {
+        // VID/PID are hex: a change of case alone is not a new identity.
+        let identity_changed =
+            !vid.eq_ignore_ascii_case(&current_vid) || !pid.eq_ignore_ascii_case(&current_pid);
+        if identity_changed {
             has_changes = true;
         }
+        let confirm_identity = self.identity_protection && identity_changed;
 
+        // Only an edited name is sent: pico-fido's FIDO path cannot write it
+        // and refuses a config that carries one.
         let product_name = self.product_name_input.read(cx).text().to_string();
-        if product_name != current_product_name {
+        let product_name_changed = product_name != current_product_name;
+        if product_name_changed {
             has_changes = true;
         }
 
@@ -700,20 +980,17 @@ impl ConfigViewModel {
         // LED driver: preserve the device's value (None = firmware default) unless
         // the user picks a different entry than the one it booted with — an
         // untouched select must not clobber a virgin phy with a bogus driver.
-        let init_driver_idx = LedDriverType::all()
-            .iter()
-            .position(|d| Some(d.value()) == current_led_driver)
-            .unwrap_or(0);
+        let init_driver_idx = LedDriverType::index_of(current_led_driver);
         let sel_driver_idx = self
             .led_driver_select
             .read(cx)
             .selected_index(cx)
-            .map(|p| p.row)
-            .unwrap_or(init_driver_idx);
-        let final_led_driver = if sel_driver_idx != init_driver_idx {
-            LedDriverType::all().get(sel_driver_idx).map(|d| d.value())
-        } else {
-            current_led_driver
+            .map(|p| p.row);
+        let final_led_driver = match sel_driver_idx {
+            Some(ix) if sel_driver_idx != init_driver_idx => {
+                LedDriverType::all().get(ix).map(|d| d.value())
+            }
+            _ => current_led_driver,
         };
         if final_led_driver != current_led_driver {
             has_changes = true;
@@ -732,23 +1009,23 @@ impl ConfigViewModel {
             has_changes = true;
         }
 
-        // Touch timeout: empty input = "firmware default" (no override written).
-        // `0` firmware-side also means the 30 s default, so an empty field is honest.
-        let touch_timeout_str = self.touch_timeout_input.read(cx).text().to_string();
-        let trimmed_tt = touch_timeout_str.trim();
-        let final_touch_timeout = if trimmed_tt.is_empty() {
-            None
-        } else {
-            trimmed_tt.parse::<u8>().ok().or(current_touch_timeout)
+        // Touch timeout: a left-out value keeps the device's, so clearing the
+        // field writes `0` (the 30 s firmware default) explicitly.
+        let final_touch_timeout = match touch_timeout {
+            None if current_touch_timeout.is_some() => Some(config_defaults::DEFAULT_TOUCH_TIMEOUT),
+            touch_timeout => touch_timeout,
         };
         if final_touch_timeout != current_touch_timeout {
             has_changes = true;
         }
 
-        if (self.led_dimmable != current_led_dimmable)
-            || (self.led_steady != current_led_steady)
-            || (self.power_cycle != current_power_cycle)
-        {
+        // The options word is only written when a switch actually moved, so an
+        // unrelated change cannot flip power-reset protection as a side effect.
+        let options_changed = self.options_known
+            && ((self.led_dimmable != current_led_dimmable)
+                || (self.led_steady != current_led_steady)
+                || (self.power_cycle != current_power_cycle));
+        if options_changed {
             has_changes = true;
         }
 
@@ -758,9 +1035,13 @@ impl ConfigViewModel {
         let (has_curve_changes, built_curves_mask) = if is_rskey {
             (false, raw_curves_mask)
         } else {
-            let new_curves_mask = Self::curves_mask_from_toggles(self);
-            let changed = Some(new_curves_mask) != raw_curves_mask
-                || (raw_curves_mask.is_none() && new_curves_mask != 0);
+            let new_curves_mask = self.curves_mask_from_toggles(raw_curves_mask.unwrap_or(0));
+            // No curves tag means the firmware's built-in set; leave it alone
+            // until a switch is turned on.
+            let changed = match raw_curves_mask {
+                Some(raw) => new_curves_mask != raw,
+                None => new_curves_mask != 0,
+            };
             (
                 changed,
                 if changed {
@@ -788,14 +1069,14 @@ impl ConfigViewModel {
         let changes = AppConfigInput {
             vid: Some(vid),
             pid: Some(pid),
-            product_name: Some(product_name),
+            product_name: product_name_changed.then_some(product_name),
             led_gpio: final_led_gpio,
             led_brightness: final_led_brightness,
             touch_timeout: final_touch_timeout,
             led_driver: final_led_driver,
-            led_dimmable: Some(self.led_dimmable),
-            power_cycle_on_reset: Some(self.power_cycle),
-            led_steady: Some(self.led_steady),
+            led_dimmable: options_changed.then_some(self.led_dimmable),
+            power_cycle_on_reset: options_changed.then_some(self.power_cycle),
+            led_steady: options_changed.then_some(self.led_steady),
             enable_secp256k1: None,
             raw_curves_mask: built_curves_mask,
             led_order,
@@ -803,9 +1084,81 @@ impl ConfigViewModel {
             led_num: None,
         };
 
-        if method == DeviceMethod::Fido {
-            if Self::status_supports_legacy_fido_config(status) || is_rskey {
-                self.open_pin_dialog(changes, window, cx);
+        let fido_config_supported = Self::status_supports_legacy_fido_config(status) || is_rskey;
+
+        let diff = ConfigDiff {
+            vid_pid: identity_changed.then(|| {
+                (
+                    format!("{}:{}", current_vid, current_pid).to_uppercase(),
+                    format!("{}:{}", vid, pid).to_uppercase(),
+                )
+            }),
+            product_name_changed,
+            aaguid: None,
+            disabled_curves: ConfigDiff::curves_disabled_between(
+                raw_curves_mask.unwrap_or(0),
+                built_curves_mask.unwrap_or(0),
+            ),
+        };
+        let impacts = impact::analyze(&diff, device.credentials.as_deref());
+        if impacts.is_empty() {
+            self.submit_changes(
+                changes,
+                method,
+                confirm_identity,
+                fido_config_supported,
+                window,
+                cx,
+            );
+            return;
+        }
+
+        // Identity or curve changes: show what they mean for existing passkeys first.
+        let breaking = impacts.iter().any(|i| i.severity == Severity::Breaking);
+        let view_handle = cx.entity().downgrade();
+        dialog::open_confirm(
+            "Impact on Existing Passkeys",
+            impact::summary(&impacts),
+            if breaking { "Apply Anyway" } else { "Continue" },
+            if breaking {
+                gpui_component::button::ButtonVariant::Danger
+            } else {
+                gpui_component::button::ButtonVariant::Primary
+            },
+            window,
+            cx,
+            move |_dialog_handle, window, cx| {
+                window.close_dialog(cx);
+                let changes = changes.clone();
+                let method = method.clone();
+                let _ = view_handle.update(cx, |this, cx| {
+                    this.submit_changes(
+                        changes,
+                        method,
+                        confirm_identity,
+                        fido_config_supported,
+                        window,
+                        cx,
+                    );
+                });
+            },
+        );
+    }
+
+    /// Route a config write through the PIN prompt or a status dialog,
+    /// depending on the channel and whether the identity change needs a touch.
+    fn submit_changes(
+        &mut self,
+        changes: AppConfigInput,
+        method: DeviceMethod,
+        confirm_identity: bool,
+        fido_config_supported: bool,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if DeviceRepo::config_needs_pin(&method) {
+            if fido_config_supported {
+                self.open_pin_dialog(changes, method, confirm_identity, window, cx);
             } else {
                 let handle =
                     dialog::open_status_dialog("Configuration Requires Rescue Mode", window, cx);
@@ -813,25 +1166,33 @@ impl ConfigViewModel {
                     changes,
                     method,
                     None,
+                    false,
                     StatusDialogHandle::Status(handle),
                     cx,
                 );
             }
+        } else if confirm_identity {
+            // Rescue writes need no PIN, and the touch + PIN check runs over
+            // CTAPHID, which a key opened over PC/SC may not offer. Confirm
+            // in a dialog instead.
+            self.open_rescue_identity_confirm(changes, method, window, cx);
         } else {
             let handle = dialog::open_status_dialog("Applying Configuration", window, cx);
             self.write_config_to_device(
                 changes,
                 method,
                 None,
+                false,
                 StatusDialogHandle::Status(handle),
                 cx,
             );
         }
     }
 
-    /// Build the curves bitmask from the current toggle states.
-    fn curves_mask_from_toggles(&self) -> u32 {
-        let mut mask = RescueCurves::empty();
+    /// Apply the current toggle states to `base`, keeping bits without a
+    /// toggle as they are.
+    fn curves_mask_from_toggles(&self, base: u32) -> u32 {
+        let mut mask = RescueCurves::from_bits_retain(base);
         mask.set(RescueCurves::SECP256R1, self.curve_p256);
         mask.set(RescueCurves::SECP384R1, self.curve_p384);
         mask.set(RescueCurves::SECP521R1, self.curve_p521);
@@ -865,6 +1226,149 @@ impl ConfigViewModel {
         self.curve_x448 = curves.contains(RescueCurves::CURVE448);
     }
 
+    /// What the apply dialog says about a write: which fields read back as
+    /// written, which were skipped, and which read back wrong.
+    fn apply_report_summary(report: &ConfigApplyReport) -> String {
+        let mismatched: Vec<String> = report
+            .mismatched()
+            .map(|f| {
+                format!(
+                    "{} (wrote {}, reads {})",
+                    f.field,
+                    f.written,
+                    f.read_back.as_deref().unwrap_or("nothing")
+                )
+            })
+            .collect();
+        let mut lines = vec![if report.applied.is_empty() {
+            "No configuration changes were needed.".to_string()
+        } else if mismatched.is_empty() {
+            "Configuration applied.".to_string()
+        } else {
+            "Configuration written, but some settings did not take effect.".to_string()
+        }];
+        if !mismatched.is_empty() {
+            lines.push(format!("Read back differently: {}.", mismatched.join(", ")));
+        }
+        if !report.verified.is_empty() {
+            lines.push(format!("Verified: {}.", report.verified.join(", ")));
+        }
+        if !report.skipped.is_empty() {
+            lines.push(format!(
+                "Not checked (not reported by the device, or not writable over \
+                 this connection): {}.",
+                report.skipped.join(", ")
+            ));
+        }
+        lines.join("\n")
+    }
+
+    /// Build the post-replug check for the identity fields `changes` actually
+    /// modifies, or `None` when nothing requires a replug to take effect.
+    fn replug_check_for(
+        status: &FullDeviceStatus,
+        changes: &AppConfigInput,
+    ) -> Option<ReplugCheck> {
+        let changed = |new: &Option<String>, current: &str| {
+            new.as_ref()
+                .filter(|v| !v.is_empty() && !v.eq_ignore_ascii_case(current))
+                .cloned()
+        };
+        let vid = changed(&changes.vid, &status.config.vid);
+        let pid = changed(&changes.pid, &status.config.pid);
+        let product_name = changed(&changes.product_name, &status.config.product_name);
+
+        if vid.is_none() && pid.is_none() && product_name.is_none() {
+            return None;
+        }
+        Some(ReplugCheck {
+            serial: status.info.serial.clone(),
+            vid,
+            pid,
+            product_name,
+        })
+    }
+
+    /// Put the firmware defaults of `section` into the form. Fields the
+    /// connected firmware cannot write are left alone; nothing is written
+    /// until the changes are applied.
+    pub(super) fn reset_section(
+        &mut self,
+        section: ConfigSection,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(status) = self.device.read(cx).status.clone() else {
+            return;
+        };
+        let is_rskey = status.firmware_type == crate::ui::models::device::FirmwareType::RSKey;
+        let is_fido_no_rskey = status.method == DeviceMethod::Fido && !is_rskey;
+        let hardware_config_disabled =
+            is_fido_no_rskey && !Self::status_supports_legacy_fido_config(&status);
+        if hardware_config_disabled {
+            return;
+        }
+
+        match section {
+            ConfigSection::Identity => {
+                let preset_idx = UsbIdentityPreset::all()
+                    .iter()
+                    .position(|p| *p == UsbIdentityPreset::Generic)
+                    .unwrap_or(0);
+                self.is_custom_vendor = false;
+                self.vendor_select.update(cx, |select, cx| {
+                    select.set_selected_index(
+                        Some(gpui_component::IndexPath::default().row(preset_idx)),
+                        window,
+                        cx,
+                    );
+                });
+                self.vid_input.update(cx, |input, cx| {
+                    input.set_value(config_defaults::DEFAULT_VID, window, cx)
+                });
+                self.pid_input.update(cx, |input, cx| {
+                    input.set_value(config_defaults::DEFAULT_PID, window, cx)
+                });
+                if !is_fido_no_rskey {
+                    self.product_name_input.update(cx, |input, cx| {
+                        input.set_value(config_defaults::DEFAULT_PRODUCT_NAME, window, cx)
+                    });
+                }
+            }
+            ConfigSection::Led => {
+                if self.options_known {
+                    self.led_dimmable = config_defaults::DEFAULT_LED_DIMMABLE;
+                    self.led_steady = config_defaults::DEFAULT_LED_STEADY;
+                }
+            }
+            ConfigSection::Touch => {
+                // An empty input means "leave as is"; `0` is written and is
+                // the firmware default.
+                if !is_fido_no_rskey {
+                    let timeout = config_defaults::DEFAULT_TOUCH_TIMEOUT.to_string();
+                    self.touch_timeout_input
+                        .update(cx, |input, cx| input.set_value(timeout, window, cx));
+                }
+            }
+            ConfigSection::Options => {
+                if self.options_known {
+                    self.power_cycle = config_defaults::DEFAULT_POWER_CYCLE_ON_RESET;
+                }
+            }
+        }
+        log::info!("Form reset to firmware defaults: {}", section.label());
+        cx.notify();
+    }
+
+    /// Put every documented default into the form and apply it, through the
+    /// same PIN, identity and impact checks as a manual change.
+    pub(super) fn reset_all_to_defaults(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        for section in ConfigSection::ALL {
+            self.reset_section(section, window, cx);
+        }
+        self.apply_changes(window, cx);
+    }
+
     pub(super) fn status_supports_legacy_fido_config(status: &FullDeviceStatus) -> bool {
         status.method == DeviceMethod::Fido
             && DeviceRepo::firmware_supports_legacy_fido_config(
@@ -899,6 +1403,7 @@ impl ConfigViewModel {
         self.led_dimmable = config.map(|c| c.led_dimmable).unwrap_or(true);
         self.led_steady = config.map(|c| c.led_steady).unwrap_or(false);
         self.power_cycle = config.map(|c| c.power_cycle_on_reset).unwrap_or(false);
+        self.options_known = config.is_some_and(|c| c.raw_options.is_some());
         Self::sync_curve_toggles(self, config);
 
         let brightness = config
@@ -906,7 +1411,7 @@ impl ConfigViewModel {
             .map(|b| b as f32)
             .unwrap_or(DEFAULT_BRIGHTNESS as f32);
 
-        let new_driver_val = config.and_then(|c| c.led_driver).unwrap_or(1);
+        let new_driver_idx = LedDriverType::index_of(config.and_then(|c| c.led_driver));
 
         if let Some(led) = &device.led_status {
             self.led_status_steady = led.steady;
@@ -950,13 +1455,9 @@ impl ConfigViewModel {
         self.led_brightness_slider
             .update(cx, |slider, cx| slider.set_value(brightness, window, cx));
 
-        let new_driver_idx = LedDriverType::all()
-            .iter()
-            .position(|d| d.value() == new_driver_val)
-            .unwrap_or(0);
         self.led_driver_select.update(cx, |select, cx| {
             select.set_selected_index(
-                Some(gpui_component::IndexPath::default().row(new_driver_idx)),
+                new_driver_idx.map(|ix| gpui_component::IndexPath::default().row(ix)),
                 window,
                 cx,
             );
@@ -1024,26 +1525,36 @@ impl ConfigViewModel {
         dialog_handle: StatusDialogHandle,
         cx: &mut Context<Self>,
     ) {
+        let action = ActionId::begin("led.apply");
         self.loading = true;
         cx.notify();
 
         let weak_self = cx.entity().downgrade();
 
         self._task = Some(cx.spawn(async move |_, cx| {
+            let act = action.clone();
             let result = cx
                 .background_executor()
-                .spawn(async move { DeviceRepo::write_led_config_blocking(method, config, pin) })
+                .spawn(async move {
+                    act.scope(|| DeviceRepo::write_led_config_blocking(method, config, pin))
+                })
                 .await;
 
             let fresh_state = if result.is_ok() {
+                let act = action.clone();
                 cx.background_executor()
-                    .spawn(async move { DeviceRepo::read_device_state_blocking().ok() })
+                    .spawn(
+                        async move { act.scope(|| DeviceRepo::read_device_state_blocking().ok()) },
+                    )
                     .await
             } else {
                 None
             };
 
             let _ = weak_self.update(cx, |this, cx| {
+                if let Err(e) = &result {
+                    action.scope(|| log::error!("Failed to apply LED config: {}", e));
+                }
                 this.loading = false;
                 match result {
                     Ok(_) => {
@@ -1144,28 +1655,36 @@ impl ConfigViewModel {
         dialog_handle: StatusDialogHandle,
         cx: &mut Context<Self>,
     ) {
+        let action = ActionId::begin("apps.apply");
         self.loading = true;
         cx.notify();
 
         let weak_self = cx.entity().downgrade();
 
         self._task = Some(cx.spawn(async move |_, cx| {
+            let act = action.clone();
             let result = cx
                 .background_executor()
                 .spawn(async move {
-                    DeviceRepo::write_management_config_blocking(method, mask, pin)
+                    act.scope(|| DeviceRepo::write_management_config_blocking(method, mask, pin))
                 })
                 .await;
 
             let fresh_state = if result.is_ok() {
+                let act = action.clone();
                 cx.background_executor()
-                    .spawn(async move { DeviceRepo::read_device_state_blocking().ok() })
+                    .spawn(
+                        async move { act.scope(|| DeviceRepo::read_device_state_blocking().ok()) },
+                    )
                     .await
             } else {
                 None
             };
 
             let _ = weak_self.update(cx, |this, cx| {
+                if let Err(e) = &result {
+                    action.scope(|| log::error!("Failed to apply USB applications: {}", e));
+                }
                 this.loading = false;
                 match result {
                     Ok(_) => {
@@ -1212,4 +1731,216 @@ impl ConfigViewModel {
             });
         }));
     }
+
+    /// Offer the restore for the identity drift the device model is holding.
+    fn offer_pending_identity_restore(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if let Some(drift) = self
+            .device
+            .update(cx, |device, _| device.take_identity_drift())
+        {
+            self.offer_identity_restore(drift, window, cx);
+        }
+    }
+
+    /// Offer to write back the USB identity recorded in the inventory after the
+    /// device came back with a different one (usually a firmware update).
+    fn offer_identity_restore(
+        &mut self,
+        drift: IdentityDrift,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let expected = &drift.expected;
+        let cause = match &drift.firmware_changed_to {
+            Some(version) => format!(
+                "The firmware was updated from {} to {}, which reset its USB identity.",
+                expected.firmware_version, version
+            ),
+            None => "Its USB identity was changed outside PicoForge.".to_string(),
+        };
+        let expected_name = expected
+            .product_name
+            .as_ref()
+            .map(|name| format!(" (\"{}\")", name))
+            .unwrap_or_default();
+        let message = format!(
+            "{} Device {} now reports {} (\"{}\"), but was configured as {}:{}{}.\n\n\
+             Apps and udev rules that match on the old identity will not recognise it until it is restored.",
+            cause,
+            drift.serial,
+            drift.found_vid_pid,
+            drift.found_product_name,
+            expected.vid.to_uppercase(),
+            expected.pid.to_uppercase(),
+            expected_name,
+        );
+
+        let view_handle = cx.entity().downgrade();
+        dialog::open_confirm(
+            "USB Identity Changed",
+            message,
+            "Restore Identity",
+            gpui_component::button::ButtonVariant::Primary,
+            window,
+            cx,
+            move |_dialog_handle, window, cx| {
+                window.close_dialog(cx);
+                let drift = drift.clone();
+                let _ = view_handle.update(cx, |this, cx| {
+                    this.restore_identity(drift, window, cx);
+                });
+            },
+        );
+    }
+
+    /// Write only the recorded VID/PID and product name back to the device.
+    fn restore_identity(
+        &mut self,
+        drift: IdentityDrift,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(status) = self.device.read(cx).status.clone() else {
+            return;
+        };
+        if status.info.serial != drift.serial {
+            window.push_notification("A different device is connected now.", cx);
+            return;
+        }
+
+        let expected = drift.expected;
+        log::info!(
+            target: AUDIT_TARGET,
+            "Restoring USB identity {}:{} on device {} after drift to {}",
+            expected.vid,
+            expected.pid,
+            drift.serial,
+            drift.found_vid_pid
+        );
+
+        let changes = AppConfigInput {
+            vid: Some(expected.vid),
+            pid: Some(expected.pid),
+            product_name: expected.product_name,
+            led_gpio: None,
+            led_brightness: None,
+            touch_timeout: None,
+            led_driver: None,
+            led_dimmable: None,
+            power_cycle_on_reset: None,
+            led_steady: None,
+            enable_secp256k1: None,
+            raw_curves_mask: None,
+            led_order: None,
+            enabled_usb_itf: None,
+            led_num: None,
+        };
+        let fido_config_supported = Self::status_supports_legacy_fido_config(&status)
+            || status.firmware_type == crate::ui::models::device::FirmwareType::RSKey;
+        self.submit_changes(
+            changes,
+            status.method.clone(),
+            self.identity_protection,
+            fido_config_supported,
+            window,
+            cx,
+        );
+    }
+
+    /// Ask for the firmware image that was flashed and attach its hash to the session.
+    pub(super) fn select_firmware_image(&mut self, cx: &mut Context<Self>) {
+        let receiver = cx.prompt_for_paths(gpui::PathPromptOptions {
+            files: true,
+            directories: false,
+            multiple: false,
+            prompt: Some("Select Firmware Image (UF2)".into()),
+        });
+        let entity = cx.entity().downgrade();
+
+        self._task = Some(cx.spawn(async move |_, cx| {
+            let Ok(Ok(Some(paths))) = receiver.await else {
+                return;
+            };
+            let Some(path) = paths.into_iter().next() else {
+                return;
+            };
+            let image = cx
+                .background_executor()
+                .spawn(async move { FirmwareImage::from_file(&path) })
+                .await;
+
+            let _ = entity.update(cx, |this, cx| {
+                this.session_result = Some(match image {
+                    Ok(image) => {
+                        let msg = format!("Firmware image {} attached", image.file_name);
+                        this.session.set_firmware_image(Some(image));
+                        Ok(msg)
+                    }
+                    Err(e) => Err(e),
+                });
+                cx.notify();
+            });
+        }));
+    }
+
+    /// Sign the session report with the app key and save it as JSON.
+    pub(super) fn export_report(&mut self, cx: &mut Context<Self>) {
+        let signed = provisioning::load_or_create_key()
+            .and_then(|key| SignedReport::sign(self.session.report(), &key))
+            .and_then(|signed| signed.to_json().map(|json| (signed, json)));
+        let (signed, json) = match signed {
+            Ok(v) => v,
+            Err(e) => {
+                log::error!("Failed to build provisioning report: {}", e);
+                self.session_result = Some(Err(e));
+                cx.notify();
+                return;
+            }
+        };
+
+        let default_dir = UserDirs::new()
+            .and_then(|d| {
+                d.document_dir()
+                    .or_else(|| d.download_dir())
+                    .map(|p| p.to_path_buf())
+            })
+            .unwrap_or_else(|| {
+                std::path::PathBuf::from(std::env::var("HOME").unwrap_or_else(|_| ".".into()))
+            });
+        let file_name = format!("provisioning-report-{}.json", signed.report.finished_at);
+        let receiver = cx.prompt_for_new_path(&default_dir, Some(&file_name));
+        let entity = cx.entity().downgrade();
+
+        self._task = Some(cx.spawn(async move |_, cx| {
+            let result = match receiver.await {
+                Ok(Ok(Some(path))) => match std::fs::write(&path, json.as_bytes()) {
+                    Ok(_) => {
+                        log::info!(
+                            target: AUDIT_TARGET,
+                            "Provisioning report for {} device(s) exported to {} (key {})",
+                            signed.report.devices.len(),
+                            path.display(),
+                            signed.signature.key_id
+                        );
+                        metrics::record_feature("provisioning.report");
+                        Ok(format!("Report saved to {}", path.display()))
+                    }
+                    Err(e) => Err(format!("Failed to save report: {}", e)),
+                },
+                Ok(Err(e)) => Err(format!("Save dialog error: {}", e)),
+                _ => return,
+            };
+            let _ = entity.update(cx, |this, cx| {
+                this.session_result = Some(result);
+                cx.notify();
+            });
+        }));
+    }
+
+    /// Discard recorded devices and start a new provisioning session.
+    pub(super) fn reset_session(&mut self, cx: &mut Context<Self>) {
+        self.session = ProvisioningSession::new();
+        self.session_result = None;
+        cx.notify();
+    }
 }
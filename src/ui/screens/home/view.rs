@@ -1,11 +1,34 @@
-use crate::ui::components::{card::Card, page_view::PageView, tag::Tag};
-use crate::ui::models::device::{DeviceMethod, FidoDeviceInfo, FirmwareType, FullDeviceStatus};
-use crate::ui::screens::home::view_model::HomeViewModel;
+use crate::advisories::{Advisory, AffectedRange};
+use crate::release_notes;
+use crate::tunables::VendorRead;
+use crate::ui::components::firmware_update;
+use crate::ui::components::help::{self, HelpTopic};
+use crate::ui::components::{
+    card::Card,
+    page_view::PageView,
+    tag::Tag,
+    view_state::{self, EmptyState},
+};
+use crate::ui::models::device::{
+    BootselBoard, BootselChip, Compatibility, DeviceMethod, FidoDeviceInfo, FingerprintVerdict,
+    FirmwareType, FullDeviceStatus, PinRetries, UsbDescriptorInfo, WHITELABEL_BASE_ROW,
+    check_fingerprint,
+};
+use crate::ui::screens::home::view_model::{HomeCard, HomeViewModel, WHITELABEL_CONFIRM_PHRASE};
 use gpui::prelude::FluentBuilder;
 use gpui::*;
-use gpui_component::{ActiveTheme, StyledExt};
+use gpui_component::{
+    ActiveTheme, Disableable, Sizable, StyledExt,
+    button::{Button, ButtonVariants},
+    input::{Input, InputState},
+    select::Select,
+    switch::Switch,
+};
 use gpui_component::{Icon, IconName, Theme, h_flex, progress::Progress, v_flex};
 
+/// Hex digits of an unknown GetInfo field shown before it is cut off.
+const UNKNOWN_FIELD_PREVIEW: usize = 48;
+
 impl HomeViewModel {
     fn render_kv(
         label: &str,
@@ -35,16 +58,129 @@ impl HomeViewModel {
             )
     }
 
-    fn render_device_info(status: &FullDeviceStatus, theme: &Theme) -> impl IntoElement {
+    fn render_nickname_field(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        v_flex()
+            .gap_1()
+            .child(
+                div()
+                    .text_sm()
+                    .text_color(cx.theme().muted_foreground)
+                    .child("Nickname (stored on this computer only)"),
+            )
+            .child(
+                h_flex()
+                    .gap_2()
+                    .child(div().flex_1().child(Input::new(&self.nickname_input)))
+                    .child(Button::new("save-nickname").label("Save").on_click(
+                        cx.listener(|this, _, window, cx| this.save_nickname(window, cx)),
+                    )),
+            )
+    }
+
+    /// Refresh button for a card's header.
+    fn render_card_refresh(&self, card: HomeCard, cx: &mut Context<Self>) -> AnyElement {
+        let loading = self.cards.get(&card).is_some_and(|state| state.loading);
+        Button::new(SharedString::from(format!("refresh-card-{:?}", card)))
+            .ghost()
+            .small()
+            .icon(Icon::default().path("icons/refresh-cw.svg"))
+            .loading(loading)
+            .on_click(cx.listener(move |this, _, _, cx| this.refresh_card(card, cx)))
+            .into_any_element()
+    }
+
+    /// Why the last refresh of a card failed, shown at the top of the card.
+    fn render_card_error(error: Option<&str>, theme: &Theme) -> Option<impl IntoElement> {
+        error.map(|error| {
+            h_flex()
+                .gap_2()
+                .items_center()
+                .text_sm()
+                .text_color(theme.danger)
+                .child(Icon::new(IconName::TriangleAlert).size_4())
+                .child(format!("Refresh failed: {}", error))
+        })
+    }
+
+    /// Placeholder for a card whose slice is missing.
+    fn render_unavailable(text: &str, theme: &Theme) -> AnyElement {
+        div()
+            .text_sm()
+            .text_color(theme.muted_foreground)
+            .child(text.to_string())
+            .into_any_element()
+    }
+
+    fn render_row(label: &str, value: impl IntoElement, theme: &Theme) -> impl IntoElement {
+        h_flex()
+            .justify_between()
+            .items_center()
+            .flex_wrap()
+            .gap_1()
+            .child(
+                div()
+                    .text_color(theme.muted_foreground)
+                    .child(label.to_string()),
+            )
+            .child(value)
+    }
+
+    /// Who the key is: serial, firmware, USB identity and descriptors.
+    fn render_identity_card(
+        status: &FullDeviceStatus,
+        compatibility: Option<Compatibility>,
+        usb: Option<&UsbDescriptorInfo>,
+        nickname_field: AnyElement,
+        error: Option<&str>,
+        refresh: AnyElement,
+        theme: &Theme,
+    ) -> impl IntoElement {
         let info = &status.info;
         let config = &status.config;
+        let release_notes_button = release_notes::repository(&status.firmware_type).map(|_| {
+            let firmware = status.firmware_type.clone();
+            let installed = info.firmware_version.clone();
+            Button::new("firmware-release-notes")
+                .ghost()
+                .small()
+                .label("Release Notes")
+                .on_click(move |_, window, cx| {
+                    firmware_update::open_firmware_update(
+                        firmware.clone(),
+                        installed.clone(),
+                        window,
+                        cx,
+                    );
+                })
+        });
+        let (compat_tag, compat_note) = match compatibility {
+            Some(Compatibility::Tested) => (Some(Tag::new("Tested").active(true)), None),
+            Some(Compatibility::NewerThanTested) => (
+                Some(Tag::new("Newer than tested")),
+                Some(
+                    "This firmware is newer than the releases this version of PicoForge \
+                     was tested with. Some features may misbehave; check for an app update.",
+                ),
+            ),
+            Some(Compatibility::TooOld) => (
+                Some(Tag::new("Too old")),
+                Some(
+                    "This firmware is older than PicoForge supports. Upgrading the \
+                     firmware is recommended before changing its configuration.",
+                ),
+            ),
+            Some(Compatibility::Unknown) | None => (None, None),
+        };
 
         Card::new()
-            .title("Device Information")
+            .title("Identity")
             .icon(Icon::default().path("icons/cpu.svg"))
+            .header_right(refresh)
+            .children(Self::render_card_error(error, theme))
             .child(
                 v_flex()
                     .gap_6()
+                    .child(nickname_field)
                     .child(
                         div()
                             .grid()
@@ -58,7 +194,12 @@ impl HomeViewModel {
                             ))
                             .child(Self::render_kv(
                                 "Firmware Version",
-                                format!("v{}", info.firmware_version),
+                                h_flex()
+                                    .gap_2()
+                                    .items_center()
+                                    .child(format!("v{}", info.firmware_version))
+                                    .children(compat_tag)
+                                    .children(release_notes_button),
                                 theme,
                                 true,
                             ))
@@ -81,177 +222,165 @@ impl HomeViewModel {
                                 false,
                             )),
                     )
+                    .when_some(compat_note, |this, note| {
+                        this.child(div().text_sm().text_color(theme.warning).child(note))
+                    })
                     .child(div().h_px().bg(theme.border))
-                    .child(
-                        v_flex()
-                            .gap_2()
-                            .child(
-                                h_flex()
-                                    .justify_between()
-                                    .text_sm()
-                                    .child(
-                                        div()
-                                            .text_color(theme.muted_foreground)
-                                            .child("Flash Memory"),
-                                    )
-                                    .child(div().text_color(theme.foreground).child(
-                                        if let (Some(used), Some(total)) =
-                                            (info.flash_used, info.flash_total)
-                                        {
-                                            format!("{:.0} / {:.0} KB", used, total)
-                                        } else {
-                                            "Not Available".to_string()
-                                        },
-                                    )),
-                            )
-                            .when(
-                                info.flash_used.is_some() && info.flash_total.is_some(),
-                                |this| {
-                                    let used = info.flash_used.unwrap();
-                                    let total = info.flash_total.unwrap();
-                                    let flash_percent = (used as f32 / total as f32) * 100.0;
-                                    this.child(Progress::new().value(flash_percent))
-                                },
-                            ),
-                    ),
+                    .child(Self::render_usb_descriptors(usb, theme)),
             )
     }
 
-    fn render_fido_info(fido: Option<&FidoDeviceInfo>, theme: &Theme) -> impl IntoElement {
+    /// Flash usage and room for discoverable credentials.
+    fn render_storage_card(
+        status: &FullDeviceStatus,
+        fido: Option<&FidoDeviceInfo>,
+        stored_passkeys: Option<usize>,
+        error: Option<&str>,
+        refresh: AnyElement,
+        theme: &Theme,
+    ) -> impl IntoElement {
+        let info = &status.info;
+        let flash = info.flash_used.zip(info.flash_total);
+        let remaining = fido.and_then(|fido| fido.remaining_discoverable_credentials);
+
         Card::new()
-            .title("FIDO2 Information")
-            .icon(Icon::default().path("icons/shield.svg"))
-            .child(if let Some(fido) = fido {
+            .title("Storage")
+            .icon(Icon::default().path("icons/microchip.svg"))
+            .header_right(refresh)
+            .children(Self::render_card_error(error, theme))
+            .child(
                 v_flex()
                     .gap_3()
                     .text_sm()
-                    .child(
-                        h_flex()
-                            .justify_between()
-                            .items_center()
-                            .flex_wrap()
-                            .gap_1()
-                            .child(div().text_color(theme.muted_foreground).child("AAGUID"))
-                            .child(
-                                div()
-                                    .font_family("Mono")
-                                    .text_color(theme.foreground)
-                                    .child(fido.aaguid.clone()),
-                            ),
-                    )
-                    .child(
-                        h_flex()
-                            .justify_between()
-                            .items_center()
-                            .flex_wrap()
-                            .gap_1()
-                            .child(
-                                div()
-                                    .text_color(theme.muted_foreground)
-                                    .child("FIDO Versions"),
-                            )
-                            .child(div().text_color(theme.foreground).child(
-                                if fido.versions.is_empty() {
-                                    "N/A".to_string()
-                                } else {
-                                    fido.versions.join(" · ")
-                                },
-                            )),
+                    .child(Self::render_row(
+                        "Flash Memory",
+                        div().text_color(theme.foreground).child(match flash {
+                            Some((used, total)) => format!("{:.0} / {:.0} KB", used, total),
+                            None => "Not Available".to_string(),
+                        }),
+                        theme,
+                    ))
+                    .when_some(
+                        flash.filter(|(_, total)| *total > 0),
+                        |this, (used, total)| {
+                            this.child(Progress::new().value((used as f32 / total as f32) * 100.0))
+                        },
                     )
                     .child(div().h_px().bg(theme.border))
-                    .child(
-                        h_flex()
-                            .justify_between()
-                            .items_center()
-                            .child(div().text_color(theme.muted_foreground).child("PIN Set"))
-                            .child({
-                                let pin_set =
-                                    fido.options.get("clientPin").copied().unwrap_or(false);
-                                Tag::new(if pin_set { "Set" } else { "Not Set" }).active(pin_set)
+                    .child(Self::render_row(
+                        "Remaining Credentials",
+                        div()
+                            .font_medium()
+                            .text_color(theme.foreground)
+                            .child(remaining.map_or("Not reported".into(), |n| n.to_string())),
+                        theme,
+                    ))
+                    .when_some(stored_passkeys, |this, count| {
+                        this.child(Self::render_row(
+                            "Stored Passkeys",
+                            div()
+                                .font_medium()
+                                .text_color(theme.foreground)
+                                .child(count.to_string()),
+                            theme,
+                        ))
+                    }),
+            )
+    }
+
+    /// What the authenticator advertises in GetInfo.
+    fn render_capabilities_card(
+        fido: Option<&FidoDeviceInfo>,
+        error: Option<&str>,
+        refresh: AnyElement,
+        theme: &Theme,
+    ) -> impl IntoElement {
+        let option = |name: &str| {
+            fido.and_then(|f| f.options.get(name).copied())
+                .unwrap_or(false)
+        };
+
+        Card::new()
+            .title("Capabilities")
+            .icon(Icon::default().path("icons/shield.svg"))
+            .header_right(refresh)
+            .children(Self::render_card_error(error, theme))
+            .child(if let Some(fido) = fido {
+                v_flex()
+                    .gap_3()
+                    .text_sm()
+                    .child(Self::render_row(
+                        "AAGUID",
+                        div()
+                            .font_family("Mono")
+                            .text_color(theme.foreground)
+                            .child(fido.aaguid.clone()),
+                        theme,
+                    ))
+                    .child(Self::render_row(
+                        "FIDO Versions",
+                        div()
+                            .text_color(theme.foreground)
+                            .child(if fido.versions.is_empty() {
+                                "N/A".to_string()
+                            } else {
+                                fido.versions.join(" · ")
                             }),
-                    )
-                    .child(
-                        h_flex()
-                            .justify_between()
-                            .items_center()
-                            .child(
-                                div()
-                                    .text_color(theme.muted_foreground)
-                                    .child("Resident Keys"),
-                            )
-                            .child({
-                                let resident_keys_supported =
-                                    fido.options.get("rk").copied().unwrap_or(false);
-                                Tag::new(if resident_keys_supported {
-                                    "Supported"
-                                } else {
-                                    "Not Supported"
-                                })
-                                .active(resident_keys_supported)
+                        theme,
+                    ))
+                    .child(Self::render_row(
+                        "Firmware Build",
+                        match check_fingerprint(fido) {
+                            FingerprintVerdict::Known { build } => Tag::new(build).active(true),
+                            FingerprintVerdict::Mismatch { .. } => Tag::new("Unrecognised"),
+                            FingerprintVerdict::Ambiguous { .. } => Tag::new("Unverified"),
+                            FingerprintVerdict::UnknownAaguid => Tag::new("Third-party"),
+                        },
+                        theme,
+                    ))
+                    .child(div().h_px().bg(theme.border))
+                    .child(Self::render_row(
+                        "Resident Keys",
+                        Tag::new(if option("rk") {
+                            "Supported"
+                        } else {
+                            "Not Supported"
+                        })
+                        .active(option("rk")),
+                        theme,
+                    ))
+                    .child(Self::render_row(
+                        "Extensions",
+                        div()
+                            .text_color(theme.foreground)
+                            .child(if fido.extensions.is_empty() {
+                                "None".to_string()
+                            } else {
+                                fido.extensions.join(" · ")
                             }),
-                    )
-                    .child(
-                        h_flex()
-                            .justify_between()
-                            .items_center()
-                            .child(
-                                div()
-                                    .text_color(theme.muted_foreground)
-                                    .child("Min PIN Length"),
-                            )
-                            .child(
-                                div()
-                                    .font_medium()
-                                    .text_color(theme.foreground)
-                                    .child(fido.min_pin_length.to_string()),
-                            ),
-                    )
-                    .child(
-                        h_flex()
-                            .justify_between()
-                            .items_center()
-                            .child(
-                                div()
-                                    .text_color(theme.muted_foreground)
-                                    .child("Enterprise attestation"),
-                            )
-                            .child(div().font_medium().text_color(theme.foreground).child({
-                                let enterprise_attestation_set =
-                                    fido.options.get("ep").copied().unwrap_or(false);
-                                Tag::new(if enterprise_attestation_set {
-                                    "Set"
-                                } else {
-                                    "Not Set"
-                                })
-                                .active(enterprise_attestation_set)
-                            })),
-                    )
-                    .when(fido.remaining_discoverable_credentials.is_some(), |this| {
-                        this.child(
-                            h_flex()
-                                .justify_between()
-                                .items_center()
-                                .child(
-                                    div()
-                                        .text_color(theme.muted_foreground)
-                                        .child("Remaining Credentials"),
-                                )
-                                .child(
-                                    div().font_medium().text_color(theme.foreground).child(
-                                        fido.remaining_discoverable_credentials
-                                            .unwrap_or(0)
-                                            .to_string(),
-                                    ),
-                                ),
+                        theme,
+                    ))
+                    .children(fido.unknown_fields.iter().map(|field| {
+                        let mut cbor = hex::encode_upper(&field.cbor);
+                        if cbor.len() > UNKNOWN_FIELD_PREVIEW {
+                            cbor.truncate(UNKNOWN_FIELD_PREVIEW);
+                            cbor.push('…');
+                        }
+                        Self::render_row(
+                            &format!("Unknown vendor field {}", field.label()),
+                            div()
+                                .font_family("Mono")
+                                .text_color(theme.muted_foreground)
+                                .child(cbor),
+                            theme,
                         )
-                    })
+                    }))
                     .into_any_element()
             } else {
-                div()
-                    .text_sm()
-                    .text_color(theme.muted_foreground)
-                    .child("FIDO information not available")
-                    .into_any_element()
+                Self::render_unavailable(
+                    "FIDO information not available. Refresh to read it again.",
+                    theme,
+                )
             })
     }
 
@@ -359,20 +488,213 @@ impl HomeViewModel {
             })
     }
 
-    fn render_security_status(status: &FullDeviceStatus, theme: &Theme) -> impl IntoElement {
+    /// USB descriptors section of the identity card.
+    fn render_usb_descriptors(usb: Option<&UsbDescriptorInfo>, theme: &Theme) -> impl IntoElement {
+        let row = |label: &str, value: String| {
+            h_flex()
+                .justify_between()
+                .items_center()
+                .flex_wrap()
+                .gap_1()
+                .child(
+                    div()
+                        .text_color(theme.muted_foreground)
+                        .child(label.to_string()),
+                )
+                .child(
+                    div()
+                        .font_family("Mono")
+                        .text_color(theme.foreground)
+                        .child(value),
+                )
+        };
+
+        let heading = v_flex()
+            .gap_1()
+            .text_sm()
+            .font_semibold()
+            .child("USB Descriptors")
+            .child(
+                div()
+                    .font_normal()
+                    .text_color(theme.muted_foreground)
+                    .child("What the operating system currently sees"),
+            );
+
+        v_flex()
+            .gap_3()
+            .child(heading)
+            .child(if let Some(usb) = usb {
+                let not_reported = || "Not reported".to_string();
+                let mut content = v_flex()
+                    .gap_3()
+                    .text_sm()
+                    .child(row("VID:PID", format!("{:04X}:{:04X}", usb.vid, usb.pid)))
+                    .child(row(
+                        "bcdDevice",
+                        format!(
+                            "{:X}.{:02X}",
+                            usb.release_number >> 8,
+                            usb.release_number & 0xFF
+                        ),
+                    ))
+                    .child(row(
+                        "Product",
+                        usb.product.clone().unwrap_or_else(not_reported),
+                    ))
+                    .child(row(
+                        "Manufacturer",
+                        usb.manufacturer.clone().unwrap_or_else(not_reported),
+                    ))
+                    .child(row(
+                        "iSerial",
+                        usb.serial.clone().unwrap_or_else(not_reported),
+                    ))
+                    .child(row("Bus", usb.bus_type.clone()))
+                    .child(row(
+                        "bcdUSB",
+                        usb.usb_version
+                            .map(|bcd| format!("{:X}.{:02X}", bcd >> 8, bcd & 0xFF))
+                            .unwrap_or_else(not_reported),
+                    ))
+                    .child(div().h_px().bg(theme.border));
+
+                if !usb.usb_interfaces.is_empty() {
+                    content = content.child(
+                        div()
+                            .text_color(theme.muted_foreground)
+                            .child(format!("Interfaces ({})", usb.usb_interfaces.len())),
+                    );
+                    for itf in &usb.usb_interfaces {
+                        let in_use = usb
+                            .interfaces
+                            .iter()
+                            .any(|hid| hid.in_use && hid.interface_number == itf.number as i32);
+                        let class = format!(
+                            "{} ({:02X}/{:02X}/{:02X}){}",
+                            itf.class_name,
+                            itf.class,
+                            itf.subclass,
+                            itf.protocol,
+                            if in_use { " · in use" } else { "" }
+                        );
+                        content = content.child(row(&format!("#{}", itf.number), class));
+                        for ep in &itf.endpoints {
+                            let direction = if ep.address & 0x80 != 0 { "IN" } else { "OUT" };
+                            content = content.child(row(
+                                &format!("    EP 0x{:02X}", ep.address),
+                                format!(
+                                    "{} {} · {} bytes",
+                                    ep.transfer_type, direction, ep.max_packet_size
+                                ),
+                            ));
+                        }
+                    }
+                } else {
+                    content = content.child(
+                        div()
+                            .text_color(theme.muted_foreground)
+                            .child(format!("HID Interfaces ({})", usb.interfaces.len())),
+                    );
+                    for itf in &usb.interfaces {
+                        let number = if itf.interface_number >= 0 {
+                            format!("#{}", itf.interface_number)
+                        } else {
+                            "#?".to_string()
+                        };
+                        let class = if itf.in_use {
+                            format!("{} · in use", itf.class)
+                        } else {
+                            itf.class.clone()
+                        };
+                        content = content.child(row(&number, class));
+                    }
+                    content =
+                        content.child(div().text_xs().text_color(theme.muted_foreground).child(
+                            "The USB device could not be read, so only HID interfaces \
+                         are listed and endpoints are not shown.",
+                        ));
+                }
+
+                content.into_any_element()
+            } else {
+                div()
+                    .text_sm()
+                    .text_color(theme.muted_foreground)
+                    .child("No FIDO HID interface found")
+                    .into_any_element()
+            })
+    }
+
+    /// PIN and user verification state, plus boot protection on keys with
+    /// vendor management.
+    fn render_security_card(
+        status: &FullDeviceStatus,
+        fido: Option<&FidoDeviceInfo>,
+        pin_retries: Option<PinRetries>,
+        vendor_management: bool,
+        error: Option<&str>,
+        refresh: AnyElement,
+        theme: &Theme,
+    ) -> impl IntoElement {
+        let option = |name: &str| {
+            fido.and_then(|f| f.options.get(name).copied())
+                .unwrap_or(false)
+        };
+        let pin_set = option("clientPin");
+
         Card::new()
-            .title("Security Status")
+            .title("Security Posture")
             .icon(Icon::default().path("icons/shield-check.svg"))
+            .header_right(refresh)
+            .children(Self::render_card_error(error, theme))
             .child(
                 v_flex()
                     .gap_3()
                     .text_sm()
-                    .child(
-                        h_flex()
-                            .justify_between()
-                            .items_center()
-                            .child(div().text_color(theme.muted_foreground).child("Boot Mode"))
-                            .child(
+                    .child(Self::render_row(
+                        "PIN Set",
+                        Tag::new(if pin_set { "Set" } else { "Not Set" }).active(pin_set),
+                        theme,
+                    ))
+                    .when_some(pin_retries.filter(|_| pin_set), |this, retries| {
+                        this.child(Self::render_row(
+                            "PIN Attempts Left",
+                            div().font_medium().text_color(theme.foreground).child(
+                                if retries.power_cycle_required {
+                                    format!("{} (replug required)", retries.retries)
+                                } else {
+                                    retries.retries.to_string()
+                                },
+                            ),
+                            theme,
+                        ))
+                    })
+                    .when_some(fido, |this, fido| {
+                        this.child(Self::render_row(
+                            "Min PIN Length",
+                            div()
+                                .font_medium()
+                                .text_color(theme.foreground)
+                                .child(fido.min_pin_length.to_string()),
+                            theme,
+                        ))
+                    })
+                    .child(Self::render_row(
+                        "Always Require UV",
+                        Tag::new(if option("alwaysUv") { "On" } else { "Off" })
+                            .active(option("alwaysUv")),
+                        theme,
+                    ))
+                    .child(Self::render_row(
+                        "Enterprise attestation",
+                        Tag::new(if option("ep") { "Set" } else { "Not Set" }).active(option("ep")),
+                        theme,
+                    ))
+                    .when(vendor_management, |this| {
+                        this.child(div().h_px().bg(theme.border))
+                            .child(Self::render_row(
+                                "Boot Mode",
                                 h_flex()
                                     .gap_2()
                                     .items_center()
@@ -395,85 +717,756 @@ impl HomeViewModel {
                                         })
                                         .active(status.secure_boot),
                                     ),
-                            ),
+                                theme,
+                            ))
+                            .child(Self::render_row(
+                                "Debug Interface",
+                                div().font_medium().text_color(theme.foreground).child(
+                                    if status.secure_boot_locked {
+                                        "Read-out Locked"
+                                    } else {
+                                        "Debug Enabled"
+                                    },
+                                ),
+                                theme,
+                            ))
+                            .child(Self::render_row(
+                                "Secure Lock",
+                                Tag::new(if status.secure_lock { "Enabled" } else { "Off" })
+                                    .active(status.secure_lock),
+                                theme,
+                            ))
+                    }),
+            )
+    }
+}
+
+impl HomeViewModel {
+    /// What PicoForge saw happen to this key, newest first.
+    fn render_activity_card(
+        timeline: &[(u64, String)],
+        refresh: AnyElement,
+        theme: &Theme,
+    ) -> impl IntoElement {
+        let body = if timeline.is_empty() {
+            div()
+                .text_sm()
+                .text_color(theme.muted_foreground)
+                .child(
+                    "Nothing recorded yet. Configuration writes, PIN changes, firmware \
+                     updates and the passkey count show up here.",
+                )
+                .into_any_element()
+        } else {
+            v_flex()
+                .gap_2()
+                .text_sm()
+                .children(timeline.iter().map(|(at, text)| {
+                    h_flex()
+                        .justify_between()
+                        .items_center()
+                        .gap_4()
+                        .child(div().text_color(theme.foreground).child(text.clone()))
+                        .child(
+                            div()
+                                .text_xs()
+                                .text_color(theme.muted_foreground)
+                                .child(release_notes::age(*at)),
+                        )
+                }))
+                .into_any_element()
+        };
+
+        Card::new()
+            .title("Recent Activity")
+            .icon(Icon::default().path("icons/scroll-text.svg"))
+            .description("Recorded by PicoForge on this computer")
+            .header_right(refresh)
+            .child(body)
+    }
+}
+
+impl HomeViewModel {
+    /// "No device" landing: connect instructions plus the last poll error, if any.
+    /// Banner for devices whose GetInfo matches no known official build.
+    fn render_fingerprint_warning(
+        family: &str,
+        reasons: &[String],
+        theme: &Theme,
+    ) -> impl IntoElement {
+        h_flex()
+            .gap_3()
+            .items_start()
+            .p_4()
+            .mb_6()
+            .border_1()
+            .border_color(theme.warning)
+            .rounded_xl()
+            .child(
+                Icon::new(IconName::TriangleAlert)
+                    .size_5()
+                    .text_color(theme.warning),
+            )
+            .child(
+                v_flex()
+                    .gap_1()
+                    .text_sm()
+                    .child(
+                        div()
+                            .font_medium()
+                            .child(format!("This device does not match any official {} build", family)),
                     )
                     .child(
-                        h_flex()
-                            .justify_between()
-                            .items_center()
-                            .child(
+                        div()
+                            .text_color(theme.muted_foreground)
+                            .child("The firmware may have been modified. If the key came pre-flashed from a third party, reflash it with an official release before storing credentials on it."),
+                    )
+                    .children(reasons.iter().map(|reason| {
+                        div()
+                            .text_color(theme.muted_foreground)
+                            .child(format!("• {}", reason))
+                    })),
+            )
+    }
+
+    /// Banner for keys whose recent transport error rate is high.
+    fn render_health_warning(hint: &str, theme: &Theme) -> impl IntoElement {
+        h_flex()
+            .gap_3()
+            .items_start()
+            .p_4()
+            .mb_6()
+            .border_1()
+            .border_color(theme.warning)
+            .rounded_xl()
+            .child(
+                Icon::new(IconName::TriangleAlert)
+                    .size_5()
+                    .text_color(theme.warning),
+            )
+            .child(div().text_sm().child(hint.to_string()))
+    }
+
+    /// Banner for a published security advisory that applies to the
+    /// connected firmware.
+    fn render_advisory_warning(advisory: &Advisory, firmware: &str, theme: &Theme) -> AnyElement {
+        let ranges = advisory
+            .affected
+            .iter()
+            .map(AffectedRange::describe)
+            .collect::<Vec<_>>()
+            .join(", ");
+        h_flex()
+            .gap_3()
+            .items_start()
+            .p_4()
+            .mb_6()
+            .border_1()
+            .border_color(theme.warning)
+            .rounded_xl()
+            .child(
+                Icon::new(IconName::TriangleAlert)
+                    .size_5()
+                    .text_color(theme.warning),
+            )
+            .child(
+                v_flex()
+                    .gap_1()
+                    .text_sm()
+                    .child(div().font_medium().child(format!(
+                        "Security advisory {}: {}",
+                        advisory.id, advisory.title
+                    )))
+                    .when(!advisory.summary.is_empty(), |this| {
+                        this.child(
+                            div()
+                                .text_color(theme.muted_foreground)
+                                .child(advisory.summary.clone()),
+                        )
+                    })
+                    .child(div().text_color(theme.muted_foreground).child(format!(
+                        "Affects {} {}; this key runs {}.",
+                        advisory.firmware, ranges, firmware
+                    )))
+                    .when_some(advisory.url.clone(), |this, url| {
+                        this.child(
+                            h_flex().child(
+                                Button::new(SharedString::from(format!(
+                                    "advisory-{}",
+                                    advisory.id
+                                )))
+                                .ghost()
+                                .small()
+                                .label("Read the advisory")
+                                .on_click(move |_, _, cx| cx.open_url(&url)),
+                            ),
+                        )
+                    }),
+            )
+            .into_any_element()
+    }
+
+    /// Banner listing the attached keys when there is more than one, with a
+    /// way to pick the one to manage by touching it.
+    fn render_key_picker(&self, cx: &mut Context<Self>) -> Option<AnyElement> {
+        let device = self.device.read(cx);
+        if device.fido_devices.len() < 2 {
+            return None;
+        }
+        let theme = cx.theme();
+        let keys = device.fido_devices.iter().map(|key| {
+            let active = device.active_device_path.as_deref() == Some(key.path.as_str());
+            h_flex()
+                .gap_2()
+                .child(div().child(format!("{:04X}:{:04X}", key.vid, key.pid)))
+                .child(div().text_color(theme.muted_foreground).child(format!(
+                    "{}{}",
+                    key.product,
+                    key.serial
+                        .as_deref()
+                        .map(|s| format!(" · {}", s))
+                        .unwrap_or_default()
+                )))
+                .when(active, |this| this.child(Tag::new("Managing").active(true)))
+        });
+        let result = self.selection_result.as_ref().map(|result| match result {
+            Ok(product) => div()
+                .text_color(theme.green)
+                .child(format!("Now managing {}.", product)),
+            Err(e) => div()
+                .text_color(theme.danger)
+                .child(format!("No key selected: {}", e)),
+        });
+
+        Some(
+            h_flex()
+                .gap_3()
+                .items_start()
+                .p_4()
+                .mb_6()
+                .border_1()
+                .border_color(theme.border)
+                .rounded_xl()
+                .child(
+                    Icon::new(IconName::Info)
+                        .size_5()
+                        .text_color(theme.muted_foreground),
+                )
+                .child(
+                    v_flex()
+                        .flex_1()
+                        .gap_1()
+                        .text_sm()
+                        .child(div().font_medium().child(format!(
+                            "{} security keys are connected",
+                            device.fido_devices.len()
+                        )))
+                        .children(keys)
+                        .when(self.selecting, |this| {
+                            this.child(
                                 div()
                                     .text_color(theme.muted_foreground)
-                                    .child("Debug Interface"),
+                                    .child("Touch the key you want to manage…"),
                             )
-                            .child(div().font_medium().text_color(theme.foreground).child(
-                                if status.secure_lock {
-                                    "Read-out Locked"
-                                } else {
-                                    "Debug Enabled"
-                                },
-                            )),
+                        })
+                        .children(result),
+                )
+                .child(
+                    Button::new("select-key-by-touch")
+                        .small()
+                        .label("Choose by touch")
+                        .loading(self.selecting)
+                        .disabled(self.selecting)
+                        .tooltip(
+                            "Asks every key for a touch; the one you touch is managed from now on",
+                        )
+                        .on_click(cx.listener(|this, _, _, cx| this.select_device(cx))),
+                )
+                .into_any_element(),
+        )
+    }
+
+    fn render_not_pico_notice(fido: Option<&FidoDeviceInfo>, theme: &Theme) -> impl IntoElement {
+        let aaguid = fido.map(|f| f.aaguid.clone()).unwrap_or_default();
+
+        h_flex()
+            .gap_3()
+            .items_start()
+            .p_4()
+            .mb_6()
+            .border_1()
+            .border_color(theme.border)
+            .rounded_xl()
+            .child(
+                Icon::new(IconName::Info)
+                    .size_5()
+                    .text_color(theme.muted_foreground),
+            )
+            .child(
+                v_flex()
+                    .gap_1()
+                    .text_sm()
+                    .child(div().font_medium().child("This is not a pico-fido device"))
+                    .child(div().text_color(theme.muted_foreground).child(
+                        "It answers standard FIDO2 requests but not the pico-fido vendor \
+                         commands, so configuration, LED, secure boot and backup are \
+                         unavailable. Basic FIDO information is shown below; passkeys can \
+                         still be managed if the key supports it, and Security can reset it.",
+                    ))
+                    .when(!aaguid.is_empty(), |this| {
+                        this.child(
+                            div()
+                                .text_color(theme.muted_foreground)
+                                .child(format!("AAGUID: {}", aaguid)),
+                        )
+                    }),
+            )
+    }
+
+    fn render_bootsel_board(board: &BootselBoard, theme: &Theme) -> impl IntoElement {
+        let chip = match board.chip {
+            BootselChip::Rp2040 => "RP2040",
+            BootselChip::Rp2350 => "RP2350",
+            BootselChip::Unknown => "Unknown chip",
+        };
+        let whitelabel = match board.whitelabel {
+            Some(true) => "Whitelabel: yes",
+            Some(false) => "Whitelabel: no",
+            None => "Whitelabel: unknown",
+        };
+        let mut details = Vec::new();
+        if let Some(revision) = &board.chip_revision {
+            details.push(format!("Revision {}", revision));
+        }
+        details.push(match board.flash_size {
+            Some(size) if size >= 1024 * 1024 => format!("{} MiB flash", size / (1024 * 1024)),
+            Some(size) => format!("{} KiB flash", size / 1024),
+            None if board.chip_revision.is_some() => "Flash blank or unreadable".to_string(),
+            None => "Flash size unknown (PICOBOOT not reachable)".to_string(),
+        });
+        if !board.board_id.is_empty() {
+            details.push(format!("Board ID {}", board.board_id));
+        }
+        if !board.bootloader.is_empty() {
+            details.push(board.bootloader.clone());
+        }
+        details.push(match &board.mount_point {
+            Some(mount) => format!("mounted at {}", mount.display()),
+            None => "drive not mounted".to_string(),
+        });
+
+        v_flex()
+            .gap_1()
+            .p_3()
+            .w_full()
+            .border_1()
+            .border_color(theme.border)
+            .rounded_lg()
+            .child(
+                h_flex()
+                    .gap_2()
+                    .child(div().text_sm().font_bold().child(board.model.clone()))
+                    .child(Tag::new(chip))
+                    .child(Tag::new(whitelabel).active(board.whitelabel == Some(true))),
+            )
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(theme.muted_foreground)
+                    .child(details.join(" · ")),
+            )
+    }
+
+    fn render_no_device(
+        error: Option<&str>,
+        bootsel_boards: &[BootselBoard],
+        theme: &Theme,
+    ) -> impl IntoElement {
+        let steps = [
+            "Plug your pico-fido or RS-Key security key into a USB port.",
+            "Close other apps that may hold the key open (browsers, gpg-agent).",
+            "Press Refresh in the sidebar. New keys are also picked up automatically.",
+        ];
+
+        EmptyState::new(
+            Icon::default().path("icons/hard-drive.svg"),
+            "No Device Connected",
+        )
+        .child(
+            v_flex()
+                .gap_1()
+                .text_sm()
+                .text_color(theme.muted_foreground)
+                .children(
+                    steps
+                        .iter()
+                        .enumerate()
+                        .map(|(ix, step)| div().child(format!("{}. {}", ix + 1, step))),
+                ),
+        )
+        .when_some(error, |this, error| {
+            this.child(
+                div()
+                    .max_w(px(520.))
+                    .text_xs()
+                    .text_color(theme.muted_foreground)
+                    .child(format!("Last attempt: {}", error)),
+            )
+        })
+        .when(!bootsel_boards.is_empty(), |this| {
+            this.child(
+                v_flex()
+                    .gap_2()
+                    .w(px(520.))
+                    .child(
+                        div()
+                            .text_sm()
+                            .font_semibold()
+                            .child("Boards in BOOTSEL mode"),
                     )
                     .child(
-                        h_flex()
-                            .justify_between()
-                            .items_center()
-                            .child(
-                                div()
-                                    .text_color(theme.muted_foreground)
-                                    .child("Secure Lock"),
-                            )
-                            .child(
-                                Tag::new(if status.secure_lock {
-                                    "Acknowledged"
+                        div()
+                            .text_xs()
+                            .text_color(theme.muted_foreground)
+                            .child("These boards are in the boot loader and can be flashed."),
+                    )
+                    .children(
+                        bootsel_boards
+                            .iter()
+                            .map(|board| Self::render_bootsel_board(board, theme)),
+                    ),
+            )
+        })
+        .child(help::help_link(
+            HelpTopic::DeviceOffline,
+            "Still not detected? Open the troubleshooting guide",
+        ))
+    }
+
+    fn render_whitelabel_wizard(&mut self, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+        let confirmed = self.whitelabel_confirmed(cx);
+        let busy = self.whitelabel_busy;
+        let form = &self.whitelabel_form;
+
+        let field = |label: &str, input: &Entity<InputState>| {
+            v_flex()
+                .gap_1()
+                .flex_1()
+                .child(div().text_sm().font_medium().child(label.to_string()))
+                .child(Input::new(input))
+        };
+        let result = self.whitelabel_result.clone().map(|result| {
+            let (msg, color) = match result {
+                Ok(msg) => (msg, theme.muted_foreground),
+                Err(msg) => (msg, theme.danger),
+            };
+            div().text_sm().text_color(color).child(msg)
+        });
+
+        let body = v_flex()
+            .gap_3()
+            .child(div().text_sm().text_color(theme.muted_foreground).child(
+                "The RP2350 boot ROM can report a custom USB identity and drive \
+                         label from its OTP whitelabel block. Unlike the product name in \
+                         the firmware configuration, it survives every reflash.",
+            ))
+            .child(div().text_sm().text_color(theme.danger).child(
+                "OTP can only be written once. A burned identity cannot be changed \
+                     or removed, and mistakes stay on the chip forever. Keep only the \
+                     board to write attached, and try it on a spare board first.",
+            ))
+            .child(
+                h_flex()
+                    .gap_3()
+                    .child(field("Manufacturer", &form.manufacturer))
+                    .child(field("Product", &form.product)),
+            )
+            .child(
+                h_flex()
+                    .gap_3()
+                    .child(field("Serial number", &form.serial_number))
+                    .child(field("Drive label", &form.volume_label)),
+            )
+            .child(
+                h_flex()
+                    .gap_3()
+                    .child(field("Boot VID", &form.vid))
+                    .child(field("Boot PID", &form.pid)),
+            )
+            .child(field(
+                &format!("Type {} to confirm", WHITELABEL_CONFIRM_PHRASE),
+                &form.confirm,
+            ))
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(theme.muted_foreground)
+                    .child(format!(
+                        "The identity is written over PICOBOOT to OTP rows from 0x{:03X}, read \
+                         back, and only then switched on in USB_BOOT_FLAGS.",
+                        WHITELABEL_BASE_ROW
+                    )),
+            )
+            .children(result)
+            .child(
+                h_flex().justify_end().child(
+                    Button::new("whitelabel-burn")
+                        .danger()
+                        .label("Burn to OTP")
+                        .loading(busy)
+                        .disabled(!confirmed || busy)
+                        .on_click(cx.listener(|this, _, _, cx| this.burn_whitelabel(cx))),
+                ),
+            );
+
+        Card::new()
+            .title("White-label RP2350")
+            .description("Burn a permanent boot identity into the chip's OTP.")
+            .icon(Icon::new(IconName::TriangleAlert))
+            .child(
+                v_flex()
+                    .gap_3()
+                    .child(
+                        h_flex().justify_end().child(
+                            Button::new("whitelabel-toggle")
+                                .ghost()
+                                .label(if self.whitelabel_open {
+                                    "Close"
                                 } else {
-                                    "Pending"
+                                    "Open wizard"
                                 })
-                                .active(status.secure_lock),
+                                .on_click(cx.listener(|this, _, _, cx| this.toggle_whitelabel(cx))),
+                        ),
+                    )
+                    .when(self.whitelabel_open, |this| this.child(body)),
+            )
+    }
+
+    /// Transport order, timeouts and skipped vendor reads for the connected
+    /// key, stored in the inventory by serial.
+    fn render_overrides_card(&mut self, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+        let form = &self.overrides_form;
+
+        let field = |label: &str, input: &Entity<InputState>| {
+            v_flex()
+                .gap_1()
+                .flex_1()
+                .child(div().text_sm().font_medium().child(label.to_string()))
+                .child(Input::new(input))
+        };
+        let reads = VendorRead::ALL.map(|read| {
+            let disabled = form.disabled_reads.contains(&read);
+            h_flex()
+                .justify_between()
+                .items_center()
+                .child(
+                    div()
+                        .text_sm()
+                        .child(format!("Skip {}", read.label().to_lowercase())),
+                )
+                .child(
+                    Switch::new(SharedString::from(format!("skip-read-{:?}", read)))
+                        .checked(disabled)
+                        .on_click(cx.listener(move |this, checked, _, cx| {
+                            this.set_vendor_read_disabled(read, *checked, cx);
+                        })),
+                )
+        });
+
+        let body = v_flex()
+            .gap_3()
+            .child(div().text_sm().text_color(theme.muted_foreground).child(
+                "For firmware builds that need a fixed transport, more time to \
+                 answer, or that misbehave on a vendor command. Applied whenever \
+                 this key is connected; leave a field empty to use picoforge.toml.",
+            ))
+            .child(
+                v_flex()
+                    .gap_1()
+                    .child(div().text_sm().font_medium().child("Transport order"))
+                    .child(Select::new(&form.transport).w_full()),
+            )
+            .child(
+                h_flex()
+                    .gap_3()
+                    .flex_wrap()
+                    .child(field("Exchange timeout (ms)", &form.total_timeout))
+                    .child(field("Response timeout (ms)", &form.response_timeout))
+                    .child(field("Touch timeout (ms)", &form.touch_timeout)),
+            )
+            .children(reads)
+            .child(
+                h_flex()
+                    .justify_end()
+                    .gap_2()
+                    .child(
+                        Button::new("overrides-clear")
+                            .ghost()
+                            .label("Clear")
+                            .on_click(
+                                cx.listener(|this, _, window, cx| this.clear_overrides(window, cx)),
+                            ),
+                    )
+                    .child(
+                        Button::new("overrides-save")
+                            .primary()
+                            .label("Save")
+                            .on_click(
+                                cx.listener(|this, _, window, cx| this.save_overrides(window, cx)),
                             ),
                     ),
+            );
+
+        Card::new()
+            .title("Connection Overrides")
+            .description("Per-key workarounds, stored on this computer")
+            .icon(Icon::default().path("icons/settings-2.svg"))
+            .child(
+                v_flex()
+                    .gap_3()
+                    .child(
+                        h_flex().justify_end().child(
+                            Button::new("overrides-toggle")
+                                .ghost()
+                                .label(if self.overrides_open { "Close" } else { "Edit" })
+                                .on_click(cx.listener(|this, _, _, cx| this.toggle_overrides(cx))),
+                        ),
+                    )
+                    .when(self.overrides_open, |this| this.child(body)),
             )
     }
 }
 
 impl Render for HomeViewModel {
     fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        // Only stock RP2350s: OTP rows that were already burned cannot be rewritten.
+        let offer_whitelabel = self
+            .device
+            .read(cx)
+            .bootsel_boards
+            .iter()
+            .any(|b| b.chip == BootselChip::Rp2350 && b.whitelabel == Some(false));
+        let whitelabel = offer_whitelabel.then(|| self.render_whitelabel_wizard(cx));
+
+        let nickname_field = self.render_nickname_field(cx).into_any_element();
+        let identify = Button::new("identify-device")
+            .ghost()
+            .small()
+            .label("Identify Device")
+            .loading(self.identifying)
+            .disabled(self.identifying)
+            .tooltip("Blinks the LED of the key being managed")
+            .on_click(cx.listener(|this, _, _, cx| this.identify_device(cx)));
+        let mut refresh = |card| self.render_card_refresh(card, cx);
+        let identity_refresh = h_flex()
+            .gap_1()
+            .child(identify)
+            .child(refresh(HomeCard::Identity))
+            .into_any_element();
+        let storage_refresh = refresh(HomeCard::Storage);
+        let security_refresh = refresh(HomeCard::Security);
+        let capabilities_refresh = refresh(HomeCard::Capabilities);
+        let activity_refresh = refresh(HomeCard::Activity);
+        let error = |card: HomeCard| self.cards.get(&card).and_then(|s| s.error.as_deref());
+        let key_picker = self.render_key_picker(cx);
+        let overrides_card = self.render_overrides_card(cx).into_any_element();
+
         let device = self.device.read(cx);
-        let connected = device.status.is_some();
+        let no_device =
+            Self::render_no_device(device.error.as_deref(), &device.bootsel_boards, cx.theme());
+        let placeholder = view_state::for_phase(&device.phase(), no_device, &self.device);
         let is_wide = window.bounds().size.width > px(1100.0);
         let columns = if is_wide { 2 } else { 1 };
 
         PageView::build(
             "Device Overview",
             "Quick view of your device status and specifications.",
-            if !connected {
-                div()
-                    .flex()
-                    .items_center()
-                    .justify_center()
-                    .h_64()
-                    .border_1()
-                    .border_color(cx.theme().border)
-                    .rounded_xl()
-                    .child(
-                        div()
-                            .text_color(cx.theme().muted_foreground)
-                            .child("No Device Connected"),
-                    )
+            if let Some(placeholder) = placeholder {
+                v_flex()
+                    .gap_6()
+                    .child(placeholder)
+                    .children(whitelabel)
                     .into_any_element()
             } else {
                 let status = device.status.as_ref().unwrap();
-                div()
+                let fido = device.fido_info.as_ref();
+                let verdict = fido
+                    .map(check_fingerprint)
+                    .filter(FingerprintVerdict::is_suspicious);
+                let vendor_management = device.capabilities().vendor_management;
+                let grid = div()
                     .grid()
                     .grid_cols(columns)
                     .gap_6()
-                    .child(Self::render_device_info(status, cx.theme()))
-                    .child(Self::render_fido_info(
-                        device.fido_info.as_ref(),
+                    .child(Self::render_identity_card(
+                        status,
+                        device.compatibility(),
+                        device.usb_descriptors.as_ref(),
+                        nickname_field,
+                        error(HomeCard::Identity),
+                        identity_refresh,
+                        cx.theme(),
+                    ))
+                    .child(Self::render_storage_card(
+                        status,
+                        fido,
+                        device.credentials.as_ref().map(Vec::len),
+                        error(HomeCard::Storage),
+                        storage_refresh,
+                        cx.theme(),
+                    ))
+                    .child(Self::render_security_card(
+                        status,
+                        fido,
+                        self.pin_retries,
+                        vendor_management,
+                        error(HomeCard::Security),
+                        security_refresh,
                         cx.theme(),
                     ))
-                    .child(Self::render_led_config(status, cx.theme()))
-                    .child(Self::render_security_status(status, cx.theme()))
+                    .child(Self::render_capabilities_card(
+                        fido,
+                        error(HomeCard::Capabilities),
+                        capabilities_refresh,
+                        cx.theme(),
+                    ))
+                    .when(vendor_management, |grid| {
+                        grid.child(Self::render_led_config(status, cx.theme()))
+                    })
+                    .child(Self::render_activity_card(
+                        &device.timeline,
+                        activity_refresh,
+                        cx.theme(),
+                    ))
+                    .child(overrides_card);
+
+                v_flex()
+                    .children(key_picker)
+                    .when(!vendor_management, |this| {
+                        this.child(Self::render_not_pico_notice(
+                            device.fido_info.as_ref(),
+                            cx.theme(),
+                        ))
+                    })
+                    .when_some(verdict, |this, verdict| match verdict {
+                        FingerprintVerdict::Mismatch { family, reasons } => this.child(
+                            Self::render_fingerprint_warning(&family, &reasons, cx.theme()),
+                        ),
+                        _ => this,
+                    })
+                    .when_some(device.health_hint.as_deref(), |this, hint| {
+                        this.child(Self::render_health_warning(hint, cx.theme()))
+                    })
+                    .children(device.advisories.iter().map(|advisory| {
+                        Self::render_advisory_warning(
+                            advisory,
+                            &status.info.firmware_version,
+                            cx.theme(),
+                        )
+                    }))
+                    .child(grid)
                     .into_any_element()
             },
             cx.theme(),
@@ -1,19 +1,608 @@
 //! View model for the home screen — tracks device connection state and polling.
+//!
+//! The dashboard is split into [`HomeCard`]s, each showing one slice of the
+//! [`DeviceRepo`] state. A card can re-read just its slice, and a failed read
+//! is shown on that card only, so one unavailable source (e.g. flash stats on
+//! firmware without the Memory command) leaves the rest of the page intact.
+//!
+//! Also hosts the RP2350 whitelabel wizard shown for boards in BOOTSEL mode,
+//! and the connection overrides of the connected key.
 
+use crate::logging::AUDIT_TARGET;
+use crate::tunables::{self, DeviceOverrides, Transport, VendorRead};
 use crate::ui::app::AppModels;
-use crate::ui::models::device::{DeviceEvent, DeviceRepo};
+use crate::ui::models::device::{
+    DeviceEvent, DeviceMethod, DeviceRepo, FidoDeviceInfo, PinRetries, UsbDescriptorInfo,
+    Whitelabel,
+};
 use gpui::*;
+use gpui_component::WindowExt;
+use gpui_component::input::{InputEvent, InputState};
+use gpui_component::select::{SelectItem, SelectState};
+use std::collections::HashMap;
+
+/// Phrase the user must type before the whitelabel is burned.
+pub(super) const WHITELABEL_CONFIRM_PHRASE: &str = "BURN OTP";
+
+/// Inputs of the whitelabel wizard.
+pub(super) struct WhitelabelForm {
+    pub(super) manufacturer: Entity<InputState>,
+    pub(super) product: Entity<InputState>,
+    pub(super) serial_number: Entity<InputState>,
+    pub(super) vid: Entity<InputState>,
+    pub(super) pid: Entity<InputState>,
+    pub(super) volume_label: Entity<InputState>,
+    pub(super) confirm: Entity<InputState>,
+}
+
+/// Transport order choices offered for a key's overrides.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum TransportChoice {
+    /// `transport.order` from `picoforge.toml`.
+    Default,
+    RescueFirst,
+    FidoFirst,
+    FidoOnly,
+    RescueOnly,
+}
+
+impl TransportChoice {
+    pub(super) const ALL: [TransportChoice; 5] = [
+        TransportChoice::Default,
+        TransportChoice::RescueFirst,
+        TransportChoice::FidoFirst,
+        TransportChoice::FidoOnly,
+        TransportChoice::RescueOnly,
+    ];
+
+    fn order(self) -> Option<Vec<Transport>> {
+        match self {
+            Self::Default => None,
+            Self::RescueFirst => Some(vec![Transport::Rescue, Transport::Fido]),
+            Self::FidoFirst => Some(vec![Transport::Fido, Transport::Rescue]),
+            Self::FidoOnly => Some(vec![Transport::Fido]),
+            Self::RescueOnly => Some(vec![Transport::Rescue]),
+        }
+    }
+
+    fn from_order(order: Option<&[Transport]>) -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|choice| choice.order().as_deref() == order)
+            .unwrap_or(Self::Default)
+    }
+}
+
+impl SelectItem for TransportChoice {
+    type Value = TransportChoice;
+
+    fn title(&self) -> SharedString {
+        match self {
+            Self::Default => "Default (picoforge.toml)",
+            Self::RescueFirst => "Rescue, then FIDO",
+            Self::FidoFirst => "FIDO, then Rescue",
+            Self::FidoOnly => "FIDO only",
+            Self::RescueOnly => "Rescue only",
+        }
+        .into()
+    }
+
+    fn value(&self) -> &Self::Value {
+        self
+    }
+}
+
+/// Inputs of the connection overrides card.
+pub(super) struct OverridesForm {
+    pub(super) transport: Entity<SelectState<Vec<TransportChoice>>>,
+    pub(super) total_timeout: Entity<InputState>,
+    pub(super) response_timeout: Entity<InputState>,
+    pub(super) touch_timeout: Entity<InputState>,
+    /// Vendor reads switched off for the key.
+    pub(super) disabled_reads: Vec<VendorRead>,
+}
+
+/// Independently refreshable sections of the dashboard.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(super) enum HomeCard {
+    /// Serial, firmware, USB identity and descriptors.
+    Identity,
+    /// Flash usage and discoverable-credential capacity.
+    Storage,
+    /// Secure boot, PIN and user-verification state.
+    Security,
+    /// GetInfo: versions, options and build fingerprint.
+    Capabilities,
+    /// Timeline recorded in the inventory.
+    Activity,
+}
+
+impl HomeCard {
+    pub(super) fn label(self) -> &'static str {
+        match self {
+            Self::Identity => "Identity",
+            Self::Storage => "Storage",
+            Self::Security => "Security posture",
+            Self::Capabilities => "Capabilities",
+            Self::Activity => "Recent activity",
+        }
+    }
+}
+
+/// Refresh state of one [`HomeCard`].
+#[derive(Default)]
+pub(super) struct CardState {
+    pub(super) loading: bool,
+    /// Why the last refresh of this card failed.
+    pub(super) error: Option<String>,
+}
+
+/// Result of a background read for one card.
+enum CardData {
+    Identity(UsbDescriptorInfo),
+    Storage(Option<(u32, u32)>),
+    Security(PinRetries),
+    Capabilities(FidoDeviceInfo),
+}
+
+impl CardData {
+    /// Read the slice behind `card` (blocking). `Activity` is local and never
+    /// read here.
+    fn read(card: HomeCard, method: Option<DeviceMethod>) -> Result<Self, String> {
+        match card {
+            HomeCard::Identity => DeviceRepo::read_usb_descriptors_blocking()
+                .map(Self::Identity)
+                .ok_or_else(|| "No FIDO HID interface found".to_string()),
+            HomeCard::Storage => {
+                let method = method.ok_or_else(|| "No device connected".to_string())?;
+                DeviceRepo::read_flash_usage_blocking(method).map(Self::Storage)
+            }
+            HomeCard::Security => DeviceRepo::get_pin_retries_blocking().map(Self::Security),
+            HomeCard::Capabilities => DeviceRepo::get_fido_info_blocking().map(Self::Capabilities),
+            HomeCard::Activity => unreachable!("activity is read from the inventory"),
+        }
+    }
+}
 
 /// Application state and device-detection polling for the home screen.
 pub struct HomeViewModel {
     pub device: Entity<DeviceRepo>,
+    pub(super) whitelabel_open: bool,
+    pub(super) whitelabel_form: WhitelabelForm,
+    /// The whitelabel is being burned.
+    pub(super) whitelabel_busy: bool,
+    /// Result of the last whitelabel burn.
+    pub(super) whitelabel_result: Option<Result<String, String>>,
+    /// Nickname of the connected device, saved in the inventory.
+    pub(super) nickname_input: Entity<InputState>,
+    /// Serial the nickname input and card states were last reset for.
+    nickname_serial: Option<String>,
+    /// Refresh state per card; a card with no entry has never been refreshed.
+    pub(super) cards: HashMap<HomeCard, CardState>,
+    /// PIN attempts left, read for the security posture card.
+    pub(super) pin_retries: Option<PinRetries>,
+    /// Waiting for a touch on one of several attached keys.
+    pub(super) selecting: bool,
+    /// Product name of the key picked by touch, or why none was.
+    pub(super) selection_result: Option<Result<String, String>>,
+    /// Waiting for the key to answer CTAPHID_WINK.
+    pub(super) identifying: bool,
+    pub(super) overrides_open: bool,
+    pub(super) overrides_form: OverridesForm,
+    _subscriptions: Vec<Subscription>,
+    /// Own slot so no other action on this screen can cancel a burn.
+    _whitelabel_task: Option<Task<()>>,
+    _task: Option<Task<()>>,
 }
 
 impl HomeViewModel {
-    pub fn new(_window: &mut Window, cx: &mut Context<Self>, models: &AppModels) -> Self {
+    pub fn new(window: &mut Window, cx: &mut Context<Self>, models: &AppModels) -> Self {
         let device = models.device.clone();
-        cx.subscribe(&device, |_, _, _: &DeviceEvent, cx| cx.notify())
-            .detach();
-        Self { device }
+        cx.subscribe_in(&device, window, |this, _, _: &DeviceEvent, window, cx| {
+            this.sync_device(window, cx);
+            cx.notify();
+        })
+        .detach();
+
+        let mut input = |placeholder: &str| {
+            let placeholder = placeholder.to_string();
+            cx.new(|cx| InputState::new(window, cx).placeholder(placeholder))
+        };
+        let whitelabel_form = WhitelabelForm {
+            manufacturer: input("Keep default"),
+            product: input("Keep default"),
+            serial_number: input("Keep default (chip ID)"),
+            vid: input("e.g. 1D50"),
+            pid: input("e.g. 619B"),
+            volume_label: input("Keep default"),
+            confirm: input(WHITELABEL_CONFIRM_PHRASE),
+        };
+        // The burn button depends on the confirmation text.
+        let subscriptions = vec![
+            cx.subscribe(&whitelabel_form.confirm, |_, _, _: &InputEvent, cx| {
+                cx.notify()
+            }),
+        ];
+
+        let nickname_input =
+            cx.new(|cx| InputState::new(window, cx).placeholder("e.g. Blue key on keyring"));
+
+        let file = tunables::get();
+        let mut default_ms = |ms: u32| {
+            cx.new(|cx| InputState::new(window, cx).placeholder(format!("Default: {} ms", ms)))
+        };
+        let overrides_form = OverridesForm {
+            total_timeout: default_ms(file.hid.total_timeout_ms),
+            response_timeout: default_ms(file.hid.response_read_timeout_ms),
+            touch_timeout: default_ms(file.keepalive.user_presence_timeout_ms),
+            transport: cx.new(|cx| {
+                SelectState::new(
+                    TransportChoice::ALL.to_vec(),
+                    Some(gpui_component::IndexPath::default()),
+                    window,
+                    cx,
+                )
+            }),
+            disabled_reads: Vec::new(),
+        };
+
+        let mut this = Self {
+            device,
+            whitelabel_open: false,
+            whitelabel_form,
+            whitelabel_busy: false,
+            whitelabel_result: None,
+            nickname_input,
+            nickname_serial: None,
+            cards: HashMap::new(),
+            pin_retries: None,
+            selecting: false,
+            selection_result: None,
+            identifying: false,
+            overrides_open: false,
+            overrides_form,
+            _subscriptions: subscriptions,
+            _whitelabel_task: None,
+            _task: None,
+        };
+        this.sync_device(window, cx);
+        this
+    }
+
+    /// Refill the nickname input and start the cards over when a different
+    /// device is connected.
+    fn sync_device(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let device = self.device.read(cx);
+        let serial = device.status.as_ref().map(|s| s.info.serial.clone());
+        if serial == self.nickname_serial {
+            return;
+        }
+        let nickname = device.nickname().unwrap_or_default().to_string();
+        let connected = serial.is_some();
+        self.nickname_serial = serial;
+        self.nickname_input
+            .update(cx, |input, cx| input.set_value(nickname, window, cx));
+        self.fill_overrides(window, cx);
+        self.cards.clear();
+        self.pin_retries = None;
+        // The poll does not read PIN retries; fetch them for the new key.
+        if connected {
+            self.refresh_card(HomeCard::Security, cx);
+        }
+    }
+
+    /// Re-read the data behind one card in the background. A failure is kept
+    /// on that card; the previously shown data stays until a read succeeds.
+    pub(super) fn refresh_card(&mut self, card: HomeCard, cx: &mut Context<Self>) {
+        if card == HomeCard::Activity {
+            self.device.update(cx, |repo, cx| repo.refresh_timeline(cx));
+            return;
+        }
+        let state = self.cards.entry(card).or_default();
+        if state.loading {
+            return;
+        }
+        state.loading = true;
+        state.error = None;
+        cx.notify();
+
+        let method = self
+            .device
+            .read(cx)
+            .status
+            .as_ref()
+            .map(|s| s.method.clone());
+        let weak_self = cx.entity().downgrade();
+        cx.spawn(async move |_, cx| {
+            let result = cx
+                .background_executor()
+                .spawn(async move { CardData::read(card, method) })
+                .await;
+            let _ = weak_self.update(cx, |this, cx| this.apply_card(card, result, cx));
+        })
+        .detach();
+    }
+
+    fn apply_card(
+        &mut self,
+        card: HomeCard,
+        result: Result<CardData, String>,
+        cx: &mut Context<Self>,
+    ) {
+        let state = self.cards.entry(card).or_default();
+        state.loading = false;
+        match result {
+            Ok(CardData::Identity(usb)) => {
+                self.device
+                    .update(cx, |repo, cx| repo.set_usb_descriptors(usb, cx));
+            }
+            Ok(CardData::Storage(usage)) => {
+                self.device
+                    .update(cx, |repo, cx| repo.set_flash_usage(usage, cx));
+            }
+            Ok(CardData::Security(retries)) => self.pin_retries = Some(retries),
+            Ok(CardData::Capabilities(info)) => {
+                self.device
+                    .update(cx, |repo, cx| repo.set_fido_info(info, cx));
+            }
+            Err(e) => {
+                log::warn!("Could not refresh the {} card: {}", card.label(), e);
+                state.error = Some(e);
+            }
+        }
+        cx.notify();
+    }
+
+    /// Ask every attached key for a touch and manage the one the user touches.
+    pub(super) fn select_device(&mut self, cx: &mut Context<Self>) {
+        if self.selecting {
+            return;
+        }
+        self.selecting = true;
+        self.selection_result = None;
+        cx.notify();
+
+        let weak_self = cx.entity().downgrade();
+        cx.spawn(async move |_, cx| {
+            let result = cx
+                .background_executor()
+                .spawn(async move { DeviceRepo::select_device_by_touch_blocking() })
+                .await;
+            let _ = weak_self.update(cx, |this, cx| {
+                this.selecting = false;
+                this.selection_result = Some(result.map(|key| key.product));
+                this.device.update(cx, |repo, cx| repo.refresh(cx));
+                cx.notify();
+            });
+        })
+        .detach();
+    }
+
+    /// Blink the managed key's LED so it can be told apart from other
+    /// attached keys. A failure is shown on the Identity card.
+    pub(super) fn identify_device(&mut self, cx: &mut Context<Self>) {
+        if self.identifying {
+            return;
+        }
+        self.identifying = true;
+        cx.notify();
+
+        let weak_self = cx.entity().downgrade();
+        cx.spawn(async move |_, cx| {
+            let result = cx
+                .background_executor()
+                .spawn(async move { DeviceRepo::wink_blocking() })
+                .await;
+            let _ = weak_self.update(cx, |this, cx| {
+                this.identifying = false;
+                let card = this.cards.entry(HomeCard::Identity).or_default();
+                card.error = result
+                    .err()
+                    .map(|e| format!("Could not identify the key: {}", e));
+                cx.notify();
+            });
+        })
+        .detach();
+    }
+
+    /// Load the connected key's stored overrides into the form.
+    fn fill_overrides(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let overrides = self.device.read(cx).device_overrides();
+        let form = &mut self.overrides_form;
+        let ms = |value: Option<u32>| value.map(|v| v.to_string()).unwrap_or_default();
+        for (input, value) in [
+            (&form.total_timeout, overrides.total_timeout_ms),
+            (&form.response_timeout, overrides.response_read_timeout_ms),
+            (&form.touch_timeout, overrides.user_presence_timeout_ms),
+        ] {
+            input.update(cx, |input, cx| input.set_value(ms(value), window, cx));
+        }
+        let choice = TransportChoice::from_order(overrides.transport_order.as_deref());
+        let row = TransportChoice::ALL
+            .iter()
+            .position(|c| *c == choice)
+            .unwrap_or(0);
+        form.transport.update(cx, |select, cx| {
+            select.set_selected_index(
+                Some(gpui_component::IndexPath::default().row(row)),
+                window,
+                cx,
+            );
+        });
+        form.disabled_reads = overrides.disabled_vendor_reads.clone();
+        self.overrides_open = !overrides.is_empty();
+    }
+
+    pub(super) fn toggle_overrides(&mut self, cx: &mut Context<Self>) {
+        self.overrides_open = !self.overrides_open;
+        cx.notify();
+    }
+
+    pub(super) fn set_vendor_read_disabled(
+        &mut self,
+        read: VendorRead,
+        disabled: bool,
+        cx: &mut Context<Self>,
+    ) {
+        let reads = &mut self.overrides_form.disabled_reads;
+        reads.retain(|r| *r != read);
+        if disabled {
+            reads.push(read);
+            reads.sort();
+        }
+        cx.notify();
+    }
+
+    fn read_overrides(&self, cx: &App) -> Result<DeviceOverrides, String> {
+        let form = &self.overrides_form;
+        let ms = |label: &str, input: &Entity<InputState>| -> Result<Option<u32>, String> {
+            let value = input.read(cx).value().trim().to_string();
+            if value.is_empty() {
+                return Ok(None);
+            }
+            value
+                .parse()
+                .map(Some)
+                .map_err(|_| format!("{} must be a number of milliseconds", label))
+        };
+        let choice = form
+            .transport
+            .read(cx)
+            .selected_index(cx)
+            .and_then(|ix| TransportChoice::ALL.get(ix.row).copied())
+            .unwrap_or(TransportChoice::Default);
+        let overrides = DeviceOverrides {
+            transport_order: choice.order(),
+            total_timeout_ms: ms("Exchange timeout", &form.total_timeout)?,
+            response_read_timeout_ms: ms("Response timeout", &form.response_timeout)?,
+            user_presence_timeout_ms: ms("Touch timeout", &form.touch_timeout)?,
+            disabled_vendor_reads: form.disabled_reads.clone(),
+        };
+        overrides.validate()?;
+        Ok(overrides)
+    }
+
+    /// Store the form as the connected key's overrides and read the key
+    /// again with them.
+    pub(super) fn save_overrides(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let result = self.read_overrides(cx).and_then(|overrides| {
+            self.device
+                .update(cx, |repo, cx| repo.set_device_overrides(overrides, cx))
+        });
+        match result {
+            Ok(()) => window.push_notification("Connection overrides saved.", cx),
+            Err(e) => window.push_notification(format!("Could not save overrides: {}", e), cx),
+        }
+    }
+
+    /// Drop the connected key's overrides.
+    pub(super) fn clear_overrides(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let result = self.device.update(cx, |repo, cx| {
+            repo.set_device_overrides(DeviceOverrides::default(), cx)
+        });
+        match result {
+            Ok(()) => {
+                self.fill_overrides(window, cx);
+                window.push_notification("Connection overrides cleared.", cx);
+            }
+            Err(e) => window.push_notification(format!("Could not clear overrides: {}", e), cx),
+        }
+    }
+
+    /// Save the nickname input for the connected device.
+    pub(super) fn save_nickname(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let name = self.nickname_input.read(cx).value().to_string();
+        let result = self
+            .device
+            .update(cx, |repo, cx| repo.set_nickname(&name, cx));
+        match result {
+            Ok(()) => window.push_notification("Nickname saved on this computer.", cx),
+            Err(e) => window.push_notification(format!("Could not save nickname: {}", e), cx),
+        }
+    }
+
+    pub(super) fn toggle_whitelabel(&mut self, cx: &mut Context<Self>) {
+        self.whitelabel_open = !self.whitelabel_open;
+        self.whitelabel_result = None;
+        cx.notify();
+    }
+
+    pub(super) fn whitelabel_confirmed(&self, cx: &App) -> bool {
+        self.whitelabel_form.confirm.read(cx).value().trim() == WHITELABEL_CONFIRM_PHRASE
+    }
+
+    fn read_whitelabel(&self, cx: &App) -> Result<Whitelabel, String> {
+        let form = &self.whitelabel_form;
+        let text = |input: &Entity<InputState>| input.read(cx).value().trim().to_string();
+        let id = |label: &str, input: &Entity<InputState>| -> Result<Option<u16>, String> {
+            let value = text(input);
+            let digits = value.trim_start_matches("0x").trim_start_matches("0X");
+            if digits.is_empty() {
+                return Ok(None);
+            }
+            u16::from_str_radix(digits, 16)
+                .map(Some)
+                .map_err(|_| format!("{} must be a 4-digit hex value", label))
+        };
+
+        let label = Whitelabel {
+            vid: id("VID", &form.vid)?,
+            pid: id("PID", &form.pid)?,
+            manufacturer: text(&form.manufacturer),
+            product: text(&form.product),
+            serial_number: text(&form.serial_number),
+            volume_label: text(&form.volume_label),
+        };
+        let problems = label.validate();
+        if problems.is_empty() {
+            Ok(label)
+        } else {
+            Err(problems.join(". "))
+        }
+    }
+
+    /// Validate the form and burn it into the OTP of the RP2350 in BOOTSEL
+    /// mode. Irreversible, so it only runs once the phrase is typed.
+    pub(super) fn burn_whitelabel(&mut self, cx: &mut Context<Self>) {
+        if !self.whitelabel_confirmed(cx) || self.whitelabel_busy {
+            return;
+        }
+        let label = match self.read_whitelabel(cx) {
+            Ok(label) => label,
+            Err(e) => {
+                self.whitelabel_result = Some(Err(e));
+                cx.notify();
+                return;
+            }
+        };
+        self.whitelabel_busy = true;
+        self.whitelabel_result = None;
+        cx.notify();
+
+        let entity = cx.entity().downgrade();
+        self._whitelabel_task = Some(cx.spawn(async move |_, cx| {
+            let result = cx
+                .background_executor()
+                .spawn(async move { DeviceRepo::write_whitelabel_blocking(label) })
+                .await;
+            match &result {
+                Ok(summary) => log::info!(
+                    target: AUDIT_TARGET,
+                    "Burned RP2350 whitelabel into OTP: {}",
+                    summary
+                ),
+                Err(e) => log::error!("RP2350 whitelabel burn failed: {}", e),
+            }
+            let result = result.map(|_| {
+                "Burned and verified. Unplug the board and hold BOOTSEL again to see the \
+                 new identity."
+                    .to_string()
+            });
+            let _ = entity.update(cx, |this, cx| {
+                this.whitelabel_busy = false;
+                this.whitelabel_result = Some(result);
+                cx.notify();
+            });
+        }));
     }
 }
@@ -1,5 +1,8 @@
 pub mod about;
+pub mod applets;
+pub mod biometrics;
 pub mod config;
 pub mod home;
 pub mod passkeys;
 pub mod security;
+pub mod vault;
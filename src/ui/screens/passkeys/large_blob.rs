@@ -0,0 +1,184 @@
+//! Large-blob section of the passkey details sheet.
+//!
+//! Reading and writing both start with a `getAssertion` for the credential's
+//! `largeBlobKey`, so every action waits for a touch. Writing also needs the
+//! PIN the storage was unlocked with.
+
+use crate::ui::models::device::{DeviceRepo, LargeBlobInfo};
+use crate::ui::screens::passkeys::view_model::PasskeysViewModel;
+use gpui::prelude::FluentBuilder;
+use gpui::*;
+use gpui_component::button::{Button, ButtonVariants};
+use gpui_component::input::{Input, InputState};
+use gpui_component::{ActiveTheme, Disableable, h_flex, v_flex};
+
+/// Reads, stores and removes one credential's large blob.
+pub(super) struct LargeBlobPanel {
+    view: WeakEntity<PasskeysViewModel>,
+    rp_id: String,
+    credential_id: String,
+    pin: Option<String>,
+    input: Entity<InputState>,
+    /// `None` until read; `Some(None)` when the credential has no blob.
+    blob: Option<Option<Vec<u8>>>,
+    error: Option<String>,
+    busy: bool,
+    _task: Option<Task<()>>,
+}
+
+impl LargeBlobPanel {
+    pub(super) fn new(
+        view: WeakEntity<PasskeysViewModel>,
+        rp_id: String,
+        credential_id: String,
+        pin: Option<String>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let input = cx.new(|cx| InputState::new(window, cx).placeholder("Text to store"));
+        Self {
+            view,
+            rp_id,
+            credential_id,
+            pin,
+            input,
+            blob: None,
+            error: None,
+            busy: false,
+            _task: None,
+        }
+    }
+
+    fn read(&mut self, cx: &mut Context<Self>) {
+        let rp_id = self.rp_id.clone();
+        let credential_id = self.credential_id.clone();
+        let pin = self.pin.clone();
+        self.run(cx, move || {
+            DeviceRepo::read_large_blob_blocking(rp_id, credential_id, pin)
+        });
+    }
+
+    /// Store the input text, or remove the blob when `remove` is set.
+    fn write(&mut self, remove: bool, cx: &mut Context<Self>) {
+        let Some(pin) = self.pin.clone() else {
+            return;
+        };
+        let data = if remove {
+            Vec::new()
+        } else {
+            self.input.read(cx).value().to_string().into_bytes()
+        };
+        let rp_id = self.rp_id.clone();
+        let credential_id = self.credential_id.clone();
+        self.run(cx, move || {
+            DeviceRepo::write_large_blob_blocking(rp_id, credential_id, data, pin)
+        });
+    }
+
+    fn run(
+        &mut self,
+        cx: &mut Context<Self>,
+        op: impl FnOnce() -> Result<LargeBlobInfo, String> + Send + 'static,
+    ) {
+        if self.busy {
+            return;
+        }
+        self.busy = true;
+        self.error = None;
+        cx.notify();
+
+        let weak_self = cx.entity().downgrade();
+        self._task = Some(cx.spawn(async move |_, cx| {
+            let result = cx.background_executor().spawn(async move { op() }).await;
+
+            let _ = weak_self.update(cx, |this, cx| {
+                this.busy = false;
+                match result {
+                    Ok(info) => {
+                        this.blob = Some(info.blob.clone());
+                        let _ = this.view.update(cx, |vm, cx| {
+                            vm.large_blobs = Some(LargeBlobInfo { blob: None, ..info });
+                            cx.notify();
+                        });
+                    }
+                    Err(e) => {
+                        log::error!("Large blob operation failed: {}", e);
+                        this.error = Some(e);
+                    }
+                }
+                cx.notify();
+            });
+        }));
+    }
+}
+
+impl Render for LargeBlobPanel {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+        let busy = self.busy;
+        let can_write = self.pin.is_some();
+
+        let content = match &self.blob {
+            None => None,
+            Some(None) => Some("No large blob stored for this passkey.".to_string()),
+            Some(Some(blob)) => Some(match std::str::from_utf8(blob) {
+                Ok(text) => format!("{} ({} bytes)", text, blob.len()),
+                Err(_) => format!("{} ({} bytes, binary)", hex::encode(blob), blob.len()),
+            }),
+        };
+
+        v_flex()
+            .gap_2()
+            .child(
+                div()
+                    .text_sm()
+                    .font_medium()
+                    .text_color(theme.muted_foreground)
+                    .child("Large Blob"),
+            )
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(theme.muted_foreground)
+                    .child("Stored encrypted on the key with this passkey's largeBlobKey. Each action asks for a touch."),
+            )
+            .children(content.map(|text| {
+                div()
+                    .text_xs()
+                    .font_family("monospace")
+                    .bg(theme.muted)
+                    .p_2()
+                    .rounded_md()
+                    .overflow_hidden()
+                    .child(text)
+            }))
+            .child(Input::new(&self.input))
+            .when_some(self.error.clone(), |this, error| {
+                this.child(div().text_sm().text_color(theme.danger).child(error))
+            })
+            .child(
+                h_flex()
+                    .gap_2()
+                    .child(
+                        Button::new("large-blob-read")
+                            .label("Read Blob")
+                            .loading(busy)
+                            .disabled(busy)
+                            .on_click(cx.listener(|this, _, _, cx| this.read(cx))),
+                    )
+                    .child(
+                        Button::new("large-blob-write")
+                            .primary()
+                            .label("Save Blob")
+                            .disabled(busy || !can_write)
+                            .on_click(cx.listener(|this, _, _, cx| this.write(false, cx))),
+                    )
+                    .child(
+                        Button::new("large-blob-remove")
+                            .label("Remove Blob")
+                            .disabled(busy || !can_write)
+                            .on_click(cx.listener(|this, _, _, cx| this.write(true, cx))),
+                    ),
+            )
+    }
+}
@@ -1,5 +1,7 @@
 //! Passkeys screen — credential listing, deletion, and PIN management.
 
+mod large_blob;
+mod sign_in_test;
 pub mod view;
 pub mod view_model;
 pub use view_model::{PasskeysEvent, PasskeysViewModel};
@@ -0,0 +1,169 @@
+//! Sign-in test section of the passkey details sheet.
+//!
+//! Signs in with just this passkey in the allow list and checks the
+//! signature against the public key the key reported when listing it, so a
+//! passkey can be confirmed to still work without visiting the site.
+
+use crate::ui::models::device::{DeviceRepo, SignInTest};
+use gpui::prelude::FluentBuilder;
+use gpui::*;
+use gpui_component::button::{Button, ButtonVariants};
+use gpui_component::{ActiveTheme, Disableable, h_flex, v_flex};
+
+/// Runs a test sign-in with one stored passkey and shows the checks.
+pub(super) struct SignInTestPanel {
+    rp_id: String,
+    credential_id: String,
+    /// CBOR-encoded COSE key from credential management.
+    public_key: Vec<u8>,
+    pin: Option<String>,
+    result: Option<SignInTest>,
+    error: Option<String>,
+    busy: bool,
+    _task: Option<Task<()>>,
+}
+
+impl SignInTestPanel {
+    pub(super) fn new(
+        rp_id: String,
+        credential_id: String,
+        public_key: Vec<u8>,
+        pin: Option<String>,
+    ) -> Self {
+        Self {
+            rp_id,
+            credential_id,
+            public_key,
+            pin,
+            result: None,
+            error: None,
+            busy: false,
+            _task: None,
+        }
+    }
+
+    fn run(&mut self, cx: &mut Context<Self>) {
+        if self.busy {
+            return;
+        }
+        self.busy = true;
+        self.result = None;
+        self.error = None;
+        cx.notify();
+
+        let rp_id = self.rp_id.clone();
+        let credential_id = self.credential_id.clone();
+        let public_key = self.public_key.clone();
+        let pin = self.pin.clone();
+        let weak_self = cx.entity().downgrade();
+        self._task = Some(cx.spawn(async move |_, cx| {
+            let result = cx
+                .background_executor()
+                .spawn(async move {
+                    DeviceRepo::test_sign_in_blocking(rp_id, credential_id, public_key, pin)
+                })
+                .await;
+
+            let _ = weak_self.update(cx, |this, cx| {
+                this.busy = false;
+                match result {
+                    Ok(test) => this.result = Some(test),
+                    Err(e) => {
+                        log::error!("Test sign-in failed: {}", e);
+                        this.error = Some(e);
+                    }
+                }
+                cx.notify();
+            });
+        }));
+    }
+}
+
+impl Render for SignInTestPanel {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+        let busy = self.busy;
+
+        let summary = self.result.as_ref().map(|test| {
+            let (text, color) = if test.valid {
+                (
+                    format!(
+                        "This passkey works. Sign count {}, user {}.",
+                        test.sign_count,
+                        if test.user_verified {
+                            "verified"
+                        } else {
+                            "not verified"
+                        }
+                    ),
+                    theme.green,
+                )
+            } else {
+                (
+                    "The key answered, but the sign-in did not verify.".to_string(),
+                    theme.danger,
+                )
+            };
+            v_flex()
+                .gap_1()
+                .child(div().text_sm().text_color(color).child(text))
+                .children(test.checks.iter().map(|check| {
+                    h_flex()
+                        .gap_2()
+                        .text_xs()
+                        .child(
+                            div()
+                                .text_color(if check.passed {
+                                    theme.muted_foreground
+                                } else {
+                                    theme.danger
+                                })
+                                .child(format!(
+                                    "{} {}",
+                                    if check.passed { "✓" } else { "✗" },
+                                    check.name
+                                )),
+                        )
+                        .child(
+                            div()
+                                .text_color(theme.muted_foreground)
+                                .child(check.detail.clone()),
+                        )
+                }))
+        });
+
+        v_flex()
+            .gap_2()
+            .child(
+                div()
+                    .text_sm()
+                    .font_medium()
+                    .text_color(theme.muted_foreground)
+                    .child("Sign-in Test"),
+            )
+            .child(div().text_xs().text_color(theme.muted_foreground).child(
+                if self.pin.is_some() {
+                    "Signs in with only this passkey allowed and checks the signature against \
+                     its stored public key. Asks for a touch; the PIN you unlocked with adds \
+                     user verification."
+                } else {
+                    "Signs in with only this passkey allowed and checks the signature against \
+                     its stored public key. Asks for a touch."
+                },
+            ))
+            .children(summary)
+            .when_some(self.error.clone(), |this, error| {
+                this.child(div().text_sm().text_color(theme.danger).child(error))
+            })
+            .child(
+                h_flex().child(
+                    Button::new("passkey-sign-in-test")
+                        .primary()
+                        .label("Test sign-in with this passkey")
+                        .loading(busy)
+                        .disabled(busy || self.public_key.is_empty())
+                        .on_click(cx.listener(|this, _, _, cx| this.run(cx))),
+                ),
+            )
+    }
+}
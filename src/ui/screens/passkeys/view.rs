@@ -1,19 +1,29 @@
+use crate::release_notes;
+use crate::synced_passkeys;
 use crate::ui::components::{
     button::{PFButton, PFIconButton},
     card::Card,
     dialog,
     page_view::PageView,
+    table::{TableColumn, VirtualTable},
+    tag::Tag,
+    view_state::{self, EmptyState},
+};
+use crate::ui::models::device::{
+    CredProtectPolicy, CredentialExportFormat, DeviceMethod, StoredCredential,
 };
-use crate::ui::models::device::{DeviceMethod, StoredCredential};
 use crate::ui::screens::passkeys::view_model::{PasskeysEvent, PasskeysViewModel};
 use directories::UserDirs;
 use gpui::prelude::FluentBuilder;
 use gpui::*;
 use gpui_component::Disableable;
-use gpui_component::button::{Button, ButtonCustomVariant, ButtonVariants};
+use gpui_component::button::{Button, ButtonVariants};
 use gpui_component::{
-    ActiveTheme, Icon, Sizable, StyledExt, Theme, badge::Badge, h_flex, switch::Switch, v_flex,
+    ActiveTheme, Icon, Sizable, StyledExt, Theme, badge::Badge, checkbox::Checkbox, h_flex,
+    input::Input, progress::Progress, switch::Switch, v_flex,
 };
+use std::ops::Range;
+use std::rc::Rc;
 
 impl PasskeysViewModel {
     fn render_enterprise_attestation(&self, cx: &mut Context<Self>) -> impl IntoElement {
@@ -83,6 +93,10 @@ impl PasskeysViewModel {
             this.open_upload_cert_dialog(window, cx);
         });
 
+        let read_cert_listener = cx.listener(|this, _, _, cx| {
+            this.read_ea_cert(cx);
+        });
+
         let theme = cx.theme();
 
         let fido_info = self.device.read(cx).fido_info.clone();
@@ -225,83 +239,80 @@ impl PasskeysViewModel {
                     .on_click(upload_listener),
             );
 
-        Card::new()
-            .title("Enterprise Attestation")
-            .description("Configure enterprise-specific features")
-            .icon(Icon::default().path("icons/shield-check.svg"))
-            .child(
-                v_flex()
-                    .gap_3()
-                    .child(enable_row)
-                    .child(csr_row)
-                    .child(upload_row),
-            )
-    }
-
-    fn render_reset_device_row(&self, cx: &mut Context<Self>) -> impl IntoElement {
-        let theme = cx.theme();
+        let cert_summary = match &self.ea_cert {
+            None => vec!["Not read from this key yet".to_string()],
+            Some(record) => match &record.certificate {
+                Some(cert) => vec![
+                    cert.subject.clone(),
+                    format!("Serial {}", cert.serial),
+                    format!("Expires {}", cert.not_after),
+                    format!("Read {}", release_notes::age(record.read_at)),
+                ],
+                None => vec![
+                    "No enterprise certificate returned".to_string(),
+                    format!("Read {}", release_notes::age(record.read_at)),
+                ],
+            },
+        };
 
-        let header = gpui_component::h_flex()
+        let cert_row = div()
+            .flex()
             .items_center()
             .justify_between()
-            .w_full()
             .gap_4()
+            .p_4()
+            .border_1()
+            .border_color(theme.border)
+            .rounded_lg()
             .child(
                 v_flex()
-                    .gap_1()
-                    .child(
-                        div()
-                            .text_base()
-                            .font_weight(gpui::FontWeight::MEDIUM)
-                            .text_color(theme.foreground)
-                            .child("Factory Reset"),
-                    )
-                    .child(
+                    .child(div().font_medium().child("Uploaded Certificate"))
+                    .children(cert_summary.into_iter().map(|line| {
                         div()
                             .text_sm()
                             .text_color(theme.muted_foreground)
-                            .child("Erase all passkeys, credentials, and PIN. Cannot be undone."),
-                    ),
+                            .child(line)
+                    }))
+                    .when(self.ea_cert_loading, |el| {
+                        el.child(
+                            div()
+                                .text_sm()
+                                .text_color(theme.muted_foreground)
+                                .child("Touch the key when it blinks"),
+                        )
+                    })
+                    .when_some(self.ea_cert_error.clone(), |el, error| {
+                        el.child(div().text_sm().text_color(theme.danger).child(error))
+                    }),
             )
             .child(
-                Button::new("reset-device")
-                    .icon(Icon::default().path("icons/circle-alert.svg"))
-                    .child("Reset Device")
-                    .custom(
-                        ButtonCustomVariant::new(cx)
-                            .color(theme.danger)
-                            .hover(theme.danger_hover)
-                            .active(theme.danger_active)
-                            .foreground(theme.danger_foreground),
-                    )
-                    .disabled(self.loading)
-                    .on_click(cx.listener(|this, _, window, cx| {
-                        this.open_reset_dialog(window, cx);
-                    })),
+                PFButton::new("Read from Device")
+                    .id("read-ea-cert-btn")
+                    .with_colors(rgb(0x222225), rgb(0x2a2a2d), rgb(0x333336))
+                    .loading(self.ea_cert_loading)
+                    .on_click(read_cert_listener),
             );
 
         Card::new()
-            .title("Reset")
-            .description("Perform a destructive factory reset")
-            .icon(Icon::default().path("icons/trash.svg"))
-            .child(header)
-    }
-
-    fn render_no_device(&self, theme: &Theme) -> impl IntoElement {
-        div()
-            .flex()
-            .items_center()
-            .justify_center()
-            .h_64()
-            .border_1()
-            .border_color(theme.border)
-            .rounded_xl()
+            .title("Enterprise Attestation")
+            .description("Configure enterprise-specific features")
+            .icon(Icon::default().path("icons/shield-check.svg"))
             .child(
-                div()
-                    .text_color(theme.muted_foreground)
-                    .child("Connect your pico-key to manage passkeys."),
+                v_flex()
+                    .gap_3()
+                    .child(enable_row)
+                    .child(csr_row)
+                    .child(upload_row)
+                    .child(cert_row),
             )
-            .into_any_element()
+    }
+
+    fn render_no_device(&self) -> impl IntoElement {
+        EmptyState::new(
+            Icon::default().path("icons/hard-drive.svg"),
+            "No Device Connected",
+        )
+        .description("Connect your pico-key to manage passkeys.")
     }
 
     fn render_not_supported(&self, theme: &Theme) -> impl IntoElement {
@@ -480,10 +491,13 @@ impl PasskeysViewModel {
             this.lock_storage(cx);
         });
 
-        let mut cards = Vec::new();
-        for cred in &self.credentials {
-            cards.push(self.render_credential_card(cred, cx).into_any_element());
-        }
+        let visible: Rc<Vec<StoredCredential>> =
+            Rc::new(self.visible_credentials(cx).into_iter().cloned().collect());
+        let table = self.render_credential_table(visible.clone(), cx);
+        let capacity = self.render_capacity(cx).map(|e| e.into_any_element());
+        let coverage = self
+            .render_synced_coverage(cx)
+            .map(|e| e.into_any_element());
 
         let theme = cx.theme();
 
@@ -523,31 +537,382 @@ impl PasskeysViewModel {
                                             .text_sm()
                                             .text_color(theme.muted_foreground)
                                             .child(format!("{} credentials stored", creds_len)),
-                                    ),
+                                    )
+                                    .children(self.large_blobs.as_ref().map(|info| {
+                                        let usage = match info.capacity {
+                                            Some(capacity) => format!(
+                                                "Large blobs: {} entries, {} of {} bytes",
+                                                info.entries, info.used_bytes, capacity
+                                            ),
+                                            None => format!(
+                                                "Large blobs: {} entries, {} bytes",
+                                                info.entries, info.used_bytes
+                                            ),
+                                        };
+                                        h_flex()
+                                            .gap_4()
+                                            .items_center()
+                                            .child(div().w_px().h_4().bg(theme.border))
+                                            .child(
+                                                div()
+                                                    .text_sm()
+                                                    .text_color(if info.trailer_valid {
+                                                        theme.muted_foreground
+                                                    } else {
+                                                        theme.warning
+                                                    })
+                                                    .child(if info.trailer_valid {
+                                                        usage
+                                                    } else {
+                                                        format!(
+                                                            "{} (corrupt, read as empty)",
+                                                            usage
+                                                        )
+                                                    }),
+                                            )
+                                    })),
                             )
                             .child(
-                                PFIconButton::new(
-                                    Icon::default().path("icons/lock.svg").size_3p5(),
-                                    "Lock Storage",
-                                )
-                                .small()
-                                .on_click(lock_listener),
+                                h_flex()
+                                    .gap_2()
+                                    .child(
+                                        Button::new("delete-by-id")
+                                            .small()
+                                            .ghost()
+                                            .label("Delete by ID…")
+                                            .on_click(cx.listener(|this, _, window, cx| {
+                                                this.open_delete_by_id_dialog(window, cx)
+                                            })),
+                                    )
+                                    .child(
+                                        Button::new("compare-synced")
+                                            .small()
+                                            .ghost()
+                                            .label("Compare Export…")
+                                            .on_click(cx.listener(|this, _, _, cx| {
+                                                this.compare_synced_export(cx)
+                                            })),
+                                    )
+                                    .child(
+                                        Button::new("export-json")
+                                            .small()
+                                            .ghost()
+                                            .label("Export JSON")
+                                            .disabled(self.credentials.is_empty())
+                                            .on_click(cx.listener(|this, _, _, cx| {
+                                                this.export_credentials(
+                                                    CredentialExportFormat::Json,
+                                                    cx,
+                                                )
+                                            })),
+                                    )
+                                    .child(
+                                        Button::new("export-csv")
+                                            .small()
+                                            .ghost()
+                                            .label("Export CSV")
+                                            .disabled(self.credentials.is_empty())
+                                            .on_click(cx.listener(|this, _, _, cx| {
+                                                this.export_credentials(
+                                                    CredentialExportFormat::Csv,
+                                                    cx,
+                                                )
+                                            })),
+                                    )
+                                    .child(
+                                        PFIconButton::new(
+                                            Icon::default().path("icons/lock.svg").size_3p5(),
+                                            "Lock Storage",
+                                        )
+                                        .small()
+                                        .on_click(lock_listener),
+                                    ),
                             ),
                     )
+                    .children(capacity)
+                    .when(!self.credentials.is_empty(), |this| {
+                        this.child(Input::new(&self.search_input))
+                    })
+                    .when(!self.selected.is_empty(), |this| {
+                        this.child(self.render_selection_bar(cx))
+                    })
+                    .when(!self.trash.is_empty() || self.emptying_trash, |this| {
+                        this.child(self.render_trash_bar(cx))
+                    })
+                    .children(coverage)
                     .child(if self.credentials.is_empty() {
                         self.render_empty_credentials_with_theme(theme)
                             .into_any_element()
-                    } else {
+                    } else if visible.is_empty() {
                         div()
-                            .grid()
-                            .grid_cols(3)
-                            .gap_4()
-                            .children(cards)
+                            .py_6()
+                            .text_sm()
+                            .text_center()
+                            .text_color(theme.muted_foreground)
+                            .child("No passkeys match your search.")
                             .into_any_element()
+                    } else {
+                        table.into_any_element()
                     }),
             )
     }
 
+    /// Resident credential capacity from `getCredsMetadata`, next to flash
+    /// usage when the device reports it.
+    fn render_capacity(&self, cx: &App) -> Option<impl IntoElement> {
+        let metadata = self.credentials_metadata?;
+        let flash = self
+            .device
+            .read(cx)
+            .status
+            .as_ref()
+            .and_then(|s| s.info.flash_used.zip(s.info.flash_total))
+            .filter(|(_, total)| *total > 0);
+        let theme = cx.theme();
+
+        let total = metadata.existing + metadata.remaining;
+        let percent = if total == 0 {
+            0.0
+        } else {
+            metadata.existing as f32 / total as f32 * 100.0
+        };
+        let meter = |label: &str, value: String, percent: f32| {
+            v_flex()
+                .flex_1()
+                .gap_2()
+                .child(
+                    h_flex()
+                        .justify_between()
+                        .text_sm()
+                        .child(
+                            div()
+                                .text_color(theme.muted_foreground)
+                                .child(label.to_string()),
+                        )
+                        .child(div().text_color(theme.foreground).child(value)),
+                )
+                .child(Progress::new().value(percent))
+        };
+
+        Some(
+            h_flex()
+                .gap_6()
+                .child(meter(
+                    "Resident Credentials",
+                    format!(
+                        "{} used, {} remaining",
+                        metadata.existing, metadata.remaining
+                    ),
+                    percent,
+                ))
+                .children(flash.map(|(used, total)| {
+                    meter(
+                        "Flash Memory",
+                        format!("{} / {} KB", used, total),
+                        used as f32 / total as f32 * 100.0,
+                    )
+                })),
+        )
+    }
+
+    /// Actions for the passkeys ticked on their cards.
+    fn render_selection_bar(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+        let text = match self.selected.len() {
+            1 => "1 passkey selected".to_string(),
+            n => format!("{} passkeys selected", n),
+        };
+
+        h_flex()
+            .justify_between()
+            .items_center()
+            .gap_4()
+            .px_4()
+            .py_2()
+            .rounded_md()
+            .bg(theme.muted)
+            .border_1()
+            .border_color(theme.border)
+            .child(
+                h_flex()
+                    .gap_2()
+                    .items_center()
+                    .text_sm()
+                    .child(Icon::default().path("icons/check.svg").size_4())
+                    .child(text),
+            )
+            .child(
+                h_flex()
+                    .gap_2()
+                    .child(
+                        Button::new("selection-clear")
+                            .small()
+                            .label("Clear")
+                            .on_click(cx.listener(|this, _, _, cx| this.clear_selection(cx))),
+                    )
+                    .child(
+                        Button::new("selection-delete")
+                            .small()
+                            .danger()
+                            .label("Delete Selected")
+                            .on_click(
+                                cx.listener(|this, _, window, cx| this.trash_selected(window, cx)),
+                            ),
+                    ),
+            )
+    }
+
+    /// Undo bar for passkeys waiting out their grace period.
+    fn render_trash_bar(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+        let text = match self.trash.len() {
+            0 => "Deleting passkeys...".to_string(),
+            1 => format!(
+                "Passkey for {} will be deleted in {} s.",
+                self.trash[0].credential.rp_id,
+                self.trash_seconds_left()
+            ),
+            n => format!(
+                "{} passkeys will be deleted, the first in {} s.",
+                n,
+                self.trash_seconds_left()
+            ),
+        };
+
+        h_flex()
+            .justify_between()
+            .items_center()
+            .gap_4()
+            .px_4()
+            .py_2()
+            .rounded_md()
+            .bg(theme.muted)
+            .border_1()
+            .border_color(theme.border)
+            .child(
+                h_flex()
+                    .gap_2()
+                    .items_center()
+                    .text_sm()
+                    .child(Icon::default().path("icons/trash-2.svg").size_4())
+                    .child(text),
+            )
+            .child(
+                h_flex()
+                    .gap_2()
+                    .child(
+                        Button::new("trash-undo")
+                            .small()
+                            .label("Undo")
+                            .disabled(self.trash.is_empty())
+                            .on_click(cx.listener(|this, _, _, cx| this.undo_trash(cx))),
+                    )
+                    .child(
+                        Button::new("trash-empty")
+                            .small()
+                            .danger()
+                            .label("Delete Now")
+                            .loading(self.emptying_trash)
+                            .disabled(self.trash.is_empty())
+                            .on_click(cx.listener(|this, _, _, cx| this.empty_trash(cx))),
+                    ),
+            )
+    }
+
+    /// Sites from a password manager export next to the passkeys on the
+    /// key: which have a hardware-bound backup and which do not.
+    fn render_synced_coverage(&self, cx: &mut Context<Self>) -> Option<impl IntoElement> {
+        let export = self.synced_export.as_ref()?;
+        let coverage = synced_passkeys::compare(&export.passkeys, &self.credentials);
+        let theme = cx.theme();
+        let (muted_fg, warning, muted_bg, border) = (
+            theme.muted_foreground,
+            theme.warning,
+            theme.muted,
+            theme.border,
+        );
+
+        let section = |title: &str, hint: &str, sites: &[String], color: Hsla| {
+            v_flex()
+                .gap_1()
+                .child(
+                    h_flex()
+                        .gap_2()
+                        .items_center()
+                        .child(div().text_sm().font_semibold().child(format!(
+                            "{} ({})",
+                            title,
+                            sites.len()
+                        )))
+                        .child(div().text_xs().text_color(muted_fg).child(hint.to_string())),
+                )
+                .child(
+                    div()
+                        .text_xs()
+                        .text_color(color)
+                        .child(if sites.is_empty() {
+                            "None".to_string()
+                        } else {
+                            sites.join(", ")
+                        }),
+                )
+        };
+
+        Some(
+            v_flex()
+                .gap_3()
+                .px_4()
+                .py_3()
+                .rounded_md()
+                .bg(muted_bg)
+                .border_1()
+                .border_color(border)
+                .child(
+                    h_flex()
+                        .justify_between()
+                        .items_center()
+                        .child(
+                            h_flex()
+                                .gap_2()
+                                .items_center()
+                                .text_sm()
+                                .child(Icon::default().path("icons/key-round.svg").size_4())
+                                .child(format!(
+                                    "{} synced passkey(s) from a {} export",
+                                    export.passkeys.len(),
+                                    export.format.label()
+                                )),
+                        )
+                        .child(
+                            Button::new("synced-dismiss")
+                                .small()
+                                .label("Dismiss")
+                                .on_click(
+                                    cx.listener(|this, _, _, cx| this.clear_synced_export(cx)),
+                                ),
+                        ),
+                )
+                .child(section(
+                    "Synced only",
+                    "no hardware-bound backup on this key",
+                    &coverage.synced_only,
+                    warning,
+                ))
+                .child(section(
+                    "Key only",
+                    "lost with the key unless registered elsewhere",
+                    &coverage.hardware_only,
+                    warning,
+                ))
+                .child(section(
+                    "Both",
+                    "synced and on this key",
+                    &coverage.both,
+                    muted_fg,
+                )),
+        )
+    }
+
     fn render_empty_credentials_with_theme(&self, theme: &Theme) -> impl IntoElement {
         v_flex()
             .items_center()
@@ -580,123 +945,449 @@ impl PasskeysViewModel {
             )
     }
 
-    fn render_credential_card(
-        &self,
-        cred: &StoredCredential,
-        cx: &mut Context<Self>,
-    ) -> impl IntoElement {
-        let cred_clone = cred.clone();
-        let cred_for_click = cred.clone();
+    fn render_test_credential(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let max_blob = self
+            .device
+            .read(cx)
+            .fido_info
+            .as_ref()
+            .and_then(|f| f.max_cred_blob_length);
+        let needs_unlock = self.test_needs_unlock(cx);
+        let blob_len = self.test_blob_input.read(cx).value().len();
+        let busy = self.test_busy;
+        let theme = cx.theme();
 
-        let delete_listener = cx.listener(move |this, _, window, cx| {
-            this.open_ask_delete_pin(cred_clone.clone(), window, cx);
-        });
+        let limit_text = match max_blob {
+            Some(max) => format!(
+                "This key advertises maxCredBlobLength = {} bytes. Longer blobs are not an \
+                 error: the key creates the credential and reports credBlob = false.",
+                max
+            ),
+            None => "This key does not advertise maxCredBlobLength; the credBlob extension \
+                     is probably not supported."
+                .to_string(),
+        };
+        let over_limit = max_blob.is_some_and(|max| blob_len as i128 > max);
 
-        let click_listener = cx.listener(move |this, _, window, cx| {
-            this.open_credential_details(&cred_for_click, window, cx);
-        });
+        let kv = |label: &str, value: String| {
+            h_flex()
+                .justify_between()
+                .text_sm()
+                .child(
+                    div()
+                        .text_color(theme.muted_foreground)
+                        .child(label.to_string()),
+                )
+                .child(div().font_family("monospace").child(value))
+        };
+        let blob_text = |blob: &[u8]| {
+            if blob.is_empty() {
+                "(empty)".to_string()
+            } else {
+                format!(
+                    "\"{}\" ({})",
+                    String::from_utf8_lossy(blob),
+                    hex::encode(blob)
+                )
+            }
+        };
 
-        let theme = cx.theme();
+        let created = self.test_credential.as_ref().map(|c| {
+            v_flex()
+                .gap_1()
+                .child(kv(
+                    "Credential ID",
+                    format!("{}…", &c.credential_id[..c.credential_id.len().min(16)]),
+                ))
+                .child(kv("Attestation", c.attestation_format.clone()))
+                .when_some(c.attestation.as_ref(), |this, verification| {
+                    this.child(kv(
+                        "Attestation verified",
+                        match (verification.valid, verification.self_attestation) {
+                            (true, true) => "yes (self attestation)".to_string(),
+                            (true, false) => "yes (certificate)".to_string(),
+                            (false, _) => "no".to_string(),
+                        },
+                    ))
+                    .children(verification.checks.iter().map(|check| {
+                        v_flex()
+                            .child(kv(
+                                &check.name,
+                                if check.passed { "ok" } else { "failed" }.to_string(),
+                            ))
+                            .child(
+                                div()
+                                    .text_xs()
+                                    .text_color(if check.passed {
+                                        theme.muted_foreground
+                                    } else {
+                                        theme.danger
+                                    })
+                                    .child(check.detail.clone()),
+                            )
+                    }))
+                })
+                .child(kv(
+                    "credBlob stored",
+                    match c.cred_blob_stored {
+                        Some(true) => "yes".to_string(),
+                        Some(false) => "no (rejected by the key)".to_string(),
+                        None => "not reported".to_string(),
+                    },
+                ))
+        });
+        let asserted = self.test_assertion.as_ref().map(|a| {
+            v_flex()
+                .gap_1()
+                .child(kv("Sign count", a.sign_count.to_string()))
+                .child(kv("User verified", a.user_verified.to_string()))
+                .child(kv(
+                    "credBlob read back",
+                    a.cred_blob
+                        .as_deref()
+                        .map(blob_text)
+                        .unwrap_or_else(|| "not returned".to_string()),
+                ))
+        });
+        let swept = self.test_sweep.as_ref().map(|sweep| {
+            let returned = sweep.assertions.len();
+            let summary = if returned as u32 == sweep.reported {
+                format!(
+                    "The key returned all {} credential(s) it reported.",
+                    returned
+                )
+            } else {
+                format!(
+                    "The key reported {} credential(s) but returned {}.",
+                    sweep.reported, returned
+                )
+            };
+            v_flex()
+                .gap_1()
+                .child(
+                    div()
+                        .text_sm()
+                        .font_medium()
+                        .child("Discoverable credentials"),
+                )
+                .children(sweep.assertions.iter().enumerate().map(|(i, a)| {
+                    kv(
+                        &format!(
+                            "{}. {}",
+                            i + 1,
+                            a.user_name.as_deref().unwrap_or("(no user name)")
+                        ),
+                        format!(
+                            "{}… · count {}",
+                            &a.credential_id[..a.credential_id.len().min(16)],
+                            a.sign_count
+                        ),
+                    )
+                }))
+                .child(
+                    div()
+                        .text_sm()
+                        .text_color(
+                            if sweep.error.is_some() || returned as u32 != sweep.reported {
+                                theme.warning
+                            } else {
+                                theme.muted_foreground
+                            },
+                        )
+                        .child(sweep.error.clone().unwrap_or(summary)),
+                )
+        });
 
-        div()
-            .id(SharedString::from(format!(
-                "cred-card-{}",
-                cred.credential_id
-            )))
-            .cursor_pointer()
-            .on_click(click_listener)
-            .border_1()
-            .border_color(theme.border)
-            .rounded_xl()
-            .p_4()
-            .hover(|s| s.bg(theme.accent).border_color(theme.primary))
+        let body = v_flex()
+            .gap_3()
+            .child(
+                div()
+                    .text_sm()
+                    .text_color(theme.muted_foreground)
+                    .child(limit_text),
+            )
             .child(
                 h_flex()
-                    .justify_between()
-                    .items_center()
+                    .gap_3()
                     .child(
-                        h_flex()
-                            .gap_3()
-                            .items_center()
+                        v_flex()
+                            .gap_1()
+                            .flex_1()
+                            .child(div().text_sm().font_medium().child("RP ID"))
+                            .child(Input::new(&self.test_rp_input)),
+                    )
+                    .child(
+                        v_flex()
+                            .gap_1()
                             .flex_1()
-                            .min_w_0()
                             .child(
                                 div()
-                                    .size_10()
-                                    .rounded_md()
-                                    .bg(rgb(0x3b3b3e))
-                                    .flex()
-                                    .items_center()
-                                    .justify_center()
-                                    .child(
-                                        Icon::default()
-                                            .path("icons/key-round.svg")
-                                            .text_color(theme.primary)
-                                            .size_5(),
-                                    ),
+                                    .text_sm()
+                                    .font_medium()
+                                    .child(format!("credBlob ({} bytes)", blob_len)),
                             )
-                            .child(
-                                v_flex()
-                                    .min_w_0()
-                                    .overflow_hidden()
-                                    .child(
-                                        div()
-                                            .font_semibold()
-                                            .whitespace_nowrap()
-                                            .overflow_hidden()
-                                            .text_ellipsis()
-                                            .child(if !cred.rp_name.is_empty() {
-                                                cred.rp_name.clone()
-                                            } else if !cred.rp_id.is_empty() {
-                                                cred.rp_id.clone()
-                                            } else {
-                                                "Unknown Service".to_string()
-                                            }),
-                                    )
-                                    .child(
-                                        div()
-                                            .text_sm()
-                                            .text_color(theme.muted_foreground)
-                                            .whitespace_nowrap()
-                                            .overflow_hidden()
-                                            .text_ellipsis()
-                                            .child(cred.user_name.clone()),
-                                    ),
-                            ),
+                            .child(Input::new(&self.test_blob_input)),
+                    ),
+            )
+            .when(over_limit, |this| {
+                this.child(
+                    div()
+                        .text_sm()
+                        .text_color(theme.warning)
+                        .child("The blob is longer than the key accepts; expect credBlob = false."),
+                )
+            })
+            .child(
+                h_flex()
+                    .gap_2()
+                    .items_center()
+                    .child(
+                        Switch::new("test-resident-key")
+                            .checked(self.test_resident_key)
+                            .on_click(cx.listener(|this, checked, _, cx| {
+                                this.test_resident_key = *checked;
+                                cx.notify();
+                            })),
                     )
+                    .child(div().text_sm().child(
+                        "Discoverable credential (stored on the key; delete it from the list above when done)",
+                    )),
+            )
+            .children(created)
+            .children(asserted)
+            .children(swept)
+            .when_some(self.test_error.clone(), |this, error| {
+                this.child(div().text_sm().text_color(theme.danger).child(error))
+            })
+            .when(needs_unlock, |this| {
+                this.child(
+                    div()
+                        .text_sm()
+                        .text_color(theme.muted_foreground)
+                        .child("Unlock the passkey storage first so the PIN can be used."),
+                )
+            })
+            .child(
+                h_flex()
+                    .gap_2()
+                    .justify_end()
                     .child(
-                        div()
-                            .on_mouse_down(MouseButton::Left, |_, _, cx| {
-                                cx.stop_propagation();
-                            })
-                            .child(
-                                Button::new("delete-cred-btn")
-                                    .ghost()
-                                    .small()
-                                    .child(
-                                        Icon::default()
-                                            .path("icons/trash-2.svg")
-                                            .size_4()
-                                            .text_color(theme.muted_foreground),
-                                    )
-                                    .on_click(delete_listener),
-                            ),
+                        Button::new("test-sweep")
+                            .label("List all (GetNextAssertion)")
+                            .disabled(busy || needs_unlock)
+                            .on_click(cx.listener(|this, _, _, cx| this.sweep_test_assertions(cx))),
+                    )
+                    .child(
+                        Button::new("test-read-blob")
+                            .label("Read credBlob")
+                            .disabled(busy || needs_unlock || self.test_credential.is_none())
+                            .on_click(cx.listener(|this, _, _, cx| this.read_test_cred_blob(cx))),
+                    )
+                    .child(
+                        Button::new("test-create")
+                            .primary()
+                            .label("Create & touch key")
+                            .loading(busy)
+                            .disabled(busy || needs_unlock)
+                            .on_click(cx.listener(|this, _, _, cx| {
+                                this.create_test_credential(cx)
+                            })),
                     ),
-            )
+            );
+
+        Card::new()
+            .title("Test Credential")
+            .description("Create a throwaway credential to check extension support")
+            .icon(Icon::default().path("icons/key.svg"))
+            .child(body)
+    }
+
+    /// The passkeys in `visible` as a virtualized table; a click or Enter
+    /// opens the details sheet.
+    fn render_credential_table(
+        &self,
+        visible: Rc<Vec<StoredCredential>>,
+        cx: &mut Context<Self>,
+    ) -> VirtualTable {
+        let columns = vec![
+            TableColumn::fixed("", px(24.)),
+            TableColumn::fill("Service"),
+            TableColumn::fill("User"),
+            TableColumn::fixed("Protection", px(150.)),
+            TableColumn::fixed("", px(72.)),
+        ];
+        let rows = visible.clone();
+        let render_rows = cx.processor(move |this, range: Range<usize>, _, cx| {
+            range
+                .filter_map(|ix| rows.get(ix))
+                .map(|cred| this.render_credential_row(cred, cx))
+                .collect::<Vec<_>>()
+        });
+        let weak_self = cx.entity().downgrade();
+
+        VirtualTable::new(
+            "passkeys-table",
+            &self.credential_table,
+            columns,
+            visible.len(),
+            render_rows,
+        )
+        .row_height(px(56.))
+        .max_height(px(560.))
+        .on_activate(move |ix, window, cx| {
+            let Some(cred) = visible.get(ix) else {
+                return;
+            };
+            let _ = weak_self.update(cx, |this, cx| {
+                this.open_credential_details(cred, window, cx);
+            });
+        })
+    }
+
+    /// Cells of one passkey row: selection, service, user and note,
+    /// protection, actions.
+    fn render_credential_row(
+        &self,
+        cred: &StoredCredential,
+        cx: &mut Context<Self>,
+    ) -> Vec<AnyElement> {
+        let cred_clone = cred.clone();
+        let cred_for_edit = cred.clone();
+        let id_for_select = cred.credential_id.clone();
+        let selected = self.selected.contains(&cred.credential_id);
+
+        let edit_listener = cx.listener(move |this, _, window, cx| {
+            this.open_edit_user_dialog(cred_for_edit.clone(), window, cx);
+        });
+        let delete_listener = cx.listener(move |this, _, window, cx| {
+            this.trash_credential(cred_clone.clone(), window, cx);
+        });
+        let select_listener = cx.listener(move |this, _: &bool, _, cx| {
+            this.toggle_selected(&id_for_select, cx);
+        });
+        let note = self.notes.get(&cred.credential_id).cloned();
+        let key_description = cred.key_description();
+        let cred_protect = cred.cred_protect;
+
+        let theme = cx.theme();
+        let service = if !cred.rp_name.is_empty() {
+            cred.rp_name.clone()
+        } else if !cred.rp_id.is_empty() {
+            cred.rp_id.clone()
+        } else {
+            "Unknown Service".to_string()
+        };
+        // Clicks on the controls must not also open the details sheet.
+        let controls = || {
+            div().on_mouse_down(MouseButton::Left, |_, _, cx| {
+                cx.stop_propagation();
+            })
+        };
+
+        vec![
+            controls()
+                .child(
+                    Checkbox::new("select-cred")
+                        .checked(selected)
+                        .on_click(select_listener),
+                )
+                .into_any_element(),
+            v_flex()
+                .min_w_0()
+                .child(
+                    div()
+                        .font_semibold()
+                        .overflow_hidden()
+                        .text_ellipsis()
+                        .child(service),
+                )
+                .when(!key_description.is_empty(), |this| {
+                    this.child(
+                        div()
+                            .text_xs()
+                            .font_family("monospace")
+                            .text_color(theme.muted_foreground)
+                            .overflow_hidden()
+                            .text_ellipsis()
+                            .child(key_description),
+                    )
+                })
+                .into_any_element(),
+            v_flex()
+                .min_w_0()
+                .child(
+                    div()
+                        .text_color(theme.muted_foreground)
+                        .overflow_hidden()
+                        .text_ellipsis()
+                        .child(cred.user_name.clone()),
+                )
+                .children(note.map(|note| {
+                    let summary = if note.tags.is_empty() {
+                        note.text
+                    } else if note.text.is_empty() {
+                        note.tags.join(" · ")
+                    } else {
+                        format!("{} — {}", note.tags.join(" · "), note.text)
+                    };
+                    div()
+                        .text_xs()
+                        .text_color(theme.primary)
+                        .overflow_hidden()
+                        .text_ellipsis()
+                        .child(summary)
+                }))
+                .into_any_element(),
+            div()
+                .children(cred_protect.map(|policy| {
+                    Tag::new(policy.label())
+                        .active(policy == CredProtectPolicy::UserVerificationRequired)
+                }))
+                .into_any_element(),
+            controls()
+                .flex()
+                .items_center()
+                .child(
+                    Button::new("edit-cred-btn")
+                        .ghost()
+                        .small()
+                        .child(
+                            Icon::default()
+                                .path("icons/pencil.svg")
+                                .size_4()
+                                .text_color(theme.muted_foreground),
+                        )
+                        .on_click(edit_listener),
+                )
+                .child(
+                    Button::new("delete-cred-btn")
+                        .ghost()
+                        .small()
+                        .child(
+                            Icon::default()
+                                .path("icons/trash-2.svg")
+                                .size_4()
+                                .text_color(theme.muted_foreground),
+                        )
+                        .on_click(delete_listener),
+                )
+                .into_any_element(),
+        ]
     }
 }
 
 impl Render for PasskeysViewModel {
     fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         let device = self.device.read(cx);
-        let device_connected = device.status.is_some();
 
-        if !device_connected {
+        if let Some(placeholder) =
+            view_state::for_phase(&device.phase(), self.render_no_device(), &self.device)
+        {
             let theme = cx.theme();
             return PageView::build(
                 "Passkeys",
                 "Manage your security PIN and the FIDO credentials (passkeys) stored on your device.",
-                self.render_no_device(theme).into_any_element(),
+                placeholder,
                 theme,
             )
             .into_any_element();
@@ -724,8 +1415,8 @@ impl Render for PasskeysViewModel {
             .gap_6()
             .child(self.render_pin_management(cx))
             .child(self.render_stored_passkeys(cx))
-            .child(self.render_enterprise_attestation(cx))
-            .child(self.render_reset_device_row(cx));
+            .child(self.render_test_credential(cx))
+            .child(self.render_enterprise_attestation(cx));
 
         let theme = cx.theme();
 
@@ -1,26 +1,97 @@
 //! View model for the passkeys screen — credential listing and management.
 
+use crate::inventory::{self, AttestationCertRecord, CredentialNote, TimelineEvent};
+use crate::logging::ActionId;
+use crate::synced_passkeys::{self, SyncedExport};
 use crate::ui::app::AppModels;
 use crate::ui::components::dialog;
 use crate::ui::components::dialog::{
-    ChangePinContent, ConfirmContent, PinPromptContent, SetPinContent, StatusContent,
+    ChangePinContent, PinPromptContent, SetPinContent, StatusContent,
 };
-use crate::ui::models::device::{DeviceEvent, DeviceRepo, StoredCredential};
+use crate::ui::components::qr::{QrCodeView, QrMatrix};
+use crate::ui::components::table::TableState;
+use crate::ui::models::device::{
+    AssertionSweep, CredentialExportFormat, CredentialsMetadata, DeviceEvent, DeviceRepo,
+    LargeBlobInfo, StoredCredential, TestAssertion, TestCredential, TestCredentialRequest,
+    parse_credential_id,
+};
+use crate::ui::screens::passkeys::large_blob::LargeBlobPanel;
+use crate::ui::screens::passkeys::sign_in_test::SignInTestPanel;
+use directories::UserDirs;
 use gpui::*;
 use gpui_component::button::ButtonVariants;
+use gpui_component::input::InputEvent;
+use gpui_component::input::InputState;
 use gpui_component::{ActiveTheme, StyledExt, WindowExt};
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::{Duration, Instant};
+
+/// RP ID suggested for test credentials; never a real site.
+const TEST_RP_ID: &str = "picoforge.test";
+
+/// How long a deleted passkey stays in the trash before DeleteCredential is sent.
+const TRASH_GRACE: Duration = Duration::from_secs(10);
 
 /// Credential state, PIN management, and FIDO storage operations.
 pub struct PasskeysViewModel {
     pub(super) device: Entity<DeviceRepo>,
     pub(super) credentials: Vec<StoredCredential>,
+    /// Local notes and tags from the inventory, by credential ID.
+    pub(super) notes: BTreeMap<String, CredentialNote>,
+    /// Filters the list by relying party, user, note or tag.
+    pub(super) search_input: Entity<InputState>,
+    /// Scroll position and active row of the passkeys table.
+    pub(super) credential_table: Entity<TableState>,
     pub(super) unlocked: bool,
     cached_pin: Option<String>,
+    /// Stored and remaining discoverable credential counts, read with the list.
+    pub(super) credentials_metadata: Option<CredentialsMetadata>,
+    /// Large-blob array usage, read after unlocking when the key supports it.
+    pub(super) large_blobs: Option<LargeBlobInfo>,
+    _large_blob_task: Option<Task<()>>,
     pub(super) loading: bool,
     pub(super) csr_loading: bool,
     pub(super) csr_pem: Option<String>,
     pub(super) show_csr: bool,
+    /// Enterprise attestation certificate last read back from this key.
+    pub(super) ea_cert: Option<AttestationCertRecord>,
+    pub(super) ea_cert_loading: bool,
+    pub(super) ea_cert_error: Option<String>,
+    _ea_cert_task: Option<Task<()>>,
     pub(super) _task: Option<Task<()>>,
+    /// Diagnostics: inputs and results of the test credential creator.
+    pub(super) test_rp_input: Entity<InputState>,
+    pub(super) test_blob_input: Entity<InputState>,
+    pub(super) test_resident_key: bool,
+    pub(super) test_credential: Option<TestCredential>,
+    pub(super) test_assertion: Option<TestAssertion>,
+    pub(super) test_sweep: Option<AssertionSweep>,
+    pub(super) test_error: Option<String>,
+    pub(super) test_busy: bool,
+    _test_task: Option<Task<()>>,
+    _search_subscription: Subscription,
+    /// Credential IDs ticked for a bulk delete.
+    pub(super) selected: BTreeSet<String>,
+    /// Deletions waiting out their grace period, oldest first.
+    pub(super) trash: Vec<TrashedCredential>,
+    /// DeleteCredential commands for due trash entries are in flight.
+    pub(super) emptying_trash: bool,
+    _trash_timer: Option<Task<()>>,
+    _trash_task: Option<Task<()>>,
+    /// Deletes what is left in the trash when the app quits.
+    _quit_subscription: Subscription,
+    /// Password manager export the passkeys are compared against.
+    pub(super) synced_export: Option<SyncedExport>,
+    _synced_task: Option<Task<()>>,
+}
+
+/// A passkey the user deleted that has not been deleted from the key yet.
+/// Dropped without being sent if the view model goes away (another key was
+/// connected), so the trash never outlives the key it belongs to. Deleted
+/// with the session PIN when due; the entry itself holds no secret.
+pub(super) struct TrashedCredential {
+    pub(super) credential: StoredCredential,
+    deadline: Instant,
 }
 
 /// Events emitted by [`PasskeysViewModel`] to notify the parent of UI-level actions.
@@ -31,23 +102,272 @@ pub enum PasskeysEvent {
 impl EventEmitter<PasskeysEvent> for PasskeysViewModel {}
 
 impl PasskeysViewModel {
-    pub fn new(_window: &mut Window, cx: &mut Context<Self>, models: &AppModels) -> Self {
+    pub fn new(window: &mut Window, cx: &mut Context<Self>, models: &AppModels) -> Self {
         let device = models.device.clone();
-        cx.subscribe(&device, |this: &mut Self, _, _: &DeviceEvent, cx| {
-            this.refresh_if_unlocked(cx);
-        })
+        cx.subscribe(
+            &device,
+            |this: &mut Self, _, event: &DeviceEvent, cx| match event {
+                DeviceEvent::Updated => this.refresh_if_unlocked(cx),
+                // Everything in the trash went with the reset; nothing to send.
+                DeviceEvent::Reset => {
+                    this.trash.clear();
+                    this.lock_storage(cx);
+                }
+                _ => {}
+            },
+        )
         .detach();
+        let test_rp_input =
+            cx.new(|cx| InputState::new(window, cx).default_value(TEST_RP_ID.to_string()));
+        let test_blob_input =
+            cx.new(|cx| InputState::new(window, cx).placeholder("Text to store (optional)"));
+        let search_input = cx
+            .new(|cx| InputState::new(window, cx).placeholder("Search by site, user, note or tag"));
+        let _search_subscription =
+            cx.subscribe(&search_input, |_, _, _: &InputEvent, cx| cx.notify());
+        let ea_cert = device.read(cx).attestation_cert_record();
+        let _quit_subscription = cx.on_app_quit(|this, _| {
+            this.delete_trash_on_quit();
+            async {}
+        });
         Self {
             device,
             credentials: Vec::new(),
+            notes: inventory::load().credential_notes,
+            search_input,
+            credential_table: cx.new(TableState::new),
             unlocked: false,
             cached_pin: None,
+            credentials_metadata: None,
+            large_blobs: None,
+            _large_blob_task: None,
             loading: false,
             csr_loading: false,
             csr_pem: None,
             show_csr: false,
+            ea_cert,
+            ea_cert_loading: false,
+            ea_cert_error: None,
+            _ea_cert_task: None,
             _task: None,
+            test_rp_input,
+            test_blob_input,
+            test_resident_key: true,
+            test_credential: None,
+            test_assertion: None,
+            test_sweep: None,
+            test_error: None,
+            test_busy: false,
+            _test_task: None,
+            _search_subscription,
+            selected: BTreeSet::new(),
+            trash: Vec::new(),
+            emptying_trash: false,
+            _trash_timer: None,
+            _trash_task: None,
+            _quit_subscription,
+            synced_export: None,
+            _synced_task: None,
+        }
+    }
+
+    /// Credentials matching the search box, in device order, minus the trash.
+    pub(super) fn visible_credentials(&self, cx: &App) -> Vec<&StoredCredential> {
+        let query = self.search_input.read(cx).text().trim().to_lowercase();
+        let credentials = self
+            .credentials
+            .iter()
+            .filter(|cred| !self.is_trashed(&cred.credential_id));
+        if query.is_empty() {
+            return credentials.collect();
+        }
+        credentials
+            .filter(|cred| {
+                [
+                    &cred.rp_id,
+                    &cred.rp_name,
+                    &cred.user_name,
+                    &cred.user_display_name,
+                ]
+                .iter()
+                .any(|field| field.to_lowercase().contains(&query))
+                    || self
+                        .notes
+                        .get(&cred.credential_id)
+                        .is_some_and(|note| note.matches(&query))
+            })
+            .collect()
+    }
+
+    /// Store the local note and tags for a credential. Nothing is sent to the device.
+    fn save_credential_note(
+        &mut self,
+        credential_id: String,
+        text: String,
+        tags: String,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        match inventory::set_credential_note(&credential_id, &text, &tags) {
+            Ok(()) => {
+                self.notes = inventory::load().credential_notes;
+                window.push_notification("Note saved on this computer.", cx);
+            }
+            Err(e) => {
+                log::warn!("Could not save passkey note: {}", e);
+                window.push_notification(format!("Could not save note: {}", e), cx);
+            }
+        }
+        cx.notify();
+    }
+
+    /// Whether the device has a PIN that has not been entered yet this session.
+    pub(super) fn test_needs_unlock(&self, cx: &App) -> bool {
+        let pin_set = self
+            .device
+            .read(cx)
+            .fido_info
+            .as_ref()
+            .and_then(|f| f.options.get("clientPin").copied())
+            .unwrap_or(false);
+        pin_set && self.cached_pin.is_none()
+    }
+
+    /// Create a test credential with the entered RP ID and `credBlob` text.
+    pub(super) fn create_test_credential(&mut self, cx: &mut Context<Self>) {
+        if self.test_busy {
+            return;
+        }
+        let rp_id = self.test_rp_input.read(cx).value().trim().to_string();
+        if rp_id.is_empty() {
+            self.test_error = Some("Enter an RP ID".into());
+            cx.notify();
+            return;
+        }
+        let blob = self.test_blob_input.read(cx).value().to_string();
+        let request = TestCredentialRequest {
+            rp_id,
+            user_name: "picoforge-test".into(),
+            resident_key: self.test_resident_key,
+            cred_blob: (!blob.is_empty()).then(|| blob.into_bytes()),
+            enterprise_attestation: None,
+        };
+        let pin = self.cached_pin.clone();
+
+        self.test_busy = true;
+        self.test_error = None;
+        self.test_credential = None;
+        self.test_assertion = None;
+        self.test_sweep = None;
+        cx.notify();
+
+        let weak_self = cx.entity().downgrade();
+        self._test_task = Some(cx.spawn(async move |_, cx| {
+            let result = cx
+                .background_executor()
+                .spawn(async move { DeviceRepo::create_test_credential_blocking(request, pin) })
+                .await;
+
+            let _ = weak_self.update(cx, |this, cx| {
+                this.test_busy = false;
+                match result {
+                    Ok(credential) => {
+                        let resident = this.test_resident_key;
+                        this.test_credential = Some(credential);
+                        if resident {
+                            this.refresh_if_unlocked(cx);
+                        }
+                    }
+                    Err(e) => {
+                        log::error!("Test credential creation failed: {}", e);
+                        this.test_error = Some(e);
+                    }
+                }
+                cx.notify();
+            });
+        }));
+    }
+
+    /// Get an assertion from the last test credential and read its `credBlob`.
+    pub(super) fn read_test_cred_blob(&mut self, cx: &mut Context<Self>) {
+        if self.test_busy {
+            return;
+        }
+        let Some(credential) = self.test_credential.clone() else {
+            return;
+        };
+        let pin = self.cached_pin.clone();
+
+        self.test_busy = true;
+        self.test_error = None;
+        self.test_assertion = None;
+        cx.notify();
+
+        let weak_self = cx.entity().downgrade();
+        self._test_task = Some(cx.spawn(async move |_, cx| {
+            let result = cx
+                .background_executor()
+                .spawn(async move {
+                    DeviceRepo::get_test_assertion_blocking(
+                        credential.rp_id,
+                        credential.credential_id,
+                        pin,
+                    )
+                })
+                .await;
+
+            let _ = weak_self.update(cx, |this, cx| {
+                this.test_busy = false;
+                match result {
+                    Ok(assertion) => this.test_assertion = Some(assertion),
+                    Err(e) => {
+                        log::error!("Test assertion failed: {}", e);
+                        this.test_error = Some(e);
+                    }
+                }
+                cx.notify();
+            });
+        }));
+    }
+
+    /// Get an assertion from every discoverable credential of the entered RP
+    /// ID, walking them with GetNextAssertion as an account picker would.
+    pub(super) fn sweep_test_assertions(&mut self, cx: &mut Context<Self>) {
+        if self.test_busy {
+            return;
+        }
+        let rp_id = self.test_rp_input.read(cx).value().trim().to_string();
+        if rp_id.is_empty() {
+            self.test_error = Some("Enter an RP ID".into());
+            cx.notify();
+            return;
         }
+        let pin = self.cached_pin.clone();
+
+        self.test_busy = true;
+        self.test_error = None;
+        self.test_sweep = None;
+        cx.notify();
+
+        let weak_self = cx.entity().downgrade();
+        self._test_task = Some(cx.spawn(async move |_, cx| {
+            let result = cx
+                .background_executor()
+                .spawn(async move { DeviceRepo::sweep_test_assertions_blocking(rp_id, pin) })
+                .await;
+
+            let _ = weak_self.update(cx, |this, cx| {
+                this.test_busy = false;
+                match result {
+                    Ok(sweep) => this.test_sweep = Some(sweep),
+                    Err(e) => {
+                        log::error!("Assertion sweep failed: {}", e);
+                        this.test_error = Some(e);
+                    }
+                }
+                cx.notify();
+            });
+        }));
     }
 
     pub(super) fn unlock_storage(
@@ -69,17 +389,24 @@ impl PasskeysViewModel {
             let pin_for_bg = pin.clone();
             let result = cx
                 .background_executor()
-                .spawn(async move { DeviceRepo::get_credentials_blocking(pin_for_bg) })
+                .spawn(async move {
+                    let creds = DeviceRepo::get_credentials_blocking(pin_for_bg.clone())?;
+                    Ok::<_, String>((creds, read_credentials_metadata(pin_for_bg)))
+                })
                 .await;
 
             let _ = weak_self.update(cx, |this, cx| {
                 this.loading = false;
                 match result {
-                    Ok(creds) => {
+                    Ok((creds, metadata)) => {
                         log::info!("Storage unlocked. {} credentials found.", creds.len());
                         this.unlocked = true;
                         this.cached_pin = Some(pin);
                         this.credentials = creds;
+                        this.credentials_metadata = metadata;
+                        this.retain_selection();
+                        this.share_credentials(cx);
+                        this.refresh_large_blobs(cx);
                         let _ = dialog_handle.update(cx, |d, cx| {
                             d.set_success("Storage unlocked successfully.".to_string(), cx);
                         });
@@ -96,67 +423,82 @@ impl PasskeysViewModel {
         }));
     }
 
-    pub(super) fn lock_storage(&mut self, cx: &mut Context<Self>) {
-        self.unlocked = false;
-        self.cached_pin = None;
-        self.credentials.clear();
-        cx.notify();
+    /// Publish the credential list on the device repo for the impact check
+    /// on the Configuration screen.
+    fn share_credentials(&self, cx: &mut Context<Self>) {
+        let credentials = self.credentials.clone();
+        self.device.update(cx, |repo, cx| {
+            repo.observe_credential_count(credentials.len(), cx);
+            repo.credentials = Some(credentials);
+        });
     }
 
-    pub(super) fn execute_delete(
-        &mut self,
-        credential_id: String,
-        pin: String,
-        dialog_handle: WeakEntity<ConfirmContent>,
-        cx: &mut Context<Self>,
-    ) {
-        if self.loading {
+    /// Whether the key advertises `largeBlobs` in GetInfo.
+    pub(super) fn supports_large_blobs(&self, cx: &App) -> bool {
+        self.device
+            .read(cx)
+            .fido_info
+            .as_ref()
+            .and_then(|f| f.options.get("largeBlobs").copied())
+            .unwrap_or(false)
+    }
+
+    /// Re-read the large-blob array summary. Needs no PIN or touch.
+    fn refresh_large_blobs(&mut self, cx: &mut Context<Self>) {
+        if !self.supports_large_blobs(cx) {
+            self.large_blobs = None;
             return;
         }
-        self.loading = true;
-        cx.notify();
-
-        log::info!("Deleting credential...");
         let weak_self = cx.entity().downgrade();
-
-        self._task = Some(cx.spawn(async move |_, cx| {
+        self._large_blob_task = Some(cx.spawn(async move |_, cx| {
             let result = cx
                 .background_executor()
-                .spawn(async move { DeviceRepo::delete_credential_blocking(pin, credential_id) })
+                .spawn(async move { DeviceRepo::read_large_blob_array_blocking() })
                 .await;
 
-            let _ = weak_self.update(cx, |this, cx| match result {
-                Ok(_) => {
-                    log::info!("Credential deleted successfully.");
-                    let _ = dialog_handle.update(cx, |d, cx| {
-                        d.set_success("Credential deleted successfully.".to_string(), cx);
-                    });
-                    this.sync_fido_state(None, cx);
-                }
-                Err(e) => {
-                    log::error!("Error deleting credential: {}", e);
-                    this.loading = false;
-                    let _ = dialog_handle.update(cx, |d, cx| {
-                        d.set_error(format!("Error deleting: {}", e), cx);
-                    });
-                    cx.notify();
+            let _ = weak_self.update(cx, |this, cx| {
+                match result {
+                    Ok(info) => this.large_blobs = Some(info),
+                    Err(e) => log::warn!("Could not read large-blob array: {}", e),
                 }
+                cx.notify();
             });
         }));
     }
 
+    /// Forget the PIN. Passkeys still in the trash are deleted right away, as
+    /// the trash cannot be undone once it is no longer shown.
+    pub fn lock_storage(&mut self, cx: &mut Context<Self>) {
+        self.empty_trash(cx);
+        self.unlocked = false;
+        self.cached_pin = None;
+        self.credentials.clear();
+        self.selected.clear();
+        self.credentials_metadata = None;
+        self.large_blobs = None;
+        self.device.update(cx, |repo, _| repo.credentials = None);
+        cx.notify();
+    }
+
     fn refresh_credentials(&mut self, pin: String, cx: &mut Context<Self>) {
         let weak_self = cx.entity().downgrade();
         self._task = Some(cx.spawn(async move |_, cx| {
             let result = cx
                 .background_executor()
-                .spawn(async move { DeviceRepo::get_credentials_blocking(pin) })
+                .spawn(async move {
+                    let creds = DeviceRepo::get_credentials_blocking(pin.clone())?;
+                    Ok::<_, String>((creds, read_credentials_metadata(pin)))
+                })
                 .await;
 
             let _ = weak_self.update(cx, |this, cx| {
                 this.loading = false;
-                if let Ok(creds) = result {
+                if let Ok((creds, metadata)) = result {
                     this.credentials = creds;
+                    this.credentials_metadata = metadata;
+                    this.retain_selection();
+                    this.share_credentials(cx);
+                    this.refresh_large_blobs(cx);
                 }
                 cx.notify();
             });
@@ -212,33 +554,6 @@ impl PasskeysViewModel {
         );
     }
 
-    fn open_delete_dialog(
-        &mut self,
-        cred: &StoredCredential,
-        pin: String,
-        window: &mut Window,
-        cx: &mut Context<Self>,
-    ) {
-        let cred_id = cred.credential_id.clone();
-        let pin_str = pin.clone();
-        let name = cred.rp_id.clone();
-        let view_handle = cx.entity().downgrade();
-
-        dialog::open_confirm(
-            "Delete Passkey",
-            format!("Are you sure you want to delete the passkey for {}?", name),
-            "Delete",
-            gpui_component::button::ButtonVariant::Danger,
-            window,
-            cx,
-            move |dialog_handle, _, cx| {
-                let _ = view_handle.update(cx, |this, cx| {
-                    this.execute_delete(cred_id.clone(), pin_str.clone(), dialog_handle, cx);
-                });
-            },
-        );
-    }
-
     pub(super) fn open_change_pin_dialog(&mut self, window: &mut Window, cx: &mut Context<Self>) {
         let view_handle = cx.entity().downgrade();
 
@@ -283,6 +598,9 @@ impl PasskeysViewModel {
             let _ = weak_self.update(cx, |this, cx| match result {
                 Ok(msg) => {
                     log::info!("PIN configured: {}", msg);
+                    this.device.update(cx, |repo, cx| {
+                        repo.record_event(TimelineEvent::PinChanged, cx);
+                    });
                     let _ = dialog_handle.update(cx, |d, cx| {
                         d.set_success("PIN configured successfully.".to_string(), cx);
                     });
@@ -454,32 +772,41 @@ impl PasskeysViewModel {
         self.loading = true;
         cx.notify();
 
-        log::info!("Changing FIDO PIN...");
+        let action = ActionId::begin("pin.change");
+        action.scope(|| log::info!("Changing FIDO PIN..."));
         let weak_self = cx.entity().downgrade();
         let new_for_sync = new.clone();
 
         self._task = Some(cx.spawn(async move |_, cx| {
+            let act = action.clone();
             let result = cx
                 .background_executor()
-                .spawn(async move { DeviceRepo::change_fido_pin_blocking(Some(current), new) })
+                .spawn(async move {
+                    act.scope(|| DeviceRepo::change_fido_pin_blocking(Some(current), new))
+                })
                 .await;
 
-            let _ = weak_self.update(cx, |this, cx| match result {
-                Ok(msg) => {
-                    log::info!("PIN changed: {}", msg);
-                    let _ = dialog_handle.update(cx, |d, cx| {
-                        d.set_success("PIN changed successfully.".to_string(), cx);
-                    });
-                    this.sync_fido_state(Some(new_for_sync), cx);
-                }
-                Err(e) => {
-                    log::error!("PIN change failed: {}", e);
-                    this.loading = false;
-                    let _ = dialog_handle.update(cx, |d, cx| {
-                        d.set_error(format!("Error: {}", e), cx);
-                    });
-                    cx.notify();
-                }
+            let _ = weak_self.update(cx, |this, cx| {
+                action.scope(|| match result {
+                    Ok(msg) => {
+                        log::info!("PIN changed: {}", msg);
+                        this.device.update(cx, |repo, cx| {
+                            repo.record_event(TimelineEvent::PinChanged, cx);
+                        });
+                        let _ = dialog_handle.update(cx, |d, cx| {
+                            d.set_success("PIN changed successfully.".to_string(), cx);
+                        });
+                        this.sync_fido_state(Some(new_for_sync), cx);
+                    }
+                    Err(e) => {
+                        log::error!("PIN change failed: {}", e);
+                        this.loading = false;
+                        let _ = dialog_handle.update(cx, |d, cx| {
+                            d.set_error(format!("Error: {}", e), cx);
+                        });
+                        cx.notify();
+                    }
+                })
             });
         }));
     }
@@ -532,6 +859,9 @@ impl PasskeysViewModel {
                 let _ = weak_self.update(cx, |this, cx| match res_pin {
                     Ok(_) => {
                         log::info!("Minimum length and PIN updated successfully.");
+                        this.device.update(cx, |repo, cx| {
+                            repo.record_event(TimelineEvent::PinChanged, cx);
+                        });
                         let _ = status_handle.update(cx, |status_content, cx| {
                             status_content
                                 .set_success("Minimum length and PIN updated.".to_string(), cx);
@@ -608,6 +938,43 @@ impl PasskeysViewModel {
         }));
     }
 
+    /// Read back the enterprise attestation certificate the key carries and
+    /// keep it in the inventory. Needs a touch on the key.
+    pub(super) fn read_ea_cert(&mut self, cx: &mut Context<Self>) {
+        if self.ea_cert_loading {
+            return;
+        }
+        self.ea_cert_loading = true;
+        self.ea_cert_error = None;
+        cx.notify();
+
+        let pin = self.cached_pin.clone();
+        let weak_self = cx.entity().downgrade();
+        self._ea_cert_task = Some(cx.spawn(async move |_, cx| {
+            let result = cx
+                .background_executor()
+                .spawn(async move { DeviceRepo::read_enterprise_attestation_cert_blocking(pin) })
+                .await;
+
+            let _ = weak_self.update(cx, |this, cx| {
+                this.ea_cert_loading = false;
+                match result {
+                    Ok(certificate) => {
+                        this.device.update(cx, |repo, cx| {
+                            repo.record_attestation_cert(certificate.as_ref(), cx);
+                        });
+                        this.ea_cert = this.device.read(cx).attestation_cert_record();
+                    }
+                    Err(e) => {
+                        log::error!("Could not read the attestation certificate: {}", e);
+                        this.ea_cert_error = Some(e);
+                    }
+                }
+                cx.notify();
+            });
+        }));
+    }
+
     fn execute_upload_cert(
         &mut self,
         pin: String,
@@ -753,122 +1120,552 @@ impl PasskeysViewModel {
         }));
     }
 
-    pub(super) fn open_reset_dialog(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        let view_handle = cx.entity().downgrade();
+    /// Move a passkey to the trash. DeleteCredential is only sent once
+    /// [`TRASH_GRACE`] has passed or the trash is emptied, so a misclick can
+    /// be undone.
+    pub(super) fn trash_credential(
+        &mut self,
+        cred: StoredCredential,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.trash_credentials(vec![cred], window, cx);
+    }
 
-        dialog::open_confirm(
-            "Factory Reset Device",
-            "Are you sure you want to completely erase your device? This will permanently delete ALL passkeys, credentials, and your PIN. This action cannot be undone.".to_string(),
-            "Reset Device",
-            gpui_component::button::ButtonVariant::Danger,
-            window,
+    /// Move every ticked passkey to the trash and clear the selection.
+    pub(super) fn trash_selected(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let creds = self
+            .credentials
+            .iter()
+            .filter(|c| self.selected.contains(&c.credential_id))
+            .cloned()
+            .collect();
+        self.selected.clear();
+        self.trash_credentials(creds, window, cx);
+    }
+
+    /// Move every passkey stored for `rp_id` to the trash.
+    pub(super) fn trash_relying_party(
+        &mut self,
+        rp_id: &str,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let creds = self
+            .credentials
+            .iter()
+            .filter(|c| c.rp_id == rp_id)
+            .cloned()
+            .collect();
+        self.trash_credentials(creds, window, cx);
+    }
+
+    fn trash_credentials(
+        &mut self,
+        creds: Vec<StoredCredential>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if self.cached_pin.is_none() {
+            window.push_notification("Session expired, please unlock again.", cx);
+            self.lock_storage(cx);
+            return;
+        }
+        let creds: Vec<StoredCredential> = creds
+            .into_iter()
+            .filter(|c| !self.is_trashed(&c.credential_id))
+            .collect();
+        let subject = match creds.as_slice() {
+            [] => return,
+            [cred] => format!("Passkey for {}", cred.rp_id),
+            _ => format!("{} passkeys", creds.len()),
+        };
+        log::info!(
+            "{} moved to trash; deleting in {} s",
+            subject,
+            TRASH_GRACE.as_secs()
+        );
+        window.push_notification(
+            format!(
+                "{} moved to trash. Undo within {} seconds.",
+                subject,
+                TRASH_GRACE.as_secs()
+            ),
             cx,
-            move |_dialog_handle, window, cx| {
-                window.close_dialog(cx);
-                let _ = view_handle.update(cx, |this, cx| {
-                    this.execute_reset(window, cx);
-                });
-            },
         );
+        let deadline = Instant::now() + TRASH_GRACE;
+        for cred in creds {
+            self.selected.remove(&cred.credential_id);
+            self.trash.push(TrashedCredential {
+                credential: cred,
+                deadline,
+            });
+        }
+        self.start_trash_timer(cx);
+        cx.notify();
     }
 
-    fn execute_reset(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        if self.loading {
-            return;
+    /// Tick or untick a passkey for bulk deletion.
+    pub(super) fn toggle_selected(&mut self, credential_id: &str, cx: &mut Context<Self>) {
+        if !self.selected.remove(credential_id) {
+            self.selected.insert(credential_id.to_string());
         }
-        self.loading = true;
+        cx.notify();
+    }
 
-        let status_handle = dialog::open_status_dialog("Resetting Device...", window, cx);
-        let weak_self = cx.entity().downgrade();
+    pub(super) fn clear_selection(&mut self, cx: &mut Context<Self>) {
+        self.selected.clear();
+        cx.notify();
+    }
 
-        let _ = status_handle.update(cx, |d, cx| {
-            d.set_loading(
-                "Unplug your security key, then plug it back in within 10 seconds.",
-                cx,
-            );
-        });
+    /// Drop selected IDs that are no longer on the key.
+    fn retain_selection(&mut self) {
+        let credentials = &self.credentials;
+        self.selected
+            .retain(|id| credentials.iter().any(|c| c.credential_id == *id));
+    }
 
-        self._task = Some(cx.spawn(async move |_, cx| {
-            let reconnected = cx
-                .background_executor()
-                .spawn(async move {
-                    let start = std::time::Instant::now();
-                    while start.elapsed().as_secs() < 15 {
-                        std::thread::sleep(std::time::Duration::from_millis(200));
-                        if !DeviceRepo::check_hid_available_blocking() {
-                            break;
-                        }
-                    }
+    /// Ask for a credential ID, as hex or base64url, and delete it directly.
+    /// For passkeys a relying party still references but that no longer
+    /// enumerate cleanly.
+    pub(super) fn open_delete_by_id_dialog(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let id_input = cx.new(|cx| InputState::new(window, cx).placeholder("Hex or base64url"));
+        let view_handle = cx.entity().downgrade();
 
-                    while start.elapsed().as_secs() < 15 {
-                        std::thread::sleep(std::time::Duration::from_millis(500));
-                        if DeviceRepo::check_hid_available_blocking() {
-                            return true;
-                        }
-                    }
-                    false
-                })
-                .await;
+        window.open_dialog(cx, move |dialog, _, cx| {
+            let theme = cx.theme();
+            let id_input = id_input.clone();
+            let view_handle = view_handle.clone();
 
-            if !reconnected {
-                let _ = weak_self.update(cx, |this, cx| {
-                    this.loading = false;
-                    let _ = status_handle.update(cx, |d, cx| {
-                        d.set_error(
-                            "Timeout waiting for device reconnection. Reset canceled.".to_string(),
-                            cx,
-                        );
-                    });
-                    cx.notify();
-                });
+            dialog
+                .title("Delete by Credential ID")
+                .child(
+                    gpui_component::v_flex()
+                        .gap_4()
+                        .child(div().text_sm().text_color(theme.muted_foreground).child(
+                            "Paste the credential ID as hex or base64url, as browser \
+                                     devtools and server logs show it. The passkey is \
+                                     deleted right away, without the trash.",
+                        ))
+                        .child(
+                            gpui_component::v_flex()
+                                .gap_1()
+                                .child(div().text_sm().font_medium().child("Credential ID"))
+                                .child(
+                                    gpui_component::input::Input::new(&id_input)
+                                        .font_family("monospace"),
+                                ),
+                        )
+                        .child(
+                            gpui_component::h_flex()
+                                .justify_end()
+                                .gap_2()
+                                .child(
+                                    gpui_component::button::Button::new("cancel")
+                                        .label("Cancel")
+                                        .on_click(|_, window, cx| {
+                                            window.close_dialog(cx);
+                                        }),
+                                )
+                                .child(
+                                    gpui_component::button::Button::new("delete")
+                                        .danger()
+                                        .label("Delete")
+                                        .on_click(move |_, window, cx| {
+                                            let input = id_input.read(cx).value().to_string();
+                                            window.close_dialog(cx);
+                                            let status_handle = dialog::open_status_dialog(
+                                                "Delete by Credential ID",
+                                                window,
+                                                cx,
+                                            );
+                                            let _ = view_handle.update(cx, |this, cx| {
+                                                this.delete_by_credential_id(
+                                                    input,
+                                                    status_handle,
+                                                    cx,
+                                                );
+                                            });
+                                        }),
+                                ),
+                        ),
+                )
+                .overlay_closable(false)
+                .close_button(false)
+        });
+    }
+
+    fn delete_by_credential_id(
+        &mut self,
+        input: String,
+        status_handle: WeakEntity<StatusContent>,
+        cx: &mut Context<Self>,
+    ) {
+        let credential_id = match parse_credential_id(&input) {
+            Ok(id) => id,
+            Err(e) => {
+                let _ = status_handle.update(cx, |d, cx| d.set_error(e, cx));
                 return;
             }
-
+        };
+        let Some(pin) = self.cached_pin.clone() else {
             let _ = status_handle.update(cx, |d, cx| {
-                d.set_loading("Touch your security key now to confirm the reset...", cx);
+                d.set_error("Session expired, please unlock again.".to_string(), cx);
             });
+            self.lock_storage(cx);
+            return;
+        };
 
+        let action = ActionId::begin("passkey.delete_by_id");
+        action.scope(|| log::info!("Deleting credential {} by ID...", credential_id));
+        let weak_self = cx.entity().downgrade();
+
+        self._task = Some(cx.spawn(async move |_, cx| {
+            let act = action.clone();
             let result = cx
                 .background_executor()
-                .spawn(async move { DeviceRepo::reset_device_blocking() })
+                .spawn(async move {
+                    act.scope(|| {
+                        DeviceRepo::delete_credentials_bulk_blocking(pin, vec![credential_id])
+                    })
+                })
                 .await;
 
-            let _ = weak_self.update(cx, |this, cx| match result {
-                Ok(msg) => {
-                    log::info!("Device Reset: {}", msg);
-                    this.lock_storage(cx);
-                    let _ = status_handle.update(cx, |d, cx| {
-                        d.set_success(msg, cx);
-                    });
-                    cx.emit(PasskeysEvent::Notification(
-                        "Device reset successfully".into(),
-                    ));
-                    this.lock_storage(cx);
-                    this.sync_fido_state(None, cx);
-                }
-                Err(e) => {
-                    log::error!("Error resetting device: {}", e);
-                    this.loading = false;
-                    let _ = status_handle.update(cx, |d, cx| {
-                        d.set_error(format!("Reset failed: {}", e), cx);
-                    });
-                    cx.notify();
-                }
+            let _ = weak_self.update(cx, |this, cx| {
+                action.scope(|| match result {
+                    Ok(failures) if failures.is_empty() => {
+                        log::info!("Deleted credential by ID.");
+                        let _ = status_handle.update(cx, |d, cx| {
+                            d.set_success("Passkey deleted.".to_string(), cx)
+                        });
+                    }
+                    Ok(failures) => {
+                        let e = failures
+                            .into_iter()
+                            .map(|(_, e)| e)
+                            .collect::<Vec<_>>()
+                            .join("; ");
+                        log::error!("Error deleting credential by ID: {}", e);
+                        let _ = status_handle.update(cx, |d, cx| d.set_error(e, cx));
+                    }
+                    Err(e) => {
+                        log::error!("Error deleting credential by ID: {}", e);
+                        let _ = status_handle.update(cx, |d, cx| d.set_error(e, cx));
+                    }
+                });
+                this.loading = true;
+                this.sync_fido_state(None, cx);
             });
         }));
     }
 
-    pub(super) fn open_ask_delete_pin(
+    /// Ask for a new user name and display name for `cred`.
+    pub(super) fn open_edit_user_dialog(
         &mut self,
         cred: StoredCredential,
         window: &mut Window,
         cx: &mut Context<Self>,
     ) {
-        if let Some(pin) = &self.cached_pin {
-            self.open_delete_dialog(&cred, pin.clone(), window, cx);
-        } else {
-            window.push_notification("Session expired, please unlock again.", cx);
+        let name_input = cx.new(|cx| {
+            InputState::new(window, cx)
+                .placeholder("e.g. alice@example.com")
+                .default_value(cred.user_name.clone())
+        });
+        let display_name_input = cx.new(|cx| {
+            InputState::new(window, cx)
+                .placeholder("e.g. Alice")
+                .default_value(cred.user_display_name.clone())
+        });
+        let view_handle = cx.entity().downgrade();
+        let title = format!("Edit User for {}", cred.rp_id);
+
+        window.open_dialog(cx, move |dialog, _, cx| {
+            let theme = cx.theme();
+            let name_input = name_input.clone();
+            let display_name_input = display_name_input.clone();
+            let view_handle = view_handle.clone();
+            let cred = cred.clone();
+
+            dialog
+                .title(title.clone())
+                .child(
+                    gpui_component::v_flex()
+                        .gap_4()
+                        .child(div().text_sm().text_color(theme.muted_foreground).child(
+                            "Only the names stored on the key change. Sites keep \
+                                     their own copy and may still show the old one.",
+                        ))
+                        .child(
+                            gpui_component::v_flex()
+                                .gap_1()
+                                .child(div().text_sm().font_medium().child("User Name"))
+                                .child(gpui_component::input::Input::new(&name_input)),
+                        )
+                        .child(
+                            gpui_component::v_flex()
+                                .gap_1()
+                                .child(div().text_sm().font_medium().child("Display Name"))
+                                .child(gpui_component::input::Input::new(&display_name_input)),
+                        )
+                        .child(
+                            gpui_component::h_flex()
+                                .justify_end()
+                                .gap_2()
+                                .child(
+                                    gpui_component::button::Button::new("cancel")
+                                        .label("Cancel")
+                                        .on_click(|_, window, cx| {
+                                            window.close_dialog(cx);
+                                        }),
+                                )
+                                .child(
+                                    gpui_component::button::Button::new("save")
+                                        .primary()
+                                        .label("Save")
+                                        .on_click(move |_, window, cx| {
+                                            let name = name_input.read(cx).value().to_string();
+                                            let display_name =
+                                                display_name_input.read(cx).value().to_string();
+                                            window.close_dialog(cx);
+                                            let status_handle =
+                                                dialog::open_status_dialog("Edit User", window, cx);
+                                            let _ = view_handle.update(cx, |this, cx| {
+                                                this.update_credential_user(
+                                                    cred.clone(),
+                                                    name,
+                                                    display_name,
+                                                    status_handle,
+                                                    cx,
+                                                );
+                                            });
+                                        }),
+                                ),
+                        ),
+                )
+                .overlay_closable(false)
+                .close_button(false)
+        });
+    }
+
+    fn update_credential_user(
+        &mut self,
+        cred: StoredCredential,
+        name: String,
+        display_name: String,
+        status_handle: WeakEntity<StatusContent>,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(pin) = self.cached_pin.clone() else {
+            let _ = status_handle.update(cx, |d, cx| {
+                d.set_error("Session expired, please unlock again.".to_string(), cx);
+            });
             self.lock_storage(cx);
+            return;
+        };
+
+        let action = ActionId::begin("passkeys.update_user");
+        action.scope(|| log::info!("Updating user information for {}...", cred.rp_id));
+        let weak_self = cx.entity().downgrade();
+
+        self._task = Some(cx.spawn(async move |_, cx| {
+            let act = action.clone();
+            let credential_id = cred.credential_id.clone();
+            let (new_name, new_display_name) = (name.clone(), display_name.clone());
+            let result = cx
+                .background_executor()
+                .spawn(async move {
+                    act.scope(|| {
+                        DeviceRepo::update_credential_user_blocking(
+                            pin,
+                            cred.credential_id,
+                            cred.user_id,
+                            name,
+                            display_name,
+                        )
+                    })
+                })
+                .await;
+
+            let _ = weak_self.update(cx, |this, cx| {
+                action.scope(|| match result {
+                    Ok(msg) => {
+                        log::info!("{}", msg);
+                        if let Some(stored) = this
+                            .credentials
+                            .iter_mut()
+                            .find(|c| c.credential_id == credential_id)
+                        {
+                            stored.user_name = new_name.trim().to_string();
+                            stored.user_display_name = new_display_name.trim().to_string();
+                        }
+                        this.share_credentials(cx);
+                        let _ = status_handle.update(cx, |d, cx| d.set_success(msg, cx));
+                    }
+                    Err(e) => {
+                        log::error!("Failed to update user information: {}", e);
+                        let _ = status_handle.update(cx, |d, cx| d.set_error(e, cx));
+                    }
+                });
+                cx.notify();
+            });
+        }));
+    }
+
+    pub(super) fn is_trashed(&self, credential_id: &str) -> bool {
+        self.trash
+            .iter()
+            .any(|t| t.credential.credential_id == credential_id)
+    }
+
+    /// Seconds until the next trashed passkey is deleted.
+    pub(super) fn trash_seconds_left(&self) -> u64 {
+        let now = Instant::now();
+        self.trash
+            .iter()
+            .map(|t| t.deadline.saturating_duration_since(now).as_secs() + 1)
+            .min()
+            .unwrap_or(0)
+    }
+
+    /// Put every trashed passkey back; nothing was sent to the key.
+    pub(super) fn undo_trash(&mut self, cx: &mut Context<Self>) {
+        if self.trash.is_empty() {
+            return;
+        }
+        log::info!("Restored {} passkeys from trash", self.trash.len());
+        self.trash.clear();
+        cx.notify();
+    }
+
+    /// Delete everything in the trash now instead of waiting.
+    pub(super) fn empty_trash(&mut self, cx: &mut Context<Self>) {
+        let now = Instant::now();
+        for item in &mut self.trash {
+            item.deadline = now;
+        }
+        self.delete_due(cx);
+    }
+
+    /// Tick once a second while the trash is not empty: refresh the countdown
+    /// and delete what is due.
+    fn start_trash_timer(&mut self, cx: &mut Context<Self>) {
+        if self._trash_timer.is_some() {
+            return;
+        }
+        let weak_self = cx.entity().downgrade();
+        self._trash_timer = Some(cx.spawn(async move |_, cx| {
+            loop {
+                cx.background_executor()
+                    .timer(Duration::from_millis(500))
+                    .await;
+                let keep_going = weak_self
+                    .update(cx, |this, cx| {
+                        this.delete_due(cx);
+                        cx.notify();
+                        !this.trash.is_empty() || this.emptying_trash
+                    })
+                    .unwrap_or(false);
+                if !keep_going {
+                    break;
+                }
+            }
+            let _ = weak_self.update(cx, |this, _| this._trash_timer = None);
+        }));
+    }
+
+    /// Send DeleteCredential for every trashed passkey whose grace period is
+    /// over. Failed deletions are reported; the passkey stays on the key and
+    /// reappears in the list.
+    fn delete_due(&mut self, cx: &mut Context<Self>) {
+        if self.emptying_trash || DeviceRepo::maintenance_reason().is_some() {
+            return;
+        }
+        let now = Instant::now();
+        let (due, pending): (Vec<_>, Vec<_>) =
+            self.trash.drain(..).partition(|t| t.deadline <= now);
+        self.trash = pending;
+        if due.is_empty() {
+            return;
+        }
+        let Some(pin) = self.cached_pin.clone() else {
+            // Only reachable if the PIN was forgotten while a deletion was in
+            // flight; nothing can be sent without it.
+            log::warn!(
+                "Deletion of {} trashed passkeys cancelled: locked",
+                due.len()
+            );
+            cx.emit(PasskeysEvent::Notification(format!(
+                "Deletion of {} passkeys was cancelled. Unlock and delete them again.",
+                due.len()
+            )));
+            return;
+        };
+        self.emptying_trash = true;
+        self.start_trash_timer(cx);
+        cx.notify();
+
+        let action = ActionId::begin("passkey.delete");
+        action.scope(|| log::info!("Deleting {} trashed passkeys...", due.len()));
+        let weak_self = cx.entity().downgrade();
+
+        self._trash_task = Some(cx.spawn(async move |_, cx| {
+            let act = action.clone();
+            let total = due.len();
+            let creds: Vec<StoredCredential> = due.into_iter().map(|t| t.credential).collect();
+            let failures: Vec<String> = cx
+                .background_executor()
+                .spawn(async move { act.scope(|| delete_trashed(pin, &creds)) })
+                .await;
+
+            let _ = weak_self.update(cx, |this, cx| {
+                this.emptying_trash = false;
+                action.scope(|| {
+                    if failures.is_empty() {
+                        log::info!("Deleted {} passkeys.", total);
+                        cx.emit(PasskeysEvent::Notification(if total == 1 {
+                            "Passkey deleted.".to_string()
+                        } else {
+                            format!("{} passkeys deleted.", total)
+                        }));
+                    } else {
+                        for failure in &failures {
+                            log::error!("Error deleting credential {}", failure);
+                        }
+                        cx.emit(PasskeysEvent::Notification(format!(
+                            "Could not delete {} of {} passkeys: {}",
+                            failures.len(),
+                            total,
+                            failures.join("; ")
+                        )));
+                    }
+                });
+                this.loading = true;
+                this.sync_fido_state(None, cx);
+            });
+        }));
+    }
+
+    /// Send DeleteCredential for everything still in the trash, blocking,
+    /// as the app quits: the user was told these passkeys are being deleted.
+    fn delete_trash_on_quit(&mut self) {
+        if self.trash.is_empty() {
+            return;
+        }
+        let creds: Vec<StoredCredential> = self.trash.drain(..).map(|t| t.credential).collect();
+        let Some(pin) = self.cached_pin.clone() else {
+            log::warn!(
+                "Deletion of {} trashed passkeys cancelled: locked",
+                creds.len()
+            );
+            return;
+        };
+        log::info!(
+            "Deleting {} trashed passkeys before quitting...",
+            creds.len()
+        );
+        let failures = delete_trashed(pin, &creds);
+        for failure in &failures {
+            log::error!("Error deleting credential {}", failure);
         }
     }
 
@@ -894,6 +1691,59 @@ impl PasskeysViewModel {
         };
         let user_id = cred.user_id.clone();
         let credential_id = cred.credential_id.clone();
+        let public_key = match cred.key_description() {
+            description if description.is_empty() => "Not reported".to_string(),
+            description => description,
+        };
+        let cred_protect = cred
+            .cred_protect
+            .map_or("Not reported", |policy| policy.label());
+        let note = self.notes.get(&credential_id).cloned().unwrap_or_default();
+        let note_input = cx.new(|cx| {
+            InputState::new(window, cx)
+                .placeholder("e.g. work account")
+                .default_value(note.text)
+        });
+        let tags_input = cx.new(|cx| {
+            InputState::new(window, cx)
+                .placeholder("Comma-separated, e.g. recovery key, shared")
+                .default_value(note.tags.join(", "))
+        });
+
+        let serial = self
+            .device
+            .read(cx)
+            .status
+            .as_ref()
+            .map(|s| s.info.serial.clone())
+            .unwrap_or_default();
+        let qr = QrMatrix::encode(&inventory_payload(cred, &serial))
+            .inspect_err(|e| log::warn!("{}", e))
+            .ok();
+        let qr_file_name = format!("passkey-{}.svg", sanitize_file_stem(&rp_id));
+        let view_handle = cx.entity().downgrade();
+        let rp_credential_count = self.credentials.iter().filter(|c| c.rp_id == rp_id).count();
+        let sign_in_test = cx.new(|_| {
+            SignInTestPanel::new(
+                rp_id.clone(),
+                credential_id.clone(),
+                cred.public_key.clone(),
+                self.cached_pin.clone(),
+            )
+        });
+        let large_blob = self.supports_large_blobs(cx).then(|| {
+            let pin = self.cached_pin.clone();
+            cx.new(|cx| {
+                LargeBlobPanel::new(
+                    view_handle.clone(),
+                    rp_id.clone(),
+                    credential_id.clone(),
+                    pin,
+                    window,
+                    cx,
+                )
+            })
+        });
 
         window.open_sheet_at(
             gpui_component::Placement::Bottom,
@@ -1006,16 +1856,314 @@ impl PasskeysViewModel {
                                 .child(separator)
                                 .child(detail_field("Display Name", display_name.clone(), false))
                                 .child(detail_field("User ID (Hex)", user_id.clone(), true))
+                                .child(detail_field("Public Key", public_key.clone(), false))
+                                .child(detail_field("Protection", cred_protect.to_string(), false))
                                 .child(detail_field(
                                     "Credential ID (Hex)",
                                     credential_id.clone(),
                                     true,
-                                )),
+                                ))
+                                .child(
+                                    gpui_component::v_flex()
+                                        .gap_2()
+                                        .child(
+                                            div()
+                                                .text_sm()
+                                                .font_medium()
+                                                .text_color(theme.muted_foreground)
+                                                .child("Notes (stored on this computer only)"),
+                                        )
+                                        .child(gpui_component::input::Input::new(&note_input))
+                                        .child(gpui_component::input::Input::new(&tags_input))
+                                        .child({
+                                            let view_handle = view_handle.clone();
+                                            let credential_id = credential_id.clone();
+                                            let note_input = note_input.clone();
+                                            let tags_input = tags_input.clone();
+                                            gpui_component::button::Button::new(
+                                                "save-credential-note",
+                                            )
+                                            .label("Save Note")
+                                            .on_click(move |_, window, cx| {
+                                                let text =
+                                                    note_input.read(cx).text().to_string();
+                                                let tags =
+                                                    tags_input.read(cx).text().to_string();
+                                                let credential_id = credential_id.clone();
+                                                let _ = view_handle.update(cx, |this, cx| {
+                                                    this.save_credential_note(
+                                                        credential_id, text, tags, window, cx,
+                                                    );
+                                                });
+                                            })
+                                        }),
+                                )
+                                .child(sign_in_test.clone())
+                                .children(large_blob.clone())
+                                .children(qr.clone().map(|matrix| {
+                                    let svg = matrix.to_svg();
+                                    let file_name = qr_file_name.clone();
+                                    let view_handle = view_handle.clone();
+                                    gpui_component::v_flex()
+                                        .gap_2()
+                                        .child(
+                                            div()
+                                                .text_sm()
+                                                .font_medium()
+                                                .text_color(theme.muted_foreground)
+                                                .child("Inventory Label"),
+                                        )
+                                        .child(
+                                            gpui_component::h_flex()
+                                                .gap_4()
+                                                .items_end()
+                                                .child(QrCodeView::new(matrix).module_size(px(3.)))
+                                                .child(
+                                                    gpui_component::v_flex()
+                                                        .gap_2()
+                                                        .child(
+                                                            div()
+                                                                .text_xs()
+                                                                .text_color(theme.muted_foreground)
+                                                                .child("Relying party, user name, a shortened credential ID and the key's serial. No secrets."),
+                                                        )
+                                                        .child(
+                                                            gpui_component::button::Button::new(
+                                                                "save-credential-qr",
+                                                            )
+                                                            .label("Save QR as SVG")
+                                                            .on_click(move |_, _, cx| {
+                                                                let svg = svg.clone();
+                                                                let file_name = file_name.clone();
+                                                                let _ = view_handle.update(
+                                                                    cx,
+                                                                    |this, cx| {
+                                                                        this.save_credential_qr(
+                                                                            svg, file_name, cx,
+                                                                        );
+                                                                    },
+                                                                );
+                                                            }),
+                                                        ),
+                                                ),
+                                        )
+                                }))
+                                .child({
+                                    let view_handle = view_handle.clone();
+                                    let rp_id = rp_id.clone();
+                                    gpui_component::button::Button::new("trash-relying-party")
+                                        .danger()
+                                        .label(if rp_credential_count == 1 {
+                                            format!("Delete the passkey for {}", rp_id)
+                                        } else {
+                                            format!(
+                                                "Delete all {} passkeys for {}",
+                                                rp_credential_count, rp_id
+                                            )
+                                        })
+                                        .on_click(move |_, window, cx| {
+                                            window.close_sheet(cx);
+                                            let _ = view_handle.update(cx, |this, cx| {
+                                                this.trash_relying_party(&rp_id, window, cx);
+                                            });
+                                        })
+                                }),
                         ),
                     )
             },
         );
     }
+
+    /// Ask for a destination, then re-read the credential list from the key
+    /// and write it there as JSON or CSV.
+    pub(super) fn export_credentials(
+        &mut self,
+        format: CredentialExportFormat,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(pin) = self.cached_pin.clone() else {
+            return;
+        };
+        let serial = self
+            .device
+            .read(cx)
+            .status
+            .as_ref()
+            .map(|s| s.info.serial.clone())
+            .unwrap_or_default();
+        let file_name = if serial.is_empty() {
+            format!("passkeys.{}", format.extension())
+        } else {
+            format!(
+                "passkeys-{}.{}",
+                sanitize_file_stem(&serial),
+                format.extension()
+            )
+        };
+        let default_dir = UserDirs::new()
+            .and_then(|d| {
+                d.document_dir()
+                    .or_else(|| d.download_dir())
+                    .map(|p| p.to_path_buf())
+            })
+            .unwrap_or_else(|| {
+                std::path::PathBuf::from(std::env::var("HOME").unwrap_or_else(|_| ".".into()))
+            });
+        let receiver = cx.prompt_for_new_path(&default_dir, Some(&file_name));
+        let entity = cx.entity().downgrade();
+        self._task = Some(cx.spawn(async move |_, cx| {
+            let path = match receiver.await {
+                Ok(Ok(Some(path))) => path,
+                Ok(Err(e)) => {
+                    let _ = entity.update(cx, |_, cx| {
+                        cx.emit(PasskeysEvent::Notification(format!(
+                            "Save dialog error: {}",
+                            e
+                        )));
+                    });
+                    return;
+                }
+                _ => return,
+            };
+            let result = cx
+                .background_executor()
+                .spawn(async move {
+                    let data = DeviceRepo::export_credentials_blocking(pin, format)?;
+                    std::fs::write(&path, data)
+                        .map(|_| path)
+                        .map_err(|e| format!("Could not write file: {}", e))
+                })
+                .await;
+            let msg = match result {
+                Ok(path) => format!("Passkey list exported to {}", path.display()),
+                Err(e) => {
+                    log::error!("Passkey export failed: {}", e);
+                    format!("Failed to export passkeys: {}", e)
+                }
+            };
+            let _ = entity.update(cx, |_, cx| {
+                cx.emit(PasskeysEvent::Notification(msg));
+            });
+        }));
+    }
+
+    /// Ask for a password manager export and compare its passkeys with the
+    /// ones on the key.
+    pub(super) fn compare_synced_export(&mut self, cx: &mut Context<Self>) {
+        let receiver = cx.prompt_for_paths(gpui::PathPromptOptions {
+            files: true,
+            directories: false,
+            multiple: false,
+            prompt: Some("Select Password Manager Export (JSON or CSV)".into()),
+        });
+        let entity = cx.entity().downgrade();
+        self._synced_task = Some(cx.spawn(async move |_, cx| {
+            let Ok(Ok(Some(paths))) = receiver.await else {
+                return;
+            };
+            let Some(path) = paths.into_iter().next() else {
+                return;
+            };
+            let result = cx
+                .background_executor()
+                .spawn(async move { synced_passkeys::load(&path) })
+                .await;
+            let _ = entity.update(cx, |this, cx| match result {
+                Ok(export) => {
+                    log::info!(
+                        "Read {} synced passkey(s) from a {} export",
+                        export.passkeys.len(),
+                        export.format.label()
+                    );
+                    this.synced_export = Some(export);
+                    cx.notify();
+                }
+                Err(e) => {
+                    log::error!("Could not read password manager export: {}", e);
+                    cx.emit(PasskeysEvent::Notification(format!(
+                        "Could not compare passkeys: {}",
+                        e
+                    )));
+                }
+            });
+        }));
+    }
+
+    pub(super) fn clear_synced_export(&mut self, cx: &mut Context<Self>) {
+        self.synced_export = None;
+        cx.notify();
+    }
+
+    /// Ask for a destination and write the inventory QR code there.
+    fn save_credential_qr(&mut self, svg: String, file_name: String, cx: &mut Context<Self>) {
+        let default_dir = UserDirs::new()
+            .and_then(|d| {
+                d.document_dir()
+                    .or_else(|| d.download_dir())
+                    .map(|p| p.to_path_buf())
+            })
+            .unwrap_or_else(|| {
+                std::path::PathBuf::from(std::env::var("HOME").unwrap_or_else(|_| ".".into()))
+            });
+        let receiver = cx.prompt_for_new_path(&default_dir, Some(&file_name));
+        let entity = cx.entity().downgrade();
+        self._task = Some(cx.spawn(async move |_, cx| {
+            let msg = match receiver.await {
+                Ok(Ok(Some(path))) => match std::fs::write(&path, svg.as_bytes()) {
+                    Ok(_) => format!("QR code saved to {}", path.display()),
+                    Err(e) => format!("Failed to save QR code: {}", e),
+                },
+                Ok(Err(e)) => format!("Save dialog error: {}", e),
+                _ => return,
+            };
+            let _ = entity.update(cx, |_, cx| {
+                cx.emit(PasskeysEvent::Notification(msg));
+            });
+        }));
+    }
+}
+
+/// `getCredsMetadata` after listing credentials. Optional: the list is still
+/// shown when the key rejects it.
+fn read_credentials_metadata(pin: String) -> Option<CredentialsMetadata> {
+    DeviceRepo::get_credentials_metadata_blocking(pin)
+        .map_err(|e| log::warn!("Could not read credential metadata: {}", e))
+        .ok()
+}
+
+/// Non-sensitive inventory record for one credential, encoded into its QR code.
+///
+/// Only the first 16 hex digits of the credential ID are included: enough to
+/// tell credentials apart in a binder, not enough to use it as an allow-list
+/// entry.
+fn inventory_payload(cred: &StoredCredential, serial: &str) -> String {
+    let short_id: String = cred.credential_id.chars().take(16).collect();
+    serde_json::json!({
+        "rp": cred.rp_id,
+        "user": cred.user_name,
+        "cred": format!("{}…", short_id),
+        "key": serial,
+    })
+    .to_string()
+}
+
+/// Replace characters that are awkward in file names.
+fn sanitize_file_stem(name: &str) -> String {
+    let stem: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if stem.is_empty() {
+        "credential".into()
+    } else {
+        stem
+    }
 }
 
 struct SliderLabel {
@@ -1028,3 +2176,25 @@ impl Render for SliderLabel {
         format!("Minimum PIN Length ({})", val)
     }
 }
+
+/// Delete `creds` with one PIN token. Returns `"<rp>: <error>"` per failure.
+fn delete_trashed(pin: String, creds: &[StoredCredential]) -> Vec<String> {
+    let rp_of = |id: &str| {
+        creds
+            .iter()
+            .find(|c| c.credential_id == id)
+            .map(|c| c.rp_id.clone())
+            .unwrap_or_default()
+    };
+    let ids = creds.iter().map(|c| c.credential_id.clone()).collect();
+    match DeviceRepo::delete_credentials_bulk_blocking(pin, ids) {
+        Ok(failed) => failed
+            .into_iter()
+            .map(|(id, e)| format!("{}: {}", rp_of(&id), e))
+            .collect(),
+        Err(e) => creds
+            .iter()
+            .map(|c| format!("{}: {}", c.rp_id, e))
+            .collect(),
+    }
+}
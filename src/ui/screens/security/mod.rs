@@ -1,4 +1,5 @@
-//! Security screen — secure boot, enterprise attestation, device reset.
+//! Security screen — secure boot, secure lock, always-UV policy, enterprise
+//! attestation, device reset.
 
 pub mod view;
 pub mod view_model;
@@ -1,16 +1,76 @@
-use crate::ui::components::page_view::PageView;
+use crate::ui::components::{
+    card::Card,
+    page_view::PageView,
+    view_state::{self, EmptyState},
+};
 use crate::ui::screens::security::view_model::SecurityViewModel;
+use gpui::prelude::FluentBuilder;
 use gpui::*;
 use gpui_component::{
     ActiveTheme, Disableable, Icon, StyledExt,
     button::{Button, ButtonCustomVariant, ButtonVariants},
     h_flex,
+    input::Input,
+    progress::Progress,
     switch::Switch,
     v_flex,
 };
 
 impl Render for SecurityViewModel {
     fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let phase = self.device.read(cx).phase();
+        let no_device = EmptyState::new(
+            Icon::default().path("icons/hard-drive.svg"),
+            "No Device Connected",
+        )
+        .description("Connect your pico-key to review its security settings.");
+        if let Some(placeholder) = view_state::for_phase(&phase, no_device, &self.device) {
+            return PageView::build(
+                "Secure Boot",
+                "Permanently lock this device to the current firmware vendor.",
+                placeholder,
+                cx.theme(),
+            )
+            .into_any_element();
+        }
+
+        // Generic FIDO2 keys get the authenticatorConfig cards and the
+        // reset; secure boot, secure lock and backup are pico-fido vendor
+        // commands.
+        let vendor = self.device.read(cx).capabilities().vendor_management;
+        let secure_boot = vendor.then(|| self.render_secure_boot(cx));
+        let secure_lock = vendor.then(|| self.render_secure_lock(cx));
+        let always_uv = self.render_always_uv(cx);
+        let enterprise_attestation = self.render_enterprise_attestation(cx);
+        let min_pin_rp_ids = self.render_min_pin_rp_ids(cx);
+        let backup = vendor.then(|| self.render_backup(cx));
+        let reset = self.render_reset(cx);
+        let theme = cx.theme();
+
+        let content = v_flex()
+            .gap_6()
+            .w_full()
+            .children(secure_boot)
+            .children(secure_lock)
+            .child(always_uv)
+            .child(enterprise_attestation)
+            .child(min_pin_rp_ids)
+            .children(backup)
+            .child(reset);
+
+        PageView::build(
+            "Secure Boot",
+            "Permanently lock this device to the current firmware vendor.",
+            content,
+            theme,
+        )
+        .into_any_element()
+    }
+}
+
+impl SecurityViewModel {
+    fn render_secure_boot(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let state = self.secure_boot(cx);
         let theme = cx.theme();
         let fg = theme.foreground;
         let muted_fg = theme.muted_foreground;
@@ -23,163 +83,598 @@ impl Render for SecurityViewModel {
         let destructive_border = rgba(0xef44444d);
         let destructive_bg_muted = rgba(0xef44441a);
 
-        let content = v_flex()
-            .gap_6()
+        let (enabled, locked) = state.unwrap_or_default();
+        let status = match state {
+            Some((_, true)) => "Enabled and locked. The device only boots signed firmware.",
+            Some((true, false)) => "Enabled. Lock it to make it permanent.",
+            Some((false, _)) => "Off. The device boots any firmware.",
+            None => {
+                "Secure boot is read and changed through the Rescue applet, which needs a \
+                 pico-fido key reachable over CCID (PC/SC)."
+            }
+        };
+        let unavailable = state.is_none() || locked || self.sb_busy;
+        let actionable = !locked && (!enabled || self.sb_lock);
+        let button_label = if self.sb_lock {
+            "Permanently Lock Device"
+        } else {
+            "Enable Secure Boot"
+        };
+        let result = self.sb_result.clone().map(|result| {
+            let (msg, color) = match result {
+                Ok(msg) => (msg, theme.muted_foreground),
+                Err(msg) => (msg, theme.danger),
+            };
+            div().text_sm().text_color(color).child(msg)
+        });
+
+        v_flex()
             .w_full()
+            .border_1()
+            .border_color(destructive_border)
+            .bg(card_bg)
+            .rounded_xl()
+            .overflow_hidden()
             .child(
                 v_flex()
-                    .w_full()
-                    .p_4()
-                    .gap_2()
-                    .border_1()
-                    .border_color(destructive_border)
-                    .bg(card_bg)
-                    .rounded_md()
+                    .p_6()
+                    .gap_1()
+                    .child(
+                        div()
+                            .text_lg()
+                            .font_bold()
+                            .text_color(fg)
+                            .child("Lock Settings"),
+                    )
+                    .child(div().text_sm().text_color(muted_fg).child(status)),
+            )
+            .child(
+                v_flex()
+                    .px_6()
+                    .pb_6()
+                    .gap_6()
+                    .child(
+                        h_flex()
+                            .justify_between()
+                            .items_center()
+                            .child(
+                                v_flex()
+                                    .gap_1()
+                                    .child(div().text_sm().font_medium().child("Secure Boot"))
+                                    .child(
+                                        div()
+                                            .text_xs()
+                                            .text_color(muted_fg)
+                                            .child("Verifies firmware signature on startup"),
+                                    ),
+                            )
+                            .child(
+                                Switch::new("secure-boot-switch")
+                                    .checked(enabled)
+                                    .disabled(true),
+                            ),
+                    )
+                    .child(
+                        h_flex()
+                            .justify_between()
+                            .items_center()
+                            .child(
+                                v_flex()
+                                    .gap_1()
+                                    .child(div().text_sm().font_medium().child("Lock Secure Boot"))
+                                    .child(div().text_xs().text_color(muted_fg).child(
+                                        "Makes secure boot permanent and prevents reading key \
+                                         material via debug ports",
+                                    )),
+                            )
+                            .child(
+                                Switch::new("secure-lock-switch")
+                                    .checked(locked || self.sb_lock)
+                                    .disabled(unavailable)
+                                    .on_click(cx.listener(|this, checked, _, cx| {
+                                        this.sb_lock = *checked;
+                                        cx.notify();
+                                    })),
+                            ),
+                    )
+                    .child(div().h_px().bg(border))
                     .child(
                         h_flex()
-                            .gap_2()
                             .items_center()
+                            .gap_4()
+                            .p_4()
+                            .rounded_md()
+                            .bg(destructive_bg_muted)
+                            .border_1()
+                            .border_color(destructive_border)
                             .child(
-                                Icon::default()
-                                    .path("icons/triangle-alert.svg")
-                                    .text_color(destructive_red),
+                                Switch::new("confirm-switch")
+                                    .checked(self.sb_confirmed)
+                                    .disabled(unavailable)
+                                    .on_click(cx.listener(|this, checked, _, cx| {
+                                        this.sb_confirmed = *checked;
+                                        cx.notify();
+                                    })),
                             )
                             .child(
                                 div()
-                                    .font_bold()
+                                    .font_medium()
                                     .text_color(destructive_red)
-                                    .child("Feature Unstable"),
+                                    .child("I understand the risks of bricking my device."),
                             ),
                     )
+                    .children(result),
+            )
+            .child(
+                div()
+                    .border_t_1()
+                    .border_color(border)
+                    .bg(gpui::rgba(0x00000033))
+                    .px_6()
+                    .py_4()
+                    .flex()
+                    .justify_end()
                     .child(
-                        div()
-                            .text_sm()
-                            .text_color(destructive_red)
-                            .child("This feature is currently under work and disabled for safety."),
+                        Button::new("lock-device-btn")
+                            .custom(
+                                ButtonCustomVariant::new(cx)
+                                    .color(destructive_red.into())
+                                    .hover(destructive_red_hover.into())
+                                    .active(destructive_red_active.into()),
+                            )
+                            .loading(self.sb_busy)
+                            .disabled(unavailable || !actionable || !self.sb_confirmed)
+                            .on_click(cx.listener(|this, _, window, cx| {
+                                this.open_secure_boot_dialog(window, cx);
+                            }))
+                            .child(
+                                h_flex()
+                                    .gap_2()
+                                    .items_center()
+                                    .child(Icon::default().path("icons/lock.svg").size_4())
+                                    .child(button_label),
+                            ),
                     ),
             )
+    }
+
+    fn render_secure_lock(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let secure_lock = self.secure_lock(cx);
+        let theme = cx.theme();
+
+        let (state, description) = match secure_lock {
+            Some(true) => (
+                "On",
+                "The device key is stored encrypted under a host key kept on this computer. \
+                 Unlock the key here after plugging it in.",
+            ),
+            Some(false) => (
+                "Off",
+                "Store the device key encrypted under a host key kept on this computer, so \
+                 the key cannot sign after a power cycle until it is unlocked from here",
+            ),
+            None => (
+                "Not supported",
+                "This key does not report the AuthEncryption vendor command, so secure lock \
+                 is unavailable",
+            ),
+        };
+        let result = self.lock_result.clone().map(|result| {
+            let (msg, color) = match result {
+                Ok(msg) => (msg, theme.muted_foreground),
+                Err(msg) => (msg, theme.danger),
+            };
+            div().text_sm().text_color(color).child(msg)
+        });
+
+        Card::new()
+            .title("Secure Lock")
+            .icon(Icon::default().path("icons/lock.svg"))
+            .description("pico-fido AuthEncryption and Unlock")
             .child(
                 v_flex()
-                    .w_full()
-                    .border_1()
-                    .border_color(destructive_border)
-                    .bg(card_bg)
-                    .rounded_xl()
-                    .overflow_hidden()
-                    .child(
-                        div().p_6().child(
-                            div()
-                                .text_lg()
-                                .font_bold()
-                                .text_color(fg)
-                                .child("Lock Settings"),
-                        ),
-                    )
+                    .gap_3()
                     .child(
-                        v_flex()
-                            .px_6()
-                            .pb_6()
-                            .gap_6()
+                        h_flex()
+                            .justify_between()
+                            .items_center()
+                            .gap_4()
                             .child(
-                                h_flex()
-                                    .justify_between()
-                                    .items_center()
+                                v_flex()
+                                    .gap_1()
                                     .child(
-                                        v_flex()
-                                            .gap_1()
+                                        h_flex()
+                                            .gap_2()
+                                            .items_baseline()
                                             .child(
-                                                div()
-                                                    .text_sm()
-                                                    .font_medium()
-                                                    .child("Enable Secure Boot"),
+                                                div().text_sm().font_medium().child("Secure Lock"),
                                             )
                                             .child(
-                                                div().text_xs().text_color(muted_fg).child(
-                                                    "Verifies firmware signature on startup",
-                                                ),
+                                                div()
+                                                    .text_xs()
+                                                    .text_color(theme.muted_foreground)
+                                                    .child(state),
                                             ),
                                     )
                                     .child(
-                                        Switch::new("secure-boot-switch")
-                                            .checked(false)
-                                            .disabled(true),
+                                        div()
+                                            .text_xs()
+                                            .text_color(theme.muted_foreground)
+                                            .child(description),
                                     ),
                             )
                             .child(
-                                h_flex()
-                                    .justify_between()
-                                    .items_center()
+                                Switch::new("security-secure-lock-switch")
+                                    .checked(secure_lock.unwrap_or(false))
+                                    .disabled(secure_lock.is_none() || self.lock_busy)
+                                    .on_click(cx.listener(|this, _, window, cx| {
+                                        this.open_secure_lock_dialog(window, cx);
+                                    })),
+                            ),
+                    )
+                    .when(secure_lock == Some(true), |this| {
+                        this.child(
+                            h_flex().justify_end().child(
+                                Button::new("security-unlock")
+                                    .label("Unlock Key")
+                                    .loading(self.lock_busy)
+                                    .disabled(self.lock_busy)
+                                    .on_click(cx.listener(|this, _, _, cx| {
+                                        this.unlock(cx);
+                                    })),
+                            ),
+                        )
+                    })
+                    .children(result),
+            )
+    }
+
+    fn render_always_uv(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let always_uv = self.always_uv(cx);
+        let theme = cx.theme();
+
+        let description = match always_uv {
+            Some(_) => {
+                "Ask for the PIN or fingerprint on every sign-in, even when the site does not request it"
+            }
+            None => "This key does not report the alwaysUv option, so the policy cannot be changed",
+        };
+        let result = self.result.clone().map(|result| {
+            let (msg, color) = match result {
+                Ok(msg) => (msg, theme.muted_foreground),
+                Err(msg) => (msg, theme.danger),
+            };
+            div().text_sm().text_color(color).child(msg)
+        });
+
+        Card::new()
+            .title("User Verification")
+            .icon(Icon::default().path("icons/shield.svg"))
+            .description("FIDO2 authenticatorConfig policy")
+            .child(
+                v_flex()
+                    .gap_3()
+                    .child(
+                        h_flex()
+                            .justify_between()
+                            .items_center()
+                            .gap_4()
+                            .child(
+                                v_flex()
+                                    .gap_1()
                                     .child(
-                                        v_flex()
-                                            .gap_1()
+                                        div()
+                                            .text_sm()
+                                            .font_medium()
+                                            .child("Always Require User Verification"),
+                                    )
+                                    .child(
+                                        div()
+                                            .text_xs()
+                                            .text_color(theme.muted_foreground)
+                                            .child(description),
+                                    ),
+                            )
+                            .child(
+                                Switch::new("always-uv-switch")
+                                    .checked(always_uv.unwrap_or(false))
+                                    .disabled(always_uv.is_none() || self.toggling)
+                                    .on_click(cx.listener(|this, _, window, cx| {
+                                        this.open_always_uv_dialog(window, cx);
+                                    })),
+                            ),
+                    )
+                    .children(result),
+            )
+    }
+
+    fn render_enterprise_attestation(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let ep = self.enterprise_attestation(cx);
+        let theme = cx.theme();
+
+        let (state, description) = match ep {
+            Some(true) => (
+                "On",
+                "Relying parties on the key's enterprise list receive a per-device \
+                 attestation. Only a reset turns it off.",
+            ),
+            Some(false) => (
+                "Off",
+                "Let relying parties on the key's enterprise list receive a per-device \
+                 attestation, so managed keys can be told apart",
+            ),
+            None => (
+                "Not supported",
+                "This key does not report the ep option, so enterprise attestation is unavailable",
+            ),
+        };
+        let result = self.ea_result.clone().map(|result| {
+            let (msg, color) = match result {
+                Ok(msg) => (msg, theme.muted_foreground),
+                Err(msg) => (msg, theme.danger),
+            };
+            div().text_sm().text_color(color).child(msg)
+        });
+
+        Card::new()
+            .title("Enterprise Attestation")
+            .icon(Icon::default().path("icons/shield-check.svg"))
+            .description("FIDO2 enableEnterpriseAttestation")
+            .child(
+                v_flex()
+                    .gap_3()
+                    .child(
+                        h_flex()
+                            .justify_between()
+                            .items_center()
+                            .gap_4()
+                            .child(
+                                v_flex()
+                                    .gap_1()
+                                    .child(
+                                        h_flex()
+                                            .gap_2()
+                                            .items_baseline()
                                             .child(
-                                                div().text_sm().font_medium().child("Secure Lock"),
+                                                div()
+                                                    .text_sm()
+                                                    .font_medium()
+                                                    .child("Enterprise Attestation"),
                                             )
-                                            .child(div().text_xs().text_color(muted_fg).child(
-                                                "Prevents reading key material via debug ports",
-                                            )),
+                                            .child(
+                                                div()
+                                                    .text_xs()
+                                                    .text_color(theme.muted_foreground)
+                                                    .child(state),
+                                            ),
                                     )
                                     .child(
-                                        Switch::new("secure-lock-switch")
-                                            .checked(false)
-                                            .disabled(true),
+                                        div()
+                                            .text_xs()
+                                            .text_color(theme.muted_foreground)
+                                            .child(description),
                                     ),
                             )
-                            .child(div().h_px().bg(border))
+                            .child(
+                                Switch::new("security-ep-switch")
+                                    .checked(ep.unwrap_or(false))
+                                    .disabled(ep != Some(false) || self.ea_busy)
+                                    .on_click(cx.listener(|this, _, window, cx| {
+                                        this.open_enterprise_attestation_dialog(window, cx);
+                                    })),
+                            ),
+                    )
+                    .children(result),
+            )
+    }
+
+    fn render_min_pin_rp_ids(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let max = self.max_rp_ids(cx);
+        let min_len = self
+            .device
+            .read(cx)
+            .fido_info
+            .as_ref()
+            .map(|info| info.min_pin_length);
+        let theme = cx.theme();
+
+        let description = match (max, min_len) {
+            (Some(max), Some(len)) => format!(
+                "Relying parties allowed to read the minimum PIN length ({}) through the \
+                 minPinLength extension. Up to {} RP IDs, one per line; the list replaces \
+                 the one on the key, which cannot be read back.",
+                len, max
+            ),
+            _ => {
+                "This key does not accept a list of RP IDs for the minimum PIN length.".to_string()
+            }
+        };
+        let result = self.rp_ids_result.clone().map(|result| {
+            let (msg, color) = match result {
+                Ok(msg) => (msg, theme.muted_foreground),
+                Err(msg) => (msg, theme.danger),
+            };
+            div().text_sm().text_color(color).child(msg)
+        });
+
+        Card::new()
+            .title("Minimum PIN Length RP IDs")
+            .icon(Icon::default().path("icons/key-round.svg"))
+            .description("FIDO2 setMinPINLength policy")
+            .child(
+                v_flex()
+                    .gap_3()
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(theme.muted_foreground)
+                            .child(description),
+                    )
+                    .child(
+                        Input::new(&self.rp_ids_input)
+                            .h(px(120.0))
+                            .font_family("monospace")
+                            .text_sm()
+                            .disabled(max.is_none() || self.rp_ids_busy),
+                    )
+                    .child(
+                        h_flex()
+                            .justify_between()
+                            .items_center()
+                            .gap_4()
                             .child(
                                 h_flex()
+                                    .gap_3()
                                     .items_center()
-                                    .gap_4()
-                                    .p_4()
-                                    .rounded_md()
-                                    .bg(destructive_bg_muted)
-                                    .border_1()
-                                    .border_color(destructive_border)
                                     .child(
-                                        Switch::new("confirm-switch").checked(false).disabled(true),
+                                        Switch::new("force-change-pin-switch")
+                                            .checked(self.force_change_pin)
+                                            .disabled(max.is_none() || self.rp_ids_busy)
+                                            .on_click(cx.listener(|this, checked, _, cx| {
+                                                this.force_change_pin = *checked;
+                                                cx.notify();
+                                            })),
                                     )
                                     .child(
                                         div()
-                                            .font_medium()
-                                            .text_color(destructive_red)
-                                            .child("I understand the risks of bricking my device."),
+                                            .text_sm()
+                                            .child("Require a PIN change before next use"),
                                     ),
+                            )
+                            .child(
+                                Button::new("apply-min-pin-rp-ids")
+                                    .primary()
+                                    .label("Apply")
+                                    .loading(self.rp_ids_busy)
+                                    .disabled(max.is_none() || self.rp_ids_busy)
+                                    .on_click(cx.listener(|this, _, window, cx| {
+                                        this.open_rp_ids_dialog(window, cx);
+                                    })),
                             ),
                     )
+                    .children(result),
+            )
+    }
+
+    fn render_backup(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+
+        let progress = self.backup_progress.map(|progress| {
+            let detail = if progress.total > 0 {
+                format!("{} of {} bytes", progress.done, progress.total)
+            } else {
+                String::new()
+            };
+            v_flex()
+                .gap_2()
+                .child(
+                    h_flex()
+                        .justify_between()
+                        .text_sm()
+                        .child(
+                            div()
+                                .text_color(theme.muted_foreground)
+                                .child(progress.stage.label()),
+                        )
+                        .child(div().text_color(theme.foreground).child(detail)),
+                )
+                .child(Progress::new().value(progress.fraction() * 100.0))
+        });
+        let result = self.backup_result.clone().map(|result| {
+            let (msg, color) = match result {
+                Ok(msg) => (msg, theme.muted_foreground),
+                Err(msg) => (msg, theme.danger),
+            };
+            div().text_sm().text_color(color).child(msg)
+        });
+
+        Card::new()
+            .title("Backup")
+            .icon(Icon::default().path("icons/hard-drive.svg"))
+            .description("pico-fido encrypted backup")
+            .child(
+                v_flex()
+                    .gap_3()
+                    .child(div().text_xs().text_color(theme.muted_foreground).child(
+                        "Save the key's secrets to a file, encrypted by the firmware, and \
+                                 restore them to this key later. The file is useless without \
+                                 the key's own unlock material, but keep it somewhere safe.",
+                    ))
+                    .children(progress)
                     .child(
-                        div()
-                            .border_t_1()
-                            .border_color(border)
-                            .bg(gpui::rgba(0x00000033))
-                            .px_6()
-                            .py_4()
-                            .flex()
+                        h_flex()
+                            .gap_2()
                             .justify_end()
                             .child(
-                                Button::new("lock-device-btn")
-                                    .custom(
-                                        ButtonCustomVariant::new(cx)
-                                            .color(destructive_red.into())
-                                            .hover(destructive_red_hover.into())
-                                            .active(destructive_red_active.into()),
-                                    )
-                                    .disabled(true)
-                                    .child(
-                                        h_flex()
-                                            .gap_2()
-                                            .items_center()
-                                            .child(Icon::default().path("icons/lock.svg").size_4())
-                                            .child("Permanently Lock Device"),
-                                    ),
+                                Button::new("security-restore-backup")
+                                    .label("Restore from File…")
+                                    .disabled(self.backup_busy)
+                                    .on_click(cx.listener(|this, _, window, cx| {
+                                        this.start_restore(window, cx);
+                                    })),
+                            )
+                            .child(
+                                Button::new("security-save-backup")
+                                    .primary()
+                                    .label("Back Up to File…")
+                                    .loading(self.backup_busy)
+                                    .disabled(self.backup_busy)
+                                    .on_click(cx.listener(|this, _, _, cx| {
+                                        this.start_backup(cx);
+                                    })),
                             ),
+                    )
+                    .children(result),
+            )
+    }
+
+    fn render_reset(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+
+        let header = h_flex()
+            .items_center()
+            .justify_between()
+            .w_full()
+            .gap_4()
+            .child(
+                v_flex()
+                    .gap_1()
+                    .child(
+                        div()
+                            .text_base()
+                            .font_weight(gpui::FontWeight::MEDIUM)
+                            .text_color(theme.foreground)
+                            .child("Factory Reset"),
+                    )
+                    .child(
+                        div()
+                            .text_sm()
+                            .text_color(theme.muted_foreground)
+                            .child("Erase all passkeys, credentials, and PIN. Cannot be undone."),
                     ),
+            )
+            .child(
+                Button::new("security-reset-device")
+                    .icon(Icon::default().path("icons/circle-alert.svg"))
+                    .child("Reset Device")
+                    .custom(
+                        ButtonCustomVariant::new(cx)
+                            .color(theme.danger)
+                            .hover(theme.danger_hover)
+                            .active(theme.danger_active)
+                            .foreground(theme.danger_foreground),
+                    )
+                    .loading(self.reset_busy)
+                    .disabled(self.reset_busy || self.backup_busy)
+                    .on_click(cx.listener(|this, _, window, cx| {
+                        this.open_reset_dialog(window, cx);
+                    })),
             );
 
-        PageView::build(
-            "Secure Boot",
-            "Permanently lock this device to the current firmware vendor.",
-            content,
-            theme,
-        )
+        Card::new()
+            .title("Reset")
+            .description("Perform a destructive factory reset")
+            .icon(Icon::default().path("icons/trash.svg"))
+            .child(header)
     }
 }
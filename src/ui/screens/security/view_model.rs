@@ -1,13 +1,1054 @@
-//! View model for the security screen — secure boot and attestation state.
+//! View model for the security screen — secure boot, secure lock,
+//! attestation state and the factory reset.
 
+use crate::logging::ActionId;
 use crate::ui::app::AppModels;
+use crate::ui::components::dialog;
+use crate::ui::components::dialog::{ConfirmContent, PinPromptContent};
+use crate::ui::models::device::{
+    BackupProgress, DeviceEvent, DeviceMethod, DeviceRepo, FirmwareType,
+};
+use directories::UserDirs;
 use gpui::*;
+use gpui_component::WindowExt;
+use gpui_component::input::InputState;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
-/// Security-related state — stub for secure boot, attestation, and reset operations.
-pub struct SecurityViewModel;
+/// How long a restore may hold the key before background traffic resumes.
+const RESTORE_MAINTENANCE: Duration = Duration::from_secs(60);
+/// Longest a reset may hold the key: the replug window plus the touch.
+const RESET_MAINTENANCE: Duration = Duration::from_secs(60);
+
+/// Security-related state. Secure boot is read and enabled through the
+/// Rescue applet, secure lock through FIDO vendor commands; the always-UV
+/// policy is read from GetInfo and can be toggled, enterprise attestation
+/// can be turned on, the RP IDs allowed to read the minimum PIN length can
+/// be set, the key's encrypted backup can be saved to a file and
+/// restored, and the key can be factory reset (authenticatorReset, which
+/// any FIDO2 key supports).
+pub struct SecurityViewModel {
+    pub(super) device: Entity<DeviceRepo>,
+    /// Secure boot switches: lock it too, and the risk acknowledgement.
+    pub(super) sb_lock: bool,
+    pub(super) sb_confirmed: bool,
+    pub(super) sb_busy: bool,
+    /// Result of the last secure boot change.
+    pub(super) sb_result: Option<Result<String, String>>,
+    pub(super) lock_busy: bool,
+    /// Result of the last secure lock enable, disable or unlock.
+    pub(super) lock_result: Option<Result<String, String>>,
+    pub(super) toggling: bool,
+    /// Result of the last always-UV toggle.
+    pub(super) result: Option<Result<String, String>>,
+    pub(super) ea_busy: bool,
+    /// Result of the last enableEnterpriseAttestation.
+    pub(super) ea_result: Option<Result<String, String>>,
+    /// minPinLengthRPIDs, one RP ID per line. The key cannot report the
+    /// current list, so this starts empty.
+    pub(super) rp_ids_input: Entity<InputState>,
+    pub(super) force_change_pin: bool,
+    pub(super) rp_ids_busy: bool,
+    /// Result of the last setMinPINLength.
+    pub(super) rp_ids_result: Option<Result<String, String>>,
+    pub(super) backup_busy: bool,
+    /// Latest progress of the running backup or restore.
+    pub(super) backup_progress: Option<BackupProgress>,
+    /// Result of the last backup or restore.
+    pub(super) backup_result: Option<Result<String, String>>,
+    pub(super) reset_busy: bool,
+    _task: Option<Task<()>>,
+    _backup_task: Option<Task<()>>,
+    _progress_timer: Option<Task<()>>,
+}
 
 impl SecurityViewModel {
-    pub fn new(_window: &mut Window, _cx: &mut Context<Self>, _models: &AppModels) -> Self {
-        Self
+    pub fn new(window: &mut Window, cx: &mut Context<Self>, models: &AppModels) -> Self {
+        let device = models.device.clone();
+        cx.subscribe(&device, |_, _, _: &DeviceEvent, cx| cx.notify())
+            .detach();
+        let rp_ids_input = cx.new(|cx| {
+            InputState::new(window, cx)
+                .multi_line(true)
+                .placeholder("example.com\nlogin.example.org")
+        });
+
+        Self {
+            device,
+            sb_lock: false,
+            sb_confirmed: false,
+            sb_busy: false,
+            sb_result: None,
+            lock_busy: false,
+            lock_result: None,
+            toggling: false,
+            result: None,
+            ea_busy: false,
+            ea_result: None,
+            rp_ids_input,
+            force_change_pin: false,
+            rp_ids_busy: false,
+            rp_ids_result: None,
+            backup_busy: false,
+            backup_progress: None,
+            backup_result: None,
+            reset_busy: false,
+            _task: None,
+            _backup_task: None,
+            _progress_timer: None,
+        }
+    }
+
+    /// How many RP IDs setMinPINLength accepts; `None` when the key does not
+    /// support setMinPINLength or reports no room for RP IDs.
+    pub(super) fn max_rp_ids(&self, cx: &App) -> Option<usize> {
+        let info = self.device.read(cx).fido_info.as_ref()?;
+        if info.options.get("setMinPINLength") != Some(&true) {
+            return None;
+        }
+        info.max_rpids_for_min_pin_length
+            .filter(|max| *max > 0)
+            .map(|max| max as usize)
+    }
+
+    /// RP IDs from the editor: trimmed, deduplicated, blank lines dropped.
+    fn parse_rp_ids(&self, cx: &App) -> Result<Vec<String>, String> {
+        let mut rp_ids: Vec<String> = Vec::new();
+        for line in self.rp_ids_input.read(cx).value().lines() {
+            let rp_id = line.trim().to_lowercase();
+            if rp_id.is_empty() || rp_ids.contains(&rp_id) {
+                continue;
+            }
+            if rp_id.contains(char::is_whitespace) || rp_id.contains("://") {
+                return Err(format!(
+                    "\"{}\" is not an RP ID. Use the bare domain, e.g. example.com.",
+                    rp_id
+                ));
+            }
+            rp_ids.push(rp_id);
+        }
+        Ok(rp_ids)
+    }
+
+    pub(super) fn open_rp_ids_dialog(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(max) = self.max_rp_ids(cx) else {
+            return;
+        };
+        let rp_ids = match self.parse_rp_ids(cx) {
+            Ok(ids) if ids.is_empty() && !self.force_change_pin => {
+                self.rp_ids_result = Some(Err("Enter at least one RP ID.".to_string()));
+                cx.notify();
+                return;
+            }
+            Ok(ids) if ids.len() > max => {
+                self.rp_ids_result = Some(Err(format!(
+                    "This key accepts at most {} RP IDs ({} entered).",
+                    max,
+                    ids.len()
+                )));
+                cx.notify();
+                return;
+            }
+            Ok(ids) => ids,
+            Err(e) => {
+                self.rp_ids_result = Some(Err(e));
+                cx.notify();
+                return;
+            }
+        };
+        let min_len = self
+            .device
+            .read(cx)
+            .fido_info
+            .as_ref()
+            .map(|info| info.min_pin_length.clamp(4, 63) as u8)
+            .unwrap_or(4);
+        let force_change_pin = self.force_change_pin;
+        let view_handle = cx.entity().downgrade();
+
+        dialog::open_pin_prompt(
+            "Set Minimum PIN Length RP IDs",
+            "Enter your device PIN to update the minimum PIN length policy",
+            Some(if force_change_pin {
+                "The list replaces the one on the key, and the PIN must be changed before the key can be used again."
+            } else {
+                "The list replaces the one on the key."
+            }),
+            "Apply",
+            window,
+            cx,
+            move |pin, dialog_handle, cx| {
+                let rp_ids = rp_ids.clone();
+                let _ = view_handle.update(cx, |this, cx| {
+                    this.set_min_pin_length_rp_ids(
+                        pin,
+                        min_len,
+                        rp_ids,
+                        force_change_pin,
+                        dialog_handle,
+                        cx,
+                    );
+                });
+            },
+        );
+    }
+
+    fn set_min_pin_length_rp_ids(
+        &mut self,
+        pin: String,
+        min_len: u8,
+        rp_ids: Vec<String>,
+        force_change_pin: bool,
+        dialog_handle: WeakEntity<PinPromptContent>,
+        cx: &mut Context<Self>,
+    ) {
+        if self.rp_ids_busy {
+            return;
+        }
+        self.rp_ids_busy = true;
+        self.rp_ids_result = None;
+        cx.notify();
+
+        let action = ActionId::begin("security.min_pin_rpids");
+        action.scope(|| log::info!("Setting minPinLengthRPIDs ({} RP IDs)...", rp_ids.len()));
+        let weak_self = cx.entity().downgrade();
+
+        self._task = Some(cx.spawn(async move |_, cx| {
+            let act = action.clone();
+            let result = cx
+                .background_executor()
+                .spawn(async move {
+                    act.scope(|| {
+                        DeviceRepo::set_min_pin_length_rp_ids_blocking(
+                            pin,
+                            min_len,
+                            rp_ids,
+                            force_change_pin,
+                        )
+                    })
+                })
+                .await;
+
+            let _ = weak_self.update(cx, |this, cx| {
+                this.rp_ids_busy = false;
+                action.scope(|| match &result {
+                    Ok(msg) => {
+                        log::info!("{}", msg);
+                        let _ = dialog_handle.update(cx, |d, cx| d.set_success(msg.clone(), cx));
+                        this.force_change_pin = false;
+                        this.device.update(cx, |repo, repo_cx| {
+                            repo.update_fido_info(repo_cx);
+                        });
+                    }
+                    Err(e) => {
+                        log::error!("Failed to set minPinLengthRPIDs: {}", e);
+                        let _ = dialog_handle
+                            .update(cx, |d, cx| d.set_error(format!("Error: {}", e), cx));
+                    }
+                });
+                this.rp_ids_result = Some(result);
+                cx.notify();
+            });
+        }));
+    }
+
+    /// `alwaysUv` from GetInfo: `None` when the key does not list the option,
+    /// i.e. does not implement toggleAlwaysUv.
+    pub(super) fn always_uv(&self, cx: &App) -> Option<bool> {
+        self.device
+            .read(cx)
+            .fido_info
+            .as_ref()
+            .and_then(|info| info.options.get("alwaysUv").copied())
+    }
+
+    pub(super) fn open_always_uv_dialog(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(current) = self.always_uv(cx) else {
+            return;
+        };
+        let view_handle = cx.entity().downgrade();
+
+        dialog::open_pin_prompt(
+            if current {
+                "Turn Off Always Require UV"
+            } else {
+                "Turn On Always Require UV"
+            },
+            "Enter your device PIN to change the user verification policy",
+            (!current).then_some(
+                "Every sign-in will ask for the PIN, including for U2F sites that cannot ask for one.",
+            ),
+            "Confirm",
+            window,
+            cx,
+            move |pin, dialog_handle, cx| {
+                let _ = view_handle.update(cx, |this, cx| {
+                    this.toggle_always_uv(pin, dialog_handle, cx);
+                });
+            },
+        );
+    }
+
+    fn toggle_always_uv(
+        &mut self,
+        pin: String,
+        dialog_handle: WeakEntity<PinPromptContent>,
+        cx: &mut Context<Self>,
+    ) {
+        if self.toggling {
+            return;
+        }
+        self.toggling = true;
+        self.result = None;
+        cx.notify();
+
+        let action = ActionId::begin("security.always_uv");
+        action.scope(|| log::info!("Toggling alwaysUv..."));
+        let weak_self = cx.entity().downgrade();
+
+        self._task = Some(cx.spawn(async move |_, cx| {
+            let act = action.clone();
+            let result = cx
+                .background_executor()
+                .spawn(async move { act.scope(|| DeviceRepo::toggle_always_uv_blocking(pin)) })
+                .await;
+
+            let _ = weak_self.update(cx, |this, cx| {
+                this.toggling = false;
+                action.scope(|| match result {
+                    Ok(enabled) => {
+                        let msg = if enabled {
+                            "Always require UV is now on."
+                        } else {
+                            "Always require UV is now off."
+                        };
+                        log::info!("{}", msg);
+                        let _ = dialog_handle.update(cx, |d, cx| {
+                            d.set_success(msg.to_string(), cx);
+                        });
+                        this.result = Some(Ok(msg.to_string()));
+                        this.device.update(cx, |repo, repo_cx| {
+                            repo.update_fido_info(repo_cx);
+                        });
+                    }
+                    Err(e) => {
+                        log::error!("Failed to toggle alwaysUv: {}", e);
+                        let _ = dialog_handle.update(cx, |d, cx| {
+                            d.set_error(format!("Error: {}", e), cx);
+                        });
+                        this.result = Some(Err(e));
+                    }
+                });
+                cx.notify();
+            });
+        }));
     }
+
+    /// `ep` from GetInfo: `None` when the key does not list the option, i.e.
+    /// has no enterprise attestation.
+    pub(super) fn enterprise_attestation(&self, cx: &App) -> Option<bool> {
+        self.device
+            .read(cx)
+            .fido_info
+            .as_ref()
+            .and_then(|info| info.options.get("ep").copied())
+    }
+
+    pub(super) fn open_enterprise_attestation_dialog(
+        &mut self,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if self.enterprise_attestation(cx) != Some(false) {
+            return;
+        }
+        let view_handle = cx.entity().downgrade();
+
+        dialog::open_pin_prompt(
+            "Enable Enterprise Attestation",
+            "Enter your device PIN to enable enterprise attestation",
+            Some("Only a reset turns enterprise attestation off again."),
+            "Enable",
+            window,
+            cx,
+            move |pin, dialog_handle, cx| {
+                let _ = view_handle.update(cx, |this, cx| {
+                    this.enable_enterprise_attestation(pin, dialog_handle, cx);
+                });
+            },
+        );
+    }
+
+    fn enable_enterprise_attestation(
+        &mut self,
+        pin: String,
+        dialog_handle: WeakEntity<PinPromptContent>,
+        cx: &mut Context<Self>,
+    ) {
+        if self.ea_busy {
+            return;
+        }
+        self.ea_busy = true;
+        self.ea_result = None;
+        cx.notify();
+
+        let action = ActionId::begin("security.enterprise_attestation");
+        action.scope(|| log::info!("Enabling enterprise attestation..."));
+        let weak_self = cx.entity().downgrade();
+
+        self._task = Some(cx.spawn(async move |_, cx| {
+            let act = action.clone();
+            let result = cx
+                .background_executor()
+                .spawn(async move {
+                    act.scope(|| DeviceRepo::enable_enterprise_attestation_blocking(pin))
+                })
+                .await;
+
+            let _ = weak_self.update(cx, |this, cx| {
+                this.ea_busy = false;
+                action.scope(|| match &result {
+                    Ok(msg) => {
+                        log::info!("{}", msg);
+                        let _ = dialog_handle.update(cx, |d, cx| d.set_success(msg.clone(), cx));
+                        this.device.update(cx, |repo, repo_cx| {
+                            repo.update_fido_info(repo_cx);
+                        });
+                    }
+                    Err(e) => {
+                        log::error!("Failed to enable enterprise attestation: {}", e);
+                        let _ = dialog_handle
+                            .update(cx, |d, cx| d.set_error(format!("Error: {}", e), cx));
+                    }
+                });
+                this.ea_result = Some(result);
+                cx.notify();
+            });
+        }));
+    }
+
+    /// `(enabled, locked)` secure boot state; `None` when it cannot be read
+    /// or changed, i.e. the Rescue applet is not reachable or the firmware is
+    /// not pico-fido (RS-Key locks OTP with a different command).
+    pub(super) fn secure_boot(&self, cx: &App) -> Option<(bool, bool)> {
+        let status = self.device.read(cx).status.as_ref()?;
+        (status.method == DeviceMethod::Rescue && status.firmware_type == FirmwareType::PicoFido)
+            .then_some((status.secure_boot, status.secure_boot_locked))
+    }
+
+    pub(super) fn open_secure_boot_dialog(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some((enabled, locked)) = self.secure_boot(cx) else {
+            return;
+        };
+        let lock = self.sb_lock;
+        if !self.sb_confirmed || locked || (enabled && !lock) {
+            return;
+        }
+        let view_handle = cx.entity().downgrade();
+
+        let (title, message, ok_label) = if lock {
+            (
+                "Permanently Lock Device",
+                "Enable and lock secure boot? This burns the boot key into the chip's OTP \
+                 and cannot be undone: the key will only ever boot firmware signed with \
+                 that key, and its debug interface is locked out. Flashing unsigned or \
+                 differently signed firmware afterwards bricks the key."
+                    .to_string(),
+                "Lock Forever",
+            )
+        } else {
+            (
+                "Enable Secure Boot",
+                "Enable secure boot? The key will refuse to boot firmware that is not \
+                 signed with its boot key. Only flash signed firmware from now on."
+                    .to_string(),
+                "Enable",
+            )
+        };
+
+        dialog::open_confirm(
+            title,
+            message,
+            ok_label,
+            gpui_component::button::ButtonVariant::Danger,
+            window,
+            cx,
+            move |dialog_handle, _, cx| {
+                let _ = view_handle.update(cx, |this, cx| {
+                    this.enable_secure_boot(lock, dialog_handle, cx);
+                });
+            },
+        );
+    }
+
+    fn enable_secure_boot(
+        &mut self,
+        lock: bool,
+        dialog_handle: WeakEntity<ConfirmContent>,
+        cx: &mut Context<Self>,
+    ) {
+        if self.sb_busy {
+            return;
+        }
+        self.sb_busy = true;
+        self.sb_result = None;
+        cx.notify();
+
+        let action = ActionId::begin("security.secure_boot");
+        action.scope(|| log::info!("Enabling secure boot (lock: {})...", lock));
+        let weak_self = cx.entity().downgrade();
+
+        self._task = Some(cx.spawn(async move |_, cx| {
+            let act = action.clone();
+            let result = cx
+                .background_executor()
+                .spawn(async move { act.scope(|| DeviceRepo::enable_secure_boot_blocking(lock)) })
+                .await;
+
+            let _ = weak_self.update(cx, |this, cx| {
+                this.sb_busy = false;
+                action.scope(|| match &result {
+                    Ok(msg) => {
+                        log::info!("{}", msg);
+                        let _ = dialog_handle.update(cx, |d, cx| d.set_success(msg.clone(), cx));
+                        this.sb_lock = false;
+                        this.sb_confirmed = false;
+                        this.device
+                            .update(cx, |repo, repo_cx| repo.refresh(repo_cx));
+                    }
+                    Err(e) => {
+                        log::error!("Failed to enable secure boot: {}", e);
+                        let _ = dialog_handle
+                            .update(cx, |d, cx| d.set_error(format!("Error: {}", e), cx));
+                    }
+                });
+                this.sb_result = Some(result);
+                cx.notify();
+            });
+        }));
+    }
+
+    /// Secure lock state; `None` when the key has no secure lock (no
+    /// AuthEncryption vendor config command or certification in GetInfo).
+    pub(super) fn secure_lock(&self, cx: &App) -> Option<bool> {
+        let repo = self.device.read(cx);
+        let info = repo.fido_info.as_ref()?;
+        let supported = info
+            .vendor_config_commands
+            .iter()
+            .any(|c| c == "AuthEncryptionEnable")
+            || info.certifications.contains_key("Auth Encryption");
+        supported.then(|| repo.status.as_ref().is_some_and(|s| s.secure_lock))
+    }
+
+    fn serial(&self, cx: &App) -> String {
+        self.device
+            .read(cx)
+            .status
+            .as_ref()
+            .map(|s| s.info.serial.clone())
+            .unwrap_or_default()
+    }
+
+    pub(super) fn open_secure_lock_dialog(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(enabled) = self.secure_lock(cx) else {
+            return;
+        };
+        let serial = self.serial(cx);
+        let view_handle = cx.entity().downgrade();
+
+        dialog::open_pin_prompt(
+            if enabled {
+                "Turn Off Secure Lock"
+            } else {
+                "Turn On Secure Lock"
+            },
+            "Enter your device PIN to change secure lock",
+            (!enabled).then_some(
+                "The host key is stored only on this computer. After every power cycle the \
+                 key must be unlocked from here; if the host key is lost, only a reset makes \
+                 the key usable again. Export Application Data on the About screen to keep a copy.",
+            ),
+            "Confirm",
+            window,
+            cx,
+            move |pin, dialog_handle, cx| {
+                let serial = serial.clone();
+                let _ = view_handle.update(cx, |this, cx| {
+                    this.set_secure_lock(!enabled, pin, serial, dialog_handle, cx);
+                });
+            },
+        );
+    }
+
+    fn set_secure_lock(
+        &mut self,
+        enable: bool,
+        pin: String,
+        serial: String,
+        dialog_handle: WeakEntity<PinPromptContent>,
+        cx: &mut Context<Self>,
+    ) {
+        if self.lock_busy {
+            return;
+        }
+        self.lock_busy = true;
+        self.lock_result = None;
+        cx.notify();
+
+        let action = ActionId::begin("security.secure_lock");
+        action.scope(|| {
+            log::info!(
+                "{} secure lock...",
+                if enable { "Enabling" } else { "Disabling" }
+            )
+        });
+        let weak_self = cx.entity().downgrade();
+
+        self._task = Some(cx.spawn(async move |_, cx| {
+            let act = action.clone();
+            let result = cx
+                .background_executor()
+                .spawn(async move {
+                    act.scope(|| {
+                        if enable {
+                            DeviceRepo::enable_secure_lock_blocking(pin, serial)
+                        } else {
+                            DeviceRepo::disable_secure_lock_blocking(pin)
+                        }
+                    })
+                })
+                .await;
+
+            let _ = weak_self.update(cx, |this, cx| {
+                this.lock_busy = false;
+                action.scope(|| match &result {
+                    Ok(msg) => {
+                        log::info!("{}", msg);
+                        let _ = dialog_handle.update(cx, |d, cx| d.set_success(msg.clone(), cx));
+                        this.device
+                            .update(cx, |repo, repo_cx| repo.refresh(repo_cx));
+                    }
+                    Err(e) => {
+                        log::error!("Failed to change secure lock: {}", e);
+                        let _ = dialog_handle
+                            .update(cx, |d, cx| d.set_error(format!("Error: {}", e), cx));
+                    }
+                });
+                this.lock_result = Some(result);
+                cx.notify();
+            });
+        }));
+    }
+
+    /// Unlock the key for this power cycle with the host key stored here.
+    pub(super) fn unlock(&mut self, cx: &mut Context<Self>) {
+        if self.lock_busy || self.secure_lock(cx) != Some(true) {
+            return;
+        }
+        self.lock_busy = true;
+        self.lock_result = None;
+        cx.notify();
+
+        let serial = self.serial(cx);
+        let action = ActionId::begin("security.unlock");
+        action.scope(|| log::info!("Unlocking secure-locked key..."));
+        let weak_self = cx.entity().downgrade();
+
+        self._task = Some(cx.spawn(async move |_, cx| {
+            let act = action.clone();
+            let result = cx
+                .background_executor()
+                .spawn(async move { act.scope(|| DeviceRepo::unlock_device_blocking(serial)) })
+                .await;
+
+            let _ = weak_self.update(cx, |this, cx| {
+                this.lock_busy = false;
+                action.scope(|| match &result {
+                    Ok(msg) => log::info!("{}", msg),
+                    Err(e) => log::error!("Failed to unlock the key: {}", e),
+                });
+                this.lock_result = Some(result);
+                cx.notify();
+            });
+        }));
+    }
+
+    /// Ask where to save the backup, then read it from the key.
+    pub(super) fn start_backup(&mut self, cx: &mut Context<Self>) {
+        if self.backup_busy {
+            return;
+        }
+        let serial = self
+            .device
+            .read(cx)
+            .status
+            .as_ref()
+            .map(|s| s.info.serial.clone())
+            .unwrap_or_default();
+        let file_name = if serial.is_empty() {
+            "picoforge-backup.pfbackup".to_string()
+        } else {
+            format!("picoforge-backup-{}.pfbackup", serial)
+        };
+        let receiver = cx.prompt_for_new_path(&default_dir(), Some(&file_name));
+        let weak_self = cx.entity().downgrade();
+
+        self._task = Some(cx.spawn(async move |_, cx| {
+            let path = match receiver.await {
+                Ok(Ok(Some(path))) => path,
+                Ok(Err(e)) => {
+                    let _ = weak_self.update(cx, |this, cx| {
+                        this.backup_result = Some(Err(format!("Save dialog error: {}", e)));
+                        cx.notify();
+                    });
+                    return;
+                }
+                _ => return,
+            };
+            let _ = weak_self.update(cx, |this, cx| this.run_backup(path, None, cx));
+        }));
+    }
+
+    /// Ask for a backup file, then confirm before restoring it.
+    pub(super) fn start_restore(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if self.backup_busy {
+            return;
+        }
+        let receiver = cx.prompt_for_paths(gpui::PathPromptOptions {
+            files: true,
+            directories: false,
+            multiple: false,
+            prompt: Some("Select Backup File".into()),
+        });
+        let weak_self = cx.entity().downgrade();
+        let window_handle = window.window_handle();
+
+        self._task = Some(cx.spawn(async move |_, cx| {
+            let Ok(Ok(Some(paths))) = receiver.await else {
+                return;
+            };
+            let Some(path) = paths.into_iter().next() else {
+                return;
+            };
+            let _ = cx.update_window(window_handle, |_, window, cx| {
+                let _ = weak_self.update(cx, |this, cx| {
+                    this.open_restore_dialog(path, window, cx);
+                });
+            });
+        }));
+    }
+
+    fn open_restore_dialog(&mut self, path: PathBuf, window: &mut Window, cx: &mut Context<Self>) {
+        let view_handle = cx.entity().downgrade();
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.display().to_string());
+
+        dialog::open_confirm(
+            "Restore Backup",
+            format!(
+                "Restore \"{}\" to the connected key? The key's current secrets are \
+                 replaced by the ones in the backup. Keep the key plugged in until the \
+                 restore finishes.",
+                name
+            ),
+            "Restore",
+            gpui_component::button::ButtonVariant::Danger,
+            window,
+            cx,
+            move |dialog_handle, _, cx| {
+                let path = path.clone();
+                let _ = view_handle.update(cx, |this, cx| {
+                    this.run_backup(path, Some(dialog_handle), cx);
+                });
+            },
+        );
+    }
+
+    /// Back up to `path`, or restore from it when the restore confirmation
+    /// is passed, on a background thread, polling the shared progress until
+    /// it finishes.
+    fn run_backup(
+        &mut self,
+        path: PathBuf,
+        restore_dialog: Option<WeakEntity<ConfirmContent>>,
+        cx: &mut Context<Self>,
+    ) {
+        if self.backup_busy {
+            return;
+        }
+        let restore = restore_dialog.is_some();
+        self.backup_busy = true;
+        self.backup_progress = None;
+        self.backup_result = None;
+        cx.notify();
+
+        let action = ActionId::begin(if restore {
+            "security.restore"
+        } else {
+            "security.backup"
+        });
+        action.scope(|| {
+            if restore {
+                log::info!("Restoring encrypted backup from {}...", path.display());
+            } else {
+                log::info!("Saving encrypted backup to {}...", path.display());
+            }
+        });
+        // Keep refreshes off the key while it is being restored.
+        let latch = restore
+            .then(|| DeviceRepo::begin_maintenance("Restoring a backup", RESTORE_MAINTENANCE));
+        let shared: Arc<Mutex<Option<BackupProgress>>> = Arc::new(Mutex::new(None));
+        self.start_progress_timer(shared.clone(), cx);
+        let weak_self = cx.entity().downgrade();
+
+        self._backup_task = Some(cx.spawn(async move |_, cx| {
+            let act = action.clone();
+            let display = path.display().to_string();
+            let result = cx
+                .background_executor()
+                .spawn(async move {
+                    act.scope(|| {
+                        let report = move |p: BackupProgress| {
+                            if let Ok(mut slot) = shared.lock() {
+                                *slot = Some(p);
+                            }
+                        };
+                        // The latch is released when this task ends.
+                        match &latch {
+                            Some(latch) => DeviceRepo::restore_device_blocking(latch, path, report),
+                            None => DeviceRepo::backup_device_blocking(path, report),
+                        }
+                    })
+                })
+                .await;
+
+            let _ = weak_self.update(cx, |this, cx| {
+                this.backup_busy = false;
+                this.backup_progress = None;
+                this._progress_timer = None;
+                action.scope(|| match result {
+                    Ok(len) => {
+                        let msg = if restore {
+                            format!("Backup restored from {} ({} bytes).", display, len)
+                        } else {
+                            format!("Backup saved to {} ({} bytes).", display, len)
+                        };
+                        log::info!("{}", msg);
+                        if let Some(dialog) = &restore_dialog {
+                            let _ = dialog.update(cx, |d, cx| d.set_success(msg.clone(), cx));
+                        }
+                        this.backup_result = Some(Ok(msg));
+                        if restore {
+                            this.device.update(cx, |repo, repo_cx| {
+                                repo.update_fido_info(repo_cx);
+                            });
+                        }
+                    }
+                    Err(e) => {
+                        log::error!(
+                            "{} failed: {}",
+                            if restore { "Restore" } else { "Backup" },
+                            e
+                        );
+                        if let Some(dialog) = &restore_dialog {
+                            let _ =
+                                dialog.update(cx, |d, cx| d.set_error(format!("Error: {}", e), cx));
+                        }
+                        this.backup_result = Some(Err(e));
+                    }
+                });
+                cx.notify();
+            });
+        }));
+    }
+
+    /// Copy the background job's progress into the view every 100 ms while
+    /// a backup or restore runs.
+    fn start_progress_timer(
+        &mut self,
+        shared: Arc<Mutex<Option<BackupProgress>>>,
+        cx: &mut Context<Self>,
+    ) {
+        let weak_self = cx.entity().downgrade();
+        self._progress_timer = Some(cx.spawn(async move |_, cx| {
+            loop {
+                cx.background_executor()
+                    .timer(Duration::from_millis(100))
+                    .await;
+                let progress = shared.lock().ok().and_then(|slot| *slot);
+                let keep_going = weak_self
+                    .update(cx, |this, cx| {
+                        if this.backup_progress != progress {
+                            this.backup_progress = progress;
+                            cx.notify();
+                        }
+                        this.backup_busy
+                    })
+                    .unwrap_or(false);
+                if !keep_going {
+                    break;
+                }
+            }
+        }));
+    }
+
+    pub(super) fn open_reset_dialog(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let view_handle = cx.entity().downgrade();
+
+        dialog::open_confirm(
+            "Factory Reset Device",
+            "Are you sure you want to completely erase your device? This will permanently \
+             delete ALL passkeys, credentials, and your PIN. This action cannot be undone."
+                .to_string(),
+            "Reset Device",
+            gpui_component::button::ButtonVariant::Danger,
+            window,
+            cx,
+            move |_dialog_handle, window, cx| {
+                window.close_dialog(cx);
+                let _ = view_handle.update(cx, |this, cx| {
+                    this.open_reset_final_confirm(window, cx);
+                });
+            },
+        );
+    }
+
+    /// Second confirmation before the reset, naming what will be lost.
+    fn open_reset_final_confirm(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let serial = self.serial(cx);
+        let erased = match &self.device.read(cx).credentials {
+            Some(creds) => format!("{} stored passkey(s)", creds.len()),
+            None => "every stored passkey".into(),
+        };
+        let message = format!(
+            "Last chance: resetting device {} erases {} and the PIN. Accounts that use \
+             these passkeys will need another sign-in method.",
+            serial, erased
+        );
+        let view_handle = cx.entity().downgrade();
+
+        dialog::open_confirm(
+            "Erase Everything?",
+            message,
+            "Erase Device",
+            gpui_component::button::ButtonVariant::Danger,
+            window,
+            cx,
+            move |_dialog_handle, window, cx| {
+                window.close_dialog(cx);
+                let _ = view_handle.update(cx, |this, cx| {
+                    this.execute_reset(window, cx);
+                });
+            },
+        );
+    }
+
+    fn execute_reset(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if self.reset_busy {
+            return;
+        }
+        self.reset_busy = true;
+        cx.notify();
+
+        let status_handle = dialog::open_status_dialog("Resetting Device...", window, cx);
+        let weak_self = cx.entity().downgrade();
+        let action = ActionId::begin("device.reset");
+
+        let _ = status_handle.update(cx, |d, cx| {
+            d.set_loading(
+                "Unplug your security key, then plug it back in within 10 seconds.",
+                cx,
+            );
+        });
+
+        // Keep the hot-plug watcher and queued reads off the key while it is
+        // replugged and reset; dropping the task (cancel, early return)
+        // releases it too.
+        let latch = DeviceRepo::begin_maintenance("Resetting the key", RESET_MAINTENANCE);
+        self._task = Some(cx.spawn(async move |_, cx| {
+            let reconnected = cx
+                .background_executor()
+                .spawn(async move {
+                    let start = std::time::Instant::now();
+                    while start.elapsed().as_secs() < 15 {
+                        std::thread::sleep(std::time::Duration::from_millis(200));
+                        if !DeviceRepo::check_hid_available_blocking() {
+                            break;
+                        }
+                    }
+
+                    while start.elapsed().as_secs() < 15 {
+                        std::thread::sleep(std::time::Duration::from_millis(500));
+                        if DeviceRepo::check_hid_available_blocking() {
+                            return true;
+                        }
+                    }
+                    false
+                })
+                .await;
+
+            if !reconnected {
+                let _ = weak_self.update(cx, |this, cx| {
+                    this.reset_busy = false;
+                    let _ = status_handle.update(cx, |d, cx| {
+                        d.set_error(
+                            "Timeout waiting for device reconnection. Reset canceled.".to_string(),
+                            cx,
+                        );
+                    });
+                    cx.notify();
+                });
+                return;
+            }
+
+            let _ = status_handle.update(cx, |d, cx| {
+                d.set_loading("Touch your security key now to confirm the reset...", cx);
+            });
+
+            let act = action.clone();
+            let result = cx
+                .background_executor()
+                .spawn(async move { act.scope(|| DeviceRepo::reset_device_blocking(&latch)) })
+                .await;
+
+            let _ = weak_self.update(cx, |this, cx| {
+                this.reset_busy = false;
+                action.scope(|| match result {
+                    Ok(msg) => {
+                        log::info!("Device Reset: {}", msg);
+                        let _ = status_handle.update(cx, |d, cx| {
+                            d.set_success(msg, cx);
+                        });
+                        this.device.update(cx, |repo, repo_cx| {
+                            repo.credentials = None;
+                            repo_cx.emit(DeviceEvent::Reset);
+                            repo.update_fido_info(repo_cx);
+                        });
+                    }
+                    Err(e) => {
+                        log::error!("Error resetting device: {}", e);
+                        let _ = status_handle.update(cx, |d, cx| {
+                            d.set_error(format!("Reset failed: {}", e), cx);
+                        });
+                    }
+                });
+                cx.notify();
+            });
+        }));
+    }
+}
+
+/// Where file dialogs start: Documents, else Downloads, else home.
+fn default_dir() -> PathBuf {
+    UserDirs::new()
+        .and_then(|d| {
+            d.document_dir()
+                .or_else(|| d.download_dir())
+                .map(|p| p.to_path_buf())
+        })
+        .unwrap_or_else(|| PathBuf::from(std::env::var("HOME").unwrap_or_else(|_| ".".into())))
 }
@@ -0,0 +1,6 @@
+//! Notes vault screen — notes on this computer, sealed with a key derived by
+//! the security key's hmac-secret extension.
+
+pub mod view;
+pub mod view_model;
+pub use view_model::VaultViewModel;
@@ -0,0 +1,297 @@
+use crate::ui::components::{button::PFIconButton, card::Card, page_view::PageView};
+use crate::ui::screens::vault::view_model::VaultViewModel;
+use gpui::prelude::FluentBuilder;
+use gpui::*;
+use gpui_component::button::{Button, ButtonVariants};
+use gpui_component::{
+    ActiveTheme, Disableable, Icon, Sizable, StyledExt, badge::Badge, h_flex, input::Input, v_flex,
+};
+
+impl Render for VaultViewModel {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let content = v_flex().gap_6().w_full().child(if self.vault.is_none() {
+            self.render_create_state(cx).into_any_element()
+        } else if self.unlocked() {
+            self.render_unlocked_state(cx).into_any_element()
+        } else {
+            self.render_locked_state(cx).into_any_element()
+        });
+
+        PageView::build(
+            "Notes Vault",
+            "Short notes stored on this computer, encrypted with a key only your security key can produce.",
+            content,
+            cx.theme(),
+        )
+    }
+}
+
+impl VaultViewModel {
+    fn render_prompt(
+        &self,
+        title: &'static str,
+        body: String,
+        button: impl IntoElement,
+        cx: &Context<Self>,
+    ) -> impl IntoElement {
+        let theme = cx.theme();
+        v_flex()
+            .items_center()
+            .justify_center()
+            .gap_3()
+            .py_3()
+            .child(
+                div().rounded_full().bg(theme.muted).p_4().child(
+                    Icon::default()
+                        .path("icons/scroll-text.svg")
+                        .size_12()
+                        .text_color(theme.muted_foreground),
+                ),
+            )
+            .child(div().text_lg().font_semibold().child(title))
+            .child(
+                div()
+                    .max_w(px(520.))
+                    .text_center()
+                    .text_color(theme.muted_foreground)
+                    .text_sm()
+                    .child(body),
+            )
+            .when_some(self.error.clone(), |this, error| {
+                this.child(div().text_sm().text_color(theme.danger).child(error))
+            })
+            .child(button)
+    }
+
+    fn render_create_state(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let listener = cx.listener(|this, _, window, cx| {
+            this.open_create_dialog(window, cx);
+        });
+
+        let body = "PicoForge creates a passkey with the hmac-secret extension on your key and \
+                    derives the vault key from it. Notes never leave this computer, and only \
+                    this key can open them. Resetting the key or deleting the passkey loses \
+                    the notes.";
+
+        Card::new()
+            .title("Notes Vault")
+            .icon(Icon::default().path("icons/scroll-text.svg"))
+            .description("No vault yet")
+            .child(
+                self.render_prompt(
+                    "Create a Vault",
+                    body.to_string(),
+                    PFIconButton::new(Icon::default().path("icons/plus.svg"), "Create Vault")
+                        .on_click(listener)
+                        .with_colors(rgb(0xe4e4e7), rgb(0xd0d0d3), rgb(0xe4e4e7))
+                        .with_text_color(rgb(0x18181b)),
+                    cx,
+                ),
+            )
+    }
+
+    fn render_locked_state(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let listener = cx.listener(|this, _, window, cx| {
+            this.open_unlock_dialog(window, cx);
+        });
+        let serial = self
+            .vault
+            .as_ref()
+            .map(|v| v.serial.clone())
+            .unwrap_or_default();
+        let body = match self.connected_serial(cx) {
+            Some(connected) if connected != serial => format!(
+                "This vault was created on the key with serial {}, which is not the one \
+                 connected. Connect that key to unlock it.",
+                serial
+            ),
+            _ => "Enter your PIN and touch the key to decrypt your notes.".to_string(),
+        };
+
+        Card::new()
+            .title("Notes Vault")
+            .icon(Icon::default().path("icons/scroll-text.svg"))
+            .description("Locked")
+            .child(
+                self.render_prompt(
+                    "Vault Locked",
+                    body,
+                    PFIconButton::new(Icon::default().path("icons/lock-open.svg"), "Unlock")
+                        .on_click(listener)
+                        .with_colors(rgb(0xe4e4e7), rgb(0xd0d0d3), rgb(0xe4e4e7))
+                        .with_text_color(rgb(0x18181b)),
+                    cx,
+                ),
+            )
+    }
+
+    fn render_unlocked_state(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+        let count = self.notes.len();
+
+        let rows: Vec<AnyElement> = self
+            .notes
+            .iter()
+            .enumerate()
+            .map(|(ix, note)| {
+                let preview: String = note
+                    .body
+                    .lines()
+                    .next()
+                    .unwrap_or("")
+                    .chars()
+                    .take(80)
+                    .collect();
+                h_flex()
+                    .justify_between()
+                    .items_center()
+                    .gap_2()
+                    .p_3()
+                    .border_1()
+                    .border_color(if self.editing == Some(ix) {
+                        theme.primary
+                    } else {
+                        theme.border
+                    })
+                    .rounded_lg()
+                    .child(
+                        v_flex()
+                            .min_w(px(0.))
+                            .child(div().font_medium().child(if note.title.is_empty() {
+                                "Untitled note".to_string()
+                            } else {
+                                note.title.clone()
+                            }))
+                            .child(
+                                div()
+                                    .text_xs()
+                                    .text_color(theme.muted_foreground)
+                                    .child(preview),
+                            ),
+                    )
+                    .child(
+                        h_flex()
+                            .gap_1()
+                            .child(
+                                Button::new(("vault-edit", ix))
+                                    .ghost()
+                                    .small()
+                                    .icon(Icon::default().path("icons/pencil.svg"))
+                                    .on_click(cx.listener(move |this, _, window, cx| {
+                                        this.edit_note(ix, window, cx);
+                                    })),
+                            )
+                            .child(
+                                Button::new(("vault-delete", ix))
+                                    .ghost()
+                                    .small()
+                                    .icon(Icon::default().path("icons/trash-2.svg"))
+                                    .on_click(cx.listener(move |this, _, window, cx| {
+                                        this.open_delete_dialog(ix, window, cx);
+                                    })),
+                            ),
+                    )
+                    .into_any_element()
+            })
+            .collect();
+
+        Card::new()
+            .title("Notes Vault")
+            .icon(Icon::default().path("icons/scroll-text.svg"))
+            .description("Decrypted in memory until you lock the vault")
+            .child(
+                v_flex()
+                    .gap_4()
+                    .child(
+                        h_flex()
+                            .justify_between()
+                            .items_center()
+                            .child(
+                                h_flex()
+                                    .gap_4()
+                                    .items_center()
+                                    .child(
+                                        Badge::new()
+                                            .child(
+                                                h_flex()
+                                                    .gap_1()
+                                                    .items_center()
+                                                    .child(
+                                                        Icon::default()
+                                                            .path("icons/lock-open.svg")
+                                                            .size_3p5(),
+                                                    )
+                                                    .child("Unlocked"),
+                                            )
+                                            .color(gpui::green()),
+                                    )
+                                    .child(div().w_px().h_4().bg(theme.border))
+                                    .child(
+                                        div()
+                                            .text_sm()
+                                            .text_color(theme.muted_foreground)
+                                            .child(format!("{} notes", count)),
+                                    ),
+                            )
+                            .child(
+                                h_flex()
+                                    .gap_2()
+                                    .child(
+                                        Button::new("vault-forget")
+                                            .ghost()
+                                            .label("Delete Vault")
+                                            .on_click(cx.listener(|this, _, window, cx| {
+                                                this.open_forget_dialog(window, cx)
+                                            })),
+                                    )
+                                    .child(
+                                        Button::new("vault-lock").ghost().label("Lock").on_click(
+                                            cx.listener(|this, _, window, cx| {
+                                                this.lock(window, cx)
+                                            }),
+                                        ),
+                                    ),
+                            ),
+                    )
+                    .when(rows.is_empty(), |this| {
+                        this.child(
+                            div()
+                                .text_sm()
+                                .text_color(theme.muted_foreground)
+                                .child("No notes yet."),
+                        )
+                    })
+                    .children(rows)
+                    .when_some(self.error.clone(), |this, error| {
+                        this.child(div().text_sm().text_color(theme.danger).child(error))
+                    })
+                    .child(div().h_px().bg(theme.border))
+                    .child(div().font_medium().child(if self.editing.is_some() {
+                        "Edit Note"
+                    } else {
+                        "New Note"
+                    }))
+                    .child(Input::new(&self.title_input))
+                    .child(Input::new(&self.body_input).h(px(140.)))
+                    .child(
+                        h_flex()
+                            .gap_2()
+                            .justify_end()
+                            .when(self.editing.is_some(), |this| {
+                                this.child(Button::new("vault-new").label("Cancel").on_click(
+                                    cx.listener(|this, _, window, cx| this.new_note(window, cx)),
+                                ))
+                            })
+                            .child(
+                                Button::new("vault-save")
+                                    .primary()
+                                    .label("Save Note")
+                                    .disabled(self.loading)
+                                    .on_click(cx.listener(|this, _, window, cx| {
+                                        this.save_note(window, cx);
+                                    })),
+                            ),
+                    ),
+            )
+    }
+}
@@ -0,0 +1,414 @@
+//! View model for the notes vault — creating the vault credential, unlocking
+//! with hmac-secret, and editing notes.
+
+use crate::logging::ActionId;
+use crate::ui::app::AppModels;
+use crate::ui::components::dialog;
+use crate::ui::components::dialog::{ConfirmContent, PinPromptContent};
+use crate::ui::models::device::DeviceRepo;
+use crate::vault::{self, KEY_LEN, Note, VAULT_RP_ID, VAULT_USER_NAME, VaultFile};
+use gpui::*;
+use gpui_component::input::InputState;
+
+/// The vault file, and its notes while unlocked.
+pub struct VaultViewModel {
+    device: Entity<DeviceRepo>,
+    /// `None` until a vault is created.
+    pub(super) vault: Option<VaultFile>,
+    /// Derived key; only set while unlocked.
+    key: Option<[u8; KEY_LEN]>,
+    pub(super) notes: Vec<Note>,
+    /// Note being edited; `None` while writing a new one.
+    pub(super) editing: Option<usize>,
+    pub(super) title_input: Entity<InputState>,
+    pub(super) body_input: Entity<InputState>,
+    pub(super) loading: bool,
+    pub(super) error: Option<String>,
+    _task: Option<Task<()>>,
+}
+
+impl VaultViewModel {
+    pub fn new(window: &mut Window, cx: &mut Context<Self>, models: &AppModels) -> Self {
+        let title_input = cx.new(|cx| InputState::new(window, cx).placeholder("Title"));
+        let body_input = cx.new(|cx| {
+            InputState::new(window, cx)
+                .multi_line(true)
+                .placeholder("Note")
+        });
+        let (vault, error) = match vault::load() {
+            Ok(vault) => (vault, None),
+            Err(e) => {
+                log::error!("Could not load the notes vault: {}", e);
+                (None, Some(e))
+            }
+        };
+
+        Self {
+            device: models.device.clone(),
+            vault,
+            key: None,
+            notes: Vec::new(),
+            editing: None,
+            title_input,
+            body_input,
+            loading: false,
+            error,
+            _task: None,
+        }
+    }
+
+    pub(super) fn unlocked(&self) -> bool {
+        self.key.is_some()
+    }
+
+    /// Serial of the connected key, when one is connected.
+    pub(super) fn connected_serial(&self, cx: &App) -> Option<String> {
+        self.device
+            .read(cx)
+            .status
+            .as_ref()
+            .map(|s| s.info.serial.clone())
+    }
+
+    pub(super) fn open_create_dialog(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let view_handle = cx.entity().downgrade();
+
+        dialog::open_pin_prompt(
+            "Create Notes Vault",
+            "Enter your device PIN. The key asks for two touches: one to create the vault \
+             credential and one to derive the vault key.",
+            None,
+            "Create",
+            window,
+            cx,
+            move |pin, dialog_handle, cx| {
+                let _ = view_handle.update(cx, |this, cx| {
+                    this.create(pin, dialog_handle, cx);
+                });
+            },
+        );
+    }
+
+    fn create(
+        &mut self,
+        pin: String,
+        dialog_handle: WeakEntity<PinPromptContent>,
+        cx: &mut Context<Self>,
+    ) {
+        if self.loading {
+            return;
+        }
+        let Some(serial) = self.connected_serial(cx) else {
+            return;
+        };
+        self.loading = true;
+        self.error = None;
+        cx.notify();
+
+        let action = ActionId::begin("vault.create");
+        action.scope(|| log::info!("Creating the notes vault..."));
+        let weak_self = cx.entity().downgrade();
+
+        self._task = Some(cx.spawn(async move |_, cx| {
+            let act = action.clone();
+            let result = cx
+                .background_executor()
+                .spawn(async move {
+                    act.scope(|| {
+                        let salt = vault::new_salt()?;
+                        let credential_id = DeviceRepo::create_hmac_secret_credential_blocking(
+                            VAULT_RP_ID.to_string(),
+                            VAULT_USER_NAME.to_string(),
+                            pin.clone(),
+                        )?;
+                        let key = DeviceRepo::derive_hmac_secret_blocking(
+                            VAULT_RP_ID.to_string(),
+                            credential_id.clone(),
+                            salt,
+                            pin,
+                        )?;
+                        let file = VaultFile::create(serial, credential_id, &salt, &key)?;
+                        vault::save(&file)?;
+                        Ok::<_, String>((file, key))
+                    })
+                })
+                .await;
+
+            let _ = weak_self.update(cx, |this, cx| {
+                this.loading = false;
+                action.scope(|| match result {
+                    Ok((file, key)) => {
+                        log::info!("Notes vault created");
+                        this.vault = Some(file);
+                        this.key = Some(key);
+                        this.notes.clear();
+                        let _ = dialog_handle.update(cx, |d, cx| {
+                            d.set_success("Notes vault created and unlocked.".to_string(), cx);
+                        });
+                    }
+                    Err(e) => {
+                        log::error!("Could not create the notes vault: {}", e);
+                        let _ = dialog_handle.update(cx, |d, cx| {
+                            d.set_error(format!("Could not create the vault: {}", e), cx);
+                        });
+                    }
+                });
+                cx.notify();
+            });
+        }));
+    }
+
+    pub(super) fn open_unlock_dialog(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let view_handle = cx.entity().downgrade();
+
+        dialog::open_pin_prompt(
+            "Unlock Notes Vault",
+            "Enter your device PIN, then touch the key to derive the vault key.",
+            None,
+            "Unlock",
+            window,
+            cx,
+            move |pin, dialog_handle, cx| {
+                let _ = view_handle.update(cx, |this, cx| {
+                    this.unlock(pin, dialog_handle, cx);
+                });
+            },
+        );
+    }
+
+    fn unlock(
+        &mut self,
+        pin: String,
+        dialog_handle: WeakEntity<PinPromptContent>,
+        cx: &mut Context<Self>,
+    ) {
+        if self.loading {
+            return;
+        }
+        let Some(file) = self.vault.clone() else {
+            return;
+        };
+        self.loading = true;
+        self.error = None;
+        cx.notify();
+
+        let weak_self = cx.entity().downgrade();
+        self._task = Some(cx.spawn(async move |_, cx| {
+            let result = cx
+                .background_executor()
+                .spawn(async move {
+                    let key = DeviceRepo::derive_hmac_secret_blocking(
+                        VAULT_RP_ID.to_string(),
+                        file.credential_id.clone(),
+                        file.salt()?,
+                        pin,
+                    )?;
+                    let notes = file.open(&key)?;
+                    Ok::<_, String>((key, notes))
+                })
+                .await;
+
+            let _ = weak_self.update(cx, |this, cx| {
+                this.loading = false;
+                match result {
+                    Ok((key, notes)) => {
+                        log::info!("Notes vault unlocked ({} notes)", notes.len());
+                        this.key = Some(key);
+                        this.notes = notes;
+                        let _ = dialog_handle.update(cx, |d, cx| {
+                            d.set_success("Notes vault unlocked.".to_string(), cx);
+                        });
+                    }
+                    Err(e) => {
+                        log::error!("Could not unlock the notes vault: {}", e);
+                        let _ = dialog_handle.update(cx, |d, cx| {
+                            d.set_error(format!("Failed to unlock: {}", e), cx);
+                        });
+                    }
+                }
+                cx.notify();
+            });
+        }));
+    }
+
+    /// Forget the key and the decrypted notes.
+    pub(super) fn lock(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.key = None;
+        self.notes.clear();
+        self.error = None;
+        self.clear_editor(window, cx);
+        cx.notify();
+    }
+
+    fn clear_editor(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.editing = None;
+        self.title_input
+            .update(cx, |input, cx| input.set_value("", window, cx));
+        self.body_input
+            .update(cx, |input, cx| input.set_value("", window, cx));
+    }
+
+    /// Load note `ix` into the editor.
+    pub(super) fn edit_note(&mut self, ix: usize, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(note) = self.notes.get(ix).cloned() else {
+            return;
+        };
+        self.editing = Some(ix);
+        self.title_input
+            .update(cx, |input, cx| input.set_value(note.title, window, cx));
+        self.body_input
+            .update(cx, |input, cx| input.set_value(note.body, window, cx));
+        cx.notify();
+    }
+
+    pub(super) fn new_note(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.clear_editor(window, cx);
+        cx.notify();
+    }
+
+    /// Store the editor contents as a new note or over the one being edited.
+    pub(super) fn save_note(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let title = self.title_input.read(cx).value().trim().to_string();
+        let body = self.body_input.read(cx).value().to_string();
+        if title.is_empty() && body.trim().is_empty() {
+            return;
+        }
+        let mut notes = self.notes.clone();
+        let note = Note::new(title, body);
+        match self.editing.filter(|ix| *ix < notes.len()) {
+            Some(ix) => notes[ix] = note,
+            None => notes.push(note),
+        }
+        if self.persist(notes, cx) {
+            self.clear_editor(window, cx);
+        }
+        cx.notify();
+    }
+
+    pub(super) fn open_delete_dialog(
+        &mut self,
+        ix: usize,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(note) = self.notes.get(ix) else {
+            return;
+        };
+        let label = if note.title.is_empty() {
+            "this note".to_string()
+        } else {
+            format!("\"{}\"", note.title)
+        };
+        let view_handle = cx.entity().downgrade();
+
+        dialog::open_confirm(
+            "Delete Note",
+            format!("Delete {} from the vault?", label),
+            "Delete",
+            gpui_component::button::ButtonVariant::Danger,
+            window,
+            cx,
+            move |dialog_handle, window, cx| {
+                let _ = view_handle.update(cx, |this, cx| {
+                    this.delete_note(ix, dialog_handle, window, cx);
+                });
+            },
+        );
+    }
+
+    fn delete_note(
+        &mut self,
+        ix: usize,
+        dialog_handle: WeakEntity<ConfirmContent>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let mut notes = self.notes.clone();
+        if ix >= notes.len() {
+            return;
+        }
+        notes.remove(ix);
+        if self.persist(notes, cx) {
+            if self.editing.is_some() {
+                self.clear_editor(window, cx);
+            }
+            let _ = dialog_handle.update(cx, |d, cx| {
+                d.set_success("Note deleted.".to_string(), cx);
+            });
+        } else if let Some(e) = self.error.clone() {
+            let _ = dialog_handle.update(cx, |d, cx| d.set_error(e, cx));
+        }
+        cx.notify();
+    }
+
+    /// Seal `notes` with the vault key and write them to disk. Sealing uses
+    /// the key already in memory, so no touch is needed.
+    fn persist(&mut self, notes: Vec<Note>, cx: &mut Context<Self>) -> bool {
+        let (Some(key), Some(file)) = (self.key, self.vault.as_ref()) else {
+            return false;
+        };
+        let result = file
+            .seal(&key, &notes)
+            .and_then(|sealed| vault::save(&sealed).map(|()| sealed));
+        match result {
+            Ok(sealed) => {
+                self.vault = Some(sealed);
+                self.notes = notes;
+                self.error = None;
+                true
+            }
+            Err(e) => {
+                log::error!("Could not save the notes vault: {}", e);
+                self.error = Some(e);
+                cx.notify();
+                false
+            }
+        }
+    }
+
+    pub(super) fn open_forget_dialog(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let view_handle = cx.entity().downgrade();
+
+        dialog::open_confirm(
+            "Delete Notes Vault",
+            format!(
+                "Delete the vault file and every note in it? This cannot be undone. \
+                 The \"{}\" passkey stays on the key; delete it from Passkeys if you no \
+                 longer need it.",
+                VAULT_RP_ID
+            ),
+            "Delete Vault",
+            gpui_component::button::ButtonVariant::Danger,
+            window,
+            cx,
+            move |dialog_handle, window, cx| {
+                let _ = view_handle.update(cx, |this, cx| {
+                    this.forget(dialog_handle, window, cx);
+                });
+            },
+        );
+    }
+
+    fn forget(
+        &mut self,
+        dialog_handle: WeakEntity<ConfirmContent>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        match vault::remove() {
+            Ok(()) => {
+                log::info!("Notes vault deleted");
+                self.vault = None;
+                self.lock(window, cx);
+                let _ = dialog_handle.update(cx, |d, cx| {
+                    d.set_success("Notes vault deleted.".to_string(), cx);
+                });
+            }
+            Err(e) => {
+                log::error!("Could not delete the notes vault: {}", e);
+                let _ = dialog_handle.update(cx, |d, cx| d.set_error(e, cx));
+            }
+        }
+        cx.notify();
+    }
+}
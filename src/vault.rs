@@ -0,0 +1,270 @@
+//! Notes vault: short notes kept on this machine, encrypted with a key that
+//! only the security key can produce.
+//!
+//! The vault is bound to one discoverable credential created with the
+//! hmac-secret extension under [`VAULT_RP_ID`] (see
+//! [`crate::hal::fido::hmac_secret`]). Unlocking asks the key for that
+//! credential's output for the vault's salt, which takes the PIN and a touch;
+//! the 32 bytes it returns are the AES-256-GCM key of the notes. The key is
+//! held in memory while the vault is open and never written anywhere, so
+//! `vault.json` cannot be read without the security key that created it.
+//! Resetting the key or deleting the credential loses the notes for good.
+//!
+//! `vault.json` lives in the platform data directory next to the inventory
+//! and holds the credential ID, the salt and the sealed notes. Every save
+//! uses a fresh nonce, and the credential ID is authenticated with the
+//! notes so a file cannot be pointed at another credential.
+
+use crate::settings;
+use ring::aead;
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// RP ID of the vault credential. Never a real website, so it cannot be
+/// used to sign in anywhere.
+pub const VAULT_RP_ID: &str = "picoforge.vault";
+
+/// User name stored with the vault credential, shown in passkey lists.
+pub const VAULT_USER_NAME: &str = "PicoForge notes vault";
+
+/// Length of the salt and of the derived key.
+pub const KEY_LEN: usize = 32;
+
+/// Current `vault.json` layout.
+const FORMAT_VERSION: u32 = 1;
+
+/// One note.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Note {
+    pub title: String,
+    pub body: String,
+    /// When the note was last edited, in UNIX seconds.
+    pub updated_at: u64,
+}
+
+impl Note {
+    /// A note stamped with the current time.
+    pub fn new(title: String, body: String) -> Self {
+        Self {
+            title,
+            body,
+            updated_at: now(),
+        }
+    }
+}
+
+/// Contents of `vault.json`. Binary fields are hex-encoded.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultFile {
+    pub version: u32,
+    /// Serial of the key holding the credential, to tell the user which key
+    /// unlocks the vault.
+    pub serial: String,
+    /// ID of the hmac-secret credential.
+    pub credential_id: String,
+    /// Salt sent to hmac-secret.
+    pub salt: String,
+    /// AES-GCM nonce of `ciphertext`.
+    pub nonce: String,
+    /// The notes as JSON, sealed with AES-256-GCM.
+    pub ciphertext: String,
+}
+
+impl VaultFile {
+    /// A vault with no notes for a freshly created credential.
+    pub fn create(
+        serial: String,
+        credential_id: String,
+        salt: &[u8; KEY_LEN],
+        key: &[u8; KEY_LEN],
+    ) -> Result<Self, String> {
+        let empty = Self {
+            version: FORMAT_VERSION,
+            serial,
+            credential_id,
+            salt: hex::encode(salt),
+            nonce: String::new(),
+            ciphertext: String::new(),
+        };
+        empty.seal(key, &[])
+    }
+
+    /// The salt to derive this vault's key with.
+    pub fn salt(&self) -> Result<[u8; KEY_LEN], String> {
+        hex::decode(&self.salt)
+            .ok()
+            .and_then(|salt| salt.try_into().ok())
+            .ok_or_else(|| "The vault file has an invalid salt".to_string())
+    }
+
+    /// Decrypt the notes. Fails when `key` is not the one the vault was
+    /// sealed with or the file was altered.
+    pub fn open(&self, key: &[u8; KEY_LEN]) -> Result<Vec<Note>, String> {
+        let nonce = hex::decode(&self.nonce)
+            .ok()
+            .and_then(|nonce| aead::Nonce::try_assume_unique_for_key(&nonce).ok())
+            .ok_or("The vault file has an invalid nonce")?;
+        let mut in_out = hex::decode(&self.ciphertext)
+            .map_err(|_| "The vault file has invalid ciphertext".to_string())?;
+        let plaintext = gcm_key(key)?
+            .open_in_place(
+                nonce,
+                aead::Aad::from(self.credential_id.as_bytes()),
+                &mut in_out,
+            )
+            .map_err(|_| {
+                "The vault could not be decrypted. It was created with a different key or \
+                 credential, or the file was modified."
+                    .to_string()
+            })?;
+        serde_json::from_slice(plaintext).map_err(|e| format!("Vault contents are invalid: {}", e))
+    }
+
+    /// A copy with `notes` sealed under `key` and a fresh nonce.
+    pub fn seal(&self, key: &[u8; KEY_LEN], notes: &[Note]) -> Result<Self, String> {
+        let mut nonce = [0u8; aead::NONCE_LEN];
+        SystemRandom::new()
+            .fill(&mut nonce)
+            .map_err(|_| "Could not generate a nonce".to_string())?;
+        let mut in_out = serde_json::to_vec(notes).map_err(|e| e.to_string())?;
+        gcm_key(key)?
+            .seal_in_place_append_tag(
+                aead::Nonce::assume_unique_for_key(nonce),
+                aead::Aad::from(self.credential_id.as_bytes()),
+                &mut in_out,
+            )
+            .map_err(|_| "Could not encrypt the vault".to_string())?;
+        Ok(Self {
+            nonce: hex::encode(nonce),
+            ciphertext: hex::encode(in_out),
+            ..self.clone()
+        })
+    }
+}
+
+fn gcm_key(key: &[u8; KEY_LEN]) -> Result<aead::LessSafeKey, String> {
+    aead::UnboundKey::new(&aead::AES_256_GCM, key)
+        .map(aead::LessSafeKey::new)
+        .map_err(|_| "Invalid vault key".to_string())
+}
+
+/// A random salt for a new vault.
+pub fn new_salt() -> Result<[u8; KEY_LEN], String> {
+    let mut salt = [0u8; KEY_LEN];
+    SystemRandom::new()
+        .fill(&mut salt)
+        .map_err(|_| "Could not generate a salt".to_string())?;
+    Ok(salt)
+}
+
+fn vault_path() -> Option<PathBuf> {
+    settings::project_dirs().map(|dirs| dirs.data_local_dir().join("vault.json"))
+}
+
+/// Load the vault file. `Ok(None)` when no vault was created yet.
+pub fn load() -> Result<Option<VaultFile>, String> {
+    let path = vault_path().ok_or("Could not determine the data directory")?;
+    match fs::read_to_string(&path) {
+        Ok(text) => serde_json::from_str(&text)
+            .map(Some)
+            .map_err(|e| format!("Could not read {}: {}", path.display(), e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("Could not read {}: {}", path.display(), e)),
+    }
+}
+
+/// Write the vault file, creating the data directory if needed.
+pub fn save(vault: &VaultFile) -> Result<(), String> {
+    let path = vault_path().ok_or("Could not determine the data directory")?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Could not create {}: {}", parent.display(), e))?;
+    }
+    let text = serde_json::to_string_pretty(vault).map_err(|e| e.to_string())?;
+    fs::write(&path, text).map_err(|e| format!("Could not write {}: {}", path.display(), e))
+}
+
+/// Replace the vault file with `vault`, keeping a different existing one as
+/// `vault.json.bak` so its notes are not lost.
+pub fn replace(vault: &VaultFile) -> Result<(), String> {
+    if let Some(existing) = load()?
+        && existing != *vault
+    {
+        let path = vault_path().ok_or("Could not determine the data directory")?;
+        let backup = path.with_extension("json.bak");
+        fs::rename(&path, &backup)
+            .map_err(|e| format!("Could not back up {}: {}", path.display(), e))?;
+    }
+    save(vault)
+}
+
+/// Delete the vault file. The credential stays on the key until it is
+/// deleted from the Passkeys screen.
+pub fn remove() -> Result<(), String> {
+    let path = vault_path().ok_or("Could not determine the data directory")?;
+    match fs::remove_file(&path) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(format!("Could not delete {}: {}", path.display(), e)),
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vault(key: &[u8; KEY_LEN]) -> VaultFile {
+        VaultFile::create("ABC123".into(), "c0ffee".into(), &[7u8; KEY_LEN], key).unwrap()
+    }
+
+    #[test]
+    fn test_notes_round_trip() {
+        let key = [0x42u8; KEY_LEN];
+        let notes = vec![Note::new("wifi".into(), "hunter2".into())];
+        let sealed = vault(&key).seal(&key, &notes).unwrap();
+
+        assert_eq!(sealed.open(&key).unwrap(), notes);
+        assert_eq!(sealed.salt().unwrap(), [7u8; KEY_LEN]);
+        assert!(!sealed.ciphertext.contains(&hex::encode("hunter2")));
+    }
+
+    #[test]
+    fn test_new_vault_is_empty() {
+        let key = [1u8; KEY_LEN];
+        assert!(vault(&key).open(&key).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_wrong_key_does_not_open() {
+        let sealed = vault(&[1u8; KEY_LEN]);
+        assert!(sealed.open(&[2u8; KEY_LEN]).is_err());
+    }
+
+    #[test]
+    fn test_credential_id_is_authenticated() {
+        let key = [1u8; KEY_LEN];
+        let mut sealed = vault(&key);
+        sealed.credential_id = "deadbeef".into();
+        assert!(sealed.open(&key).is_err());
+    }
+
+    #[test]
+    fn test_each_seal_uses_a_new_nonce() {
+        let key = [1u8; KEY_LEN];
+        let first = vault(&key);
+        let second = first.seal(&key, &[]).unwrap();
+        assert_ne!(first.nonce, second.nonce);
+    }
+}